@@ -6,6 +6,7 @@
 //! - `is_subtype_of()` - Subtype checking
 //! - Category predicates (`is_definition()`, `is_usage()`, etc.)
 //! - Definition↔Usage mappings
+//! - Abstract/concrete predicates (`is_abstract()`, `is_concrete()`)
 
 use crate::ttl_parser::TypeInfo;
 use std::collections::{HashMap, HashSet};
@@ -50,6 +51,60 @@ pub fn generate_hierarchy_methods(
     output
 }
 
+/// Generate `is_abstract()` and `is_concrete()` for the ElementKind enum.
+///
+/// The TTL vocabulary doesn't record which metaclasses are abstract, so the
+/// set comes from the XMI metamodel (see `parse_all_xmi_abstract_classes`).
+/// Names that aren't ElementKind variants are ignored.
+pub fn generate_abstract_predicates(
+    kerml_types: &[TypeInfo],
+    sysml_types: &[TypeInfo],
+    abstract_classes: &HashSet<String>,
+) -> String {
+    let all_types = get_all_type_names(kerml_types, sysml_types);
+    let abstract_types: Vec<&str> = all_types
+        .iter()
+        .filter(|t| abstract_classes.contains(**t))
+        .copied()
+        .collect();
+
+    let mut output = String::new();
+    output.push_str("impl ElementKind {\n");
+
+    // is_abstract()
+    output.push_str("    /// Returns `true` if this is an abstract metaclass in the KerML/SysML metamodel.\n");
+    output.push_str("    ///\n");
+    output.push_str("    /// Abstract metaclasses (e.g., Element, Relationship) cannot be instantiated\n");
+    output.push_str("    /// directly; only their concrete subtypes can.\n");
+    output.push_str("    pub const fn is_abstract(&self) -> bool {\n");
+    if abstract_types.is_empty() {
+        output.push_str("        false\n");
+    } else {
+        output.push_str("        matches!(self,\n");
+        for (i, abstract_type) in abstract_types.iter().enumerate() {
+            if i == abstract_types.len() - 1 {
+                output.push_str(&format!("            ElementKind::{}\n", abstract_type));
+            } else {
+                output.push_str(&format!("            ElementKind::{} |\n", abstract_type));
+            }
+        }
+        output.push_str("        )\n");
+    }
+    output.push_str("    }\n\n");
+
+    // is_concrete()
+    output.push_str("    /// Returns `true` if elements of this kind can be instantiated directly.\n");
+    output.push_str("    ///\n");
+    output.push_str("    /// This is the negation of [`ElementKind::is_abstract`].\n");
+    output.push_str("    pub const fn is_concrete(&self) -> bool {\n");
+    output.push_str("        !self.is_abstract()\n");
+    output.push_str("    }\n");
+
+    output.push_str("}\n\n");
+
+    output
+}
+
 /// Build a map from type name to all its supertypes (direct + transitive).
 fn build_type_hierarchy(
     kerml_types: &[TypeInfo],
@@ -556,6 +611,34 @@ mod tests {
         assert!(code.contains("ElementKind::PartUsage => Some(ElementKind::PartDefinition)"));
     }
 
+    #[test]
+    fn test_generates_abstract_predicates() {
+        let kerml = vec![
+            make_type("Element", &[]),
+            make_type("Relationship", &["Element"]),
+            make_type("Feature", &["Element"]),
+        ];
+        let sysml = vec![make_type("PartUsage", &["Feature"])];
+        let abstract_classes: HashSet<String> = ["Element", "Relationship", "NotAKind"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let code = generate_abstract_predicates(&kerml, &sysml, &abstract_classes);
+
+        assert!(code.contains("pub const fn is_abstract(&self)"));
+        assert!(code.contains("pub const fn is_concrete(&self)"));
+        assert!(code.contains("ElementKind::Element |"));
+        assert!(code.contains("ElementKind::Relationship\n"));
+        assert!(!code.contains("ElementKind::Feature"));
+        assert!(!code.contains("ElementKind::PartUsage"));
+        assert!(!code.contains("NotAKind"));
+
+        // An empty set still yields valid code
+        let empty = generate_abstract_predicates(&kerml, &sysml, &HashSet::new());
+        assert!(empty.contains("        false\n"));
+    }
+
     #[test]
     fn test_handles_cycles() {
         // Even with cycles, should not infinite loop
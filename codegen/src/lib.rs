@@ -65,7 +65,7 @@ pub mod xtext_parser;
 
 pub use enum_generator::generate_enum;
 pub use enum_value_generator::generate_value_enums;
-pub use hierarchy_generator::generate_hierarchy_methods;
+pub use hierarchy_generator::{generate_abstract_predicates, generate_hierarchy_methods};
 pub use json_schema_parser::{
     expected_enum_types, parse_all_enums_from_json, parse_enum_json,
     parse_relationship_constraints_from_json, parse_relationship_json, JsonEnumInfo,
@@ -87,7 +87,9 @@ pub use spec_validation::{
 pub use ttl_parser::{
     merge_enum_info, parse_ttl_enums, parse_ttl_vocab, EnumInfo, EnumValue, ParseError, TypeInfo,
 };
-pub use xmi_class_parser::{parse_all_xmi_classes, parse_xmi_classes_from_file};
+pub use xmi_class_parser::{
+    parse_all_xmi_abstract_classes, parse_all_xmi_classes, parse_xmi_classes_from_file,
+};
 pub use xmi_relationship_parser::{
     parse_relationship_constraints, validate_relationship_coverage, CoverageReport, XmiParseError,
     XmiRelationshipConstraint,
@@ -1,7 +1,8 @@
 //! Parser for XMI (UML metamodel) files to extract all class names.
 //!
 //! This module extracts all class names from the KerML and SysML XMI files
-//! for cross-validation against TTL vocabulary types.
+//! for cross-validation against TTL vocabulary types, and the names of
+//! abstract classes (`isAbstract="true"`), which the TTL vocabulary does not record.
 
 use quick_xml::events::{BytesStart, Event};
 use quick_xml::Reader;
@@ -21,6 +22,16 @@ fn get_attr(e: &BytesStart<'_>, name: &[u8]) -> Option<String> {
 
 /// Parse a single XMI file to extract all class names.
 fn parse_xmi_classes_from_content(content: &str) -> Result<HashSet<String>, XmiParseError> {
+    collect_xmi_classes(content, false)
+}
+
+/// Parse a single XMI file to extract the names of abstract classes.
+fn parse_xmi_abstract_classes_from_content(content: &str) -> Result<HashSet<String>, XmiParseError> {
+    collect_xmi_classes(content, true)
+}
+
+/// Collect class names, optionally only those marked `isAbstract="true"`.
+fn collect_xmi_classes(content: &str, abstract_only: bool) -> Result<HashSet<String>, XmiParseError> {
     let mut reader = Reader::from_str(content);
     let mut classes = HashSet::new();
     let mut buf = Vec::new();
@@ -32,7 +43,8 @@ fn parse_xmi_classes_from_content(content: &str) -> Result<HashSet<String>, XmiP
                 if local_name.as_ref() == b"packagedElement" {
                     // Check if this is a uml:Class
                     if let Some(xmi_type) = get_attr(&e, b"xmi:type") {
-                        if xmi_type == "uml:Class" {
+                        let is_abstract = get_attr(&e, b"isAbstract").as_deref() == Some("true");
+                        if xmi_type == "uml:Class" && (is_abstract || !abstract_only) {
                             if let Some(name) = get_attr(&e, b"name") {
                                 // Filter out empty names and internal names
                                 if !name.is_empty() && !name.starts_with('_') {
@@ -74,6 +86,23 @@ pub fn parse_all_xmi_classes(
     Ok(all_classes)
 }
 
+/// Parse KerML.xmi and SysML.xmi to extract the names of abstract classes.
+///
+/// Abstract metaclasses (e.g. `Element`, `Relationship`) cannot be instantiated
+/// directly; only their concrete subclasses can.
+pub fn parse_all_xmi_abstract_classes(
+    kerml_xmi_path: &Path,
+    sysml_xmi_path: &Path,
+) -> Result<HashSet<String>, XmiParseError> {
+    let kerml_content = fs::read_to_string(kerml_xmi_path)?;
+    let sysml_content = fs::read_to_string(sysml_xmi_path)?;
+
+    let mut abstract_classes = parse_xmi_abstract_classes_from_content(&kerml_content)?;
+    abstract_classes.extend(parse_xmi_abstract_classes_from_content(&sysml_content)?);
+
+    Ok(abstract_classes)
+}
+
 /// Parse a single XMI file and return the class names.
 ///
 /// Useful for reporting KerML vs SysML breakdown.
@@ -125,6 +154,26 @@ mod tests {
         assert!(classes.contains("Element"));
     }
 
+    #[test]
+    fn test_abstract_classes() {
+        let xmi = r#"<?xml version='1.0' encoding='UTF-8'?>
+<xmi:XMI xmlns:xmi="http://www.omg.org/spec/XMI/20161101" xmlns:uml="http://www.omg.org/spec/UML/20161101">
+  <uml:Package xmi:id="Test" name="Test">
+    <packagedElement xmi:id="Test-Element" xmi:type="uml:Class" isAbstract="true" name="Element">
+      <ownedOperation xmi:id="Test-Element-op" xmi:type="uml:Operation" isAbstract="true" name="op"/>
+    </packagedElement>
+    <packagedElement xmi:id="Test-Feature" xmi:type="uml:Class" name="Feature"/>
+    <packagedElement xmi:id="Test-Type" xmi:type="uml:Class" isAbstract="false" name="Type"/>
+  </uml:Package>
+</xmi:XMI>"#;
+
+        let abstract_classes = parse_xmi_abstract_classes_from_content(xmi).unwrap();
+        assert_eq!(abstract_classes, HashSet::from(["Element".to_string()]));
+
+        // All classes are still reported by the plain parser
+        assert_eq!(parse_xmi_classes_from_content(xmi).unwrap().len(), 3);
+    }
+
     #[test]
     fn test_nested_classes() {
         let xmi = r#"<?xml version='1.0' encoding='UTF-8'?>
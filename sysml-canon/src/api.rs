@@ -0,0 +1,582 @@
+//! SysML v2 API JSON import and export.
+//!
+//! The Systems Modeling API exchanges models as JSON element objects carrying
+//! `@id` and `@type`, with references written as `{"@id": ...}` and ownership
+//! expressed through `owningRelationship`/`ownedRelationship` and
+//! `owningRelatedElement`/`ownedRelatedElement`. This module maps that shape to
+//! and from a `ModelGraph`:
+//!
+//! - `@type` becomes the `ElementKind` and `@id` the `ElementId`
+//! - `declaredName` (or `name`) becomes the element name
+//! - A membership's owning namespace and owned member become the
+//!   `membershipOwningNamespace`/`ownedMemberElement` props, and the member's
+//!   `owner`/`owning_membership` fields
+//! - Relationships owned directly by an element (e.g. a `FeatureTyping`) get
+//!   that element as `owner` with no owning membership
+//! - All other properties are kept as props, with `{"@id": ...}` references
+//!   as `Value::Ref`
+//!
+//! Derived properties that the graph recomputes (`qualifiedName`,
+//! `owningNamespace`, `ownedElement`, ...) are not stored. Spans and the
+//! graph's legacy `relationships` are not part of the API format.
+//!
+//! An `@id` that is not a UUID is hashed into an `ElementId`, so the id as
+//! written is kept in the `apiId` prop and written back on export.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{json, Map, Value as Json};
+use sysml_core::{Element, ElementId, ElementKind, ModelGraph, Value};
+
+use crate::CanonError;
+
+/// API properties mapped onto the graph structure rather than stored as props.
+const STRUCTURAL_KEYS: &[&str] = &[
+    "@id",
+    "@type",
+    "@context",
+    "elementId",
+    "declaredName",
+    "name",
+    "qualifiedName",
+    "owner",
+    "owningMembership",
+    "owningNamespace",
+    "owningRelationship",
+    "owningRelatedElement",
+    "ownedRelationship",
+    "ownedRelatedElement",
+    "ownedElement",
+];
+
+/// API properties whose string values are enumeration literals.
+const ENUM_KEYS: &[&str] = &[
+    "visibility",
+    "direction",
+    "portionKind",
+    "requirementConstraintKind",
+    "stateSubactionKind",
+    "transitionFeatureKind",
+    "triggerKind",
+];
+
+const MEMBERSHIP_OWNING_NAMESPACE: &str = "membershipOwningNamespace";
+const MEMBER_ELEMENT: &str = "memberElement";
+const OWNED_MEMBER_ELEMENT: &str = "ownedMemberElement";
+/// The `@id` as written, kept when it does not survive as the element id.
+const API_ID: &str = "apiId";
+
+/// Import a model from SysML v2 API JSON.
+///
+/// The input is either a single element object or an array of them, as
+/// returned by the API's element endpoints. Owned elements may be listed flat
+/// and linked by `{"@id": ...}` references, or nested inline under
+/// `ownedRelationship` and `ownedRelatedElement`.
+///
+/// # Errors
+///
+/// Returns [`CanonError::UnknownTypes`] listing every `@type` that is not a
+/// known element kind, or [`CanonError::DeserializeError`] if the JSON is not
+/// a list of element objects with `@id` and `@type`.
+///
+/// # Example
+///
+/// ```
+/// use sysml_canon::from_api_json;
+/// use sysml_core::ElementKind;
+///
+/// let json = r#"[{
+///     "@id": "3f2b6a4e-1c55-4c1e-9a59-0d3c8f1c2b7a",
+///     "@type": "Package",
+///     "declaredName": "Vehicles"
+/// }]"#;
+/// let graph = from_api_json(json).unwrap();
+/// let package = graph.elements_by_kind(&ElementKind::Package).next().unwrap();
+/// assert_eq!(package.name.as_deref(), Some("Vehicles"));
+/// ```
+pub fn from_api_json(json: &str) -> Result<ModelGraph, CanonError> {
+    let items = match serde_json::from_str::<Json>(json)? {
+        Json::Array(items) => items,
+        object @ Json::Object(_) => vec![object],
+        _ => {
+            return Err(CanonError::DeserializeError(
+                "expected an element object or an array of element objects".to_string(),
+            ))
+        }
+    };
+
+    let mut objects = Vec::new();
+    for item in items {
+        flatten(item, &mut objects)?;
+    }
+
+    // Resolve every kind up front so all unknown types are reported together
+    let mut unknown = BTreeSet::new();
+    let mut kinds = BTreeMap::new();
+    for object in &objects {
+        let type_name = object["@type"].as_str().unwrap_or_default();
+        match ElementKind::from_str(type_name) {
+            Some(kind) => {
+                kinds.insert(object_id(object), kind);
+            }
+            None => {
+                unknown.insert(type_name.to_string());
+            }
+        }
+    }
+    if !unknown.is_empty() {
+        return Err(CanonError::UnknownTypes(unknown.into_iter().collect()));
+    }
+
+    let by_id: BTreeMap<ElementId, &Map<String, Json>> =
+        objects.iter().map(|object| (object_id(object), object)).collect();
+    let is_membership = |id: &ElementId| kinds.get(id).is_some_and(is_membership_kind);
+
+    let mut elements = Vec::with_capacity(objects.len());
+    for object in &objects {
+        let id = object_id(object);
+        let mut element = Element::new(id.clone(), kinds[&id].clone());
+        let api_id = object["@id"].as_str().unwrap_or_default();
+        if id.to_string() != api_id {
+            element.props.insert(API_ID.to_string(), Value::String(api_id.to_string()));
+        }
+        element.name = object
+            .get("declaredName")
+            .and_then(Json::as_str)
+            .or_else(|| object.get("name").and_then(Json::as_str))
+            .map(str::to_string);
+
+        for (key, value) in object {
+            if STRUCTURAL_KEYS.contains(&key.as_str()) {
+                continue;
+            }
+            if let Some(value) = json_to_value(key, value) {
+                element.props.insert(key.clone(), value);
+            }
+        }
+
+        let owning_relationship = reference(object, "owningRelationship");
+        if is_membership_kind(&element.kind) {
+            if !element.props.contains_key(MEMBERSHIP_OWNING_NAMESPACE) {
+                if let Some(namespace) = reference(object, "owningRelatedElement") {
+                    element.props.insert(MEMBERSHIP_OWNING_NAMESPACE.to_string(), Value::Ref(namespace));
+                }
+            }
+            let owned_member = references(object, "ownedRelatedElement").into_iter().next();
+            if let Some(member) = owned_member {
+                if is_owning_membership_kind(&element.kind) {
+                    element
+                        .props
+                        .entry(OWNED_MEMBER_ELEMENT.to_string())
+                        .or_insert_with(|| Value::Ref(member.clone()));
+                }
+                element
+                    .props
+                    .entry(MEMBER_ELEMENT.to_string())
+                    .or_insert(Value::Ref(member));
+            }
+        } else {
+            element.owning_membership = reference(object, "owningMembership")
+                .or_else(|| owning_relationship.clone().filter(|id| is_membership(id)));
+            element.owner = reference(object, "owner")
+                .or_else(|| {
+                    let relationship = by_id.get(owning_relationship.as_ref()?)?;
+                    reference(relationship, MEMBERSHIP_OWNING_NAMESPACE)
+                        .or_else(|| reference(relationship, "owningRelatedElement"))
+                })
+                .or_else(|| reference(object, "owningRelatedElement"));
+        }
+
+        elements.push(element);
+    }
+
+    let mut graph = ModelGraph::new();
+    graph.add_elements_bulk(elements);
+    graph.rebuild_indexes();
+    Ok(graph)
+}
+
+/// Export a model as SysML v2 API JSON.
+///
+/// The output is a pretty-printed array of element objects sorted by `@id`,
+/// with references written as `{"@id": ...}` and ownership as
+/// `owner`/`owningMembership`/`owningRelationship` on owned elements,
+/// `owningRelatedElement`/`ownedRelatedElement` on relationships, and
+/// `ownedRelationship` on their owners. Element ids are preserved, including
+/// non-UUID `@id`s read by [`from_api_json`], so it restores the same graph.
+///
+/// # Example
+///
+/// ```
+/// use sysml_canon::{from_api_json, to_api_json};
+/// use sysml_core::{Element, ElementKind, ModelGraph, VisibilityKind};
+///
+/// let mut graph = ModelGraph::new();
+/// let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("P"));
+/// let part = graph.add_owned_element(
+///     Element::new_with_kind(ElementKind::PartDefinition).with_name("Engine"),
+///     pkg.clone(),
+///     VisibilityKind::Public,
+/// );
+///
+/// let json = to_api_json(&graph);
+/// let restored = from_api_json(&json).unwrap();
+/// assert_eq!(restored.get_element(&part).unwrap().owner, Some(pkg));
+/// ```
+pub fn to_api_json(graph: &ModelGraph) -> String {
+    let ids: BTreeMap<ElementId, String> = graph
+        .elements
+        .values()
+        .map(|element| {
+            let api_id = element.props.get(API_ID).and_then(Value::as_str);
+            (element.id.clone(), api_id.map_or_else(|| element.id.to_string(), str::to_string))
+        })
+        .collect();
+    let mut elements: Vec<&Element> = graph.elements.values().collect();
+    elements.sort_by_key(|element| ids[&element.id].as_str());
+
+    // ownedRelationship of each element, in output order
+    let mut owned_relationships: BTreeMap<&ElementId, Vec<&ElementId>> = BTreeMap::new();
+    for element in &elements {
+        let owner = if is_membership_kind(&element.kind) {
+            element.props.get(MEMBERSHIP_OWNING_NAMESPACE).and_then(Value::as_ref)
+        } else if is_relationship_kind(&element.kind) && element.owning_membership.is_none() {
+            element.owner.as_ref()
+        } else {
+            None
+        };
+        if let Some(owner) = owner {
+            owned_relationships.entry(owner).or_default().push(&element.id);
+        }
+    }
+
+    let objects: Vec<Json> = elements
+        .iter()
+        .map(|element| {
+            let mut object = Map::new();
+            for (key, value) in &element.props {
+                if key != API_ID {
+                    object.insert(key.clone(), value_to_json(value, &ids));
+                }
+            }
+
+            let id = &ids[&element.id];
+            object.insert("@id".into(), json!(id));
+            object.insert("@type".into(), json!(element.kind.as_str()));
+            object.insert("elementId".into(), json!(id));
+            object.insert("declaredName".into(), json!(element.name));
+
+            if is_membership_kind(&element.kind) {
+                let namespace = element.props.get(MEMBERSHIP_OWNING_NAMESPACE).and_then(Value::as_ref);
+                object.insert("owningRelatedElement".into(), ref_json(namespace, &ids));
+                let owned_member = element.props.get(OWNED_MEMBER_ELEMENT).and_then(Value::as_ref);
+                object.insert(
+                    "ownedRelatedElement".into(),
+                    Json::Array(owned_member.into_iter().map(|id| ref_json(Some(id), &ids)).collect()),
+                );
+            } else if is_relationship_kind(&element.kind) && element.owning_membership.is_none() {
+                object.insert("owningRelatedElement".into(), ref_json(element.owner.as_ref(), &ids));
+            }
+            if !is_membership_kind(&element.kind) {
+                object.insert("owner".into(), ref_json(element.owner.as_ref(), &ids));
+                object.insert("owningMembership".into(), ref_json(element.owning_membership.as_ref(), &ids));
+                object.insert("owningRelationship".into(), ref_json(element.owning_membership.as_ref(), &ids));
+            }
+
+            let owned = owned_relationships.get(&element.id).map(Vec::as_slice).unwrap_or_default();
+            object.insert(
+                "ownedRelationship".into(),
+                Json::Array(owned.iter().map(|id| ref_json(Some(id), &ids)).collect()),
+            );
+
+            Json::Object(object)
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&objects).expect("API JSON should always be serializable")
+}
+
+/// Collect `item` and any element objects nested in it, replacing nested
+/// elements with `{"@id": ...}` references and linking them to their parent.
+fn flatten(item: Json, objects: &mut Vec<Map<String, Json>>) -> Result<(), CanonError> {
+    let mut object = match item {
+        Json::Object(object) if object.get("@id").is_some_and(Json::is_string) && object.get("@type").is_some_and(Json::is_string) => object,
+        other => {
+            return Err(CanonError::DeserializeError(format!(
+                "expected an element object with string @id and @type, found {}",
+                other
+            )))
+        }
+    };
+    let parent = object["@id"].clone();
+
+    for (key, value) in object.iter_mut() {
+        let nested: Vec<&mut Json> = match value {
+            Json::Array(items) => items.iter_mut().collect(),
+            value => vec![value],
+        };
+        for value in nested {
+            if value.get("@type").is_none() {
+                continue;
+            }
+            let mut child = value.take();
+            *value = json!({ "@id": child["@id"].clone() });
+            if let Json::Object(child) = &mut child {
+                match key.as_str() {
+                    "ownedRelationship" => {
+                        child.entry("owningRelatedElement").or_insert_with(|| json!({ "@id": parent }));
+                    }
+                    "ownedRelatedElement" => {
+                        child.entry("owningRelationship").or_insert_with(|| json!({ "@id": parent }));
+                    }
+                    _ => {}
+                }
+            }
+            flatten(child, objects)?;
+        }
+    }
+
+    objects.push(object);
+    Ok(())
+}
+
+fn object_id(object: &Map<String, Json>) -> ElementId {
+    ElementId::from_string(object["@id"].as_str().unwrap_or_default())
+}
+
+/// The id in a `{"@id": ...}` reference, if present.
+fn as_reference(value: &Json) -> Option<ElementId> {
+    let object = value.as_object()?;
+    if object.len() != 1 {
+        return None;
+    }
+    object.get("@id")?.as_str().map(ElementId::from_string)
+}
+
+fn reference(object: &Map<String, Json>, key: &str) -> Option<ElementId> {
+    object.get(key).and_then(as_reference)
+}
+
+fn references(object: &Map<String, Json>, key: &str) -> Vec<ElementId> {
+    match object.get(key) {
+        Some(Json::Array(items)) => items.iter().filter_map(as_reference).collect(),
+        Some(value) => as_reference(value).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+/// A `{"@id": ...}` reference, using the exported `@id` of elements in `ids`.
+fn ref_json(id: Option<&ElementId>, ids: &BTreeMap<ElementId, String>) -> Json {
+    match id {
+        Some(id) => match ids.get(id) {
+            Some(api_id) => json!({ "@id": api_id }),
+            None => json!({ "@id": id.to_string() }),
+        },
+        None => Json::Null,
+    }
+}
+
+/// Convert an API property value; `null` means the property is absent.
+fn json_to_value(key: &str, value: &Json) -> Option<Value> {
+    let value = match value {
+        Json::Null => return None,
+        Json::Bool(b) => Value::Bool(*b),
+        Json::Number(n) => match n.as_i64() {
+            Some(i) => Value::Int(i),
+            None => Value::Float(n.as_f64().unwrap_or_default()),
+        },
+        Json::String(s) if ENUM_KEYS.contains(&key) => Value::Enum(s.clone()),
+        Json::String(s) => Value::String(s.clone()),
+        Json::Array(items) => Value::List(items.iter().filter_map(|item| json_to_value(key, item)).collect()),
+        Json::Object(object) => match as_reference(value) {
+            Some(id) => Value::Ref(id),
+            None => Value::Map(
+                object
+                    .iter()
+                    .filter_map(|(k, v)| json_to_value(k, v).map(|v| (k.clone(), v)))
+                    .collect(),
+            ),
+        },
+    };
+    Some(value)
+}
+
+fn value_to_json(value: &Value, ids: &BTreeMap<ElementId, String>) -> Json {
+    match value {
+        Value::Bool(b) => json!(b),
+        Value::Int(i) => json!(i),
+        Value::Float(f) => json!(f),
+        Value::String(s) | Value::Enum(s) => json!(s),
+        Value::Ref(id) => ref_json(Some(id), ids),
+        Value::List(items) => Json::Array(items.iter().map(|item| value_to_json(item, ids)).collect()),
+        Value::Map(map) => Json::Object(map.iter().map(|(k, v)| (k.clone(), value_to_json(v, ids))).collect()),
+        Value::Null => Json::Null,
+    }
+}
+
+fn is_membership_kind(kind: &ElementKind) -> bool {
+    *kind == ElementKind::Membership || kind.is_subtype_of(ElementKind::Membership)
+}
+
+fn is_owning_membership_kind(kind: &ElementKind) -> bool {
+    *kind == ElementKind::OwningMembership || kind.is_subtype_of(ElementKind::OwningMembership)
+}
+
+fn is_relationship_kind(kind: &ElementKind) -> bool {
+    *kind == ElementKind::Relationship || kind.is_subtype_of(ElementKind::Relationship)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysml_core::VisibilityKind;
+
+    const VEHICLE: &str = include_str!("../fixtures/vehicle_api.json");
+
+    fn id(s: &str) -> ElementId {
+        ElementId::from_string(s)
+    }
+
+    const PACKAGE: &str = "a1d3c0f4-5b6e-4c2a-9f1d-2e3b4c5d6e01";
+    const VEHICLE_DEF: &str = "a1d3c0f4-5b6e-4c2a-9f1d-2e3b4c5d6e03";
+    const ENGINE_DEF: &str = "a1d3c0f4-5b6e-4c2a-9f1d-2e3b4c5d6e05";
+    const ENGINE_USAGE: &str = "a1d3c0f4-5b6e-4c2a-9f1d-2e3b4c5d6e07";
+    const ENGINE_TYPING: &str = "a1d3c0f4-5b6e-4c2a-9f1d-2e3b4c5d6e08";
+
+    #[test]
+    fn imports_pilot_fixture() {
+        let graph = from_api_json(VEHICLE).unwrap();
+
+        assert_eq!(graph.element_count(), 8);
+        assert_eq!(graph.elements_by_kind(&ElementKind::Package).count(), 1);
+        assert_eq!(graph.elements_by_kind(&ElementKind::PartDefinition).count(), 2);
+        assert_eq!(graph.elements_by_kind(&ElementKind::OwningMembership).count(), 2);
+        assert_eq!(graph.elements_by_kind(&ElementKind::FeatureMembership).count(), 1);
+
+        let vehicle = graph.get_element(&id(VEHICLE_DEF)).unwrap();
+        assert_eq!(vehicle.name.as_deref(), Some("Vehicle"));
+        assert_eq!(vehicle.owner, Some(id(PACKAGE)));
+        assert_eq!(vehicle.get_bool("isAbstract"), Some(true));
+        assert!(graph.children_of(&id(PACKAGE)).any(|e| e.id == id(ENGINE_DEF)));
+
+        let usage = graph.get_element(&id(ENGINE_USAGE)).unwrap();
+        assert_eq!(usage.owner, Some(id(VEHICLE_DEF)));
+        assert_eq!(graph.owning_membership_of(&usage.id).unwrap().kind, ElementKind::FeatureMembership);
+        assert_eq!(usage.get_prop("direction"), None);
+
+        let typing = graph.get_element(&id(ENGINE_TYPING)).unwrap();
+        assert_eq!(typing.owner, Some(id(ENGINE_USAGE)));
+        assert_eq!(typing.owning_membership, None);
+        assert_eq!(typing.get_ref("type"), Some(&id(ENGINE_DEF)));
+
+        let membership = graph.owning_membership_of(&id(VEHICLE_DEF)).unwrap();
+        let view = membership.as_membership_view().unwrap();
+        assert_eq!(view.visibility(), VisibilityKind::Public);
+        assert_eq!(membership.get_ref(OWNED_MEMBER_ELEMENT), Some(&id(VEHICLE_DEF)));
+    }
+
+    #[test]
+    fn roundtrip_preserves_ids_and_ownership() {
+        let graph = from_api_json(VEHICLE).unwrap();
+        let json = to_api_json(&graph);
+        let restored = from_api_json(&json).unwrap();
+
+        assert_eq!(graph.elements, restored.elements);
+
+        let exported: Vec<Json> = serde_json::from_str(&json).unwrap();
+        let original: Vec<Json> = serde_json::from_str(VEHICLE).unwrap();
+        let ids = |items: &[Json]| items.iter().map(|o| o["@id"].to_string()).collect::<BTreeSet<_>>();
+        assert_eq!(ids(&exported), ids(&original));
+
+        let usage = exported.iter().find(|o| o["@id"] == ENGINE_USAGE).unwrap();
+        assert_eq!(usage["ownedRelationship"], json!([{ "@id": ENGINE_TYPING }]));
+        let typing = exported.iter().find(|o| o["@id"] == ENGINE_TYPING).unwrap();
+        assert_eq!(typing["owningRelatedElement"], json!({ "@id": ENGINE_USAGE }));
+        assert_eq!(typing["owningRelationship"], Json::Null);
+    }
+
+    #[test]
+    fn roundtrip_preserves_non_uuid_ids() {
+        let json = r#"[
+            { "@id": "pkg-1", "@type": "Package", "declaredName": "P" },
+            {
+                "@id": "typing-1",
+                "@type": "FeatureTyping",
+                "owningRelatedElement": { "@id": "pkg-1" },
+                "type": { "@id": "pkg-1" }
+            }
+        ]"#;
+        let graph = from_api_json(json).unwrap();
+        let exported = to_api_json(&graph);
+
+        let objects: Vec<Json> = serde_json::from_str(&exported).unwrap();
+        let ids: Vec<&Json> = objects.iter().map(|o| &o["@id"]).collect();
+        assert_eq!(ids, [&json!("pkg-1"), &json!("typing-1")]);
+        assert_eq!(objects[0]["elementId"], json!("pkg-1"));
+        assert_eq!(objects[0]["ownedRelationship"], json!([{ "@id": "typing-1" }]));
+        assert_eq!(objects[1]["type"], json!({ "@id": "pkg-1" }));
+        assert_eq!(objects[1]["owningRelatedElement"], json!({ "@id": "pkg-1" }));
+        assert!(objects.iter().all(|o| o.get(API_ID).is_none()));
+
+        let restored = from_api_json(&exported).unwrap();
+        assert_eq!(graph.elements, restored.elements);
+    }
+
+    #[test]
+    fn nested_elements_become_owned() {
+        let json = r#"{
+            "@id": "0b6f3c1e-2d4a-4e8b-8c1f-6a7b8c9d0e11",
+            "@type": "Package",
+            "declaredName": "P",
+            "ownedRelationship": [{
+                "@id": "0b6f3c1e-2d4a-4e8b-8c1f-6a7b8c9d0e12",
+                "@type": "OwningMembership",
+                "visibility": "private",
+                "ownedRelatedElement": [{
+                    "@id": "0b6f3c1e-2d4a-4e8b-8c1f-6a7b8c9d0e13",
+                    "@type": "PartDefinition",
+                    "declaredName": "Engine"
+                }]
+            }]
+        }"#;
+
+        let graph = from_api_json(json).unwrap();
+        assert_eq!(graph.element_count(), 3);
+
+        let engine = graph.get_element(&id("0b6f3c1e-2d4a-4e8b-8c1f-6a7b8c9d0e13")).unwrap();
+        assert_eq!(engine.owner, Some(id("0b6f3c1e-2d4a-4e8b-8c1f-6a7b8c9d0e11")));
+        assert_eq!(engine.owning_membership, Some(id("0b6f3c1e-2d4a-4e8b-8c1f-6a7b8c9d0e12")));
+
+        let membership = graph.owning_membership_of(&engine.id).unwrap();
+        assert_eq!(membership.as_membership_view().unwrap().visibility(), VisibilityKind::Private);
+        assert_eq!(
+            membership.get_ref(MEMBERSHIP_OWNING_NAMESPACE),
+            Some(&id("0b6f3c1e-2d4a-4e8b-8c1f-6a7b8c9d0e11"))
+        );
+    }
+
+    #[test]
+    fn unknown_types_are_listed() {
+        let json = r#"[
+            {"@id": "0b6f3c1e-2d4a-4e8b-8c1f-6a7b8c9d0e21", "@type": "Package"},
+            {"@id": "0b6f3c1e-2d4a-4e8b-8c1f-6a7b8c9d0e22", "@type": "Gizmo"},
+            {"@id": "0b6f3c1e-2d4a-4e8b-8c1f-6a7b8c9d0e23", "@type": "Widget"},
+            {"@id": "0b6f3c1e-2d4a-4e8b-8c1f-6a7b8c9d0e24", "@type": "Gizmo"}
+        ]"#;
+
+        match from_api_json(json) {
+            Err(CanonError::UnknownTypes(types)) => assert_eq!(types, vec!["Gizmo", "Widget"]),
+            other => panic!("expected UnknownTypes, got {:?}", other.map(|g| g.element_count())),
+        }
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        assert!(matches!(from_api_json("42"), Err(CanonError::DeserializeError(_))));
+        assert!(matches!(
+            from_api_json(r#"[{"@type": "Package"}]"#),
+            Err(CanonError::DeserializeError(_))
+        ));
+        assert!(matches!(from_api_json("not json"), Err(CanonError::DeserializeError(_))));
+    }
+}
@@ -0,0 +1,229 @@
+//! Order-independent content hashes of graphs and their elements.
+//!
+//! Each element and relationship is hashed from its canonical JSON without
+//! derived or non-semantic fields: the computed qualified name is always
+//! left out, and source spans unless [`HashOptions::with_spans`] is set. A
+//! graph's hash combines the sorted per-item hashes, so it does not depend
+//! on the order in which elements were added. Hashes are 128-bit FNV-1a,
+//! written as 32 hex digits.
+
+use serde_json::Value;
+use sysml_core::{Element, ModelGraph, Relationship};
+
+/// What [`graph_hash_with`] includes beyond the semantic content.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HashOptions {
+    include_spans: bool,
+}
+
+impl HashOptions {
+    /// Hash semantic content only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also hash source spans, so that moving an element changes the hash.
+    pub fn with_spans(mut self) -> Self {
+        self.include_spans = true;
+        self
+    }
+}
+
+/// Hash an element's semantic content: its id, kind, name, ownership and
+/// props.
+///
+/// # Example
+///
+/// ```
+/// use sysml_core::{Element, ElementKind};
+/// use sysml_canon::element_hash;
+///
+/// let element = Element::new_with_kind(ElementKind::PartUsage).with_name("engine");
+/// assert_eq!(element_hash(&element), element_hash(&element.clone()));
+/// assert_ne!(element_hash(&element), element_hash(&element.clone().with_name("wheel")));
+/// ```
+pub fn element_hash(element: &Element) -> String {
+    hex(hash_element(element, HashOptions::new()))
+}
+
+/// Hash a graph's semantic content, independent of insertion order.
+///
+/// Two graphs with the same elements and relationships hash the same,
+/// whatever their source locations.
+///
+/// # Example
+///
+/// ```
+/// use sysml_core::{Element, ElementKind, ModelGraph};
+/// use sysml_canon::graph_hash;
+///
+/// let a = Element::new_with_kind(ElementKind::Package).with_name("A");
+/// let b = Element::new_with_kind(ElementKind::Package).with_name("B");
+///
+/// let mut forward = ModelGraph::new();
+/// forward.add_element(a.clone());
+/// forward.add_element(b.clone());
+/// let mut backward = ModelGraph::new();
+/// backward.add_element(b);
+/// backward.add_element(a);
+/// assert_eq!(graph_hash(&forward), graph_hash(&backward));
+/// ```
+pub fn graph_hash(graph: &ModelGraph) -> String {
+    graph_hash_with(graph, HashOptions::new())
+}
+
+/// Like [`graph_hash`], with options for what to include.
+pub fn graph_hash_with(graph: &ModelGraph, options: HashOptions) -> String {
+    // Tagged so that an element and a relationship with the same content
+    // still count as different items
+    let mut items: Vec<(u8, u128)> = graph
+        .elements
+        .values()
+        .map(|element| (0, hash_element(element, options)))
+        .chain(
+            graph
+                .relationships
+                .values()
+                .map(|relationship| (1, hash_relationship(relationship))),
+        )
+        .collect();
+    items.sort_unstable();
+
+    let mut bytes = Vec::with_capacity(items.len() * 17);
+    for (tag, hash) in items {
+        bytes.push(tag);
+        bytes.extend_from_slice(&hash.to_be_bytes());
+    }
+    hex(fnv1a(&bytes))
+}
+
+/// Hash raw bytes the way every hash in this module is computed.
+///
+/// Content-addressed stores key their blobs with this, so a blob's key and
+/// the hashes of this module are the same kind of value.
+///
+/// # Example
+///
+/// ```
+/// use sysml_canon::bytes_hash;
+///
+/// assert_eq!(bytes_hash(b"{}"), bytes_hash(b"{}"));
+/// assert_ne!(bytes_hash(b"{\"a\":1}"), bytes_hash(b"{\"a\":2}"));
+/// assert_eq!(bytes_hash(b"").len(), 32);
+/// ```
+pub fn bytes_hash(bytes: &[u8]) -> String {
+    hex(fnv1a(bytes))
+}
+
+fn hash_element(element: &Element, options: HashOptions) -> u128 {
+    let mut value = serde_json::to_value(element).expect("elements should always be serializable");
+    if let Value::Object(fields) = &mut value {
+        fields.remove("qname");
+        if !options.include_spans {
+            fields.remove("spans");
+        }
+    }
+    hash_value(&value)
+}
+
+fn hash_relationship(relationship: &Relationship) -> u128 {
+    let value = serde_json::to_value(relationship).expect("relationships should always be serializable");
+    hash_value(&value)
+}
+
+/// Hash JSON with object keys in sorted order, as serde_json maps keep them.
+fn hash_value(value: &Value) -> u128 {
+    let json = serde_json::to_string(value).expect("JSON values always serialize");
+    fnv1a(json.as_bytes())
+}
+
+fn fnv1a(bytes: &[u8]) -> u128 {
+    let mut hash: u128 = 0x6c62272e07bb014262b821756295c58d;
+    for &byte in bytes {
+        hash ^= byte as u128;
+        hash = hash.wrapping_mul(0x0000000001000000000000000000013b);
+    }
+    hash
+}
+
+fn hex(hash: u128) -> String {
+    format!("{:032x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysml_core::{ElementKind, RelationshipKind, Span, Value as PropValue};
+
+    /// A package owning a typed part usage, with an owning relationship.
+    fn elements() -> (Element, Element, Relationship) {
+        let pkg = Element::new_with_kind(ElementKind::Package).with_name("Vehicles");
+        let part = Element::new_with_kind(ElementKind::PartUsage)
+            .with_name("engine")
+            .with_owner(pkg.id.clone())
+            .with_prop("multiplicity_lower", PropValue::Int(1))
+            .with_span(Span::new("car.sysml", 10, 20));
+        let owning = Relationship::new(RelationshipKind::Owning, pkg.id.clone(), part.id.clone());
+        (pkg, part, owning)
+    }
+
+    fn graph(elements: &[&Element], relationships: &[&Relationship]) -> ModelGraph {
+        let mut graph = ModelGraph::new();
+        for element in elements {
+            graph.add_element((*element).clone());
+        }
+        for relationship in relationships {
+            graph.add_relationship((*relationship).clone());
+        }
+        graph
+    }
+
+    #[test]
+    fn insertion_order_does_not_matter() {
+        let (pkg, part, owning) = elements();
+        let forward = graph(&[&pkg, &part], &[&owning]);
+        let backward = graph(&[&part, &pkg], &[&owning]);
+        assert_eq!(graph_hash(&forward), graph_hash(&backward));
+        assert_eq!(graph_hash(&forward).len(), 32);
+        assert_ne!(graph_hash(&forward), graph_hash(&graph(&[&pkg, &part], &[])));
+    }
+
+    #[test]
+    fn any_prop_change_changes_the_hash() {
+        let (pkg, part, owning) = elements();
+        let original = graph_hash(&graph(&[&pkg, &part], &[&owning]));
+
+        let changes = [
+            part.clone().with_prop("multiplicity_lower", PropValue::Int(2)),
+            part.clone().with_prop("direction", "in"),
+            part.clone().with_name("motor"),
+            Element { kind: ElementKind::AttributeUsage, ..part.clone() },
+            Element { owner: None, ..part.clone() },
+        ];
+        for changed in &changes {
+            assert_ne!(element_hash(changed), element_hash(&part), "{:?}", changed);
+            assert_ne!(graph_hash(&graph(&[&pkg, changed], &[&owning])), original, "{:?}", changed);
+        }
+    }
+
+    #[test]
+    fn spans_and_qualified_names_are_skipped_by_default() {
+        let (pkg, part, _) = elements();
+        let moved = part.clone().with_span(Span::new("car.sysml", 30, 40));
+        let named = Element {
+            qname: Some("Vehicles::engine".parse().unwrap()),
+            ..part.clone()
+        };
+        assert_eq!(element_hash(&moved), element_hash(&part));
+        assert_eq!(element_hash(&named), element_hash(&part));
+
+        let with_spans = HashOptions::new().with_spans();
+        let before = graph_hash_with(&graph(&[&pkg, &part], &[]), with_spans);
+        let after = graph_hash_with(&graph(&[&pkg, &moved], &[]), with_spans);
+        assert_ne!(before, after);
+        assert_eq!(
+            graph_hash(&graph(&[&pkg, &part], &[])),
+            graph_hash(&graph(&[&pkg, &moved], &[]))
+        );
+    }
+}
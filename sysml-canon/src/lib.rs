@@ -10,10 +10,21 @@
 //! - Diffing and comparison
 //! - Reproducible builds
 //! - Testing
+//!
+//! It also imports and exports the SysML v2 API JSON element format
+//! (`@id`/`@type` objects) with [`from_api_json`] and [`to_api_json`], and
+//! computes order-independent content hashes with [`graph_hash`] and
+//! [`element_hash`], and hashes of raw bytes with [`bytes_hash`].
 
 use serde::{Deserialize, Serialize};
 use sysml_core::{Element, ModelGraph, Relationship};
 
+mod api;
+mod hash;
+
+pub use api::{from_api_json, to_api_json};
+pub use hash::{bytes_hash, element_hash, graph_hash, graph_hash_with, HashOptions};
+
 /// Error type for serialization/deserialization failures.
 #[derive(Debug)]
 pub enum CanonError {
@@ -21,6 +32,8 @@ pub enum CanonError {
     SerializeError(String),
     /// JSON deserialization error.
     DeserializeError(String),
+    /// API JSON `@type` names that are not known element kinds.
+    UnknownTypes(Vec<String>),
 }
 
 impl std::fmt::Display for CanonError {
@@ -28,6 +41,7 @@ impl std::fmt::Display for CanonError {
         match self {
             CanonError::SerializeError(s) => write!(f, "serialization error: {}", s),
             CanonError::DeserializeError(s) => write!(f, "deserialization error: {}", s),
+            CanonError::UnknownTypes(types) => write!(f, "unknown element types: {}", types.join(", ")),
         }
     }
 }
@@ -2,7 +2,8 @@
 //!
 //! This script generates:
 //! 1. The `ElementKind` enum from KerML and SysML vocabulary TTL files
-//! 2. Type hierarchy methods (supertypes, predicates, definition/usage mappings)
+//! 2. Type hierarchy methods (supertypes, predicates, definition/usage mappings,
+//!    and abstract/concrete predicates from the XMI metamodel)
 //! 3. Relationship source/target constraint methods (from JSON schemas)
 //! 4. Value enumeration types (FeatureDirectionKind, VisibilityKind, etc.)
 //! 5. Typed property accessors from OSLC shapes files
@@ -436,6 +437,18 @@ fn main() {
     // Generate type hierarchy methods
     let hierarchy_code = sysml_codegen::generate_hierarchy_methods(&kerml_types, &sysml_types);
 
+    // Generate abstract/concrete predicates (abstract flags only exist in XMI)
+    let abstract_classes =
+        sysml_codegen::parse_all_xmi_abstract_classes(&kerml_xmi_path, &sysml_xmi_path)
+            .unwrap_or_else(|e| panic!("Failed to parse XMI abstract classes: {}", e));
+    let abstract_code =
+        sysml_codegen::generate_abstract_predicates(&kerml_types, &sysml_types, &abstract_classes);
+
+    println!(
+        "cargo:warning=Abstract metaclasses from XMI: {}",
+        abstract_classes.len()
+    );
+
     // Get list of relationship types from the type hierarchy (deduplicated)
     let hierarchy_map = sysml_codegen::inheritance::build_type_hierarchy(&kerml_types, &sysml_types);
     let relationship_type_set: HashSet<&str> = kerml_types
@@ -524,8 +537,8 @@ fn main() {
 
     // Combine all ElementKind-related code
     let element_kind_code = format!(
-        "{}\n{}\n{}\n{}",
-        enum_code, hierarchy_code, relationship_code, relationship_property_code
+        "{}\n{}\n{}\n{}\n{}",
+        enum_code, hierarchy_code, abstract_code, relationship_code, relationship_property_code
     );
 
     // Collect valid element kinds for filtering
@@ -59,7 +59,7 @@ fn main() {
     result.validate_structure();
     result.validate_relationships();
 
-    let sysml_text::ParseResult { graph, diagnostics } = result;
+    let sysml_text::ParseResult { graph, diagnostics, .. } = result;
     print_diagnostics(&diagnostics);
     highlight_elements(&graph);
 }
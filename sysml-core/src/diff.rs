@@ -0,0 +1,1051 @@
+//! Structural diff between two model graphs.
+//!
+//! This module compares two `ModelGraph`s by `ElementId` and reports which
+//! elements and relationships were added, removed, or changed. It is the basis
+//! for "what changed" views between store commits.
+//!
+//! ## Comparison Rules
+//!
+//! - Elements are compared on `kind`, `name`, `owner`, and `props`
+//! - Relationships are compared on `kind`, `source`, `target`, and `props`
+//! - Spans, qualified names, and indexes are ignored
+//!
+//! All collections are ordered by id so the output is stable. `GraphDiff`
+//! implements `Display` with a short human-readable summary for CLI output.
+//!
+//! ## Applying Diffs
+//!
+//! [`GraphDiff::apply`] replays a diff onto another graph, for example a commit
+//! onto a branch. Field and property changes whose recorded old value no longer
+//! matches the target graph are conflicts: they are skipped and reported unless
+//! [`ApplyPolicy::Force`] is used.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Element, ElementKind, ModelGraph, Relationship, RelationshipKind};
+use sysml_id::ElementId;
+use sysml_meta::Value;
+
+/// How [`GraphDiff::apply`] handles changes that conflict with the target graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ApplyPolicy {
+    /// Skip conflicting changes and report them.
+    #[default]
+    Strict,
+    /// Apply conflicting changes anyway; they are still reported as conflicts.
+    Force,
+}
+
+/// The part of an element or relationship a change touches.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeField {
+    /// The element or relationship kind.
+    Kind,
+    /// The element name.
+    Name,
+    /// The element owner.
+    Owner,
+    /// The relationship source.
+    Source,
+    /// The relationship target.
+    Target,
+    /// A property, by key.
+    Prop(String),
+}
+
+/// A single change from a [`GraphDiff`], as reported by [`GraphDiff::apply`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchOp {
+    /// An element was added.
+    AddElement(ElementId),
+    /// An element was removed.
+    RemoveElement(ElementId),
+    /// A field of an existing element was changed.
+    ChangeElement(ElementId, ChangeField),
+    /// A relationship was added.
+    AddRelationship(ElementId),
+    /// A relationship was removed.
+    RemoveRelationship(ElementId),
+    /// A field of an existing relationship was changed.
+    ChangeRelationship(ElementId, ChangeField),
+}
+
+/// Summary of a [`GraphDiff::apply`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplyReport {
+    /// Changes that were applied, including ones the graph already reflected.
+    pub applied: Vec<PatchOp>,
+    /// Changes whose old value did not match the graph. With
+    /// [`ApplyPolicy::Force`] these were applied anyway and also appear in `applied`.
+    pub conflicts: Vec<PatchOp>,
+}
+
+impl ApplyReport {
+    /// Check if every change applied cleanly.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    fn record(&mut self, op: PatchOp, outcome: Outcome) {
+        match outcome {
+            Outcome::Applied => self.applied.push(op),
+            Outcome::Skipped => self.conflicts.push(op),
+            Outcome::Forced => {
+                self.applied.push(op.clone());
+                self.conflicts.push(op);
+            }
+        }
+    }
+}
+
+/// An error from [`GraphDiff::apply`]. The graph is left untouched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApplyError {
+    /// A changed element does not exist in the graph.
+    MissingElement(ElementId),
+    /// A changed relationship does not exist in the graph.
+    MissingRelationship(ElementId),
+    /// A removed element would still own a child that is not removed.
+    OwnsChildren {
+        /// The element scheduled for removal.
+        element: ElementId,
+        /// A child that would be left without its owner.
+        child: ElementId,
+    },
+    /// A relationship that is not removed still references a removed element.
+    DanglingRelationship {
+        /// The relationship that would be left dangling.
+        relationship: ElementId,
+        /// The removed element it references as source or target.
+        element: ElementId,
+    },
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyError::MissingElement(id) => write!(f, "changed element {} does not exist", id),
+            ApplyError::MissingRelationship(id) => {
+                write!(f, "changed relationship {} does not exist", id)
+            }
+            ApplyError::OwnsChildren { element, child } => write!(
+                f,
+                "cannot remove element {}: it still owns {}",
+                element, child
+            ),
+            ApplyError::DanglingRelationship { relationship, element } => write!(
+                f,
+                "relationship {} still references removed element {}",
+                relationship, element
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ApplyError {}
+
+/// What happened to a single change during apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Applied,
+    Skipped,
+    Forced,
+}
+
+/// Move `current` from `old` to `new`, treating a mismatch with `old` as a conflict.
+fn apply_value<T: PartialEq + Clone>(current: &mut T, old: &T, new: &T, policy: ApplyPolicy) -> Outcome {
+    if current == new {
+        Outcome::Applied
+    } else if current == old {
+        *current = new.clone();
+        Outcome::Applied
+    } else if policy == ApplyPolicy::Force {
+        *current = new.clone();
+        Outcome::Forced
+    } else {
+        Outcome::Skipped
+    }
+}
+
+/// Apply property changes to a property map.
+fn apply_props(
+    props: &mut BTreeMap<String, Value>,
+    changes: &BTreeMap<String, PropertyChange>,
+    policy: ApplyPolicy,
+) -> Vec<(String, Outcome)> {
+    changes
+        .iter()
+        .map(|(key, change)| {
+            let mut current = props.get(key).cloned();
+            let outcome = apply_value(&mut current, &change.old, &change.new, policy);
+            match current {
+                Some(value) => props.insert(key.clone(), value),
+                None => props.remove(key),
+            };
+            (key.clone(), outcome)
+        })
+        .collect()
+}
+
+/// A change of a single field from `old` to `new`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FieldChange<T> {
+    /// The value in the old graph.
+    pub old: T,
+    /// The value in the new graph.
+    pub new: T,
+}
+
+impl<T: PartialEq + Clone> FieldChange<T> {
+    /// Build a change if the two values differ.
+    fn between(old: &T, new: &T) -> Option<Self> {
+        if old == new {
+            None
+        } else {
+            Some(FieldChange {
+                old: old.clone(),
+                new: new.clone(),
+            })
+        }
+    }
+}
+
+/// A change to a single property.
+///
+/// `old` is None when the property was added, `new` is None when it was removed.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PropertyChange {
+    /// The property value in the old graph.
+    pub old: Option<Value>,
+    /// The property value in the new graph.
+    pub new: Option<Value>,
+}
+
+/// Changes to an element present in both graphs.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ElementChange {
+    /// The id of the changed element.
+    pub id: ElementId,
+    /// Kind change, if any.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub kind: Option<FieldChange<ElementKind>>,
+    /// Name change, if any.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub name: Option<FieldChange<Option<String>>>,
+    /// Owner change, if any.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub owner: Option<FieldChange<Option<ElementId>>>,
+    /// Property changes keyed by property name.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub props: BTreeMap<String, PropertyChange>,
+}
+
+impl ElementChange {
+    /// Compare two versions of an element. Returns None if they are equivalent.
+    fn between(old: &Element, new: &Element) -> Option<Self> {
+        let change = ElementChange {
+            id: new.id.clone(),
+            kind: FieldChange::between(&old.kind, &new.kind),
+            name: FieldChange::between(&old.name, &new.name),
+            owner: FieldChange::between(&old.owner, &new.owner),
+            props: diff_props(&old.props, &new.props),
+        };
+
+        if change.kind.is_none()
+            && change.name.is_none()
+            && change.owner.is_none()
+            && change.props.is_empty()
+        {
+            None
+        } else {
+            Some(change)
+        }
+    }
+}
+
+/// Changes to a relationship present in both graphs.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RelationshipChange {
+    /// The id of the changed relationship.
+    pub id: ElementId,
+    /// Kind change, if any.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub kind: Option<FieldChange<RelationshipKind>>,
+    /// Source change, if any.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub source: Option<FieldChange<ElementId>>,
+    /// Target change, if any.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub target: Option<FieldChange<ElementId>>,
+    /// Property changes keyed by property name.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub props: BTreeMap<String, PropertyChange>,
+}
+
+impl RelationshipChange {
+    /// Compare two versions of a relationship. Returns None if they are equivalent.
+    fn between(old: &Relationship, new: &Relationship) -> Option<Self> {
+        let change = RelationshipChange {
+            id: new.id.clone(),
+            kind: FieldChange::between(&old.kind, &new.kind),
+            source: FieldChange::between(&old.source, &new.source),
+            target: FieldChange::between(&old.target, &new.target),
+            props: diff_props(&old.props, &new.props),
+        };
+
+        if change.kind.is_none()
+            && change.source.is_none()
+            && change.target.is_none()
+            && change.props.is_empty()
+        {
+            None
+        } else {
+            Some(change)
+        }
+    }
+}
+
+/// The structural difference between two model graphs.
+///
+/// Produced by [`ModelGraph::diff`]. Added items carry their full content so the
+/// diff is self-contained; removed items are listed by id.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GraphDiff {
+    /// Elements only present in the new graph.
+    pub added_elements: Vec<Element>,
+    /// Ids of elements only present in the old graph.
+    pub removed_elements: Vec<ElementId>,
+    /// Elements present in both graphs whose content differs.
+    pub changed_elements: Vec<ElementChange>,
+    /// Relationships only present in the new graph.
+    pub added_relationships: Vec<Relationship>,
+    /// Ids of relationships only present in the old graph.
+    pub removed_relationships: Vec<ElementId>,
+    /// Relationships present in both graphs whose content differs.
+    pub changed_relationships: Vec<RelationshipChange>,
+}
+
+impl GraphDiff {
+    /// Check if the two graphs were equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.added_elements.is_empty()
+            && self.removed_elements.is_empty()
+            && self.changed_elements.is_empty()
+            && self.added_relationships.is_empty()
+            && self.removed_relationships.is_empty()
+            && self.changed_relationships.is_empty()
+    }
+}
+
+impl fmt::Display for GraphDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no changes");
+        }
+
+        writeln!(
+            f,
+            "elements: {} added, {} removed, {} changed; relationships: {} added, {} removed, {} changed",
+            self.added_elements.len(),
+            self.removed_elements.len(),
+            self.changed_elements.len(),
+            self.added_relationships.len(),
+            self.removed_relationships.len(),
+            self.changed_relationships.len(),
+        )?;
+
+        for element in &self.added_elements {
+            write!(f, "  + {} {}", element.kind, element.id)?;
+            if let Some(name) = &element.name {
+                write!(f, " \"{}\"", name)?;
+            }
+            writeln!(f)?;
+        }
+        for id in &self.removed_elements {
+            writeln!(f, "  - element {}", id)?;
+        }
+        for change in &self.changed_elements {
+            writeln!(f, "  ~ element {}", change.id)?;
+            if let Some(kind) = &change.kind {
+                writeln!(f, "      kind: {} -> {}", kind.old, kind.new)?;
+            }
+            if let Some(name) = &change.name {
+                writeln!(f, "      name: {} -> {}", display_opt(&name.old), display_opt(&name.new))?;
+            }
+            if let Some(owner) = &change.owner {
+                writeln!(f, "      owner: {} -> {}", display_opt(&owner.old), display_opt(&owner.new))?;
+            }
+            write_props(f, &change.props)?;
+        }
+
+        for rel in &self.added_relationships {
+            writeln!(f, "  + {} {} ({} -> {})", rel.kind, rel.id, rel.source, rel.target)?;
+        }
+        for id in &self.removed_relationships {
+            writeln!(f, "  - relationship {}", id)?;
+        }
+        for change in &self.changed_relationships {
+            writeln!(f, "  ~ relationship {}", change.id)?;
+            if let Some(kind) = &change.kind {
+                writeln!(f, "      kind: {} -> {}", kind.old, kind.new)?;
+            }
+            if let Some(source) = &change.source {
+                writeln!(f, "      source: {} -> {}", source.old, source.new)?;
+            }
+            if let Some(target) = &change.target {
+                writeln!(f, "      target: {} -> {}", target.old, target.new)?;
+            }
+            write_props(f, &change.props)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl GraphDiff {
+    /// Apply this diff to `graph`.
+    ///
+    /// Removals run first, then additions, then field and property changes.
+    /// A change whose recorded old value does not match the graph is a
+    /// conflict and is handled according to `policy`; a change the graph
+    /// already reflects counts as applied. Adding an element or relationship
+    /// whose id exists with different content is a conflict, and removing one
+    /// that is already gone is a no-op.
+    ///
+    /// Relationships are only removed when the diff lists them, and implicit
+    /// memberships are not created for added elements, so applying
+    /// `old.diff(&new)` to a copy of `old` reproduces `new`. Indexes are rebuilt
+    /// afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Fails without modifying the graph if a changed element or relationship
+    /// is missing, if a removed element would still own a remaining element,
+    /// or if a relationship the diff does not remove would still have a removed
+    /// element as its source or target.
+    pub fn apply(&self, graph: &mut ModelGraph, policy: ApplyPolicy) -> Result<ApplyReport, ApplyError> {
+        self.check_applicable(graph)?;
+
+        let mut report = ApplyReport::default();
+
+        for id in &self.removed_relationships {
+            graph.relationships.remove(id);
+            report.record(PatchOp::RemoveRelationship(id.clone()), Outcome::Applied);
+        }
+
+        let removed: BTreeSet<&ElementId> = self.removed_elements.iter().collect();
+        for id in &self.removed_elements {
+            graph.elements.remove(id);
+            graph.unregister_library_package(id);
+            report.record(PatchOp::RemoveElement(id.clone()), Outcome::Applied);
+        }
+        graph.library_name_index.retain(|_, indexed| !removed.contains(indexed));
+
+        for element in &self.added_elements {
+            let outcome = match graph.elements.get(&element.id) {
+                Some(existing) if ElementChange::between(existing, element).is_some() => {
+                    if policy == ApplyPolicy::Force {
+                        Outcome::Forced
+                    } else {
+                        Outcome::Skipped
+                    }
+                }
+                _ => Outcome::Applied,
+            };
+            if outcome != Outcome::Skipped {
+                graph.elements.insert(element.id.clone(), element.clone());
+            }
+            report.record(PatchOp::AddElement(element.id.clone()), outcome);
+        }
+
+        for rel in &self.added_relationships {
+            let outcome = match graph.relationships.get(&rel.id) {
+                Some(existing) if RelationshipChange::between(existing, rel).is_some() => {
+                    if policy == ApplyPolicy::Force {
+                        Outcome::Forced
+                    } else {
+                        Outcome::Skipped
+                    }
+                }
+                _ => Outcome::Applied,
+            };
+            if outcome != Outcome::Skipped {
+                graph.relationships.insert(rel.id.clone(), rel.clone());
+            }
+            report.record(PatchOp::AddRelationship(rel.id.clone()), outcome);
+        }
+
+        for change in &self.changed_elements {
+            let element = match graph.elements.get_mut(&change.id) {
+                Some(element) => element,
+                None => continue,
+            };
+            let op = |field| PatchOp::ChangeElement(change.id.clone(), field);
+            if let Some(kind) = &change.kind {
+                report.record(op(ChangeField::Kind), apply_value(&mut element.kind, &kind.old, &kind.new, policy));
+            }
+            if let Some(name) = &change.name {
+                report.record(op(ChangeField::Name), apply_value(&mut element.name, &name.old, &name.new, policy));
+            }
+            if let Some(owner) = &change.owner {
+                report.record(op(ChangeField::Owner), apply_value(&mut element.owner, &owner.old, &owner.new, policy));
+            }
+            for (key, outcome) in apply_props(&mut element.props, &change.props, policy) {
+                report.record(op(ChangeField::Prop(key)), outcome);
+            }
+        }
+
+        for change in &self.changed_relationships {
+            let rel = match graph.relationships.get_mut(&change.id) {
+                Some(rel) => rel,
+                None => continue,
+            };
+            let op = |field| PatchOp::ChangeRelationship(change.id.clone(), field);
+            if let Some(kind) = &change.kind {
+                report.record(op(ChangeField::Kind), apply_value(&mut rel.kind, &kind.old, &kind.new, policy));
+            }
+            if let Some(source) = &change.source {
+                report.record(op(ChangeField::Source), apply_value(&mut rel.source, &source.old, &source.new, policy));
+            }
+            if let Some(target) = &change.target {
+                report.record(op(ChangeField::Target), apply_value(&mut rel.target, &target.old, &target.new, policy));
+            }
+            for (key, outcome) in apply_props(&mut rel.props, &change.props, policy) {
+                report.record(op(ChangeField::Prop(key)), outcome);
+            }
+        }
+
+        graph.rebuild_indexes();
+        graph.library_index_dirty = true;
+        Ok(report)
+    }
+
+    /// Reject diffs that cannot be applied to `graph` at all.
+    fn check_applicable(&self, graph: &ModelGraph) -> Result<(), ApplyError> {
+        for change in &self.changed_elements {
+            if !graph.elements.contains_key(&change.id) {
+                return Err(ApplyError::MissingElement(change.id.clone()));
+            }
+        }
+        for change in &self.changed_relationships {
+            if !graph.relationships.contains_key(&change.id) {
+                return Err(ApplyError::MissingRelationship(change.id.clone()));
+            }
+        }
+
+        let removed: BTreeSet<&ElementId> = self.removed_elements.iter().collect();
+        if removed.is_empty() {
+            return Ok(());
+        }
+        let new_owners: BTreeMap<&ElementId, &Option<ElementId>> = self
+            .changed_elements
+            .iter()
+            .filter_map(|change| change.owner.as_ref().map(|owner| (&change.id, &owner.new)))
+            .collect();
+        let remaining = graph
+            .elements
+            .values()
+            .filter(|element| !removed.contains(&element.id))
+            .chain(&self.added_elements);
+        for element in remaining {
+            let owner = new_owners.get(&element.id).copied().unwrap_or(&element.owner);
+            if let Some(owner) = owner {
+                if removed.contains(owner) {
+                    return Err(ApplyError::OwnsChildren {
+                        element: owner.clone(),
+                        child: element.id.clone(),
+                    });
+                }
+            }
+        }
+
+        let removed_rels: BTreeSet<&ElementId> = self.removed_relationships.iter().collect();
+        let changed_rels: BTreeMap<&ElementId, &RelationshipChange> =
+            self.changed_relationships.iter().map(|change| (&change.id, change)).collect();
+        let remaining_rels = graph
+            .relationships
+            .values()
+            .filter(|rel| !removed_rels.contains(&rel.id))
+            .chain(&self.added_relationships);
+        for rel in remaining_rels {
+            let change = changed_rels.get(&rel.id);
+            let source = change.and_then(|c| c.source.as_ref()).map_or(&rel.source, |source| &source.new);
+            let target = change.and_then(|c| c.target.as_ref()).map_or(&rel.target, |target| &target.new);
+            for end in [source, target] {
+                if removed.contains(end) {
+                    return Err(ApplyError::DanglingRelationship {
+                        relationship: rel.id.clone(),
+                        element: end.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Render an optional field value, using `none` for a missing one.
+fn display_opt<T: fmt::Display>(value: &Option<T>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "none".to_string(),
+    }
+}
+
+/// Write one line per property change.
+fn write_props(f: &mut fmt::Formatter<'_>, props: &BTreeMap<String, PropertyChange>) -> fmt::Result {
+    for (key, change) in props {
+        writeln!(f, "      {}: {} -> {}", key, display_opt(&change.old), display_opt(&change.new))?;
+    }
+    Ok(())
+}
+
+/// Compute the structural diff between an old and a new graph.
+///
+/// Equivalent to `old.diff(new)`; see [`ModelGraph::diff`].
+pub fn diff_graphs(old: &ModelGraph, new: &ModelGraph) -> GraphDiff {
+    old.diff(new)
+}
+
+/// Compare two property maps, returning the keys whose values differ.
+fn diff_props(
+    old: &BTreeMap<String, Value>,
+    new: &BTreeMap<String, Value>,
+) -> BTreeMap<String, PropertyChange> {
+    let mut changes = BTreeMap::new();
+
+    for (key, old_value) in old {
+        match new.get(key) {
+            Some(new_value) if new_value == old_value => {}
+            new_value => {
+                changes.insert(
+                    key.clone(),
+                    PropertyChange {
+                        old: Some(old_value.clone()),
+                        new: new_value.cloned(),
+                    },
+                );
+            }
+        }
+    }
+
+    for (key, new_value) in new {
+        if !old.contains_key(key) {
+            changes.insert(
+                key.clone(),
+                PropertyChange {
+                    old: None,
+                    new: Some(new_value.clone()),
+                },
+            );
+        }
+    }
+
+    changes
+}
+
+impl ModelGraph {
+    /// Compute the structural diff from this graph to `other`.
+    ///
+    /// `self` is treated as the old version and `other` as the new one.
+    /// Elements and relationships are matched by id.
+    pub fn diff(&self, other: &ModelGraph) -> GraphDiff {
+        let mut diff = GraphDiff::default();
+
+        for (id, old) in &self.elements {
+            match other.elements.get(id) {
+                Some(new) => {
+                    if let Some(change) = ElementChange::between(old, new) {
+                        diff.changed_elements.push(change);
+                    }
+                }
+                None => diff.removed_elements.push(id.clone()),
+            }
+        }
+        for (id, new) in &other.elements {
+            if !self.elements.contains_key(id) {
+                diff.added_elements.push(new.clone());
+            }
+        }
+
+        for (id, old) in &self.relationships {
+            match other.relationships.get(id) {
+                Some(new) => {
+                    if let Some(change) = RelationshipChange::between(old, new) {
+                        diff.changed_relationships.push(change);
+                    }
+                }
+                None => diff.removed_relationships.push(id.clone()),
+            }
+        }
+        for (id, new) in &other.relationships {
+            if !self.relationships.contains_key(id) {
+                diff.added_relationships.push(new.clone());
+            }
+        }
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VisibilityKind;
+
+    /// Package `P` owning `engine` (which owns `piston`) and `wheel`, with a
+    /// relationship from `wheel` to `engine`.
+    fn create_patch_graph() -> (ModelGraph, ElementId, ElementId, ElementId, ElementId) {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("P"));
+        let engine = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartUsage)
+                .with_name("engine")
+                .with_prop("mass", 100i64),
+            pkg.clone(),
+            VisibilityKind::Public,
+        );
+        graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartUsage).with_name("piston"),
+            engine.clone(),
+            VisibilityKind::Public,
+        );
+        let wheel = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartUsage).with_name("wheel"),
+            pkg.clone(),
+            VisibilityKind::Public,
+        );
+        let rel = graph.add_relationship(Relationship::new(
+            RelationshipKind::Satisfy,
+            wheel.clone(),
+            engine.clone(),
+        ));
+        (graph, pkg, engine, wheel, rel)
+    }
+
+    #[test]
+    fn identical_graphs_have_empty_diff() {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("P"));
+        graph.add_element(
+            Element::new_with_kind(ElementKind::PartUsage)
+                .with_name("a")
+                .with_owner(pkg),
+        );
+
+        assert!(graph.diff(&graph.clone()).is_empty());
+    }
+
+    #[test]
+    fn added_and_removed_elements() {
+        let mut old = ModelGraph::new();
+        let kept = old.add_element(Element::new_with_kind(ElementKind::Package).with_name("P"));
+        let removed = old.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("gone"));
+
+        let mut new = ModelGraph::new();
+        new.add_element(old.get_element(&kept).unwrap().clone());
+        let added = new.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("fresh"));
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.removed_elements, vec![removed]);
+        assert_eq!(diff.added_elements.len(), 1);
+        assert_eq!(diff.added_elements[0].id, added);
+        assert!(diff.changed_elements.is_empty());
+    }
+
+    #[test]
+    fn changed_element_reports_field_and_prop_deltas() {
+        let id = ElementId::new_v4();
+        let owner = ElementId::new_v4();
+
+        let mut old = ModelGraph::new();
+        old.add_element(
+            Element::new(id.clone(), ElementKind::PartUsage)
+                .with_name("engine")
+                .with_prop("mass", 100i64)
+                .with_prop("legacy", true),
+        );
+
+        let mut new = ModelGraph::new();
+        new.add_element(
+            Element::new(id.clone(), ElementKind::PartUsage)
+                .with_name("motor")
+                .with_owner(owner.clone())
+                .with_prop("mass", 120i64)
+                .with_prop("color", "red"),
+        );
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.changed_elements.len(), 1);
+
+        let change = &diff.changed_elements[0];
+        assert_eq!(change.id, id);
+        assert!(change.kind.is_none());
+        assert_eq!(
+            change.name,
+            Some(FieldChange {
+                old: Some("engine".to_string()),
+                new: Some("motor".to_string()),
+            })
+        );
+        assert_eq!(change.owner.as_ref().unwrap().new, Some(owner));
+
+        assert_eq!(change.props.len(), 3);
+        assert_eq!(change.props["mass"].old, Some(Value::Int(100)));
+        assert_eq!(change.props["mass"].new, Some(Value::Int(120)));
+        assert_eq!(change.props["legacy"].new, None);
+        assert_eq!(change.props["color"].old, None);
+    }
+
+    #[test]
+    fn list_reordering_is_a_prop_change() {
+        let id = ElementId::new_v4();
+        let list = |items: &[&str]| Value::List(items.iter().map(|s| Value::from(*s)).collect());
+
+        let mut old = ModelGraph::new();
+        old.add_element(Element::new(id.clone(), ElementKind::PartUsage).with_prop("tags", list(&["a", "b"])));
+        let mut new = ModelGraph::new();
+        new.add_element(Element::new(id.clone(), ElementKind::PartUsage).with_prop("tags", list(&["b", "a"])));
+
+        let diff = diff_graphs(&old, &new);
+        assert_eq!(diff.changed_elements.len(), 1);
+        let change = &diff.changed_elements[0].props["tags"];
+        assert_eq!(change.old, Some(list(&["a", "b"])));
+        assert_eq!(change.new, Some(list(&["b", "a"])));
+    }
+
+    #[test]
+    fn nested_map_change_reports_whole_value() {
+        let id = ElementId::new_v4();
+        let nested = |limit: i64| {
+            let mut inner = BTreeMap::new();
+            inner.insert("limit".to_string(), Value::Int(limit));
+            let mut outer = BTreeMap::new();
+            outer.insert("bounds".to_string(), Value::Map(inner));
+            outer.insert("unit".to_string(), Value::from("kg"));
+            Value::Map(outer)
+        };
+
+        let mut old = ModelGraph::new();
+        old.add_element(Element::new(id.clone(), ElementKind::AttributeUsage).with_prop("constraint", nested(10)));
+        let mut same = ModelGraph::new();
+        same.add_element(Element::new(id.clone(), ElementKind::AttributeUsage).with_prop("constraint", nested(10)));
+        let mut new = ModelGraph::new();
+        new.add_element(Element::new(id.clone(), ElementKind::AttributeUsage).with_prop("constraint", nested(20)));
+
+        assert!(diff_graphs(&old, &same).is_empty());
+
+        let diff = diff_graphs(&old, &new);
+        let change = &diff.changed_elements[0].props["constraint"];
+        assert_eq!(change.old, Some(nested(10)));
+        assert_eq!(change.new, Some(nested(20)));
+    }
+
+    #[test]
+    fn display_summarizes_changes() {
+        let id = ElementId::new_v4();
+        let mut old = ModelGraph::new();
+        old.add_element(Element::new(id.clone(), ElementKind::PartUsage).with_name("engine"));
+        let removed = old.add_element(Element::new_with_kind(ElementKind::PartUsage));
+
+        let mut new = ModelGraph::new();
+        new.add_element(
+            Element::new(id.clone(), ElementKind::PartUsage)
+                .with_name("motor")
+                .with_prop("mass", 120i64),
+        );
+
+        let text = diff_graphs(&old, &new).to_string();
+        assert!(text.starts_with("elements: 0 added, 1 removed, 1 changed;"));
+        assert!(text.contains(&format!("  - element {}", removed)));
+        assert!(text.contains(&format!("  ~ element {}", id)));
+        assert!(text.contains("      name: engine -> motor"));
+        assert!(text.contains("      mass: none -> 120"));
+
+        assert_eq!(diff_graphs(&old, &old).to_string(), "no changes");
+    }
+
+    #[test]
+    fn apply_round_trips_diff() {
+        let (old, pkg, engine, wheel, rel) = create_patch_graph();
+
+        let mut new = old.clone();
+        new.get_element_mut(&engine).unwrap().name = Some("motor".to_string());
+        new.get_element_mut(&engine).unwrap().set_prop("mass", 120i64);
+        new.remove_subtree(&wheel);
+        let axle = new.add_owned_element(
+            Element::new_with_kind(ElementKind::PartUsage)
+                .with_name("axle")
+                .with_prop("tags", Value::List(vec![Value::from("a"), Value::from("b")])),
+            pkg.clone(),
+            VisibilityKind::Public,
+        );
+        new.add_relationship(Relationship::new(RelationshipKind::Satisfy, axle.clone(), engine.clone()));
+        assert!(new.get_relationship(&rel).is_none());
+
+        let diff = old.diff(&new);
+        let mut patched = old.clone();
+        let report = diff.apply(&mut patched, ApplyPolicy::Strict).unwrap();
+
+        assert!(report.is_clean());
+        assert!(report.applied.contains(&PatchOp::ChangeElement(engine.clone(), ChangeField::Name)));
+        assert!(report.applied.contains(&PatchOp::RemoveElement(wheel.clone())));
+        assert!(patched.diff(&new).is_empty());
+        assert_eq!(patched.element_count(), new.element_count());
+        assert_eq!(patched.relationship_count(), new.relationship_count());
+        assert_eq!(patched.get_element(&engine).unwrap().props, new.get_element(&engine).unwrap().props);
+
+        // Indexes were rebuilt to match the new content
+        assert!(!patched.is_indexes_dirty());
+        for id in new.elements.keys() {
+            let children = |g: &ModelGraph| g.children_of(id).map(|c| c.id.clone()).collect::<BTreeSet<_>>();
+            assert_eq!(children(&patched), children(&new));
+            assert_eq!(patched.incoming(id).count(), new.incoming(id).count());
+        }
+        assert_eq!(patched.outgoing(&axle).count(), 1);
+        assert!(patched.validate_structure().is_empty());
+    }
+
+    #[test]
+    fn apply_reports_prop_conflicts() {
+        let (old, _pkg, engine, _wheel, _rel) = create_patch_graph();
+        let mut new = old.clone();
+        new.get_element_mut(&engine).unwrap().set_prop("mass", 120i64);
+        new.get_element_mut(&engine).unwrap().name = Some("motor".to_string());
+        let diff = old.diff(&new);
+
+        // Someone else changed the mass on the branch
+        let mut branch = old.clone();
+        branch.get_element_mut(&engine).unwrap().set_prop("mass", 110i64);
+
+        let mut strict = branch.clone();
+        let report = diff.apply(&mut strict, ApplyPolicy::Strict).unwrap();
+        let mass = PatchOp::ChangeElement(engine.clone(), ChangeField::Prop("mass".to_string()));
+        assert_eq!(report.conflicts, vec![mass.clone()]);
+        assert!(!report.applied.contains(&mass));
+        let engine_el = strict.get_element(&engine).unwrap();
+        assert_eq!(engine_el.get_int("mass"), Some(110));
+        assert_eq!(engine_el.name.as_deref(), Some("motor"));
+
+        let mut forced = branch.clone();
+        let report = diff.apply(&mut forced, ApplyPolicy::Force).unwrap();
+        assert_eq!(report.conflicts, vec![mass.clone()]);
+        assert!(report.applied.contains(&mass));
+        assert_eq!(forced.get_element(&engine).unwrap().get_int("mass"), Some(120));
+
+        // Applying twice is a no-op
+        let report = diff.apply(&mut forced, ApplyPolicy::Strict).unwrap();
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn apply_rejects_removing_an_owner_of_remaining_children() {
+        let (old, _pkg, engine, _wheel, _rel) = create_patch_graph();
+        let mut new = old.clone();
+        new.remove_subtree(&engine);
+        let diff = old.diff(&new);
+
+        // The branch added a child under engine that the diff knows nothing about
+        let mut branch = old.clone();
+        let extra = branch.add_owned_element(
+            Element::new_with_kind(ElementKind::PartUsage).with_name("crank"),
+            engine.clone(),
+            VisibilityKind::Public,
+        );
+        let before = branch.element_count();
+
+        let err = diff.apply(&mut branch, ApplyPolicy::Force).unwrap_err();
+        assert_eq!(err, ApplyError::OwnsChildren { element: engine.clone(), child: extra });
+        assert_eq!(branch.element_count(), before);
+        assert!(branch.get_element(&engine).is_some());
+    }
+
+    #[test]
+    fn apply_rejects_leaving_relationships_to_removed_elements() {
+        let (old, _pkg, engine, wheel, _rel) = create_patch_graph();
+        let mut new = old.clone();
+        new.remove_subtree(&wheel);
+        let diff = old.diff(&new);
+
+        // The branch added a relationship to wheel that the diff does not remove
+        let mut branch = old.clone();
+        let trace = branch.add_relationship(Relationship::new(RelationshipKind::Trace, engine.clone(), wheel.clone()));
+        let before = branch.relationship_count();
+
+        let err = diff.apply(&mut branch, ApplyPolicy::Force).unwrap_err();
+        assert_eq!(err, ApplyError::DanglingRelationship { relationship: trace, element: wheel.clone() });
+        assert!(err.to_string().contains("still references removed element"));
+        assert_eq!(branch.relationship_count(), before);
+        assert!(branch.get_element(&wheel).is_some());
+
+        // Applying to the graph the diff was made from leaves nothing dangling
+        let mut patched = old.clone();
+        diff.apply(&mut patched, ApplyPolicy::Strict).unwrap();
+        for rel in patched.relationships.values() {
+            assert!(rel.source != wheel && rel.target != wheel);
+        }
+    }
+
+    #[test]
+    fn apply_rejects_changes_to_missing_elements() {
+        let (old, _pkg, engine, _wheel, _rel) = create_patch_graph();
+        let mut new = old.clone();
+        new.get_element_mut(&engine).unwrap().name = Some("motor".to_string());
+
+        let err = old.diff(&new).apply(&mut ModelGraph::new(), ApplyPolicy::Force).unwrap_err();
+        assert_eq!(err, ApplyError::MissingElement(engine));
+        assert!(err.to_string().contains("does not exist"));
+    }
+
+    #[test]
+    fn relationship_changes() {
+        let a = ElementId::new_v4();
+        let b = ElementId::new_v4();
+        let c = ElementId::new_v4();
+        let rel_id = ElementId::new_v4();
+
+        let mut old = ModelGraph::new();
+        old.add_relationship(Relationship::with_id(rel_id.clone(), RelationshipKind::Satisfy, a.clone(), b));
+        let dropped = old.add_relationship(Relationship::new(RelationshipKind::Trace, a.clone(), c.clone()));
+
+        let mut new = ModelGraph::new();
+        new.add_relationship(Relationship::with_id(rel_id.clone(), RelationshipKind::Satisfy, a.clone(), c.clone()));
+        let added = new.add_relationship(Relationship::new(RelationshipKind::Verify, c, a));
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.removed_relationships, vec![dropped]);
+        assert_eq!(diff.added_relationships.len(), 1);
+        assert_eq!(diff.added_relationships[0].id, added);
+        assert_eq!(diff.changed_relationships.len(), 1);
+        assert_eq!(diff.changed_relationships[0].id, rel_id);
+        assert!(diff.changed_relationships[0].source.is_none());
+        assert!(diff.changed_relationships[0].target.is_some());
+    }
+
+    #[test]
+    fn diff_is_ordered_by_id() {
+        let old = ModelGraph::new();
+        let mut new = ModelGraph::new();
+        for i in 0..10 {
+            new.add_element(Element::new(
+                ElementId::from_string(format!("e{}", 9 - i)),
+                ElementKind::PartUsage,
+            ));
+        }
+
+        let diff = old.diff(&new);
+        let ids: Vec<_> = diff.added_elements.iter().map(|e| e.id.clone()).collect();
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(ids, sorted);
+    }
+}
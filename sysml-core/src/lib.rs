@@ -20,7 +20,7 @@
 //! This crate also provides typed property accessors generated from OSLC shapes.
 //! Use `element.as_part_usage()` to get a typed accessor for PartUsage properties.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use rustc_hash::{FxHashMap, FxHashSet};
 
@@ -39,14 +39,24 @@ mod membership;
 mod ownership;
 mod namespace;
 mod structural_validation;
+mod multiplicity_validation;
+mod unresolved;
 mod factory;
+mod diff;
 
 // Name resolution module (Phase 2d)
 pub mod resolution;
 
 pub use membership::{MembershipBuilder, MembershipView, OwningMembershipView};
+pub use ownership::{ElementReferences, PropReference};
 pub use structural_validation::StructuralError;
+pub use multiplicity_validation::{multiplicity_bounds, validate_multiplicities, Bounds};
+pub use unresolved::{similar_names, UnresolvedRef};
 pub use factory::ElementFactory;
+pub use diff::{
+    diff_graphs, ApplyError, ApplyPolicy, ApplyReport, ChangeField, ElementChange, FieldChange,
+    GraphDiff, PatchOp, PropertyChange, RelationshipChange,
+};
 
 // Include the generated ElementKind enum (with hierarchy, predicates, and relationship methods)
 include!(concat!(env!("OUT_DIR"), "/element_kind.generated.rs"));
@@ -114,6 +124,27 @@ impl RelationshipKind {
             RelationshipKind::Transition => "Transition",
         }
     }
+
+    /// Get the metamodel relationship kind that constrains this kind's endpoints.
+    ///
+    /// Returns `None` for kinds without a direct metamodel counterpart (e.g.
+    /// `Trace` or `Transition`), whose endpoints are unconstrained.
+    pub fn metamodel_kind(&self) -> Option<ElementKind> {
+        match self {
+            RelationshipKind::Owning => Some(ElementKind::OwningMembership),
+            RelationshipKind::TypeOf => Some(ElementKind::FeatureTyping),
+            RelationshipKind::Specialize => Some(ElementKind::Specialization),
+            RelationshipKind::Redefine => Some(ElementKind::Redefinition),
+            RelationshipKind::Subsetting => Some(ElementKind::Subsetting),
+            RelationshipKind::Flow => Some(ElementKind::Flow),
+            RelationshipKind::Satisfy
+            | RelationshipKind::Verify
+            | RelationshipKind::Derive
+            | RelationshipKind::Trace
+            | RelationshipKind::Reference
+            | RelationshipKind::Transition => None,
+        }
+    }
 }
 
 impl Default for RelationshipKind {
@@ -230,6 +261,31 @@ impl Element {
         self.props.get(key)
     }
 
+    /// Get a boolean property (see [`Value::as_bool`]).
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get_prop(key).and_then(|v| v.as_bool())
+    }
+
+    /// Get an integer property (see [`Value::as_int`]).
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        self.get_prop(key).and_then(|v| v.as_int())
+    }
+
+    /// Get a numeric property as a float; integers are converted (see [`Value::as_float`]).
+    pub fn get_float(&self, key: &str) -> Option<f64> {
+        self.get_prop(key).and_then(|v| v.as_float())
+    }
+
+    /// Get a string or enum property (see [`Value::as_str`]).
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get_prop(key).and_then(|v| v.as_str())
+    }
+
+    /// Get a reference property (see [`Value::as_ref`]).
+    pub fn get_ref(&self, key: &str) -> Option<&ElementId> {
+        self.get_prop(key).and_then(|v| v.as_ref())
+    }
+
     /// Set a property value.
     pub fn set_prop(&mut self, key: impl Into<String>, value: impl Into<Value>) {
         self.props.insert(key.into(), value.into());
@@ -283,6 +339,52 @@ impl Relationship {
     }
 }
 
+/// How [`ModelGraph::merge`] handles ids present in both graphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePolicy {
+    /// Overwrite the existing element or relationship with the merged one.
+    #[default]
+    ReplaceExisting,
+    /// Keep the existing element or relationship and skip the merged one.
+    KeepExisting,
+    /// Refuse to merge if any id collides, leaving the graph untouched.
+    Error,
+}
+
+/// Summary of a [`ModelGraph::merge`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Number of elements inserted, including ones that replaced an existing element.
+    pub merged: usize,
+    /// Ids of existing elements that were replaced, in id order.
+    pub replaced: Vec<ElementId>,
+    /// Number of elements skipped because the id was already present.
+    pub skipped: usize,
+}
+
+/// An error from [`ModelGraph::merge`] with [`MergePolicy::Error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeError {
+    /// Elements or relationships in both graphs share these ids (sorted).
+    IdCollision(Vec<ElementId>),
+}
+
+impl std::fmt::Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeError::IdCollision(ids) => {
+                write!(f, "merge would overwrite {} existing id(s)", ids.len())?;
+                if let Some(first) = ids.first() {
+                    write!(f, ", first: {}", first)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
 /// A graph of model elements and relationships.
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -299,6 +401,14 @@ pub struct ModelGraph {
     source_to_rels: FxHashMap<ElementId, FxHashSet<ElementId>>,
     #[cfg_attr(feature = "serde", serde(skip))]
     target_to_rels: FxHashMap<ElementId, FxHashSet<ElementId>>,
+    /// Maps element kind to the IDs of elements of that kind.
+    /// Uses an ordered set so `elements_by_kind` keeps yielding elements in id order.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    kind_to_elements: FxHashMap<ElementKind, BTreeSet<ElementId>>,
+    /// Maps stored `qname` values to the elements carrying them.
+    /// Each list is kept in id order; more than one entry means a duplicate qname.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    qname_to_element: FxHashMap<QualifiedName, Vec<ElementId>>,
 
     // NEW: Membership-based ownership indexes
     /// Maps namespace ID to its membership element IDs.
@@ -347,6 +457,8 @@ impl ModelGraph {
             owner_to_children: FxHashMap::default(),
             source_to_rels: FxHashMap::default(),
             target_to_rels: FxHashMap::default(),
+            kind_to_elements: FxHashMap::default(),
+            qname_to_element: FxHashMap::default(),
             namespace_to_memberships: FxHashMap::default(),
             element_to_owning_membership: FxHashMap::default(),
             typed_feature_to_typings: FxHashMap::default(),
@@ -398,10 +510,74 @@ impl ModelGraph {
             }
         }
 
-        self.elements.insert(id.clone(), element);
+        // Update namespace index for Membership elements, such as aliases,
+        // that are added directly rather than through create_owning_membership
+        if element.kind == ElementKind::Membership
+            || element.kind.is_subtype_of(ElementKind::Membership)
+        {
+            if let Some(ns_id) = element
+                .props
+                .get("membershipOwningNamespace")
+                .and_then(|v| v.as_ref())
+            {
+                self.namespace_to_memberships
+                    .entry(ns_id.clone())
+                    .or_default()
+                    .insert(id.clone());
+            }
+        }
+
+        // Update kind index (dropping a stale entry if this replaces an element of another kind)
+        self.kind_to_elements
+            .entry(element.kind.clone())
+            .or_default()
+            .insert(id.clone());
+        let kind = element.kind.clone();
+        let qname = element.qname.clone();
+        if let Some(previous) = self.elements.insert(id.clone(), element) {
+            if previous.kind != kind {
+                self.unindex_kind(&previous.kind, &id);
+            }
+            if let Some(old_qname) = &previous.qname {
+                self.unindex_qname(old_qname, &id);
+            }
+        }
+
+        // Update qualified name index
+        if let Some(qname) = qname {
+            self.index_qname(qname, &id);
+        }
         id
     }
 
+    /// Remove an element id from the kind index, dropping the entry if it becomes empty.
+    fn unindex_kind(&mut self, kind: &ElementKind, id: &ElementId) {
+        if let Some(ids) = self.kind_to_elements.get_mut(kind) {
+            ids.remove(id);
+            if ids.is_empty() {
+                self.kind_to_elements.remove(kind);
+            }
+        }
+    }
+
+    /// Record that `id` carries `qname`, keeping the list in id order.
+    pub(crate) fn index_qname(&mut self, qname: QualifiedName, id: &ElementId) {
+        let ids = self.qname_to_element.entry(qname).or_default();
+        if let Err(pos) = ids.binary_search(id) {
+            ids.insert(pos, id.clone());
+        }
+    }
+
+    /// Remove `id` from the entry for `qname`, dropping the entry if it becomes empty.
+    pub(crate) fn unindex_qname(&mut self, qname: &QualifiedName, id: &ElementId) {
+        if let Some(ids) = self.qname_to_element.get_mut(qname) {
+            ids.retain(|existing| existing != id);
+            if ids.is_empty() {
+                self.qname_to_element.remove(qname);
+            }
+        }
+    }
+
     /// Add a relationship to the graph.
     pub fn add_relationship(&mut self, relationship: Relationship) -> ElementId {
         let id = relationship.id.clone();
@@ -422,6 +598,418 @@ impl ModelGraph {
         id
     }
 
+    /// Add a relationship unless an identical one already exists.
+    ///
+    /// Two relationships are identical when their `kind`, `source`, `target`
+    /// and `props` are equal; ids are ignored. Candidates are looked up through
+    /// the source index, falling back to a scan while the indexes are dirty.
+    ///
+    /// # Returns
+    ///
+    /// The id of the existing relationship, or of the newly inserted one.
+    pub fn add_relationship_dedup(&mut self, relationship: Relationship) -> ElementId {
+        let is_same = |existing: &Relationship| {
+            existing.kind == relationship.kind
+                && existing.target == relationship.target
+                && existing.source == relationship.source
+                && existing.props == relationship.props
+        };
+
+        let existing = if self.indexes_dirty {
+            self.relationships.values().find(|r| is_same(r)).map(|r| r.id.clone())
+        } else {
+            self.source_to_rels
+                .get(&relationship.source)
+                .into_iter()
+                .flat_map(|ids| ids.iter())
+                .filter_map(|id| self.relationships.get(id))
+                .filter(|r| is_same(r))
+                .map(|r| r.id.clone())
+                .min()
+        };
+
+        match existing {
+            Some(id) => id,
+            None => self.add_relationship(relationship),
+        }
+    }
+
+    /// Add a relationship after checking its endpoints.
+    ///
+    /// Both `source` and `target` must be elements of this graph. If the
+    /// relationship kind has a metamodel counterpart (see
+    /// [`RelationshipKind::metamodel_kind`]), each endpoint must also be of the
+    /// generated `relationship_source_type`/`relationship_target_type` or a
+    /// subtype of it. Parsers that already guarantee this should keep using the
+    /// cheaper [`add_relationship`](Self::add_relationship).
+    ///
+    /// # Errors
+    ///
+    /// - `DanglingEndpoint` if the source or target does not exist
+    /// - `EndpointKindMismatch` if an endpoint has the wrong kind
+    pub fn add_relationship_checked(&mut self, relationship: Relationship) -> Result<ElementId, ValidationError> {
+        self.check_relationship(&relationship)?;
+        Ok(self.add_relationship(relationship))
+    }
+
+    /// Check and add many relationships at once.
+    ///
+    /// Every relationship is checked as in
+    /// [`add_relationship_checked`](Self::add_relationship_checked) before any
+    /// is added. If any check fails, nothing is added and all failures are
+    /// returned, each with the id of the offending relationship.
+    pub fn add_relationships_checked(
+        &mut self,
+        relationships: impl IntoIterator<Item = Relationship>,
+    ) -> Result<Vec<ElementId>, Vec<(ElementId, ValidationError)>> {
+        let relationships: Vec<Relationship> = relationships.into_iter().collect();
+        let errors: Vec<(ElementId, ValidationError)> = relationships
+            .iter()
+            .filter_map(|rel| self.check_relationship(rel).err().map(|err| (rel.id.clone(), err)))
+            .collect();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(relationships
+            .into_iter()
+            .map(|rel| self.add_relationship(rel))
+            .collect())
+    }
+
+    /// Validate a relationship's endpoints against the graph and the metamodel.
+    fn check_relationship(&self, relationship: &Relationship) -> Result<(), ValidationError> {
+        let source = self.elements.get(&relationship.source).ok_or_else(|| {
+            ValidationError::dangling_endpoint("source", relationship.source.clone())
+        })?;
+        let target = self.elements.get(&relationship.target).ok_or_else(|| {
+            ValidationError::dangling_endpoint("target", relationship.target.clone())
+        })?;
+
+        let metamodel_kind = match relationship.kind.metamodel_kind() {
+            Some(kind) => kind,
+            None => return Ok(()),
+        };
+        let endpoints = [
+            ("source", source, metamodel_kind.relationship_source_type()),
+            ("target", target, metamodel_kind.relationship_target_type()),
+        ];
+        for (property, element, expected) in endpoints {
+            if let Some(expected) = expected {
+                if element.kind != expected && !element.kind.is_subtype_of(expected.clone()) {
+                    return Err(ValidationError::endpoint_kind_mismatch(
+                        property,
+                        relationship.kind.clone(),
+                        expected,
+                        element.kind.clone(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add many elements without updating the indexes.
+    ///
+    /// This is faster than repeated [`add_element`](Self::add_element) calls for
+    /// large loads. The graph is marked dirty, and until
+    /// [`rebuild_indexes`](Self::rebuild_indexes) is called index-backed queries
+    /// such as `children_of` fall back to scanning every element, so call it
+    /// once loading is done.
+    pub fn add_elements_bulk(&mut self, elements: impl IntoIterator<Item = Element>) {
+        for element in elements {
+            self.elements.insert(element.id.clone(), element);
+        }
+        self.indexes_dirty = true;
+        self.library_index_dirty = true;
+    }
+
+    /// Add many relationships without updating the indexes.
+    ///
+    /// See [`add_elements_bulk`](Self::add_elements_bulk); call
+    /// [`rebuild_indexes`](Self::rebuild_indexes) once loading is done.
+    pub fn add_relationships_bulk(&mut self, relationships: impl IntoIterator<Item = Relationship>) {
+        for relationship in relationships {
+            self.relationships.insert(relationship.id.clone(), relationship);
+        }
+        self.indexes_dirty = true;
+    }
+
+    /// Check whether the indexes are out of date after a bulk insertion.
+    ///
+    /// Returns true between `add_elements_bulk`/`add_relationships_bulk` and the
+    /// next `rebuild_indexes` call.
+    pub fn is_indexes_dirty(&self) -> bool {
+        self.indexes_dirty
+    }
+
+    /// Remove a relationship from the graph.
+    ///
+    /// The relationship is removed from the source and target indexes as well.
+    /// Index entries that become empty are dropped so repeated add/remove
+    /// cycles don't accumulate empty sets.
+    ///
+    /// # Returns
+    ///
+    /// The removed relationship, or None if no relationship has the given id.
+    pub fn remove_relationship(&mut self, id: &ElementId) -> Option<Relationship> {
+        let relationship = self.relationships.remove(id)?;
+
+        Self::remove_from_index(&mut self.source_to_rels, &relationship.source, id);
+        Self::remove_from_index(&mut self.target_to_rels, &relationship.target, id);
+
+        Some(relationship)
+    }
+
+    /// Remove an element from the graph.
+    ///
+    /// Every index entry derived from the element is dropped: its kind, its place
+    /// under its owner, its owning membership, and (for FeatureTyping,
+    /// Specialization, and Membership elements) the reverse index entries it
+    /// contributes. A removed library package is unregistered, and the element is
+    /// dropped from the library name index.
+    ///
+    /// Relationships whose source or target is the removed element are detached
+    /// (removed as well), so `outgoing`/`incoming` never return dangling
+    /// relationships. Owned children and the element's owning membership are left
+    /// in place; use [`remove_subtree`](Self::remove_subtree) to remove them too.
+    ///
+    /// # Returns
+    ///
+    /// The removed element, or None if no element has the given id.
+    pub fn remove_element(&mut self, id: &ElementId) -> Option<Element> {
+        let element = self.elements.remove(id)?;
+
+        if self.indexes_dirty {
+            // The indexes are rebuilt wholesale later; just drop attached relationships
+            self.relationships
+                .retain(|_, rel| &rel.source != id && &rel.target != id);
+        } else {
+            self.unindex_removed_element(id, &element);
+
+            let attached: FxHashSet<ElementId> = self
+                .source_to_rels
+                .get(id)
+                .into_iter()
+                .chain(self.target_to_rels.get(id))
+                .flatten()
+                .cloned()
+                .collect();
+            for rel_id in attached {
+                self.remove_relationship(&rel_id);
+            }
+        }
+
+        if self.library_packages.remove(id) {
+            self.library_index_dirty = true;
+        }
+        self.library_name_index.retain(|_, indexed| indexed != id);
+
+        Some(element)
+    }
+
+    /// Remove an element together with everything it owns.
+    ///
+    /// This removes, recursively, the element's owned children (`children_of`),
+    /// the memberships of each removed namespace, and the owning membership of
+    /// each removed element, each via [`remove_element`](Self::remove_element).
+    ///
+    /// # Returns
+    ///
+    /// The removed elements, starting with the subtree root. Empty if no
+    /// element has the given id.
+    pub fn remove_subtree(&mut self, id: &ElementId) -> Vec<Element> {
+        if !self.elements.contains_key(id) {
+            return Vec::new();
+        }
+        if self.indexes_dirty {
+            self.rebuild_indexes();
+        }
+
+        // Collect the subtree first so removal doesn't disturb the traversal
+        let mut order = vec![id.clone()];
+        let mut seen: FxHashSet<ElementId> = order.iter().cloned().collect();
+        let mut next = 0;
+        while let Some(current) = order.get(next).cloned() {
+            next += 1;
+            let mut owned: Vec<ElementId> = self.children_of(&current).map(|c| c.id.clone()).collect();
+            owned.extend(
+                self.namespace_to_memberships
+                    .get(&current)
+                    .into_iter()
+                    .flatten()
+                    .cloned(),
+            );
+            if let Some(membership) = self.element_to_owning_membership.get(&current) {
+                owned.push(membership.clone());
+            }
+            owned.sort();
+            for owned_id in owned {
+                if self.elements.contains_key(&owned_id) && seen.insert(owned_id.clone()) {
+                    order.push(owned_id);
+                }
+            }
+        }
+
+        order
+            .iter()
+            .filter_map(|removed_id| self.remove_element(removed_id))
+            .collect()
+    }
+
+    /// Extract an element and everything it transitively owns into a new graph.
+    ///
+    /// The subset is the same one [`remove_subtree`](Self::remove_subtree)
+    /// would remove, minus the root's own membership in its parent. Relationships
+    /// with both endpoints in the subset are copied. With `include_external_rels`,
+    /// relationships with exactly one endpoint in the subset are copied too,
+    /// together with a stub of the outside endpoint: same id, kind and name, and
+    /// an `external: true` prop.
+    ///
+    /// The root's owner and owning membership are cleared so the result stands
+    /// alone, and its indexes are built. Library package registrations are not
+    /// carried over. Returns an empty graph if `root` does not exist.
+    pub fn subgraph(&self, root: &ElementId, include_external_rels: bool) -> ModelGraph {
+        let mut sub = ModelGraph::new();
+        if !self.elements.contains_key(root) {
+            return sub;
+        }
+
+        // Scan once rather than trusting the indexes, which may be dirty
+        let mut owned_by: FxHashMap<&ElementId, Vec<&ElementId>> = FxHashMap::default();
+        for (id, element) in &self.elements {
+            if let Some(owner) = &element.owner {
+                owned_by.entry(owner).or_default().push(id);
+            }
+            if element.kind == ElementKind::Membership
+                || element.kind.is_subtype_of(ElementKind::Membership)
+            {
+                if let Some(ns_id) = element
+                    .props
+                    .get("membershipOwningNamespace")
+                    .and_then(|v| v.as_ref())
+                {
+                    owned_by.entry(ns_id).or_default().push(id);
+                }
+            }
+        }
+
+        let mut order = vec![root];
+        let mut seen: FxHashSet<&ElementId> = order.iter().copied().collect();
+        let mut next = 0;
+        while let Some(&current) = order.get(next) {
+            next += 1;
+            let mut owned: Vec<&ElementId> = owned_by.get(current).cloned().unwrap_or_default();
+            if current != root {
+                owned.extend(self.elements[current].owning_membership.as_ref());
+            }
+            owned.sort();
+            for owned_id in owned {
+                if self.elements.contains_key(owned_id) && seen.insert(owned_id) {
+                    order.push(owned_id);
+                }
+            }
+        }
+
+        let mut elements: Vec<Element> = order.iter().map(|id| self.elements[*id].clone()).collect();
+        elements[0].owner = None;
+        elements[0].owning_membership = None;
+
+        let mut relationships = Vec::new();
+        for rel in self.relationships.values() {
+            let source_inside = seen.contains(&rel.source);
+            let target_inside = seen.contains(&rel.target);
+            if source_inside && target_inside {
+                relationships.push(rel.clone());
+            } else if include_external_rels && (source_inside || target_inside) {
+                let outside = if source_inside { &rel.target } else { &rel.source };
+                if let Some(external) = self.elements.get(outside) {
+                    let mut stub = Element::new(external.id.clone(), external.kind.clone());
+                    stub.name = external.name.clone();
+                    stub.set_prop("external", true);
+                    elements.push(stub);
+                }
+                relationships.push(rel.clone());
+            }
+        }
+
+        sub.add_elements_bulk(elements);
+        sub.add_relationships_bulk(relationships);
+        sub.rebuild_indexes();
+        sub
+    }
+
+    /// Drop every index entry that `rebuild_indexes` would derive from a removed element.
+    fn unindex_removed_element(&mut self, id: &ElementId, element: &Element) {
+        self.unindex_kind(&element.kind, id);
+
+        if let Some(qname) = &element.qname {
+            self.unindex_qname(qname, id);
+        }
+
+        if let Some(owner) = &element.owner {
+            Self::remove_from_index(&mut self.owner_to_children, owner, id);
+        }
+
+        self.element_to_owning_membership.remove(id);
+
+        if element.kind == ElementKind::FeatureTyping
+            || element.kind.is_subtype_of(ElementKind::FeatureTyping)
+        {
+            if let Some(tf_id) = element.props.get("typedFeature").and_then(|v| v.as_ref()) {
+                Self::remove_from_list_index(&mut self.typed_feature_to_typings, tf_id, id);
+            }
+        }
+
+        if element.kind == ElementKind::Specialization
+            || element.kind.is_subtype_of(ElementKind::Specialization)
+        {
+            if let Some(specific_id) = element.props.get("specific").and_then(|v| v.as_ref()) {
+                Self::remove_from_list_index(&mut self.specific_to_specializations, specific_id, id);
+            }
+        }
+
+        if element.kind == ElementKind::Membership
+            || element.kind.is_subtype_of(ElementKind::Membership)
+        {
+            if let Some(ns_id) = element
+                .props
+                .get("membershipOwningNamespace")
+                .and_then(|v| v.as_ref())
+            {
+                Self::remove_from_index(&mut self.namespace_to_memberships, ns_id, id);
+            }
+        }
+    }
+
+    /// Like [`remove_from_index`](Self::remove_from_index), for the list-valued indexes.
+    fn remove_from_list_index(
+        index: &mut FxHashMap<ElementId, Vec<ElementId>>,
+        key: &ElementId,
+        id: &ElementId,
+    ) {
+        if let Some(ids) = index.get_mut(key) {
+            ids.retain(|existing| existing != id);
+            if ids.is_empty() {
+                index.remove(key);
+            }
+        }
+    }
+
+    /// Remove `id` from the set stored under `key`, dropping the set if it becomes empty.
+    fn remove_from_index(
+        index: &mut FxHashMap<ElementId, FxHashSet<ElementId>>,
+        key: &ElementId,
+        id: &ElementId,
+    ) {
+        if let Some(ids) = index.get_mut(key) {
+            ids.remove(id);
+            if ids.is_empty() {
+                index.remove(key);
+            }
+        }
+    }
+
     /// Get an element by id.
     pub fn get_element(&self, id: &ElementId) -> Option<&Element> {
         self.elements.get(id)
@@ -437,36 +1025,201 @@ impl ModelGraph {
         self.relationships.get(id)
     }
 
+    /// Check whether index-backed queries must scan instead.
+    ///
+    /// True while the graph is dirty after a bulk insertion, and when the
+    /// indexes were never built (e.g. right after deserialization).
+    fn indexes_need_scan(&self) -> bool {
+        self.indexes_dirty
+            || (self.kind_to_elements.is_empty() && !self.elements.is_empty())
+            || (self.source_to_rels.is_empty() && !self.relationships.is_empty())
+    }
+
     /// Get the children of an owner element.
+    ///
+    /// Uses the owner index. While the indexes are stale (see
+    /// [`is_indexes_dirty`](Self::is_indexes_dirty)) this falls back to scanning
+    /// all elements, so results are always complete.
     pub fn children_of(&self, owner: &ElementId) -> impl Iterator<Item = &Element> {
-        self.owner_to_children
-            .get(owner)
+        let needs_scan = self.indexes_need_scan();
+
+        let indexed = (!needs_scan)
+            .then(|| self.owner_to_children.get(owner))
+            .flatten()
             .into_iter()
-            .flat_map(|children| children.iter())
-            .filter_map(move |id| self.elements.get(id))
+            .flatten()
+            .filter_map(move |id| self.elements.get(id));
+
+        // The owned copy keeps the returned iterator independent of `owner`'s borrow
+        let scanned = needs_scan
+            .then(|| {
+                let owner = owner.clone();
+                self.elements.values().filter(move |e| e.owner.as_ref() == Some(&owner))
+            })
+            .into_iter()
+            .flatten();
+
+        indexed.chain(scanned)
     }
 
     /// Get outgoing relationships from a source element.
+    ///
+    /// Uses the source index, falling back to a scan while the indexes are
+    /// stale (see [`children_of`](Self::children_of)).
     pub fn outgoing(&self, source: &ElementId) -> impl Iterator<Item = &Relationship> {
-        self.source_to_rels
-            .get(source)
+        let needs_scan = self.indexes_need_scan();
+
+        let indexed = (!needs_scan)
+            .then(|| self.source_to_rels.get(source))
+            .flatten()
+            .into_iter()
+            .flatten()
+            .filter_map(move |id| self.relationships.get(id));
+
+        let scanned = needs_scan
+            .then(|| {
+                let source = source.clone();
+                self.relationships.values().filter(move |r| r.source == source)
+            })
             .into_iter()
-            .flat_map(|rels| rels.iter())
-            .filter_map(move |id| self.relationships.get(id))
+            .flatten();
+
+        indexed.chain(scanned)
     }
 
     /// Get incoming relationships to a target element.
+    ///
+    /// Uses the target index, falling back to a scan while the indexes are
+    /// stale (see [`children_of`](Self::children_of)).
     pub fn incoming(&self, target: &ElementId) -> impl Iterator<Item = &Relationship> {
-        self.target_to_rels
-            .get(target)
+        let needs_scan = self.indexes_need_scan();
+
+        let indexed = (!needs_scan)
+            .then(|| self.target_to_rels.get(target))
+            .flatten()
+            .into_iter()
+            .flatten()
+            .filter_map(move |id| self.relationships.get(id));
+
+        let scanned = needs_scan
+            .then(|| {
+                let target = target.clone();
+                self.relationships.values().filter(move |r| r.target == target)
+            })
+            .into_iter()
+            .flatten();
+
+        indexed.chain(scanned)
+    }
+
+    /// Get the Specialization elements (including subkinds such as
+    /// Subclassification) whose `specific` is the given type.
+    ///
+    /// Uses the specialization index, falling back to a scan while the indexes
+    /// are stale (see [`children_of`](Self::children_of)).
+    pub fn specializations_of_specific(&self, specific: &ElementId) -> impl Iterator<Item = &Element> {
+        let needs_scan = self.indexes_need_scan();
+
+        let indexed = (!needs_scan)
+            .then(|| self.specific_to_specializations.get(specific))
+            .flatten()
+            .into_iter()
+            .flatten()
+            .filter_map(move |id| self.elements.get(id));
+
+        let scanned = needs_scan
+            .then(|| {
+                let specific = specific.clone();
+                self.elements.values().filter(move |e| {
+                    (e.kind == ElementKind::Specialization || e.kind.is_subtype_of(ElementKind::Specialization))
+                        && e.props.get("specific").and_then(|v| v.as_ref()) == Some(&specific)
+                })
+            })
             .into_iter()
-            .flat_map(|rels| rels.iter())
-            .filter_map(move |id| self.relationships.get(id))
+            .flatten();
+
+        indexed.chain(scanned)
+    }
+
+    /// Get every Specialization element (including subkinds such as
+    /// Subclassification) that has a `specific`.
+    ///
+    /// Uses the specialization index, falling back to a scan while the indexes
+    /// are stale (see [`children_of`](Self::children_of)).
+    pub fn specializations(&self) -> impl Iterator<Item = &Element> {
+        let needs_scan = self.indexes_need_scan();
+
+        let indexed = (!needs_scan)
+            .then(|| self.specific_to_specializations.values().flatten())
+            .into_iter()
+            .flatten()
+            .filter_map(move |id| self.elements.get(id));
+
+        let scanned = needs_scan
+            .then(|| {
+                self.elements.values().filter(|e| {
+                    (e.kind == ElementKind::Specialization || e.kind.is_subtype_of(ElementKind::Specialization))
+                        && e.props.get("specific").and_then(|v| v.as_ref()).is_some()
+                })
+            })
+            .into_iter()
+            .flatten();
+
+        indexed.chain(scanned)
+    }
+
+    /// Get the FeatureTyping elements whose `typedFeature` is the given feature.
+    ///
+    /// Uses the typing index, falling back to a scan while the indexes are
+    /// stale (see [`children_of`](Self::children_of)).
+    pub fn typings_of_feature(&self, feature: &ElementId) -> impl Iterator<Item = &Element> {
+        let needs_scan = self.indexes_need_scan();
+
+        let indexed = (!needs_scan)
+            .then(|| self.typed_feature_to_typings.get(feature))
+            .flatten()
+            .into_iter()
+            .flatten()
+            .filter_map(move |id| self.elements.get(id));
+
+        let scanned = needs_scan
+            .then(|| {
+                let feature = feature.clone();
+                self.elements.values().filter(move |e| {
+                    (e.kind == ElementKind::FeatureTyping || e.kind.is_subtype_of(ElementKind::FeatureTyping))
+                        && e.props.get("typedFeature").and_then(|v| v.as_ref()) == Some(&feature)
+                })
+            })
+            .into_iter()
+            .flatten();
+
+        indexed.chain(scanned)
     }
 
-    /// Get all elements of a specific kind.
-    pub fn elements_by_kind<'a>(&'a self, kind: &'a ElementKind) -> impl Iterator<Item = &'a Element> {
-        self.elements.values().filter(move |e| &e.kind == kind)
+    /// Get all elements of a specific kind, in id order.
+    ///
+    /// Uses the kind index, so the cost is proportional to the number of matching
+    /// elements. If the index is stale or has not been built (e.g. during a bulk
+    /// load or right after deserialization), this falls back to scanning all elements.
+    pub fn elements_by_kind(&self, kind: &ElementKind) -> impl Iterator<Item = &Element> {
+        let needs_scan = self.indexes_need_scan();
+
+        let indexed = (!needs_scan)
+            .then(|| self.kind_to_elements.get(kind))
+            .flatten()
+            .into_iter()
+            .flatten()
+            .filter_map(move |id| self.elements.get(id));
+
+        let scanned = needs_scan
+            .then(|| {
+                let kind = kind.clone();
+                self.elements.values().filter(move |e| e.kind == kind)
+            })
+            .into_iter()
+            .flatten();
+
+        indexed.chain(scanned)
     }
 
     /// Get all relationships of a specific kind.
@@ -479,18 +1232,92 @@ impl ModelGraph {
         self.elements.values().filter(|e| e.owner.is_none())
     }
 
-    /// Get the number of elements.
-    pub fn element_count(&self) -> usize {
-        self.elements.len()
+    /// Look up an element by its stored qualified name.
+    ///
+    /// Uses the qname index, so this is O(1) rather than a scan. Only the
+    /// `qname` field is consulted; see
+    /// [`compute_qualified_names`](Self::compute_qualified_names) to fill it in
+    /// from the ownership tree. If several elements share the qualified name,
+    /// the one with the smallest id is returned (see
+    /// [`get_all_by_qname`](Self::get_all_by_qname)).
+    ///
+    /// Results are incomplete while [`is_indexes_dirty`](Self::is_indexes_dirty)
+    /// is true, or after editing `qname` directly without calling
+    /// [`rebuild_indexes`](Self::rebuild_indexes).
+    pub fn get_by_qname(&self, qname: &QualifiedName) -> Option<&Element> {
+        self.qname_to_element
+            .get(qname)?
+            .iter()
+            .filter_map(|id| self.elements.get(id))
+            .find(|element| element.qname.as_ref() == Some(qname))
     }
 
-    /// Get the number of relationships.
-    pub fn relationship_count(&self) -> usize {
-        self.relationships.len()
+    /// Like [`get_by_qname`](Self::get_by_qname), parsing the name from `Pkg::Part` form.
+    ///
+    /// Returns None if the string is not a valid qualified name.
+    pub fn get_by_qname_str(&self, qname: &str) -> Option<&Element> {
+        let qname: QualifiedName = qname.parse().ok()?;
+        self.get_by_qname(&qname)
     }
 
-    /// Check if the graph is empty.
-    pub fn is_empty(&self) -> bool {
+    /// Get every element whose stored qualified name is `qname`, in id order.
+    pub fn get_all_by_qname<'a>(&'a self, qname: &'a QualifiedName) -> impl Iterator<Item = &'a Element> {
+        self.qname_to_element
+            .get(qname)
+            .into_iter()
+            .flatten()
+            .filter_map(move |id| self.elements.get(id))
+            .filter(move |element| element.qname.as_ref() == Some(qname))
+    }
+
+    /// Fill in missing `qname` fields from the ownership tree.
+    ///
+    /// Walks down from the roots, deriving each qualified name from the owner's
+    /// path and the element's `name`, as [`qualified_name_of`](Self::qualified_name_of)
+    /// does. Elements that already have a `qname` keep it, and unnamed elements
+    /// (and everything below them) get none. The qname index is updated.
+    pub fn compute_qualified_names(&mut self) {
+        if self.indexes_dirty {
+            self.rebuild_indexes();
+        }
+
+        let mut stack: Vec<(ElementId, QualifiedName)> = self
+            .roots()
+            .filter_map(|root| {
+                let name = root.name.clone()?;
+                Some((root.id.clone(), QualifiedName::from_segments(vec![name])))
+            })
+            .collect();
+
+        while let Some((id, qname)) = stack.pop() {
+            for child in self.children_of(&id) {
+                if let Some(name) = &child.name {
+                    stack.push((child.id.clone(), qname.child(name.clone())));
+                }
+            }
+
+            let missing = self.elements.get(&id).is_some_and(|e| e.qname.is_none());
+            if missing {
+                if let Some(element) = self.elements.get_mut(&id) {
+                    element.qname = Some(qname.clone());
+                }
+                self.index_qname(qname, &id);
+            }
+        }
+    }
+
+    /// Get the number of elements.
+    pub fn element_count(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Get the number of relationships.
+    pub fn relationship_count(&self) -> usize {
+        self.relationships.len()
+    }
+
+    /// Check if the graph is empty.
+    pub fn is_empty(&self) -> bool {
         self.elements.is_empty() && self.relationships.is_empty()
     }
 
@@ -499,12 +1326,27 @@ impl ModelGraph {
         self.owner_to_children.clear();
         self.source_to_rels.clear();
         self.target_to_rels.clear();
+        self.kind_to_elements.clear();
+        self.qname_to_element.clear();
         self.namespace_to_memberships.clear();
         self.element_to_owning_membership.clear();
         self.typed_feature_to_typings.clear();
         self.specific_to_specializations.clear();
 
         for (id, element) in &self.elements {
+            self.kind_to_elements
+                .entry(element.kind.clone())
+                .or_default()
+                .insert(id.clone());
+
+            // Elements are visited in id order, so each list stays sorted
+            if let Some(qname) = &element.qname {
+                self.qname_to_element
+                    .entry(qname.clone())
+                    .or_default()
+                    .push(id.clone());
+            }
+
             if let Some(owner) = &element.owner {
                 self.owner_to_children
                     .entry(owner.clone())
@@ -586,6 +1428,8 @@ impl ModelGraph {
         self.owner_to_children.clear();
         self.source_to_rels.clear();
         self.target_to_rels.clear();
+        self.kind_to_elements.clear();
+        self.qname_to_element.clear();
         self.namespace_to_memberships.clear();
         self.element_to_owning_membership.clear();
         self.typed_feature_to_typings.clear();
@@ -815,22 +1659,124 @@ impl ModelGraph {
         id
     }
 
+    /// Add a merged element to every index, as `rebuild_indexes` would.
+    fn index_merged_element(&mut self, id: &ElementId, element: &Element) {
+        self.kind_to_elements
+            .entry(element.kind.clone())
+            .or_default()
+            .insert(id.clone());
+
+        if let Some(qname) = &element.qname {
+            self.index_qname(qname.clone(), id);
+        }
+
+        if let Some(owner) = &element.owner {
+            self.owner_to_children
+                .entry(owner.clone())
+                .or_default()
+                .insert(id.clone());
+        }
+
+        if let Some(membership_id) = &element.owning_membership {
+            self.element_to_owning_membership
+                .insert(id.clone(), membership_id.clone());
+        }
+
+        if element.kind == ElementKind::FeatureTyping
+            || element.kind.is_subtype_of(ElementKind::FeatureTyping)
+        {
+            if let Some(tf_id) = element.props.get("typedFeature").and_then(|v| v.as_ref()) {
+                let typings = self.typed_feature_to_typings.entry(tf_id.clone()).or_default();
+                if !typings.contains(id) {
+                    typings.push(id.clone());
+                }
+            }
+        }
+
+        if element.kind == ElementKind::Specialization
+            || element.kind.is_subtype_of(ElementKind::Specialization)
+        {
+            if let Some(specific_id) = element.props.get("specific").and_then(|v| v.as_ref()) {
+                let specializations = self
+                    .specific_to_specializations
+                    .entry(specific_id.clone())
+                    .or_default();
+                if !specializations.contains(id) {
+                    specializations.push(id.clone());
+                }
+            }
+        }
+
+        if element.kind == ElementKind::Membership
+            || element.kind.is_subtype_of(ElementKind::Membership)
+        {
+            if let Some(ns_id) = element
+                .props
+                .get("membershipOwningNamespace")
+                .and_then(|v| v.as_ref())
+            {
+                self.namespace_to_memberships
+                    .entry(ns_id.clone())
+                    .or_default()
+                    .insert(id.clone());
+            }
+        }
+    }
+
     /// Merge another graph's elements into this graph.
     ///
     /// This is useful for loading standard library graphs into a user graph.
     /// If `as_library` is true, all root packages from the source graph
     /// are registered as library packages.
     ///
+    /// The ownership, relationship, kind, and membership indexes are updated
+    /// for the merged content, so `children_of`, `outgoing`, etc. work right
+    /// away without calling `rebuild_indexes()`. This holds even if the other
+    /// graph's own indexes were never built (e.g. after a bulk load).
+    ///
     /// # Arguments
     ///
     /// * `other` - The graph to merge from
     /// * `as_library` - Whether to mark merged root packages as library packages
+    /// * `policy` - What to do with elements and relationships whose id is
+    ///   already in this graph
     ///
     /// # Returns
     ///
-    /// The number of elements merged.
-    pub fn merge(&mut self, other: ModelGraph, as_library: bool) -> usize {
-        let count = other.elements.len();
+    /// A [`MergeReport`] counting merged, replaced and skipped elements.
+    /// Relationships follow the same policy but are not counted.
+    ///
+    /// # Errors
+    ///
+    /// With [`MergePolicy::Error`], returns [`MergeError::IdCollision`] listing
+    /// every colliding element and relationship id, and merges nothing.
+    pub fn merge(
+        &mut self,
+        other: ModelGraph,
+        as_library: bool,
+        policy: MergePolicy,
+    ) -> Result<MergeReport, MergeError> {
+        if policy == MergePolicy::Error {
+            let mut collisions: Vec<ElementId> = other
+                .elements
+                .keys()
+                .filter(|id| self.elements.contains_key(id))
+                .chain(other.relationships.keys().filter(|id| self.relationships.contains_key(id)))
+                .cloned()
+                .collect();
+            if !collisions.is_empty() {
+                collisions.sort();
+                collisions.dedup();
+                return Err(MergeError::IdCollision(collisions));
+            }
+        }
+
+        Ok(self.merge_unchecked(other, as_library, policy == MergePolicy::KeepExisting))
+    }
+
+    /// Merge without collision checks, either keeping or replacing existing ids.
+    fn merge_unchecked(&mut self, other: ModelGraph, as_library: bool, keep_existing: bool) -> MergeReport {
+        let mut report = MergeReport::default();
 
         // Collect root package IDs before merging
         let root_package_ids: Vec<ElementId> = if as_library {
@@ -849,14 +1795,41 @@ impl ModelGraph {
             Vec::new()
         };
 
-        // Merge elements
+        // Merge elements, indexing each one from its own data so the result does
+        // not depend on whether the other graph's indexes were up to date
         for (id, element) in other.elements {
-            self.elements.insert(id.clone(), element);
-            // Note: We don't update owner_to_children here as they're for the original graph
+            if keep_existing && self.elements.contains_key(&id) {
+                report.skipped += 1;
+                continue;
+            }
+            // A replaced element leaves every index before its new version
+            // is indexed, so no reverse index keeps pointing at old data
+            if let Some(previous) = self.elements.remove(&id) {
+                self.unindex_removed_element(&id, &previous);
+                report.replaced.push(id.clone());
+            }
+            self.index_merged_element(&id, &element);
+            self.elements.insert(id, element);
+            report.merged += 1;
         }
 
         // Merge relationships
         for (id, rel) in other.relationships {
+            if keep_existing && self.relationships.contains_key(&id) {
+                continue;
+            }
+            if let Some(previous) = self.relationships.remove(&id) {
+                Self::remove_from_index(&mut self.source_to_rels, &previous.source, &id);
+                Self::remove_from_index(&mut self.target_to_rels, &previous.target, &id);
+            }
+            self.source_to_rels
+                .entry(rel.source.clone())
+                .or_default()
+                .insert(id.clone());
+            self.target_to_rels
+                .entry(rel.target.clone())
+                .or_default()
+                .insert(id.clone());
             self.relationships.insert(id, rel);
         }
 
@@ -865,63 +1838,6 @@ impl ModelGraph {
             self.library_packages.insert(id);
         }
 
-        // Merge indexes from the other graph to preserve pre-built index data.
-        // This is critical for library merging: the library's namespace_to_memberships
-        // index enables build_library_index() to work correctly.
-
-        // Merge namespace_to_memberships index
-        for (ns_id, membership_ids) in other.namespace_to_memberships {
-            self.namespace_to_memberships
-                .entry(ns_id)
-                .or_default()
-                .extend(membership_ids);
-        }
-
-        // Merge owner_to_children index
-        for (owner_id, child_ids) in other.owner_to_children {
-            self.owner_to_children
-                .entry(owner_id)
-                .or_default()
-                .extend(child_ids);
-        }
-
-        // Merge element_to_owning_membership index
-        for (elem_id, membership_id) in other.element_to_owning_membership {
-            self.element_to_owning_membership
-                .entry(elem_id)
-                .or_insert(membership_id);
-        }
-
-        // Merge typed_feature_to_typings index
-        for (feature_id, typing_ids) in other.typed_feature_to_typings {
-            self.typed_feature_to_typings
-                .entry(feature_id)
-                .or_default()
-                .extend(typing_ids);
-        }
-
-        // Merge specific_to_specializations index
-        for (specific_id, spec_ids) in other.specific_to_specializations {
-            self.specific_to_specializations
-                .entry(specific_id)
-                .or_default()
-                .extend(spec_ids);
-        }
-
-        // Merge source_to_rels and target_to_rels indexes
-        for (source_id, rel_ids) in other.source_to_rels {
-            self.source_to_rels
-                .entry(source_id)
-                .or_default()
-                .extend(rel_ids);
-        }
-        for (target_id, rel_ids) in other.target_to_rels {
-            self.target_to_rels
-                .entry(target_id)
-                .or_default()
-                .extend(rel_ids);
-        }
-
         // Merge library_name_index if the other graph had one built
         if !other.library_name_index.is_empty() {
             for (name, elem_id) in other.library_name_index {
@@ -929,8 +1845,7 @@ impl ModelGraph {
             }
         }
 
-        // Note: We don't mark indexes_dirty since we've properly merged them.
-        // The indexes are now consistent with the merged elements/relationships.
+        // Note: We don't mark indexes_dirty since the merged content was indexed above.
 
         // Mark library index as needing rebuild if we added library packages
         // (to index newly registered library packages)
@@ -938,7 +1853,89 @@ impl ModelGraph {
             self.library_index_dirty = true;
         }
 
-        count
+        report
+    }
+
+    /// Merge another graph, giving fresh ids to elements and relationships that collide.
+    ///
+    /// Any id in `other` that is already used in this graph (by an element or a
+    /// relationship) is replaced by a new random id. All references inside `other`
+    /// are rewritten to match: `owner`, `owning_membership`, relationship
+    /// `source`/`target`, and `Value::Ref` props (including inside lists and maps).
+    /// The remapped graph no longer collides and is merged as with `merge`.
+    ///
+    /// # Returns
+    ///
+    /// The old → new mapping for every remapped id, so callers can fix up
+    /// references they hold into `other`. Ids that did not collide are absent.
+    pub fn merge_with_remap(&mut self, other: ModelGraph) -> HashMap<ElementId, ElementId> {
+        let mapping: HashMap<ElementId, ElementId> = other
+            .elements
+            .keys()
+            .chain(other.relationships.keys())
+            .filter(|id| self.elements.contains_key(id) || self.relationships.contains_key(id))
+            .map(|id| (id.clone(), ElementId::new_v4()))
+            .collect();
+
+        if mapping.is_empty() {
+            self.merge_unchecked(other, false, false);
+            return mapping;
+        }
+
+        let remap = |id: &mut ElementId| {
+            if let Some(new_id) = mapping.get(id) {
+                *id = new_id.clone();
+            }
+        };
+
+        let mut remapped = ModelGraph::new();
+        for (_, mut element) in other.elements {
+            remap(&mut element.id);
+            if let Some(owner) = element.owner.as_mut() {
+                remap(owner);
+            }
+            if let Some(membership) = element.owning_membership.as_mut() {
+                remap(membership);
+            }
+            for value in element.props.values_mut() {
+                Self::remap_value_refs(value, &mapping);
+            }
+            remapped.elements.insert(element.id.clone(), element);
+        }
+        for (_, mut rel) in other.relationships {
+            remap(&mut rel.id);
+            remap(&mut rel.source);
+            remap(&mut rel.target);
+            for value in rel.props.values_mut() {
+                Self::remap_value_refs(value, &mapping);
+            }
+            remapped.relationships.insert(rel.id.clone(), rel);
+        }
+
+        self.merge_unchecked(remapped, false, false);
+        mapping
+    }
+
+    /// Rewrite `Value::Ref`s (recursively) according to an id mapping.
+    fn remap_value_refs(value: &mut Value, mapping: &HashMap<ElementId, ElementId>) {
+        match value {
+            Value::Ref(id) => {
+                if let Some(new_id) = mapping.get(id) {
+                    *id = new_id.clone();
+                }
+            }
+            Value::List(items) => {
+                for item in items {
+                    Self::remap_value_refs(item, mapping);
+                }
+            }
+            Value::Map(entries) => {
+                for item in entries.values_mut() {
+                    Self::remap_value_refs(item, mapping);
+                }
+            }
+            _ => {}
+        }
     }
 }
 
@@ -1000,6 +1997,623 @@ mod tests {
         assert_eq!(retrieved.target, id2);
     }
 
+    #[test]
+    fn typed_prop_getters() {
+        let target = ElementId::new_v4();
+        let element = Element::new_with_kind(ElementKind::PartUsage)
+            .with_prop("flag", true)
+            .with_prop("count", Value::Int(3))
+            .with_prop("ratio", Value::Float(0.5))
+            .with_prop("label", "engine")
+            .with_prop("direction", Value::Enum("in".to_string()))
+            .with_prop("target", Value::Ref(target.clone()));
+
+        assert_eq!(element.get_bool("flag"), Some(true));
+        assert_eq!(element.get_int("count"), Some(3));
+        assert_eq!(element.get_float("ratio"), Some(0.5));
+        assert_eq!(element.get_float("count"), Some(3.0));
+        assert_eq!(element.get_str("label"), Some("engine"));
+        assert_eq!(element.get_str("direction"), Some("in"));
+        assert_eq!(element.get_ref("target"), Some(&target));
+
+        // Missing keys and mismatched types
+        assert_eq!(element.get_bool("missing"), None);
+        assert_eq!(element.get_bool("count"), None);
+        assert_eq!(element.get_int("ratio"), None);
+        assert_eq!(element.get_str("flag"), None);
+        assert_eq!(element.get_ref("label"), None);
+    }
+
+    #[test]
+    fn remove_relationship() {
+        let mut graph = ModelGraph::new();
+        let id1 = graph.add_element(Element::new_with_kind(ElementKind::PartUsage));
+        let id2 = graph.add_element(Element::new_with_kind(ElementKind::RequirementUsage));
+
+        let rel = Relationship::new(RelationshipKind::Satisfy, id1.clone(), id2.clone());
+        let rel_id = graph.add_relationship(rel);
+
+        let removed = graph.remove_relationship(&rel_id).unwrap();
+        assert_eq!(removed.id, rel_id);
+        assert!(graph.get_relationship(&rel_id).is_none());
+        assert_eq!(graph.outgoing(&id1).count(), 0);
+        assert_eq!(graph.incoming(&id2).count(), 0);
+
+        // Removing again is a no-op
+        assert!(graph.remove_relationship(&rel_id).is_none());
+    }
+
+    #[test]
+    fn remove_relationship_drops_empty_index_entries() {
+        let mut graph = ModelGraph::new();
+        let id1 = graph.add_element(Element::new_with_kind(ElementKind::PartUsage));
+        let id2 = graph.add_element(Element::new_with_kind(ElementKind::RequirementUsage));
+
+        for _ in 0..10 {
+            let rel = Relationship::new(RelationshipKind::Satisfy, id1.clone(), id2.clone());
+            let rel_id = graph.add_relationship(rel);
+            graph.remove_relationship(&rel_id);
+        }
+
+        assert!(graph.source_to_rels.is_empty());
+        assert!(graph.target_to_rels.is_empty());
+        assert_eq!(graph.relationship_count(), 0);
+    }
+
+    #[test]
+    fn add_relationship_dedup_reuses_identical_relationship() {
+        let mut graph = ModelGraph::new();
+        let id1 = graph.add_element(Element::new_with_kind(ElementKind::StateUsage));
+        let id2 = graph.add_element(Element::new_with_kind(ElementKind::StateUsage));
+
+        let first = Relationship::new(RelationshipKind::Transition, id1.clone(), id2.clone())
+            .with_prop("trigger", "start");
+        let first_id = graph.add_relationship_dedup(first);
+
+        let again = Relationship::new(RelationshipKind::Transition, id1.clone(), id2.clone())
+            .with_prop("trigger", "start");
+        assert_eq!(graph.add_relationship_dedup(again), first_id);
+        assert_eq!(graph.relationship_count(), 1);
+        assert_eq!(graph.outgoing(&id1).count(), 1);
+
+        // Any difference in kind, endpoints or props is a distinct relationship
+        let other_props = Relationship::new(RelationshipKind::Transition, id1.clone(), id2.clone())
+            .with_prop("trigger", "stop");
+        let other_kind = Relationship::new(RelationshipKind::Trace, id1.clone(), id2.clone())
+            .with_prop("trigger", "start");
+        let reversed = Relationship::new(RelationshipKind::Transition, id2.clone(), id1.clone())
+            .with_prop("trigger", "start");
+        assert_ne!(graph.add_relationship_dedup(other_props), first_id);
+        assert_ne!(graph.add_relationship_dedup(other_kind), first_id);
+        assert_ne!(graph.add_relationship_dedup(reversed), first_id);
+        assert_eq!(graph.relationship_count(), 4);
+    }
+
+    #[test]
+    fn add_relationship_dedup_scans_when_indexes_dirty() {
+        let mut graph = ModelGraph::new();
+        let id1 = graph.add_element(Element::new_with_kind(ElementKind::StateUsage));
+        let id2 = graph.add_element(Element::new_with_kind(ElementKind::StateUsage));
+
+        let bulk = Relationship::new(RelationshipKind::Transition, id1.clone(), id2.clone());
+        let bulk_id = bulk.id.clone();
+        graph.add_relationships_bulk(vec![bulk]);
+        assert!(graph.is_indexes_dirty());
+
+        let dup = Relationship::new(RelationshipKind::Transition, id1, id2);
+        assert_eq!(graph.add_relationship_dedup(dup), bulk_id);
+        assert_eq!(graph.relationship_count(), 1);
+    }
+
+    #[test]
+    fn add_relationship_checked_accepts_valid_endpoints() {
+        let mut graph = ModelGraph::new();
+        let usage = graph.add_element(Element::new_with_kind(ElementKind::PartUsage));
+        let def = graph.add_element(Element::new_with_kind(ElementKind::PartDefinition));
+        let base = graph.add_element(Element::new_with_kind(ElementKind::PartDefinition));
+        let note = graph.add_element(Element::new_with_kind(ElementKind::Package));
+
+        // PartUsage is a Feature and PartDefinition a Type
+        let typing = graph
+            .add_relationship_checked(Relationship::new(RelationshipKind::TypeOf, usage.clone(), def.clone()))
+            .unwrap();
+        assert_eq!(graph.outgoing(&usage).next().unwrap().id, typing);
+        graph
+            .add_relationship_checked(Relationship::new(RelationshipKind::Specialize, def.clone(), base))
+            .unwrap();
+        // Kinds without a metamodel counterpart only need existing endpoints
+        graph
+            .add_relationship_checked(Relationship::new(RelationshipKind::Trace, note, usage))
+            .unwrap();
+        assert_eq!(graph.relationship_count(), 3);
+    }
+
+    #[test]
+    fn add_relationship_checked_rejects_dangling_endpoints() {
+        let mut graph = ModelGraph::new();
+        let usage = graph.add_element(Element::new_with_kind(ElementKind::PartUsage));
+        let missing = ElementId::new_v4();
+
+        let err = graph
+            .add_relationship_checked(Relationship::new(RelationshipKind::Trace, missing.clone(), usage.clone()))
+            .unwrap_err();
+        assert_eq!(err, ValidationError::dangling_endpoint("source", missing.clone()));
+
+        let err = graph
+            .add_relationship_checked(Relationship::new(RelationshipKind::Trace, usage, missing.clone()))
+            .unwrap_err();
+        assert_eq!(err.property, "target");
+        assert_eq!(err.kind, ValidationErrorKind::DanglingEndpoint { id: missing });
+        assert_eq!(graph.relationship_count(), 0);
+    }
+
+    #[test]
+    fn add_relationship_checked_rejects_wrong_endpoint_kinds() {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package));
+        let def = graph.add_element(Element::new_with_kind(ElementKind::PartDefinition));
+        let usage = graph.add_element(Element::new_with_kind(ElementKind::PartUsage));
+
+        // A Package is not a Type, so it cannot specialize anything
+        let err = graph
+            .add_relationship_checked(Relationship::new(RelationshipKind::Specialize, pkg.clone(), def.clone()))
+            .unwrap_err();
+        assert_eq!(
+            err.kind,
+            ValidationErrorKind::EndpointKindMismatch {
+                relationship: RelationshipKind::Specialize,
+                expected: ElementKind::Type,
+                got: ElementKind::Package,
+            }
+        );
+        assert_eq!(err.to_string(), "property 'source' of a Specialize relationship must be a Type, got Package");
+
+        // A PartDefinition is not a Feature, so it cannot be typed
+        let err = graph
+            .add_relationship_checked(Relationship::new(RelationshipKind::TypeOf, def.clone(), def.clone()))
+            .unwrap_err();
+        assert_eq!(err.property, "source");
+
+        // Redefinition targets must be Features too
+        let err = graph
+            .add_relationship_checked(Relationship::new(RelationshipKind::Redefine, usage, def))
+            .unwrap_err();
+        assert_eq!(err.property, "target");
+        assert_eq!(graph.relationship_count(), 0);
+    }
+
+    #[test]
+    fn add_relationships_checked_is_all_or_nothing() {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package));
+        let usage = graph.add_element(Element::new_with_kind(ElementKind::PartUsage));
+        let def = graph.add_element(Element::new_with_kind(ElementKind::PartDefinition));
+
+        let good = Relationship::new(RelationshipKind::TypeOf, usage.clone(), def.clone());
+        let wrong_kind = Relationship::new(RelationshipKind::Specialize, pkg, def.clone());
+        let dangling = Relationship::new(RelationshipKind::Trace, usage.clone(), ElementId::new_v4());
+        let (wrong_kind_id, dangling_id) = (wrong_kind.id.clone(), dangling.id.clone());
+
+        let errors = graph
+            .add_relationships_checked(vec![good.clone(), wrong_kind, dangling])
+            .unwrap_err();
+        let failed: Vec<&ElementId> = errors.iter().map(|(id, _)| id).collect();
+        assert_eq!(failed, vec![&wrong_kind_id, &dangling_id]);
+        assert_eq!(graph.relationship_count(), 0);
+
+        let ids = graph.add_relationships_checked(vec![good.clone()]).unwrap();
+        assert_eq!(ids, vec![good.id]);
+        assert_eq!(graph.incoming(&def).count(), 1);
+    }
+
+    fn qn(s: &str) -> QualifiedName {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn get_by_qname_uses_index() {
+        let mut graph = ModelGraph::new();
+        let engine = graph.add_element(
+            Element::new_with_kind(ElementKind::PartDefinition)
+                .with_name("Engine")
+                .with_qname(qn("Vehicle::Engine")),
+        );
+        graph.add_element(Element::new_with_kind(ElementKind::PartDefinition).with_name("Loose"));
+
+        assert_eq!(graph.get_by_qname(&qn("Vehicle::Engine")).unwrap().id, engine);
+        assert_eq!(graph.get_by_qname_str("Vehicle::Engine").unwrap().id, engine);
+        assert!(graph.get_by_qname_str("Vehicle::Wheel").is_none());
+        assert!(graph.get_by_qname_str("Vehicle::::Engine").is_none());
+        // Elements without a stored qname are not indexed
+        assert!(graph.get_by_qname_str("Loose").is_none());
+
+        // Replacing an element re-indexes it under its new qname
+        let mut moved = graph.get_element(&engine).unwrap().clone();
+        moved.qname = Some(qn("Vehicle::Powertrain::Engine"));
+        graph.add_element(moved);
+        assert!(graph.get_by_qname_str("Vehicle::Engine").is_none());
+        assert_eq!(graph.get_by_qname_str("Vehicle::Powertrain::Engine").unwrap().id, engine);
+
+        graph.remove_element(&engine);
+        assert!(graph.get_by_qname_str("Vehicle::Powertrain::Engine").is_none());
+        assert!(graph.qname_to_element.is_empty());
+    }
+
+    #[test]
+    fn get_all_by_qname_reports_duplicates_in_id_order() {
+        let mut graph = ModelGraph::new();
+        let mut ids: Vec<ElementId> = (0..3)
+            .map(|_| {
+                graph.add_element(
+                    Element::new_with_kind(ElementKind::PartDefinition).with_qname(qn("Pkg::Dup")),
+                )
+            })
+            .collect();
+        ids.sort();
+
+        let dup = qn("Pkg::Dup");
+        let found: Vec<ElementId> = graph.get_all_by_qname(&dup).map(|e| e.id.clone()).collect();
+        assert_eq!(found, ids);
+        assert_eq!(graph.get_by_qname(&dup).unwrap().id, ids[0]);
+
+        let incremental = index_snapshot(&graph);
+        graph.rebuild_indexes();
+        assert_eq!(index_snapshot(&graph), incremental);
+    }
+
+    #[test]
+    fn compute_qualified_names_fills_missing_qnames() {
+        let mut graph = ModelGraph::new();
+        let vehicle = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Vehicle"));
+        let powertrain = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::Package).with_name("Powertrain"),
+            vehicle.clone(),
+            VisibilityKind::Public,
+        );
+        let engine = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartDefinition).with_name("Engine"),
+            powertrain.clone(),
+            VisibilityKind::Public,
+        );
+        let preset = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartDefinition)
+                .with_name("Wheel")
+                .with_qname(qn("Legacy::Wheel")),
+            vehicle.clone(),
+            VisibilityKind::Public,
+        );
+        let anonymous = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartUsage),
+            vehicle.clone(),
+            VisibilityKind::Public,
+        );
+        let under_anonymous = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartUsage).with_name("inner"),
+            anonymous.clone(),
+            VisibilityKind::Public,
+        );
+
+        graph.compute_qualified_names();
+
+        assert_eq!(graph.get_by_qname_str("Vehicle").unwrap().id, vehicle);
+        assert_eq!(graph.get_by_qname_str("Vehicle::Powertrain").unwrap().id, powertrain);
+        assert_eq!(graph.get_by_qname_str("Vehicle::Powertrain::Engine").unwrap().id, engine);
+        assert_eq!(
+            graph.get_element(&engine).unwrap().qname,
+            graph.qualified_name_of(&engine)
+        );
+        // Existing qnames are kept
+        assert_eq!(graph.get_by_qname_str("Legacy::Wheel").unwrap().id, preset);
+        assert!(graph.get_by_qname_str("Vehicle::Wheel").is_none());
+        assert!(graph.get_element(&anonymous).unwrap().qname.is_none());
+        assert!(graph.get_element(&under_anonymous).unwrap().qname.is_none());
+
+        let incremental = index_snapshot(&graph);
+        graph.rebuild_indexes();
+        assert_eq!(index_snapshot(&graph), incremental);
+    }
+
+    #[test]
+    fn qname_index_follows_merge_and_clear() {
+        let mut graph = ModelGraph::new();
+        let kept = graph.add_element(
+            Element::new_with_kind(ElementKind::Package).with_qname(qn("Kept")),
+        );
+        let replaced = Element::new_with_kind(ElementKind::Package).with_qname(qn("Old"));
+        let replaced_id = graph.add_element(replaced.clone());
+
+        let mut other = ModelGraph::new();
+        let mut renamed = replaced;
+        renamed.qname = Some(qn("New"));
+        other.add_element(renamed);
+        let added = other.add_element(
+            Element::new_with_kind(ElementKind::Package).with_qname(qn("Lib::Added")),
+        );
+        graph.merge(other, false, MergePolicy::ReplaceExisting).unwrap();
+
+        assert_eq!(graph.get_by_qname_str("Kept").unwrap().id, kept);
+        assert!(graph.get_by_qname_str("Old").is_none());
+        assert_eq!(graph.get_by_qname_str("New").unwrap().id, replaced_id);
+        assert_eq!(graph.get_by_qname_str("Lib::Added").unwrap().id, added);
+
+        let incremental = index_snapshot(&graph);
+        graph.rebuild_indexes();
+        assert_eq!(index_snapshot(&graph), incremental);
+
+        graph.clear();
+        assert!(graph.get_by_qname_str("Kept").is_none());
+        assert!(graph.qname_to_element.is_empty());
+    }
+
+    #[test]
+    fn qname_lookup_in_large_graph_does_not_scan() {
+        let mut graph = ModelGraph::new();
+        let count = 5000;
+        let ids: Vec<ElementId> = (0..count)
+            .map(|i| {
+                graph.add_element(
+                    Element::new_with_kind(ElementKind::PartDefinition)
+                        .with_qname(qn(&format!("Pkg{}::Part{}", i % 50, i))),
+                )
+            })
+            .collect();
+
+        for (i, id) in ids.iter().enumerate() {
+            let found = graph.get_by_qname_str(&format!("Pkg{}::Part{}", i % 50, i)).unwrap();
+            assert_eq!(&found.id, id);
+        }
+
+        // An element inserted behind the index's back is invisible until a rebuild,
+        // which shows lookups go through the index rather than scanning `elements`
+        let hidden = Element::new_with_kind(ElementKind::PartDefinition).with_qname(qn("Hidden"));
+        let hidden_id = hidden.id.clone();
+        graph.elements.insert(hidden_id.clone(), hidden);
+        assert!(graph.get_by_qname_str("Hidden").is_none());
+
+        graph.rebuild_indexes();
+        assert_eq!(graph.get_by_qname_str("Hidden").unwrap().id, hidden_id);
+        assert_eq!(graph.qname_to_element.len(), count + 1);
+    }
+
+    /// The graph's indexes in a canonical form, for comparing incremental updates with a rebuild.
+    fn index_snapshot(
+        graph: &ModelGraph,
+    ) -> Vec<BTreeMap<String, BTreeSet<ElementId>>> {
+        fn sets(index: &FxHashMap<ElementId, FxHashSet<ElementId>>) -> BTreeMap<String, BTreeSet<ElementId>> {
+            index
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.iter().cloned().collect()))
+                .collect()
+        }
+        fn lists(index: &FxHashMap<ElementId, Vec<ElementId>>) -> BTreeMap<String, BTreeSet<ElementId>> {
+            index
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.iter().cloned().collect()))
+                .collect()
+        }
+        vec![
+            sets(&graph.owner_to_children),
+            sets(&graph.source_to_rels),
+            sets(&graph.target_to_rels),
+            graph
+                .kind_to_elements
+                .iter()
+                .map(|(k, v)| (k.as_str().to_string(), v.clone()))
+                .collect(),
+            sets(&graph.namespace_to_memberships),
+            graph
+                .element_to_owning_membership
+                .iter()
+                .map(|(k, v)| (k.to_string(), BTreeSet::from([v.clone()])))
+                .collect(),
+            lists(&graph.typed_feature_to_typings),
+            lists(&graph.specific_to_specializations),
+            graph
+                .qname_to_element
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.iter().cloned().collect()))
+                .collect(),
+        ]
+    }
+
+    /// Package `Pkg` owning part `engine` (which owns `piston`) and part `wheel`,
+    /// with a typing of `engine` and a relationship from `wheel` to `engine`.
+    fn create_removal_graph() -> (ModelGraph, ElementId, ElementId, ElementId, ElementId) {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Pkg"));
+        let engine = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartUsage).with_name("engine"),
+            pkg.clone(),
+            VisibilityKind::Public,
+        );
+        let piston = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartUsage).with_name("piston"),
+            engine.clone(),
+            VisibilityKind::Public,
+        );
+        let wheel = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartUsage).with_name("wheel"),
+            pkg.clone(),
+            VisibilityKind::Public,
+        );
+        let def = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartDefinition).with_name("Engine"),
+            pkg.clone(),
+            VisibilityKind::Public,
+        );
+        graph.add_element(
+            Element::new_with_kind(ElementKind::FeatureTyping)
+                .with_owner(engine.clone())
+                .with_prop("typedFeature", Value::Ref(engine.clone()))
+                .with_prop("type", Value::Ref(def)),
+        );
+        graph.add_relationship(Relationship::new(
+            RelationshipKind::Satisfy,
+            wheel.clone(),
+            engine.clone(),
+        ));
+        (graph, pkg, engine, piston, wheel)
+    }
+
+    #[test]
+    fn typings_and_specializations_use_index_or_scan() {
+        let mut graph = ModelGraph::new();
+        let vehicle = graph.add_element(Element::new_with_kind(ElementKind::PartDefinition).with_name("Vehicle"));
+        let car = graph.add_element(Element::new_with_kind(ElementKind::PartDefinition).with_name("Car"));
+        let my_car = graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("myCar"));
+
+        let subclassification = Element::new_with_kind(ElementKind::Subclassification)
+            .with_prop("specific", Value::Ref(car.clone()))
+            .with_prop("general", Value::Ref(vehicle.clone()));
+        let typing = Element::new_with_kind(ElementKind::FeatureTyping)
+            .with_prop("typedFeature", Value::Ref(my_car.clone()))
+            .with_prop("type", Value::Ref(car.clone()));
+        graph.add_elements_bulk(vec![subclassification, typing]);
+
+        // Stale indexes fall back to a scan
+        assert!(graph.is_indexes_dirty());
+        assert_eq!(graph.specializations_of_specific(&car).count(), 1);
+        assert_eq!(graph.specializations().count(), 1);
+        assert_eq!(graph.typings_of_feature(&my_car).count(), 1);
+
+        graph.rebuild_indexes();
+        assert_eq!(graph.specializations().count(), 1);
+        assert_eq!(
+            graph.specializations_of_specific(&car).next().map(|e| e.kind.clone()),
+            Some(ElementKind::Subclassification)
+        );
+        assert_eq!(graph.typings_of_feature(&my_car).count(), 1);
+        assert_eq!(graph.specializations_of_specific(&vehicle).count(), 0);
+        assert_eq!(graph.typings_of_feature(&car).count(), 0);
+    }
+
+    #[test]
+    fn remove_element_updates_indexes() {
+        let (mut graph, pkg, engine, piston, wheel) = create_removal_graph();
+        let typing_id = graph.typed_feature_to_typings[&engine][0].clone();
+
+        let removed = graph.remove_element(&typing_id).unwrap();
+        assert_eq!(removed.kind, ElementKind::FeatureTyping);
+        assert!(!graph.typed_feature_to_typings.contains_key(&engine));
+        assert_eq!(graph.elements_by_kind(&ElementKind::FeatureTyping).count(), 0);
+
+        let removed = graph.remove_element(&wheel).unwrap();
+        assert_eq!(removed.name.as_deref(), Some("wheel"));
+        assert!(graph.get_element(&wheel).is_none());
+        assert!(graph.children_of(&pkg).all(|c| c.id != wheel));
+        assert!(!graph.element_to_owning_membership.contains_key(&wheel));
+
+        // The relationship from wheel was detached
+        assert_eq!(graph.relationship_count(), 0);
+        assert_eq!(graph.incoming(&engine).count(), 0);
+        assert_eq!(graph.outgoing(&wheel).count(), 0);
+
+        // Children are left in place
+        assert!(graph.get_element(&piston).is_some());
+        assert!(graph.remove_element(&wheel).is_none());
+
+        let incremental = index_snapshot(&graph);
+        graph.rebuild_indexes();
+        assert_eq!(incremental, index_snapshot(&graph));
+    }
+
+    #[test]
+    fn remove_subtree_removes_owned_content() {
+        let (mut graph, pkg, engine, piston, wheel) = create_removal_graph();
+        let before = graph.element_count();
+
+        let removed = graph.remove_subtree(&engine);
+        assert_eq!(removed[0].id, engine);
+        // engine, piston, the typing, engine's membership in Pkg, piston's membership in engine
+        assert_eq!(removed.len(), 5);
+        assert_eq!(graph.element_count(), before - 5);
+        assert!(graph.get_element(&piston).is_none());
+        assert!(graph.children_of(&engine).next().is_none());
+        assert!(!graph.namespace_to_memberships.contains_key(&engine));
+        assert_eq!(graph.namespace_to_memberships[&pkg].len(), 2);
+        assert_eq!(graph.outgoing(&wheel).count(), 0);
+
+        let incremental = index_snapshot(&graph);
+        graph.rebuild_indexes();
+        assert_eq!(incremental, index_snapshot(&graph));
+        assert!(graph.validate_structure().is_empty());
+
+        assert!(graph.remove_subtree(&engine).is_empty());
+    }
+
+    #[test]
+    fn subgraph_extracts_owned_content() {
+        let (graph, _pkg, engine, piston, wheel) = create_removal_graph();
+        let before = (graph.element_count(), graph.relationship_count());
+
+        let sub = graph.subgraph(&engine, false);
+        // engine, piston, the typing and piston's membership in engine
+        assert_eq!(sub.element_count(), 4);
+        assert_eq!(sub.relationship_count(), 0);
+        assert!(sub.get_element(&wheel).is_none());
+        assert!(sub.children_of(&engine).any(|c| c.id == piston));
+        let root = sub.get_element(&engine).unwrap();
+        assert!(root.owner.is_none());
+        assert!(root.owning_membership.is_none());
+        assert!(!sub.is_indexes_dirty());
+
+        // The source graph is untouched
+        assert_eq!((graph.element_count(), graph.relationship_count()), before);
+        assert!(graph.get_element(&engine).unwrap().owner.is_some());
+    }
+
+    #[test]
+    fn subgraph_with_external_relationships_adds_stubs() {
+        let (graph, _pkg, engine, _piston, wheel) = create_removal_graph();
+
+        let sub = graph.subgraph(&engine, true);
+        assert_eq!(sub.element_count(), 5);
+        assert_eq!(sub.relationship_count(), 1);
+        let stub = sub.get_element(&wheel).unwrap();
+        assert_eq!(stub.kind, ElementKind::PartUsage);
+        assert_eq!(stub.name.as_deref(), Some("wheel"));
+        assert_eq!(stub.get_bool("external"), Some(true));
+        assert!(stub.owner.is_none());
+        assert_eq!(sub.incoming(&engine).count(), 1);
+    }
+
+    #[test]
+    fn subgraph_does_not_carry_library_packages() {
+        let mut graph = ModelGraph::new();
+        let lib = graph.add_library_package(Element::new_with_kind(ElementKind::Package).with_name("Lib"));
+        graph.add_owned_element(
+            Element::new_with_kind(ElementKind::AttributeDefinition).with_name("Real"),
+            lib.clone(),
+            VisibilityKind::Public,
+        );
+
+        let sub = graph.subgraph(&lib, false);
+        assert_eq!(sub.element_count(), 3);
+        assert!(sub.validate_structure().is_empty());
+        assert!(sub.library_packages().is_empty());
+        assert!(graph.is_library_package(&lib));
+        assert!(graph.subgraph(&ElementId::new_v4(), true).is_empty());
+    }
+
+    #[test]
+    fn remove_library_package() {
+        let mut graph = ModelGraph::new();
+        let lib = graph.add_library_package(Element::new_with_kind(ElementKind::Package).with_name("Lib"));
+        graph.add_owned_element(
+            Element::new_with_kind(ElementKind::AttributeDefinition).with_name("Real"),
+            lib.clone(),
+            VisibilityKind::Public,
+        );
+        graph.ensure_library_index();
+        assert!(graph.resolve_in_library("Real").is_some());
+
+        graph.remove_subtree(&lib);
+        assert!(!graph.is_library_package(&lib));
+        assert!(graph.resolve_in_library("Real").is_none());
+        assert!(graph.resolve_in_library("Lib").is_none());
+        assert!(graph.is_empty());
+    }
+
     #[test]
     fn children_of() {
         let graph = create_test_graph();
@@ -1027,6 +2641,396 @@ mod tests {
         assert_eq!(parts.len(), 1);
     }
 
+    #[test]
+    fn elements_by_kind_uses_index() {
+        let mut graph = ModelGraph::new();
+        for i in 0..50_000 {
+            let kind = if i % 5_000 == 0 {
+                ElementKind::RequirementUsage
+            } else {
+                ElementKind::PartUsage
+            };
+            graph.add_element(Element::new_with_kind(kind));
+        }
+
+        // Lookups only visit the indexed ids for the requested kind
+        assert_eq!(graph.kind_to_elements[&ElementKind::RequirementUsage].len(), 10);
+        assert_eq!(graph.elements_by_kind(&ElementKind::RequirementUsage).count(), 10);
+        assert_eq!(graph.elements_by_kind(&ElementKind::PartUsage).count(), 49_990);
+        assert_eq!(graph.elements_by_kind(&ElementKind::Package).count(), 0);
+    }
+
+    #[test]
+    fn elements_by_kind_tracks_replaced_and_cleared_elements() {
+        let mut graph = ModelGraph::new();
+        let id = graph.add_element(Element::new_with_kind(ElementKind::PartUsage));
+
+        // Re-adding the same id with a new kind moves it between index entries
+        graph.add_element(Element::new(id.clone(), ElementKind::PortUsage));
+        assert_eq!(graph.elements_by_kind(&ElementKind::PartUsage).count(), 0);
+        assert_eq!(graph.elements_by_kind(&ElementKind::PortUsage).next().unwrap().id, id);
+
+        graph.clear();
+        assert_eq!(graph.elements_by_kind(&ElementKind::PortUsage).count(), 0);
+    }
+
+    #[test]
+    fn elements_by_kind_without_index_falls_back_to_scan() {
+        let mut graph = ModelGraph::new();
+        let part = Element::new_with_kind(ElementKind::PartUsage);
+        let part_id = part.id.clone();
+        // Direct insertion bypasses the indexes, as deserialization does
+        graph.elements.insert(part_id.clone(), part);
+
+        let parts: Vec<_> = graph.elements_by_kind(&ElementKind::PartUsage).collect();
+        assert_eq!(parts.len(), 1);
+
+        graph.rebuild_indexes();
+        assert!(graph.kind_to_elements.contains_key(&ElementKind::PartUsage));
+        assert_eq!(graph.elements_by_kind(&ElementKind::PartUsage).next().unwrap().id, part_id);
+    }
+
+    #[test]
+    fn bulk_insertion_defers_index_rebuild() {
+        let mut graph = ModelGraph::new();
+
+        let pkg = Element::new_with_kind(ElementKind::Package).with_name("Pkg");
+        let pkg_id = pkg.id.clone();
+        let parts: Vec<Element> = (0..100)
+            .map(|i| {
+                Element::new_with_kind(ElementKind::PartUsage)
+                    .with_name(format!("p{}", i))
+                    .with_owner(pkg_id.clone())
+            })
+            .collect();
+        let first_part = parts[0].id.clone();
+        let second_part = parts[1].id.clone();
+
+        graph.add_elements_bulk(std::iter::once(pkg).chain(parts));
+        graph.add_relationships_bulk(vec![Relationship::new(
+            RelationshipKind::Satisfy,
+            first_part.clone(),
+            second_part,
+        )]);
+
+        assert!(graph.is_indexes_dirty());
+        assert_eq!(graph.element_count(), 101);
+        assert_eq!(graph.relationship_count(), 1);
+        // The indexes are not updated yet, so index-backed queries scan instead
+        assert!(graph.owner_to_children.is_empty());
+        assert_eq!(graph.children_of(&pkg_id).count(), 100);
+        assert_eq!(graph.outgoing(&first_part).count(), 1);
+        assert_eq!(graph.elements_by_kind(&ElementKind::PartUsage).count(), 100);
+
+        graph.rebuild_indexes();
+        assert!(!graph.is_indexes_dirty());
+        assert_eq!(graph.children_of(&pkg_id).count(), 100);
+        assert_eq!(graph.outgoing(&first_part).count(), 1);
+        assert_eq!(graph.elements_by_kind(&ElementKind::PartUsage).count(), 100);
+    }
+
+    #[test]
+    fn merge_indexes_content_without_rebuild() {
+        // Build the other graph with bulk insertion so its own indexes are never built
+        let mut library = ModelGraph::new();
+        let pkg = Element::new_with_kind(ElementKind::LibraryPackage).with_name("ScalarValues");
+        let pkg_id = pkg.id.clone();
+        let real = Element::new_with_kind(ElementKind::DataType)
+            .with_name("Real")
+            .with_owner(pkg_id.clone());
+        let real_id = real.id.clone();
+        let membership = MembershipBuilder::owning()
+            .owning_namespace(pkg_id.clone())
+            .member_element(real_id.clone())
+            .build();
+        library.add_elements_bulk(vec![pkg, real, membership]);
+
+        let mut graph = create_test_graph();
+        let part_id = graph.elements_by_kind(&ElementKind::PartUsage).next().unwrap().id.clone();
+        library.add_relationships_bulk(vec![Relationship::new(
+            RelationshipKind::TypeOf,
+            part_id.clone(),
+            real_id.clone(),
+        )]);
+
+        graph.merge(library, true, MergePolicy::ReplaceExisting).unwrap();
+
+        assert!(!graph.is_indexes_dirty());
+        assert_eq!(graph.children_of(&pkg_id).next().unwrap().id, real_id);
+        assert_eq!(graph.incoming(&real_id).count(), 1);
+        assert_eq!(graph.outgoing(&part_id).filter(|r| r.target == real_id).count(), 1);
+        assert_eq!(graph.owned_memberships(&pkg_id).count(), 1);
+        assert_eq!(graph.elements_by_kind(&ElementKind::DataType).count(), 1);
+        assert!(graph.is_library_package(&pkg_id));
+    }
+
+    #[test]
+    fn merge_library_into_bulk_loaded_graph_has_no_stale_reads() {
+        // Regression: children_of/incoming used to return nothing for merged or
+        // bulk-loaded content until the caller ran rebuild_indexes()
+        let mut library = ModelGraph::new();
+        let lib_pkg = library.add_element(Element::new_with_kind(ElementKind::LibraryPackage).with_name("Lib"));
+        let base = library.add_owned_element(
+            Element::new_with_kind(ElementKind::Classifier).with_name("Anything"),
+            lib_pkg.clone(),
+            VisibilityKind::Public,
+        );
+
+        let mut graph = ModelGraph::new();
+        let user_pkg = Element::new_with_kind(ElementKind::Package).with_name("User");
+        let user_pkg_id = user_pkg.id.clone();
+        let part = Element::new_with_kind(ElementKind::PartUsage).with_owner(user_pkg_id.clone());
+        let part_id = part.id.clone();
+        graph.add_elements_bulk(vec![user_pkg, part]);
+        graph.add_relationships_bulk(vec![Relationship::new(RelationshipKind::TypeOf, part_id.clone(), base.clone())]);
+
+        graph.merge(library, true, MergePolicy::ReplaceExisting).unwrap();
+
+        assert_eq!(graph.children_of(&lib_pkg).next().unwrap().id, base);
+        assert_eq!(graph.children_of(&user_pkg_id).next().unwrap().id, part_id);
+        assert_eq!(graph.incoming(&base).next().unwrap().source, part_id);
+        assert_eq!(graph.outgoing(&part_id).count(), 1);
+    }
+
+    #[test]
+    fn index_queries_work_on_unindexed_graph() {
+        // Simulates a deserialized graph, whose indexes are skipped by serde
+        let mut graph = ModelGraph::new();
+        let pkg = Element::new_with_kind(ElementKind::Package);
+        let part = Element::new_with_kind(ElementKind::PartUsage).with_owner(pkg.id.clone());
+        let rel = Relationship::new(RelationshipKind::Satisfy, part.id.clone(), pkg.id.clone());
+        let (pkg_id, part_id) = (pkg.id.clone(), part.id.clone());
+        graph.elements.insert(pkg_id.clone(), pkg);
+        graph.elements.insert(part_id.clone(), part);
+        graph.relationships.insert(rel.id.clone(), rel);
+        assert!(!graph.is_indexes_dirty());
+
+        assert_eq!(graph.children_of(&pkg_id).next().unwrap().id, part_id);
+        assert_eq!(graph.outgoing(&part_id).count(), 1);
+        assert_eq!(graph.incoming(&pkg_id).count(), 1);
+    }
+
+    #[test]
+    fn merge_replacing_element_moves_owner_index() {
+        let mut graph = ModelGraph::new();
+        let a = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("A"));
+        let b = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("B"));
+        let part = Element::new_with_kind(ElementKind::PartUsage).with_owner(a.clone());
+        let part_id = graph.add_element(part);
+
+        let mut other = ModelGraph::new();
+        other.add_element(Element::new(part_id.clone(), ElementKind::PartUsage).with_owner(b.clone()));
+        graph.merge(other, false, MergePolicy::ReplaceExisting).unwrap();
+
+        assert_eq!(graph.children_of(&a).count(), 0);
+        assert_eq!(graph.children_of(&b).next().unwrap().id, part_id);
+    }
+
+    #[test]
+    fn merge_replacing_content_moves_every_reverse_index() {
+        let mut graph = ModelGraph::new();
+        let ns_a = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("A"));
+        let ns_b = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("B"));
+        let x = graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("x"));
+        let y = graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("y"));
+        let typing = graph.add_element(
+            Element::new_with_kind(ElementKind::FeatureTyping).with_prop("typedFeature", Value::Ref(x.clone())),
+        );
+        let specialization = graph.add_element(
+            Element::new_with_kind(ElementKind::Specialization).with_prop("specific", Value::Ref(x.clone())),
+        );
+        let membership = graph.add_element(
+            Element::new_with_kind(ElementKind::Membership)
+                .with_prop("membershipOwningNamespace", Value::Ref(ns_a.clone())),
+        );
+        let rel = graph.add_relationship(Relationship::new(RelationshipKind::TypeOf, x.clone(), y.clone()));
+
+        // The same ids, now pointing from `y` and into `B`
+        let mut other = ModelGraph::new();
+        other.add_element(
+            Element::new(typing.clone(), ElementKind::FeatureTyping).with_prop("typedFeature", Value::Ref(y.clone())),
+        );
+        other.add_element(
+            Element::new(specialization.clone(), ElementKind::Specialization).with_prop("specific", Value::Ref(y.clone())),
+        );
+        other.add_element(
+            Element::new(membership.clone(), ElementKind::Membership)
+                .with_prop("membershipOwningNamespace", Value::Ref(ns_b.clone())),
+        );
+        let mut moved = Relationship::new(RelationshipKind::TypeOf, y.clone(), x.clone());
+        moved.id = rel.clone();
+        other.add_relationship(moved);
+        graph.merge(other, false, MergePolicy::ReplaceExisting).unwrap();
+
+        assert_eq!(graph.typings_of_feature(&x).count(), 0);
+        assert_eq!(graph.typings_of_feature(&y).next().unwrap().id, typing);
+        assert_eq!(graph.specializations_of_specific(&x).count(), 0);
+        assert_eq!(graph.specializations_of_specific(&y).next().unwrap().id, specialization);
+        assert_eq!(graph.memberships(&ns_a).count(), 0);
+        assert_eq!(graph.memberships(&ns_b).next().unwrap().id, membership);
+        assert_eq!(graph.outgoing(&x).count(), 0);
+        assert_eq!(graph.outgoing(&y).next().unwrap().id, rel);
+        assert_eq!(graph.incoming(&y).count(), 0);
+        assert_eq!(graph.incoming(&x).next().unwrap().id, rel);
+    }
+
+    /// A graph with package `Pkg` owning `Engine`, and a second graph reusing
+    /// `Engine`'s id (renamed, moved to a new `Other` package) plus a new `Wheel`.
+    fn create_colliding_graphs() -> (ModelGraph, ModelGraph, ElementId, ElementId) {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Pkg"));
+        let engine = graph.add_element(
+            Element::new_with_kind(ElementKind::PartDefinition)
+                .with_name("Engine")
+                .with_qname("Pkg::Engine".parse().unwrap())
+                .with_owner(pkg),
+        );
+
+        let mut other = ModelGraph::new();
+        let other_pkg = other.add_element(Element::new_with_kind(ElementKind::Package).with_name("Other"));
+        other.add_element(
+            Element::new(engine.clone(), ElementKind::PartDefinition)
+                .with_name("Motor")
+                .with_qname("Other::Motor".parse().unwrap())
+                .with_owner(other_pkg),
+        );
+        let wheel = other.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("Wheel"));
+        other.add_relationship(Relationship::new(RelationshipKind::TypeOf, wheel.clone(), engine.clone()));
+
+        (graph, other, engine, wheel)
+    }
+
+    #[test]
+    fn merge_replace_existing_reports_replaced_ids() {
+        let (mut graph, other, engine, wheel) = create_colliding_graphs();
+
+        let report = graph.merge(other, false, MergePolicy::ReplaceExisting).unwrap();
+
+        assert_eq!(report, MergeReport { merged: 3, replaced: vec![engine.clone()], skipped: 0 });
+        assert_eq!(graph.get_element(&engine).unwrap().name.as_deref(), Some("Motor"));
+        assert!(graph.get_by_qname_str("Pkg::Engine").is_none());
+        assert_eq!(graph.get_by_qname_str("Other::Motor").unwrap().id, engine);
+        assert_eq!(graph.incoming(&engine).next().unwrap().source, wheel);
+
+        let incremental = index_snapshot(&graph);
+        graph.rebuild_indexes();
+        assert_eq!(index_snapshot(&graph), incremental);
+    }
+
+    #[test]
+    fn merge_keep_existing_skips_colliding_ids() {
+        let (mut graph, other, engine, wheel) = create_colliding_graphs();
+        let library = other.clone();
+
+        let report = graph.merge(other, false, MergePolicy::KeepExisting).unwrap();
+
+        assert_eq!(report, MergeReport { merged: 2, replaced: vec![], skipped: 1 });
+        assert_eq!(graph.get_element(&engine).unwrap().name.as_deref(), Some("Engine"));
+        assert_eq!(graph.get_by_qname_str("Pkg::Engine").unwrap().id, engine);
+        assert!(graph.get_by_qname_str("Other::Motor").is_none());
+        assert!(graph.get_element(&wheel).is_some());
+
+        let incremental = index_snapshot(&graph);
+        graph.rebuild_indexes();
+        assert_eq!(index_snapshot(&graph), incremental);
+
+        // Merging the same graph again is a no-op
+        let elements = graph.elements.clone();
+        let relationship_count = graph.relationship_count();
+        let report = graph.merge(library, false, MergePolicy::KeepExisting).unwrap();
+        assert_eq!(report, MergeReport { merged: 0, replaced: vec![], skipped: 3 });
+        assert_eq!(graph.elements, elements);
+        assert_eq!(graph.relationship_count(), relationship_count);
+        assert_eq!(index_snapshot(&graph), incremental);
+    }
+
+    #[test]
+    fn merge_error_policy_leaves_graph_untouched() {
+        let (mut graph, other, engine, _) = create_colliding_graphs();
+        let elements = graph.elements.clone();
+        let snapshot = index_snapshot(&graph);
+
+        let err = graph.merge(other.clone(), true, MergePolicy::Error).unwrap_err();
+
+        assert_eq!(err, MergeError::IdCollision(vec![engine]));
+        assert_eq!(graph.elements, elements);
+        assert_eq!(graph.relationship_count(), 0);
+        assert!(graph.library_packages().is_empty());
+        assert_eq!(index_snapshot(&graph), snapshot);
+
+        // Without collisions the Error policy merges normally
+        let report = ModelGraph::new().merge(other, false, MergePolicy::Error).unwrap();
+        assert_eq!(report.merged, 3);
+    }
+
+    #[test]
+    fn merge_with_remap_rewrites_colliding_ids() {
+        let mut graph = ModelGraph::new();
+        let shared_pkg = ElementId::from_string("Pkg");
+        let shared_part = ElementId::from_string("Pkg::Part");
+        graph.add_element(Element::new(shared_pkg.clone(), ElementKind::Package).with_name("Mine"));
+        graph.add_element(
+            Element::new(shared_part.clone(), ElementKind::PartUsage)
+                .with_name("MinePart")
+                .with_owner(shared_pkg.clone()),
+        );
+
+        // An independently built graph using the same deterministic ids
+        let mut other = ModelGraph::new();
+        other.add_element(Element::new(shared_pkg.clone(), ElementKind::Package).with_name("Theirs"));
+        other.add_element(
+            Element::new(shared_part.clone(), ElementKind::PartUsage)
+                .with_name("TheirPart")
+                .with_owner(shared_pkg.clone())
+                .with_prop("peers", Value::List(vec![Value::Ref(shared_pkg.clone())])),
+        );
+        let unique = other.add_element(
+            Element::new_with_kind(ElementKind::PartUsage)
+                .with_name("Unique")
+                .with_owner(shared_pkg.clone())
+                .with_prop("ref", Value::Ref(shared_part.clone())),
+        );
+        other.add_relationship(Relationship::new(
+            RelationshipKind::Satisfy,
+            unique.clone(),
+            shared_part.clone(),
+        ));
+
+        let mapping = graph.merge_with_remap(other);
+
+        assert_eq!(mapping.len(), 2);
+        assert!(!mapping.contains_key(&unique));
+        let new_pkg = &mapping[&shared_pkg];
+        let new_part = &mapping[&shared_part];
+
+        // Nothing of ours was overwritten
+        assert_eq!(graph.get_element(&shared_pkg).unwrap().name.as_deref(), Some("Mine"));
+        assert_eq!(graph.get_element(&shared_part).unwrap().name.as_deref(), Some("MinePart"));
+        assert_eq!(graph.element_count(), 5);
+
+        // References inside the merged graph follow the remapping
+        let their_part = graph.get_element(new_part).unwrap();
+        assert_eq!(their_part.owner.as_ref(), Some(new_pkg));
+        assert_eq!(their_part.get_prop("peers"), Some(&Value::List(vec![Value::Ref(new_pkg.clone())])));
+        let unique_elem = graph.get_element(&unique).unwrap();
+        assert_eq!(unique_elem.owner.as_ref(), Some(new_pkg));
+        assert_eq!(unique_elem.get_prop("ref"), Some(&Value::Ref(new_part.clone())));
+        assert_eq!(graph.outgoing(&unique).next().unwrap().target, *new_part);
+        assert_eq!(graph.children_of(new_pkg).count(), 2);
+        assert_eq!(graph.children_of(&shared_pkg).count(), 1);
+    }
+
+    #[test]
+    fn merge_with_remap_without_collisions() {
+        let mut graph = create_test_graph();
+        let other = create_test_graph();
+        let count = graph.element_count() + other.element_count();
+
+        let mapping = graph.merge_with_remap(other);
+        assert!(mapping.is_empty());
+        assert_eq!(graph.element_count(), count);
+    }
+
     #[test]
     fn roots() {
         let graph = create_test_graph();
@@ -1170,6 +3174,37 @@ mod tests {
         assert!(!ElementKind::Element.is_usage());
     }
 
+    #[test]
+    fn test_is_abstract_predicate() {
+        // Abstract metaclasses as flagged in the KerML/SysML XMI
+        let expected = [
+            ElementKind::ConnectorAsUsage,
+            ElementKind::ControlNode,
+            ElementKind::Element,
+            ElementKind::Expose,
+            ElementKind::Import,
+            ElementKind::InstantiationExpression,
+            ElementKind::LoopActionUsage,
+            ElementKind::Relationship,
+        ];
+
+        let mut abstract_kinds = Vec::new();
+        for kind in ElementKind::iter() {
+            assert_ne!(kind.is_abstract(), kind.is_concrete(), "{:?}", kind);
+            if kind.is_abstract() {
+                abstract_kinds.push(kind);
+            }
+        }
+        assert_eq!(abstract_kinds.len(), expected.len(), "{:?}", abstract_kinds);
+        for kind in expected {
+            assert!(kind.is_abstract(), "{:?} should be abstract", kind);
+        }
+
+        assert!(ElementKind::PartUsage.is_concrete());
+        assert!(ElementKind::Feature.is_concrete());
+        assert!(ElementKind::Specialization.is_concrete());
+    }
+
     #[test]
     fn test_is_relationship_predicate() {
         assert!(ElementKind::Relationship.is_relationship());
@@ -1326,7 +3361,7 @@ mod tests {
         );
 
         // Merge graph2 into graph1
-        graph1.merge(graph2, false);
+        graph1.merge(graph2, false, MergePolicy::ReplaceExisting).unwrap();
 
         // Verify graph1 now has the index entry (critical for library resolution)
         assert!(
@@ -1353,7 +3388,7 @@ mod tests {
         assert!(graph2.owner_to_children.get(&pkg_id).map_or(false, |children| children.contains(&child_id)));
 
         // Merge and verify
-        graph1.merge(graph2, false);
+        graph1.merge(graph2, false, MergePolicy::ReplaceExisting).unwrap();
         assert!(
             graph1.owner_to_children.get(&pkg_id).map_or(false, |children| children.contains(&child_id)),
             "owner_to_children should be preserved after merge"
@@ -1370,7 +3405,7 @@ mod tests {
         let lib_pkg_id = graph2.add_element(lib_pkg);
 
         // Merge as library
-        graph1.merge(graph2, true);
+        graph1.merge(graph2, true, MergePolicy::ReplaceExisting).unwrap();
 
         // Verify the package is registered as a library package
         assert!(
@@ -0,0 +1,208 @@
+//! Multiplicity validation for SysML v2 features.
+//!
+//! Features carry their multiplicity as `multiplicity_lower` (an integer)
+//! and `multiplicity_upper` (an integer, or `"*"` for unbounded) props, as
+//! written by the parser for `[n]`, `[n..m]` and `[n..*]`. This module checks:
+//! - Each feature's own bounds: lower is non-negative and at most upper
+//! - A feature that redefines or subsets another stays within its bounds
+//!
+//! The conformance check only looks at resolved targets, so it is most
+//! useful after name resolution.
+//!
+//! ## Usage
+//!
+//! ```ignore
+//! for error in validate_multiplicities(&graph) {
+//!     eprintln!("Error: {}", error);
+//! }
+//! ```
+
+use crate::resolution::resolved_props;
+use crate::{Element, ElementKind, ModelGraph, RelationshipKind, ValidationError};
+
+/// The prop holding a feature's lower bound.
+pub(crate) const LOWER: &str = "multiplicity_lower";
+/// The prop holding a feature's upper bound.
+pub(crate) const UPPER: &str = "multiplicity_upper";
+
+/// Multiplicity bounds, with `None` as an unbounded (`*`) upper bound.
+pub type Bounds = (i64, Option<i64>);
+
+/// The multiplicity bounds of a feature, if it declares any.
+///
+/// A missing upper bound is taken to equal the lower one, as in `[n]`.
+pub fn multiplicity_bounds(element: &Element) -> Option<Bounds> {
+    let lower = element.get_int(LOWER)?;
+    let upper = match element.get_prop(UPPER) {
+        Some(upper) if upper.as_str() == Some("*") => None,
+        Some(upper) => Some(upper.as_int()?),
+        None => Some(lower),
+    };
+    Some((lower, upper))
+}
+
+/// Whether `inner` lies within `outer`, with unbounded uppers as infinity.
+fn within(inner: Bounds, outer: Bounds) -> bool {
+    let upper_ok = match (inner.1, outer.1) {
+        (_, None) => true,
+        (None, Some(_)) => false,
+        (Some(inner), Some(outer)) => inner <= outer,
+    };
+    inner.0 >= outer.0 && upper_ok
+}
+
+/// Validate the multiplicities of every feature in a graph.
+///
+/// Reports features whose lower bound is negative or above their upper
+/// bound, and features whose bounds are not within those of a feature they
+/// redefine or subset. Features without bounds, and general features
+/// without bounds, are not checked. Generals come from resolved
+/// Redefinition and Subsetting elements owned by the feature, and from
+/// `Redefine` and `Subsetting` relationships. Errors are ordered by
+/// element id.
+pub fn validate_multiplicities(graph: &ModelGraph) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    for element in graph.elements.values() {
+        let bounds = match multiplicity_bounds(element) {
+            Some(bounds) => bounds,
+            None => continue,
+        };
+        let (lower, upper) = bounds;
+        if lower < 0 || upper.is_some_and(|upper| upper < lower) {
+            // Conformance means little for bounds that are wrong themselves
+            errors.push(ValidationError::invalid_multiplicity(element.id.clone(), lower, upper));
+            continue;
+        }
+
+        let mut generals: Vec<&Element> = generals_of(graph, element)
+            .filter(|general| general.id != element.id)
+            .collect();
+        generals.sort_by(|a, b| a.id.cmp(&b.id));
+        generals.dedup_by(|a, b| a.id == b.id);
+        for general in generals {
+            if let Some(general_bounds) = multiplicity_bounds(general) {
+                if !within(bounds, general_bounds) {
+                    errors.push(ValidationError::multiplicity_not_within(
+                        element.id.clone(),
+                        bounds,
+                        general.id.clone(),
+                        general_bounds,
+                    ));
+                }
+            }
+        }
+    }
+    errors
+}
+
+/// The features a feature redefines or subsets.
+fn generals_of<'a>(graph: &'a ModelGraph, feature: &'a Element) -> impl Iterator<Item = &'a Element> {
+    let owned = graph
+        .children_of(&feature.id)
+        .filter(|child| child.kind == ElementKind::Subsetting || child.kind.is_subtype_of(ElementKind::Subsetting))
+        .filter_map(|subsetting| {
+            [
+                resolved_props::REDEFINED_FEATURE,
+                resolved_props::SUBSETTED_FEATURE,
+                resolved_props::REFERENCED_FEATURE,
+            ]
+            .iter()
+            .find_map(|key| subsetting.get_ref(key))
+        });
+    let related = graph
+        .outgoing(&feature.id)
+        .filter(|rel| matches!(rel.kind, RelationshipKind::Redefine | RelationshipKind::Subsetting))
+        .map(|rel| &rel.target);
+    owned.chain(related).filter_map(move |id| graph.get_element(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Relationship, ValidationErrorKind, Value, VisibilityKind};
+
+    fn feature(graph: &mut ModelGraph, name: &str, lower: i64, upper: Option<i64>) -> crate::ElementId {
+        let upper = match upper {
+            Some(upper) => Value::Int(upper),
+            None => Value::String("*".to_string()),
+        };
+        let element = Element::new_with_kind(ElementKind::PartUsage)
+            .with_name(name)
+            .with_prop(LOWER, lower)
+            .with_prop(UPPER, upper);
+        graph.add_element(element)
+    }
+
+    /// Give `feature` an owned, resolved relationship of `kind` to `general`.
+    fn relate(graph: &mut ModelGraph, kind: ElementKind, feature: &crate::ElementId, general: &crate::ElementId) {
+        let key = match kind {
+            ElementKind::Redefinition => resolved_props::REDEFINED_FEATURE,
+            _ => resolved_props::SUBSETTED_FEATURE,
+        };
+        let element = Element::new_with_kind(kind).with_prop(key, Value::Ref(general.clone()));
+        graph.add_owned_element(element, feature.clone(), VisibilityKind::Public);
+    }
+
+    #[test]
+    fn inverted_and_negative_bounds_are_reported() {
+        let mut graph = ModelGraph::new();
+        let inverted = feature(&mut graph, "inverted", 5, Some(2));
+        let negative = feature(&mut graph, "negative", -1, None);
+        feature(&mut graph, "exact", 4, Some(4));
+        feature(&mut graph, "many", 0, None);
+
+        let mut expected = vec![
+            ValidationError::invalid_multiplicity(inverted, 5, Some(2)),
+            ValidationError::invalid_multiplicity(negative, -1, None),
+        ];
+        expected.sort_by_key(|error| error.element().cloned());
+        assert_eq!(validate_multiplicities(&graph), expected);
+        let messages: Vec<String> = expected.iter().map(ToString::to_string).collect();
+        assert!(messages.iter().any(|message| message.contains("invalid bounds [5..2]")));
+        assert!(messages.iter().any(|message| message.contains("invalid bounds [-1..*]")));
+    }
+
+    #[test]
+    fn conforming_redefinition_passes() {
+        let mut graph = ModelGraph::new();
+        let wheels = feature(&mut graph, "wheels", 2, None);
+        let front = feature(&mut graph, "front", 2, Some(2));
+        relate(&mut graph, ElementKind::Redefinition, &front, &wheels);
+        let spares = feature(&mut graph, "spares", 3, Some(8));
+        graph.add_relationship(Relationship::new(RelationshipKind::Redefine, spares, wheels));
+
+        assert_eq!(validate_multiplicities(&graph), vec![]);
+    }
+
+    #[test]
+    fn violating_subsetting_is_reported() {
+        let mut graph = ModelGraph::new();
+        let wheels = feature(&mut graph, "wheels", 2, Some(4));
+        let extra = feature(&mut graph, "extra", 1, Some(6));
+        relate(&mut graph, ElementKind::Subsetting, &extra, &wheels);
+        let unbounded = feature(&mut graph, "unbounded", 2, None);
+        graph.add_relationship(Relationship::new(RelationshipKind::Subsetting, unbounded.clone(), wheels.clone()));
+
+        let mut expected = vec![
+            ValidationError::multiplicity_not_within(extra.clone(), (1, Some(6)), wheels.clone(), (2, Some(4))),
+            ValidationError::multiplicity_not_within(unbounded, (2, None), wheels.clone(), (2, Some(4))),
+        ];
+        expected.sort_by_key(|error| error.element().cloned());
+        let errors = validate_multiplicities(&graph);
+        assert_eq!(errors, expected);
+        assert!(errors.iter().all(|error| matches!(error.kind, ValidationErrorKind::MultiplicityNotWithin { .. })));
+
+        let error = errors.iter().find(|error| error.element() == Some(&extra)).unwrap();
+        assert!(error.to_string().contains("[1..6] is not within [2..4]"));
+    }
+
+    #[test]
+    fn bounds_read_from_props() {
+        let exact = Element::new_with_kind(ElementKind::PartUsage).with_prop(LOWER, 3i64);
+        assert_eq!(multiplicity_bounds(&exact), Some((3, Some(3))));
+        let many = exact.clone().with_prop(UPPER, "*");
+        assert_eq!(multiplicity_bounds(&many), Some((3, None)));
+        let none = Element::new_with_kind(ElementKind::PartUsage).with_prop(UPPER, 3i64);
+        assert_eq!(multiplicity_bounds(&none), None);
+    }
+}
@@ -5,6 +5,7 @@
 //! - Getting owned memberships and members of a namespace
 //! - Resolving names within a namespace
 //! - Resolving qualified names from the root
+//! - Looking up elements by `QualifiedName`, including library packages
 //!
 //! ## Visibility
 //!
@@ -15,7 +16,7 @@
 
 use crate::membership::{props as membership_props, MembershipView};
 use crate::{Element, ElementKind, ModelGraph, VisibilityKind};
-use sysml_id::ElementId;
+use sysml_id::{ElementId, QualifiedName};
 
 impl ModelGraph {
     /// Get the owned memberships of a namespace.
@@ -190,6 +191,49 @@ impl ModelGraph {
         Some(current)
     }
 
+    /// Find the element a qualified name refers to.
+    ///
+    /// The first segment is matched by name against root elements and registered
+    /// library packages; if neither matches, the library name index is consulted
+    /// so standard-library names resolve (call `ensure_library_index()` first).
+    /// Each following segment is matched against the `children_of` the element
+    /// found so far.
+    ///
+    /// When several siblings share a name, the one with the smallest `ElementId`
+    /// is chosen, so the result is deterministic.
+    ///
+    /// # Returns
+    ///
+    /// The element at the end of the path, or None if any segment fails to match
+    /// or the name is empty.
+    pub fn find_by_qualified_name(&self, qn: &QualifiedName) -> Option<&Element> {
+        let (first, rest) = qn.segments().split_first()?;
+
+        let top_level = self.roots().chain(self.library_package_elements());
+        let mut current = match Self::first_named(top_level, first) {
+            Some(element) => element,
+            None => self
+                .resolve_in_library(first)
+                .and_then(|id| self.elements.get(id))?,
+        };
+
+        for segment in rest {
+            current = Self::first_named(self.children_of(&current.id), segment)?;
+        }
+
+        Some(current)
+    }
+
+    /// Pick the element with the given name and the smallest id.
+    fn first_named<'a>(
+        candidates: impl Iterator<Item = &'a Element>,
+        name: &str,
+    ) -> Option<&'a Element> {
+        candidates
+            .filter(|e| e.name.as_deref() == Some(name))
+            .min_by(|a, b| a.id.cmp(&b.id))
+    }
+
     /// Resolve a path relative to a namespace.
     ///
     /// Similar to `resolve_qname` but starts from a specific namespace instead of root.
@@ -362,4 +406,63 @@ mod tests {
         let resolved = graph.resolve_path(&pkg_id, "SubPackage::PartDef").unwrap();
         assert_eq!(resolved.id, part_id);
     }
+
+    #[test]
+    fn find_by_qualified_name_full_path() {
+        let (graph, pkg_id, sub_id, part_id) = create_test_hierarchy();
+
+        let qn: QualifiedName = "TestPackage::SubPackage::PartDef".parse().unwrap();
+        assert_eq!(graph.find_by_qualified_name(&qn).unwrap().id, part_id);
+
+        let qn: QualifiedName = "TestPackage::SubPackage".parse().unwrap();
+        assert_eq!(graph.find_by_qualified_name(&qn).unwrap().id, sub_id);
+
+        let qn: QualifiedName = "TestPackage".parse().unwrap();
+        assert_eq!(graph.find_by_qualified_name(&qn).unwrap().id, pkg_id);
+    }
+
+    #[test]
+    fn find_by_qualified_name_missing_segment() {
+        let (graph, _, _, _) = create_test_hierarchy();
+
+        let qn: QualifiedName = "TestPackage::Missing".parse().unwrap();
+        assert!(graph.find_by_qualified_name(&qn).is_none());
+        assert!(graph.find_by_qualified_name(&QualifiedName::empty()).is_none());
+    }
+
+    #[test]
+    fn find_by_qualified_name_in_library() {
+        let mut graph = ModelGraph::new();
+
+        let lib = crate::ElementFactory::library_package("ScalarValues");
+        let lib_id = graph.add_library_package(lib);
+        let real = Element::new_with_kind(ElementKind::DataType).with_name("Real");
+        let real_id = graph.add_owned_element(real, lib_id, VisibilityKind::Public);
+        graph.ensure_library_index();
+
+        let qn: QualifiedName = "ScalarValues::Real".parse().unwrap();
+        assert_eq!(graph.find_by_qualified_name(&qn).unwrap().id, real_id);
+
+        // Library members resolve by their simple name through the library index
+        let qn: QualifiedName = "Real".parse().unwrap();
+        assert_eq!(graph.find_by_qualified_name(&qn).unwrap().id, real_id);
+    }
+
+    #[test]
+    fn find_by_qualified_name_picks_smallest_id_among_duplicates() {
+        let mut graph = ModelGraph::new();
+
+        let pkg = Element::new_with_kind(ElementKind::Package).with_name("Pkg");
+        let pkg_id = graph.add_element(pkg);
+
+        let mut dup_ids = Vec::new();
+        for _ in 0..5 {
+            let part = Element::new_with_kind(ElementKind::PartDefinition).with_name("Dup");
+            dup_ids.push(graph.add_owned_element(part, pkg_id.clone(), VisibilityKind::Public));
+        }
+        let expected = dup_ids.iter().min().unwrap();
+
+        let qn: QualifiedName = "Pkg::Dup".parse().unwrap();
+        assert_eq!(&graph.find_by_qualified_name(&qn).unwrap().id, expected);
+    }
 }
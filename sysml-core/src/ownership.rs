@@ -10,11 +10,46 @@
 //! - `owner_of`: Get the owner by following owning_membership
 //! - `ancestors`: Get all ancestors (owner chain to root)
 //! - `build_qualified_name`: Build qualified name from ownership chain
+//! - `qualified_name_of`: Cycle-safe qualified name lookup
+//! - `ownership_cycles`: Find cycles in the `owner` relation
+//! - `rename_element`: Rename an element and refresh dependent names
+//! - `find_references_to`: Find relationships and props pointing at an element
 
 use crate::membership::{props as membership_props, MembershipBuilder};
-use crate::{Element, ModelGraph, VisibilityKind};
+use crate::validation::ValidationError;
+use crate::{Element, ElementKind, ModelGraph, Value, VisibilityKind};
+use rustc_hash::FxHashSet;
 use sysml_id::{ElementId, QualifiedName};
 
+/// Everything in a graph that points at a given element.
+///
+/// Returned by [`ModelGraph::find_references_to`]. Structural props such as a
+/// membership's `memberElement` are included; callers that only care about
+/// textual references can filter on [`PropReference::key`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ElementReferences {
+    /// Relationships whose source or target is the element, sorted by id.
+    pub relationships: Vec<ElementId>,
+    /// Props (on elements or relationships) holding a `Value::Ref` to the element.
+    pub props: Vec<PropReference>,
+}
+
+impl ElementReferences {
+    /// Check if nothing refers to the element.
+    pub fn is_empty(&self) -> bool {
+        self.relationships.is_empty() && self.props.is_empty()
+    }
+}
+
+/// A property whose value (possibly nested in a list or map) refers to an element.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PropReference {
+    /// The element or relationship holding the property.
+    pub holder: ElementId,
+    /// The property key.
+    pub key: String,
+}
+
 impl ModelGraph {
     /// Create an OwningMembership to establish ownership between a namespace and an element.
     ///
@@ -75,11 +110,23 @@ impl ModelGraph {
         self.add_element(membership);
 
         // Update the owned element's owning_membership and owner
+        let mut previous_owner = None;
         if let Some(owned) = self.elements.get_mut(&owned_element_id) {
             owned.owning_membership = Some(membership_id.clone());
-            owned.owner = Some(namespace_id.clone());
+            previous_owner = owned.owner.replace(namespace_id.clone());
         }
 
+        // Keep children_of in sync with the new owner
+        if let Some(previous) = previous_owner {
+            if let Some(children) = self.owner_to_children.get_mut(&previous) {
+                children.remove(&owned_element_id);
+            }
+        }
+        self.owner_to_children
+            .entry(namespace_id.clone())
+            .or_default()
+            .insert(owned_element_id.clone());
+
         // Update the namespace_to_memberships index
         self.namespace_to_memberships
             .entry(namespace_id)
@@ -183,30 +230,84 @@ impl ModelGraph {
     /// # Returns
     ///
     /// The qualified name, or None if the element or any ancestor has no name.
+    /// See [`ModelGraph::qualified_name_of`].
     pub fn build_qualified_name(&self, element_id: &ElementId) -> Option<QualifiedName> {
-        let element = self.elements.get(element_id)?;
-        let element_name = element.name.as_ref()?;
-
-        // Collect ancestor names (in reverse order, root to element)
-        let ancestors = self.ancestors(element_id);
-        let mut segments: Vec<String> = Vec::with_capacity(ancestors.len() + 1);
-
-        // Ancestors are in order [parent, grandparent, ...], so reverse
-        for ancestor in ancestors.into_iter().rev() {
-            if let Some(name) = &ancestor.name {
-                segments.push(name.clone());
-            } else {
-                // Ancestor has no name, can't build qualified name
+        self.qualified_name_of(element_id)
+    }
+
+    /// Compute the fully qualified name of an element by walking its owner chain.
+    ///
+    /// Unlike the stored `qname` field, this is always derived from the current
+    /// ownership structure. Names are collected from the root down to the element.
+    ///
+    /// # Returns
+    ///
+    /// - `None` if the element does not exist
+    /// - `None` if the element or any ancestor is unnamed (anonymous elements
+    ///   have no qualified name, so the walk stops rather than skipping them)
+    /// - `None` if the owner chain contains a cycle
+    pub fn qualified_name_of(&self, id: &ElementId) -> Option<QualifiedName> {
+        let element = self.elements.get(id)?;
+        let mut segments = vec![element.name.clone()?];
+
+        let mut visited = FxHashSet::default();
+        visited.insert(id.clone());
+
+        let mut current_id = id.clone();
+        while let Some(owner) = self.owner_of(&current_id) {
+            if !visited.insert(owner.id.clone()) {
+                // Ownership cycle
                 return None;
             }
+            segments.push(owner.name.clone()?);
+            current_id = owner.id.clone();
         }
 
-        // Add the element's own name
-        segments.push(element_name.clone());
-
+        segments.reverse();
         Some(QualifiedName::from_segments(segments))
     }
 
+    /// Find all cycles in the `owner` relation.
+    ///
+    /// Each element has at most one owner, so every cycle is found by following
+    /// owners from each element until reaching a root, an already explored
+    /// element, or an element on the current path. Elements that merely lead
+    /// into a cycle are not reported as members.
+    ///
+    /// # Returns
+    ///
+    /// One entry per cycle, listing its members in owner order (each element is
+    /// owned by the next, and the last by the first), starting from the member
+    /// with the smallest id. Cycles are sorted by their first member.
+    pub fn ownership_cycles(&self) -> Vec<Vec<ElementId>> {
+        let mut cycles = Vec::new();
+        let mut explored: FxHashSet<ElementId> = FxHashSet::default();
+
+        for start in self.elements.keys() {
+            let mut path: Vec<ElementId> = Vec::new();
+            let mut current = Some(start.clone());
+
+            while let Some(id) = current {
+                if let Some(pos) = path.iter().position(|p| p == &id) {
+                    let mut cycle = path.split_off(pos);
+                    let min = (0..cycle.len()).min_by(|&a, &b| cycle[a].cmp(&cycle[b])).unwrap_or(0);
+                    cycle.rotate_left(min);
+                    cycles.push(cycle);
+                    break;
+                }
+                if !explored.insert(id.clone()) {
+                    // Reached a chain explored from an earlier start
+                    break;
+                }
+                current = self.elements.get(&id).and_then(|e| e.owner.clone());
+                path.push(id);
+            }
+        }
+
+        cycles.sort();
+        cycles
+    }
+
     /// Get the owning membership element for an element.
     ///
     /// # Returns
@@ -238,12 +339,128 @@ impl ModelGraph {
         }
         Some(self.ancestors(element_id).len())
     }
+
+    /// Rename an element, keeping derived names consistent.
+    ///
+    /// Updates the element's `name`, the `memberName` of its owning membership,
+    /// and every stored `qname` of the element and its descendants. Descendants
+    /// without a stored `qname` are left alone.
+    ///
+    /// # Errors
+    ///
+    /// - `UnknownElement` if the element does not exist
+    /// - `DuplicateName` if another owned member of the same namespace already
+    ///   has `new_name` (the graph is left unchanged)
+    pub fn rename_element(&mut self, id: &ElementId, new_name: &str) -> Result<(), ValidationError> {
+        let owner = match self.elements.get(id) {
+            Some(element) => element.owner.clone(),
+            None => return Err(ValidationError::unknown_element("name")),
+        };
+
+        if let Some(owner_id) = &owner {
+            let taken = self.children_of(owner_id).any(|sibling| {
+                sibling.id != *id
+                    && sibling.kind != ElementKind::Membership
+                    && !sibling.kind.is_subtype_of(ElementKind::Membership)
+                    && sibling.name.as_deref() == Some(new_name)
+            });
+            if taken {
+                return Err(ValidationError::duplicate_name("name", new_name));
+            }
+        }
+
+        if let Some(element) = self.elements.get_mut(id) {
+            element.name = Some(new_name.to_string());
+        }
+
+        let membership_id = self.owning_membership_of(id).map(|m| m.id.clone());
+        if let Some(membership_id) = membership_id {
+            if let Some(membership) = self.elements.get_mut(&membership_id) {
+                membership.set_prop(membership_props::MEMBER_NAME, new_name.to_string());
+            }
+        }
+
+        // Refresh stored qualified names for the renamed subtree
+        let mut visited = FxHashSet::default();
+        let mut stack = vec![id.clone()];
+        while let Some(current) = stack.pop() {
+            if !visited.insert(current.clone()) {
+                continue;
+            }
+            let old_qname = self.elements.get(&current).and_then(|e| e.qname.clone());
+            if let Some(old_qname) = old_qname {
+                let qname = self.qualified_name_of(&current);
+                self.unindex_qname(&old_qname, &current);
+                if let Some(qname) = &qname {
+                    self.index_qname(qname.clone(), &current);
+                }
+                if let Some(element) = self.elements.get_mut(&current) {
+                    element.qname = qname;
+                }
+            }
+            stack.extend(self.children_of(&current).map(|child| child.id.clone()));
+        }
+
+        // Library lookups are keyed by name
+        self.library_index_dirty = true;
+
+        Ok(())
+    }
+
+    /// Find everything that refers to an element.
+    ///
+    /// Collects relationships attached to the element and props on elements or
+    /// relationships whose value is, or contains, a `Value::Ref` to it. This is
+    /// what a rename refactoring needs to know to update references.
+    pub fn find_references_to(&self, id: &ElementId) -> ElementReferences {
+        let relationships = self
+            .relationships
+            .values()
+            .filter(|rel| rel.source == *id || rel.target == *id)
+            .map(|rel| rel.id.clone())
+            .collect();
+
+        let element_props = self
+            .elements
+            .values()
+            .map(|element| (&element.id, &element.props));
+        let relationship_props = self
+            .relationships
+            .values()
+            .map(|rel| (&rel.id, &rel.props));
+
+        let mut props: Vec<PropReference> = element_props
+            .chain(relationship_props)
+            .flat_map(|(holder, props)| {
+                props
+                    .iter()
+                    .filter(|(_, value)| value_refers_to(value, id))
+                    .map(move |(key, _)| PropReference {
+                        holder: holder.clone(),
+                        key: key.clone(),
+                    })
+            })
+            .collect();
+        props.sort();
+
+        ElementReferences { relationships, props }
+    }
+}
+
+/// Check whether a value is, or contains, a reference to `id`.
+fn value_refers_to(value: &Value, id: &ElementId) -> bool {
+    match value {
+        Value::Ref(target) => target == id,
+        Value::List(items) => items.iter().any(|item| value_refers_to(item, id)),
+        Value::Map(entries) => entries.values().any(|entry| value_refers_to(entry, id)),
+        _ => false,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Element, ElementKind, VisibilityKind};
+    use crate::{Element, ElementKind, ValidationErrorKind, VisibilityKind};
 
     #[test]
     fn create_owning_membership_basic() {
@@ -373,6 +590,96 @@ mod tests {
         assert!(graph.build_qualified_name(&part_id).is_none());
     }
 
+    #[test]
+    fn qualified_name_of_walks_owner_chain() {
+        let mut graph = ModelGraph::new();
+
+        let pkg = Element::new_with_kind(ElementKind::Package).with_name("Vehicle");
+        let pkg_id = graph.add_element(pkg);
+
+        let part = Element::new_with_kind(ElementKind::PartDefinition).with_name("Engine");
+        let part_id = graph.add_owned_element(part, pkg_id.clone(), VisibilityKind::Public);
+
+        let qname = graph.qualified_name_of(&part_id).unwrap();
+        assert_eq!(qname.to_string(), "Vehicle::Engine");
+        assert_eq!(graph.qualified_name_of(&pkg_id).unwrap().to_string(), "Vehicle");
+        assert!(graph.qualified_name_of(&ElementId::new_v4()).is_none());
+    }
+
+    #[test]
+    fn qualified_name_of_unnamed_element_returns_none() {
+        let mut graph = ModelGraph::new();
+
+        let pkg = Element::new_with_kind(ElementKind::Package).with_name("Pkg");
+        let pkg_id = graph.add_element(pkg);
+
+        let anon = Element::new_with_kind(ElementKind::PartUsage).with_owner(pkg_id);
+        let anon_id = graph.add_element(anon);
+
+        assert!(graph.qualified_name_of(&anon_id).is_none());
+    }
+
+    #[test]
+    fn qualified_name_of_detects_cycles() {
+        let mut graph = ModelGraph::new();
+
+        let a_id = ElementId::new_v4();
+        let b_id = ElementId::new_v4();
+        graph.add_element(
+            Element::new(a_id.clone(), ElementKind::Package)
+                .with_name("A")
+                .with_owner(b_id.clone()),
+        );
+        graph.add_element(
+            Element::new(b_id.clone(), ElementKind::Package)
+                .with_name("B")
+                .with_owner(a_id.clone()),
+        );
+
+        assert!(graph.qualified_name_of(&a_id).is_none());
+        assert!(graph.build_qualified_name(&b_id).is_none());
+    }
+
+    #[test]
+    fn ownership_cycles_none_for_tree() {
+        let mut graph = ModelGraph::new();
+
+        let pkg = Element::new_with_kind(ElementKind::Package).with_name("Pkg");
+        let pkg_id = graph.add_element(pkg);
+        let part = Element::new_with_kind(ElementKind::PartDefinition).with_name("Part");
+        graph.add_owned_element(part, pkg_id, VisibilityKind::Public);
+
+        assert!(graph.ownership_cycles().is_empty());
+    }
+
+    #[test]
+    fn ownership_cycles_reports_members() {
+        let mut graph = ModelGraph::new();
+
+        // a -> b -> c -> a, plus d -> a leading into the cycle, and a self-owned e
+        let ids: Vec<ElementId> = (0..5).map(|_| ElementId::new_v4()).collect();
+        let (a, b, c, d, e) = (&ids[0], &ids[1], &ids[2], &ids[3], &ids[4]);
+        for (id, owner) in [(a, b), (b, c), (c, a), (d, a), (e, e)] {
+            graph.add_element(
+                Element::new(id.clone(), ElementKind::Package).with_owner(owner.clone()),
+            );
+        }
+
+        let cycles = graph.ownership_cycles();
+        assert_eq!(cycles.len(), 2);
+
+        let three = cycles.iter().find(|c| c.len() == 3).unwrap();
+        assert!(!three.contains(d));
+        // Members are in owner order, starting from the smallest id
+        assert_eq!(&three[0], three.iter().min().unwrap());
+        for i in 0..3 {
+            let owner = graph.get_element(&three[i]).unwrap().owner.as_ref().unwrap();
+            assert_eq!(owner, &three[(i + 1) % 3]);
+        }
+
+        assert!(cycles.contains(&vec![e.clone()]));
+    }
+
     #[test]
     fn is_root_and_depth() {
         let mut graph = ModelGraph::new();
@@ -389,4 +696,105 @@ mod tests {
         assert_eq!(graph.depth_of(&pkg_id), Some(0));
         assert_eq!(graph.depth_of(&part_id), Some(1));
     }
+
+    #[test]
+    fn rename_element_updates_nested_qnames_and_member_name() {
+        let mut graph = ModelGraph::new();
+
+        // Vehicle::Powertrain::Engine, with Vehicle::Powertrain renamed to Drivetrain
+        let vehicle = Element::new_with_kind(ElementKind::Package).with_name("Vehicle");
+        let vehicle_id = graph.add_element(vehicle);
+        let powertrain = Element::new_with_kind(ElementKind::Package).with_name("Powertrain");
+        let powertrain_id = graph.add_owned_element(powertrain, vehicle_id.clone(), VisibilityKind::Public);
+        let engine = Element::new_with_kind(ElementKind::PartDefinition).with_name("Engine");
+        let engine_id = graph.add_owned_element(engine, powertrain_id.clone(), VisibilityKind::Public);
+        graph.compute_qualified_names();
+
+        graph.rename_element(&powertrain_id, "Drivetrain").unwrap();
+
+        let renamed = graph.get_element(&powertrain_id).unwrap();
+        assert_eq!(renamed.name.as_deref(), Some("Drivetrain"));
+        assert_eq!(renamed.qname.as_ref().unwrap().to_string(), "Vehicle::Drivetrain");
+        let engine = graph.get_element(&engine_id).unwrap();
+        assert_eq!(engine.qname.as_ref().unwrap().to_string(), "Vehicle::Drivetrain::Engine");
+        assert_eq!(graph.get_element(&vehicle_id).unwrap().qname.as_ref().unwrap().to_string(), "Vehicle");
+
+        let membership = graph.owning_membership_of(&powertrain_id).unwrap();
+        assert_eq!(membership.get_str(membership_props::MEMBER_NAME), Some("Drivetrain"));
+        assert_eq!(
+            graph.resolve_qname("Vehicle::Drivetrain::Engine").map(|e| e.id.clone()),
+            Some(engine_id.clone())
+        );
+        assert!(graph.resolve_qname("Vehicle::Powertrain").is_none());
+        assert_eq!(graph.get_by_qname_str("Vehicle::Drivetrain::Engine").unwrap().id, engine_id);
+        assert!(graph.get_by_qname_str("Vehicle::Powertrain::Engine").is_none());
+    }
+
+    #[test]
+    fn rename_element_rejects_sibling_duplicate() {
+        let mut graph = ModelGraph::new();
+
+        let pkg = Element::new_with_kind(ElementKind::Package).with_name("Pkg");
+        let pkg_id = graph.add_element(pkg);
+        let engine = Element::new_with_kind(ElementKind::PartDefinition).with_name("Engine");
+        graph.add_owned_element(engine, pkg_id.clone(), VisibilityKind::Public);
+        let wheel = Element::new_with_kind(ElementKind::PartDefinition).with_name("Wheel");
+        let wheel_id = graph.add_owned_element(wheel, pkg_id.clone(), VisibilityKind::Public);
+
+        // Same name in a different namespace is fine
+        let other = Element::new_with_kind(ElementKind::Package).with_name("Other");
+        let other_id = graph.add_element(other);
+        let nested = Element::new_with_kind(ElementKind::PartDefinition).with_name("Nested");
+        let nested_id = graph.add_owned_element(nested, other_id, VisibilityKind::Public);
+        assert!(graph.rename_element(&nested_id, "Engine").is_ok());
+
+        let err = graph.rename_element(&wheel_id, "Engine").unwrap_err();
+        assert_eq!(err.kind, ValidationErrorKind::DuplicateName { name: "Engine".to_string() });
+        assert_eq!(graph.get_element(&wheel_id).unwrap().name.as_deref(), Some("Wheel"));
+        let membership = graph.owning_membership_of(&wheel_id).unwrap();
+        assert_eq!(membership.get_str(membership_props::MEMBER_NAME), Some("Wheel"));
+
+        // Renaming to its own current name is not a conflict
+        assert!(graph.rename_element(&wheel_id, "Wheel").is_ok());
+
+        let missing = ElementId::new_v4();
+        let err = graph.rename_element(&missing, "X").unwrap_err();
+        assert_eq!(err.kind, ValidationErrorKind::UnknownElement);
+    }
+
+    #[test]
+    fn find_references_to_collects_relationships_and_nested_refs() {
+        use crate::{Relationship, RelationshipKind};
+
+        let mut graph = ModelGraph::new();
+
+        let pkg = Element::new_with_kind(ElementKind::Package).with_name("Pkg");
+        let pkg_id = graph.add_element(pkg);
+        let engine = Element::new_with_kind(ElementKind::PartDefinition).with_name("Engine");
+        let engine_id = graph.add_owned_element(engine, pkg_id.clone(), VisibilityKind::Public);
+
+        let mut usage = Element::new_with_kind(ElementKind::PartUsage).with_name("engine");
+        usage.set_prop("type", Value::List(vec![Value::Ref(engine_id.clone())]));
+        let usage_id = graph.add_owned_element(usage, pkg_id.clone(), VisibilityKind::Public);
+
+        let rel = Relationship::new(RelationshipKind::TypeOf, usage_id.clone(), engine_id.clone());
+        let rel_id = rel.id.clone();
+        graph.add_relationship(rel);
+
+        let refs = graph.find_references_to(&engine_id);
+        assert_eq!(refs.relationships, vec![rel_id]);
+        assert!(refs.props.contains(&PropReference {
+            holder: usage_id,
+            key: "type".to_string(),
+        }));
+        let membership_id = graph.owning_membership_of(&engine_id).unwrap().id.clone();
+        assert!(refs.props.contains(&PropReference {
+            holder: membership_id,
+            key: membership_props::MEMBER_ELEMENT.to_string(),
+        }));
+
+        let unreferenced = Element::new_with_kind(ElementKind::PartDefinition);
+        let unreferenced_id = graph.add_element(unreferenced);
+        assert!(graph.find_references_to(&unreferenced_id).is_empty());
+    }
 }
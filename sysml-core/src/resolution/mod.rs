@@ -5,7 +5,8 @@
 //!
 //! 1. **OWNED**: Local owned memberships of the namespace
 //! 2. **INHERITED**: Members via Specialization chain (for Types)
-//! 3. **IMPORTED**: Members from Import statements
+//! 3. **IMPORTED**: Members from Import statements, including the names the
+//!    imported namespaces re-export through their own `public` imports
 //! 4. **PARENT**: Walk up to parent namespace
 //! 5. **GLOBAL**: Root packages
 //! 6. **LIBRARY**: Standard library package members (implicit)
@@ -157,6 +158,16 @@ pub mod import_props {
     pub const IS_RECURSIVE: &str = "isRecursive";
     /// Whether 'all' keyword was used.
     pub const IMPORTS_ALL: &str = "importsAll";
+    /// The declared visibility; only `public` imports re-export their names.
+    pub const VISIBILITY: &str = "visibility";
+}
+
+/// Check if an element is an Import of any kind.
+fn is_import(element: &crate::Element) -> bool {
+    element.kind == ElementKind::Import
+        || element.kind == ElementKind::NamespaceImport
+        || element.kind == ElementKind::MembershipImport
+        || element.kind.is_subtype_of(ElementKind::Import)
 }
 
 /// Static map of primitive type aliases to their canonical names.
@@ -875,14 +886,8 @@ impl<'a> ResolutionContext<'a> {
                         );
                     } else {
                         // Membership import: import the specific element
-                        if let Some(target) = self.graph.get_element(&target_id) {
-                            if let Some(name) = &target.name {
-                                table.add_imported(
-                                    name.clone(),
-                                    target_id.clone(),
-                                    VisibilityKind::Public,
-                                );
-                            }
+                        if let Some(name) = self.membership_import_name(ref_name, &target_id) {
+                            table.add_imported(name, target_id.clone(), VisibilityKind::Public);
                         }
                     }
                 }
@@ -903,7 +908,11 @@ impl<'a> ResolutionContext<'a> {
             }
         }
 
-        // Cache miss - perform resolution
+        // Cache miss - perform resolution, marking the name as failed
+        // meanwhile so that circular re-exports terminate
+        self.import_cache
+            .borrow_mut()
+            .insert(ref_name.to_string(), None);
         let result = self.resolve_import_target_uncached(ref_name);
 
         // Cache the result (including None for negative caching)
@@ -936,27 +945,135 @@ impl<'a> ResolutionContext<'a> {
             .id
             .clone();
 
-        // Resolve each subsequent segment by checking owned members directly
+        // Resolve each subsequent segment among the members of the last
         for segment in segments.iter().skip(1) {
-            let mut found = false;
-            for member in self.graph.owned_members(&current) {
-                if member
-                    .name
-                    .as_ref()
-                    .map(|n| Self::names_match(n, segment))
-                    .unwrap_or(false)
-                {
-                    current = member.id.clone();
-                    found = true;
-                    break;
+            current = self.find_import_member(&current, segment, &mut HashSet::new())?;
+        }
+
+        Some(current)
+    }
+
+    /// Find a member of a namespace by name, for an import path.
+    ///
+    /// Owned members come first, then aliases, then the names that the
+    /// namespace re-exports through its public imports.
+    fn find_import_member(
+        &self,
+        namespace_id: &ElementId,
+        name: &str,
+        visited: &mut HashSet<ElementId>,
+    ) -> Option<ElementId> {
+        if !visited.insert(namespace_id.clone()) {
+            return None;
+        }
+
+        for member in self.graph.owned_members(namespace_id) {
+            if member.name.as_ref().is_some_and(|n| Self::names_match(n, name)) {
+                return Some(member.id.clone());
+            }
+        }
+
+        for membership in self.graph.memberships(namespace_id) {
+            if let Some(view) = MembershipView::try_from_element(membership) {
+                if view.member_name().is_some_and(|n| Self::names_match(n, name)) {
+                    if let Some(member_id) = view.member_element() {
+                        return Some(member_id.clone());
+                    }
                 }
             }
-            if !found {
-                return None;
+        }
+
+        for import in self.public_imports(namespace_id) {
+            let ref_name = match import.props.get(import_props::IMPORTED_REFERENCE).and_then(|v| v.as_str()) {
+                Some(ref_name) => ref_name,
+                None => continue,
+            };
+            let target_id = match self.resolve_import_target(ref_name) {
+                Some(target_id) => target_id,
+                None => continue,
+            };
+            if Self::is_namespace_import(import) {
+                if let Some(found) = self.find_import_member(&target_id, name, visited) {
+                    return Some(found);
+                }
+            } else if self
+                .membership_import_name(ref_name, &target_id)
+                .is_some_and(|n| Self::names_match(&n, name))
+            {
+                return Some(target_id);
             }
         }
 
-        Some(current)
+        None
+    }
+
+    /// The imports that a namespace re-exports: those declared `public`.
+    fn public_imports(&self, namespace_id: &ElementId) -> Vec<&'a crate::Element> {
+        self.graph
+            .owned_members(namespace_id)
+            .filter(|e| {
+                is_import(e) && e.props.get(import_props::VISIBILITY).and_then(|v| v.as_str()) == Some("public")
+            })
+            .collect()
+    }
+
+    /// Check if an import brings in a namespace's members (`::*` or `::**`).
+    fn is_namespace_import(import: &crate::Element) -> bool {
+        [import_props::IS_NAMESPACE, import_props::IS_RECURSIVE]
+            .iter()
+            .any(|key| import.props.get(*key).and_then(|v| v.as_bool()).unwrap_or(false))
+    }
+
+    /// The name a membership import brings in: the target's own name, or
+    /// the alias it was imported through.
+    fn membership_import_name(&self, ref_name: &str, target_id: &ElementId) -> Option<String> {
+        let last = Self::parse_qualified_name_segments(ref_name).last().copied()?;
+        match self.graph.get_element(target_id).and_then(|t| t.name.as_deref()) {
+            Some(name) if Self::names_match(name, last) => Some(name.to_string()),
+            _ => Some(last.trim_matches('\'').to_string()),
+        }
+    }
+
+    /// Import the names a namespace re-exports through its public imports.
+    ///
+    /// Re-exported names never replace a name already in the table, so a
+    /// namespace's own members win over what it re-exports, and the first
+    /// re-export of a name wins over later ones.
+    fn import_reexports(
+        &self,
+        namespace_id: &ElementId,
+        table: &mut ScopeTable,
+        visited: &mut HashSet<ElementId>,
+    ) {
+        let mut reexported = ScopeTable::new();
+        for import in self.public_imports(namespace_id) {
+            if !visited.insert(import.id.clone()) {
+                continue;
+            }
+            let ref_name = match import.props.get(import_props::IMPORTED_REFERENCE).and_then(|v| v.as_str()) {
+                Some(ref_name) => ref_name,
+                None => continue,
+            };
+            let target_id = match self.resolve_import_target(ref_name) {
+                Some(target_id) => target_id,
+                None => continue,
+            };
+            if Self::is_namespace_import(import) {
+                let recursive = import
+                    .props
+                    .get(import_props::IS_RECURSIVE)
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                self.import_namespace_members(&target_id, &mut reexported, recursive, visited);
+            } else if let Some(name) = self.membership_import_name(ref_name, &target_id) {
+                if reexported.lookup_imported(&name).is_none() {
+                    reexported.add_imported(name, target_id, VisibilityKind::Public);
+                }
+            }
+        }
+        for (name, entry) in reexported.imported {
+            table.imported.entry(name).or_insert(entry);
+        }
     }
 
     /// Import all public members from a namespace.
@@ -1008,6 +1125,8 @@ impl<'a> ResolutionContext<'a> {
                 }
             }
         }
+
+        self.import_reexports(namespace_id, table, visited);
     }
 
     /// Expand inherited members for a Type and add them to the scope table.
@@ -1995,6 +2114,9 @@ impl ModelGraph {
 pub fn resolve_references(graph: &mut ModelGraph) -> ResolutionResult {
     let mut result = ResolutionResult::new();
 
+    // Aliases first, so that names bound by them are visible to both passes
+    resolve_aliases(graph, &HashSet::new());
+
     // Collect elements that need resolution (to avoid borrowing issues)
     let elements_to_resolve: Vec<(ElementId, ElementKind)> = graph
         .elements
@@ -2321,6 +2443,51 @@ pub fn resolve_references(graph: &mut ModelGraph) -> ResolutionResult {
     result
 }
 
+/// Resolve the targets of alias memberships (`alias X for A::B;`).
+///
+/// An alias is a non-owning Membership of its namespace whose member element
+/// is still a name. Once its `memberElement` is set, the scope tables bind
+/// the alias's `memberName` like any other member, and imports of the
+/// namespace carry it along. Since an alias may name another alias, this
+/// repeats until a round resolves nothing, which also stops circular
+/// aliases; those are left for the main passes to report.
+fn resolve_aliases(graph: &mut ModelGraph, exclude_ids: &HashSet<ElementId>) {
+    loop {
+        let mut updates: Vec<(ElementId, ElementId)> = Vec::new();
+        {
+            let ctx_graph = &*graph;
+            let mut ctx = ResolutionContext::new(ctx_graph);
+            for element in ctx_graph.elements.values() {
+                if element.kind != ElementKind::Membership
+                    || exclude_ids.contains(&element.id)
+                    || element.props.contains_key(resolved_props::MEMBER_ELEMENT)
+                {
+                    continue;
+                }
+                let target = match element.props.get(unresolved_props::MEMBER_ELEMENT).and_then(|v| v.as_str()) {
+                    Some(target) => target,
+                    None => continue,
+                };
+                let scope_id = MembershipView::try_from_element(element)
+                    .and_then(|view| view.membership_owning_namespace().cloned())
+                    .unwrap_or_else(|| element.id.clone());
+                if let Some(resolved_id) = ctx.resolve_qualified_name(&scope_id, target) {
+                    updates.push((element.id.clone(), resolved_id));
+                }
+            }
+        }
+
+        if updates.is_empty() {
+            break;
+        }
+        for (element_id, resolved_id) in updates {
+            if let Some(element) = graph.elements.get_mut(&element_id) {
+                element.set_prop(resolved_props::MEMBER_ELEMENT, crate::Value::Ref(resolved_id));
+            }
+        }
+    }
+}
+
 /// Resolve all cross-references in a model graph, excluding specified elements.
 ///
 /// This is useful when resolving user-defined elements while excluding library
@@ -2340,6 +2507,8 @@ pub fn resolve_references_excluding(
 ) -> ResolutionResult {
     let mut result = ResolutionResult::new();
 
+    resolve_aliases(graph, exclude_ids);
+
     // Collect elements that need resolution, excluding specified IDs
     let elements_to_resolve: Vec<(ElementId, ElementKind)> = graph
         .elements
@@ -3951,6 +4120,114 @@ mod tests {
         assert_eq!(resolved_type.and_then(|v| v.as_ref()), Some(&type_id));
     }
 
+    /// Add `alias <name> for <target>;` to a namespace.
+    fn create_alias(graph: &mut ModelGraph, namespace_id: &ElementId, name: &str, target: &str) -> ElementId {
+        use crate::MembershipBuilder;
+
+        let mut alias = MembershipBuilder::new()
+            .owning_namespace(namespace_id.clone())
+            .member_name(name)
+            .build();
+        alias.set_prop(unresolved_props::MEMBER_ELEMENT, target);
+        graph.add_element(alias)
+    }
+
+    /// Add an unresolved FeatureTyping of `type_ref` to a namespace.
+    fn create_typing(graph: &mut ModelGraph, owner_id: &ElementId, type_ref: &str) -> ElementId {
+        let typing = Element::new_with_kind(ElementKind::FeatureTyping).with_prop(unresolved_props::TYPE, type_ref);
+        graph.add_owned_element(typing, owner_id.clone(), VisibilityKind::Public)
+    }
+
+    fn resolved_type(graph: &ModelGraph, typing_id: &ElementId) -> Option<ElementId> {
+        graph.get_element(typing_id)?.get_ref(resolved_props::TYPE).cloned()
+    }
+
+    #[test]
+    fn alias_to_nested_definition_resolves() {
+        let mut graph = ModelGraph::new();
+        let lib_id = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Lib"));
+        let inner = Element::new_with_kind(ElementKind::Package).with_name("Inner");
+        let inner_id = graph.add_owned_element(inner, lib_id, VisibilityKind::Public);
+        let deep = Element::new_with_kind(ElementKind::PartDefinition).with_name("Deep");
+        let deep_id = graph.add_owned_element(deep, inner_id, VisibilityKind::Public);
+
+        let user_id = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("User"));
+        create_alias(&mut graph, &user_id, "D", "Lib::Inner::Deep");
+        // An alias of an alias, declared before the alias it names
+        let chained = create_alias(&mut graph, &user_id, "E", "User::D");
+        let by_alias = create_typing(&mut graph, &user_id, "D");
+        let by_chain = create_typing(&mut graph, &user_id, "E");
+        let qualified = create_typing(&mut graph, &user_id, "User::D");
+
+        let result = resolve_references(&mut graph);
+        assert_eq!(result.unresolved_count, 0, "{:?}", result.diagnostics);
+        assert_eq!(resolved_type(&graph, &by_alias), Some(deep_id.clone()));
+        assert_eq!(resolved_type(&graph, &by_chain), Some(deep_id.clone()));
+        assert_eq!(resolved_type(&graph, &qualified), Some(deep_id.clone()));
+        assert_eq!(graph.get_element(&chained).and_then(|e| e.get_ref(resolved_props::MEMBER_ELEMENT)), Some(&deep_id));
+    }
+
+    #[test]
+    fn two_hop_reexport_resolves() {
+        let mut graph = ModelGraph::new();
+        let base_id = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Base"));
+        let engine = Element::new_with_kind(ElementKind::PartDefinition).with_name("Engine");
+        let engine_id = graph.add_owned_element(engine, base_id, VisibilityKind::Public);
+
+        // Base -> A -> B by public imports, and C importing Base privately
+        for (name, imported) in [("A", "Base"), ("B", "A"), ("C", "Base")] {
+            let pkg_id = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name(name));
+            let import_id = create_import(&mut graph, &pkg_id, imported, true, false);
+            if name != "C" {
+                graph.elements.get_mut(&import_id).unwrap().set_prop(import_props::VISIBILITY, "public");
+            }
+        }
+
+        let consumer_id = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Consumer"));
+        create_import(&mut graph, &consumer_id, "B", true, false);
+        let via_namespace = create_typing(&mut graph, &consumer_id, "Engine");
+        let single_id = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Single"));
+        create_import(&mut graph, &single_id, "B::Engine", false, false);
+        let via_membership = create_typing(&mut graph, &single_id, "Engine");
+        let private_id = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Private"));
+        create_import(&mut graph, &private_id, "C", true, false);
+        let not_reexported = create_typing(&mut graph, &private_id, "Engine");
+
+        let result = resolve_references(&mut graph);
+        assert_eq!(resolved_type(&graph, &via_namespace), Some(engine_id.clone()));
+        assert_eq!(resolved_type(&graph, &via_membership), Some(engine_id));
+        assert_eq!(resolved_type(&graph, &not_reexported), None);
+        assert_eq!(result.unresolved_count, 1);
+    }
+
+    #[test]
+    fn circular_imports_and_aliases_terminate() {
+        let mut graph = ModelGraph::new();
+        let a_id = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("A"));
+        let b_id = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("B"));
+        let y = Element::new_with_kind(ElementKind::PartDefinition).with_name("Y");
+        let y_id = graph.add_owned_element(y, b_id.clone(), VisibilityKind::Public);
+        for (pkg_id, imported) in [(&a_id, "B"), (&b_id, "A")] {
+            let import_id = create_import(&mut graph, pkg_id, imported, true, false);
+            graph.elements.get_mut(&import_id).unwrap().set_prop(import_props::VISIBILITY, "public");
+        }
+        create_alias(&mut graph, &a_id, "P", "A::Q");
+        create_alias(&mut graph, &a_id, "Q", "A::P");
+
+        let consumer_id = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Consumer"));
+        create_import(&mut graph, &consumer_id, "A", true, false);
+        let found = create_typing(&mut graph, &consumer_id, "Y");
+        let qualified = create_typing(&mut graph, &consumer_id, "A::Y");
+        let missing = create_typing(&mut graph, &consumer_id, "Missing");
+        let circular = create_typing(&mut graph, &consumer_id, "P");
+
+        resolve_references(&mut graph);
+        assert_eq!(resolved_type(&graph, &found), Some(y_id.clone()));
+        assert_eq!(resolved_type(&graph, &qualified), Some(y_id));
+        assert_eq!(resolved_type(&graph, &missing), None);
+        assert_eq!(resolved_type(&graph, &circular), None);
+    }
+
     #[test]
     fn resolve_references_multiple_elements() {
         use crate::Value;
@@ -4233,11 +4510,8 @@ mod tests {
         let user_id = user_graph.add_element(user_pkg);
 
         // Merge library into user graph
-        let count = user_graph.merge(lib_graph, true);
-        assert!(count > 0);
-
-        // Rebuild indexes after merge
-        user_graph.rebuild_indexes();
+        let report = user_graph.merge(lib_graph, true, crate::MergePolicy::ReplaceExisting).unwrap();
+        assert!(report.merged > 0);
 
         // Verify library package was registered
         assert!(user_graph.is_library_package(&base_id));
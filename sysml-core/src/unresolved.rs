@@ -0,0 +1,218 @@
+//! An audit of the references left unresolved in a graph.
+//!
+//! The parser stores each cross-reference as an `unresolved_*` prop holding
+//! the name as written, and name resolution adds the resolved prop next to
+//! it. A reference whose resolved prop is still missing is left over. Unlike
+//! the diagnostics of a resolution pass, the audit reads the graph as it
+//! stands, so it can be run at any time, on any graph.
+//!
+//! ## Usage
+//!
+//! ```ignore
+//! for unresolved in graph.unresolved_references() {
+//!     let candidates = similar_names(&graph, &unresolved.reference);
+//!     eprintln!("{}: did you mean {}?", unresolved.reference, candidates.join(", "));
+//! }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::resolution::{resolved_props, unresolved_props};
+use crate::{ElementId, ModelGraph, Span};
+
+/// The most names [`similar_names`] returns.
+const MAX_SUGGESTIONS: usize = 5;
+/// The largest edit distance at which [`similar_names`] counts a name as similar.
+const MAX_DISTANCE: usize = 2;
+
+/// A reference that name resolution has not resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedRef {
+    /// The element holding the reference.
+    pub element_id: ElementId,
+    /// The `unresolved_*` prop holding the reference.
+    pub property: String,
+    /// The reference as written.
+    pub reference: String,
+    /// The first span of the element, if any.
+    pub span: Option<Span>,
+}
+
+/// The resolved prop for an `unresolved_*` prop, if it has one.
+///
+/// Unresolved values are expressions rather than names, so they have none.
+fn resolved_key(property: &str) -> Option<&str> {
+    match property {
+        unresolved_props::SOURCES => Some(resolved_props::SOURCES),
+        unresolved_props::TARGETS => Some(resolved_props::TARGETS),
+        unresolved_props::VALUE => None,
+        _ => property.strip_prefix("unresolved_"),
+    }
+}
+
+impl ModelGraph {
+    /// List the references in the graph that are still unresolved.
+    ///
+    /// A reference counts as unresolved while its element has the
+    /// `unresolved_*` prop but not the matching resolved one. Lists, such as
+    /// the sources of a dependency, count as resolved once any entry is,
+    /// since resolution keeps one resolved entry. References are ordered by
+    /// element id, then prop.
+    pub fn unresolved_references(&self) -> Vec<UnresolvedRef> {
+        let mut references = Vec::new();
+        for element in self.elements.values() {
+            for (property, value) in &element.props {
+                let resolved = match resolved_key(property) {
+                    Some(resolved) => resolved,
+                    None => continue,
+                };
+                if element.props.contains_key(resolved) {
+                    continue;
+                }
+                let names: Vec<&str> = match value.as_list() {
+                    Some(list) => list.iter().filter_map(|entry| entry.as_str()).collect(),
+                    None => value.as_str().into_iter().collect(),
+                };
+                for name in names {
+                    references.push(UnresolvedRef {
+                        element_id: element.id.clone(),
+                        property: property.clone(),
+                        reference: name.to_string(),
+                        span: element.spans.first().cloned(),
+                    });
+                }
+            }
+        }
+        references.sort_by(|a, b| (&a.element_id, &a.property).cmp(&(&b.element_id, &b.property)));
+        references
+    }
+
+    /// Count the unresolved references in the graph by `unresolved_*` prop.
+    pub fn unresolved_count_by_prop(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for reference in self.unresolved_references() {
+            *counts.entry(reference.property).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+/// Names in the graph close to the last segment of a reference.
+///
+/// Returns up to five element names within an edit distance of two of the
+/// segment after the last `::` or `.`, closest first and then in name order.
+pub fn similar_names(graph: &ModelGraph, reference: &str) -> Vec<String> {
+    let target = reference.rsplit("::").next().unwrap_or(reference);
+    let target = target.rsplit('.').next().unwrap_or(target).trim_matches('\'');
+
+    let mut candidates = BTreeSet::new();
+    for element in graph.elements.values() {
+        if let Some(name) = &element.name {
+            let distance = edit_distance(target, name);
+            if distance <= MAX_DISTANCE {
+                candidates.insert((distance, name.as_str()));
+            }
+        }
+    }
+
+    // A name shared by several elements is only suggested once
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// The Levenshtein distance between two strings, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = Vec::with_capacity(b.len() + 1);
+        current.push(i + 1);
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Element, ElementKind, Value, VisibilityKind};
+
+    /// A package with `Engine` and `Wheel` definitions and a usage typed by `engine_type`.
+    fn graph(engine_type: &str) -> (ModelGraph, ElementId) {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Vehicles"));
+        for name in ["Engine", "Wheel"] {
+            let def = Element::new_with_kind(ElementKind::PartDefinition).with_name(name);
+            graph.add_owned_element(def, pkg.clone(), VisibilityKind::Public);
+        }
+        let usage = Element::new_with_kind(ElementKind::PartUsage)
+            .with_name("engine")
+            .with_prop(unresolved_props::TYPE, engine_type)
+            .with_span(Span::new("car.sysml", 40, 60));
+        let usage = graph.add_owned_element(usage, pkg, VisibilityKind::Public);
+        (graph, usage)
+    }
+
+    #[test]
+    fn leftover_references_are_listed_with_spans() {
+        let (mut graph, usage) = graph("Engin");
+        let dependency = Element::new_with_kind(ElementKind::Dependency).with_prop(
+            unresolved_props::SOURCES,
+            Value::List(vec![Value::from("A"), Value::from("B")]),
+        );
+        let dependency = graph.add_element(dependency);
+
+        let references = graph.unresolved_references();
+        assert_eq!(references.len(), 3);
+        let typing = references.iter().find(|r| r.element_id == usage).unwrap();
+        assert_eq!(typing.property, unresolved_props::TYPE);
+        assert_eq!(typing.reference, "Engin");
+        assert_eq!(typing.span, Some(Span::new("car.sysml", 40, 60)));
+        let sources: Vec<&str> = references
+            .iter()
+            .filter(|r| r.element_id == dependency)
+            .map(|r| r.reference.as_str())
+            .collect();
+        assert_eq!(sources, ["A", "B"]);
+
+        let counts = graph.unresolved_count_by_prop();
+        assert_eq!(counts.get(unresolved_props::TYPE), Some(&1));
+        assert_eq!(counts.get(unresolved_props::SOURCES), Some(&2));
+    }
+
+    #[test]
+    fn resolved_references_and_values_are_not_listed() {
+        let (mut graph, usage) = graph("Engine");
+        let target = graph.elements.keys().next().unwrap().clone();
+        graph.elements.get_mut(&usage).unwrap().set_prop(resolved_props::TYPE, Value::Ref(target));
+        let valued = Element::new_with_kind(ElementKind::AttributeUsage).with_prop(unresolved_props::VALUE, "1 + x");
+        graph.add_element(valued);
+
+        assert!(graph.unresolved_references().is_empty());
+        assert!(graph.unresolved_count_by_prop().is_empty());
+    }
+
+    #[test]
+    fn one_character_typos_suggest_the_intended_name() {
+        let (graph, _) = graph("Engin");
+        assert_eq!(similar_names(&graph, "Engin"), ["Engine", "engine"]);
+        assert_eq!(similar_names(&graph, "Vehicles::Wheal"), ["Wheel"]);
+        assert!(similar_names(&graph, "Transmission").is_empty());
+    }
+
+    #[test]
+    fn edit_distance_counts_characters() {
+        assert_eq!(edit_distance("Engine", "Engine"), 0);
+        assert_eq!(edit_distance("Engin", "Engine"), 1);
+        assert_eq!(edit_distance("Wheel", "Wheat"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("Größe", "Grösse"), 2);
+    }
+}
@@ -5,6 +5,9 @@
 
 use std::fmt;
 
+use crate::{ElementId, ElementKind, ModelGraph, RelationshipKind};
+use sysml_span::Diagnostic;
+
 /// A validation error for an element property.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ValidationError {
@@ -61,6 +64,114 @@ impl ValidationError {
             kind: ValidationErrorKind::ReadOnly,
         }
     }
+
+    /// Create an error for a name that is already taken by a sibling in the same namespace.
+    pub fn duplicate_name(property: impl Into<String>, name: impl Into<String>) -> Self {
+        ValidationError {
+            property: property.into(),
+            kind: ValidationErrorKind::DuplicateName { name: name.into() },
+        }
+    }
+
+    /// Create an error for an operation on an element that is not in the graph.
+    pub fn unknown_element(property: impl Into<String>) -> Self {
+        ValidationError {
+            property: property.into(),
+            kind: ValidationErrorKind::UnknownElement,
+        }
+    }
+
+    /// Create an error for a relationship endpoint that is not in the graph.
+    pub fn dangling_endpoint(property: impl Into<String>, id: ElementId) -> Self {
+        ValidationError {
+            property: property.into(),
+            kind: ValidationErrorKind::DanglingEndpoint { id },
+        }
+    }
+
+    /// Create an error for a relationship endpoint of the wrong element kind.
+    pub fn endpoint_kind_mismatch(
+        property: impl Into<String>,
+        relationship: RelationshipKind,
+        expected: ElementKind,
+        got: ElementKind,
+    ) -> Self {
+        ValidationError {
+            property: property.into(),
+            kind: ValidationErrorKind::EndpointKindMismatch {
+                relationship,
+                expected,
+                got,
+            },
+        }
+    }
+
+    /// Create an error for a feature whose multiplicity bounds are
+    /// negative or inverted.
+    pub fn invalid_multiplicity(element: ElementId, lower: i64, upper: Option<i64>) -> Self {
+        ValidationError {
+            property: "multiplicity".to_string(),
+            kind: ValidationErrorKind::InvalidMultiplicity { element, lower, upper },
+        }
+    }
+
+    /// Create an error for a feature whose multiplicity is not within that
+    /// of a feature it redefines or subsets.
+    pub fn multiplicity_not_within(
+        element: ElementId,
+        bounds: (i64, Option<i64>),
+        general: ElementId,
+        general_bounds: (i64, Option<i64>),
+    ) -> Self {
+        ValidationError {
+            property: "multiplicity".to_string(),
+            kind: ValidationErrorKind::MultiplicityNotWithin {
+                element,
+                bounds,
+                general,
+                general_bounds,
+            },
+        }
+    }
+
+    /// The element the error is about, for errors found by validating a
+    /// whole graph.
+    pub fn element(&self) -> Option<&ElementId> {
+        match &self.kind {
+            ValidationErrorKind::InvalidMultiplicity { element, .. }
+            | ValidationErrorKind::MultiplicityNotWithin { element, .. } => Some(element),
+            _ => None,
+        }
+    }
+
+    /// Convert this error into a Diagnostic located at the element's span,
+    /// with the general feature of a multiplicity error as a related location.
+    pub fn to_diagnostic_with_graph(&self, graph: &ModelGraph) -> Diagnostic {
+        let mut diagnostic = Diagnostic::from(self.clone());
+        if let Some(span) = self.element().and_then(|id| graph.get_element(id)?.spans.first()) {
+            diagnostic = diagnostic.with_span(span.clone());
+        }
+        if let ValidationErrorKind::MultiplicityNotWithin { general, general_bounds, .. } = &self.kind {
+            if let Some(element) = graph.get_element(general) {
+                if let Some(span) = element.spans.first() {
+                    let name = element.name.as_deref().unwrap_or("feature");
+                    diagnostic = diagnostic.with_related(
+                        span.clone(),
+                        format!("'{}' has multiplicity {}", name, format_bounds(*general_bounds)),
+                    );
+                }
+            }
+        }
+        diagnostic
+    }
+}
+
+/// Format multiplicity bounds as `[lower..upper]`, with `*` for unbounded.
+fn format_bounds((lower, upper): (i64, Option<i64>)) -> String {
+    match upper {
+        Some(upper) => format!("[{}..{}]", lower, upper),
+        None => format!("[{}..*]", lower),
+    }
 }
 
 impl fmt::Display for ValidationError {
@@ -89,6 +200,58 @@ impl fmt::Display for ValidationError {
             ValidationErrorKind::ReadOnly => {
                 write!(f, "property '{}' is read-only", self.property)
             }
+            ValidationErrorKind::DuplicateName { name } => {
+                write!(
+                    f,
+                    "property '{}' value '{}' is already used in this namespace",
+                    self.property, name
+                )
+            }
+            ValidationErrorKind::UnknownElement => {
+                write!(f, "cannot set property '{}' on an unknown element", self.property)
+            }
+            ValidationErrorKind::DanglingEndpoint { id } => {
+                write!(f, "property '{}' refers to missing element {}", self.property, id)
+            }
+            ValidationErrorKind::EndpointKindMismatch {
+                relationship,
+                expected,
+                got,
+            } => {
+                write!(
+                    f,
+                    "property '{}' of a {} relationship must be a {}, got {}",
+                    self.property,
+                    relationship,
+                    expected.as_str(),
+                    got.as_str()
+                )
+            }
+            ValidationErrorKind::InvalidMultiplicity { element, lower, upper } => {
+                write!(
+                    f,
+                    "property '{}' of {} has invalid bounds {}",
+                    self.property,
+                    element,
+                    format_bounds((*lower, *upper))
+                )
+            }
+            ValidationErrorKind::MultiplicityNotWithin {
+                element,
+                bounds,
+                general,
+                general_bounds,
+            } => {
+                write!(
+                    f,
+                    "property '{}' of {}: {} is not within {} of {}",
+                    self.property,
+                    element,
+                    format_bounds(*bounds),
+                    format_bounds(*general_bounds),
+                    general
+                )
+            }
         }
     }
 }
@@ -103,6 +266,13 @@ impl std::error::Error for ValidationError {}
 /// - V003: MinCardinality
 /// - V004: MaxCardinality
 /// - V005: ReadOnly
+/// - V006: retired; duplicate names are reported as
+///   `StructuralError::DuplicateName` (E009), so DuplicateName maps to E009
+/// - V007: UnknownElement
+/// - V008: DanglingEndpoint
+/// - V009: EndpointKindMismatch
+/// - V010: InvalidMultiplicity
+/// - V011: MultiplicityNotWithin
 impl From<ValidationError> for sysml_span::Diagnostic {
     fn from(error: ValidationError) -> Self {
         let code = match &error.kind {
@@ -111,6 +281,12 @@ impl From<ValidationError> for sysml_span::Diagnostic {
             ValidationErrorKind::MinCardinality => "V003",
             ValidationErrorKind::MaxCardinality => "V004",
             ValidationErrorKind::ReadOnly => "V005",
+            ValidationErrorKind::DuplicateName { .. } => "E009",
+            ValidationErrorKind::UnknownElement => "V007",
+            ValidationErrorKind::DanglingEndpoint { .. } => "V008",
+            ValidationErrorKind::EndpointKindMismatch { .. } => "V009",
+            ValidationErrorKind::InvalidMultiplicity { .. } => "V010",
+            ValidationErrorKind::MultiplicityNotWithin { .. } => "V011",
         };
 
         sysml_span::Diagnostic::error(format!("{}: {}", error.property, error.kind))
@@ -136,6 +312,49 @@ pub enum ValidationErrorKind {
     MaxCardinality,
     /// A read-only property was modified.
     ReadOnly,
+    /// A name is already used by another member of the same namespace.
+    DuplicateName {
+        /// The conflicting name.
+        name: String,
+    },
+    /// The element being modified does not exist.
+    UnknownElement,
+    /// A relationship endpoint refers to an element that does not exist.
+    DanglingEndpoint {
+        /// The missing element.
+        id: ElementId,
+    },
+    /// A relationship endpoint is not of the kind its relationship requires.
+    EndpointKindMismatch {
+        /// The relationship being added.
+        relationship: RelationshipKind,
+        /// The required element kind (or a supertype of the allowed kinds).
+        expected: ElementKind,
+        /// The actual element kind.
+        got: ElementKind,
+    },
+    /// A feature's multiplicity has a negative lower bound or a lower bound
+    /// above its upper bound.
+    InvalidMultiplicity {
+        /// The feature.
+        element: ElementId,
+        /// The lower bound.
+        lower: i64,
+        /// The upper bound, `None` if unbounded.
+        upper: Option<i64>,
+    },
+    /// A feature's multiplicity is not within that of a feature it
+    /// redefines or subsets.
+    MultiplicityNotWithin {
+        /// The redefining or subsetting feature.
+        element: ElementId,
+        /// Its bounds, with `None` as an unbounded upper.
+        bounds: (i64, Option<i64>),
+        /// The redefined or subsetted feature.
+        general: ElementId,
+        /// Its bounds.
+        general_bounds: (i64, Option<i64>),
+    },
 }
 
 
@@ -157,6 +376,43 @@ impl fmt::Display for ValidationErrorKind {
             ValidationErrorKind::ReadOnly => {
                 write!(f, "read-only property")
             }
+            ValidationErrorKind::DuplicateName { name } => {
+                write!(f, "duplicate name '{}'", name)
+            }
+            ValidationErrorKind::UnknownElement => {
+                write!(f, "unknown element")
+            }
+            ValidationErrorKind::DanglingEndpoint { id } => {
+                write!(f, "missing element {}", id)
+            }
+            ValidationErrorKind::EndpointKindMismatch {
+                relationship,
+                expected,
+                got,
+            } => {
+                write!(
+                    f,
+                    "{} relationship requires {}, got {}",
+                    relationship,
+                    expected.as_str(),
+                    got.as_str()
+                )
+            }
+            ValidationErrorKind::InvalidMultiplicity { lower, upper, .. } => {
+                write!(f, "invalid bounds {}", format_bounds((*lower, *upper)))
+            }
+            ValidationErrorKind::MultiplicityNotWithin {
+                bounds,
+                general_bounds,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{} is not within {} of the general feature",
+                    format_bounds(*bounds),
+                    format_bounds(*general_bounds)
+                )
+            }
         }
     }
 }
@@ -278,14 +534,29 @@ mod tests {
             ValidationError::min_cardinality("prop3"),
             ValidationError::max_cardinality("prop4"),
             ValidationError::read_only("prop5"),
+            ValidationError::duplicate_name("name", "Engine"),
+            ValidationError::unknown_element("name"),
+            ValidationError::dangling_endpoint("source", ElementId::new_v4()),
+            ValidationError::endpoint_kind_mismatch(
+                "source",
+                RelationshipKind::Specialize,
+                ElementKind::Type,
+                ElementKind::Package,
+            ),
+            ValidationError::invalid_multiplicity(ElementId::new_v4(), 2, Some(1)),
+            ValidationError::multiplicity_not_within(ElementId::new_v4(), (0, None), ElementId::new_v4(), (1, Some(4))),
         ];
 
-        let expected_codes = ["V001", "V002", "V003", "V004", "V005"];
-
-        for (error, expected_code) in errors.into_iter().zip(expected_codes.iter()) {
+        // Every kind has its own code, and config files may name all of them
+        let mut codes = std::collections::BTreeSet::new();
+        let count = errors.len();
+        for error in errors {
             let diag: Diagnostic = error.into();
-            assert_eq!(diag.code, Some(expected_code.to_string()), "Wrong code for error");
             assert!(diag.is_error());
+            let code = diag.code.expect("validation errors have codes");
+            assert!(sysml_span::KNOWN_CODES.contains(&code.as_str()), "{} is not a known code", code);
+            codes.insert(code);
         }
+        assert_eq!(codes.len(), count);
     }
 }
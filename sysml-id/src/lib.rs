@@ -7,7 +7,7 @@
 //!
 //! ## Features
 //!
-//! - `uuid` (default): Use UUID v4 for ElementId
+//! - `uuid` (default): Use UUID v4 for ElementId, and UUID v5 for name-based ids
 //! - `serde`: Enable serde serialization support
 //!
 //! ## Examples
@@ -33,6 +33,9 @@ use std::str::FromStr;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "uuid")]
+pub use uuid::Uuid;
+
 /// Error type for ID parsing failures.
 ///
 /// # Examples
@@ -129,11 +132,48 @@ impl ElementId {
         ElementId(format!("elem_{:016x}", id))
     }
 
+    /// A namespace for name-based ids created with [`from_namespace`](Self::from_namespace).
+    ///
+    /// This is the UUID v5 of `https://github.com/wearebasis/sysml-rs` in the
+    /// RFC 4122 URL namespace.
+    #[cfg(feature = "uuid")]
+    pub const DEFAULT_NAMESPACE: uuid::Uuid = uuid::Uuid::from_bytes([
+        0xa3, 0xd2, 0x8c, 0x05, 0x11, 0x2a, 0x5d, 0x82, 0x90, 0x32, 0x49, 0x08, 0x74, 0xd2, 0x9b, 0x07,
+    ]);
+
+    /// Create a name-based ElementId (UUID v5) within a namespace.
+    ///
+    /// The id is the SHA-1 based UUID of `name` in `namespace`, as specified by
+    /// RFC 4122, so the same pair always gives the same id and ids for similar
+    /// names (e.g. qualified names differing in one segment) do not collide in
+    /// practice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_id::{ElementId, Uuid};
+    ///
+    /// let id = ElementId::from_namespace(&Uuid::NAMESPACE_DNS, "www.example.com");
+    /// assert_eq!(id.to_string(), "2ed6657d-e927-568b-95e1-2665a8aea6a2");
+    ///
+    /// let a = ElementId::from_namespace(&ElementId::DEFAULT_NAMESPACE, "Vehicle::Engine::a");
+    /// let b = ElementId::from_namespace(&ElementId::DEFAULT_NAMESPACE, "Vehicle::Engine::b");
+    /// assert_ne!(a, b);
+    /// ```
+    #[cfg(feature = "uuid")]
+    pub fn from_namespace(namespace: &uuid::Uuid, name: &str) -> Self {
+        ElementId(uuid::Uuid::new_v5(namespace, name.as_bytes()))
+    }
+
     /// Create an ElementId from a string representation.
     ///
     /// If the string is a valid UUID, it will be parsed. Otherwise, a deterministic
     /// UUID will be generated from the string content.
     ///
+    /// The generated ids are kept stable for ids already persisted, but similar
+    /// strings can collide; use [`from_namespace`](Self::from_namespace) for new
+    /// name-based ids.
+    ///
     /// # Examples
     ///
     /// ```
@@ -509,6 +549,82 @@ impl QualifiedName {
         QualifiedName { segments }
     }
 
+    /// Concatenate another qualified name onto this one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_id::QualifiedName;
+    ///
+    /// let base: QualifiedName = "Root::Pkg".parse().unwrap();
+    /// let relative: QualifiedName = "Sub::Part".parse().unwrap();
+    /// assert_eq!(base.join(&relative).to_string(), "Root::Pkg::Sub::Part");
+    ///
+    /// // Joining an empty name is a no-op
+    /// assert_eq!(base.join(&QualifiedName::empty()), base);
+    /// ```
+    pub fn join(&self, other: &QualifiedName) -> QualifiedName {
+        let mut segments = self.segments.clone();
+        segments.extend(other.segments.iter().cloned());
+        QualifiedName { segments }
+    }
+
+    /// Resolve a name relative to this one.
+    ///
+    /// A leading `::` makes the name absolute, so this qualified name is ignored.
+    /// Otherwise the segments are appended, with each `..` segment ascending one
+    /// level (dropping the last segment so far). An empty string resolves to
+    /// this qualified name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IdError::InvalidQualifiedName` if a segment is empty or if `..`
+    /// ascends above the root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_id::QualifiedName;
+    ///
+    /// let base: QualifiedName = "Root::Pkg".parse().unwrap();
+    /// assert_eq!(base.resolve_relative("Sub::Part").unwrap().to_string(), "Root::Pkg::Sub::Part");
+    /// assert_eq!(base.resolve_relative("..::Other").unwrap().to_string(), "Root::Other");
+    /// assert_eq!(base.resolve_relative("::Lib::Part").unwrap().to_string(), "Lib::Part");
+    /// assert!(base.resolve_relative("..::..::..").is_err());
+    /// ```
+    pub fn resolve_relative(&self, relative: &str) -> Result<QualifiedName, IdError> {
+        if relative.is_empty() {
+            return Ok(self.clone());
+        }
+
+        let (mut segments, path) = match relative.strip_prefix("::") {
+            Some(rest) => (Vec::new(), rest),
+            None => (self.segments.clone(), relative),
+        };
+
+        for segment in path.split("::").map(str::trim) {
+            match segment {
+                "" => {
+                    return Err(IdError::InvalidQualifiedName(format!(
+                        "empty segment in '{}'",
+                        relative
+                    )));
+                }
+                ".." => {
+                    if segments.pop().is_none() {
+                        return Err(IdError::InvalidQualifiedName(format!(
+                            "'{}' ascends above the root of '{}'",
+                            relative, self
+                        )));
+                    }
+                }
+                _ => segments.push(segment.to_string()),
+            }
+        }
+
+        Ok(QualifiedName { segments })
+    }
+
     /// Check if this qualified name is empty.
     ///
     /// # Examples
@@ -560,6 +676,60 @@ impl QualifiedName {
         self.segments[..prefix.segments.len()] == prefix.segments[..]
     }
 
+    /// Get the longest prefix shared by this and another qualified name.
+    ///
+    /// Returns an empty qualified name if the first segments differ.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_id::QualifiedName;
+    ///
+    /// let a: QualifiedName = "Root::A::B::Part".parse().unwrap();
+    /// let b: QualifiedName = "Root::A::C".parse().unwrap();
+    /// assert_eq!(a.common_prefix(&b).to_string(), "Root::A");
+    ///
+    /// let other: QualifiedName = "X::Y".parse().unwrap();
+    /// assert!(a.common_prefix(&other).is_empty());
+    /// ```
+    pub fn common_prefix(&self, other: &QualifiedName) -> QualifiedName {
+        let segments = self
+            .segments
+            .iter()
+            .zip(&other.segments)
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.clone())
+            .collect();
+        QualifiedName { segments }
+    }
+
+    /// Remove a prefix, returning the remaining segments.
+    ///
+    /// Returns `None` if `prefix` is not a prefix of this qualified name (see
+    /// [`starts_with`](Self::starts_with)). Stripping the whole name leaves an
+    /// empty qualified name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_id::QualifiedName;
+    ///
+    /// let full: QualifiedName = "Root::A::B::Part::Attr".parse().unwrap();
+    /// let scope: QualifiedName = "Root::A::B".parse().unwrap();
+    /// assert_eq!(full.strip_prefix(&scope).unwrap().to_string(), "Part::Attr");
+    ///
+    /// let other: QualifiedName = "Root::X".parse().unwrap();
+    /// assert!(full.strip_prefix(&other).is_none());
+    /// ```
+    pub fn strip_prefix(&self, prefix: &QualifiedName) -> Option<QualifiedName> {
+        if !self.starts_with(prefix) {
+            return None;
+        }
+        Some(QualifiedName {
+            segments: self.segments[prefix.segments.len()..].to_vec(),
+        })
+    }
+
     /// Escape a segment for display, handling special characters.
     ///
     /// Escapes backslash as `\\` and colon as `\:`.
@@ -714,6 +884,53 @@ mod tests {
         assert_eq!(id1, id2);
     }
 
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn element_id_from_namespace_is_uuid_v5() {
+        let id = ElementId::from_namespace(&Uuid::NAMESPACE_DNS, "www.example.com");
+        assert_eq!(id.to_string(), "2ed6657d-e927-568b-95e1-2665a8aea6a2");
+
+        let namespace = ElementId::from_namespace(&Uuid::NAMESPACE_URL, "https://github.com/wearebasis/sysml-rs");
+        assert_eq!(namespace.to_string(), ElementId::DEFAULT_NAMESPACE.to_string());
+        assert_eq!(ElementId::DEFAULT_NAMESPACE.get_version_num(), 5);
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn element_id_from_string_keeps_persisted_ids() {
+        // Ids derived from non-UUID strings are stored in models and must not change
+        assert_eq!(ElementId::from_string("my-element").to_string(), "e1792d65-6c65-4d08-9759-656c656d656e");
+        assert_ne!(
+            ElementId::from_string("my-element"),
+            ElementId::from_namespace(&ElementId::DEFAULT_NAMESPACE, "my-element")
+        );
+
+        // Valid UUIDs are still parsed rather than hashed
+        let uuid_str = "550e8400-e29b-41d4-a716-446655440000";
+        assert_eq!(ElementId::from_string(uuid_str).to_string(), uuid_str);
+        assert_eq!(
+            ElementId::from_string("550E8400E29B41D4A716446655440000").to_string(),
+            uuid_str
+        );
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn element_id_from_namespace_similar_names_do_not_collide() {
+        use std::collections::HashSet;
+
+        // Names differing in a single segment, including swapped characters that
+        // the XOR-based hash of from_string maps to the same id
+        let mut ids = HashSet::new();
+        for i in 0..2000 {
+            ids.insert(ElementId::from_namespace(&ElementId::DEFAULT_NAMESPACE, &format!("Root::Pkg{}::Part::Attr", i)));
+            ids.insert(ElementId::from_namespace(&ElementId::DEFAULT_NAMESPACE, &format!("Root::Gkp{}::Part::Attr", i)));
+        }
+        assert_eq!(ids.len(), 4000);
+        let id = |name: &str| ElementId::from_namespace(&ElementId::DEFAULT_NAMESPACE, name);
+        assert_ne!(id("ab"), id("ba"));
+    }
+
     #[test]
     fn project_id_roundtrip() {
         let id = ProjectId::new("my-project");
@@ -811,6 +1028,41 @@ mod tests {
         assert!(full.starts_with(&QualifiedName::empty()));
     }
 
+    #[test]
+    fn qualified_name_common_prefix() {
+        let a: QualifiedName = "Root::A::B::Part".parse().unwrap();
+        let b: QualifiedName = "Root::A::C".parse().unwrap();
+        assert_eq!(a.common_prefix(&b).segments(), &["Root", "A"]);
+        assert_eq!(b.common_prefix(&a), a.common_prefix(&b));
+        assert_eq!(a.common_prefix(&a), a);
+        assert!(a.common_prefix(&QualifiedName::empty()).is_empty());
+
+        // Segments must match whole, not by string prefix
+        let c: QualifiedName = "Root::AB".parse().unwrap();
+        assert_eq!(a.common_prefix(&c).to_string(), "Root");
+
+        let u1: QualifiedName = "包::部品::属性".parse().unwrap();
+        let u2: QualifiedName = "包::部品::値".parse().unwrap();
+        assert_eq!(u1.common_prefix(&u2).to_string(), "包::部品");
+    }
+
+    #[test]
+    fn qualified_name_strip_prefix() {
+        let full: QualifiedName = "Root::A::B::Part::Attr".parse().unwrap();
+        let scope: QualifiedName = "Root::A::B".parse().unwrap();
+        assert_eq!(full.strip_prefix(&scope).unwrap().segments(), &["Part", "Attr"]);
+        assert_eq!(full.strip_prefix(&QualifiedName::empty()).unwrap(), full);
+        assert!(full.strip_prefix(&full).unwrap().is_empty());
+
+        assert!(scope.strip_prefix(&full).is_none());
+        let other: QualifiedName = "Root::X".parse().unwrap();
+        assert!(full.strip_prefix(&other).is_none());
+
+        // Stripping a common prefix and joining it back round-trips
+        let prefix = full.common_prefix(&scope);
+        assert_eq!(prefix.join(&full.strip_prefix(&prefix).unwrap()), full);
+    }
+
     #[test]
     fn qualified_name_escaping() {
         // Segment with colon
@@ -841,6 +1093,41 @@ mod tests {
         assert_eq!(qn.segments(), &["A B", "C D"]);
     }
 
+    #[test]
+    fn qualified_name_join() {
+        let base: QualifiedName = "Root::Pkg".parse().unwrap();
+        let relative: QualifiedName = "Sub::Part".parse().unwrap();
+        assert_eq!(base.join(&relative).segments(), &["Root", "Pkg", "Sub", "Part"]);
+        assert_eq!(QualifiedName::empty().join(&relative), relative);
+
+        let unicode: QualifiedName = "部品::属性".parse().unwrap();
+        assert_eq!(base.join(&unicode).to_string(), "Root::Pkg::部品::属性");
+    }
+
+    #[test]
+    fn qualified_name_resolve_relative() {
+        let base: QualifiedName = "Root::Pkg".parse().unwrap();
+
+        assert_eq!(base.resolve_relative("Sub::Part").unwrap().to_string(), "Root::Pkg::Sub::Part");
+        assert_eq!(base.resolve_relative("").unwrap(), base);
+        assert_eq!(base.resolve_relative("..").unwrap().to_string(), "Root");
+        assert_eq!(base.resolve_relative("..::..::Lib").unwrap().to_string(), "Lib");
+        assert_eq!(base.resolve_relative("Sub::..::Other").unwrap().to_string(), "Root::Pkg::Other");
+        assert_eq!(base.resolve_relative(" Sub :: Part ").unwrap().to_string(), "Root::Pkg::Sub::Part");
+
+        // Leading :: is absolute
+        assert_eq!(base.resolve_relative("::Lib::Part").unwrap().to_string(), "Lib::Part");
+        assert!(base.resolve_relative("::..").is_err());
+
+        // Unicode segments
+        assert_eq!(base.resolve_relative("..::包::部品").unwrap().to_string(), "Root::包::部品");
+
+        assert!(base.resolve_relative("..::..::..").is_err());
+        assert!(base.resolve_relative("Sub::::Part").is_err());
+        assert!(base.resolve_relative("Sub::").is_err());
+        assert!(base.resolve_relative("::").is_err());
+    }
+
     #[cfg(feature = "serde")]
     mod serde_tests {
         use super::*;
@@ -4,25 +4,35 @@
 //!
 //! This crate provides a Language Server Protocol server that uses:
 //! - sysml-text-pest for full parsing + resolution diagnostics
-//! - sysml-text for library loading and parser traits
+//! - sysml-text for library loading, parser traits and the multi-file
+//!   workspace used for cross-file resolution
 //! - sysml-ts for fast CST parsing (outline)
-//! - sysml-lsp for protocol types
+//! - sysml-text-fmt for document formatting
+//! - sysml-lsp for protocol types and go-to-definition lookups
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 
-use sysml_lsp::{DiagnosticSeverity as SysmlSeverity, LspDiagnostic, Range as LspRange};
+use sysml_lsp::{
+    apply_content_change, completion_candidates, document_symbols, find_definition, find_element_at_offset, find_hover_element, find_references, folding_ranges,
+    hover_markdown, workspace_symbols, CompletionContext, CompletionItemKind as SysmlCompletionKind, DiagnosticSeverity as SysmlSeverity,
+    DocumentSymbol as SysmlDocumentSymbol, FoldingRangeKind as SysmlFoldingKind, LspDiagnostic, Position as LspPosition, Range as LspRange, SymbolKind as SysmlSymbolKind,
+};
 use sysml_text::library::{load_standard_library, LibraryConfig};
-use sysml_text::{Parser as SysmlParser, SysmlFile as TextFile};
+use sysml_text::workspace::{sysml_files, Workspace};
+use sysml_text::{Formatter as _, Parser as SysmlParser, SysmlFile as TextFile};
+use sysml_text_fmt::TextFormatter;
 use sysml_text_pest::PestParser;
 use sysml_ts::{extract_outline, FastParser, StubTreeSitterParser, SysmlFile as TsFile};
 
 use sysml_core::ModelGraph;
-use sysml_span::Diagnostic as SysmlDiagnostic;
+use sysml_span::{Diagnostic as SysmlDiagnostic, LineIndex, Severity as SysmlDiagnosticSeverity, Span};
 
 /// Document state.
 #[derive(Debug, Clone)]
@@ -33,16 +43,48 @@ struct Document {
     content: String,
     /// The document version.
     version: i32,
+    /// Bumped by every change, so that debounced diagnostics for older
+    /// content are dropped.
+    generation: u64,
+    /// The resolved graph from the last successful parse, merged with the
+    /// standard library when it is loaded. It is kept through later edits
+    /// and failed parses until a newer successful parse replaces it.
+    graph: Option<ModelGraph>,
+    /// The generation of the content `graph` was resolved from.
+    graph_generation: u64,
+}
+
+impl Document {
+    /// Keep the graph resolved from the current content, if it resolved.
+    fn store_graph(&mut self, graph: Option<ModelGraph>) {
+        if let Some(graph) = graph {
+            self.graph = Some(graph);
+            self.graph_generation = self.generation;
+        }
+    }
+
+    /// The cached graph, if it was resolved from the current content.
+    fn current_graph(&self) -> Option<&ModelGraph> {
+        self.graph.as_ref().filter(|_| self.graph_generation == self.generation)
+    }
 }
 
 #[derive(Debug, Clone)]
 enum LibraryState {
     Unloaded,
-    Loaded(ModelGraph),
-    Failed(String),
+    Loaded(Box<ModelGraph>),
+    /// Loading failed; the reason was logged to the client when it happened.
+    Failed,
 }
 
+/// How long to wait after a change before publishing diagnostics, unless
+/// the client sets `diagnosticsDelayMs` in its initialization options.
+const DEFAULT_DIAGNOSTICS_DELAY: Duration = Duration::from_millis(250);
+
 /// The SysML language server backend.
+///
+/// Clones share the same state, so a clone can be moved into a spawned task.
+#[derive(Clone)]
 pub struct SysmlLanguageServer {
     /// The LSP client.
     client: Client,
@@ -54,6 +96,12 @@ pub struct SysmlLanguageServer {
     semantic_parser: PestParser,
     /// Standard library cache.
     library_state: Arc<RwLock<LibraryState>>,
+    /// Parsed content of the workspace files, for cross-file resolution.
+    workspace: Arc<RwLock<Workspace>>,
+    /// The workspace folders given by the client.
+    workspace_roots: Arc<RwLock<Vec<PathBuf>>>,
+    /// The delay before publishing diagnostics after a change.
+    diagnostics_delay: Arc<RwLock<Duration>>,
 }
 
 impl SysmlLanguageServer {
@@ -65,55 +113,149 @@ impl SysmlLanguageServer {
             cst_parser: StubTreeSitterParser::new(),
             semantic_parser: PestParser::new(),
             library_state: Arc::new(RwLock::new(LibraryState::Unloaded)),
+            workspace: Arc::new(RwLock::new(Workspace::new())),
+            workspace_roots: Arc::new(RwLock::new(Vec::new())),
+            diagnostics_delay: Arc::new(RwLock::new(DEFAULT_DIAGNOSTICS_DELAY)),
         }
     }
 
-    /// Publish diagnostics for a document.
-    async fn publish_diagnostics(&self, uri: &str, content: &str) {
+    /// Publish diagnostics for a version of a document and keep its
+    /// resolved graph.
+    ///
+    /// Nothing is published if the document has changed or closed in the
+    /// meantime; the newer content gets diagnostics of its own.
+    async fn publish_diagnostics(&self, uri: &str, content: &str, version: i32) {
+        let (sysml_diags, graph) = self.analyze(uri, content).await;
+
+        match self.documents.write().await.get_mut(uri) {
+            Some(doc) if doc.version == version && doc.content == content => doc.store_graph(graph),
+            _ => return,
+        }
+
+        let index = LineIndex::new(content);
+        let diagnostics: Vec<Diagnostic> = sysml_diags
+            .iter()
+            .map(|diag| to_lsp_diagnostic(diag, &index, content))
+            .collect();
+
+        self.client
+            .publish_diagnostics(
+                Url::parse(uri).unwrap_or_else(|_| Url::parse("file:///unknown").unwrap()),
+                diagnostics,
+                Some(version),
+            )
+            .await;
+    }
+
+    /// Publish diagnostics for a document once it has gone unchanged for
+    /// the diagnostics delay.
+    ///
+    /// Each change schedules a task; a task whose generation is no longer
+    /// the document's gives up without analyzing, so fast typing runs the
+    /// pipeline once per pause rather than once per keystroke.
+    fn schedule_diagnostics(&self, uri: String, generation: u64) {
+        let server = self.clone();
+        tokio::spawn(async move {
+            let delay = *server.diagnostics_delay.read().await;
+            tokio::time::sleep(delay).await;
+
+            let (content, version) = match server.documents.read().await.get(&uri) {
+                Some(doc) if doc.generation == generation => (doc.content.clone(), doc.version),
+                _ => return,
+            };
+            server.publish_diagnostics(&uri, &content, version).await;
+        });
+    }
+
+    /// Parse, resolve and validate a document.
+    ///
+    /// Returns the diagnostics and, when the document parsed without errors,
+    /// the resolved graph.
+    async fn analyze(&self, uri: &str, content: &str) -> (Vec<SysmlDiagnostic>, Option<ModelGraph>) {
         let file = TextFile::new(uri, content);
-        let mut result = self.semantic_parser.parse(&[file]);
+        let parse_ok = {
+            let mut workspace = self.workspace.write().await;
+            let diagnostics = workspace.update_file(&self.semantic_parser, file);
+            !diagnostics.iter().any(|diag| diag.severity == SysmlDiagnosticSeverity::Error)
+        };
 
-        let parse_ok = result.error_count() == 0;
+        // Resolve against the whole workspace, so references into sibling
+        // files resolve
+        let library = if parse_ok {
+            self.load_library_if_needed().await
+        } else {
+            None
+        };
+        let mut result = self.workspace.read().await.analyze(uri, library.as_ref());
         let mut sysml_diags = result.diagnostics.clone();
 
         if parse_ok {
-            let library = self.load_library_if_needed().await;
-            let resolution = if let Some(lib) = library {
-                result.resolve_with_library(lib)
-            } else {
-                result.resolve()
-            };
-            sysml_diags.extend(resolution.diagnostics.into_iter());
-
             const ENABLE_VALIDATION: bool = true;
             if ENABLE_VALIDATION {
                 let base_len = result.diagnostics.len();
                 result.validate_structure();
                 result.validate_relationships();
-                sysml_diags.extend(result.diagnostics.iter().skip(base_len).cloned());
+                result.validate_multiplicities();
+                // The graph holds every workspace file; report this one only
+                sysml_diags.extend(
+                    result
+                        .diagnostics
+                        .iter()
+                        .skip(base_len)
+                        .filter(|diag| diag.span.as_ref().is_some_and(|span| span.file == uri))
+                        .cloned(),
+                );
             }
         }
 
-        let diagnostics: Vec<Diagnostic> = sysml_diags
-            .iter()
-            .map(|diag| to_lsp_diagnostic(diag, content))
-            .collect();
+        let graph = parse_ok.then_some(result.graph);
+        (sysml_diags, graph)
+    }
 
-        self.client
-            .publish_diagnostics(
-                Url::parse(uri).unwrap_or_else(|_| Url::parse("file:///unknown").unwrap()),
-                diagnostics,
-                None,
-            )
-            .await;
+    /// Parse the `.sysml` files under the workspace folders into the
+    /// workspace.
+    async fn scan_workspace(&self) {
+        let roots = self.workspace_roots.read().await.clone();
+        let mut count = 0;
+        for path in roots.iter().flat_map(sysml_files) {
+            let (uri, text) = match (Url::from_file_path(&path), std::fs::read_to_string(&path)) {
+                (Ok(uri), Ok(text)) => (uri.to_string(), text),
+                _ => continue,
+            };
+            self.workspace
+                .write()
+                .await
+                .update_file(&self.semantic_parser, TextFile::new(uri, text));
+            count += 1;
+        }
+        if count > 0 {
+            self.client
+                .log_message(MessageType::INFO, format!("Loaded {} SysML files from the workspace", count))
+                .await;
+        }
+    }
+
+    /// The content of an open document with its resolved graph, reparsing
+    /// when the cached graph is from older content or the last parse failed.
+    async fn resolved_document(&self, uri: &str) -> Option<(String, ModelGraph)> {
+        let (content, graph) = {
+            let docs = self.documents.read().await;
+            let doc = docs.get(uri)?;
+            (doc.content.clone(), doc.current_graph().cloned())
+        };
+        let graph = match graph {
+            Some(graph) => graph,
+            None => self.analyze(uri, &content).await.1?,
+        };
+        Some((content, graph))
     }
 
     async fn load_library_if_needed(&self) -> Option<ModelGraph> {
         {
             let state = self.library_state.read().await;
             match &*state {
-                LibraryState::Loaded(lib) => return Some(lib.clone()),
-                LibraryState::Failed(_) => return None,
+                LibraryState::Loaded(lib) => return Some(lib.as_ref().clone()),
+                LibraryState::Failed => return None,
                 LibraryState::Unloaded => {}
             }
         }
@@ -121,18 +263,20 @@ impl SysmlLanguageServer {
         let config = match LibraryConfig::from_env_optional() {
             Some(config) => config,
             None => {
-                let mut state = self.library_state.write().await;
-                *state = LibraryState::Failed(
-                    "Standard library not configured (SYSML_LIBRARY_PATH not set and default not found)".to_string(),
-                );
+                *self.library_state.write().await = LibraryState::Failed;
+                self.client
+                    .log_message(
+                        MessageType::WARNING,
+                        "Standard library not configured (SYSML_LIBRARY_PATH not set and default not found)",
+                    )
+                    .await;
                 return None;
             }
         };
 
         match load_standard_library(&self.semantic_parser, &config) {
             Ok(library) => {
-                let mut state = self.library_state.write().await;
-                *state = LibraryState::Loaded(library.clone());
+                *self.library_state.write().await = LibraryState::Loaded(Box::new(library.clone()));
                 self.client
                     .log_message(
                         MessageType::INFO,
@@ -142,18 +286,18 @@ impl SysmlLanguageServer {
                 Some(library)
             }
             Err(err) => {
-                let message = format!("Failed to load standard library: {}", err);
-                let mut state = self.library_state.write().await;
-                *state = LibraryState::Failed(message.clone());
-                self.client.log_message(MessageType::ERROR, message).await;
+                *self.library_state.write().await = LibraryState::Failed;
+                self.client
+                    .log_message(MessageType::ERROR, format!("Failed to load standard library: {}", err))
+                    .await;
                 None
             }
         }
     }
 }
 
-fn to_lsp_diagnostic(diag: &SysmlDiagnostic, source: &str) -> Diagnostic {
-    let lsp_diag = LspDiagnostic::from_sysml(diag, source);
+fn to_lsp_diagnostic(diag: &SysmlDiagnostic, index: &LineIndex, source: &str) -> Diagnostic {
+    let lsp_diag = LspDiagnostic::from_sysml_with_index(diag, index, source);
     let range = to_lsp_range(lsp_diag.range);
     let severity = lsp_diag.severity.map(|s| match s {
         SysmlSeverity::Error => DiagnosticSeverity::ERROR,
@@ -193,6 +337,71 @@ fn to_lsp_diagnostic(diag: &SysmlDiagnostic, source: &str) -> Diagnostic {
     }
 }
 
+/// The location of a definition or reference span, reading the source of
+/// files other than the current document from disk. `index` is the line
+/// index of the current document's `content`.
+fn definition_location(span: &Span, uri: &str, content: &str, index: &LineIndex) -> Option<Location> {
+    let range = if span.file == uri {
+        LspRange::from_span_with_index(span, index, content)
+    } else {
+        LspRange::from_span(span, &read_source(&span.file)?)
+    };
+    Some(Location {
+        uri: parse_uri(&span.file)?,
+        range: to_lsp_range(range),
+    })
+}
+
+#[allow(deprecated)]
+fn to_lsp_symbol(symbol: SysmlDocumentSymbol) -> DocumentSymbol {
+    let children: Vec<DocumentSymbol> = symbol.children.into_iter().map(to_lsp_symbol).collect();
+    DocumentSymbol {
+        name: symbol.name,
+        detail: symbol.detail,
+        kind: to_lsp_symbol_kind(symbol.kind),
+        tags: None,
+        deprecated: None,
+        range: to_lsp_range(symbol.range),
+        selection_range: to_lsp_range(symbol.selection_range),
+        children: if children.is_empty() {
+            None
+        } else {
+            Some(children)
+        },
+    }
+}
+
+fn to_lsp_symbol_kind(kind: SysmlSymbolKind) -> SymbolKind {
+    match kind {
+        SysmlSymbolKind::File => SymbolKind::FILE,
+        SysmlSymbolKind::Module => SymbolKind::MODULE,
+        SysmlSymbolKind::Namespace => SymbolKind::NAMESPACE,
+        SysmlSymbolKind::Package => SymbolKind::PACKAGE,
+        SysmlSymbolKind::Class => SymbolKind::CLASS,
+        SysmlSymbolKind::Method => SymbolKind::METHOD,
+        SysmlSymbolKind::Property => SymbolKind::PROPERTY,
+        SysmlSymbolKind::Field => SymbolKind::FIELD,
+        SysmlSymbolKind::Constructor => SymbolKind::CONSTRUCTOR,
+        SysmlSymbolKind::Enum => SymbolKind::ENUM,
+        SysmlSymbolKind::Interface => SymbolKind::INTERFACE,
+        SysmlSymbolKind::Function => SymbolKind::FUNCTION,
+        SysmlSymbolKind::Variable => SymbolKind::VARIABLE,
+        SysmlSymbolKind::Constant => SymbolKind::CONSTANT,
+        SysmlSymbolKind::String => SymbolKind::STRING,
+        SysmlSymbolKind::Number => SymbolKind::NUMBER,
+        SysmlSymbolKind::Boolean => SymbolKind::BOOLEAN,
+        SysmlSymbolKind::Array => SymbolKind::ARRAY,
+        SysmlSymbolKind::Object => SymbolKind::OBJECT,
+        SysmlSymbolKind::Key => SymbolKind::KEY,
+        SysmlSymbolKind::Null => SymbolKind::NULL,
+        SysmlSymbolKind::EnumMember => SymbolKind::ENUM_MEMBER,
+        SysmlSymbolKind::Struct => SymbolKind::STRUCT,
+        SysmlSymbolKind::Event => SymbolKind::EVENT,
+        SysmlSymbolKind::Operator => SymbolKind::OPERATOR,
+        SysmlSymbolKind::TypeParameter => SymbolKind::TYPE_PARAMETER,
+    }
+}
+
 fn to_lsp_range(range: LspRange) -> Range {
     Range {
         start: Position {
@@ -206,6 +415,23 @@ fn to_lsp_range(range: LspRange) -> Range {
     }
 }
 
+/// Read the source of a span's file from disk. Workspace files are named
+/// by `file://` URI and library files by path.
+fn read_source(file: &str) -> Option<String> {
+    match Url::parse(file).ok().and_then(|url| url.to_file_path().ok()) {
+        Some(path) => std::fs::read_to_string(path).ok(),
+        None => std::fs::read_to_string(file).ok(),
+    }
+}
+
+/// The `diagnosticsDelayMs` initialization option, if the client set one.
+fn diagnostics_delay(options: Option<&LSPAny>) -> Option<Duration> {
+    options?
+        .get("diagnosticsDelayMs")?
+        .as_u64()
+        .map(Duration::from_millis)
+}
+
 fn parse_uri(uri: &str) -> Option<Url> {
     Url::parse(uri)
         .ok()
@@ -214,13 +440,34 @@ fn parse_uri(uri: &str) -> Option<Url> {
 
 #[tower_lsp::async_trait]
 impl LanguageServer for SysmlLanguageServer {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        #[allow(deprecated)]
+        let roots: Vec<PathBuf> = match params.workspace_folders {
+            Some(folders) => folders.iter().filter_map(|folder| folder.uri.to_file_path().ok()).collect(),
+            None => params.root_uri.iter().filter_map(|uri| uri.to_file_path().ok()).collect(),
+        };
+        *self.workspace_roots.write().await = roots;
+        if let Some(delay) = diagnostics_delay(params.initialization_options.as_ref()) {
+            *self.diagnostics_delay.write().await = delay;
+        }
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                    TextDocumentSyncKind::FULL,
+                    TextDocumentSyncKind::INCREMENTAL,
                 )),
                 document_symbol_provider: Some(OneOf::Left(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                references_provider: Some(OneOf::Left(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                completion_provider: Some(CompletionOptions {
+                    // `::` arrives as two `:` triggers
+                    trigger_characters: Some(vec![":".to_string(), " ".to_string()]),
+                    ..Default::default()
+                }),
                 ..Default::default()
             },
             server_info: Some(ServerInfo {
@@ -234,6 +481,7 @@ impl LanguageServer for SysmlLanguageServer {
         self.client
             .log_message(MessageType::INFO, "SysML language server initialized")
             .await;
+        self.scan_workspace().await;
     }
 
     async fn shutdown(&self) -> Result<()> {
@@ -253,41 +501,279 @@ impl LanguageServer for SysmlLanguageServer {
                     uri: uri.clone(),
                     content: content.clone(),
                     version,
+                    generation: 0,
+                    graph: None,
+                    graph_generation: 0,
                 },
             );
         }
 
-        self.publish_diagnostics(&uri, &content).await;
+        // Opening is a single event, so there is nothing to wait for
+        self.publish_diagnostics(&uri, &content, version).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let uri = params.text_document.uri.to_string();
         let version = params.text_document.version;
 
-        // Full sync - take the last change
-        if let Some(change) = params.content_changes.into_iter().last() {
-            let content = change.text;
-
-            {
-                let mut docs = self.documents.write().await;
-                docs.insert(
-                    uri.clone(),
-                    Document {
-                        uri: uri.clone(),
-                        content: content.clone(),
-                        version,
-                    },
-                );
+        // Changes apply in order, each to the result of the previous one
+        let generation = {
+            let mut docs = self.documents.write().await;
+            let doc = match docs.get_mut(&uri) {
+                Some(doc) => doc,
+                None => return,
+            };
+            for change in params.content_changes {
+                let range = change.range.map(|range| {
+                    LspRange::new(
+                        LspPosition::new(range.start.line, range.start.character),
+                        LspPosition::new(range.end.line, range.end.character),
+                    )
+                });
+                apply_content_change(&mut doc.content, range, &change.text);
             }
+            doc.version = version;
+            doc.generation += 1;
+            doc.generation
+        };
 
-            self.publish_diagnostics(&uri, &content).await;
-        }
+        self.schedule_diagnostics(uri, generation);
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         let uri = params.text_document.uri.to_string();
-        let mut docs = self.documents.write().await;
-        docs.remove(&uri);
+        self.documents.write().await.remove(&uri);
+
+        // Unsaved edits are gone, so go back to the file on disk
+        let mut workspace = self.workspace.write().await;
+        match params.text_document.uri.to_file_path().map(std::fs::read_to_string) {
+            Ok(Ok(text)) => {
+                workspace.update_file(&self.semantic_parser, TextFile::new(uri, text));
+            }
+            _ => {
+                workspace.remove_file(&uri);
+            }
+        }
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let uri = params.text_document_position_params.text_document.uri.to_string();
+        let position = params.text_document_position_params.position;
+
+        let (content, graph) = match self.resolved_document(&uri).await {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+
+        let offset = LspPosition::new(position.line, position.character).to_offset(&content);
+        let location = find_definition(&graph, &uri, offset)
+            .and_then(|span| definition_location(span, &uri, &content, &LineIndex::new(&content)));
+        Ok(location.map(GotoDefinitionResponse::Scalar))
+    }
+
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let uri = params.text_document_position.text_document.uri.to_string();
+        let position = params.text_document_position.position;
+
+        let (content, graph) = match self.resolved_document(&uri).await {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+
+        let offset = LspPosition::new(position.line, position.character).to_offset(&content);
+        let target = match find_hover_element(&graph, &uri, offset) {
+            Some(target) => target,
+            None => return Ok(None),
+        };
+        let index = LineIndex::new(&content);
+        let locations: Vec<Location> = find_references(&graph, target, params.context.include_declaration)
+            .into_iter()
+            .filter_map(|span| definition_location(span, &uri, &content, &index))
+            .collect();
+        Ok(Some(locations))
+    }
+
+    #[allow(deprecated)]
+    async fn symbol(&self, params: WorkspaceSymbolParams) -> Result<Option<Vec<SymbolInformation>>> {
+        // Open documents are read from memory, so unsaved edits are matched;
+        // the contents are copied out so that no lock is held on both maps
+        let open: HashMap<String, String> = self
+            .documents
+            .read()
+            .await
+            .iter()
+            .map(|(uri, doc)| (uri.clone(), doc.content.clone()))
+            .collect();
+
+        let workspace = self.workspace.read().await;
+        let symbols = workspace_symbols(workspace.graph(), &params.query, |file| match open.get(file) {
+            Some(content) => Some(content.clone()),
+            None => read_source(file),
+        })
+        .into_iter()
+        .filter_map(|symbol| {
+            Some(SymbolInformation {
+                name: symbol.name,
+                kind: to_lsp_symbol_kind(symbol.kind),
+                tags: None,
+                deprecated: None,
+                location: Location {
+                    uri: parse_uri(&symbol.location.uri)?,
+                    range: to_lsp_range(symbol.location.range),
+                },
+                container_name: symbol.container_name,
+            })
+        })
+        .collect();
+        Ok(Some(symbols))
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let uri = params.text_document_position_params.text_document.uri.to_string();
+        let position = params.text_document_position_params.position;
+
+        let (content, graph) = match self.resolved_document(&uri).await {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+
+        let offset = LspPosition::new(position.line, position.character).to_offset(&content);
+        let element = match find_hover_element(&graph, &uri, offset) {
+            Some(element) => element,
+            None => return Ok(None),
+        };
+        let range = element
+            .spans
+            .iter()
+            .find(|span| span.file == uri)
+            .map(|span| to_lsp_range(LspRange::from_span(span, &content)));
+
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: hover_markdown(&graph, element),
+            }),
+            range,
+        }))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let uri = params.text_document_position.text_document.uri.to_string();
+        let position = params.text_document_position.position;
+
+        let docs = self.documents.read().await;
+        let doc = match docs.get(&uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        // The last resolved graph is used even while newer edits are being
+        // analyzed or fail to parse, so names stay available as the user
+        // types; documents that never parsed only get keywords
+        let offset = LspPosition::new(position.line, position.character).to_offset(&doc.content);
+        let context = CompletionContext::at(&doc.content, offset);
+        let scope = doc
+            .graph
+            .as_ref()
+            .and_then(|graph| find_element_at_offset(graph, &uri, offset))
+            .map(|element| element.id.clone());
+        let items: Vec<CompletionItem> = completion_candidates(doc.graph.as_ref(), &context, scope.as_ref())
+            .into_iter()
+            .map(|item| CompletionItem {
+                label: item.label,
+                kind: Some(match item.kind {
+                    SysmlCompletionKind::Field => CompletionItemKind::FIELD,
+                    SysmlCompletionKind::Class => CompletionItemKind::CLASS,
+                    SysmlCompletionKind::Module => CompletionItemKind::MODULE,
+                    SysmlCompletionKind::Keyword => CompletionItemKind::KEYWORD,
+                }),
+                detail: item.detail,
+                sort_text: Some(item.sort_text),
+                ..Default::default()
+            })
+            .collect();
+
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri.to_string();
+
+        let content = {
+            let docs = self.documents.read().await;
+            match docs.get(&uri) {
+                Some(doc) => doc.content.clone(),
+                None => return Ok(None),
+            }
+        };
+
+        // Leave documents with syntax errors alone rather than dropping
+        // whatever failed to parse
+        let result = self.semantic_parser.parse(&[TextFile::new(&uri, &content)]);
+        if result.error_count() > 0 {
+            return Ok(None);
+        }
+
+        let formatted = TextFormatter::new()
+            .with_indent(params.options.tab_size as usize)
+            .format(&result.graph);
+        if formatted == content {
+            return Ok(Some(Vec::new()));
+        }
+
+        // One edit replacing the whole document
+        let end = LspRange::from_span(&Span::new(uri.as_str(), 0, content.len()), &content).end;
+        Ok(Some(vec![TextEdit {
+            range: Range {
+                start: Position::new(0, 0),
+                end: Position::new(end.line, end.character),
+            },
+            new_text: formatted,
+        }]))
+    }
+
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        let uri = params.text_document.uri.to_string();
+
+        let docs = self.documents.read().await;
+        let doc = match docs.get(&uri) {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        // Declarations come from the resolved graph of the current content,
+        // or from the CST outline while there is none (syntax errors, or a
+        // change not analyzed yet)
+        let spans: Vec<Span> = match doc.current_graph() {
+            Some(graph) => graph
+                .elements
+                .values()
+                .flat_map(|element| element.spans.iter())
+                .filter(|span| span.file == uri)
+                .cloned()
+                .collect(),
+            None => {
+                let cst = self.cst_parser.parse_cst(&TsFile::new(&uri, &doc.content));
+                extract_outline(&cst, &doc.content).into_iter().map(|item| item.span).collect()
+            }
+        };
+
+        let ranges = folding_ranges(&spans, &doc.content)
+            .into_iter()
+            .map(|range| FoldingRange {
+                start_line: range.start_line,
+                end_line: range.end_line,
+                kind: Some(match range.kind {
+                    SysmlFoldingKind::Comment => FoldingRangeKind::Comment,
+                    SysmlFoldingKind::Region => FoldingRangeKind::Region,
+                }),
+                ..Default::default()
+            })
+            .collect();
+        Ok(Some(ranges))
     }
 
     async fn document_symbol(
@@ -302,18 +788,30 @@ impl LanguageServer for SysmlLanguageServer {
             None => return Ok(None),
         };
 
+        let result = self.semantic_parser.parse(&[TextFile::new(&uri, &doc.content)]);
+        if result.error_count() == 0 {
+            let symbols = document_symbols(&result.graph, &uri, &doc.content)
+                .into_iter()
+                .map(to_lsp_symbol)
+                .collect();
+            return Ok(Some(DocumentSymbolResponse::Nested(symbols)));
+        }
+
+        // Broken documents still get the flat CST outline
         let file = TsFile::new(&uri, &doc.content);
         let cst = self.cst_parser.parse_cst(&file);
         let outline = extract_outline(&cst, &doc.content);
 
+        let index = LineIndex::new(&doc.content);
         let symbols: Vec<DocumentSymbol> = outline
             .into_iter()
             .map(|item| {
-                let range = LspRange::from_span(&item.span, &doc.content);
+                let range = LspRange::from_span_with_index(&item.span, &index, &doc.content);
                 DocumentSymbol {
                     name: item.name,
                     detail: None,
-                    kind: SymbolKind::PACKAGE,
+                    // The CST outline only has packages
+                    kind: to_lsp_symbol_kind(SysmlSymbolKind::Module),
                     tags: None,
                     deprecated: None,
                     range: tower_lsp::lsp_types::Range {
@@ -368,4 +866,39 @@ mod tests {
         let (_service, _socket) = create_service();
         // Just verify it compiles and creates without panic
     }
+
+    #[test]
+    fn diagnostics_delay_from_initialization_options() {
+        let options: LSPAny = serde_json::json!({ "diagnosticsDelayMs": 500 });
+        assert_eq!(diagnostics_delay(Some(&options)), Some(Duration::from_millis(500)));
+
+        let options: LSPAny = serde_json::json!({ "diagnosticsDelayMs": "fast" });
+        assert_eq!(diagnostics_delay(Some(&options)), None);
+        assert_eq!(diagnostics_delay(Some(&serde_json::json!({}))), None);
+        assert_eq!(diagnostics_delay(None), None);
+    }
+
+    #[test]
+    fn documents_keep_the_last_resolved_graph() {
+        let mut doc = Document {
+            uri: "file:///a.sysml".to_string(),
+            content: "part def A;".to_string(),
+            version: 1,
+            generation: 0,
+            graph: None,
+            graph_generation: 0,
+        };
+        doc.store_graph(Some(ModelGraph::new()));
+        assert!(doc.current_graph().is_some());
+
+        // An edit makes the graph stale, and a failed parse does not drop it
+        doc.generation += 1;
+        doc.store_graph(None);
+        assert!(doc.graph.is_some());
+        assert!(doc.current_graph().is_none());
+
+        doc.store_graph(Some(ModelGraph::new()));
+        assert_eq!(doc.graph_generation, 1);
+        assert!(doc.current_graph().is_some());
+    }
 }
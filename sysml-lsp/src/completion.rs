@@ -0,0 +1,429 @@
+//! Completion candidates for SysML documents.
+//!
+//! Keywords are always offered. After `:`, `:>`, `:>>` or `import`, the
+//! names of classifiers and packages in the document's resolved graph are
+//! offered as well (with the `linking` feature), and after `Qualifier::` the
+//! members of that namespace. Scoping is approximate: every named classifier
+//! in the graph, including merged library packages, counts as visible, but
+//! names owned by the enclosing namespaces and of the definition kind that
+//! matches the declaration (`PartDefinition` after `part x :`) sort first.
+
+#[cfg(feature = "linking")]
+use std::collections::BTreeMap;
+
+#[cfg(feature = "linking")]
+use sysml_core::{Element, ElementId, ElementKind, ModelGraph};
+
+/// Keywords offered as completions, in the order they are listed.
+pub const COMPLETION_KEYWORDS: &[&str] = &[
+    "package", "import", "alias", "part", "attribute", "item", "port", "connection", "interface",
+    "action", "state", "transition", "requirement", "constraint", "calc", "case", "analysis",
+    "verification", "use", "view", "viewpoint", "occurrence", "allocation", "flow", "enum", "def",
+    "abstract", "ref", "in", "out", "inout", "private", "protected", "public", "doc", "comment",
+    "entry", "exit", "do", "first", "accept", "then", "if", "subject", "satisfy", "verify",
+    "redefines", "subsets", "specializes",
+];
+
+/// What the text before the cursor asks to be completed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompletionContext {
+    /// Anywhere else: only keywords apply.
+    Keyword,
+    /// After `:`, `:>` or `:>>`: a type or feature to reference.
+    TypeReference {
+        /// The keyword of the declaration, such as `part` in `part engine :`
+        /// or `part def Car :>`.
+        keyword: Option<String>,
+    },
+    /// After `import`: a package or element to import.
+    Import,
+    /// After `Qualifier::`: a member of the named namespace.
+    Member {
+        /// The (possibly qualified) name before the last `::`.
+        qualifier: String,
+    },
+}
+
+impl CompletionContext {
+    /// Find the completion context at a byte offset in source text.
+    ///
+    /// A partially typed name before the cursor is skipped, so
+    /// `part x : Eng|` is still a type reference and `import ISQ::Len|` a
+    /// member of `ISQ`. An offset past the end or inside a character is
+    /// clamped back to a character boundary.
+    pub fn at(source: &str, offset: usize) -> Self {
+        let mut offset = offset.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let is_name_char = |c: char| c.is_alphanumeric() || c == '_';
+        let before = source[..offset].trim_end_matches(is_name_char);
+        if let Some(prefix) = before.strip_suffix("::") {
+            let start = prefix.trim_end_matches(|c: char| is_name_char(c) || c == ':').len();
+            let qualifier = prefix[start..].trim_start_matches(':');
+            if !qualifier.is_empty() {
+                return CompletionContext::Member {
+                    qualifier: qualifier.to_string(),
+                };
+            }
+        }
+        let before = before.trim_end();
+
+        let declaration = [":>>", ":>", ":"].iter().find_map(|op| before.strip_suffix(op));
+        if let Some(declaration) = declaration {
+            CompletionContext::TypeReference {
+                keyword: declaration_keyword(declaration).map(String::from),
+            }
+        } else if before
+            .strip_suffix("import")
+            .is_some_and(|prefix| !prefix.ends_with(is_name_char))
+        {
+            CompletionContext::Import
+        } else {
+            CompletionContext::Keyword
+        }
+    }
+}
+
+/// The keyword of the declaration that text ends in, skipping the declared
+/// name and `def`: `part` for both `in part engine[4]` and `part def Car`.
+fn declaration_keyword(text: &str) -> Option<&str> {
+    let statement = text.rsplit([';', '{', '}', '\n']).next().unwrap_or(text);
+    let mut words = statement.split_whitespace().rev().peekable();
+    if words.peek().is_some_and(|word| !COMPLETION_KEYWORDS.contains(word)) {
+        words.next();
+    }
+    if words.peek() == Some(&"def") {
+        words.next();
+    }
+    words.next().filter(|word| COMPLETION_KEYWORDS.contains(word) && *word != "def")
+}
+
+/// Completion item kinds (subset of LSP spec).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionItemKind {
+    Field = 5,
+    Class = 7,
+    Module = 9,
+    Keyword = 14,
+}
+
+/// A completion candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionItem {
+    /// The text to insert and show.
+    pub label: String,
+    /// The kind of this item.
+    pub kind: CompletionItemKind,
+    /// The qualified name of a named element.
+    pub detail: Option<String>,
+    /// The key clients sort by instead of the label.
+    pub sort_text: String,
+}
+
+impl CompletionItem {
+    /// Create a keyword completion, which sorts after names.
+    pub fn keyword(keyword: &str) -> Self {
+        CompletionItem {
+            label: keyword.to_string(),
+            kind: CompletionItemKind::Keyword,
+            detail: None,
+            sort_text: format!("9_{}", keyword),
+        }
+    }
+}
+
+/// Collect completion candidates for a context, in sort order.
+///
+/// `scope` is the element at the cursor (see
+/// [`find_element_at_offset`](crate::find_element_at_offset)). Names of the
+/// definition kind that matches the declaration keyword sort first, and
+/// within that, names owned by the scope or one of its owners. Names are
+/// deduplicated by label, keeping the best-ranked and then the shortest
+/// qualified name as detail. Keywords follow the names, except for members
+/// after `::`. Without a graph, as for a document that does not parse, only
+/// keywords are offered.
+#[cfg(feature = "linking")]
+pub fn completion_candidates(
+    graph: Option<&ModelGraph>,
+    context: &CompletionContext,
+    scope: Option<&ElementId>,
+) -> Vec<CompletionItem> {
+    let keywords = || COMPLETION_KEYWORDS.iter().map(|keyword| CompletionItem::keyword(keyword));
+    let graph = match (graph, context) {
+        (_, CompletionContext::Keyword) => return keywords().collect(),
+        (Some(graph), _) => graph,
+        (None, CompletionContext::Member { .. }) => return Vec::new(),
+        (None, _) => return keywords().collect(),
+    };
+
+    let candidates: Vec<&Element> = match context {
+        CompletionContext::Member { qualifier } => match namespace(graph, qualifier) {
+            Some(namespace) => graph
+                .children_of(&namespace.id)
+                .filter(|child| !child.kind.is_relationship())
+                .collect(),
+            None => Vec::new(),
+        },
+        _ => graph
+            .elements
+            .values()
+            .filter(|element| {
+                element.kind.is_classifier() || (*context == CompletionContext::Import && is_package(element))
+            })
+            .collect(),
+    };
+
+    // The scope and its owners, bounded in case of an ownership cycle
+    let mut enclosing: Vec<&ElementId> = Vec::new();
+    let mut next = scope;
+    while let Some(id) = next {
+        if enclosing.len() > graph.element_count() {
+            break;
+        }
+        enclosing.push(id);
+        next = graph.get_element(id).and_then(|element| element.owner.as_ref());
+    }
+    let preferred = match context {
+        CompletionContext::TypeReference { keyword: Some(keyword) } => definition_kind(keyword),
+        _ => None,
+    };
+    let rank = |element: &Element| {
+        let in_scope = element.owner.as_ref().is_some_and(|owner| enclosing.contains(&owner));
+        let is_preferred = preferred.as_ref() == Some(&element.kind);
+        match (is_preferred, in_scope) {
+            (true, true) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (false, false) => 3,
+        }
+    };
+
+    let mut names: BTreeMap<&str, (u8, CompletionItem)> = BTreeMap::new();
+    for element in candidates {
+        let name = match &element.name {
+            Some(name) if !name.is_empty() => name,
+            _ => continue,
+        };
+        let rank = rank(element);
+        let item = CompletionItem {
+            label: name.clone(),
+            kind: if is_package(element) {
+                CompletionItemKind::Module
+            } else if element.kind.is_classifier() {
+                CompletionItemKind::Class
+            } else {
+                CompletionItemKind::Field
+            },
+            detail: element.qname.as_ref().map(|qname| qname.to_string()),
+            sort_text: format!("{}_{}", rank, name),
+        };
+        let better = |(existing_rank, existing): &(u8, CompletionItem)| match (&item.detail, &existing.detail) {
+            (Some(new), Some(old)) => (rank, new.len(), new) < (*existing_rank, old.len(), old),
+            (new, old) => rank < *existing_rank || (rank == *existing_rank && new.is_some() && old.is_none()),
+        };
+        match names.get(name.as_str()) {
+            Some(existing) if !better(existing) => {}
+            _ => {
+                names.insert(name, (rank, item));
+            }
+        }
+    }
+
+    let mut items: Vec<CompletionItem> = names.into_values().map(|(_, item)| item).collect();
+    items.sort_by(|a, b| a.sort_text.cmp(&b.sort_text));
+    if !matches!(context, CompletionContext::Member { .. }) {
+        items.extend(keywords());
+    }
+    items
+}
+
+#[cfg(feature = "linking")]
+fn is_package(element: &Element) -> bool {
+    matches!(element.kind, ElementKind::Package | ElementKind::LibraryPackage)
+}
+
+/// The namespace a qualifier names: the element with that qualified name,
+/// or else one named by its last segment, as for an imported name.
+#[cfg(feature = "linking")]
+fn namespace<'a>(graph: &'a ModelGraph, qualifier: &str) -> Option<&'a Element> {
+    let last = qualifier.rsplit("::").next().unwrap_or(qualifier);
+    graph.get_by_qname_str(qualifier).or_else(|| {
+        graph
+            .elements
+            .values()
+            .find(|element| element.name.as_deref() == Some(last) && !element.kind.is_relationship())
+    })
+}
+
+/// The definition kind that typically types a usage declared with `keyword`.
+#[cfg(feature = "linking")]
+fn definition_kind(keyword: &str) -> Option<ElementKind> {
+    let kind = match keyword {
+        "part" => ElementKind::PartDefinition,
+        "attribute" => ElementKind::AttributeDefinition,
+        "item" => ElementKind::ItemDefinition,
+        "port" => ElementKind::PortDefinition,
+        "connection" => ElementKind::ConnectionDefinition,
+        "interface" => ElementKind::InterfaceDefinition,
+        "action" => ElementKind::ActionDefinition,
+        "state" => ElementKind::StateDefinition,
+        "requirement" => ElementKind::RequirementDefinition,
+        "constraint" => ElementKind::ConstraintDefinition,
+        "calc" => ElementKind::CalculationDefinition,
+        "occurrence" => ElementKind::OccurrenceDefinition,
+        "allocation" => ElementKind::AllocationDefinition,
+        "flow" => ElementKind::FlowDefinition,
+        "enum" => ElementKind::EnumerationDefinition,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(keyword: Option<&str>) -> CompletionContext {
+        CompletionContext::TypeReference {
+            keyword: keyword.map(String::from),
+        }
+    }
+
+    fn member(qualifier: &str) -> CompletionContext {
+        CompletionContext::Member {
+            qualifier: qualifier.to_string(),
+        }
+    }
+
+    #[test]
+    fn context_after_typing_and_import() {
+        let cases = [
+            ("part engine : ", reference(Some("part"))),
+            ("    in part engine[4]:Eng", reference(Some("part"))),
+            ("part def Sports :> Ca", reference(Some("part"))),
+            ("attribute :>> mass", reference(Some("attribute"))),
+            ("part a;\nx : ", reference(None)),
+            ("part x : ISQ::Len", member("ISQ")),
+            ("private import ScalarValues::", member("ScalarValues")),
+            ("part x : A::B::", member("A::B")),
+            ("    import ", CompletionContext::Import),
+            ("part ", CompletionContext::Keyword),
+            ("part reimport ", CompletionContext::Keyword),
+            ("", CompletionContext::Keyword),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(CompletionContext::at(text, text.len()), expected, "{:?}", text);
+        }
+        // Offsets inside a character or past the end do not panic
+        assert_eq!(CompletionContext::at("a : é", 5), reference(None));
+        assert_eq!(CompletionContext::at("a : é", 100), reference(None));
+    }
+
+    #[cfg(feature = "linking")]
+    mod linking {
+        use super::*;
+
+        fn add(graph: &mut ModelGraph, kind: ElementKind, name: &str, owner: Option<&ElementId>) -> ElementId {
+            let element = Element::new_with_kind(kind).with_name(name);
+            match owner {
+                Some(owner) => graph.add_element(element.with_owner(owner.clone())),
+                None => graph.add_element(element),
+            }
+        }
+
+        /// Returns the graph and its `Vehicles::Car` definition.
+        fn graph() -> (ModelGraph, ElementId) {
+            let mut graph = ModelGraph::new();
+            let vehicles = add(&mut graph, ElementKind::Package, "Vehicles", None);
+            let car = add(&mut graph, ElementKind::PartDefinition, "Car", Some(&vehicles));
+            add(&mut graph, ElementKind::PartUsage, "engine", Some(&car));
+            add(&mut graph, ElementKind::PartDefinition, "Engine", Some(&vehicles));
+            add(&mut graph, ElementKind::AttributeDefinition, "Speed", Some(&vehicles));
+            let parts = add(&mut graph, ElementKind::Package, "Parts", Some(&vehicles));
+            add(&mut graph, ElementKind::PartDefinition, "Engine", Some(&parts));
+            let scalars = add(&mut graph, ElementKind::LibraryPackage, "ScalarValues", None);
+            add(&mut graph, ElementKind::AttributeDefinition, "Real", Some(&scalars));
+            add(&mut graph, ElementKind::PartDefinition, "Wheel", Some(&scalars));
+            graph.compute_qualified_names();
+            (graph, car)
+        }
+
+        fn labels(items: &[CompletionItem]) -> Vec<&str> {
+            items
+                .iter()
+                .filter(|item| item.kind != CompletionItemKind::Keyword)
+                .map(|item| item.label.as_str())
+                .collect()
+        }
+
+        #[test]
+        fn type_references_offer_deduplicated_classifiers() {
+            let (graph, _) = graph();
+            let items = completion_candidates(Some(&graph), &reference(None), None);
+            assert_eq!(labels(&items), ["Car", "Engine", "Real", "Speed", "Wheel"]);
+            assert_eq!(items[1].kind, CompletionItemKind::Class);
+            assert_eq!(items[1].detail.as_deref(), Some("Vehicles::Engine"));
+            assert_eq!(items.len(), 5 + COMPLETION_KEYWORDS.len());
+        }
+
+        #[test]
+        fn scope_and_declaration_kind_sort_first() {
+            let (graph, car) = graph();
+            // Inside Car after `part x :`: part definitions in scope, other
+            // part definitions, other names in scope, the rest
+            let items = completion_candidates(Some(&graph), &reference(Some("part")), Some(&car));
+            assert_eq!(labels(&items), ["Car", "Engine", "Wheel", "Speed", "Real"]);
+            assert!(items[2].sort_text < items[3].sort_text);
+            assert!(items[4].sort_text < items[5].sort_text, "keywords sort last");
+
+            let items = completion_candidates(Some(&graph), &reference(Some("attribute")), None);
+            assert_eq!(labels(&items), ["Real", "Speed", "Car", "Engine", "Wheel"]);
+        }
+
+        #[test]
+        fn members_follow_a_qualifier() {
+            let (graph, _) = graph();
+            let items = completion_candidates(Some(&graph), &member("Vehicles"), None);
+            assert_eq!(labels(&items), ["Car", "Engine", "Parts", "Speed"]);
+            assert_eq!(items.len(), 4, "no keywords after '::'");
+            assert_eq!(items[2].kind, CompletionItemKind::Module);
+
+            let items = completion_candidates(Some(&graph), &member("Vehicles::Car"), None);
+            assert_eq!(labels(&items), ["engine"]);
+            assert_eq!(items[0].kind, CompletionItemKind::Field);
+
+            // A qualifier that is not a full qualified name, as after an
+            // import, is looked up by its last segment
+            let items = completion_candidates(Some(&graph), &member("Other::Parts"), None);
+            assert_eq!(labels(&items), ["Engine"]);
+            assert!(completion_candidates(Some(&graph), &member("Missing"), None).is_empty());
+            assert!(completion_candidates(None, &member("Vehicles"), None).is_empty());
+        }
+
+        #[test]
+        fn imports_offer_packages_too() {
+            let (graph, _) = graph();
+            let items = completion_candidates(Some(&graph), &CompletionContext::Import, None);
+            assert_eq!(
+                labels(&items),
+                ["Car", "Engine", "Parts", "Real", "ScalarValues", "Speed", "Vehicles", "Wheel"]
+            );
+            let package = items.iter().find(|item| item.label == "Vehicles").unwrap();
+            assert_eq!(package.kind, CompletionItemKind::Module);
+        }
+
+        #[test]
+        fn only_keywords_without_graph_or_reference() {
+            let (graph, _) = graph();
+            for items in [
+                completion_candidates(None, &reference(None), None),
+                completion_candidates(Some(&graph), &CompletionContext::Keyword, None),
+            ] {
+                assert_eq!(items.len(), COMPLETION_KEYWORDS.len());
+                assert!(items.iter().all(|item| item.kind == CompletionItemKind::Keyword));
+            }
+        }
+    }
+}
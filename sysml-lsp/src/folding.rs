@@ -0,0 +1,173 @@
+//! Folding ranges for SysML documents.
+//!
+//! Ranges come from two sources: the spans of declarations, from whichever
+//! parse of the document is at hand, and a scan of the text for `/* ... */`
+//! comments. Neither needs the document to resolve, so a file with errors
+//! still folds as far as its spans go.
+
+use sysml_span::{LineIndex, Span};
+
+/// What a folding range covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldingRangeKind {
+    /// A block comment.
+    Comment,
+    /// A declaration body.
+    Region,
+}
+
+/// A foldable range of whole lines (0-indexed, end inclusive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoldingRange {
+    /// The first line of the range.
+    pub start_line: u32,
+    /// The last line of the range.
+    pub end_line: u32,
+    /// What the range covers.
+    pub kind: FoldingRangeKind,
+}
+
+/// Compute the folding ranges of a document.
+///
+/// Each span covering more than one line becomes a region, from the line of
+/// its start to the line of its last character, and each block comment
+/// covering more than one line becomes a comment range. Ranges are sorted by
+/// start line, with duplicates removed; a comment and a region on the same
+/// lines, such as a `comment` declaration, fold as a comment.
+///
+/// # Example
+///
+/// ```
+/// use sysml_lsp::{folding_ranges, FoldingRangeKind};
+/// use sysml_span::Span;
+///
+/// let source = "package P {\n    /* a\n       b */\n}\n";
+/// let ranges = folding_ranges([&Span::new("p.sysml", 0, source.len())], source);
+/// assert_eq!((ranges[0].start_line, ranges[0].end_line), (0, 3));
+/// assert_eq!(ranges[1].kind, FoldingRangeKind::Comment);
+/// ```
+pub fn folding_ranges<'a>(spans: impl IntoIterator<Item = &'a Span>, source: &str) -> Vec<FoldingRange> {
+    let index = LineIndex::new(source);
+    let line_range = |start: usize, end: usize| {
+        let (start_line, _) = index.line_col(start);
+        let (end_line, _) = index.line_col(end.saturating_sub(1).max(start));
+        (start_line - 1, end_line - 1)
+    };
+
+    let mut ranges: Vec<FoldingRange> = block_comments(source)
+        .into_iter()
+        .map(|(start, end)| (line_range(start, end), FoldingRangeKind::Comment))
+        .chain(
+            spans
+                .into_iter()
+                .filter(|span| span.start < span.end && span.end <= source.len())
+                .map(|span| (line_range(span.start, span.end), FoldingRangeKind::Region)),
+        )
+        .filter(|((start_line, end_line), _)| end_line > start_line)
+        .map(|((start_line, end_line), kind)| FoldingRange {
+            start_line,
+            end_line,
+            kind,
+        })
+        .collect();
+
+    // Comments sort before regions, so they survive deduplication
+    ranges.sort_by_key(|range| (range.start_line, range.end_line, range.kind != FoldingRangeKind::Comment));
+    ranges.dedup_by_key(|range| (range.start_line, range.end_line));
+    ranges
+}
+
+/// The byte ranges of the `/* ... */` comments in a source text.
+///
+/// Line comments, strings and quoted names are skipped, so comment markers
+/// inside them do not count. An unterminated comment runs to the end.
+fn block_comments(source: &str) -> Vec<(usize, usize)> {
+    let bytes = source.as_bytes();
+    let mut comments = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = source[i + 2..].find("*/").map_or(source.len(), |at| i + 2 + at + 2);
+                comments.push((i, end));
+                i = end;
+            }
+            // `//*` opens a block comment too
+            b'/' if bytes.get(i + 1) == Some(&b'/') && bytes.get(i + 2) != Some(&b'*') => {
+                i = source[i..].find('\n').map_or(source.len(), |at| i + at);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => i += 1,
+            quote @ (b'"' | b'\'') => {
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j] != quote {
+                    j += if bytes[j] == b'\\' { 2 } else { 1 };
+                }
+                i = j + 1;
+            }
+            _ => i += 1,
+        }
+    }
+    comments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(ranges: &[FoldingRange]) -> Vec<(u32, u32, FoldingRangeKind)> {
+        ranges.iter().map(|r| (r.start_line, r.end_line, r.kind)).collect()
+    }
+
+    #[test]
+    fn multi_line_spans_fold_from_start_to_end_line() {
+        let source = "package P {\n    part def A {\n        attribute x;\n    }\n    part b;\n}\n";
+        let package = Span::new("p.sysml", 0, source.len());
+        let def_start = source.find("part def").unwrap();
+        let def = Span::new("p.sysml", def_start, source.find("    part b").unwrap());
+        let part_start = source.find("part b").unwrap();
+        let part = Span::new("p.sysml", part_start, part_start + "part b;".len());
+
+        let ranges = folding_ranges([&package, &def, &part, &def], source);
+        assert_eq!(
+            lines(&ranges),
+            [(0, 5, FoldingRangeKind::Region), (1, 3, FoldingRangeKind::Region)]
+        );
+    }
+
+    #[test]
+    fn block_comments_fold_outside_strings_and_line_comments() {
+        let source = concat!(
+            "/* header\n",
+            "   more */\n",
+            "// not /* a comment\n",
+            "doc /* one line */\n",
+            "attribute s = \"/* no\n",
+            "*/\";\n",
+            "//* also\n",
+            "    a comment */\n",
+            "/* unterminated\n",
+            "   to the end",
+        );
+        let ranges = folding_ranges([], source);
+        assert_eq!(
+            lines(&ranges),
+            [
+                (0, 1, FoldingRangeKind::Comment),
+                (6, 7, FoldingRangeKind::Comment),
+                (8, 9, FoldingRangeKind::Comment),
+            ]
+        );
+    }
+
+    #[test]
+    fn comment_spans_fold_as_comments() {
+        let source = "comment /* a\n   b */\n";
+        let comment = Span::new("p.sysml", 0, source.len() - 1);
+        let ranges = folding_ranges([&comment], source);
+        assert_eq!(lines(&ranges), [(0, 1, FoldingRangeKind::Comment)]);
+
+        // Spans out of bounds are ignored rather than panicking
+        let ranges = folding_ranges([&Span::new("p.sysml", 0, 100)], source);
+        assert_eq!(ranges.len(), 1);
+    }
+}
@@ -8,10 +8,22 @@
 //!
 //! ## Features
 //!
-//! - `linking`: Enable sysml-core integration for semantic symbol linking
+//! - `linking`: Enable sysml-core integration for semantic symbol linking,
+//!   workspace symbol search, and go-to-definition lookups and name
+//!   completions on resolved graphs
 
+mod completion;
+mod folding;
+
+#[cfg(feature = "linking")]
+pub use completion::completion_candidates;
+pub use completion::{CompletionContext, CompletionItem, CompletionItemKind, COMPLETION_KEYWORDS};
+pub use folding::{folding_ranges, FoldingRange, FoldingRangeKind};
+
+#[cfg(feature = "linking")]
+use sysml_core::{resolution::resolved_props, Element, ModelGraph};
 use sysml_id::ElementId;
-use sysml_span::{Diagnostic as SysmlDiagnostic, Severity as SysmlSeverity, Span};
+use sysml_span::{Diagnostic as SysmlDiagnostic, LineIndex, Severity as SysmlSeverity, Span};
 
 /// LSP diagnostic severity levels.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -46,6 +58,44 @@ impl Position {
     pub fn new(line: u32, character: u32) -> Self {
         Position { line, character }
     }
+
+    /// Convert this position to a byte offset in source text.
+    ///
+    /// Characters are counted in UTF-16 code units. Positions past the end of
+    /// a line clamp to the end of that line, and lines past the end of the
+    /// source to the end of the source. Builds a [`LineIndex`] for `source`;
+    /// use [`to_offset_in`](Self::to_offset_in) to reuse one.
+    pub fn to_offset(self, source: &str) -> usize {
+        self.to_offset_in(&LineIndex::new(source), source)
+    }
+
+    /// Convert this position to a byte offset using an existing index of `source`.
+    pub fn to_offset_in(self, index: &LineIndex, source: &str) -> usize {
+        index
+            .offset_at_utf16(self.line + 1, self.character + 1, source)
+            .unwrap_or(source.len())
+    }
+}
+
+/// Apply a document content change, as sent with incremental sync.
+///
+/// A change with a range replaces that range, whose positions are mapped to
+/// byte offsets with a [`LineIndex`]; a change without one replaces the
+/// whole text. Positions past the end clamp to the end of the text, and a
+/// range whose end comes before its start deletes nothing.
+pub fn apply_content_change(text: &mut String, range: Option<Range>, new_text: &str) {
+    let range = match range {
+        Some(range) => range,
+        None => {
+            text.clear();
+            text.push_str(new_text);
+            return;
+        }
+    };
+    let index = LineIndex::new(text);
+    let start = range.start.to_offset_in(&index, text);
+    let end = range.end.to_offset_in(&index, text).max(start);
+    text.replace_range(start..end, new_text);
 }
 
 /// A range in a text document.
@@ -65,32 +115,39 @@ impl Range {
 
     /// Create a range from a span and source text.
     ///
-    /// Converts byte offsets to line/character positions.
+    /// Start and end come from the span's line/column fields when it has
+    /// them (`line`/`col` and `end_line`/`end_col`), and from its byte offsets
+    /// otherwise, so multi-line spans keep their real end. Those columns count
+    /// bytes, so characters are recounted in UTF-16 code units as LSP requires.
+    ///
+    /// This builds a [`LineIndex`] of the source; converting many spans of one
+    /// document should use [`from_span_with_index`](Self::from_span_with_index).
     pub fn from_span(span: &Span, source: &str) -> Self {
-        let start = offset_to_position(span.start, source);
-        let end = offset_to_position(span.end, source);
+        Self::from_span_with_index(span, &LineIndex::new(source), source)
+    }
+
+    /// Create a range from a span, using a line index built from `source`.
+    pub fn from_span_with_index(span: &Span, index: &LineIndex, source: &str) -> Self {
+        let start = span_position(span.line, span.col, span.start, index, source);
+        let end = span_position(span.end_line, span.end_col, span.end, index, source);
         Range { start, end }
     }
 }
 
-/// Convert a byte offset to a line/character position.
-fn offset_to_position(offset: usize, source: &str) -> Position {
-    let mut line = 0u32;
-    let mut col = 0u32;
-
-    for (i, ch) in source.char_indices() {
-        if i >= offset {
-            break;
-        }
-        if ch == '\n' {
-            line += 1;
-            col = 0;
-        } else {
-            col += 1;
-        }
-    }
+/// The position of a 1-indexed line and byte column, or of the byte offset
+/// when either is missing or the line is out of range.
+fn span_position(line: Option<u32>, col: Option<u32>, offset: usize, index: &LineIndex, source: &str) -> Position {
+    let offset = match (line, col) {
+        (Some(line), Some(col)) => index.offset_at(line, col).unwrap_or(offset),
+        _ => offset,
+    };
+    offset_to_position(offset, index, source)
+}
 
-    Position::new(line, col)
+/// Convert a byte offset to a line/character position.
+fn offset_to_position(offset: usize, index: &LineIndex, source: &str) -> Position {
+    let (line, col) = index.line_col_utf16(offset, source);
+    Position::new(line - 1, col - 1)
 }
 
 /// An LSP diagnostic.
@@ -113,10 +170,16 @@ pub struct LspDiagnostic {
 impl LspDiagnostic {
     /// Convert from a SysML diagnostic.
     pub fn from_sysml(diag: &SysmlDiagnostic, source_text: &str) -> Self {
+        Self::from_sysml_with_index(diag, &LineIndex::new(source_text), source_text)
+    }
+
+    /// Convert from a SysML diagnostic, using a line index built from
+    /// `source_text`, so that the diagnostics of one document share it.
+    pub fn from_sysml_with_index(diag: &SysmlDiagnostic, index: &LineIndex, source_text: &str) -> Self {
         let range = diag
             .span
             .as_ref()
-            .map(|s| Range::from_span(s, source_text))
+            .map(|s| Range::from_span_with_index(s, index, source_text))
             .unwrap_or_default();
 
         LspDiagnostic {
@@ -234,24 +297,405 @@ pub struct LinkedSymbol {
 }
 
 /// Convert SysML element kind to LSP symbol kind.
+///
+/// Packages become modules, definitions classes and usages fields, with
+/// states, transitions, actions and requirements singled out.
 #[cfg(feature = "linking")]
 pub fn element_kind_to_symbol_kind(kind: &sysml_core::ElementKind) -> SymbolKind {
     use sysml_core::ElementKind;
     match kind {
-        ElementKind::Package => SymbolKind::Package,
-        ElementKind::PartUsage | ElementKind::PartDefinition => SymbolKind::Class,
+        ElementKind::Package | ElementKind::LibraryPackage => SymbolKind::Module,
         ElementKind::RequirementUsage | ElementKind::RequirementDefinition => SymbolKind::Interface,
         ElementKind::VerificationCaseUsage | ElementKind::VerificationCaseDefinition => SymbolKind::Method,
-        ElementKind::StateDefinition => SymbolKind::Class,
-        ElementKind::StateUsage => SymbolKind::Enum,
+        ElementKind::StateUsage => SymbolKind::EnumMember,
         ElementKind::TransitionUsage => SymbolKind::Event,
-        ElementKind::ActionUsage | ElementKind::ActionDefinition => SymbolKind::Function,
-        ElementKind::AttributeUsage | ElementKind::AttributeDefinition => SymbolKind::Property,
+        ElementKind::ActionUsage => SymbolKind::Method,
+        ElementKind::ActionDefinition => SymbolKind::Function,
+        ElementKind::AttributeUsage => SymbolKind::Field,
+        ElementKind::AttributeDefinition => SymbolKind::Struct,
+        ElementKind::EnumerationDefinition => SymbolKind::Enum,
         ElementKind::Documentation => SymbolKind::File,
+        kind if kind.is_definition() => SymbolKind::Class,
+        kind if kind.is_usage() => SymbolKind::Field,
         _ => SymbolKind::Object,
     }
 }
 
+/// The types of a feature as a symbol detail, such as `: Vehicle`.
+///
+/// Resolved typings give the type's name; unresolved ones the name as
+/// written. Returns None for elements without typings.
+#[cfg(feature = "linking")]
+fn type_detail(graph: &ModelGraph, element: &Element) -> Option<String> {
+    use sysml_core::resolution::unresolved_props;
+
+    let types: Vec<String> = graph
+        .typings_of_feature(&element.id)
+        .filter_map(|typing| match typing.get_ref(resolved_props::TYPE) {
+            Some(id) => graph.get_element(id)?.name.clone(),
+            None => typing.get_str(unresolved_props::TYPE).map(String::from),
+        })
+        .collect();
+    if types.is_empty() {
+        None
+    } else {
+        Some(format!(": {}", types.join(", ")))
+    }
+}
+
+/// Build the outline of `file` from a model graph.
+///
+/// Every named element with a span in the file becomes a symbol, nested under
+/// its nearest owning symbol, so memberships and other unnamed elements in
+/// between are skipped. Relationships, comments and elements without a span
+/// in the file are left out. Siblings are ordered by position, the selection
+/// range covers the first occurrence of the name in the span, and the detail
+/// lists the element's types.
+#[cfg(feature = "linking")]
+pub fn document_symbols(graph: &ModelGraph, file: &str, source: &str) -> Vec<DocumentSymbol> {
+    use std::collections::BTreeMap;
+    use sysml_core::ElementKind;
+
+    let symbols: BTreeMap<&ElementId, &Span> = graph
+        .elements
+        .values()
+        .filter(|element| {
+            element.name.as_deref().is_some_and(|name| !name.is_empty())
+                && !element.kind.is_relationship()
+                && !matches!(element.kind, ElementKind::Documentation | ElementKind::Comment)
+        })
+        .filter_map(|element| Some((&element.id, element.spans.iter().find(|span| span.file == file)?)))
+        .collect();
+
+    // Group symbols under their nearest owning symbol, with None for the top level
+    let mut children: BTreeMap<Option<&ElementId>, Vec<&ElementId>> = BTreeMap::new();
+    for id in symbols.keys().copied() {
+        let mut parent = None;
+        let mut owner = graph.get_element(id).and_then(|element| element.owner.as_ref());
+        // Bounded in case of an ownership cycle
+        for _ in 0..graph.element_count() {
+            match owner {
+                Some(owner_id) if symbols.contains_key(owner_id) => {
+                    parent = Some(owner_id);
+                    break;
+                }
+                Some(owner_id) => owner = graph.get_element(owner_id).and_then(|element| element.owner.as_ref()),
+                None => break,
+            }
+        }
+        children.entry(parent).or_default().push(id);
+    }
+    for ids in children.values_mut() {
+        ids.sort_by_key(|id| (symbols[id].start, *id));
+    }
+
+    fn build(
+        parent: Option<&ElementId>,
+        graph: &ModelGraph,
+        symbols: &BTreeMap<&ElementId, &Span>,
+        children: &BTreeMap<Option<&ElementId>, Vec<&ElementId>>,
+        index: &LineIndex,
+        source: &str,
+    ) -> Vec<DocumentSymbol> {
+        let ids = match children.get(&parent) {
+            Some(ids) => ids,
+            None => return Vec::new(),
+        };
+        ids.iter()
+            .filter_map(|id| {
+                let element = graph.get_element(id)?;
+                let name = element.name.clone()?;
+                let span = symbols[id];
+                let name_start = source
+                    .get(span.start..span.end)
+                    .and_then(|text| text.find(name.as_str()))
+                    .map(|at| span.start + at);
+                let selection_range = match name_start {
+                    Some(start) => {
+                        Range::from_span_with_index(&Span::new(span.file.as_str(), start, start + name.len()), index, source)
+                    }
+                    None => Range::from_span_with_index(span, index, source),
+                };
+                Some(DocumentSymbol {
+                    name,
+                    detail: type_detail(graph, element),
+                    kind: element_kind_to_symbol_kind(&element.kind),
+                    range: Range::from_span_with_index(span, index, source),
+                    selection_range,
+                    children: build(Some(*id), graph, symbols, children, index, source),
+                })
+            })
+            .collect()
+    }
+    build(None, graph, &symbols, &children, &LineIndex::new(source), source)
+}
+
+/// Search a graph for symbols whose name contains `query`.
+///
+/// Matches come from [`sysml_query::find_by_name_contains`], with the same
+/// elements left out as in [`document_symbols`]. Each symbol is located at
+/// its first span; `read_source` is asked once per file for the text to
+/// compute ranges from, and symbols in files it cannot supply are skipped.
+/// The container name is the qualified name, or else the name, of the
+/// nearest named owner. Results are sorted by name, then location.
+#[cfg(feature = "linking")]
+pub fn workspace_symbols(
+    graph: &ModelGraph,
+    query: &str,
+    mut read_source: impl FnMut(&str) -> Option<String>,
+) -> Vec<SymbolInformation> {
+    use std::collections::HashMap;
+    use sysml_core::ElementKind;
+
+    let mut sources: HashMap<&str, Option<(String, LineIndex)>> = HashMap::new();
+    let mut symbols = Vec::new();
+    for element in sysml_query::find_by_name_contains(graph, None, query) {
+        if element.kind.is_relationship() || matches!(element.kind, ElementKind::Documentation | ElementKind::Comment) {
+            continue;
+        }
+        let (name, span) = match (&element.name, element.spans.first()) {
+            (Some(name), Some(span)) if !name.is_empty() => (name, span),
+            _ => continue,
+        };
+        let source = sources
+            .entry(span.file.as_str())
+            .or_insert_with(|| read_source(&span.file).map(|source| {
+                let index = LineIndex::new(&source);
+                (source, index)
+            }));
+        let range = match source {
+            Some((source, index)) => Range::from_span_with_index(span, index, source),
+            None => continue,
+        };
+
+        // Bounded in case of an ownership cycle
+        let mut container_name = None;
+        let mut owner = element.owner.as_ref().and_then(|id| graph.get_element(id));
+        for _ in 0..graph.element_count() {
+            match owner {
+                Some(parent) if parent.name.is_some() => {
+                    container_name = parent.qname.as_ref().map(|qname| qname.to_string()).or(parent.name.clone());
+                    break;
+                }
+                Some(parent) => owner = parent.owner.as_ref().and_then(|id| graph.get_element(id)),
+                None => break,
+            }
+        }
+
+        symbols.push(SymbolInformation {
+            name: name.clone(),
+            kind: element_kind_to_symbol_kind(&element.kind),
+            location: Location {
+                uri: span.file.clone(),
+                range,
+            },
+            container_name,
+        });
+    }
+    symbols.sort_by(|a, b| {
+        let key = |symbol: &SymbolInformation| {
+            let start = symbol.location.range.start;
+            (symbol.name.clone(), symbol.location.uri.clone(), start.line, start.character)
+        };
+        key(a).cmp(&key(b))
+    });
+    symbols
+}
+
+/// Resolved reference props followed to a definition, in order of preference.
+#[cfg(feature = "linking")]
+const DEFINITION_PROPS: &[&str] = &[
+    resolved_props::TYPE,
+    resolved_props::GENERAL,
+    resolved_props::SUPERCLASSIFIER,
+    resolved_props::REDEFINED_FEATURE,
+    resolved_props::SUBSETTED_FEATURE,
+    resolved_props::REFERENCED_FEATURE,
+    resolved_props::CONJUGATED_PORT_DEFINITION,
+    resolved_props::MEMBER_ELEMENT,
+];
+
+/// Find the innermost element with a span in `file` that contains `offset`.
+///
+/// A span contains the offsets from its start up to and including its end,
+/// so a cursor right after a name still finds it.
+#[cfg(feature = "linking")]
+pub fn find_element_at_offset<'a>(graph: &'a ModelGraph, file: &str, offset: usize) -> Option<&'a Element> {
+    graph
+        .elements
+        .values()
+        .filter_map(|element| {
+            let len = element
+                .spans
+                .iter()
+                .filter(|span| span.file == file && span.start <= offset && offset <= span.end)
+                .map(|span| span.end - span.start)
+                .min()?;
+            Some((len, element))
+        })
+        .min_by_key(|(len, _)| *len)
+        .map(|(_, element)| element)
+}
+
+/// The element a reference at `element` points to in a resolved graph.
+///
+/// Follows the element's own resolved reference props (as on a FeatureTyping
+/// or Specialization), then the FeatureTypings of the element as a feature,
+/// then its Specializations.
+#[cfg(feature = "linking")]
+pub fn definition_target<'a>(graph: &'a ModelGraph, element: &Element) -> Option<&'a Element> {
+    let referenced = |element: &Element| {
+        DEFINITION_PROPS
+            .iter()
+            .find_map(|key| element.get_ref(key))
+            .and_then(|id| graph.get_element(id))
+    };
+    referenced(element)
+        .or_else(|| graph.typings_of_feature(&element.id).find_map(referenced))
+        .or_else(|| graph.specializations_of_specific(&element.id).find_map(referenced))
+}
+
+/// The first span of the definition referenced at `offset` in `file`.
+///
+/// Returns `None` when there is no element at the offset, it references
+/// nothing, or the target has no span (as for library elements loaded
+/// without source locations).
+#[cfg(feature = "linking")]
+pub fn find_definition<'a>(graph: &'a ModelGraph, file: &str, offset: usize) -> Option<&'a Span> {
+    let element = find_element_at_offset(graph, file, offset)?;
+    definition_target(graph, element)?.spans.first()
+}
+
+/// The spans of everything in a resolved graph that references `target`.
+///
+/// These are the elements whose resolved reference props (other than a
+/// membership's `memberElement`) point at the target, such as FeatureTypings,
+/// Specializations, Redefinitions and Subsettings, at their own span or
+/// else their owner's, plus the sources of graph relationships such as
+/// Satisfy and Verify that end at the target. The result is sorted by
+/// position and deduplicated, with the declaration first when
+/// `include_declaration` is set and the target has a span.
+#[cfg(feature = "linking")]
+pub fn find_references<'a>(graph: &'a ModelGraph, target: &'a Element, include_declaration: bool) -> Vec<&'a Span> {
+    let span_of = |id: &ElementId| graph.get_element(id).and_then(|element| element.spans.first());
+    let references = |element: &Element| {
+        DEFINITION_PROPS
+            .iter()
+            .filter(|key| **key != resolved_props::MEMBER_ELEMENT)
+            .any(|key| element.get_ref(key) == Some(&target.id))
+    };
+
+    let mut spans: Vec<&Span> = graph
+        .elements
+        .values()
+        .filter(|element| references(element))
+        .filter_map(|element| element.spans.first().or_else(|| element.owner.as_ref().and_then(span_of)))
+        .chain(
+            graph
+                .incoming(&target.id)
+                .filter(|relationship| relationship.kind != sysml_core::RelationshipKind::Owning)
+                .filter_map(|relationship| span_of(&relationship.source)),
+        )
+        .collect();
+    spans.sort_by(|a, b| (&a.file, a.start, a.end).cmp(&(&b.file, b.start, b.end)));
+    spans.dedup();
+
+    if let Some(declaration) = target.spans.first().filter(|_| include_declaration) {
+        spans.retain(|span| *span != declaration);
+        spans.insert(0, declaration);
+    }
+    spans
+}
+
+/// The element to describe when hovering at `offset` in `file`.
+///
+/// This is the innermost element at the offset when it is named. Otherwise
+/// (as for a FeatureTyping or a doc comment) it is the element it references,
+/// or failing that its owner.
+#[cfg(feature = "linking")]
+pub fn find_hover_element<'a>(graph: &'a ModelGraph, file: &str, offset: usize) -> Option<&'a Element> {
+    let element = find_element_at_offset(graph, file, offset)?;
+    if element.name.is_some() {
+        return Some(element);
+    }
+    definition_target(graph, element).or_else(|| element.owner.as_ref().and_then(|owner| graph.get_element(owner)))
+}
+
+/// Describe an element as Markdown for a hover.
+///
+/// The first line is the kind in bold, then the direction, the qualified
+/// name (or plain name), the feature's types as `: Type`, the direct
+/// supertypes as `:> General` and the multiplicity, in declaration order.
+/// The bodies of owned `doc` comments follow as paragraphs.
+#[cfg(feature = "linking")]
+pub fn hover_markdown(graph: &ModelGraph, element: &Element) -> String {
+    let name = |element: &Element| match (&element.qname, &element.name) {
+        (Some(qname), _) => qname.to_string(),
+        (None, Some(name)) => name.clone(),
+        (None, None) => String::new(),
+    };
+    let names = |targets: Vec<&Element>| {
+        targets
+            .into_iter()
+            .filter_map(|target| target.name.clone())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut text = format!("**{}**", element.kind.as_str());
+    if let Some(direction) = element.get_str("direction") {
+        text.push(' ');
+        text.push_str(direction);
+    }
+    let own_name = name(element);
+    if !own_name.is_empty() {
+        text.push(' ');
+        text.push_str(&own_name);
+    }
+    let types = names(
+        graph
+            .typings_of_feature(&element.id)
+            .filter_map(|typing| typing.get_ref(resolved_props::TYPE))
+            .filter_map(|id| graph.get_element(id))
+            .collect(),
+    );
+    let generals = names(
+        graph
+            .specializations_of_specific(&element.id)
+            .filter_map(|specialization| specialization.get_ref(resolved_props::GENERAL))
+            .filter_map(|id| graph.get_element(id))
+            .collect(),
+    );
+    if !types.is_empty() {
+        text.push_str(" : ");
+        text.push_str(&types);
+    }
+    if !generals.is_empty() {
+        text.push_str(" :> ");
+        text.push_str(&generals);
+    }
+    if let Some(lower) = element.get_int("multiplicity_lower") {
+        match element.get_int("multiplicity_upper") {
+            Some(upper) if upper == lower => text.push_str(&format!(" [{}]", lower)),
+            Some(upper) => text.push_str(&format!(" [{}..{}]", lower, upper)),
+            None => text.push_str(&format!(" [{}..*]", lower)),
+        }
+    }
+
+    let mut docs: Vec<&Element> = graph
+        .children_of(&element.id)
+        .filter(|child| child.kind == sysml_core::ElementKind::Documentation)
+        .collect();
+    docs.sort_by_key(|doc| doc.spans.first().map(|span| span.start));
+    for body in docs.iter().filter_map(|doc| doc.get_str("body")).map(str::trim) {
+        if !body.is_empty() {
+            text.push_str("\n\n");
+            text.push_str(body);
+        }
+    }
+    text
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -273,10 +717,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn content_changes_replace_ranges() {
+        let range = |start: (u32, u32), end: (u32, u32)| {
+            Some(Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1)))
+        };
+        let mut text = "part def Car;\npart wheel;\n".to_string();
+        apply_content_change(&mut text, range((0, 9), (0, 12)), "Vehicle");
+        assert_eq!(text, "part def Vehicle;\npart wheel;\n");
+        // Insertion, deletion across lines, and edits after a UTF-16 pair
+        apply_content_change(&mut text, range((1, 5), (1, 5)), "front");
+        assert_eq!(text, "part def Vehicle;\npart frontwheel;\n");
+        apply_content_change(&mut text, range((0, 16), (1, 15)), "");
+        assert_eq!(text, "part def Vehicle;\n");
+        apply_content_change(&mut text, range((0, 9), (0, 9)), "🚗");
+        apply_content_change(&mut text, range((0, 11), (0, 18)), "Car");
+        assert_eq!(text, "part def 🚗Car;\n");
+
+        // Past the end clamps; a reversed range only inserts
+        apply_content_change(&mut text, range((9, 0), (9, 0)), "x");
+        assert_eq!(text, "part def 🚗Car;\nx");
+        apply_content_change(&mut text, range((0, 4), (0, 0)), "!");
+        assert_eq!(text, "part! def 🚗Car;\nx");
+        apply_content_change(&mut text, None, "package P;");
+        assert_eq!(text, "package P;");
+    }
+
     #[test]
     fn offset_to_position_simple() {
         let source = "line1\nline2\nline3";
-        let pos = offset_to_position(0, source);
+        let pos = offset_to_position(0, &LineIndex::new(source), source);
         assert_eq!(pos.line, 0);
         assert_eq!(pos.character, 0);
     }
@@ -284,7 +754,7 @@ mod tests {
     #[test]
     fn offset_to_position_second_line() {
         let source = "line1\nline2\nline3";
-        let pos = offset_to_position(6, source); // Start of "line2"
+        let pos = offset_to_position(6, &LineIndex::new(source), source); // Start of "line2"
         assert_eq!(pos.line, 1);
         assert_eq!(pos.character, 0);
     }
@@ -292,7 +762,7 @@ mod tests {
     #[test]
     fn offset_to_position_middle() {
         let source = "line1\nline2\nline3";
-        let pos = offset_to_position(8, source); // "ne2" in line2
+        let pos = offset_to_position(8, &LineIndex::new(source), source); // "ne2" in line2
         assert_eq!(pos.line, 1);
         assert_eq!(pos.character, 2);
     }
@@ -306,6 +776,351 @@ mod tests {
         assert_eq!(range.start.character, 8);
     }
 
+    #[test]
+    fn range_from_span_multiline() {
+        let source = "package Test {\n  part x;\n}";
+        let mut span = Span::new("test.sysml", 0, source.len());
+        span.with_line_cols(&LineIndex::new(source));
+        let range = Range::from_span(&span, source);
+        assert_eq!(range.start, Position::new(0, 0));
+        assert_eq!(range.end, Position::new(2, 1));
+    }
+
+    #[test]
+    fn diagnostic_spanning_three_lines() {
+        let source = "package P {\n    part def Broken {\n        attribute x\n    }\n}\n";
+        let start = source.find("part def").unwrap();
+        let end = source.find("x\n").unwrap() + 1;
+        let mut span = Span::new("test.sysml", start, end);
+        span.with_line_cols(&LineIndex::new(source));
+        assert_eq!((span.line, span.end_line), (Some(2), Some(3)));
+
+        let diag = SysmlDiagnostic::error("malformed definition").with_span(span);
+        let lsp = LspDiagnostic::from_sysml(&diag, source);
+        assert_eq!(lsp.range.start, Position::new(1, 4));
+        assert_eq!(lsp.range.end, Position::new(2, 19));
+    }
+
+    #[test]
+    fn range_from_span_counts_utf16_units() {
+        let source = "package P {\n  attribute 属性 : 𝔸;\n}";
+        let start = source.find('属').unwrap();
+        let span = Span::new("test.sysml", start, start + "属性".len());
+        let range = Range::from_span(&span, source);
+        assert_eq!(range.start, Position::new(1, 12));
+        assert_eq!(range.end, Position::new(1, 14));
+
+        // '𝔸' is a surrogate pair, so ';' is two UTF-16 units after it
+        let semi = source.find(';').unwrap();
+        assert_eq!(offset_to_position(semi, &LineIndex::new(source), source), Position::new(1, 19));
+    }
+
+    #[test]
+    fn range_from_span_uses_line_cols_in_utf16_units() {
+        let source = "package P {\n  attribute 属性 : 𝔸;\n}";
+        // Line/column fields say `属性 : 𝔸`, by bytes; the offsets are unset
+        let mut span = Span::new("test.sysml", 0, 0);
+        span.line = Some(2);
+        span.col = Some(13);
+        span.end_line = Some(2);
+        span.end_col = Some(26);
+        let range = Range::from_span(&span, source);
+        assert_eq!(range.start, Position::new(1, 12));
+        assert_eq!(range.end, Position::new(1, 19));
+
+        // Without an end line/column, the end offset is used
+        span.end_line = None;
+        assert_eq!(Range::from_span(&span, source).end, Position::new(0, 0));
+    }
+
+    #[test]
+    fn position_to_offset_round_trips() {
+        let source = "package P {\r\n  attribute 属性 : 𝔸;\r\n}";
+        let index = LineIndex::new(source);
+        for offset in [0, 8, source.find('属').unwrap(), source.find(';').unwrap(), source.len()] {
+            assert_eq!(offset_to_position(offset, &index, source).to_offset(source), offset);
+        }
+        // Past the end of a line or the source
+        assert_eq!(Position::new(0, 40).to_offset(source), source.find('\r').unwrap());
+        assert_eq!(Position::new(9, 0).to_offset(source), source.len());
+    }
+
+    #[cfg(feature = "linking")]
+    mod linking {
+        use super::*;
+        use sysml_core::{ElementKind, Value};
+
+        const SOURCE: &str = "part def Engine;\npart def Car {\n    part engine : Engine;\n}\n";
+
+        fn span(text: &str) -> Span {
+            let start = SOURCE.find(text).unwrap();
+            Span::new("car.sysml", start, start + text.len())
+        }
+
+        /// `Engine` and `Car`, with `Car::engine` typed by `Engine`.
+        fn fixture() -> (ModelGraph, ElementId) {
+            let mut graph = ModelGraph::new();
+            let engine_def = graph.add_element(
+                Element::new_with_kind(ElementKind::PartDefinition)
+                    .with_name("Engine")
+                    .with_span(span("part def Engine;")),
+            );
+            let car = graph.add_element(
+                Element::new_with_kind(ElementKind::PartDefinition)
+                    .with_name("Car")
+                    .with_span(span("part def Car {\n    part engine : Engine;\n}")),
+            );
+            let engine = graph.add_element(
+                Element::new_with_kind(ElementKind::PartUsage)
+                    .with_name("engine")
+                    .with_owner(car)
+                    .with_span(span("part engine : Engine;")),
+            );
+            graph.add_element(
+                Element::new_with_kind(ElementKind::FeatureTyping)
+                    .with_owner(engine.clone())
+                    .with_prop("typedFeature", Value::Ref(engine))
+                    .with_prop(resolved_props::TYPE, Value::Ref(engine_def.clone()))
+                    .with_span(span(": Engine")),
+            );
+            (graph, engine_def)
+        }
+
+        #[test]
+        fn document_symbols_show_types_as_detail() {
+            let (mut graph, _) = fixture();
+            let engine = graph
+                .elements
+                .values()
+                .find(|element| element.name.as_deref() == Some("engine"))
+                .map(|element| element.id.clone())
+                .unwrap();
+            graph.add_element(
+                Element::new_with_kind(ElementKind::FeatureTyping)
+                    .with_owner(engine.clone())
+                    .with_prop("typedFeature", Value::Ref(engine))
+                    .with_prop(sysml_core::resolution::unresolved_props::TYPE, "Parts::Spare"),
+            );
+
+            let symbols = document_symbols(&graph, "car.sysml", SOURCE);
+            let details: Vec<(&str, Option<&str>)> = symbols
+                .iter()
+                .chain(&symbols[1].children)
+                .map(|symbol| (symbol.name.as_str(), symbol.detail.as_deref()))
+                .collect();
+            assert_eq!(
+                details,
+                [("Engine", None), ("Car", None), ("engine", Some(": Engine, Parts::Spare"))]
+            );
+        }
+
+        #[test]
+        fn workspace_symbols_match_name_substrings() {
+            let (mut graph, _) = fixture();
+            graph.add_element(
+                Element::new_with_kind(ElementKind::PartDefinition)
+                    .with_name("Engineering")
+                    .with_span(Span::new("unreadable.sysml", 0, 1)),
+            );
+            let mut reads = Vec::new();
+            let symbols = workspace_symbols(&graph, "ngine", |file| {
+                reads.push(file.to_string());
+                (file == "car.sysml").then(|| SOURCE.to_string())
+            });
+
+            let found: Vec<(&str, SymbolKind, Option<&str>)> = symbols
+                .iter()
+                .map(|symbol| (symbol.name.as_str(), symbol.kind, symbol.container_name.as_deref()))
+                .collect();
+            assert_eq!(
+                found,
+                [("Engine", SymbolKind::Class, None), ("engine", SymbolKind::Field, Some("Car"))]
+            );
+            assert_eq!(symbols[1].location.uri, "car.sysml");
+            assert_eq!(symbols[1].location.range.start, Position::new(2, 4));
+            reads.sort();
+            assert_eq!(reads, ["car.sysml", "unreadable.sysml"], "each file is read once");
+            assert!(workspace_symbols(&graph, "Wheel", |_| Some(SOURCE.to_string())).is_empty());
+        }
+
+        #[test]
+        fn finds_innermost_element_at_offset() {
+            let (graph, _) = fixture();
+            let at = |text: &str| {
+                let offset = SOURCE.find(text).unwrap();
+                find_element_at_offset(&graph, "car.sysml", offset).map(|e| e.kind.clone())
+            };
+            assert_eq!(at("Car"), Some(ElementKind::PartDefinition));
+            assert_eq!(at("engine :"), Some(ElementKind::PartUsage));
+            assert_eq!(at(": Engine"), Some(ElementKind::FeatureTyping));
+            assert_eq!(find_element_at_offset(&graph, "other.sysml", 0), None);
+            assert_eq!(find_element_at_offset(&graph, "car.sysml", SOURCE.len()), None);
+        }
+
+        #[test]
+        fn resolves_typing_target() {
+            let (graph, engine_def) = fixture();
+            let target = Some(span("part def Engine;"));
+
+            // From the type name and from the typed feature itself
+            let type_name = SOURCE.rfind("Engine").unwrap();
+            assert_eq!(find_definition(&graph, "car.sysml", type_name), target.as_ref());
+            let feature = SOURCE.find("engine").unwrap();
+            let usage = find_element_at_offset(&graph, "car.sysml", feature).unwrap();
+            assert_eq!(definition_target(&graph, usage).map(|e| &e.id), Some(&engine_def));
+
+            // A definition references nothing
+            assert_eq!(find_definition(&graph, "car.sysml", SOURCE.find("Car").unwrap()), None);
+        }
+
+        #[test]
+        fn finds_references_to_a_definition() {
+            let (mut graph, engine_def) = fixture();
+            let car = find_element_at_offset(&graph, "car.sysml", SOURCE.find("Car").unwrap()).unwrap().id.clone();
+            graph.add_relationship(sysml_core::Relationship::new(
+                sysml_core::RelationshipKind::Satisfy,
+                car,
+                engine_def.clone(),
+            ));
+            let target = graph.get_element(&engine_def).unwrap();
+
+            let references = find_references(&graph, target, false);
+            assert_eq!(references, [&span("part def Car {\n    part engine : Engine;\n}"), &span(": Engine")]);
+
+            let with_declaration = find_references(&graph, target, true);
+            assert_eq!(with_declaration[0], &span("part def Engine;"));
+            assert_eq!(with_declaration.len(), 3);
+
+            // The cursor on the type name finds the same target
+            let at_type = find_hover_element(&graph, "car.sysml", SOURCE.rfind("Engine").unwrap()).unwrap();
+            assert_eq!(find_references(&graph, at_type, false), references);
+        }
+
+        #[test]
+        fn hover_describes_innermost_named_element() {
+            let (mut graph, engine_def) = fixture();
+            graph.add_element(
+                Element::new_with_kind(ElementKind::Documentation)
+                    .with_owner(engine_def.clone())
+                    .with_prop("body", " The power unit. "),
+            );
+            graph.compute_qualified_names();
+
+            let engine = SOURCE.find("engine").unwrap();
+            let usage = find_hover_element(&graph, "car.sysml", engine).unwrap();
+            assert_eq!(hover_markdown(&graph, usage), "**PartUsage** Car::engine : Engine");
+
+            // The type name is a FeatureTyping, so its target is described
+            let type_name = SOURCE.rfind("Engine").unwrap();
+            let target = find_hover_element(&graph, "car.sysml", type_name).unwrap();
+            assert_eq!(target.id, engine_def);
+            assert_eq!(hover_markdown(&graph, target), "**PartDefinition** Engine\n\nThe power unit.");
+        }
+
+        #[test]
+        fn hover_lists_direction_supertypes_and_multiplicity() {
+            let (mut graph, engine_def) = fixture();
+            let parts = graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("parts"));
+            let engine = find_element_at_offset(&graph, "car.sysml", SOURCE.find("engine").unwrap()).unwrap().id.clone();
+            let usage = graph.get_element_mut(&engine).unwrap();
+            usage.set_prop("direction", "in");
+            usage.set_prop("multiplicity_lower", 1i64);
+            graph.add_element(
+                Element::new_with_kind(ElementKind::Subsetting)
+                    .with_owner(engine.clone())
+                    .with_prop("specific", Value::Ref(engine.clone()))
+                    .with_prop(resolved_props::GENERAL, Value::Ref(parts)),
+            );
+            graph.add_element(
+                Element::new_with_kind(ElementKind::Documentation)
+                    .with_owner(engine.clone())
+                    .with_prop("body", "Drives the wheels."),
+            );
+            graph.compute_qualified_names();
+
+            let usage = graph.get_element(&engine).unwrap();
+            assert_eq!(
+                hover_markdown(&graph, usage),
+                "**PartUsage** in Car::engine : Engine :> parts [1..*]\n\nDrives the wheels."
+            );
+            let definition = graph.get_element(&engine_def).unwrap();
+            assert_eq!(hover_markdown(&graph, definition), "**PartDefinition** Engine");
+        }
+    }
+
+    #[cfg(feature = "linking")]
+    #[test]
+    fn document_symbols_follow_ownership() {
+        use sysml_core::{Element, ElementKind, ModelGraph};
+
+        let source = "package Vehicles {\n    part def Car {\n        attribute mass;\n        part engine;\n    }\n    state def Lamp;\n}\n";
+        let span = |text: &str| {
+            let start = source.find(text).unwrap();
+            Span::new("v.sysml", start, start + text.len())
+        };
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(
+            Element::new_with_kind(ElementKind::Package)
+                .with_name("Vehicles")
+                .with_span(span(source.trim_end())),
+        );
+        // Added before the car so that ordering comes from spans, not ids
+        graph.add_element(
+            Element::new_with_kind(ElementKind::StateDefinition)
+                .with_name("Lamp")
+                .with_owner(pkg.clone())
+                .with_span(span("state def Lamp;")),
+        );
+        let car = graph.add_element(
+            Element::new_with_kind(ElementKind::PartDefinition)
+                .with_name("Car")
+                .with_owner(pkg.clone())
+                .with_span(span("part def Car {\n        attribute mass;\n        part engine;\n    }")),
+        );
+        // An unnamed element between the car and its engine is skipped
+        let wrapper = graph.add_element(Element::new_with_kind(ElementKind::Namespace).with_owner(car.clone()));
+        graph.add_element(
+            Element::new_with_kind(ElementKind::PartUsage)
+                .with_name("engine")
+                .with_owner(wrapper)
+                .with_span(span("part engine;")),
+        );
+        graph.add_element(
+            Element::new_with_kind(ElementKind::AttributeUsage)
+                .with_name("mass")
+                .with_owner(car.clone())
+                .with_span(span("attribute mass;")),
+        );
+        // Without a span in this file there is nothing to show
+        graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("spare").with_owner(car));
+        graph.add_element(
+            Element::new_with_kind(ElementKind::PartDefinition)
+                .with_name("Elsewhere")
+                .with_span(Span::new("other.sysml", 0, 5)),
+        );
+
+        fn outline(symbols: &[DocumentSymbol]) -> String {
+            let symbols: Vec<String> = symbols
+                .iter()
+                .map(|symbol| match outline(&symbol.children).as_str() {
+                    "" => format!("{}({:?})", symbol.name, symbol.kind),
+                    children => format!("{}({:?})[{}]", symbol.name, symbol.kind, children),
+                })
+                .collect();
+            symbols.join(", ")
+        }
+
+        let symbols = document_symbols(&graph, "v.sysml", source);
+        assert_eq!(
+            outline(&symbols),
+            "Vehicles(Module)[Car(Class)[mass(Field), engine(Field)], Lamp(Class)]"
+        );
+        let car = &symbols[0].children[0];
+        assert_eq!(car.range.start, Position::new(1, 4));
+        assert_eq!(car.range.end, Position::new(4, 5));
+        assert_eq!(car.selection_range, Range::new(Position::new(1, 13), Position::new(1, 16)));
+    }
+
     #[test]
     fn lsp_diagnostic_from_sysml() {
         let source = "package Test {}";
@@ -0,0 +1,293 @@
+use std::collections::{HashMap, HashSet};
+
+use sysml_core::{ElementId, ElementKind, ModelGraph, RelationshipKind};
+
+/// Satisfaction and verification coverage of one requirement.
+#[derive(Debug, Clone)]
+pub struct CoverageRow {
+    /// The requirement.
+    pub id: ElementId,
+    /// The requirement name.
+    pub name: Option<String>,
+    /// The requirement's qualified name, if it has one.
+    pub qualified_name: Option<String>,
+    /// Sources of Satisfy relationships targeting the requirement.
+    pub satisfied_by: Vec<ElementId>,
+    /// Sources of Verify relationships targeting the requirement.
+    pub verified_by: Vec<ElementId>,
+    /// Whether every owned child requirement is covered (true without children).
+    pub children_covered: bool,
+    /// Whether the requirement is covered, see [`requirement_coverage`].
+    pub covered: bool,
+}
+
+impl CoverageRow {
+    /// Whether the requirement is directly satisfied.
+    pub fn is_satisfied(&self) -> bool {
+        !self.satisfied_by.is_empty()
+    }
+
+    /// Whether the requirement is directly verified.
+    pub fn is_verified(&self) -> bool {
+        !self.verified_by.is_empty()
+    }
+}
+
+/// Requirement coverage of a whole model.
+#[derive(Debug, Clone)]
+pub struct CoverageReport {
+    /// One row per requirement, ordered by qualified name.
+    pub rows: Vec<CoverageRow>,
+    /// Percentage of requirements that are directly satisfied.
+    pub satisfied_percent: f64,
+    /// Percentage of requirements that are directly verified.
+    pub verified_percent: f64,
+    /// Percentage of requirements that are covered.
+    pub covered_percent: f64,
+}
+
+impl CoverageReport {
+    /// Get the rows of requirements that are not covered.
+    pub fn uncovered(&self) -> Vec<&CoverageRow> {
+        self.rows.iter().filter(|row| !row.covered).collect()
+    }
+
+    /// Serialize the report rows as CSV, with a header line.
+    ///
+    /// `satisfied_by` and `verified_by` hold space-separated ElementIds.
+    pub fn to_csv(&self) -> String {
+        let mut output = String::from(
+            "id,name,qualified_name,satisfied,verified,children_covered,covered,satisfied_by,verified_by\n",
+        );
+        for row in &self.rows {
+            let ids = |ids: &[ElementId]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(" ");
+            let fields = [
+                row.id.to_string(),
+                row.name.clone().unwrap_or_default(),
+                row.qualified_name.clone().unwrap_or_default(),
+                row.is_satisfied().to_string(),
+                row.is_verified().to_string(),
+                row.children_covered.to_string(),
+                row.covered.to_string(),
+                ids(&row.satisfied_by),
+                ids(&row.verified_by),
+            ];
+            let fields: Vec<String> = fields.iter().map(|field| escape_csv(field)).collect();
+            output.push_str(&fields.join(","));
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// Build a coverage report for every RequirementUsage in a model.
+///
+/// A requirement without child requirements is covered when it is both
+/// satisfied and verified. A requirement that owns child requirements is
+/// covered only when all of them are covered, recursively down the tree;
+/// its own Satisfy and Verify relationships are reported but do not make up
+/// for an uncovered child. Every requirement is counted once in the
+/// percentages, whether or not it has children, and an empty model reports
+/// 100% coverage.
+pub fn requirement_coverage(graph: &ModelGraph) -> CoverageReport {
+    let mut satisfied_by: HashMap<&ElementId, Vec<ElementId>> = HashMap::new();
+    let mut verified_by: HashMap<&ElementId, Vec<ElementId>> = HashMap::new();
+    for rel in graph.relationships.values() {
+        match rel.kind {
+            RelationshipKind::Satisfy => satisfied_by.entry(&rel.target).or_default().push(rel.source.clone()),
+            RelationshipKind::Verify => verified_by.entry(&rel.target).or_default().push(rel.source.clone()),
+            _ => {}
+        }
+    }
+
+    let requirements: Vec<&ElementId> = graph
+        .elements_by_kind(&ElementKind::RequirementUsage)
+        .map(|e| &e.id)
+        .collect();
+    let mut rollup = Rollup {
+        graph,
+        satisfied_by: &satisfied_by,
+        verified_by: &verified_by,
+        covered: HashMap::new(),
+        visiting: HashSet::new(),
+    };
+
+    let mut rows: Vec<CoverageRow> = requirements
+        .iter()
+        .map(|id| {
+            let children_covered = rollup.children_covered(id);
+            CoverageRow {
+                id: (*id).clone(),
+                name: graph.get_element(id).and_then(|e| e.name.clone()),
+                qualified_name: graph.qualified_name_of(id).map(|q| q.to_string()),
+                satisfied_by: satisfied_by.get(id).cloned().unwrap_or_default(),
+                verified_by: verified_by.get(id).cloned().unwrap_or_default(),
+                children_covered,
+                covered: rollup.covered(id),
+            }
+        })
+        .collect();
+    rows.sort_by(|a, b| a.qualified_name.cmp(&b.qualified_name).then_with(|| a.id.cmp(&b.id)));
+
+    let percent = |count: usize| {
+        if rows.is_empty() {
+            100.0
+        } else {
+            count as f64 * 100.0 / rows.len() as f64
+        }
+    };
+    CoverageReport {
+        satisfied_percent: percent(rows.iter().filter(|row| row.is_satisfied()).count()),
+        verified_percent: percent(rows.iter().filter(|row| row.is_verified()).count()),
+        covered_percent: percent(rows.iter().filter(|row| row.covered).count()),
+        rows,
+    }
+}
+
+/// Memoized bottom-up coverage over the requirement ownership tree.
+struct Rollup<'a> {
+    graph: &'a ModelGraph,
+    satisfied_by: &'a HashMap<&'a ElementId, Vec<ElementId>>,
+    verified_by: &'a HashMap<&'a ElementId, Vec<ElementId>>,
+    covered: HashMap<ElementId, bool>,
+    /// Requirements on the current path, so ownership cycles terminate.
+    visiting: HashSet<ElementId>,
+}
+
+impl Rollup<'_> {
+    fn child_requirements(&self, id: &ElementId) -> Vec<ElementId> {
+        self.graph
+            .children_of(id)
+            .filter(|child| child.kind == ElementKind::RequirementUsage)
+            .map(|child| child.id.clone())
+            .collect()
+    }
+
+    fn children_covered(&mut self, id: &ElementId) -> bool {
+        let children = self.child_requirements(id);
+        children.iter().all(|child| self.covered(child))
+    }
+
+    fn covered(&mut self, id: &ElementId) -> bool {
+        if let Some(covered) = self.covered.get(id) {
+            return *covered;
+        }
+        // A requirement that (indirectly) owns itself is never covered
+        if !self.visiting.insert(id.clone()) {
+            return false;
+        }
+
+        let covered = if self.child_requirements(id).is_empty() {
+            self.satisfied_by.contains_key(id) && self.verified_by.contains_key(id)
+        } else {
+            self.children_covered(id)
+        };
+
+        self.visiting.remove(id);
+        self.covered.insert(id.clone(), covered);
+        covered
+    }
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysml_core::{Element, Relationship};
+
+    /// Shall
+    /// - Braking (covered through its children)
+    ///   - StopDistance (satisfied and verified)
+    ///   - Fade (satisfied and verified)
+    /// - Range (verified and satisfied itself, but Charging is not covered)
+    ///   - Charging (satisfied only)
+    /// - Noise (nothing)
+    fn create_requirement_tree() -> ModelGraph {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Shall"));
+        let requirement = |graph: &mut ModelGraph, name: &str, owner: &ElementId| {
+            graph.add_element(
+                Element::new_with_kind(ElementKind::RequirementUsage)
+                    .with_name(name)
+                    .with_owner(owner.clone()),
+            )
+        };
+        let braking = requirement(&mut graph, "Braking", &pkg);
+        let stop = requirement(&mut graph, "StopDistance", &braking);
+        let fade = requirement(&mut graph, "Fade", &braking);
+        let range = requirement(&mut graph, "Range", &pkg);
+        let charging = requirement(&mut graph, "Charging", &range);
+        requirement(&mut graph, "Noise", &pkg);
+
+        let design = graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("Brakes"));
+        let test = graph.add_element(Element::new_with_kind(ElementKind::VerificationCaseUsage).with_name("BrakeTest"));
+        for req in [&stop, &fade, &range, &charging] {
+            graph.add_relationship(Relationship::new(RelationshipKind::Satisfy, design.clone(), req.clone()));
+        }
+        for req in [&stop, &fade, &range] {
+            graph.add_relationship(Relationship::new(RelationshipKind::Verify, test.clone(), req.clone()));
+        }
+
+        graph
+    }
+
+    fn row<'a>(report: &'a CoverageReport, name: &str) -> &'a CoverageRow {
+        report.rows.iter().find(|row| row.name.as_deref() == Some(name)).unwrap()
+    }
+
+    #[test]
+    fn coverage_rolls_up_child_requirements() {
+        let report = requirement_coverage(&create_requirement_tree());
+
+        assert_eq!(report.rows.len(), 6);
+        assert_eq!(report.rows[0].qualified_name.as_deref(), Some("Shall::Braking"));
+
+        let braking = row(&report, "Braking");
+        assert!(!braking.is_satisfied() && !braking.is_verified());
+        assert!(braking.children_covered && braking.covered);
+
+        let range = row(&report, "Range");
+        assert!(range.is_satisfied() && range.is_verified());
+        assert!(!range.children_covered && !range.covered);
+
+        let charging = row(&report, "Charging");
+        assert_eq!(charging.satisfied_by.len(), 1);
+        assert!(charging.children_covered && !charging.covered);
+
+        let uncovered: Vec<_> = report.uncovered().iter().filter_map(|row| row.name.as_deref()).collect();
+        assert_eq!(uncovered, vec!["Noise", "Range", "Charging"]);
+
+        // Each requirement counts once: 4 of 6 satisfied, 3 of 6 verified, 3 of 6 covered
+        assert!((report.satisfied_percent - 400.0 / 6.0).abs() < 1e-9);
+        assert!((report.verified_percent - 50.0).abs() < 1e-9);
+        assert!((report.covered_percent - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn coverage_csv_has_header_and_escapes_fields() {
+        let mut graph = create_requirement_tree();
+        graph.add_element(Element::new_with_kind(ElementKind::RequirementUsage).with_name("Say \"hi\", twice"));
+        let csv = requirement_coverage(&graph).to_csv();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert!(lines[0].starts_with("id,name,qualified_name,satisfied,verified"));
+        assert!(csv.contains(",\"Say \"\"hi\"\", twice\","));
+        assert!(csv.contains(",Noise,Shall::Noise,false,false,true,false,,\n"));
+    }
+
+    #[test]
+    fn empty_model_is_fully_covered() {
+        let report = requirement_coverage(&ModelGraph::new());
+        assert!(report.rows.is_empty());
+        assert_eq!(report.covered_percent, 100.0);
+        assert_eq!(report.to_csv().lines().count(), 1);
+    }
+}
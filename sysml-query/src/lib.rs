@@ -5,6 +5,17 @@
 //! This crate provides higher-level query functions built on top of
 //! the core ModelGraph type.
 
+mod coverage;
+mod names;
+mod query;
+
+pub use coverage::{requirement_coverage, CoverageReport, CoverageRow};
+pub use names::{find_by_pattern, find_by_qualified_name, NamePattern};
+pub use query::{Query, Traversal};
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use sysml_core::resolution::resolved_props;
 use sysml_core::{Element, ElementId, ElementKind, ModelGraph, Relationship, RelationshipKind, Value};
 
 /// Find elements by name, optionally filtered by kind.
@@ -201,6 +212,243 @@ pub fn descendants<'a>(graph: &'a ModelGraph, element_id: &'a ElementId) -> Vec<
     result
 }
 
+/// Find all types that specialize a type, directly or transitively.
+///
+/// Follows Specialization elements (and subkinds such as Subclassification,
+/// but not FeatureTyping) from their `general` side to their `specific` side.
+/// Results are in breadth-first order and never include `type_id` itself,
+/// even when it is on a specialization cycle (see [`specialization_cycles`]).
+pub fn specializations_of<'a>(graph: &'a ModelGraph, type_id: &ElementId) -> Vec<&'a Element> {
+    // In id order, so siblings come out in the same order on every run
+    let mut specs: Vec<&Element> = graph.specializations().filter(|e| is_specialization(e)).collect();
+    specs.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut specifics: HashMap<&ElementId, Vec<&ElementId>> = HashMap::new();
+    for spec in specs {
+        if let (Some(general), Some(specific)) = (general_of(spec), specific_of(spec)) {
+            specifics.entry(general).or_default().push(specific);
+        }
+    }
+
+    closure(graph, type_id, |id| specifics.get(id).cloned().unwrap_or_default())
+}
+
+/// Find all types that a type specializes, directly or transitively.
+///
+/// The upward counterpart of [`specializations_of`], using the graph's
+/// specialization index.
+pub fn general_types_of<'a>(graph: &'a ModelGraph, type_id: &ElementId) -> Vec<&'a Element> {
+    closure(graph, type_id, |id| direct_general_types(graph, id))
+}
+
+/// Check whether a usage is typed by a definition or one of its specializations.
+///
+/// Follows the usage's FeatureTyping elements to their `type`, then the
+/// specialization closure upward from each type.
+pub fn is_typed_by(graph: &ModelGraph, usage_id: &ElementId, def_id: &ElementId) -> bool {
+    graph
+        .typings_of_feature(usage_id)
+        .filter_map(|typing| typing.props.get(resolved_props::TYPE).and_then(|v| v.as_ref()))
+        .any(|type_id| type_id == def_id || general_types_of(graph, type_id).iter().any(|t| &t.id == def_id))
+}
+
+/// Find the cycles in the specialization hierarchy.
+///
+/// Each cycle lists the types that specialize each other, in id order.
+/// A well-formed model has none.
+pub fn specialization_cycles(graph: &ModelGraph) -> Vec<Vec<&Element>> {
+    let mut cycles = Vec::new();
+    let mut seen: HashSet<&ElementId> = HashSet::new();
+
+    for id in graph.elements.keys() {
+        if seen.contains(id) {
+            continue;
+        }
+        let above = general_types_of(graph, id);
+        let on_cycle = direct_general_types(graph, id).contains(&id)
+            || above.iter().any(|t| direct_general_types(graph, &t.id).contains(&id));
+        if !on_cycle {
+            continue;
+        }
+        // The cycle holds `id` and the types above it that also have `id` above them
+        let mut cycle: Vec<&Element> = above
+            .into_iter()
+            .filter(|t| general_types_of(graph, &t.id).iter().any(|g| &g.id == id))
+            .collect();
+        cycle.extend(graph.get_element(id));
+        cycle.sort_by(|a, b| a.id.cmp(&b.id));
+        seen.extend(cycle.iter().map(|t| &t.id));
+        cycles.push(cycle);
+    }
+
+    cycles
+}
+
+fn is_specialization(element: &Element) -> bool {
+    let is_a = |kind: ElementKind| element.kind == kind || element.kind.is_subtype_of(kind);
+    is_a(ElementKind::Specialization) && !is_a(ElementKind::FeatureTyping)
+}
+
+/// The supertype of a specialization (`superclassifier` for Subclassification).
+fn general_of(spec: &Element) -> Option<&ElementId> {
+    spec.props
+        .get(resolved_props::GENERAL)
+        .or_else(|| spec.props.get(resolved_props::SUPERCLASSIFIER))
+        .and_then(|v| v.as_ref())
+}
+
+fn specific_of(spec: &Element) -> Option<&ElementId> {
+    spec.props.get("specific").and_then(|v| v.as_ref())
+}
+
+fn direct_general_types<'a>(graph: &'a ModelGraph, type_id: &ElementId) -> Vec<&'a ElementId> {
+    graph
+        .specializations_of_specific(type_id)
+        .filter(|spec| is_specialization(spec))
+        .filter_map(general_of)
+        .collect()
+}
+
+/// Breadth-first closure from `start`, excluding `start` and missing elements.
+fn closure<'a, F>(graph: &'a ModelGraph, start: &ElementId, mut next: F) -> Vec<&'a Element>
+where
+    F: FnMut(&ElementId) -> Vec<&'a ElementId>,
+{
+    let mut result = Vec::new();
+    let mut visited: HashSet<&ElementId> = HashSet::new();
+    let mut queue: VecDeque<&ElementId> = next(start).into_iter().collect();
+
+    while let Some(id) = queue.pop_front() {
+        if !visited.insert(id) {
+            continue;
+        }
+        if id != start {
+            if let Some(element) = graph.get_element(id) {
+                result.push(element);
+            }
+        }
+        queue.extend(next(id));
+    }
+
+    result
+}
+
+/// Find the parts linked to a part by connections or flows, in either direction.
+///
+/// Links are Flow relationships and ConnectionUsage, InterfaceUsage and
+/// FlowUsage elements whose `source` and `target` props are references.
+/// Results are in id order and never include `part_id` itself.
+pub fn connected_to<'a>(graph: &'a ModelGraph, part_id: &ElementId) -> Vec<&'a Element> {
+    let mut linked: BTreeSet<&ElementId> = BTreeSet::new();
+    let connections = graph
+        .elements
+        .values()
+        .filter(|e| is_kind_or_subtype(e, ElementKind::ConnectionUsage))
+        .filter_map(endpoints);
+    for (source, target) in flow_edges(graph).into_iter().chain(connections) {
+        if source == part_id {
+            linked.insert(target);
+        } else if target == part_id {
+            linked.insert(source);
+        }
+    }
+    linked.remove(part_id);
+
+    linked.into_iter().filter_map(|id| graph.get_element(id)).collect()
+}
+
+/// Find the parts that flow into a part, in id order.
+///
+/// Follows Flow relationships and FlowUsage elements against their direction.
+pub fn flow_sources<'a>(graph: &'a ModelGraph, part_id: &ElementId) -> Vec<&'a Element> {
+    let sources: BTreeSet<&ElementId> = flow_edges(graph)
+        .into_iter()
+        .filter(|(_, target)| *target == part_id)
+        .map(|(source, _)| source)
+        .collect();
+    sources.into_iter().filter_map(|id| graph.get_element(id)).collect()
+}
+
+/// Find the parts that a part flows into, in id order.
+///
+/// Follows Flow relationships and FlowUsage elements in their direction.
+pub fn flow_targets<'a>(graph: &'a ModelGraph, part_id: &ElementId) -> Vec<&'a Element> {
+    let targets: BTreeSet<&ElementId> = flow_edges(graph)
+        .into_iter()
+        .filter(|(source, _)| *source == part_id)
+        .map(|(_, target)| target)
+        .collect();
+    targets.into_iter().filter_map(|id| graph.get_element(id)).collect()
+}
+
+/// Find the flow paths from one part to another.
+///
+/// A breadth-first search over the same flows as [`flow_targets`], so paths
+/// come out shortest first. Each path starts with `from_id`, ends with
+/// `to_id` and visits no part twice; paths longer than `max_depth` flows are
+/// not explored, which bounds the search on cyclic models.
+pub fn flow_paths(
+    graph: &ModelGraph,
+    from_id: &ElementId,
+    to_id: &ElementId,
+    max_depth: usize,
+) -> Vec<Vec<ElementId>> {
+    let mut targets: HashMap<&ElementId, Vec<&ElementId>> = HashMap::new();
+    for (source, target) in flow_edges(graph) {
+        targets.entry(source).or_default().push(target);
+    }
+
+    let mut paths = Vec::new();
+    let mut queue: VecDeque<Vec<&ElementId>> = VecDeque::new();
+    queue.push_back(vec![from_id]);
+
+    while let Some(path) = queue.pop_front() {
+        let last = path[path.len() - 1];
+        if last == to_id {
+            paths.push(path.into_iter().cloned().collect());
+            continue;
+        }
+        if path.len() > max_depth {
+            continue;
+        }
+        for next in targets.get(last).into_iter().flatten() {
+            if !path.contains(next) {
+                let mut longer = path.clone();
+                longer.push(next);
+                queue.push_back(longer);
+            }
+        }
+    }
+
+    paths
+}
+
+/// Directed flows: Flow relationships, then FlowUsage elements.
+fn flow_edges(graph: &ModelGraph) -> Vec<(&ElementId, &ElementId)> {
+    let relationships = graph
+        .relationships
+        .values()
+        .filter(|r| r.kind == RelationshipKind::Flow)
+        .map(|r| (&r.source, &r.target));
+    let usages = graph
+        .elements
+        .values()
+        .filter(|e| is_kind_or_subtype(e, ElementKind::FlowUsage))
+        .filter_map(endpoints);
+    relationships.chain(usages).collect()
+}
+
+/// The `source` and `target` references of a connection or flow usage.
+fn endpoints(element: &Element) -> Option<(&ElementId, &ElementId)> {
+    let source = element.props.get("source").and_then(|v| v.as_ref())?;
+    let target = element.props.get("target").and_then(|v| v.as_ref())?;
+    Some((source, target))
+}
+
+fn is_kind_or_subtype(element: &Element, kind: ElementKind) -> bool {
+    element.kind == kind || element.kind.is_subtype_of(kind)
+}
+
 /// Find elements by property value.
 pub fn find_by_property<'a>(
     graph: &'a ModelGraph,
@@ -372,4 +620,167 @@ mod tests {
         assert_eq!(counts.get("Satisfy"), Some(&1));
         assert_eq!(counts.get("Verify"), Some(&1));
     }
+
+    fn specialize(graph: &mut ModelGraph, kind: ElementKind, specific: &ElementId, general: &ElementId) {
+        let spec = Element::new_with_kind(kind)
+            .with_owner(specific.clone())
+            .with_prop("specific", Value::Ref(specific.clone()))
+            .with_prop(resolved_props::GENERAL, Value::Ref(general.clone()));
+        graph.add_element(spec);
+    }
+
+    /// Vehicle <- Car <- SportsCar, with `myCar : SportsCar` and `truck : Vehicle`.
+    fn create_specialization_graph() -> ModelGraph {
+        let mut graph = ModelGraph::new();
+        let def = |name: &str| Element::new_with_kind(ElementKind::PartDefinition).with_name(name);
+        let vehicle = graph.add_element(def("Vehicle"));
+        let car = graph.add_element(def("Car"));
+        let sports_car = graph.add_element(def("SportsCar"));
+        graph.add_element(def("Boat"));
+
+        specialize(&mut graph, ElementKind::Subclassification, &car, &vehicle);
+        specialize(&mut graph, ElementKind::Specialization, &sports_car, &car);
+
+        for (usage, type_id) in [("myCar", &sports_car), ("truck", &vehicle)] {
+            let usage_id = graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name(usage));
+            let typing = Element::new_with_kind(ElementKind::FeatureTyping)
+                .with_owner(usage_id.clone())
+                .with_prop("typedFeature", Value::Ref(usage_id))
+                .with_prop(resolved_props::TYPE, Value::Ref(type_id.clone()));
+            graph.add_element(typing);
+        }
+
+        graph
+    }
+
+    fn id_of(graph: &ModelGraph, name: &str) -> ElementId {
+        find_by_name(graph, None, name).next().unwrap().id.clone()
+    }
+
+    fn names(elements: &[&Element]) -> Vec<String> {
+        elements.iter().filter_map(|e| e.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_specializations_of_is_transitive() {
+        let graph = create_specialization_graph();
+
+        let below = specializations_of(&graph, &id_of(&graph, "Vehicle"));
+        assert_eq!(names(&below), vec!["Car", "SportsCar"]);
+        assert!(specializations_of(&graph, &id_of(&graph, "SportsCar")).is_empty());
+        // Typing is not specialization
+        assert!(specializations_of(&graph, &id_of(&graph, "myCar")).is_empty());
+    }
+
+    #[test]
+    fn test_general_types_of_is_transitive() {
+        let graph = create_specialization_graph();
+
+        let above = general_types_of(&graph, &id_of(&graph, "SportsCar"));
+        assert_eq!(names(&above), vec!["Car", "Vehicle"]);
+        assert!(general_types_of(&graph, &id_of(&graph, "Vehicle")).is_empty());
+        assert!(general_types_of(&graph, &id_of(&graph, "myCar")).is_empty());
+    }
+
+    #[test]
+    fn test_is_typed_by_follows_specialization() {
+        let graph = create_specialization_graph();
+        let my_car = id_of(&graph, "myCar");
+        let truck = id_of(&graph, "truck");
+
+        assert!(is_typed_by(&graph, &my_car, &id_of(&graph, "SportsCar")));
+        assert!(is_typed_by(&graph, &my_car, &id_of(&graph, "Car")));
+        assert!(is_typed_by(&graph, &my_car, &id_of(&graph, "Vehicle")));
+        assert!(!is_typed_by(&graph, &my_car, &id_of(&graph, "Boat")));
+
+        assert!(is_typed_by(&graph, &truck, &id_of(&graph, "Vehicle")));
+        assert!(!is_typed_by(&graph, &truck, &id_of(&graph, "Car")));
+    }
+
+    #[test]
+    fn test_specialization_cycles_terminate_and_are_reported() {
+        let mut graph = create_specialization_graph();
+        let vehicle = id_of(&graph, "Vehicle");
+        let sports_car = id_of(&graph, "SportsCar");
+        specialize(&mut graph, ElementKind::Specialization, &vehicle, &sports_car);
+
+        assert_eq!(names(&general_types_of(&graph, &vehicle)).len(), 2);
+        assert_eq!(names(&specializations_of(&graph, &vehicle)).len(), 2);
+
+        let cycles = specialization_cycles(&graph);
+        assert_eq!(cycles.len(), 1);
+        let mut cycle = names(&cycles[0]);
+        cycle.sort();
+        assert_eq!(cycle, vec!["Car", "SportsCar", "Vehicle"]);
+
+        assert!(specialization_cycles(&create_specialization_graph()).is_empty());
+    }
+
+    /// pump -> valve (Flow relationship) -> tank (FlowUsage), plus a
+    /// connection from a sensor to the tank.
+    fn create_pipeline_graph() -> (ModelGraph, ElementId, ElementId, ElementId, ElementId) {
+        let mut graph = ModelGraph::new();
+        let part = |name: &str| Element::new_with_kind(ElementKind::PartUsage).with_name(name);
+        let pump = graph.add_element(part("pump"));
+        let valve = graph.add_element(part("valve"));
+        let tank = graph.add_element(part("tank"));
+        let sensor = graph.add_element(part("sensor"));
+
+        graph.add_relationship(Relationship::new(RelationshipKind::Flow, pump.clone(), valve.clone()));
+        graph.add_element(
+            Element::new_with_kind(ElementKind::FlowUsage)
+                .with_prop("source", Value::Ref(valve.clone()))
+                .with_prop("target", Value::Ref(tank.clone())),
+        );
+        graph.add_element(
+            Element::new_with_kind(ElementKind::ConnectionUsage)
+                .with_prop("source", Value::Ref(sensor.clone()))
+                .with_prop("target", Value::Ref(tank.clone())),
+        );
+
+        (graph, pump, valve, tank, sensor)
+    }
+
+    fn sorted_names(elements: &[&Element]) -> Vec<String> {
+        let mut sorted = names(elements);
+        sorted.sort();
+        sorted
+    }
+
+    #[test]
+    fn test_connected_to_and_flow_direction() {
+        let (graph, pump, valve, tank, _) = create_pipeline_graph();
+
+        assert_eq!(sorted_names(&connected_to(&graph, &valve)), vec!["pump", "tank"]);
+        assert_eq!(sorted_names(&connected_to(&graph, &tank)), vec!["sensor", "valve"]);
+        assert_eq!(sorted_names(&flow_sources(&graph, &valve)), vec!["pump"]);
+        assert_eq!(sorted_names(&flow_targets(&graph, &valve)), vec!["tank"]);
+        // Plain connections are not flows
+        assert_eq!(sorted_names(&flow_sources(&graph, &tank)), vec!["valve"]);
+        assert!(flow_targets(&graph, &tank).is_empty());
+        assert!(flow_sources(&graph, &pump).is_empty());
+    }
+
+    #[test]
+    fn test_flow_paths() {
+        let (graph, pump, valve, tank, sensor) = create_pipeline_graph();
+
+        assert_eq!(flow_paths(&graph, &pump, &tank, 5), vec![vec![pump.clone(), valve.clone(), tank.clone()]]);
+        assert!(flow_paths(&graph, &pump, &tank, 1).is_empty());
+        assert!(flow_paths(&graph, &tank, &pump, 5).is_empty());
+        assert!(flow_paths(&graph, &sensor, &tank, 5).is_empty());
+    }
+
+    #[test]
+    fn test_flow_paths_on_cycle() {
+        let (mut graph, pump, valve, tank, _) = create_pipeline_graph();
+        // tank -> pump closes the loop, and a bypass gives a second path
+        graph.add_relationship(Relationship::new(RelationshipKind::Flow, tank.clone(), pump.clone()));
+        graph.add_relationship(Relationship::new(RelationshipKind::Flow, pump.clone(), tank.clone()));
+
+        let paths = flow_paths(&graph, &pump, &tank, 10);
+        assert_eq!(paths, vec![vec![pump.clone(), tank.clone()], vec![pump.clone(), valve, tank.clone()]]);
+        assert_eq!(flow_paths(&graph, &pump, &tank, 1).len(), 1);
+        assert_eq!(flow_paths(&graph, &pump, &pump, 0), vec![vec![pump]]);
+    }
 }
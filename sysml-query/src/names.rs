@@ -0,0 +1,272 @@
+use std::collections::HashSet;
+
+use sysml_core::{Element, ElementKind, ModelGraph, QualifiedName};
+
+/// Find the elements at a qualified name such as `Vehicle::engine::mass`.
+///
+/// The name is resolved one segment at a time through the ownership
+/// hierarchy, comparing element names, so it works on a freshly parsed graph
+/// without precomputed qualified names. The first segment matches top-level
+/// elements (unowned, or owned by an unnamed root namespace). Usually there
+/// is at most one result; duplicate names within one namespace yield all of
+/// them, in ElementId order. An empty or malformed name matches nothing.
+pub fn find_by_qualified_name<'a>(graph: &'a ModelGraph, qualified_name: &str) -> Vec<&'a Element> {
+    let qname: QualifiedName = match qualified_name.parse() {
+        Ok(qname) => qname,
+        Err(_) => return Vec::new(),
+    };
+    let mut segments = qname.segments().iter();
+    let first = match segments.next() {
+        Some(first) => first,
+        None => return Vec::new(),
+    };
+
+    let mut current: Vec<&Element> = graph
+        .elements
+        .values()
+        .filter(|e| e.name.as_ref() == Some(first) && is_top_level(graph, e))
+        .collect();
+    for segment in segments {
+        current = current
+            .iter()
+            .flat_map(|owner| graph.children_of(&owner.id))
+            .filter(|child| child.name.as_ref() == Some(segment))
+            .collect();
+        current.sort_by(|a, b| a.id.cmp(&b.id));
+        current.dedup_by(|a, b| a.id == b.id);
+    }
+
+    current
+}
+
+fn is_top_level(graph: &ModelGraph, element: &Element) -> bool {
+    match element.owner.as_ref().and_then(|id| graph.get_element(id)) {
+        None => true,
+        Some(owner) => owner.name.is_none() && owner.owner.is_none(),
+    }
+}
+
+/// The `::`-separated names from the top-level ancestor down to `element`,
+/// ignoring an unnamed root namespace, as [`find_by_qualified_name`] resolves
+/// them. `None` for elements with an unnamed ancestor or an owner cycle.
+fn qualified_path(graph: &ModelGraph, element: &Element) -> Option<String> {
+    let mut segments = vec![element.name.as_deref()?];
+    let mut visited = HashSet::new();
+    let mut current = element;
+    while !is_top_level(graph, current) {
+        let owner = current.owner.as_ref().and_then(|id| graph.get_element(id))?;
+        if !visited.insert(&owner.id) {
+            return None;
+        }
+        segments.push(owner.name.as_deref()?);
+        current = owner;
+    }
+    segments.reverse();
+    Some(segments.join("::"))
+}
+
+/// A glob pattern over element names: `*` matches any run of characters
+/// (including none) and `?` matches exactly one.
+///
+/// Patterns are case-sensitive unless [`case_insensitive`](Self::case_insensitive)
+/// is set. A pattern containing `::` or `.` is matched against qualified names
+/// (resolved like [`find_by_qualified_name`]) rather than simple names; `.` is
+/// read as `::`, so `*.Req*` and `*::Req*` are the same pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamePattern {
+    pattern: Vec<char>,
+    case_insensitive: bool,
+}
+
+impl NamePattern {
+    /// Compile a glob pattern.
+    pub fn new(pattern: &str) -> Self {
+        NamePattern {
+            pattern: pattern.replace('.', "::").chars().collect(),
+            case_insensitive: false,
+        }
+    }
+
+    /// Ignore case when matching.
+    pub fn case_insensitive(mut self) -> Self {
+        self.pattern = self.pattern.into_iter().map(fold_case).collect();
+        self.case_insensitive = true;
+        self
+    }
+
+    /// Check whether the pattern should be matched against qualified names.
+    pub fn is_qualified(&self) -> bool {
+        self.pattern.windows(2).any(|pair| pair == [':', ':'])
+    }
+
+    /// Check whether a name matches the whole pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        let pattern = &self.pattern;
+        let name: Vec<char> = if self.case_insensitive {
+            name.chars().map(fold_case).collect()
+        } else {
+            name.chars().collect()
+        };
+
+        // Greedy matching that backtracks to the most recent `*`
+        let (mut p, mut n) = (0, 0);
+        let mut star: Option<(usize, usize)> = None;
+        while n < name.len() {
+            if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+                p += 1;
+                n += 1;
+            } else if p < pattern.len() && pattern[p] == '*' {
+                star = Some((p, n));
+                p += 1;
+            } else if let Some((star_p, star_n)) = star {
+                p = star_p + 1;
+                n = star_n + 1;
+                star = Some((star_p, star_n + 1));
+            } else {
+                return false;
+            }
+        }
+        pattern[p..].iter().all(|c| *c == '*')
+    }
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+impl From<&str> for NamePattern {
+    fn from(pattern: &str) -> Self {
+        NamePattern::new(pattern)
+    }
+}
+
+/// Find elements whose name matches a glob pattern, optionally filtered by kind.
+///
+/// # Arguments
+///
+/// * `graph` - The model graph to search
+/// * `kind` - Optional element kind filter
+/// * `pattern` - The pattern to match (see [`NamePattern`])
+///
+/// # Returns
+///
+/// An iterator over matching elements.
+pub fn find_by_pattern<'a>(
+    graph: &'a ModelGraph,
+    kind: Option<&'a ElementKind>,
+    pattern: &'a NamePattern,
+) -> impl Iterator<Item = &'a Element> {
+    graph.elements.values().filter(move |e| {
+        let kind_matches = kind.is_none_or(|k| &e.kind == k);
+        let name_matches = if pattern.is_qualified() {
+            qualified_path(graph, e).is_some_and(|qname| pattern.matches(&qname))
+        } else {
+            e.name.as_deref().is_some_and(|name| pattern.matches(name))
+        };
+        kind_matches && name_matches
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysml_core::ElementId;
+
+    /// Two packages that both contain an `engine` part with a `mass` attribute.
+    fn create_namespace_graph() -> (ModelGraph, ElementId, ElementId) {
+        let mut graph = ModelGraph::new();
+        let root = graph.add_element(Element::new_with_kind(ElementKind::Namespace));
+        let add = |graph: &mut ModelGraph, kind: ElementKind, name: &str, owner: &ElementId| {
+            graph.add_element(Element::new_with_kind(kind).with_name(name).with_owner(owner.clone()))
+        };
+
+        let car = add(&mut graph, ElementKind::Package, "Car", &root);
+        let car_engine = add(&mut graph, ElementKind::PartUsage, "engine", &car);
+        let car_mass = add(&mut graph, ElementKind::AttributeUsage, "mass", &car_engine);
+        add(&mut graph, ElementKind::RequirementUsage, "SpeedReq", &car);
+
+        let boat = add(&mut graph, ElementKind::Package, "Boat", &root);
+        let boat_engine = add(&mut graph, ElementKind::PartUsage, "engine", &boat);
+        add(&mut graph, ElementKind::AttributeUsage, "mass", &boat_engine);
+        add(&mut graph, ElementKind::RequirementUsage, "Hull_Req", &boat);
+
+        (graph, car_engine, car_mass)
+    }
+
+    #[test]
+    fn qualified_name_disambiguates_duplicate_simple_names() {
+        let (graph, car_engine, car_mass) = create_namespace_graph();
+        assert_eq!(crate::find_by_name(&graph, None, "engine").count(), 2);
+
+        let engines = find_by_qualified_name(&graph, "Car::engine");
+        assert_eq!(engines.len(), 1);
+        assert_eq!(engines[0].id, car_engine);
+
+        let masses = find_by_qualified_name(&graph, "Car::engine::mass");
+        assert_eq!(masses.len(), 1);
+        assert_eq!(masses[0].id, car_mass);
+        assert_ne!(find_by_qualified_name(&graph, "Boat::engine::mass")[0].id, car_mass);
+    }
+
+    #[test]
+    fn qualified_name_misses() {
+        let (graph, ..) = create_namespace_graph();
+        assert!(find_by_qualified_name(&graph, "engine").is_empty());
+        assert!(find_by_qualified_name(&graph, "Car::mass").is_empty());
+        assert!(find_by_qualified_name(&graph, "Car::engine::mass::x").is_empty());
+        assert!(find_by_qualified_name(&graph, "Car::::engine").is_empty());
+        assert!(find_by_qualified_name(&graph, "").is_empty());
+    }
+
+    #[test]
+    fn glob_matching() {
+        let pattern = NamePattern::new("*Req*");
+        assert!(pattern.matches("SpeedReq"));
+        assert!(pattern.matches("Req"));
+        assert!(!pattern.matches("speedreq"));
+        assert!(NamePattern::new("*Req*").case_insensitive().matches("speedreq"));
+
+        assert!(NamePattern::new("eng?ne").matches("engine"));
+        assert!(!NamePattern::new("eng?ne").matches("enggine"));
+        assert!(NamePattern::new("a*b*c").matches("aXXbYYbc"));
+        assert!(!NamePattern::new("a*b*c").matches("aXXbYY"));
+        assert!(NamePattern::new("*").matches(""));
+        assert!(!NamePattern::new("?").matches(""));
+        assert!(NamePattern::new("").matches(""));
+    }
+
+    #[test]
+    fn dots_separate_qualified_segments() {
+        let pattern = NamePattern::new("*.Req*");
+        assert!(pattern.is_qualified());
+        assert_eq!(pattern, NamePattern::new("*::Req*"));
+        assert!(pattern.matches("Car::ReqList"));
+        assert!(!pattern.matches("ReqList"));
+
+        let (graph, _, car_mass) = create_namespace_graph();
+        let pattern = NamePattern::new("*.*req").case_insensitive();
+        assert_eq!(find_by_pattern(&graph, None, &pattern).count(), 2);
+        let pattern = NamePattern::new("Car.engine.mass");
+        let masses: Vec<_> = find_by_pattern(&graph, None, &pattern).collect();
+        assert_eq!(masses.len(), 1);
+        assert_eq!(masses[0].id, car_mass);
+    }
+
+    #[test]
+    fn find_by_pattern_simple_and_qualified() {
+        let (graph, car_engine, _) = create_namespace_graph();
+
+        let pattern = NamePattern::from("*Req");
+        let reqs: Vec<_> = find_by_pattern(&graph, None, &pattern).collect();
+        assert_eq!(reqs.len(), 2);
+
+        let pattern = NamePattern::new("car::*").case_insensitive();
+        let in_car: Vec<_> = find_by_pattern(&graph, Some(&ElementKind::PartUsage), &pattern).collect();
+        assert_eq!(in_car.len(), 1);
+        assert_eq!(in_car[0].id, car_engine);
+
+        let pattern = NamePattern::new("*::engine::mass");
+        let masses: Vec<_> = find_by_pattern(&graph, None, &pattern).collect();
+        assert_eq!(masses.len(), 2);
+    }
+}
@@ -0,0 +1,455 @@
+use std::collections::HashSet;
+
+use sysml_core::{Element, ElementId, ElementKind, ModelGraph, RelationshipKind, Value};
+
+/// A chained path query over a ModelGraph.
+///
+/// A query starts from a set of elements and applies its steps in order;
+/// nothing is evaluated until [`run`](Query::run). Filter steps keep some of
+/// the current elements, navigation steps replace them with related ones.
+/// Every step deduplicates by ElementId, so diamond-shaped graphs do not
+/// multiply the results. Results keep the order in which elements are first
+/// reached, starting from ElementId order.
+///
+/// # Example
+///
+/// ```
+/// use sysml_core::{ElementKind, ModelGraph, RelationshipKind};
+/// use sysml_query::Query;
+///
+/// let graph = ModelGraph::new();
+/// let requirements = Query::from_kind(ElementKind::PartUsage)
+///     .named_contains("Engine")
+///     .outgoing(RelationshipKind::Satisfy)
+///     .targets()
+///     .of_kind(ElementKind::RequirementUsage)
+///     .run(&graph);
+/// assert!(requirements.is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Query {
+    start: Start,
+    steps: Vec<Step>,
+}
+
+/// A query step that follows relationships of one kind; pick an end with
+/// [`targets`](Traversal::targets) or [`sources`](Traversal::sources).
+#[derive(Debug, Clone)]
+pub struct Traversal {
+    query: Query,
+    direction: Direction,
+    kind: RelationshipKind,
+}
+
+#[derive(Debug, Clone)]
+enum Start {
+    All,
+    Kind(ElementKind),
+    Id(ElementId),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Outgoing,
+    Incoming,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum End {
+    Source,
+    Target,
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Kind(ElementKind),
+    Named(String),
+    NameContains(String),
+    Prop(String, Value),
+    WithoutIncoming(RelationshipKind),
+    Follow(Direction, RelationshipKind, End),
+    Children,
+    Owner,
+    Descendants,
+    Ancestors,
+}
+
+impl Query {
+    /// Start from every element.
+    pub fn all() -> Self {
+        Self::starting_at(Start::All)
+    }
+
+    /// Start from the elements of one kind.
+    pub fn from_kind(kind: ElementKind) -> Self {
+        Self::starting_at(Start::Kind(kind))
+    }
+
+    /// Start from a single element.
+    pub fn from_id(id: ElementId) -> Self {
+        Self::starting_at(Start::Id(id))
+    }
+
+    fn starting_at(start: Start) -> Self {
+        Query { start, steps: Vec::new() }
+    }
+
+    fn then(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Keep elements of this kind.
+    pub fn of_kind(self, kind: ElementKind) -> Self {
+        self.then(Step::Kind(kind))
+    }
+
+    /// Keep elements with exactly this name.
+    pub fn named(self, name: impl Into<String>) -> Self {
+        self.then(Step::Named(name.into()))
+    }
+
+    /// Keep elements whose name contains `pattern`.
+    pub fn named_contains(self, pattern: impl Into<String>) -> Self {
+        self.then(Step::NameContains(pattern.into()))
+    }
+
+    /// Keep elements whose property `key` equals `value`.
+    pub fn with_prop(self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.then(Step::Prop(key.into(), value.into()))
+    }
+
+    /// Keep elements that no relationship of this kind targets.
+    pub fn without_incoming(self, kind: RelationshipKind) -> Self {
+        self.then(Step::WithoutIncoming(kind))
+    }
+
+    /// Follow relationships of this kind that start at the current elements.
+    pub fn outgoing(self, kind: RelationshipKind) -> Traversal {
+        Traversal { query: self, direction: Direction::Outgoing, kind }
+    }
+
+    /// Follow relationships of this kind that end at the current elements.
+    pub fn incoming(self, kind: RelationshipKind) -> Traversal {
+        Traversal { query: self, direction: Direction::Incoming, kind }
+    }
+
+    /// Move to the directly owned elements.
+    pub fn children(self) -> Self {
+        self.then(Step::Children)
+    }
+
+    /// Move to the owning element.
+    pub fn owner(self) -> Self {
+        self.then(Step::Owner)
+    }
+
+    /// Move to the owned elements, recursively.
+    pub fn descendants(self) -> Self {
+        self.then(Step::Descendants)
+    }
+
+    /// Move to the owner chain, nearest owner first.
+    pub fn ancestors(self) -> Self {
+        self.then(Step::Ancestors)
+    }
+
+    /// Evaluate the query.
+    pub fn run<'a>(&self, graph: &'a ModelGraph) -> Vec<&'a Element> {
+        let mut current: Vec<&Element> = match &self.start {
+            Start::All => graph.elements.values().collect(),
+            Start::Kind(kind) => graph.elements_by_kind(kind).collect(),
+            Start::Id(id) => graph.get_element(id).into_iter().collect(),
+        };
+
+        for step in &self.steps {
+            current = match step {
+                Step::Kind(kind) => retain(current, |e| &e.kind == kind),
+                Step::Named(name) => retain(current, |e| e.name.as_deref() == Some(name.as_str())),
+                Step::NameContains(pattern) => {
+                    retain(current, |e| e.name.as_ref().is_some_and(|n| n.contains(pattern.as_str())))
+                }
+                Step::Prop(key, value) => retain(current, |e| e.get_prop(key) == Some(value)),
+                Step::WithoutIncoming(kind) => {
+                    retain(current, |e| !graph.incoming(&e.id).any(|r| &r.kind == kind))
+                }
+                Step::Follow(direction, kind, end) => navigate(current, |e, next| {
+                    let relationships: Vec<_> = match direction {
+                        Direction::Outgoing => graph.outgoing(&e.id).collect(),
+                        Direction::Incoming => graph.incoming(&e.id).collect(),
+                    };
+                    for rel in relationships.into_iter().filter(|r| &r.kind == kind) {
+                        let id = match end {
+                            End::Source => &rel.source,
+                            End::Target => &rel.target,
+                        };
+                        next.extend(graph.get_element(id));
+                    }
+                }),
+                Step::Children => navigate(current, |e, next| next.extend(graph.children_of(&e.id))),
+                Step::Owner => navigate(current, |e, next| {
+                    next.extend(e.owner.as_ref().and_then(|id| graph.get_element(id)))
+                }),
+                Step::Descendants => navigate(current, |e, next| {
+                    let mut seen = HashSet::new();
+                    let mut stack = vec![&e.id];
+                    while let Some(id) = stack.pop() {
+                        for child in graph.children_of(id) {
+                            if seen.insert(&child.id) {
+                                next.push(child);
+                                stack.push(&child.id);
+                            }
+                        }
+                    }
+                }),
+                Step::Ancestors => navigate(current, |e, next| {
+                    let mut seen = HashSet::new();
+                    let mut owner = e.owner.as_ref().and_then(|id| graph.get_element(id));
+                    while let Some(element) = owner {
+                        if !seen.insert(&element.id) {
+                            break;
+                        }
+                        next.push(element);
+                        owner = element.owner.as_ref().and_then(|id| graph.get_element(id));
+                    }
+                }),
+            };
+        }
+
+        current
+    }
+}
+
+impl Traversal {
+    /// Move to the targets of the followed relationships.
+    pub fn targets(self) -> Query {
+        self.query.then(Step::Follow(self.direction, self.kind, End::Target))
+    }
+
+    /// Move to the sources of the followed relationships.
+    pub fn sources(self) -> Query {
+        self.query.then(Step::Follow(self.direction, self.kind, End::Source))
+    }
+}
+
+fn retain(elements: Vec<&Element>, mut keep: impl FnMut(&Element) -> bool) -> Vec<&Element> {
+    elements.into_iter().filter(|e| keep(e)).collect()
+}
+
+/// Replace each element by the ones `step` reaches from it, deduplicated.
+fn navigate<'a>(
+    elements: Vec<&'a Element>,
+    mut step: impl FnMut(&'a Element, &mut Vec<&'a Element>),
+) -> Vec<&'a Element> {
+    let mut seen: HashSet<&ElementId> = HashSet::new();
+    let mut result = Vec::new();
+    let mut next = Vec::new();
+    for element in elements {
+        step(element, &mut next);
+        for reached in next.drain(..) {
+            if seen.insert(&reached.id) {
+                result.push(reached);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ancestors, descendants, elements_satisfying, elements_verifying, find_by_name, find_by_name_contains,
+        find_by_property, requirements_satisfied_by, requirements_unverified, trace_matrix,
+    };
+    use sysml_core::Relationship;
+
+    /// Vehicle package with an engine satisfying two requirements, one of
+    /// which is verified.
+    fn create_test_graph() -> (ModelGraph, ElementId, ElementId, ElementId) {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Vehicle"));
+        let powertrain = graph.add_element(
+            Element::new_with_kind(ElementKind::PartUsage)
+                .with_name("Powertrain")
+                .with_owner(pkg.clone()),
+        );
+        let engine = graph.add_element(
+            Element::new_with_kind(ElementKind::PartUsage)
+                .with_name("MainEngine")
+                .with_owner(powertrain.clone())
+                .with_prop("mass", 200),
+        );
+        graph.add_element(
+            Element::new_with_kind(ElementKind::PartUsage)
+                .with_name("Wheel")
+                .with_owner(pkg.clone())
+                .with_prop("mass", 20),
+        );
+        let power = graph.add_element(
+            Element::new_with_kind(ElementKind::RequirementUsage)
+                .with_name("PowerReq")
+                .with_owner(pkg.clone()),
+        );
+        let emissions = graph.add_element(
+            Element::new_with_kind(ElementKind::RequirementUsage)
+                .with_name("EmissionsReq")
+                .with_owner(pkg.clone()),
+        );
+        let test = graph.add_element(
+            Element::new_with_kind(ElementKind::VerificationCaseUsage)
+                .with_name("PowerTest")
+                .with_owner(pkg.clone()),
+        );
+
+        graph.add_relationship(Relationship::new(RelationshipKind::Satisfy, engine.clone(), power.clone()));
+        graph.add_relationship(Relationship::new(RelationshipKind::Satisfy, engine.clone(), emissions));
+        graph.add_relationship(Relationship::new(RelationshipKind::Verify, test, power.clone()));
+
+        (graph, pkg, engine, power)
+    }
+
+    fn ids<'a>(elements: impl IntoIterator<Item = &'a Element>) -> Vec<ElementId> {
+        let mut ids: Vec<ElementId> = elements.into_iter().map(|e| e.id.clone()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn query_matches_find_by_name() {
+        let (graph, ..) = create_test_graph();
+        let query = Query::from_kind(ElementKind::PartUsage).named("Wheel").run(&graph);
+        assert_eq!(ids(query), ids(find_by_name(&graph, Some(&ElementKind::PartUsage), "Wheel")));
+    }
+
+    #[test]
+    fn query_matches_find_by_name_contains() {
+        let (graph, ..) = create_test_graph();
+        let query = Query::all().named_contains("Req").run(&graph);
+        assert_eq!(query.len(), 2);
+        assert_eq!(ids(query), ids(find_by_name_contains(&graph, None, "Req")));
+    }
+
+    #[test]
+    fn query_matches_find_by_property() {
+        let (graph, ..) = create_test_graph();
+        let query = Query::all().with_prop("mass", 200).run(&graph);
+        assert_eq!(ids(query), ids(find_by_property(&graph, "mass", &Value::from(200))));
+    }
+
+    #[test]
+    fn query_matches_requirements_unverified() {
+        let (graph, ..) = create_test_graph();
+        let query = Query::from_kind(ElementKind::RequirementUsage)
+            .without_incoming(RelationshipKind::Verify)
+            .run(&graph);
+        assert_eq!(query[0].name.as_deref(), Some("EmissionsReq"));
+        assert_eq!(ids(query), ids(requirements_unverified(&graph)));
+    }
+
+    #[test]
+    fn query_matches_elements_satisfying() {
+        let (graph, _, _, power) = create_test_graph();
+        let query = Query::from_id(power.clone()).incoming(RelationshipKind::Satisfy).sources().run(&graph);
+        assert_eq!(ids(query), ids(elements_satisfying(&graph, &power)));
+    }
+
+    #[test]
+    fn query_matches_elements_verifying() {
+        let (graph, _, _, power) = create_test_graph();
+        let query = Query::from_id(power.clone()).incoming(RelationshipKind::Verify).sources().run(&graph);
+        assert_eq!(ids(query), ids(elements_verifying(&graph, &power)));
+    }
+
+    #[test]
+    fn query_matches_requirements_satisfied_by() {
+        let (graph, _, engine, _) = create_test_graph();
+        let query = Query::from_id(engine.clone()).outgoing(RelationshipKind::Satisfy).targets().run(&graph);
+        assert_eq!(query.len(), 2);
+        assert_eq!(ids(query), ids(requirements_satisfied_by(&graph, &engine)));
+    }
+
+    #[test]
+    fn query_matches_ancestors_in_order() {
+        let (graph, _, engine, _) = create_test_graph();
+        let query: Vec<_> = Query::from_id(engine.clone()).ancestors().run(&graph);
+        let names: Vec<_> = query.iter().filter_map(|e| e.name.as_deref()).collect();
+        assert_eq!(names, vec!["Powertrain", "Vehicle"]);
+        assert_eq!(ids(query), ids(ancestors(&graph, &engine)));
+    }
+
+    #[test]
+    fn query_matches_descendants() {
+        let (graph, pkg, ..) = create_test_graph();
+        let query = Query::from_id(pkg.clone()).descendants().run(&graph);
+        assert_eq!(query.len(), 6);
+        assert_eq!(ids(query), ids(descendants(&graph, &pkg)));
+    }
+
+    #[test]
+    fn query_matches_trace_matrix() {
+        let (graph, ..) = create_test_graph();
+        let query = Query::from_kind(ElementKind::PartUsage)
+            .named_contains("Engine")
+            .outgoing(RelationshipKind::Satisfy)
+            .targets()
+            .of_kind(ElementKind::RequirementUsage)
+            .run(&graph);
+        let matrix = trace_matrix(
+            &graph,
+            &ElementKind::PartUsage,
+            &RelationshipKind::Satisfy,
+            &ElementKind::RequirementUsage,
+        );
+        assert_eq!(ids(query), ids(matrix.iter().filter_map(|row| graph.get_element(&row.target))));
+    }
+
+    #[test]
+    fn query_children_and_owner_compose() {
+        let (graph, pkg, engine, _) = create_test_graph();
+
+        // Siblings of the engine's owner, i.e. everything the package owns
+        let query = Query::from_id(engine).owner().owner().children().of_kind(ElementKind::PartUsage).run(&graph);
+        let names: Vec<_> = query.iter().filter_map(|e| e.name.as_deref()).collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"Powertrain") && names.contains(&"Wheel"));
+
+        assert!(Query::from_id(pkg).owner().run(&graph).is_empty());
+        assert!(Query::from_id(ElementId::new_v4()).children().run(&graph).is_empty());
+    }
+
+    #[test]
+    fn query_deduplicates_diamonds() {
+        // Ten layers of two nodes, each node linked to both nodes of the next
+        // layer: 2^10 paths, but only two elements per layer
+        let mut graph = ModelGraph::new();
+        let root = graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("root"));
+        let mut layer = vec![root.clone()];
+        for depth in 0..10 {
+            let next: Vec<ElementId> = (0..2)
+                .map(|i| {
+                    graph.add_element(
+                        Element::new_with_kind(ElementKind::PartUsage).with_name(format!("n{}_{}", depth, i)),
+                    )
+                })
+                .collect();
+            for from in &layer {
+                for to in &next {
+                    graph.add_relationship(Relationship::new(RelationshipKind::Flow, from.clone(), to.clone()));
+                }
+            }
+            layer = next;
+        }
+
+        let mut query = Query::from_id(root);
+        for _ in 0..10 {
+            query = query.outgoing(RelationshipKind::Flow).targets();
+        }
+        let result = query.run(&graph);
+        assert_eq!(ids(result.iter().copied()), {
+            let mut last = layer;
+            last.sort();
+            last
+        });
+    }
+}
@@ -0,0 +1,591 @@
+//! # sysml-run-activity
+//!
+//! Activity (action flow) compilation and execution for SysML v2.
+//!
+//! This crate provides:
+//! - Compilation from ModelGraph action definitions to ActivityIR
+//! - A runner that executes the actions in succession order, one action or
+//!   parallel group per step
+
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use sysml_core::{Element, ElementId, ElementKind, ModelGraph, RelationshipKind};
+use sysml_run::{ActivityIR, ActivityNodeIR, CompileToIR, Runner, StepResult, SuccessionIR};
+use sysml_run_statemachine::parse_action;
+use sysml_span::Diagnostic;
+
+/// Names that stand for the start and end of an activity, as in
+/// `first start then a;` and `then done;`.
+const START: &str = "start";
+const DONE: &str = "done";
+
+/// Compiler for activities.
+///
+/// An activity is an ActionDefinition that owns ActionUsages. Its nodes are
+/// the owned actions, with ForkNode and JoinNode children as control nodes,
+/// and its edges come from the Succession elements it owns (through
+/// `source`/`target` references or `unresolved_source`/`unresolved_target`
+/// names) and from Flow relationships between its actions. Nodes and edges
+/// keep declaration (source span) order. Successions from
+/// `start` or to `done` carry no ordering and are skipped. An action's
+/// `action` or `body` property is parsed as its body.
+///
+/// Compilation fails on successions that do not connect two nodes of the
+/// activity, on decision and merge nodes, and on succession cycles.
+pub struct ActivityCompiler;
+
+impl ActivityCompiler {
+    /// Compile the action definition with the given simple or qualified name.
+    pub fn compile_by_name(graph: &ModelGraph, name: &str) -> Result<ActivityIR, Vec<Diagnostic>> {
+        let qualified = name.contains("::");
+        let found = Self::activities(graph)
+            .filter(|e| {
+                if qualified {
+                    graph
+                        .qualified_name_of(&e.id)
+                        .is_some_and(|qn| qn.to_string() == name)
+                } else {
+                    e.name.as_deref() == Some(name)
+                }
+            })
+            .min_by(|a, b| a.id.cmp(&b.id));
+
+        match found {
+            Some(definition) => Self::compile_definition(graph, definition),
+            None => Err(vec![Diagnostic::error(format!("Activity '{}' not found", name))
+                .with_note(Self::available_note(graph))]),
+        }
+    }
+
+    /// Action definitions that own at least one ActionUsage.
+    fn activities(graph: &ModelGraph) -> impl Iterator<Item = &Element> {
+        graph
+            .elements_by_kind(&ElementKind::ActionDefinition)
+            .filter(move |def| graph.children_of(&def.id).any(|c| is_kind_or_subtype(c, ElementKind::ActionUsage)))
+    }
+
+    /// A note naming the activities in a model.
+    fn available_note(graph: &ModelGraph) -> String {
+        let mut available: Vec<String> = Self::activities(graph)
+            .map(|def| {
+                graph
+                    .qualified_name_of(&def.id)
+                    .map(|qn| qn.to_string())
+                    .or_else(|| def.name.clone())
+                    .unwrap_or_else(|| def.id.to_string())
+            })
+            .collect();
+        available.sort();
+        if available.is_empty() {
+            "no activities in model".to_string()
+        } else {
+            format!("available activities: {}", available.join(", "))
+        }
+    }
+
+    /// Compile a specific action definition element.
+    fn compile_definition(graph: &ModelGraph, definition: &Element) -> Result<ActivityIR, Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        let mut ir = ActivityIR::new(definition.name.clone().unwrap_or_else(|| "Activity".to_string()));
+
+        // Nodes, keyed by id so references can be resolved to names
+        let mut names: HashMap<&ElementId, String> = HashMap::new();
+        let children = declaration_order(graph.children_of(&definition.id));
+        for child in children.iter().copied() {
+            if !is_kind_or_subtype(child, ElementKind::ActionUsage) {
+                continue;
+            }
+            let name = child.name.clone().unwrap_or_else(|| child.id.to_string());
+            let node = match child.kind {
+                ElementKind::ForkNode => ActivityNodeIR::fork(name.clone()),
+                ElementKind::JoinNode => ActivityNodeIR::join(name.clone()),
+                ElementKind::DecisionNode | ElementKind::MergeNode => {
+                    diagnostics.push(Diagnostic::error(format!(
+                        "{} '{}' is not supported in activity '{}'",
+                        child.kind.as_str(),
+                        name,
+                        ir.name
+                    )));
+                    continue;
+                }
+                _ => match text_prop(child, &["action", "body"]) {
+                    Some(body) => ActivityNodeIR::action(name.clone()).with_body(parse_action(body)),
+                    None => ActivityNodeIR::action(name.clone()),
+                },
+            };
+            names.insert(&child.id, name);
+            ir = ir.with_node(node);
+        }
+
+        let mut endpoints: Vec<(Option<String>, Option<String>)> = children
+            .iter()
+            .filter(|c| is_kind_or_subtype(c, ElementKind::Succession))
+            .map(|succession| {
+                (
+                    endpoint_name(graph, succession, "source"),
+                    endpoint_name(graph, succession, "target"),
+                )
+            })
+            .collect();
+        endpoints.extend(
+            graph
+                .relationships_by_kind(&RelationshipKind::Flow)
+                .filter(|rel| names.contains_key(&rel.source) && names.contains_key(&rel.target))
+                .map(|rel| (names.get(&rel.source).cloned(), names.get(&rel.target).cloned())),
+        );
+
+        for (from, to) in endpoints {
+            let (from, to) = match (from, to) {
+                (Some(from), Some(to)) => (from, to),
+                _ => {
+                    diagnostics.push(Diagnostic::error(format!(
+                        "Succession in activity '{}' is missing its source or target",
+                        ir.name
+                    )));
+                    continue;
+                }
+            };
+            if from == START || to == DONE {
+                continue;
+            }
+            let unknown: Vec<&str> = [&from, &to]
+                .into_iter()
+                .filter(|name| ir.find_node(name).is_none())
+                .map(|name| name.as_str())
+                .collect();
+            if !unknown.is_empty() {
+                diagnostics.push(Diagnostic::error(format!(
+                    "Succession {} -> {} references unknown action '{}' in activity '{}'",
+                    from, to, unknown[0], ir.name
+                )));
+                continue;
+            }
+            let edge = SuccessionIR::new(from, to);
+            if !ir.edges.contains(&edge) {
+                ir.edges.push(edge);
+            }
+        }
+
+        if let Some(cycle) = find_cycle(&ir) {
+            diagnostics.push(
+                Diagnostic::error(format!("Activity '{}' has a succession cycle", ir.name))
+                    .with_note(format!("cycle: {}", cycle.join(" -> "))),
+            );
+        }
+
+        if diagnostics.is_empty() {
+            Ok(ir)
+        } else {
+            Err(diagnostics)
+        }
+    }
+}
+
+impl CompileToIR<ActivityIR> for ActivityCompiler {
+    /// Compiles the model's only activity.
+    ///
+    /// Fails when the model has several action definitions that own actions;
+    /// use [`ActivityCompiler::compile_by_name`] to choose.
+    fn compile(graph: &ModelGraph) -> Result<ActivityIR, Vec<Diagnostic>> {
+        let activities: Vec<&Element> = ActivityCompiler::activities(graph).collect();
+        match activities.as_slice() {
+            [definition] => ActivityCompiler::compile_definition(graph, definition),
+            [] => Err(vec![Diagnostic::error("No activity found in model")]),
+            _ => Err(vec![Diagnostic::error(format!(
+                "Model has {} activities; select one by name",
+                activities.len()
+            ))
+            .with_note(ActivityCompiler::available_note(graph))]),
+        }
+    }
+}
+
+fn is_kind_or_subtype(element: &Element, kind: ElementKind) -> bool {
+    element.kind == kind || element.kind.is_subtype_of(kind)
+}
+
+/// Sort elements by where they are declared, falling back to id order for
+/// elements without a span.
+fn declaration_order<'a>(elements: impl Iterator<Item = &'a Element>) -> Vec<&'a Element> {
+    let mut elements: Vec<&Element> = elements.collect();
+    elements.sort_by(|a, b| {
+        match (a.spans.first(), b.spans.first()) {
+            (Some(x), Some(y)) => x.start.cmp(&y.start),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+        .then_with(|| a.id.cmp(&b.id))
+    });
+    elements
+}
+
+fn text_prop<'a>(element: &'a Element, keys: &[&str]) -> Option<&'a str> {
+    keys.iter().find_map(|key| element.get_str(key))
+}
+
+/// The name of a succession end, from a reference, a name, or the parser's
+/// unresolved name.
+fn endpoint_name(graph: &ModelGraph, succession: &Element, end: &str) -> Option<String> {
+    succession
+        .props
+        .get(end)
+        .and_then(|v| {
+            v.as_ref()
+                .and_then(|id| graph.get_element(id))
+                .and_then(|e| e.name.clone())
+                .or_else(|| v.as_str().map(String::from))
+        })
+        .or_else(|| succession.get_str(&format!("unresolved_{}", end)).map(String::from))
+}
+
+/// Find a succession cycle, returned as the node names along it with the
+/// first node repeated at the end.
+fn find_cycle(ir: &ActivityIR) -> Option<Vec<String>> {
+    fn visit<'a>(
+        ir: &'a ActivityIR,
+        node: &'a str,
+        path: &mut Vec<&'a str>,
+        finished: &mut HashSet<&'a str>,
+    ) -> Option<Vec<String>> {
+        if let Some(start) = path.iter().position(|n| *n == node) {
+            let mut cycle: Vec<String> = path[start..].iter().map(|n| n.to_string()).collect();
+            cycle.push(node.to_string());
+            return Some(cycle);
+        }
+        if !finished.insert(node) {
+            return None;
+        }
+        path.push(node);
+        for next in ir.successors(node) {
+            if let Some(cycle) = visit(ir, next, path, finished) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        None
+    }
+
+    let mut finished = HashSet::new();
+    ir.nodes
+        .iter()
+        .find_map(|node| visit(ir, &node.name, &mut Vec::new(), &mut finished))
+}
+
+/// A runner that executes an activity.
+///
+/// Each step runs every action whose predecessors have all finished: one
+/// action at a time along a sequence, or a whole parallel group after a
+/// fork. Fork and join nodes are passed through without a step of their own.
+/// Every executed action adds an output with its name, followed by its body
+/// when it has one (`name: body`). The activity completes with the step that
+/// runs its last actions. Events passed to `step` are ignored.
+pub struct ActivityRunner {
+    ir: ActivityIR,
+    /// Nodes that have finished.
+    finished: BTreeSet<String>,
+    /// The actions run by the most recent step, or `start` before the first.
+    current: String,
+    completed: bool,
+}
+
+impl ActivityRunner {
+    /// Create a new runner from IR.
+    pub fn new(ir: ActivityIR) -> Self {
+        let completed = ir.nodes.is_empty();
+        ActivityRunner {
+            ir,
+            finished: BTreeSet::new(),
+            current: START.to_string(),
+            completed,
+        }
+    }
+
+    /// Create a runner from a ModelGraph.
+    pub fn from_graph(graph: &ModelGraph) -> Result<Self, Vec<Diagnostic>> {
+        let ir = ActivityCompiler::compile(graph)?;
+        Ok(Self::new(ir))
+    }
+
+    /// Get the names of the actions that the next step will run.
+    pub fn ready_actions(&self) -> Vec<&str> {
+        let mut finished = self.finished.clone();
+        pass_control_nodes(&self.ir, &mut finished);
+        ready_nodes(&self.ir, &finished)
+            .into_iter()
+            .map(|node| node.name.as_str())
+            .collect()
+    }
+
+    /// Get the names of the nodes that have finished, in name order.
+    pub fn finished(&self) -> Vec<&str> {
+        self.finished.iter().map(String::as_str).collect()
+    }
+}
+
+/// Nodes that have not finished but whose predecessors all have.
+fn ready_nodes<'a>(ir: &'a ActivityIR, finished: &BTreeSet<String>) -> Vec<&'a ActivityNodeIR> {
+    ir.nodes
+        .iter()
+        .filter(|node| {
+            !finished.contains(&node.name) && ir.predecessors(&node.name).iter().all(|p| finished.contains(*p))
+        })
+        .collect()
+}
+
+/// Mark control nodes finished as soon as they are reached.
+fn pass_control_nodes(ir: &ActivityIR, finished: &mut BTreeSet<String>) {
+    loop {
+        let reached: Vec<String> = ready_nodes(ir, finished)
+            .into_iter()
+            .filter(|node| node.is_control())
+            .map(|node| node.name.clone())
+            .collect();
+        if reached.is_empty() {
+            break;
+        }
+        finished.extend(reached);
+    }
+}
+
+impl Runner for ActivityRunner {
+    fn step(&mut self, _event: Option<&str>) -> StepResult {
+        if self.completed {
+            return StepResult::new(self.current_state()).completed();
+        }
+
+        pass_control_nodes(&self.ir, &mut self.finished);
+        let group: Vec<ActivityNodeIR> = self
+            .ready_actions()
+            .into_iter()
+            .filter_map(|name| self.ir.find_node(name).cloned())
+            .collect();
+
+        let mut outputs = Vec::new();
+        for node in &group {
+            match &node.action {
+                Some(action) => outputs.push(format!("{}: {}", node.name, action)),
+                None => outputs.push(node.name.clone()),
+            }
+            self.finished.insert(node.name.clone());
+        }
+        if !group.is_empty() {
+            self.current = group.iter().map(|n| n.name.as_str()).collect::<Vec<_>>().join(", ");
+        }
+
+        pass_control_nodes(&self.ir, &mut self.finished);
+        self.completed = self.ir.nodes.iter().all(|n| self.finished.contains(&n.name));
+
+        let result = StepResult::new(self.current_state()).with_outputs(outputs);
+        if self.completed {
+            result.completed()
+        } else {
+            result
+        }
+    }
+
+    fn current_state(&self) -> &str {
+        &self.current
+    }
+
+    fn is_completed(&self) -> bool {
+        self.completed
+    }
+
+    fn reset(&mut self) {
+        self.finished.clear();
+        self.current = START.to_string();
+        self.completed = self.ir.nodes.is_empty();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysml_core::Relationship;
+    use sysml_run::ActivityNodeKind;
+    use sysml_span::Span;
+
+    /// Add an element with a span after every element added so far, so the
+    /// graph keeps declaration order.
+    fn declare(graph: &mut ModelGraph, element: Element) -> ElementId {
+        let offset = graph.elements.len() * 10;
+        graph.add_element(element.with_span(Span::new("test.sysml", offset, offset + 5)))
+    }
+
+    /// Add an element owned by `owner`.
+    fn add(graph: &mut ModelGraph, kind: ElementKind, name: &str, owner: &ElementId) -> ElementId {
+        declare(graph, Element::new_with_kind(kind).with_name(name).with_owner(owner.clone()))
+    }
+
+    /// Add a succession as the parser emits it for `first <from> then <to>;`.
+    fn succession(graph: &mut ModelGraph, owner: &ElementId, from: &str, to: &str) {
+        declare(
+            graph,
+            Element::new_with_kind(ElementKind::SuccessionAsUsage)
+                .with_owner(owner.clone())
+                .with_prop("unresolved_source", from)
+                .with_prop("unresolved_target", to),
+        );
+    }
+
+    /// `action def Startup { first start then check; then warmUp; then run; then done; }`
+    fn create_linear_graph() -> ModelGraph {
+        let mut graph = ModelGraph::new();
+        let def = graph.add_element(Element::new_with_kind(ElementKind::ActionDefinition).with_name("Startup"));
+        add(&mut graph, ElementKind::ActionUsage, "check", &def);
+        let warm_up = add(&mut graph, ElementKind::ActionUsage, "warmUp", &def);
+        add(&mut graph, ElementKind::ActionUsage, "run", &def);
+        graph.elements.get_mut(&warm_up).unwrap().set_prop("body", "temp += 40");
+
+        succession(&mut graph, &def, "start", "check");
+        succession(&mut graph, &def, "check", "warmUp");
+        succession(&mut graph, &def, "warmUp", "run");
+        succession(&mut graph, &def, "run", "done");
+        graph
+    }
+
+    /// prepare -> fork -> (left, right) -> join -> finish
+    fn create_diamond_graph() -> (ModelGraph, ElementId) {
+        let mut graph = ModelGraph::new();
+        let def = graph.add_element(Element::new_with_kind(ElementKind::ActionDefinition).with_name("Assemble"));
+        let prepare = add(&mut graph, ElementKind::ActionUsage, "prepare", &def);
+        add(&mut graph, ElementKind::ForkNode, "split", &def);
+        add(&mut graph, ElementKind::ActionUsage, "left", &def);
+        add(&mut graph, ElementKind::ActionUsage, "right", &def);
+        add(&mut graph, ElementKind::JoinNode, "merged", &def);
+        let finish = add(&mut graph, ElementKind::ActionUsage, "finish", &def);
+
+        // The first and last edges as references, the rest by name
+        declare(
+            &mut graph,
+            Element::new_with_kind(ElementKind::Succession)
+                .with_owner(def.clone())
+                .with_prop("source", prepare)
+                .with_prop("target", "split"),
+        );
+        succession(&mut graph, &def, "split", "left");
+        succession(&mut graph, &def, "split", "right");
+        succession(&mut graph, &def, "left", "merged");
+        succession(&mut graph, &def, "right", "merged");
+        declare(
+            &mut graph,
+            Element::new_with_kind(ElementKind::Succession)
+                .with_owner(def.clone())
+                .with_prop("source", "merged")
+                .with_prop("target", finish),
+        );
+        (graph, def)
+    }
+
+    #[test]
+    fn compile_linear_activity() {
+        let ir = ActivityCompiler::compile(&create_linear_graph()).unwrap();
+
+        assert_eq!(ir.name, "Startup");
+        assert_eq!(ir.nodes.len(), 3);
+        assert_eq!(
+            ir.edges,
+            vec![SuccessionIR::new("check", "warmUp"), SuccessionIR::new("warmUp", "run")]
+        );
+        assert!(ir.find_node("warmUp").unwrap().action.is_some());
+    }
+
+    #[test]
+    fn linear_activity_runs_one_action_per_step() {
+        let mut runner = ActivityRunner::from_graph(&create_linear_graph()).unwrap();
+        assert_eq!(runner.current_state(), "start");
+        assert_eq!(runner.ready_actions(), vec!["check"]);
+
+        let result = runner.step(None);
+        assert_eq!(result.outputs, vec!["check"]);
+        assert!(!result.completed);
+
+        let result = runner.step(None);
+        assert_eq!(result.outputs, vec!["warmUp: temp += 40"]);
+        assert_eq!(runner.current_state(), "warmUp");
+
+        let result = runner.step(Some("ignored"));
+        assert_eq!(result.outputs, vec!["run"]);
+        assert!(result.completed && runner.is_completed());
+
+        // Further steps do nothing
+        let result = runner.step(None);
+        assert!(result.completed && result.outputs.is_empty());
+
+        runner.reset();
+        assert!(!runner.is_completed());
+        assert_eq!(runner.step(None).outputs, vec!["check"]);
+    }
+
+    #[test]
+    fn fork_join_diamond_runs_branches_as_one_group() {
+        let (graph, _) = create_diamond_graph();
+        let ir = ActivityCompiler::compile(&graph).unwrap();
+        assert_eq!(ir.find_node("split").unwrap().kind, ActivityNodeKind::Fork);
+        assert_eq!(ir.predecessors("merged"), vec!["left", "right"]);
+
+        let mut runner = ActivityRunner::new(ir);
+        assert_eq!(runner.step(None).outputs, vec!["prepare"]);
+
+        let result = runner.step(None);
+        assert_eq!(result.outputs, vec!["left", "right"]);
+        assert_eq!(result.state, "left, right");
+        // The join is passed as soon as both branches finish
+        assert_eq!(runner.ready_actions(), vec!["finish"]);
+
+        let result = runner.step(None);
+        assert_eq!(result.outputs, vec!["finish"]);
+        assert!(result.completed);
+        assert_eq!(runner.finished().len(), 6);
+    }
+
+    #[test]
+    fn flow_relationships_order_actions() {
+        let mut graph = ModelGraph::new();
+        let def = graph.add_element(Element::new_with_kind(ElementKind::ActionDefinition).with_name("Pump"));
+        let fill = add(&mut graph, ElementKind::ActionUsage, "fill", &def);
+        let drain = add(&mut graph, ElementKind::ActionUsage, "drain", &def);
+        graph.add_relationship(Relationship::new(RelationshipKind::Flow, drain, fill));
+
+        let mut runner = ActivityRunner::from_graph(&graph).unwrap();
+        assert_eq!(runner.step(None).outputs, vec!["drain"]);
+        assert_eq!(runner.step(None).outputs, vec!["fill"]);
+    }
+
+    #[test]
+    fn succession_cycle_is_a_compile_error() {
+        let mut graph = create_linear_graph();
+        let def = graph
+            .elements_by_kind(&ElementKind::ActionDefinition)
+            .next()
+            .unwrap()
+            .id
+            .clone();
+        succession(&mut graph, &def, "run", "check");
+
+        let diags = ActivityCompiler::compile(&graph).unwrap_err();
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].message.contains("cycle"));
+        assert_eq!(diags[0].notes, vec!["cycle: check -> warmUp -> run -> check".to_string()]);
+    }
+
+    #[test]
+    fn unknown_endpoints_and_ambiguous_models() {
+        let (mut graph, def) = create_diamond_graph();
+        succession(&mut graph, &def, "finish", "polish");
+        let diags = ActivityCompiler::compile(&graph).unwrap_err();
+        assert!(diags[0].message.contains("unknown action 'polish'"));
+
+        let mut graph = create_linear_graph();
+        let other = graph.add_element(Element::new_with_kind(ElementKind::ActionDefinition).with_name("Shutdown"));
+        add(&mut graph, ElementKind::ActionUsage, "stop", &other);
+        let diags = ActivityCompiler::compile(&graph).unwrap_err();
+        assert_eq!(diags[0].notes, vec!["available activities: Shutdown, Startup".to_string()]);
+
+        let ir = ActivityCompiler::compile_by_name(&graph, "Shutdown").unwrap();
+        assert_eq!(ir.nodes, vec![ActivityNodeIR::action("stop")]);
+        assert!(ActivityCompiler::compile_by_name(&graph, "Missing").is_err());
+    }
+}
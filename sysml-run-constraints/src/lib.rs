@@ -4,11 +4,14 @@
 //!
 //! This crate provides:
 //! - Extraction of constraints from ModelGraph
-//! - A simple constraint evaluator (stub)
-
-use std::collections::HashMap;
-use sysml_core::{ElementKind, ModelGraph, Value};
-use sysml_run::ConstraintIR;
+//! - [`ConstraintCompiler`], which compiles ConstraintUsage and
+//!   RequirementUsage expressions to ConstraintIR
+//! - [`ConstraintEvaluator`], which checks constraints against attribute values
+
+use std::collections::{BTreeMap, HashMap};
+use sysml_core::{Element, ElementKind, ModelGraph, Value};
+use sysml_run::expr::{Expr, Scalar};
+use sysml_run::{CompileToIR, ConstraintIR};
 use sysml_span::Diagnostic;
 
 /// A compiled set of constraints.
@@ -113,20 +116,18 @@ impl EvaluationContext {
 
 /// Evaluate a single constraint.
 ///
-/// This is a stub implementation that only handles simple comparisons.
+/// A constraint is satisfied unless it definitely fails, so constraints
+/// that are inconclusive (see [`ConstraintEvaluator`]) count as satisfied.
 pub fn evaluate(
     constraint: &ConstraintIR,
     context: &EvaluationContext,
 ) -> EvaluationResult {
-    let expr = &constraint.expr;
-
-    // Stub: very simple expression evaluation
-    let satisfied = evaluate_simple_expr(expr, context);
+    let check = check(constraint, &context.variables);
 
     EvaluationResult {
-        constraint: constraint.clone(),
-        satisfied,
-        diagnostics: Vec::new(),
+        constraint: check.constraint,
+        satisfied: check.outcome != ConstraintOutcome::Fail,
+        diagnostics: check.diagnostics,
     }
 }
 
@@ -152,66 +153,259 @@ pub fn failed_constraints(results: &[EvaluationResult]) -> Vec<&EvaluationResult
     results.iter().filter(|r| !r.satisfied).collect()
 }
 
-// Simple expression evaluator (stub)
-fn evaluate_simple_expr(expr: &str, context: &EvaluationContext) -> bool {
-    // Handle simple comparisons like "x < 10" or "y == 5"
-    let expr = expr.trim();
-
-    // Try "var < num"
-    if let Some((var, rest)) = expr.split_once('<') {
-        let var = var.trim();
-        let num: f64 = rest.trim().parse().unwrap_or(0.0);
-        if let Some(val) = context.get(var).and_then(|v| v.as_float()) {
-            return val < num;
-        }
+/// Compiles constraint expressions of ConstraintUsage elements (including
+/// RequirementUsage and the other constraint kinds) into ConstraintIR.
+///
+/// The expression is read from the element's `expression` or `body`
+/// property, or else from the first owned expression element that has one.
+/// The pest parser sets `expression` to the result expression of a
+/// constraint body as written.
+/// Each ConstraintIR is described by its element's name, and elements without
+/// an expression are skipped. Compilation fails with one error per
+/// expression that does not parse.
+pub struct ConstraintCompiler;
+
+impl ConstraintCompiler {
+    /// Get the expression text of a constraint element, if it has one.
+    pub fn expression_of<'a>(graph: &'a ModelGraph, element: &'a Element) -> Option<&'a str> {
+        text_prop(element, &["expression", "body"]).or_else(|| {
+            graph
+                .children_of(&element.id)
+                .filter(|child| is_kind_or_subtype(child, ElementKind::Expression) && !is_constraint(child))
+                .find_map(|child| text_prop(child, &["expression", "body"]))
+        })
     }
+}
 
-    // Try "var > num"
-    if let Some((var, rest)) = expr.split_once('>') {
-        if !rest.starts_with('=') {
-            let var = var.trim();
-            let num: f64 = rest.trim().parse().unwrap_or(0.0);
-            if let Some(val) = context.get(var).and_then(|v| v.as_float()) {
-                return val > num;
+impl CompileToIR<Vec<ConstraintIR>> for ConstraintCompiler {
+    fn compile(graph: &ModelGraph) -> Result<Vec<ConstraintIR>, Vec<Diagnostic>> {
+        let mut constraints = Vec::new();
+        let mut diagnostics = Vec::new();
+
+        for element in graph.elements.values().filter(|e| is_constraint(e)) {
+            let text = match Self::expression_of(graph, element) {
+                Some(text) => text,
+                None => continue,
+            };
+            if let Err(err) = Expr::parse(text) {
+                let mut diagnostic = Diagnostic::error(format!(
+                    "constraint '{}' has an invalid expression: {}",
+                    element.name.as_deref().unwrap_or("<unnamed>"),
+                    err
+                ))
+                .with_note(format!("expression: {}", text));
+                if let Some(span) = element.spans.first() {
+                    diagnostic = diagnostic.with_span(span.clone());
+                }
+                diagnostics.push(diagnostic);
+                continue;
+            }
+            let mut constraint = ConstraintIR::new(text);
+            if let Some(name) = &element.name {
+                constraint = constraint.with_description(name.clone());
             }
+            constraints.push(constraint);
         }
-    }
 
-    // Try "var == num"
-    if let Some((var, rest)) = expr.split_once("==") {
-        let var = var.trim();
-        let num: f64 = rest.trim().parse().unwrap_or(0.0);
-        if let Some(val) = context.get(var).and_then(|v| v.as_float()) {
-            return (val - num).abs() < f64::EPSILON;
+        if diagnostics.is_empty() {
+            Ok(constraints)
+        } else {
+            Err(diagnostics)
         }
     }
+}
 
-    // Try "var <= num"
-    if let Some((var, rest)) = expr.split_once("<=") {
-        let var = var.trim();
-        let num: f64 = rest.trim().parse().unwrap_or(0.0);
-        if let Some(val) = context.get(var).and_then(|v| v.as_float()) {
-            return val <= num;
-        }
+fn is_constraint(element: &Element) -> bool {
+    is_kind_or_subtype(element, ElementKind::ConstraintUsage)
+}
+
+fn is_kind_or_subtype(element: &Element, kind: ElementKind) -> bool {
+    element.kind == kind || element.kind.is_subtype_of(kind)
+}
+
+fn text_prop<'a>(element: &'a Element, keys: &[&str]) -> Option<&'a str> {
+    keys.iter().find_map(|key| element.get_str(key))
+}
+
+/// The outcome of checking one constraint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintOutcome {
+    /// The constraint holds.
+    Pass,
+    /// The constraint is violated.
+    Fail,
+    /// The constraint could not be decided, because attributes it reads have
+    /// no value or the expression is invalid.
+    Inconclusive,
+}
+
+/// The result of checking one constraint with a [`ConstraintEvaluator`].
+#[derive(Debug, Clone)]
+pub struct ConstraintCheck {
+    /// The constraint that was checked.
+    pub constraint: ConstraintIR,
+    /// Whether the constraint passed, failed, or was inconclusive.
+    pub outcome: ConstraintOutcome,
+    /// The values of the attributes the expression reads, by name.
+    pub operands: BTreeMap<String, Value>,
+    /// Attributes the expression reads that have no value.
+    pub unknown: Vec<String>,
+    /// A diagnostic for every outcome other than [`ConstraintOutcome::Pass`].
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ConstraintCheck {
+    /// Check if the constraint passed.
+    pub fn passed(&self) -> bool {
+        self.outcome == ConstraintOutcome::Pass
+    }
+
+    /// Check if the constraint failed.
+    pub fn failed(&self) -> bool {
+        self.outcome == ConstraintOutcome::Fail
+    }
+}
+
+/// Evaluates constraint expressions against attribute values.
+///
+/// Expressions compare numbers and booleans with `< <= > >= == !=`, combine
+/// comparisons with `&&`, `||` and `!` (or `and`, `or`, `not`), and may use
+/// `+ - * /` and parentheses. Integer and float attributes are numbers.
+///
+/// An attribute without a value makes the constraint
+/// [`Inconclusive`](ConstraintOutcome::Inconclusive) rather than failed,
+/// unless the rest of the expression decides it anyway (`x > 0 || true`).
+/// Syntax errors, type errors and attributes that are neither numbers nor
+/// booleans are also inconclusive, with an error diagnostic.
+///
+/// # Example
+///
+/// ```
+/// use sysml_run::ConstraintIR;
+/// use sysml_run_constraints::{ConstraintEvaluator, ConstraintOutcome};
+///
+/// let mut evaluator = ConstraintEvaluator::default();
+/// evaluator.set("mass", 1100.0);
+///
+/// let check = evaluator.evaluate(&ConstraintIR::new("mass <= 1200"));
+/// assert_eq!(check.outcome, ConstraintOutcome::Pass);
+///
+/// let check = evaluator.evaluate(&ConstraintIR::new("speed > 0 && speed < 300"));
+/// assert_eq!(check.outcome, ConstraintOutcome::Inconclusive);
+/// assert_eq!(check.unknown, vec!["speed"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConstraintEvaluator {
+    attributes: HashMap<String, Value>,
+}
+
+impl ConstraintEvaluator {
+    /// Create an evaluator for a map of attribute name to value.
+    pub fn new(attributes: HashMap<String, Value>) -> Self {
+        ConstraintEvaluator { attributes }
+    }
+
+    /// Set an attribute value.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<Value>) {
+        self.attributes.insert(name.into(), value.into());
+    }
+
+    /// Get an attribute value.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.attributes.get(name)
     }
 
-    // Try "var >= num"
-    if let Some((var, rest)) = expr.split_once(">=") {
-        let var = var.trim();
-        let num: f64 = rest.trim().parse().unwrap_or(0.0);
-        if let Some(val) = context.get(var).and_then(|v| v.as_float()) {
-            return val >= num;
+    /// Check a single constraint.
+    pub fn evaluate(&self, constraint: &ConstraintIR) -> ConstraintCheck {
+        check(constraint, &self.attributes)
+    }
+
+    /// Check each constraint, in order.
+    pub fn evaluate_all(&self, constraints: &[ConstraintIR]) -> Vec<ConstraintCheck> {
+        constraints.iter().map(|c| self.evaluate(c)).collect()
+    }
+}
+
+fn check(constraint: &ConstraintIR, attributes: &HashMap<String, Value>) -> ConstraintCheck {
+    let label = constraint.description.as_deref().filter(|d| !d.is_empty()).unwrap_or(&constraint.expr);
+    let mut check = ConstraintCheck {
+        constraint: constraint.clone(),
+        outcome: ConstraintOutcome::Inconclusive,
+        operands: BTreeMap::new(),
+        unknown: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    let not_evaluated = |message: String| {
+        Diagnostic::error(format!("constraint '{}' could not be evaluated: {}", label, message))
+    };
+
+    let expr = match Expr::parse(&constraint.expr) {
+        Ok(expr) => expr,
+        Err(err) => {
+            check.diagnostics.push(not_evaluated(err.to_string()));
+            return check;
         }
+    };
+
+    let mut scalars = HashMap::new();
+    for name in expr.variables() {
+        let value = match attributes.get(name) {
+            Some(value) => value,
+            None => {
+                check.unknown.push(name.to_string());
+                continue;
+            }
+        };
+        check.operands.insert(name.to_string(), value.clone());
+        let scalar = match value {
+            Value::Bool(b) => Scalar::Bool(*b),
+            Value::Int(_) | Value::Float(_) => Scalar::Number(value.as_float().unwrap_or_default()),
+            _ => {
+                check.diagnostics.push(not_evaluated(format!(
+                    "'{}' is a {}, not a number or boolean",
+                    name,
+                    value.type_name()
+                )));
+                return check;
+            }
+        };
+        scalars.insert(name, scalar);
     }
 
-    // Unknown expression - assume true (stub behavior)
-    true
+    let operands = || {
+        let values: Vec<String> = check.operands.iter().map(|(name, value)| format!("{} = {}", name, value)).collect();
+        format!("evaluated with {}", values.join(", "))
+    };
+    let (outcome, diagnostic) = match expr.evaluate(&|name| scalars.get(name).copied()) {
+        Ok(Some(Scalar::Bool(true))) => (ConstraintOutcome::Pass, None),
+        Ok(Some(Scalar::Bool(false))) => {
+            let mut diagnostic = Diagnostic::error(format!("constraint '{}' failed: {}", label, constraint.expr));
+            if !check.operands.is_empty() {
+                diagnostic = diagnostic.with_note(operands());
+            }
+            (ConstraintOutcome::Fail, Some(diagnostic))
+        }
+        Ok(Some(Scalar::Number(n))) => (
+            ConstraintOutcome::Inconclusive,
+            Some(not_evaluated(format!("type error: evaluates to {}, not a boolean", n))),
+        ),
+        Ok(None) => (
+            ConstraintOutcome::Inconclusive,
+            Some(
+                Diagnostic::warning(format!("constraint '{}' is inconclusive", label))
+                    .with_note(format!("no value for {}", check.unknown.join(", "))),
+            ),
+        ),
+        Err(err) => (ConstraintOutcome::Inconclusive, Some(not_evaluated(err.to_string()))),
+    };
+    check.outcome = outcome;
+    check.diagnostics.extend(diagnostic);
+    check
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sysml_core::Element;
 
     #[test]
     fn constraint_set_creation() {
@@ -308,4 +502,134 @@ mod tests {
         let failed = failed_constraints(&results);
         assert_eq!(failed.len(), 1);
     }
+
+    /// Constraints in each form the compiler reads: `MassLimit` with an
+    /// `expression` prop, as the parser sets it for a constraint body, and
+    /// `SpeedRange` with an owned Expression carrying a `body`.
+    fn create_constraint_graph() -> ModelGraph {
+        let mut graph = ModelGraph::new();
+        graph.add_element(
+            Element::new_with_kind(ElementKind::ConstraintUsage)
+                .with_name("MassLimit")
+                .with_prop("expression", "mass <= 1200 [kg]"),
+        );
+        let requirement =
+            graph.add_element(Element::new_with_kind(ElementKind::RequirementUsage).with_name("SpeedRange"));
+        graph.add_element(
+            Element::new_with_kind(ElementKind::Expression)
+                .with_prop("body", "speed > 0 && speed < 300")
+                .with_owner(requirement),
+        );
+        graph.add_element(Element::new_with_kind(ElementKind::ConstraintUsage).with_name("Empty"));
+        graph.add_element(
+            Element::new_with_kind(ElementKind::AttributeUsage)
+                .with_name("mass")
+                .with_prop("expression", "1100"),
+        );
+        graph
+    }
+
+    fn compile(graph: &ModelGraph) -> Vec<ConstraintIR> {
+        ConstraintCompiler::compile(graph).expect("constraints should compile")
+    }
+
+    #[test]
+    fn compiler_collects_constraint_and_requirement_expressions() {
+        let mut constraints = compile(&create_constraint_graph());
+        constraints.sort_by(|a, b| a.description.cmp(&b.description));
+
+        assert_eq!(constraints.len(), 2);
+        assert_eq!(constraints[0].expr, "mass <= 1200 [kg]");
+        assert_eq!(constraints[0].description.as_deref(), Some("MassLimit"));
+        assert_eq!(constraints[1].expr, "speed > 0 && speed < 300");
+        assert_eq!(constraints[1].description.as_deref(), Some("SpeedRange"));
+    }
+
+    #[test]
+    fn compiler_reads_constraints_from_parsed_text() {
+        use sysml_text::{Parser, SysmlFile};
+
+        let source = "package P {\n  part def Car {\n    assert constraint speedOk { speed > 0 && speed < 300 }\n  }\n  \
+                      requirement def MassReq { require constraint { mass <= 1200 } }\n}";
+        let result = sysml_text_pest::PestParser::new().parse(&[SysmlFile::new("car.sysml", source)]);
+        assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+
+        let mut constraints = compile(&result.graph);
+        constraints.sort_by(|a, b| a.expr.cmp(&b.expr));
+        assert_eq!(constraints.len(), 2);
+        assert_eq!(constraints[0].expr, "mass <= 1200");
+        assert_eq!(constraints[1].expr, "speed > 0 && speed < 300");
+        assert_eq!(constraints[1].description.as_deref(), Some("speedOk"));
+
+        let mut evaluator = ConstraintEvaluator::default();
+        evaluator.set("mass", 1100i64);
+        evaluator.set("speed", 320.0);
+        let checks = evaluator.evaluate_all(&constraints);
+        assert!(checks[0].passed());
+        assert!(checks[1].failed());
+    }
+
+    #[test]
+    fn compiler_reports_invalid_expressions() {
+        let mut graph = create_constraint_graph();
+        graph.add_element(
+            Element::new_with_kind(ElementKind::ConstraintUsage)
+                .with_name("Broken")
+                .with_prop("expression", "mass <"),
+        );
+
+        let diagnostics = ConstraintCompiler::compile(&graph).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("'Broken'"));
+    }
+
+    #[test]
+    fn evaluator_passes_and_fails_attribute_sets() {
+        let constraints = compile(&create_constraint_graph());
+
+        let mut passing = ConstraintEvaluator::default();
+        passing.set("mass", 1100i64);
+        passing.set("speed", 120.0);
+        let checks = passing.evaluate_all(&constraints);
+        assert!(checks.iter().all(|c| c.passed() && c.diagnostics.is_empty()));
+
+        let mut failing = ConstraintEvaluator::default();
+        failing.set("mass", 1300i64);
+        failing.set("speed", 120.0);
+        let mass = failing.evaluate(&ConstraintIR::new("mass <= 1200 [kg]").with_description("MassLimit"));
+        assert!(mass.failed());
+        assert_eq!(mass.operands.get("mass"), Some(&Value::Int(1300)));
+        assert_eq!(mass.diagnostics[0].notes, vec!["evaluated with mass = 1300".to_string()]);
+
+        failing.set("speed", 300.0);
+        let speed = failing.evaluate(&ConstraintIR::new("speed > 0 && speed < 300"));
+        assert_eq!(speed.outcome, ConstraintOutcome::Fail);
+        assert_eq!(speed.operands.len(), 1);
+    }
+
+    #[test]
+    fn unknown_attributes_are_inconclusive() {
+        let mut evaluator = ConstraintEvaluator::default();
+        evaluator.set("speed", 120.0);
+
+        let check = evaluator.evaluate(&ConstraintIR::new("mass <= 1200 && speed < 300"));
+        assert_eq!(check.outcome, ConstraintOutcome::Inconclusive);
+        assert_eq!(check.unknown, vec!["mass".to_string()]);
+        assert_eq!(check.operands.len(), 1);
+        assert!(!check.failed());
+
+        // A known failing part still decides the result
+        evaluator.set("speed", 400.0);
+        let check = evaluator.evaluate(&ConstraintIR::new("mass <= 1200 && speed < 300"));
+        assert_eq!(check.outcome, ConstraintOutcome::Fail);
+
+        let check = evaluator.evaluate(&ConstraintIR::new("speed"));
+        assert_eq!(check.outcome, ConstraintOutcome::Inconclusive);
+        assert!(!check.diagnostics.is_empty());
+
+        evaluator.set("mode", "cruise");
+        let check = evaluator.evaluate(&ConstraintIR::new("mode == 1"));
+        assert_eq!(check.outcome, ConstraintOutcome::Inconclusive);
+        assert!(check.diagnostics[0].message.contains("not a number or boolean"));
+    }
 }
@@ -1,25 +1,34 @@
 //! Parser for action expressions in state machine actions.
 //!
 //! Parses action strings like:
-//! - `t += 10` -> AssignmentIR { var: "t", op: Add, value: 10.0 }
-//! - `send('eventName')` -> adds "eventName" to sends list
+//! - `t += 10.5` -> AssignmentIR { var: "t", op: Add, value: Literal(10.5) }
+//! - `mode = other` -> AssignmentIR { var: "mode", op: Set, value: Variable("other") }
+//! - `t = u + 5` -> AssignmentIR { var: "t", op: Set, value: Binary(u + 5) }
+//! - `send powerLost` or `send('powerLost')` -> adds "powerLost" to sends list
 //! - `doSomething()` -> ActionIR::Simple("doSomething()")
 
-use sysml_run::{ActionIR, AssignmentIR, AssignmentOp};
+use sysml_run::expr::Expr;
+use sysml_run::{ActionIR, AssignmentIR, AssignmentOp, BinaryOp, ValueRef};
 
 /// Parse an action string into an ActionIR.
 ///
 /// Supports:
 /// - Simple actions: any string that doesn't match structured patterns
-/// - Assignments: `var = value`, `var += value`, `var -= value`
-/// - Send events: `send('event')` or `send("event")`
+/// - Assignments: `var = value`, `var += value`, `var -= value`, `var *= value`
+///   and `var /= value`, where the value is a (possibly negative or decimal)
+///   number, another variable, or arithmetic over both with `+ - * /` and
+///   parentheses (`x = x * 2`, `t = (u + 5) / 2`)
+/// - Send events: `send event`, `send('event')` or `send("event")`
 /// - Multiple statements separated by `;`
 ///
+/// If any statement cannot be parsed, the whole action is kept as an
+/// `ActionIR::Simple` with the original text, so nothing is silently dropped.
+///
 /// # Examples
 ///
 /// ```
 /// use sysml_run_statemachine::parse_action;
-/// use sysml_run::{ActionIR, AssignmentOp};
+/// use sysml_run::{ActionIR, AssignmentOp, ValueRef};
 ///
 /// // Simple action
 /// let action = parse_action("doSomething()");
@@ -31,7 +40,7 @@ use sysml_run::{ActionIR, AssignmentIR, AssignmentOp};
 ///     assert_eq!(assignments.len(), 1);
 ///     assert_eq!(assignments[0].variable, "t");
 ///     assert_eq!(assignments[0].operator, AssignmentOp::Add);
-///     assert_eq!(assignments[0].value, 10.0);
+///     assert_eq!(assignments[0].value, ValueRef::Literal(10.0));
 /// }
 ///
 /// // Send event
@@ -40,6 +49,13 @@ use sysml_run::{ActionIR, AssignmentIR, AssignmentOp};
 ///     assert_eq!(sends.len(), 1);
 ///     assert_eq!(sends[0], "gridFail");
 /// }
+///
+/// // Mixed statements
+/// let action = parse_action("t += 10.5; send powerLost; mode = -1");
+/// if let ActionIR::Structured { assignments, sends } = action {
+///     assert_eq!(assignments.len(), 2);
+///     assert_eq!(sends, vec!["powerLost".to_string()]);
+/// }
 /// ```
 pub fn parse_action(input: &str) -> ActionIR {
     let trimmed = input.trim();
@@ -48,13 +64,10 @@ pub fn parse_action(input: &str) -> ActionIR {
         return ActionIR::Simple(String::new());
     }
 
-    // Try to parse as structured action
     let mut assignments = Vec::new();
     let mut sends = Vec::new();
-    let mut has_structured = false;
 
-    // Split by semicolons for multiple statements
-    for statement in trimmed.split(';') {
+    for statement in split_statements(trimmed) {
         let stmt = statement.trim();
         if stmt.is_empty() {
             continue;
@@ -62,55 +75,79 @@ pub fn parse_action(input: &str) -> ActionIR {
 
         if let Some(assign) = try_parse_assignment(stmt) {
             assignments.push(assign);
-            has_structured = true;
         } else if let Some(event) = try_parse_send(stmt) {
             sends.push(event);
-            has_structured = true;
+        } else {
+            // Keep the original text rather than dropping the statement
+            return ActionIR::Simple(trimmed.to_string());
         }
     }
 
-    if has_structured {
-        ActionIR::Structured { assignments, sends }
-    } else {
+    if assignments.is_empty() && sends.is_empty() {
         ActionIR::Simple(trimmed.to_string())
+    } else {
+        ActionIR::Structured { assignments, sends }
+    }
+}
+
+/// Split an action into statements on `;`, ignoring semicolons inside quotes.
+fn split_statements(input: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+
+    for (i, c) in input.char_indices() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, ';') => {
+                statements.push(&input[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
     }
+    statements.push(&input[start..]);
+    statements
 }
 
 /// Try to parse an assignment statement.
 ///
 /// Formats:
 /// - `var = value`
-/// - `var += value`
-/// - `var -= value`
+/// - `var += value`, `var -= value`, `var *= value`, `var /= value`
 fn try_parse_assignment(input: &str) -> Option<AssignmentIR> {
     let trimmed = input.trim();
 
-    // Try compound operators first (+=, -=)
-    if let Some((var, value)) = try_split_operator(trimmed, "+=") {
-        let val = parse_number(value)?;
-        return Some(AssignmentIR::new(var.trim(), AssignmentOp::Add, val));
-    }
-
-    if let Some((var, value)) = try_split_operator(trimmed, "-=") {
-        let val = parse_number(value)?;
-        return Some(AssignmentIR::new(var.trim(), AssignmentOp::Subtract, val));
+    // Try compound operators first
+    for (op_str, op) in [
+        ("+=", AssignmentOp::Add),
+        ("-=", AssignmentOp::Subtract),
+        ("*=", AssignmentOp::Multiply),
+        ("/=", AssignmentOp::Divide),
+    ] {
+        if let Some((var, value)) = try_split_operator(trimmed, op_str) {
+            let var = var.trim();
+            if !is_valid_identifier(var) {
+                return None;
+            }
+            return Some(AssignmentIR::new(var, op, parse_value(value)?));
+        }
     }
 
     // Simple assignment (but not == comparison)
     if let Some(pos) = trimmed.find('=') {
-        // Make sure it's not == or part of +=/-=
         if pos > 0 {
             let before = &trimmed[..pos];
             let after = &trimmed[pos + 1..];
 
-            // Skip if it's == or if there's another = immediately after
-            if !after.starts_with('=') && !before.ends_with('+') && !before.ends_with('-') {
+            // Skip if it's == (relational operators fail the identifier check below)
+            if !after.starts_with('=') {
                 let var = before.trim();
-                let val = parse_number(after.trim())?;
 
                 // Validate variable name (simple alphanumeric + underscore)
                 if is_valid_identifier(var) {
-                    return Some(AssignmentIR::new(var, AssignmentOp::Set, val));
+                    return Some(AssignmentIR::new(var, AssignmentOp::Set, parse_value(after)?));
                 }
             }
         }
@@ -132,27 +169,26 @@ fn try_split_operator<'a>(input: &'a str, op: &str) -> Option<(&'a str, &'a str)
 /// Try to parse a send statement.
 ///
 /// Formats:
+/// - `send eventName`
 /// - `send('eventName')`
 /// - `send("eventName")`
+/// - `send(eventName)`
 fn try_parse_send(input: &str) -> Option<String> {
-    let trimmed = input.trim();
-
-    // Check for send( prefix
-    if !trimmed.starts_with("send(") {
-        return None;
-    }
+    let rest = input.trim().strip_prefix("send")?;
 
-    // Find closing paren
-    if !trimmed.ends_with(')') {
+    let content = if let Some(inner) = rest.trim_start().strip_prefix('(') {
+        inner.strip_suffix(')')?.trim()
+    } else if rest.starts_with(char::is_whitespace) {
+        rest.trim()
+    } else {
+        // e.g. `sendAll()`
         return None;
-    }
-
-    // Extract content between send( and )
-    let content = &trimmed[5..trimmed.len() - 1].trim();
+    };
 
     // Extract quoted string
-    if (content.starts_with('\'') && content.ends_with('\''))
-        || (content.starts_with('"') && content.ends_with('"'))
+    if content.len() >= 2
+        && ((content.starts_with('\'') && content.ends_with('\''))
+            || (content.starts_with('"') && content.ends_with('"')))
     {
         let event = &content[1..content.len() - 1];
         return Some(event.to_string());
@@ -166,9 +202,37 @@ fn try_parse_send(input: &str) -> Option<String> {
     None
 }
 
-/// Parse a string as a number.
-fn parse_number(input: &str) -> Option<f64> {
-    input.trim().parse().ok()
+/// Parse the right-hand side of an assignment: numbers and variable names
+/// combined with `+ - * /` and parentheses, read with the expression parser of
+/// [`sysml_run::expr`].
+///
+/// A sign is only accepted directly before a number (`-1`, `- 2.5`, `+3`).
+/// Boolean values, comparisons and logical operators are not numeric values,
+/// so they are rejected.
+fn parse_value(input: &str) -> Option<ValueRef> {
+    to_value(&Expr::parse(input).ok()?)
+}
+
+fn to_value(expr: &Expr) -> Option<ValueRef> {
+    match expr {
+        Expr::Number(value) => Some(ValueRef::Literal(*value)),
+        Expr::Variable(name) => Some(ValueRef::Variable(name.clone())),
+        Expr::Unary("-", operand) => match operand.as_ref() {
+            Expr::Number(value) => Some(ValueRef::Literal(-value)),
+            _ => None,
+        },
+        Expr::Binary(op, left, right) => {
+            let op = match *op {
+                "+" => BinaryOp::Add,
+                "-" => BinaryOp::Subtract,
+                "*" => BinaryOp::Multiply,
+                "/" => BinaryOp::Divide,
+                _ => return None,
+            };
+            Some(ValueRef::binary(op, to_value(left)?, to_value(right)?))
+        }
+        Expr::Bool(_) | Expr::Unary(..) => None,
+    }
 }
 
 /// Check if a string is a valid identifier.
@@ -212,7 +276,7 @@ mod tests {
             assert_eq!(assignments.len(), 1);
             assert_eq!(assignments[0].variable, "x");
             assert_eq!(assignments[0].operator, AssignmentOp::Set);
-            assert_eq!(assignments[0].value, 5.0);
+            assert_eq!(assignments[0].value, ValueRef::Literal(5.0));
             assert!(sends.is_empty());
         } else {
             panic!("Expected structured action");
@@ -226,7 +290,7 @@ mod tests {
             assert_eq!(assignments.len(), 1);
             assert_eq!(assignments[0].variable, "t");
             assert_eq!(assignments[0].operator, AssignmentOp::Add);
-            assert_eq!(assignments[0].value, 10.0);
+            assert_eq!(assignments[0].value, ValueRef::Literal(10.0));
         } else {
             panic!("Expected structured action");
         }
@@ -239,7 +303,7 @@ mod tests {
             assert_eq!(assignments.len(), 1);
             assert_eq!(assignments[0].variable, "count");
             assert_eq!(assignments[0].operator, AssignmentOp::Subtract);
-            assert_eq!(assignments[0].value, 1.0);
+            assert_eq!(assignments[0].value, ValueRef::Literal(1.0));
         } else {
             panic!("Expected structured action");
         }
@@ -309,7 +373,7 @@ mod tests {
     fn parse_float_values() {
         let action = parse_action("delay = 15.5");
         if let ActionIR::Structured { assignments, .. } = action {
-            assert_eq!(assignments[0].value, 15.5);
+            assert_eq!(assignments[0].value, ValueRef::Literal(15.5));
         } else {
             panic!("Expected structured action");
         }
@@ -319,7 +383,7 @@ mod tests {
     fn parse_negative_values() {
         let action = parse_action("offset = -10");
         if let ActionIR::Structured { assignments, .. } = action {
-            assert_eq!(assignments[0].value, -10.0);
+            assert_eq!(assignments[0].value, ValueRef::Literal(-10.0));
         } else {
             panic!("Expected structured action");
         }
@@ -359,4 +423,121 @@ mod tests {
         let action = parse_action("123 = 5");
         assert!(action.is_simple());
     }
+
+    fn structured(input: &str) -> (Vec<AssignmentIR>, Vec<String>) {
+        match parse_action(input) {
+            ActionIR::Structured { assignments, sends } => (assignments, sends),
+            ActionIR::Simple(s) => panic!("Expected structured action, got simple '{}'", s),
+        }
+    }
+
+    #[test]
+    fn parse_mixed_statements() {
+        let (assignments, sends) = structured("t += 10.5; send powerLost; mode = -1");
+        assert_eq!(
+            assignments,
+            vec![AssignmentIR::add("t", 10.5), AssignmentIR::set("mode", -1.0)]
+        );
+        assert_eq!(sends, vec!["powerLost"]);
+    }
+
+    #[test]
+    fn parse_send_keyword_forms() {
+        for input in ["send powerLost", "send  powerLost ", "send('powerLost')", "send (powerLost)", "send 'powerLost'"] {
+            let (assignments, sends) = structured(input);
+            assert!(assignments.is_empty());
+            assert_eq!(sends, vec!["powerLost"], "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn parse_variable_assignments() {
+        let (assignments, _) = structured("a = b; total += delta; rest -= _used");
+        assert_eq!(
+            assignments,
+            vec![
+                AssignmentIR::set("a", ValueRef::variable("b")),
+                AssignmentIR::add("total", ValueRef::variable("delta")),
+                AssignmentIR::subtract("rest", ValueRef::variable("_used")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_signed_and_decimal_literals() {
+        let cases = [
+            ("x = -1", -1.0),
+            ("x = - 2.5", -2.5),
+            ("x = +3", 3.0),
+            ("x = .5", 0.5),
+            ("x = 1e3", 1000.0),
+            ("x -= -0.25", -0.25),
+        ];
+        for (input, expected) in cases {
+            let (assignments, _) = structured(input);
+            assert_eq!(assignments[0].value, ValueRef::Literal(expected), "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn parse_multiply_and_divide_assignments() {
+        let (assignments, _) = structured("x *= 2; rate /= 3");
+        assert_eq!(assignments, vec![AssignmentIR::multiply("x", 2.0), AssignmentIR::divide("rate", 3.0)]);
+    }
+
+    #[test]
+    fn parse_arithmetic_values() {
+        let (assignments, _) = structured("x = x * 2; t = u + 5; y = (a - 1) / -2 + b * c");
+        assert_eq!(
+            assignments[0].value,
+            ValueRef::binary(BinaryOp::Multiply, ValueRef::variable("x"), 2.0)
+        );
+        assert_eq!(assignments[1].value, ValueRef::binary(BinaryOp::Add, ValueRef::variable("u"), 5.0));
+        assert_eq!(assignments[2].value.to_string(), "(a - 1) / -2 + b * c");
+
+        let lookup = |name: &str| match name {
+            "a" => Some(5.0),
+            "b" => Some(2.0),
+            "c" => Some(3.0),
+            _ => None,
+        };
+        assert_eq!(assignments[2].value.resolve(lookup), 4.0);
+    }
+
+    #[test]
+    fn parse_ignores_empty_statements_and_quoted_semicolons() {
+        let (assignments, sends) = structured("; x = 1;; send('a;b');");
+        assert_eq!(assignments.len(), 1);
+        assert_eq!(sends, vec!["a;b"]);
+    }
+
+    #[test]
+    fn malformed_statements_degrade_to_simple() {
+        for input in [
+            "t += 10; doSomething()",
+            "x = ",
+            "x = 1 +",
+            "x = (a + 1",
+            "x = a * * 2",
+            "x = 2 a",
+            "x += foo()",
+            "foo() += 1",
+            "x = -y",
+            "x = 1e999",
+            "x = true",
+            "x = a < b",
+            "x = !a",
+            "send",
+            "send()",
+            "send('unterminated",
+            "send a b",
+            "sendAll()",
+            "x <= 5",
+            "x != 5",
+            ";;",
+        ] {
+            let action = parse_action(input);
+            assert_eq!(action.as_simple(), Some(input.trim()), "input: {}", input);
+        }
+    }
 }
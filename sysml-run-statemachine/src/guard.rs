@@ -0,0 +1,208 @@
+//! Evaluator for transition guard expressions.
+//!
+//! Evaluates guard strings like:
+//! - `carsWaiting > 3`
+//! - `speed >= 10.5 && !braking`
+//! - `mode == 2 || override`
+//!
+//! Guards are parsed with the expression parser of [`sysml_run::expr`], so
+//! they share its operators and precedence. Operands are numeric literals,
+//! `true`/`false`, and context variables. `and`, `or`, and `not` are accepted
+//! as aliases for `&&`, `||`, and `!`.
+
+use std::fmt;
+
+use sysml_run::expr::{Expr, ExprError, Scalar};
+
+/// A value held in a runner context and used by guard expressions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContextValue {
+    /// Numeric value.
+    Number(f64),
+    /// Boolean value.
+    Bool(bool),
+}
+
+impl ContextValue {
+    /// Get the value as a number, if it is one.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            ContextValue::Number(n) => Some(*n),
+            ContextValue::Bool(_) => None,
+        }
+    }
+
+    /// Get the value as a boolean, if it is one.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ContextValue::Bool(b) => Some(*b),
+            ContextValue::Number(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ContextValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContextValue::Number(n) => write!(f, "{}", n),
+            ContextValue::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl From<f64> for ContextValue {
+    fn from(n: f64) -> Self {
+        ContextValue::Number(n)
+    }
+}
+
+impl From<i64> for ContextValue {
+    fn from(n: i64) -> Self {
+        ContextValue::Number(n as f64)
+    }
+}
+
+impl From<i32> for ContextValue {
+    fn from(n: i32) -> Self {
+        ContextValue::Number(n as f64)
+    }
+}
+
+impl From<bool> for ContextValue {
+    fn from(b: bool) -> Self {
+        ContextValue::Bool(b)
+    }
+}
+
+/// An error raised while evaluating a guard.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GuardError {
+    /// The guard references a variable that is not in the context.
+    UnknownVariable(String),
+    /// The guard could not be parsed.
+    Syntax(String),
+    /// An operator was applied to operands of the wrong type.
+    Type(String),
+}
+
+impl fmt::Display for GuardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GuardError::UnknownVariable(name) => write!(f, "unknown variable '{}'", name),
+            GuardError::Syntax(msg) => write!(f, "syntax error: {}", msg),
+            GuardError::Type(msg) => write!(f, "type error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for GuardError {}
+
+/// Evaluate a guard expression against a variable lookup.
+///
+/// # Examples
+///
+/// ```
+/// use sysml_run_statemachine::guard::{evaluate_guard, ContextValue};
+///
+/// let lookup = |name: &str| match name {
+///     "carsWaiting" => Some(ContextValue::Number(5.0)),
+///     _ => None,
+/// };
+/// assert_eq!(evaluate_guard("carsWaiting > 3", lookup), Ok(true));
+/// assert!(evaluate_guard("x > 3", lookup).is_err());
+/// ```
+pub fn evaluate_guard<F>(expr: &str, lookup: F) -> Result<bool, GuardError>
+where
+    F: Fn(&str) -> Option<ContextValue>,
+{
+    let expr = Expr::parse(expr)?;
+    if let Some(name) = expr.variables().into_iter().find(|name| lookup(name).is_none()) {
+        return Err(GuardError::UnknownVariable(name.to_string()));
+    }
+
+    let value = expr
+        .evaluate(&|name: &str| {
+            lookup(name).map(|value| match value {
+                ContextValue::Number(n) => Scalar::Number(n),
+                ContextValue::Bool(b) => Scalar::Bool(b),
+            })
+        })?;
+    match value {
+        Some(Scalar::Bool(b)) => Ok(b),
+        Some(Scalar::Number(n)) => Err(GuardError::Type(format!("guard evaluates to {}, not a boolean", n))),
+        // Every variable has a value, so the result is known
+        None => Err(GuardError::Type("guard has no value".to_string())),
+    }
+}
+
+impl From<ExprError> for GuardError {
+    fn from(err: ExprError) -> Self {
+        match err {
+            ExprError::Syntax(msg) => GuardError::Syntax(msg),
+            ExprError::Type(msg) => GuardError::Type(msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn eval(expr: &str, vars: &[(&str, ContextValue)]) -> Result<bool, GuardError> {
+        let map: HashMap<String, ContextValue> =
+            vars.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        evaluate_guard(expr, |name| map.get(name).copied())
+    }
+
+    #[test]
+    fn numeric_comparisons() {
+        let vars = [("x", ContextValue::Number(5.0))];
+        assert_eq!(eval("x > 3", &vars), Ok(true));
+        assert_eq!(eval("x < 3", &vars), Ok(false));
+        assert_eq!(eval("x >= 5", &vars), Ok(true));
+        assert_eq!(eval("x <= 4.5", &vars), Ok(false));
+        assert_eq!(eval("x == 5", &vars), Ok(true));
+        assert_eq!(eval("x != 5", &vars), Ok(false));
+        assert_eq!(eval("x > -1", &vars), Ok(true));
+    }
+
+    #[test]
+    fn boolean_literals_and_variables() {
+        let vars = [("ready", ContextValue::Bool(true))];
+        assert_eq!(eval("true", &vars), Ok(true));
+        assert_eq!(eval("false", &vars), Ok(false));
+        assert_eq!(eval("ready", &vars), Ok(true));
+        assert_eq!(eval("!ready", &vars), Ok(false));
+        assert_eq!(eval("ready == false", &vars), Ok(false));
+    }
+
+    #[test]
+    fn logical_operators_and_precedence() {
+        let vars = [("a", ContextValue::Number(1.0)), ("b", ContextValue::Number(2.0))];
+        assert_eq!(eval("a < b && b < 3", &vars), Ok(true));
+        assert_eq!(eval("a > b || b == 2", &vars), Ok(true));
+        // && binds tighter than ||
+        assert_eq!(eval("true || false && false", &vars), Ok(true));
+        assert_eq!(eval("(true || false) && false", &vars), Ok(false));
+        assert_eq!(eval("a == 1 and not (b == 1)", &vars), Ok(true));
+        assert_eq!(eval("a + b * 2 == 5", &vars), Ok(true));
+        assert_eq!(eval("b / 2 >= 1e0", &vars), Ok(true));
+    }
+
+    #[test]
+    fn unknown_variable_is_an_error() {
+        let err = eval("x > 3", &[]).unwrap_err();
+        assert_eq!(err, GuardError::UnknownVariable("x".to_string()));
+        assert_eq!(err.to_string(), "unknown variable 'x'");
+    }
+
+    #[test]
+    fn syntax_and_type_errors() {
+        assert!(matches!(eval("x >", &[("x", 1.0.into())]), Err(GuardError::Syntax(_))));
+        assert!(matches!(eval("(true", &[]), Err(GuardError::Syntax(_))));
+        assert!(matches!(eval("1 2", &[]), Err(GuardError::Syntax(_))));
+        assert!(matches!(eval("true > 1", &[]), Err(GuardError::Type(_))));
+        assert!(matches!(eval("3", &[]), Err(GuardError::Type(_))));
+    }
+}
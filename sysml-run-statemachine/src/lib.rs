@@ -6,17 +6,33 @@
 //! - Compilation from ModelGraph state machines to StateMachineIR
 //! - A simple runner that executes the IR
 //! - Parallel state machine runner for composite state machines with concurrent regions
+//! - Export of the IR to W3C SCXML, and import back
+//! - Export of the IR to a Mermaid state diagram (re-exported from sysml-vis)
+//! - Scripted scenarios that drive a runner and report each step
 
 pub mod action_parser;
+pub mod guard;
 pub mod parallel;
+pub mod scenario;
+pub mod scxml;
 
 pub use action_parser::parse_action;
-pub use parallel::ParallelStateMachineRunner;
-
-use sysml_core::{Element, ElementId, ElementKind, ModelGraph, RelationshipKind};
-use sysml_run::{ActionIR, CompileToIR, RegionIR, Runner, StateIR, StateMachineIR, StepResult, TransitionIR};
+pub use guard::{evaluate_guard, ContextValue, GuardError};
+pub use parallel::{dwell_variable, ParallelRunnerSnapshot, ParallelStateMachineRunner, DEFAULT_MAX_INTERNAL_ITERATIONS};
+pub use scenario::{Comparison, Scenario, ScenarioReport, ScenarioRunner, ScenarioStep, StepReport};
+pub use scxml::{from_scxml, to_scxml};
+pub use sysml_vis::to_mermaid_statechart as to_mermaid;
+
+use sysml_core::{
+    Element, ElementId, ElementKind, ModelGraph, Relationship, RelationshipKind, StateSubactionKind,
+    TransitionFeatureKind, Value,
+};
+use sysml_run::{
+    ActionIR, ActionKind, CompileToIR, RegionIR, Runner, StateIR, StateMachineIR, StepResult,
+    TraceEvent, TraceLog, TransitionIR, DEFAULT_TRACE_CAPACITY,
+};
 use sysml_span::Diagnostic;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
 /// Compiler for state machines.
 pub struct StateMachineCompiler;
@@ -44,11 +60,7 @@ impl StateMachineCompiler {
         // Find initial state (first state or one marked as initial)
         let initial_state = states
             .iter()
-            .find(|s| {
-                s.get_prop("initial")
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false)
-            })
+            .find(|s| s.get_bool("initial").unwrap_or(false))
             .or_else(|| states.first())
             .unwrap();
 
@@ -62,33 +74,23 @@ impl StateMachineCompiler {
 
         // Add states
         for state in &states {
-            let state_ir = Self::compile_state(state);
+            let state_ir = Self::compile_state(graph, state);
             ir = ir.with_state(state_ir);
         }
 
-        // Find transitions
-        for rel in graph.relationships_by_kind(&RelationshipKind::Transition) {
-            let source = graph.get_element(&rel.source);
-            let target = graph.get_element(&rel.target);
-
-            if let (Some(src), Some(tgt)) = (source, target) {
-                let from = src.name.clone().unwrap_or_else(|| src.id.to_string());
-                let to = tgt.name.clone().unwrap_or_else(|| tgt.id.to_string());
-
-                let mut transition = TransitionIR::new(from, to);
-
-                if let Some(event) = rel.props.get("event").and_then(|v| v.as_str()) {
-                    transition = transition.with_event(event);
-                }
-
-                if let Some(guard) = rel.props.get("guard").and_then(|v| v.as_str()) {
-                    transition = transition.with_guard(guard);
-                }
-
-                if let Some(action) = rel.props.get("action").and_then(|v| v.as_str()) {
-                    transition = transition.with_action(parse_action(action));
-                }
-
+        // Find transitions leaving this machine's states, at any depth
+        let mut state_ids: HashSet<&ElementId> = HashSet::new();
+        let mut pending = states.clone();
+        while let Some(state) = pending.pop() {
+            if state_ids.insert(&state.id) {
+                pending.extend(graph.children_of(&state.id).filter(|e| matches!(e.kind, ElementKind::StateUsage)));
+            }
+        }
+        for rel in graph
+            .relationships_by_kind(&RelationshipKind::Transition)
+            .filter(|rel| state_ids.contains(&rel.source))
+        {
+            if let Some(transition) = Self::compile_transition_relationship(graph, rel) {
                 ir = ir.with_transition(transition);
             }
         }
@@ -124,11 +126,7 @@ impl StateMachineCompiler {
             // Find initial state for this region
             let initial_state = states
                 .iter()
-                .find(|s| {
-                    s.get_prop("initial")
-                        .and_then(|v| v.as_bool())
-                        .unwrap_or(false)
-                })
+                .find(|s| s.get_bool("initial").unwrap_or(false))
                 .or_else(|| states.first())
                 .unwrap();
 
@@ -137,11 +135,11 @@ impl StateMachineCompiler {
                 .clone()
                 .unwrap_or_else(|| "initial".to_string());
 
-            let mut region = RegionIR::new(&region_name, initial_name);
+            let mut region = RegionIR::new(&region_name, initial_name).with_history(has_history(region_elem));
 
             // Add states to region
             for state in &states {
-                let state_ir = Self::compile_state(state);
+                let state_ir = Self::compile_state(graph, state);
                 region = region.with_state(state_ir);
             }
 
@@ -156,55 +154,109 @@ impl StateMachineCompiler {
                     continue;
                 }
 
-                let source = graph.get_element(&rel.source);
-                let target = graph.get_element(&rel.target);
+                if let Some(transition) = Self::compile_transition_relationship(graph, rel) {
+                    region = region.with_transition(transition);
+                }
+            }
 
-                if let (Some(src), Some(tgt)) = (source, target) {
-                    let from = src.name.clone().unwrap_or_else(|| src.id.to_string());
-                    let to = tgt.name.clone().unwrap_or_else(|| tgt.id.to_string());
+            ir = ir.with_region(region);
+        }
 
-                    let mut transition = TransitionIR::new(from, to);
+        Ok(ir)
+    }
 
-                    if let Some(event) = rel.props.get("event").and_then(|v| v.as_str()) {
-                        transition = transition.with_event(event);
-                    }
+    /// Compile a Transition relationship into TransitionIR.
+    ///
+    /// Returns None if either endpoint is missing from the graph.
+    fn compile_transition_relationship(graph: &ModelGraph, rel: &Relationship) -> Option<TransitionIR> {
+        let src = graph.get_element(&rel.source)?;
+        let tgt = graph.get_element(&rel.target)?;
 
-                    if let Some(guard) = rel.props.get("guard").and_then(|v| v.as_str()) {
-                        transition = transition.with_guard(guard);
-                    }
+        let from = src.name.clone().unwrap_or_else(|| src.id.to_string());
+        let to = tgt.name.clone().unwrap_or_else(|| tgt.id.to_string());
 
-                    if let Some(action) = rel.props.get("action").and_then(|v| v.as_str()) {
-                        transition = transition.with_action(parse_action(action));
-                    }
+        let mut transition = TransitionIR::new(from, to).with_priority(read_priority(&rel.props));
 
-                    region = region.with_transition(transition);
-                }
-            }
+        if let Some(event) = rel.props.get("event").and_then(|v| v.as_str()) {
+            transition = with_trigger(transition, event);
+        }
 
-            ir = ir.with_region(region);
+        if let Some(guard) = rel.props.get("guard").and_then(|v| v.as_str()) {
+            transition = transition.with_guard(guard);
         }
 
-        Ok(ir)
+        if let Some(action) = rel.props.get("action").and_then(|v| v.as_str()) {
+            transition = transition.with_action(parse_action(action));
+        }
+
+        if let Some(after) = read_after(&rel.props) {
+            transition = transition.with_after(after);
+        }
+
+        Some(transition)
     }
 
     /// Compile a single state element into StateIR.
-    fn compile_state(state: &Element) -> StateIR {
+    ///
+    /// Entry, exit, and do actions are read from `entry`/`exit`/`do` string props,
+    /// and from owned subaction children whose `kind` prop holds a
+    /// `StateSubactionKind`. Subaction children take precedence over props.
+    /// Owned StateUsage children become substates; the one marked `initial`
+    /// (or else the first) is the initial substate. An `isHistory`/`history`
+    /// prop gives the substates shallow history.
+    fn compile_state(graph: &ModelGraph, state: &Element) -> StateIR {
+        Self::compile_state_nested(graph, state, &mut HashSet::new())
+    }
+
+    fn compile_state_nested<'a>(graph: &'a ModelGraph, state: &'a Element, visiting: &mut HashSet<&'a ElementId>) -> StateIR {
         let name = state.name.clone().unwrap_or_else(|| state.id.to_string());
-        let is_final = state
-            .get_prop("final")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
+        let is_final = state.get_bool("final").unwrap_or(false);
 
         let mut state_ir = StateIR::new(&name);
 
-        if let Some(entry) = state.get_prop("entry").and_then(|v| v.as_str()) {
+        if let Some(entry) = state.get_str("entry") {
             state_ir = state_ir.with_entry_action(parse_action(entry));
         }
 
-        if let Some(exit) = state.get_prop("exit").and_then(|v| v.as_str()) {
+        if let Some(exit) = state.get_str("exit") {
             state_ir = state_ir.with_exit_action(parse_action(exit));
         }
 
+        if let Some(do_action) = state.get_str("do") {
+            state_ir = state_ir.with_do_action(parse_action(do_action));
+        }
+
+        for child in graph.children_of(&state.id) {
+            let kind = match child.get_str("kind").and_then(StateSubactionKind::from_str) {
+                Some(kind) => kind,
+                None => continue,
+            };
+
+            if let Some(body) = Self::subaction_body(graph, child) {
+                let action = parse_action(body);
+                state_ir = match kind {
+                    StateSubactionKind::Entry => state_ir.with_entry_action(action),
+                    StateSubactionKind::Do_ => state_ir.with_do_action(action),
+                    StateSubactionKind::Exit => state_ir.with_exit_action(action),
+                };
+            }
+        }
+
+        // Guard against ownership cycles
+        visiting.insert(&state.id);
+        for child in graph.children_of(&state.id) {
+            if !matches!(child.kind, ElementKind::StateUsage) || visiting.contains(&child.id) {
+                continue;
+            }
+            let substate = Self::compile_state_nested(graph, child, visiting);
+            if child.get_bool("initial").unwrap_or(false) && state_ir.initial_substate.is_none() {
+                state_ir = state_ir.with_initial_substate(&substate.name);
+            }
+            state_ir = state_ir.with_substate(substate);
+        }
+        visiting.remove(&state.id);
+        state_ir = state_ir.with_history(has_history(state));
+
         if is_final {
             state_ir = state_ir.final_state();
         }
@@ -212,6 +264,28 @@ impl StateMachineCompiler {
         state_ir
     }
 
+    /// Find the action body of a state subaction element.
+    ///
+    /// The body is taken from the subaction itself, from the ActionUsage it
+    /// references through an `action` prop, or from its first owned ActionUsage.
+    fn subaction_body<'a>(graph: &'a ModelGraph, subaction: &'a Element) -> Option<&'a str> {
+        let body_of = |e: &'a Element| e.get_str("body");
+
+        body_of(subaction)
+            .or_else(|| {
+                subaction
+                    .get_ref("action")
+                    .and_then(|id| graph.get_element(id))
+                    .and_then(body_of)
+            })
+            .or_else(|| {
+                graph
+                    .children_of(&subaction.id)
+                    .filter(|c| matches!(c.kind, ElementKind::ActionUsage))
+                    .find_map(body_of)
+            })
+    }
+
     /// Check if a state machine should be compiled as parallel.
     /// Returns the region elements if parallel, None otherwise.
     fn detect_parallel_regions<'a>(
@@ -219,10 +293,7 @@ impl StateMachineCompiler {
         sm: &'a Element,
     ) -> Option<Vec<&'a Element>> {
         // Check isParallel property
-        let is_parallel = sm
-            .get_prop("isParallel")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
+        let is_parallel = sm.get_bool("isParallel").unwrap_or(false);
 
         if is_parallel {
             // Top-level StateUsage children are regions
@@ -434,7 +505,7 @@ impl StateMachineCompiler {
         state_def_id: &ElementId,
         region_name: &str,
     ) -> Option<RegionIR> {
-        let _state_def = graph.get_element(state_def_id)?;
+        let state_def = graph.get_element(state_def_id)?;
 
         // Find all states within this state definition
         let states: Vec<_> = graph
@@ -449,11 +520,7 @@ impl StateMachineCompiler {
         // Find initial state (first state or one marked initial)
         let initial_state = states
             .iter()
-            .find(|s| {
-                s.get_prop("initial")
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false)
-            })
+            .find(|s| s.get_bool("initial").unwrap_or(false))
             .or_else(|| states.first())
             .unwrap();
 
@@ -462,11 +529,11 @@ impl StateMachineCompiler {
             .clone()
             .unwrap_or_else(|| "initial".to_string());
 
-        let mut region = RegionIR::new(region_name, &initial_name);
+        let mut region = RegionIR::new(region_name, &initial_name).with_history(has_history(state_def));
 
         // Add states
         for state in &states {
-            let state_ir = Self::compile_state(state);
+            let state_ir = Self::compile_state(graph, state);
             region = region.with_state(state_ir);
         }
 
@@ -486,27 +553,7 @@ impl StateMachineCompiler {
         // Also check for transitions as relationships
         for rel in graph.relationships_by_kind(&RelationshipKind::Transition) {
             if state_ids.contains(&rel.source) {
-                let source = graph.get_element(&rel.source);
-                let target = graph.get_element(&rel.target);
-
-                if let (Some(src), Some(tgt)) = (source, target) {
-                    let from = src.name.clone().unwrap_or_else(|| src.id.to_string());
-                    let to = tgt.name.clone().unwrap_or_else(|| tgt.id.to_string());
-
-                    let mut transition = TransitionIR::new(from, to);
-
-                    if let Some(event) = rel.props.get("event").and_then(|v| v.as_str()) {
-                        transition = transition.with_event(event);
-                    }
-
-                    if let Some(guard) = rel.props.get("guard").and_then(|v| v.as_str()) {
-                        transition = transition.with_guard(guard);
-                    }
-
-                    if let Some(action) = rel.props.get("action").and_then(|v| v.as_str()) {
-                        transition = transition.with_action(parse_action(action));
-                    }
-
+                if let Some(transition) = Self::compile_transition_relationship(graph, rel) {
                     region = region.with_transition(transition);
                 }
             }
@@ -539,11 +586,11 @@ impl StateMachineCompiler {
 
         // Try unresolved properties
         let source_name = source_name.or_else(|| {
-            transition.props.get("unresolved_source").and_then(|v| v.as_str().map(String::from))
+            transition.get_str("unresolved_source").map(String::from)
         });
 
         let target_name = target_name.or_else(|| {
-            transition.props.get("unresolved_target").and_then(|v| v.as_str().map(String::from))
+            transition.get_str("unresolved_target").map(String::from)
         });
 
         let (from, to) = match (source_name, target_name) {
@@ -555,44 +602,163 @@ impl StateMachineCompiler {
             }
         };
 
-        let mut ir = TransitionIR::new(from, to);
+        let mut ir = TransitionIR::new(from, to).with_priority(read_priority(&transition.props));
 
         // Extract event from trigger
-        if let Some(trigger) = transition.props.get("trigger").and_then(|v| v.as_str()) {
-            ir = ir.with_event(trigger);
+        if let Some(trigger) = transition.get_str("trigger") {
+            ir = with_trigger(ir, trigger);
         }
 
         // Extract guard
-        if let Some(guard) = transition.props.get("guard").and_then(|v| v.as_str()) {
+        if let Some(guard) = transition.get_str("guard") {
             ir = ir.with_guard(guard);
         }
 
         // Extract action
-        if let Some(action) = transition.props.get("effect").and_then(|v| v.as_str()) {
+        if let Some(action) = transition.get_str("effect") {
             ir = ir.with_action(parse_action(action));
         }
 
+        // Owned trigger/guard/effect features, as emitted by the parser.
+        // Explicit props above take precedence.
+        for child in graph.children_of(&transition.id) {
+            match Self::transition_feature_kind(child) {
+                Some(TransitionFeatureKind::Trigger) if ir.event.is_none() && !ir.is_timed() => {
+                    if let Some(event) = Self::trigger_event(graph, child) {
+                        ir = with_trigger(ir, event);
+                    }
+                }
+                Some(TransitionFeatureKind::Guard) if ir.guard.is_none() => {
+                    if let Some(guard) = text_prop(child, &["expression", "body", "text"]) {
+                        ir = ir.with_guard(guard);
+                    }
+                }
+                Some(TransitionFeatureKind::Effect) if ir.action.is_none() => {
+                    if let Some(body) = Self::subaction_body(graph, child).or_else(|| text_prop(child, &["text"])) {
+                        ir = ir.with_action(parse_action(body));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(after) = read_after(&transition.props) {
+            ir = ir.with_after(after);
+        }
+
         Some(ir)
     }
-}
 
-impl CompileToIR<StateMachineIR> for StateMachineCompiler {
-    fn compile(graph: &ModelGraph) -> Result<StateMachineIR, Vec<Diagnostic>> {
-        let mut diagnostics = Vec::new();
+    /// Classify a child of a TransitionUsage as a trigger, guard, or effect.
+    ///
+    /// An explicit `kind` prop holding a `TransitionFeatureKind` wins; otherwise
+    /// accept actions are triggers, expressions (including constraints) are
+    /// guards, and other actions are effects.
+    fn transition_feature_kind(child: &Element) -> Option<TransitionFeatureKind> {
+        if let Some(kind) = child.get_str("kind").and_then(TransitionFeatureKind::from_str) {
+            return Some(kind);
+        }
+
+        let is_a = |kind: ElementKind| child.kind == kind || child.kind.is_subtype_of(kind);
+        if is_a(ElementKind::AcceptActionUsage) {
+            Some(TransitionFeatureKind::Trigger)
+        } else if is_a(ElementKind::Expression) || is_a(ElementKind::ConstraintUsage) {
+            Some(TransitionFeatureKind::Guard)
+        } else if is_a(ElementKind::ActionUsage) {
+            Some(TransitionFeatureKind::Effect)
+        } else {
+            None
+        }
+    }
+
+    /// Find the event name accepted by a trigger element.
+    ///
+    /// Checks the `payload`, `trigger`, and `event` props, then the first named
+    /// child (the payload parameter), then the trigger's own name.
+    fn trigger_event<'a>(graph: &'a ModelGraph, trigger: &'a Element) -> Option<&'a str> {
+        text_prop(trigger, &["payload", "trigger", "event"])
+            .or_else(|| {
+                let mut named: Vec<_> = graph
+                    .children_of(&trigger.id)
+                    .filter(|c| c.name.is_some())
+                    .collect();
+                named.sort_by(|a, b| a.id.cmp(&b.id));
+                named.first().and_then(|c| c.name.as_deref())
+            })
+            .or(trigger.name.as_deref())
+    }
 
-        // Find the first state machine element
-        let sm_element = graph
+    /// Compile the state definition with the given simple or qualified name.
+    ///
+    /// A qualified name (`Package::VehicleStates`) must match the definition's
+    /// qualified name exactly; a simple name matches the first definition (by id)
+    /// with that name. If nothing matches, the error lists the available state
+    /// machines.
+    pub fn compile_by_name(graph: &ModelGraph, name: &str) -> Result<StateMachineIR, Vec<Diagnostic>> {
+        let qualified = name.contains("::");
+        let found = graph
             .elements_by_kind(&ElementKind::StateDefinition)
-            .next();
+            .filter(|e| {
+                if qualified {
+                    graph
+                        .qualified_name_of(&e.id)
+                        .is_some_and(|qn| qn.to_string() == name)
+                } else {
+                    e.name.as_deref() == Some(name)
+                }
+            })
+            .min_by(|a, b| a.id.cmp(&b.id));
 
-        let sm = match sm_element {
-            Some(e) => e,
-            None => {
-                diagnostics.push(Diagnostic::error("No state machine found in model"));
-                return Err(diagnostics);
-            }
-        };
+        match found {
+            Some(sm) => Self::compile_definition(graph, sm),
+            None => Err(vec![Diagnostic::error(format!("State machine '{}' not found", name))
+                .with_note(Self::available_note(graph))]),
+        }
+    }
+
+    /// Compile the state definition with the given simple or qualified name.
+    ///
+    /// The entry point [`CompileToIR::compile`] points to when a model holds
+    /// several state machines; the same as [`Self::compile_by_name`].
+    pub fn compile_named(graph: &ModelGraph, name: &str) -> Result<StateMachineIR, Vec<Diagnostic>> {
+        Self::compile_by_name(graph, name)
+    }
+
+    /// List the state definitions that own at least one state, with their
+    /// qualified names (or simple names, or ids, when they have none).
+    ///
+    /// Results are in the order the definitions are stored in the graph, and
+    /// each name can be passed to [`Self::compile_by_name`].
+    pub fn list_state_machines(graph: &ModelGraph) -> Vec<(ElementId, String)> {
+        Self::state_machines(graph)
+            .map(|sm| (sm.id.clone(), Self::display_name(graph, sm)))
+            .collect()
+    }
+
+    /// A note naming the state machines in a model.
+    fn available_note(graph: &ModelGraph) -> String {
+        let available: Vec<String> = Self::list_state_machines(graph)
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect();
+        if available.is_empty() {
+            "no state machines in model".to_string()
+        } else {
+            format!("available state machines: {}", available.join(", "))
+        }
+    }
 
+    /// Compile every state definition that owns at least one state.
+    ///
+    /// Results are in the order the definitions are stored in the graph.
+    pub fn compile_all(graph: &ModelGraph) -> Vec<Result<StateMachineIR, Vec<Diagnostic>>> {
+        Self::state_machines(graph)
+            .map(|sm| Self::compile_definition(graph, sm))
+            .collect()
+    }
+
+    /// Compile a specific state definition element.
+    fn compile_definition(graph: &ModelGraph, sm: &Element) -> Result<StateMachineIR, Vec<Diagnostic>> {
         let sm_name = sm.name.clone().unwrap_or_else(|| "StateMachine".to_string());
 
         // Check if this should be compiled as a parallel state machine
@@ -602,111 +768,899 @@ impl CompileToIR<StateMachineIR> for StateMachineCompiler {
             Self::compile_simple(graph, sm, sm_name)
         }
     }
+
+    /// The state definition [`CompileToIR::compile`] compiles: the only one
+    /// that owns states, or else the first state definition.
+    fn select_machine(graph: &ModelGraph) -> Result<&Element, Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+
+        let machines: Vec<&Element> = Self::state_machines(graph).collect();
+        if machines.len() > 1 {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "Model has {} state machines; select one by name with StateMachineCompiler::compile_named",
+                    machines.len()
+                ))
+                .with_note(Self::available_note(graph)),
+            );
+            return Err(diagnostics);
+        }
+        let sm_element = machines
+            .first()
+            .copied()
+            .or_else(|| graph.elements_by_kind(&ElementKind::StateDefinition).next());
+
+        match sm_element {
+            Some(e) => Ok(e),
+            None => {
+                diagnostics.push(Diagnostic::error("No state machine found in model"));
+                Err(diagnostics)
+            }
+        }
+    }
+
+    /// State definitions that own at least one StateUsage.
+    fn state_machines(graph: &ModelGraph) -> impl Iterator<Item = &Element> {
+        graph
+            .elements_by_kind(&ElementKind::StateDefinition)
+            .filter(move |sm| {
+                graph
+                    .children_of(&sm.id)
+                    .any(|c| matches!(c.kind, ElementKind::StateUsage))
+            })
+    }
+
+    /// Qualified name of an element if it has one, otherwise its simple name or id.
+    fn display_name(graph: &ModelGraph, element: &Element) -> String {
+        graph
+            .qualified_name_of(&element.id)
+            .map(|qn| qn.to_string())
+            .or_else(|| element.name.clone())
+            .unwrap_or_else(|| element.id.to_string())
+    }
+
+    /// Compile a state machine and report non-fatal warnings alongside the IR.
+    ///
+    /// Warnings currently cover ambiguous transitions (see [`Self::conflict_warnings`])
+    /// and unreachable states (see [`Self::unreachable_warnings`]), which carry
+    /// the span of their `StateUsage`.
+    pub fn compile_with_warnings(
+        graph: &ModelGraph,
+    ) -> Result<(StateMachineIR, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let sm = Self::select_machine(graph)?;
+        let ir = Self::compile_definition(graph, sm)?;
+        let mut warnings = Self::conflict_warnings(&ir);
+        warnings.extend(unreachable_diagnostics(&ir, Some((graph, &sm.id))));
+        Ok((ir, warnings))
+    }
+
+    /// Find transitions that leave the same state on the same event with the same priority.
+    ///
+    /// Such transitions are only disambiguated by guards and declaration order,
+    /// which is rarely what the modeler intended.
+    pub fn conflict_warnings(ir: &StateMachineIR) -> Vec<Diagnostic> {
+        let mut warnings = transition_conflicts(&ir.transitions, None);
+        for region in &ir.regions {
+            warnings.extend(transition_conflicts(&region.transitions, Some(&region.name)));
+        }
+        warnings
+    }
+
+    /// Find states that no transition path from the initial state enters,
+    /// checking each region of a parallel machine on its own.
+    ///
+    /// These usually come from a typo in a transition's source or target name.
+    pub fn unreachable_warnings(ir: &StateMachineIR) -> Vec<Diagnostic> {
+        unreachable_diagnostics(ir, None)
+    }
 }
 
-/// Format an action for output.
-fn format_action(action: &ActionIR) -> String {
-    match action {
-        ActionIR::Simple(s) => s.clone(),
-        ActionIR::Structured { assignments, sends } => {
-            let mut parts = Vec::new();
-            for assign in assignments {
-                let op = match assign.operator {
-                    sysml_run::AssignmentOp::Set => "=",
-                    sysml_run::AssignmentOp::Add => "+=",
-                    sysml_run::AssignmentOp::Subtract => "-=",
-                };
-                parts.push(format!("{} {} {}", assign.variable, op, assign.value));
+/// Check whether a region or state element declares shallow history (`isHistory` or `history`).
+fn has_history(region: &Element) -> bool {
+    ["isHistory", "history"]
+        .iter()
+        .any(|key| region.get_bool(key).unwrap_or(false))
+}
+
+/// Get the first of the given props that holds a string.
+fn text_prop<'a>(element: &'a Element, keys: &[&str]) -> Option<&'a str> {
+    keys.iter().find_map(|key| element.get_str(key))
+}
+
+/// Read the `priority` property of a transition, defaulting to 0.
+fn read_priority(props: &BTreeMap<String, Value>) -> i32 {
+    props
+        .get("priority")
+        .and_then(|v| v.as_int().or_else(|| v.as_float().map(|f| f as i64)))
+        .map(|p| p.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+        .unwrap_or(0)
+}
+
+/// Read the delay of a timed transition from its `after` (or `delay`) property.
+///
+/// Numbers are taken as they are; strings may carry a unit (`"20 [ms]"`),
+/// which is ignored.
+fn read_after(props: &BTreeMap<String, Value>) -> Option<f64> {
+    let value = props.get("after").or_else(|| props.get("delay"))?;
+    value
+        .as_float()
+        .or_else(|| value.as_int().map(|i| i as f64))
+        .or_else(|| value.as_str().and_then(parse_duration))
+}
+
+/// Parse a duration such as `20`, `0.5` or `20 [ms]`, ignoring the unit.
+fn parse_duration(text: &str) -> Option<f64> {
+    let number = match text.find('[') {
+        Some(pos) => &text[..pos],
+        None => text,
+    };
+    number.trim().parse().ok().filter(|d: &f64| *d >= 0.0)
+}
+
+/// Set the trigger of a transition from an event string. A time trigger,
+/// `after(20)` or `after 20 [ms]`, makes the transition timed instead.
+fn with_trigger(transition: TransitionIR, event: &str) -> TransitionIR {
+    let delay = event
+        .trim()
+        .strip_prefix("after")
+        .map(|rest| rest.trim())
+        .map(|rest| rest.strip_prefix('(').and_then(|r| r.strip_suffix(')')).unwrap_or(rest))
+        .and_then(parse_duration);
+    match delay {
+        Some(delay) => transition.with_after(delay),
+        None => transition.with_event(event),
+    }
+}
+
+/// Build warnings for transitions sharing source state, event, and priority.
+fn transition_conflicts(transitions: &[TransitionIR], region: Option<&str>) -> Vec<Diagnostic> {
+    let mut groups: BTreeMap<(&str, Option<&str>, i32), Vec<&TransitionIR>> = BTreeMap::new();
+    for t in transitions {
+        // Timed transitions are chosen by delay, not by event
+        if t.is_timed() {
+            continue;
+        }
+        groups
+            .entry((t.from.as_str(), t.event.as_deref(), t.priority))
+            .or_default()
+            .push(t);
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, group)| group.len() > 1)
+        .map(|((from, event, priority), group)| {
+            let targets: Vec<&str> = group.iter().map(|t| t.to.as_str()).collect();
+            let location = match region {
+                Some(r) => format!("[{}] state '{}'", r, from),
+                None => format!("state '{}'", from),
+            };
+            let mut warning = Diagnostic::warning(format!(
+                "{} has {} transitions on event '{}' with priority {}",
+                location,
+                group.len(),
+                event.unwrap_or("<none>"),
+                priority
+            ))
+            .with_note(format!("targets: {}", targets.join(", ")));
+            // Guards may still tell the others apart, but never these
+            let guardless = group.iter().filter(|t| t.guard.is_none()).count();
+            if guardless > 1 {
+                warning = warning.with_note(format!(
+                    "{} of them have no guard, so only declaration order decides between them",
+                    guardless
+                ));
+            }
+            warning.with_note("set a distinct `priority` to make the choice explicit")
+        })
+        .collect()
+}
+
+/// Build warnings for unreachable states, with the span of the matching
+/// `StateUsage` when the graph and state definition the IR was compiled from
+/// are given.
+fn unreachable_diagnostics(ir: &StateMachineIR, source: Option<(&ModelGraph, &ElementId)>) -> Vec<Diagnostic> {
+    let mut scopes = vec![(None, ir.states.as_slice(), ir.initial.as_str(), ir.transitions.as_slice())];
+    for region in &ir.regions {
+        scopes.push((
+            Some(region.name.as_str()),
+            region.states.as_slice(),
+            region.initial.as_str(),
+            region.transitions.as_slice(),
+        ));
+    }
+
+    let mut warnings = Vec::new();
+    for (region, states, initial, transitions) in scopes {
+        for name in unreachable_states(states, initial, transitions) {
+            let location = match region {
+                Some(r) => format!("[{}] state '{}'", r, name),
+                None => format!("state '{}'", name),
+            };
+            let mut warning = Diagnostic::warning(format!(
+                "{} is unreachable from initial state '{}'",
+                location, initial
+            ))
+            .with_note("no transition path leads to it; check the source and target names of transitions");
+            if let Some(span) = source.and_then(|(graph, machine)| state_span(graph, machine, name)) {
+                warning = warning.with_span(span);
             }
-            for send in sends {
-                parts.push(format!("send('{}')", send));
+            warnings.push(warning);
+        }
+    }
+    warnings
+}
+
+/// Get the states of a scope (in declaration order, substates after their
+/// parent) that cannot become active starting from `initial`.
+///
+/// Entering a state also activates its ancestors, and an active state can
+/// take the transitions leaving it. Only a state that is itself entered (as
+/// the initial state or a transition target) enters its initial substate;
+/// one activated as the ancestor of an entered substate does not.
+fn unreachable_states<'a>(states: &'a [StateIR], initial: &'a str, transitions: &'a [TransitionIR]) -> Vec<&'a str> {
+    fn collect<'a>(
+        states: &'a [StateIR],
+        parent: Option<&'a str>,
+        order: &mut Vec<&'a StateIR>,
+        parents: &mut HashMap<&'a str, Option<&'a str>>,
+    ) {
+        for state in states {
+            order.push(state);
+            parents.insert(&state.name, parent);
+            collect(&state.substates, Some(&state.name), order, parents);
+        }
+    }
+
+    let mut order = Vec::new();
+    let mut parents = HashMap::new();
+    collect(states, None, &mut order, &mut parents);
+    let by_name: HashMap<&str, &StateIR> = order.iter().map(|s| (s.name.as_str(), *s)).collect();
+
+    let mut reached: HashSet<&str> = HashSet::new();
+    let mut entered: HashSet<&str> = HashSet::new();
+    // Each state is queued with whether it is entered itself
+    let mut queue: VecDeque<(&str, bool)> = VecDeque::from([(initial, true)]);
+    while let Some((name, enter)) = queue.pop_front() {
+        let state = match by_name.get(name) {
+            Some(state) => *state,
+            None => continue,
+        };
+        if enter && entered.insert(name) {
+            if let Some(substate) = state.initial() {
+                queue.push_back((&substate.name, true));
             }
-            parts.join("; ")
         }
+        if !reached.insert(name) {
+            continue;
+        }
+        if let Some(Some(parent)) = parents.get(name) {
+            queue.push_back((parent, false));
+        }
+        queue.extend(
+            transitions
+                .iter()
+                .filter(|t| t.from == name)
+                .map(|t| (t.to.as_str(), true)),
+        );
+    }
+
+    order
+        .into_iter()
+        .map(|s| s.name.as_str())
+        .filter(|name| !reached.contains(name))
+        .collect()
+}
+
+/// The span of the `StateUsage` with a name within a state definition,
+/// picking the earliest in source order when several share it.
+fn state_span(graph: &ModelGraph, machine: &ElementId, name: &str) -> Option<sysml_span::Span> {
+    graph
+        .elements
+        .values()
+        .filter(|e| e.kind == ElementKind::StateUsage && e.name.as_deref() == Some(name))
+        .filter(|e| graph.is_descendant_of(&e.id, machine))
+        .filter_map(|e| e.spans.first())
+        .min_by(|a, b| (&a.file, a.start).cmp(&(&b.file, b.start)))
+        .cloned()
+}
+
+impl CompileToIR<StateMachineIR> for StateMachineCompiler {
+    /// Compiles the model's only state machine.
+    ///
+    /// Fails when the model has several state definitions that own states, since
+    /// picking one would be arbitrary; use [`StateMachineCompiler::compile_named`]
+    /// to choose. Without any state definition that owns states, the first
+    /// state definition is compiled.
+    fn compile(graph: &ModelGraph) -> Result<StateMachineIR, Vec<Diagnostic>> {
+        let sm = Self::select_machine(graph)?;
+        Self::compile_definition(graph, sm)
+    }
+}
+
+/// The events sent by an action.
+fn sent_events(action: &ActionIR) -> &[String] {
+    match action {
+        ActionIR::Simple(_) => &[],
+        ActionIR::Structured { sends, .. } => sends,
     }
 }
 
+/// Context variable holding the total time advanced by
+/// [`StateMachineRunner::advance_time`].
+pub const TIME_CONTEXT_VARIABLE: &str = "t";
+
 /// A simple state machine runner.
+///
+/// Transition guards are evaluated against the runner's context variables
+/// (see [`StateMachineRunner::set_context`]). Timed (`after`) transitions fire
+/// from [`StateMachineRunner::advance_time`].
+///
+/// Composite states are supported: the runner keeps a stack of active states
+/// from the outermost down to the current (innermost) one. Entering a
+/// composite state also enters its initial substate, running entry actions
+/// top-down; leaving it runs exit actions bottom-up. A composite state with
+/// `history` resumes at the substate that was active when it was last left.
+/// Transitions of inner states take precedence over those of their
+/// ancestors. The machine completes when a top-level final state is entered.
+///
+/// Actions update the context through their assignments (`x += 1`), so
+/// guards can depend on values computed by earlier actions. Events sent by
+/// actions (`send('x')`) are queued and processed in FIFO order before `step`
+/// returns (run-to-completion).
+/// Each transition fired by such an internal event adds an
+/// `event 'x': from -> to` output, followed by the outputs of its actions. At
+/// most `max_internal_iterations` internal events are processed per step;
+/// beyond that the queue is dropped and the result carries a warning.
 pub struct StateMachineRunner {
     ir: StateMachineIR,
-    current_state: String,
+    /// Active states, outermost first; the last one is the current state.
+    active: Vec<ActiveState>,
+    completed: bool,
+    /// Context variables used by guard expressions.
+    context: HashMap<String, ContextValue>,
+    /// Events sent by actions, waiting to be processed.
+    event_queue: VecDeque<String>,
+    /// Maximum number of internal events processed per step.
+    max_internal_iterations: usize,
+    /// Last active substate of each exited history state (state name -> substate name).
+    history: HashMap<String, String>,
+    /// Number of calls to `step` and `advance_time` so far.
+    steps: u64,
+    /// Structured execution trace, empty unless tracing is enabled.
+    trace: TraceLog,
+}
+
+/// An active state and the time spent in it.
+#[derive(Debug, Clone, PartialEq)]
+struct ActiveState {
+    name: String,
+    time: f64,
+}
+
+impl ActiveState {
+    fn new(name: impl Into<String>) -> Self {
+        ActiveState { name: name.into(), time: 0.0 }
+    }
+}
+
+/// The execution state of a [`StateMachineRunner`], captured by
+/// [`StateMachineRunner::snapshot`].
+///
+/// Holds the active states (with the time spent in each), the completion
+/// flag, the context variables and the remembered history substates. The IR,
+/// configuration and trace are not part of the snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunnerSnapshot {
+    active: Vec<ActiveState>,
     completed: bool,
+    context: HashMap<String, ContextValue>,
+    history: HashMap<String, String>,
+}
+
+impl RunnerSnapshot {
+    /// Get the current (innermost active) state.
+    pub fn current_state(&self) -> &str {
+        self.active.last().map(|s| s.name.as_str()).unwrap_or("")
+    }
+
+    /// Check whether the runner had completed.
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+
+    /// Get the context variables.
+    pub fn context(&self) -> &HashMap<String, ContextValue> {
+        &self.context
+    }
 }
 
 impl StateMachineRunner {
     /// Create a new runner from IR.
     pub fn new(ir: StateMachineIR) -> Self {
-        let initial = ir.initial.clone();
+        let history = HashMap::new();
+        let active = Self::entry_path(&ir, &history, &ir.initial).into_iter().map(ActiveState::new).collect();
         StateMachineRunner {
             ir,
-            current_state: initial,
+            active,
             completed: false,
+            context: HashMap::new(),
+            event_queue: VecDeque::new(),
+            max_internal_iterations: DEFAULT_MAX_INTERNAL_ITERATIONS,
+            history,
+            steps: 0,
+            trace: TraceLog::new(0),
         }
     }
 
-    /// Create a runner by compiling a model graph.
-    pub fn from_graph(graph: &ModelGraph) -> Result<Self, Vec<Diagnostic>> {
-        let ir = StateMachineCompiler::compile(graph)?;
-        Ok(Self::new(ir))
+    /// Set the maximum number of internal events processed per step.
+    pub fn with_max_internal_iterations(mut self, max: usize) -> Self {
+        self.max_internal_iterations = max;
+        self
     }
-}
 
-impl Runner for StateMachineRunner {
-    fn reset(&mut self) {
-        self.current_state = self.ir.initial.clone();
-        self.completed = false;
+    /// Get the maximum number of internal events processed per step.
+    pub fn max_internal_iterations(&self) -> usize {
+        self.max_internal_iterations
     }
 
-    fn step(&mut self, event: Option<&str>) -> StepResult {
-        if self.completed {
-            return StepResult::new(&self.current_state).completed();
-        }
+    /// Record an execution trace of up to [`DEFAULT_TRACE_CAPACITY`] events.
+    pub fn with_tracing(self) -> Self {
+        self.with_trace_capacity(DEFAULT_TRACE_CAPACITY)
+    }
 
-        let mut outputs = Vec::new();
+    /// Set the maximum number of trace events kept (0 disables tracing).
+    pub fn with_trace_capacity(mut self, capacity: usize) -> Self {
+        self.trace.set_capacity(capacity);
+        self
+    }
 
-        // Find a matching transition
-        let transitions = self.ir.transitions_from(&self.current_state);
-        let matching = transitions.iter().find(|t| t.matches(event));
+    /// Get the execution trace recorded by `step`, oldest first.
+    ///
+    /// Tracing is off by default; see [`with_tracing`](Self::with_tracing).
+    /// Each taken transition carries the number of the `step` or
+    /// `advance_time` call it happened in, counted from 1 since the runner
+    /// was created. The trace is kept across `reset`; use
+    /// [`clear_trace`](Self::clear_trace) to discard it.
+    pub fn trace(&self) -> &[TraceEvent] {
+        self.trace.events()
+    }
 
-        if let Some(transition) = matching {
-            // Execute exit action of current state
-            if let Some(state) = self.ir.find_state(&self.current_state) {
-                if let Some(exit) = &state.exit_action {
-                    outputs.push(format!("exit: {}", format_action(exit)));
-                }
-            }
+    /// Discard the recorded execution trace.
+    pub fn clear_trace(&mut self) {
+        self.trace.clear();
+    }
 
-            // Execute transition action
-            if let Some(action) = &transition.action {
-                outputs.push(format!("action: {}", format_action(action)));
-            }
+    /// Remove and return the recorded execution trace, oldest first.
+    ///
+    /// Useful for collecting the trace of one step at a time.
+    pub fn take_trace(&mut self) -> Vec<TraceEvent> {
+        self.trace.take()
+    }
 
-            // Move to new state
-            self.current_state = transition.to.clone();
+    /// Set a context variable.
+    pub fn set_context(&mut self, name: impl Into<String>, value: impl Into<ContextValue>) {
+        self.context.insert(name.into(), value.into());
+    }
 
-            // Execute entry action of new state
-            if let Some(state) = self.ir.find_state(&self.current_state) {
-                if let Some(entry) = &state.entry_action {
-                    outputs.push(format!("entry: {}", format_action(entry)));
-                }
+    /// Get a context variable value.
+    pub fn get_context(&self, name: &str) -> Option<ContextValue> {
+        self.context.get(name).copied()
+    }
 
-                if state.is_final {
-                    self.completed = true;
-                }
-            }
-        }
+    /// Create a runner by compiling a model graph.
+    pub fn from_graph(graph: &ModelGraph) -> Result<Self, Vec<Diagnostic>> {
+        let ir = StateMachineCompiler::compile(graph)?;
+        Ok(Self::new(ir))
+    }
 
-        let mut result = StepResult::new(&self.current_state).with_outputs(outputs);
+    /// Get the active states, from the outermost to the current one.
+    pub fn active_states(&self) -> Vec<&str> {
+        self.active.iter().map(|s| s.name.as_str()).collect()
+    }
 
-        if self.completed {
-            result = result.completed();
-        }
+    /// Get the time spent in the current state.
+    pub fn state_time(&self) -> f64 {
+        self.active.last().map(|s| s.time).unwrap_or(0.0)
+    }
 
-        result
+    /// Get the substate a composite state will resume at when re-entered, if remembered.
+    ///
+    /// Only states with `history` enabled remember their last active substate.
+    pub fn history_state(&self, state: &str) -> Option<&str> {
+        self.history.get(state).map(|s| s.as_str())
     }
 
-    fn current_state(&self) -> &str {
-        &self.current_state
+    /// Capture the runner's execution state, e.g. to explore several event
+    /// sequences from a common prefix.
+    pub fn snapshot(&self) -> RunnerSnapshot {
+        RunnerSnapshot {
+            active: self.active.clone(),
+            completed: self.completed,
+            context: self.context.clone(),
+            history: self.history.clone(),
+        }
+    }
+
+    /// Return to a state captured by [`snapshot`](Self::snapshot).
+    ///
+    /// The snapshot should come from a runner for the same IR. The trace is
+    /// left as it is.
+    pub fn restore(&mut self, snapshot: RunnerSnapshot) {
+        self.active = snapshot.active;
+        self.completed = snapshot.completed;
+        self.context = snapshot.context;
+        self.history = snapshot.history;
+        self.event_queue.clear();
+    }
+
+    /// Advance simulated time by `dt`, firing every `after` transition that
+    /// becomes due.
+    ///
+    /// Time accumulates in every active state and in the
+    /// [`TIME_CONTEXT_VARIABLE`] context variable, so guards can refer to it.
+    /// When several timed transitions are pending, the one with the smallest
+    /// remaining time fires first; its guard is evaluated at the moment it
+    /// becomes due, and a failing guard lets the next one fire instead. Time
+    /// left over after a transition keeps running in the new state, so one
+    /// call can fire a chain of transitions. At most
+    /// [`DEFAULT_MAX_INTERNAL_ITERATIONS`] transitions fire per call. Events
+    /// sent by the actions of a timed transition are processed right after it.
+    pub fn advance_time(&mut self, dt: f64) -> StepResult {
+        self.steps += 1;
+        let mut outputs = Vec::new();
+        let mut diagnostics = Vec::new();
+        let mut remaining = dt.max(0.0);
+        let mut fired = 0;
+
+        while !self.completed && fired < DEFAULT_MAX_INTERNAL_ITERATIONS {
+            // Pending transitions of every active state, innermost first
+            let mut pending: Vec<(f64, TransitionIR)> = Vec::new();
+            for state in self.active.iter().rev() {
+                for transition in self.ir.timed_transitions(&state.name) {
+                    let wait = (transition.after.unwrap_or(0.0) - state.time).max(0.0);
+                    pending.push((wait, transition.clone()));
+                }
+            }
+            // Stable, so inner states win ties
+            pending.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut waited = 0.0;
+            let mut due = None;
+            for (wait, transition) in pending {
+                if wait > remaining {
+                    break;
+                }
+                self.tick(wait - waited);
+                waited = wait;
+                if self.guard_holds(&transition, &mut outputs) {
+                    due = Some(transition);
+                    break;
+                }
+            }
+            remaining -= waited;
+
+            match due {
+                Some(transition) => {
+                    self.take_transition(&transition, &mut outputs);
+                    diagnostics.extend(self.run_to_completion(&mut outputs));
+                    fired += 1;
+                }
+                None => break,
+            }
+        }
+        if fired == DEFAULT_MAX_INTERNAL_ITERATIONS {
+            outputs.push(parallel::ITERATION_LIMIT_OUTPUT.to_string());
+        }
+        self.tick(remaining);
+
+        let mut result = StepResult::new(self.current_state()).with_outputs(outputs);
+        for diagnostic in diagnostics {
+            result = result.with_diagnostic(diagnostic);
+        }
+        if self.completed {
+            result = result.completed();
+        }
+        result
+    }
+
+    /// Let `dt` pass in the active states.
+    fn tick(&mut self, dt: f64) {
+        for state in &mut self.active {
+            state.time += dt;
+        }
+        let total = self
+            .context
+            .get(TIME_CONTEXT_VARIABLE)
+            .and_then(|v| v.as_number())
+            .unwrap_or(0.0);
+        self.context
+            .insert(TIME_CONTEXT_VARIABLE.to_string(), ContextValue::Number(total + dt));
+    }
+
+    /// The states entered to reach `target`: its ancestors, the state itself,
+    /// then the substates below it. Below a history state that is the
+    /// remembered substate in `history`, otherwise the initial one. Unknown
+    /// states are treated as top level.
+    fn entry_path(ir: &StateMachineIR, history: &HashMap<String, String>, target: &str) -> Vec<String> {
+        let path = match ir.state_path(target) {
+            Some(path) => path,
+            None => return vec![target.to_string()],
+        };
+        let mut names: Vec<String> = path.iter().map(|s| s.name.clone()).collect();
+        let mut state = path.last().copied();
+        while let Some(current) = state {
+            let remembered = history
+                .get(&current.name)
+                .filter(|_| current.history)
+                .and_then(|name| current.substates.iter().find(|s| &s.name == name));
+            state = remembered.or_else(|| current.initial());
+            if let Some(next) = state {
+                names.push(next.name.clone());
+            }
+        }
+        names
+    }
+
+    /// Evaluate a transition's guard; a guard that fails to evaluate blocks it.
+    fn guard_holds(&self, transition: &TransitionIR, outputs: &mut Vec<String>) -> bool {
+        match &transition.guard {
+            None => true,
+            Some(guard) => match evaluate_guard(guard, |name| self.context.get(name).copied()) {
+                Ok(enabled) => enabled,
+                Err(e) => {
+                    outputs.push(format!("guard error: {}", e));
+                    false
+                }
+            },
+        }
+    }
+
+    /// Exit states up to the common ancestor of source and target, run the
+    /// transition action, then enter the target and its initial (or, with
+    /// history, remembered) substates.
+    fn take_transition(&mut self, transition: &TransitionIR, outputs: &mut Vec<String>) {
+        let source = self
+            .active
+            .iter()
+            .rposition(|s| s.name == transition.from)
+            .unwrap_or(self.active.len().saturating_sub(1));
+        let target: Vec<String> = match self.ir.state_path(&transition.to) {
+            Some(path) => path.iter().map(|s| s.name.clone()).collect(),
+            None => vec![transition.to.clone()],
+        };
+        let common = self
+            .active
+            .iter()
+            .zip(&target)
+            .take_while(|(active, name)| &active.name == *name)
+            .count();
+        // The source is always exited, and so is a target that is already active
+        let keep = common.min(source).min(target.len() - 1);
+
+        // Remember the active substate of every exited history state
+        let exited = self.active.split_off(keep);
+        for pair in exited.windows(2) {
+            if self.ir.find_state(&pair[0].name).is_some_and(|s| s.history) {
+                self.history.insert(pair[0].name.clone(), pair[1].name.clone());
+            }
+        }
+        let entered: Vec<String> = Self::entry_path(&self.ir, &self.history, &transition.to)
+            .into_iter()
+            .skip(keep)
+            .collect();
+
+        // Exit actions innermost first, the transition action, then entry actions outermost first
+        let mut actions = Vec::new();
+        for state in exited.iter().rev() {
+            if let Some(exit) = self.ir.find_state(&state.name).and_then(|s| s.exit_action.clone()) {
+                actions.push((ActionKind::Exit, exit));
+            }
+        }
+        if let Some(action) = &transition.action {
+            actions.push((ActionKind::Transition, action.clone()));
+        }
+        for name in &entered {
+            if let Some(entry) = self.ir.find_state(name).and_then(|s| s.entry_action.clone()) {
+                actions.push((ActionKind::Entry, entry));
+            }
+        }
+
+        self.trace.push(TraceEvent::TransitionTaken {
+            from: transition.from.clone(),
+            to: transition.to.clone(),
+            event: transition.event.clone(),
+            guard: transition.guard.clone(),
+            guard_result: transition.guard.as_ref().map(|_| true),
+            actions: actions.iter().map(|(_, action)| action.to_string()).collect(),
+            timestamp_step: self.steps,
+        });
+
+        for (kind, action) in actions {
+            self.execute_action(kind, &action, outputs);
+        }
+        for name in entered {
+            let is_final = self.ir.find_state(&name).is_some_and(|s| s.is_final);
+            if is_final && self.active.is_empty() {
+                self.completed = true;
+            }
+            self.active.push(ActiveState::new(name));
+        }
+    }
+
+    /// Run an action: record it in the outputs and trace, apply its
+    /// assignments to the context and queue the events it sends.
+    fn execute_action(&mut self, kind: ActionKind, action: &ActionIR, outputs: &mut Vec<String>) {
+        let prefix = match kind {
+            ActionKind::Entry => "entry",
+            ActionKind::Exit => "exit",
+            ActionKind::Transition => "action",
+            ActionKind::Do => "do",
+        };
+        let detail = action.to_string();
+        outputs.push(format!("{}: {}", prefix, detail));
+        self.trace.push(TraceEvent::ActionExecuted { kind, detail });
+        self.apply_assignments(action);
+        self.event_queue.extend(sent_events(action).iter().cloned());
+    }
+
+    /// Pick the highest-priority transition for `event` whose guard holds,
+    /// trying the current state first and then its ancestors.
+    fn select_transition(&self, event: Option<&str>, outputs: &mut Vec<String>) -> Option<TransitionIR> {
+        for state in self.active.iter().rev() {
+            let matching = self
+                .ir
+                .matching_transitions(&state.name, event)
+                .into_iter()
+                .find(|t| self.guard_holds(t, outputs));
+            if let Some(transition) = matching {
+                return Some(transition.clone());
+            }
+        }
+        None
+    }
+
+    /// Process queued internal events until the queue is empty or the machine
+    /// completes.
+    ///
+    /// Returns a warning when more than `max_internal_iterations` events were
+    /// queued; the remaining ones are dropped.
+    fn run_to_completion(&mut self, outputs: &mut Vec<String>) -> Option<Diagnostic> {
+        let mut processed = 0;
+        while let Some(event) = self.event_queue.pop_front() {
+            if self.completed {
+                self.event_queue.clear();
+                break;
+            }
+            if processed == self.max_internal_iterations {
+                let dropped = self.event_queue.len() + 1;
+                self.event_queue.clear();
+                outputs.push(parallel::ITERATION_LIMIT_OUTPUT.to_string());
+                return Some(Diagnostic::warning(format!(
+                    "Internal event limit of {} reached in state '{}'; {} queued event(s) dropped",
+                    self.max_internal_iterations,
+                    self.current_state(),
+                    dropped
+                )));
+            }
+            processed += 1;
+
+            match self.select_transition(Some(&event), outputs) {
+                Some(transition) => {
+                    outputs.push(format!("event '{}': {} -> {}", event, transition.from, transition.to));
+                    self.take_transition(&transition, outputs);
+                }
+                None => self.trace.push(TraceEvent::EventIgnored {
+                    event,
+                    state: self.current_state().to_string(),
+                }),
+            }
+        }
+        None
+    }
+
+    /// Apply the assignments of a structured action to the context.
+    ///
+    /// Variables that are unset or not numeric (on either side) are treated as 0.
+    fn apply_assignments(&mut self, action: &ActionIR) {
+        if let ActionIR::Structured { assignments, .. } = action {
+            for assign in assignments {
+                let current = self
+                    .context
+                    .get(&assign.variable)
+                    .and_then(|v| v.as_number())
+                    .unwrap_or(0.0);
+                let new_value = assign.apply(current, |name| {
+                    self.context.get(name).and_then(|v| v.as_number())
+                });
+                self.context
+                    .insert(assign.variable.clone(), ContextValue::Number(new_value));
+            }
+        }
+    }
+
+    /// Get the transitions that compete for an event.
+    ///
+    /// Looks at the innermost active state with a matching transition and
+    /// returns every matching transition that shares the highest priority,
+    /// or an empty list when the choice is unambiguous.
+    pub fn conflicting_transitions(&self, event: Option<&str>) -> Vec<&TransitionIR> {
+        let matching = self
+            .active
+            .iter()
+            .rev()
+            .map(|s| self.ir.matching_transitions(&s.name, event))
+            .find(|m| !m.is_empty())
+            .unwrap_or_default();
+        let top = match matching.first() {
+            Some(t) => t.priority,
+            None => return Vec::new(),
+        };
+        let tied: Vec<_> = matching.into_iter().filter(|t| t.priority == top).collect();
+        if tied.len() > 1 {
+            tied
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl Runner for StateMachineRunner {
+    fn reset(&mut self) {
+        self.history.clear();
+        self.active = Self::entry_path(&self.ir, &self.history, &self.ir.initial)
+            .into_iter()
+            .map(ActiveState::new)
+            .collect();
+        self.completed = false;
+        self.context.clear();
+        self.event_queue.clear();
+    }
+
+    fn step(&mut self, event: Option<&str>) -> StepResult {
+        self.steps += 1;
+        if self.completed {
+            return StepResult::new(self.current_state()).completed();
+        }
+
+        let mut outputs = Vec::new();
+
+        if let Some(transition) = self.select_transition(event, &mut outputs) {
+            self.take_transition(&transition, &mut outputs);
+        } else {
+            if let Some(event) = event {
+                self.trace.push(TraceEvent::EventIgnored {
+                    event: event.to_string(),
+                    state: self.current_state().to_string(),
+                });
+            }
+
+            // Remained in the active states: perform their do actions, outermost first
+            let do_actions: Vec<ActionIR> = self
+                .active
+                .iter()
+                .filter_map(|s| self.ir.find_state(&s.name).and_then(|s| s.do_action.clone()))
+                .collect();
+            for action in do_actions {
+                self.execute_action(ActionKind::Do, &action, &mut outputs);
+            }
+        }
+        let diagnostic = self.run_to_completion(&mut outputs);
+
+        let mut result = StepResult::new(self.current_state()).with_outputs(outputs);
+        if let Some(diagnostic) = diagnostic {
+            result = result.with_diagnostic(diagnostic);
+        }
+
+        if self.completed {
+            result = result.completed();
+        }
+
+        result
+    }
+
+    fn current_state(&self) -> &str {
+        self.active.last().map(|s| s.name.as_str()).unwrap_or("")
     }
 
     fn is_completed(&self) -> bool {
@@ -718,6 +1672,7 @@ impl Runner for StateMachineRunner {
 mod tests {
     use super::*;
     use sysml_core::{Element, Relationship};
+    use sysml_run::AssignmentOp;
 
     fn create_traffic_light_graph() -> ModelGraph {
         let mut graph = ModelGraph::new();
@@ -829,6 +1784,691 @@ mod tests {
         assert!(diags[0].message.contains("No state machine"));
     }
 
+    /// Two state machines in one package; `Alarm` is stored first.
+    fn create_two_machine_graph() -> ModelGraph {
+        let mut graph = ModelGraph::new();
+        let pkg_id = ElementId::from_string("pkg");
+        graph.add_element(Element::new(pkg_id.clone(), ElementKind::Package).with_name("Vehicle"));
+
+        for (sm_key, sm_name, states) in [
+            ("sm-a", "Alarm", ["Armed", "Triggered"]),
+            ("sm-b", "VehicleStates", ["Off", "On"]),
+        ] {
+            let sm_id = ElementId::from_string(sm_key);
+            graph.add_element(
+                Element::new(sm_id.clone(), ElementKind::StateDefinition)
+                    .with_name(sm_name)
+                    .with_owner(pkg_id.clone()),
+            );
+            let ids: Vec<ElementId> = states
+                .iter()
+                .enumerate()
+                .map(|(i, state)| {
+                    graph.add_element(
+                        Element::new_with_kind(ElementKind::StateUsage)
+                            .with_name(*state)
+                            .with_owner(sm_id.clone())
+                            .with_prop("initial", i == 0),
+                    )
+                })
+                .collect();
+            graph.add_relationship(
+                Relationship::new(RelationshipKind::Transition, ids[0].clone(), ids[1].clone())
+                    .with_prop("event", "go"),
+            );
+        }
+
+        // A state definition without states is not a state machine
+        graph.add_element(
+            Element::new(ElementId::from_string("sm-c"), ElementKind::StateDefinition)
+                .with_name("Empty")
+                .with_owner(pkg_id),
+        );
+        graph
+    }
+
+    #[test]
+    fn compile_by_name_picks_requested_machine() {
+        let graph = create_two_machine_graph();
+
+        // compile() refuses to pick one of several machines
+        let diags = StateMachineCompiler::compile(&graph).unwrap_err();
+        assert!(diags[0].message.contains("2 state machines"));
+        assert!(diags[0].message.contains("compile_named"));
+        assert_eq!(
+            diags[0].notes,
+            vec!["available state machines: Vehicle::Alarm, Vehicle::VehicleStates".to_string()]
+        );
+
+        for name in ["VehicleStates", "Vehicle::VehicleStates"] {
+            let ir = StateMachineCompiler::compile_by_name(&graph, name).unwrap();
+            assert_eq!(ir.name, "VehicleStates");
+            assert_eq!(ir.initial, "Off");
+            assert_eq!(ir.states.len(), 2);
+            // Only this machine's transitions are included
+            assert_eq!(ir.transitions.len(), 1);
+            assert_eq!(ir.transitions[0].from, "Off");
+        }
+    }
+
+    #[test]
+    fn compile_named_matches_compile_by_name() {
+        let graph = create_two_machine_graph();
+
+        let ir = StateMachineCompiler::compile_named(&graph, "Vehicle::Alarm").unwrap();
+        assert_eq!(ir, StateMachineCompiler::compile_by_name(&graph, "Vehicle::Alarm").unwrap());
+        assert_eq!(ir.name, "Alarm");
+
+        let diags = StateMachineCompiler::compile_named(&graph, "Missing").unwrap_err();
+        assert!(diags[0].message.contains("'Missing' not found"));
+
+        // A model with one machine compiles without naming it
+        let mut single = graph.clone();
+        single.remove_subtree(&ElementId::from_string("sm-a"));
+        assert_eq!(
+            StateMachineCompiler::compile(&single).unwrap(),
+            StateMachineCompiler::compile_named(&single, "VehicleStates").unwrap()
+        );
+    }
+
+    #[test]
+    fn compile_by_name_lists_available_machines() {
+        let graph = create_two_machine_graph();
+
+        for name in ["Missing", "Other::VehicleStates"] {
+            let diags = StateMachineCompiler::compile_by_name(&graph, name).unwrap_err();
+            assert!(diags[0].message.contains(name));
+            assert_eq!(
+                diags[0].notes,
+                vec!["available state machines: Vehicle::Alarm, Vehicle::VehicleStates".to_string()]
+            );
+        }
+    }
+
+    #[test]
+    fn list_state_machines_skips_definitions_without_states() {
+        let graph = create_two_machine_graph();
+
+        let machines = StateMachineCompiler::list_state_machines(&graph);
+        assert_eq!(
+            machines,
+            vec![
+                (ElementId::from_string("sm-a"), "Vehicle::Alarm".to_string()),
+                (ElementId::from_string("sm-b"), "Vehicle::VehicleStates".to_string()),
+            ]
+        );
+        for (_, name) in machines {
+            assert!(StateMachineCompiler::compile_by_name(&graph, &name).is_ok());
+        }
+    }
+
+    #[test]
+    fn compile_all_compiles_every_machine() {
+        let graph = create_two_machine_graph();
+
+        let results = StateMachineCompiler::compile_all(&graph);
+        let names: Vec<String> = results.into_iter().map(|r| r.unwrap().name).collect();
+        assert_eq!(names, vec!["Alarm", "VehicleStates"]);
+        assert!(StateMachineCompiler::compile_all(&ModelGraph::new()).is_empty());
+    }
+
+    fn create_priority_graph(high_priority: i64) -> ModelGraph {
+        let mut graph = ModelGraph::new();
+
+        let sm = Element::new_with_kind(ElementKind::StateDefinition).with_name("Door");
+        let sm_id = graph.add_element(sm);
+
+        let closed = Element::new_with_kind(ElementKind::StateUsage)
+            .with_name("Closed")
+            .with_owner(sm_id.clone())
+            .with_prop("initial", true);
+        let closed_id = graph.add_element(closed);
+
+        let open = Element::new_with_kind(ElementKind::StateUsage)
+            .with_name("Open")
+            .with_owner(sm_id.clone());
+        let open_id = graph.add_element(open);
+
+        let locked = Element::new_with_kind(ElementKind::StateUsage)
+            .with_name("Locked")
+            .with_owner(sm_id);
+        let locked_id = graph.add_element(locked);
+
+        let t1 = Relationship::new(RelationshipKind::Transition, closed_id.clone(), open_id)
+            .with_prop("event", "push");
+        graph.add_relationship(t1);
+
+        let t2 = Relationship::new(RelationshipKind::Transition, closed_id, locked_id)
+            .with_prop("event", "push")
+            .with_prop("priority", high_priority);
+        graph.add_relationship(t2);
+
+        graph
+    }
+
+    #[test]
+    fn compile_reads_transition_priority() {
+        let graph = create_priority_graph(3);
+        let ir = StateMachineCompiler::compile(&graph).unwrap();
+
+        let locked = ir.transitions.iter().find(|t| t.to == "Locked").unwrap();
+        assert_eq!(locked.priority, 3);
+        let open = ir.transitions.iter().find(|t| t.to == "Open").unwrap();
+        assert_eq!(open.priority, 0);
+    }
+
+    #[test]
+    fn runner_picks_highest_priority_transition() {
+        let graph = create_priority_graph(3);
+        let mut runner = StateMachineRunner::from_graph(&graph).unwrap();
+
+        assert!(runner.conflicting_transitions(Some("push")).is_empty());
+        let result = runner.step(Some("push"));
+        assert_eq!(result.state, "Locked");
+    }
+
+    #[test]
+    fn runner_prefers_guarded_then_declaration_order() {
+        let ir = StateMachineIR::new("M", "s")
+            .with_state(StateIR::new("s"))
+            .with_state(StateIR::new("a"))
+            .with_state(StateIR::new("b"))
+            .with_state(StateIR::new("c"))
+            .with_transition(TransitionIR::new("s", "a").with_event("go"))
+            .with_transition(TransitionIR::new("s", "b").with_event("go"))
+            .with_transition(TransitionIR::new("s", "c").with_event("go").with_guard("true"));
+
+        let mut runner = StateMachineRunner::new(ir.clone());
+        assert_eq!(runner.step(Some("go")).state, "c");
+
+        let ir = StateMachineIR::new("M", "s")
+            .with_transition(TransitionIR::new("s", "a").with_event("go"))
+            .with_transition(TransitionIR::new("s", "b").with_event("go"));
+        let mut runner = StateMachineRunner::new(ir);
+        assert_eq!(runner.conflicting_transitions(Some("go")).len(), 2);
+        assert_eq!(runner.step(Some("go")).state, "a");
+    }
+
+    #[test]
+    fn compiler_warns_on_equal_priority_conflicts() {
+        let graph = create_priority_graph(0);
+        let (ir, warnings) = StateMachineCompiler::compile_with_warnings(&graph).unwrap();
+
+        assert_eq!(ir.transitions.len(), 2);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("state 'Closed'"));
+        assert!(warnings[0].message.contains("'push'"));
+
+        let graph = create_priority_graph(1);
+        let (_, warnings) = StateMachineCompiler::compile_with_warnings(&graph).unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn conflict_warning_notes_guardless_duplicates() {
+        let guarded = StateMachineIR::new("M", "s")
+            .with_transition(TransitionIR::new("s", "a").with_event("go").with_guard("x > 0"))
+            .with_transition(TransitionIR::new("s", "b").with_event("go"));
+        let warnings = StateMachineCompiler::conflict_warnings(&guarded);
+        assert_eq!(warnings.len(), 1);
+        assert!(!warnings[0].notes.iter().any(|n| n.contains("no guard")));
+
+        let guardless = guarded.with_transition(TransitionIR::new("s", "c").with_event("go"));
+        let warnings = StateMachineCompiler::conflict_warnings(&guardless);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].notes.iter().any(|n| n.starts_with("2 of them have no guard")));
+    }
+
+    #[test]
+    fn compiler_warns_on_unreachable_states_with_span() {
+        let mut graph = create_priority_graph(1);
+        let sm_id = graph.elements.values().find(|e| e.name.as_deref() == Some("Door")).unwrap().id.clone();
+        // A typo in a transition source leaves `Ajar` without a way in
+        graph.add_element(
+            Element::new_with_kind(ElementKind::StateUsage)
+                .with_name("Ajar")
+                .with_owner(sm_id)
+                .with_span(sysml_span::Span::new("door.sysml", 120, 124)),
+        );
+
+        let (_, warnings) = StateMachineCompiler::compile_with_warnings(&graph).unwrap();
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert_eq!(warnings[0].message, "state 'Ajar' is unreachable from initial state 'Closed'");
+        assert_eq!(warnings[0].span, Some(sysml_span::Span::new("door.sysml", 120, 124)));
+    }
+
+    #[test]
+    fn unreachable_state_spans_come_from_the_compiled_machine() {
+        let mut graph = create_priority_graph(1);
+        let sm_id = graph.elements.values().find(|e| e.name.as_deref() == Some("Door")).unwrap().id.clone();
+        // An earlier `Ajar` outside the machine must not lend its span
+        let other = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Other"));
+        graph.add_element(
+            Element::new_with_kind(ElementKind::StateUsage)
+                .with_name("Ajar")
+                .with_owner(other)
+                .with_span(sysml_span::Span::new("a.sysml", 0, 4)),
+        );
+        graph.add_element(
+            Element::new_with_kind(ElementKind::StateUsage)
+                .with_name("Ajar")
+                .with_owner(sm_id)
+                .with_span(sysml_span::Span::new("door.sysml", 120, 124)),
+        );
+
+        let (_, warnings) = StateMachineCompiler::compile_with_warnings(&graph).unwrap();
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert_eq!(warnings[0].span, Some(sysml_span::Span::new("door.sysml", 120, 124)));
+    }
+
+    #[test]
+    fn targeting_a_substate_does_not_enter_its_initial_sibling() {
+        // `Busy` is entered directly, so `On` is active but never enters `Idle`
+        let ir = StateMachineIR::new("M", "Off")
+            .with_state(StateIR::new("Off"))
+            .with_state(
+                StateIR::new("On")
+                    .with_substate(StateIR::new("Idle"))
+                    .with_substate(StateIR::new("Busy")),
+            )
+            .with_transition(TransitionIR::new("Off", "Busy").with_event("work"));
+        let messages: Vec<String> = StateMachineCompiler::unreachable_warnings(&ir)
+            .into_iter()
+            .map(|d| d.message)
+            .collect();
+        assert_eq!(messages, vec!["state 'Idle' is unreachable from initial state 'Off'"]);
+
+        // Entering `On` itself does
+        let ir = ir.with_transition(TransitionIR::new("Busy", "On").with_event("reset"));
+        assert!(StateMachineCompiler::unreachable_warnings(&ir).is_empty());
+    }
+
+    #[test]
+    fn unreachable_states_follow_hierarchy_and_regions() {
+        // Entering `On` enters `Idle`; `Busy` is reached through a transition
+        // from its parent, and `Stuck` only from the unreachable `Broken`
+        let ir = StateMachineIR::new("M", "Off")
+            .with_state(StateIR::new("Off"))
+            .with_state(
+                StateIR::new("On")
+                    .with_substate(StateIR::new("Idle"))
+                    .with_substate(StateIR::new("Busy")),
+            )
+            .with_state(StateIR::new("Broken"))
+            .with_state(StateIR::new("Stuck"))
+            .with_transition(TransitionIR::new("Off", "On").with_event("power"))
+            .with_transition(TransitionIR::new("On", "Busy").with_event("work"))
+            .with_transition(TransitionIR::new("Broken", "Stuck"))
+            .with_transition(TransitionIR::new("Brokn", "Off"));
+        let messages: Vec<String> = StateMachineCompiler::unreachable_warnings(&ir)
+            .into_iter()
+            .map(|d| d.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "state 'Broken' is unreachable from initial state 'Off'",
+                "state 'Stuck' is unreachable from initial state 'Off'",
+            ]
+        );
+
+        let parallel = StateMachineIR::parallel("P")
+            .with_region(
+                RegionIR::new("a", "X")
+                    .with_state(StateIR::new("X"))
+                    .with_state(StateIR::new("Y"))
+                    .with_transition(TransitionIR::new("X", "Y")),
+            )
+            .with_region(RegionIR::new("b", "U").with_state(StateIR::new("U")).with_state(StateIR::new("V")));
+        let warnings = StateMachineCompiler::unreachable_warnings(&parallel);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "[b] state 'V' is unreachable from initial state 'U'");
+    }
+
+    fn create_guarded_traffic_light_graph() -> ModelGraph {
+        let mut graph = ModelGraph::new();
+
+        let sm = Element::new_with_kind(ElementKind::StateDefinition).with_name("TrafficLight");
+        let sm_id = graph.add_element(sm);
+
+        let red = Element::new_with_kind(ElementKind::StateUsage)
+            .with_name("Red")
+            .with_owner(sm_id.clone())
+            .with_prop("initial", true);
+        let red_id = graph.add_element(red);
+
+        let green = Element::new_with_kind(ElementKind::StateUsage)
+            .with_name("Green")
+            .with_owner(sm_id);
+        let green_id = graph.add_element(green);
+
+        let t1 = Relationship::new(RelationshipKind::Transition, red_id, green_id)
+            .with_prop("event", "timer")
+            .with_prop("guard", "carsWaiting > 3");
+        graph.add_relationship(t1);
+
+        graph
+    }
+
+    #[test]
+    fn guard_blocks_transition_until_condition_holds() {
+        let graph = create_guarded_traffic_light_graph();
+        let mut runner = StateMachineRunner::from_graph(&graph).unwrap();
+
+        runner.set_context("carsWaiting", 2.0);
+        let result = runner.step(Some("timer"));
+        assert_eq!(result.state, "Red");
+        assert!(result.outputs.is_empty());
+
+        runner.set_context("carsWaiting", 4.0);
+        let result = runner.step(Some("timer"));
+        assert_eq!(result.state, "Green");
+        assert_eq!(runner.get_context("carsWaiting"), Some(ContextValue::Number(4.0)));
+    }
+
+    #[test]
+    fn arithmetic_assignments_read_the_context() {
+        let ir = StateMachineIR::new("M", "s")
+            .with_state(StateIR::new("s"))
+            .with_state(StateIR::new("t"))
+            .with_transition(
+                TransitionIR::new("s", "t")
+                    .with_event("go")
+                    .with_action_ir(parse_action("x = x * 2; rate /= 4; t = u + 5")),
+            );
+        let mut runner = StateMachineRunner::new(ir.clone());
+        runner.set_context("x", 3.0);
+        runner.set_context("rate", 10.0);
+        runner.set_context("u", 1.5);
+        runner.step(Some("go"));
+        assert_eq!(runner.get_context("x"), Some(ContextValue::Number(6.0)));
+        assert_eq!(runner.get_context("rate"), Some(ContextValue::Number(2.5)));
+        assert_eq!(runner.get_context("t"), Some(ContextValue::Number(6.5)));
+
+        let mut parallel = ParallelStateMachineRunner::new(StateMachineIR::parallel("P").with_region(
+            RegionIR::new("r", "s")
+                .with_state(StateIR::new("s"))
+                .with_state(StateIR::new("t"))
+                .with_transition(ir.transitions[0].clone()),
+        ));
+        parallel.set_context("x", 3.0);
+        parallel.set_context("u", 1.5);
+        let result = parallel.send("go");
+        assert_eq!(parallel.get_context("x"), Some(6.0));
+        assert_eq!(parallel.get_context("t"), Some(6.5));
+        assert_eq!(result.outputs[0], "[r] action: x = x * 2");
+    }
+
+    #[test]
+    fn guard_with_unknown_variable_does_not_fire() {
+        let graph = create_guarded_traffic_light_graph();
+        let mut runner = StateMachineRunner::from_graph(&graph).unwrap();
+
+        let result = runner.step(Some("timer"));
+        assert_eq!(result.state, "Red");
+        assert_eq!(result.outputs, vec!["guard error: unknown variable 'carsWaiting'"]);
+    }
+
+    #[test]
+    fn failed_guard_falls_through_to_next_candidate() {
+        let ir = StateMachineIR::new("M", "s")
+            .with_transition(TransitionIR::new("s", "fast").with_event("go").with_guard("speed > 100"))
+            .with_transition(TransitionIR::new("s", "slow").with_event("go"));
+
+        let mut runner = StateMachineRunner::new(ir);
+        runner.set_context("speed", 50.0);
+        assert_eq!(runner.step(Some("go")).state, "slow");
+    }
+
+    #[test]
+    fn restore_returns_to_snapshot() {
+        let ir = StateMachineIR::new("M", "outer")
+            .with_state(
+                StateIR::new("outer")
+                    .with_substate(StateIR::new("a"))
+                    .with_substate(StateIR::new("b"))
+                    .with_initial_substate("a"),
+            )
+            .with_state(StateIR::new("done").final_state())
+            .with_transition(
+                TransitionIR::new("a", "b")
+                    .with_event("next")
+                    .with_action(parse_action("count += 1")),
+            )
+            .with_transition(TransitionIR::new("b", "done").with_event("finish"));
+
+        let mut runner = StateMachineRunner::new(ir);
+        runner.step(Some("next"));
+        runner.advance_time(2.0);
+        let snapshot = runner.snapshot();
+        assert_eq!(snapshot.current_state(), "b");
+        assert_eq!(snapshot.context().get("count"), Some(&ContextValue::Number(1.0)));
+
+        runner.step(Some("finish"));
+        assert!(runner.is_completed());
+
+        runner.restore(snapshot.clone());
+        assert!(!runner.is_completed());
+        assert_eq!(runner.active_states(), vec!["outer", "b"]);
+        assert_eq!(runner.state_time(), 2.0);
+        assert_eq!(runner.get_context("count"), Some(ContextValue::Number(1.0)));
+        assert_eq!(runner.snapshot(), snapshot);
+    }
+
+    #[test]
+    fn after_transition_fires_when_time_elapses() {
+        let ir = StateMachineIR::new("Light", "red")
+            .with_state(StateIR::new("red"))
+            .with_state(StateIR::new("green"))
+            .with_transition(TransitionIR::new("red", "green").with_after(10.0));
+
+        let mut runner = StateMachineRunner::new(ir);
+        assert_eq!(runner.step(None).state, "red");
+        assert_eq!(runner.advance_time(4.0).state, "red");
+        assert_eq!(runner.state_time(), 4.0);
+        assert_eq!(runner.advance_time(7.0).state, "green");
+        assert_eq!(runner.state_time(), 1.0);
+        assert_eq!(runner.get_context(TIME_CONTEXT_VARIABLE), Some(ContextValue::Number(11.0)));
+    }
+
+    #[test]
+    fn smallest_remaining_after_fires_first() {
+        let ir = StateMachineIR::new("M", "s")
+            .with_state(StateIR::new("s"))
+            .with_state(StateIR::new("slow"))
+            .with_state(StateIR::new("fast"))
+            .with_state(StateIR::new("done").final_state())
+            .with_transition(TransitionIR::new("s", "slow").with_after(5.0))
+            .with_transition(TransitionIR::new("s", "fast").with_after(2.0))
+            .with_transition(TransitionIR::new("fast", "done").with_after(1.0));
+
+        // One advance fires the chain; leftover time carries into each state
+        let mut runner = StateMachineRunner::new(ir.clone());
+        let result = runner.advance_time(3.5);
+        assert_eq!(result.state, "done");
+        assert!(result.completed);
+        assert_eq!(runner.state_time(), 0.5);
+
+        let mut runner = StateMachineRunner::new(ir);
+        assert_eq!(runner.advance_time(2.5).state, "fast");
+    }
+
+    #[test]
+    fn after_guard_is_checked_when_due() {
+        let ir = StateMachineIR::new("M", "s")
+            .with_transition(TransitionIR::new("s", "early").with_after(2.0).with_guard("t > 5"))
+            .with_transition(TransitionIR::new("s", "late").with_after(4.0).with_guard("t > 3"));
+
+        let mut runner = StateMachineRunner::new(ir);
+        let result = runner.advance_time(10.0);
+        assert_eq!(result.state, "late");
+        assert_eq!(runner.state_time(), 6.0);
+    }
+
+    #[test]
+    fn events_do_not_trigger_after_transitions() {
+        let ir = StateMachineIR::new("M", "s")
+            .with_transition(TransitionIR::new("s", "timed").with_event("go").with_after(1.0));
+
+        let mut runner = StateMachineRunner::new(ir);
+        assert_eq!(runner.step(Some("go")).state, "s");
+        assert_eq!(runner.step(None).state, "s");
+        assert_eq!(runner.advance_time(1.0).state, "timed");
+    }
+
+    #[test]
+    fn zero_delay_after_loop_is_bounded() {
+        let ir = StateMachineIR::new("M", "a")
+            .with_transition(TransitionIR::new("a", "b").with_after(0.0))
+            .with_transition(TransitionIR::new("b", "a").with_after(0.0));
+
+        let mut runner = StateMachineRunner::new(ir);
+        let result = runner.advance_time(1.0);
+        assert_eq!(result.outputs.last().map(String::as_str), Some(parallel::ITERATION_LIMIT_OUTPUT));
+    }
+
+    #[test]
+    fn compile_reads_transition_after() {
+        let mut graph = create_priority_graph(0);
+        let rel_id = graph.relationships.keys().next().unwrap().clone();
+        graph.relationships.get_mut(&rel_id).unwrap().props.insert("after".into(), Value::Int(30));
+
+        let ir = StateMachineCompiler::compile(&graph).unwrap();
+        assert_eq!(ir.transitions.iter().filter(|t| t.after == Some(30.0)).count(), 1);
+    }
+
+    #[test]
+    fn compile_reads_delay_prop_and_after_trigger() {
+        let mut graph = create_priority_graph(0);
+        let rel_ids: Vec<_> = graph.relationships.keys().cloned().collect();
+        let props = &mut graph.relationships.get_mut(&rel_ids[0]).unwrap().props;
+        props.insert("delay".into(), Value::String("2.5 [s]".into()));
+        props.remove("event");
+        let props = &mut graph.relationships.get_mut(&rel_ids[1]).unwrap().props;
+        props.insert("event".into(), Value::String("after(20)".into()));
+
+        let ir = StateMachineCompiler::compile(&graph).unwrap();
+        let timed: Vec<_> = ir.transitions.iter().filter_map(|t| t.after).collect();
+        assert_eq!(timed.len(), 2);
+        assert!(timed.contains(&2.5) && timed.contains(&20.0));
+        assert!(ir.transitions.iter().all(|t| t.event.as_deref() != Some("after(20)")));
+    }
+
+    #[test]
+    fn after_trigger_strings() {
+        let delay = |event: &str| with_trigger(TransitionIR::new("a", "b"), event).after;
+        assert_eq!(delay("after(20)"), Some(20.0));
+        assert_eq!(delay("after 20 [ms]"), Some(20.0));
+        assert_eq!(delay(" after ( 0.5 ) "), Some(0.5));
+        assert_eq!(delay("afterglow"), None);
+        assert_eq!(delay("after(-1)"), None);
+        assert_eq!(with_trigger(TransitionIR::new("a", "b"), "afterglow").event.as_deref(), Some("afterglow"));
+    }
+
+    fn create_nested_ir() -> StateMachineIR {
+        StateMachineIR::new("Heater", "off")
+            .with_state(StateIR::new("off").with_entry("offEntry"))
+            .with_state(
+                StateIR::new("on")
+                    .with_entry("onEntry")
+                    .with_exit("onExit")
+                    .with_substate(StateIR::new("low").with_entry("lowEntry").with_exit("lowExit"))
+                    .with_substate(
+                        StateIR::new("high")
+                            .with_entry("highEntry")
+                            .with_exit("highExit")
+                            .with_substate(StateIR::new("boost").with_entry("boostEntry").with_exit("boostExit")),
+                    ),
+            )
+            .with_state(StateIR::new("done").final_state())
+            .with_transition(TransitionIR::new("off", "on").with_event("power"))
+            .with_transition(TransitionIR::new("low", "high").with_event("up"))
+            .with_transition(TransitionIR::new("on", "off").with_event("power"))
+            .with_transition(TransitionIR::new("boost", "low").with_event("power"))
+            .with_transition(TransitionIR::new("on", "done").with_event("stop"))
+    }
+
+    #[test]
+    fn entering_composite_state_enters_initial_substate_top_down() {
+        let mut runner = StateMachineRunner::new(create_nested_ir());
+
+        let result = runner.step(Some("power"));
+        assert_eq!(result.state, "low");
+        assert_eq!(result.outputs, vec!["entry: onEntry", "entry: lowEntry"]);
+        assert_eq!(runner.active_states(), vec!["on", "low"]);
+
+        // Sibling transition stays inside the composite state
+        let result = runner.step(Some("up"));
+        assert_eq!(result.outputs, vec!["exit: lowExit", "entry: highEntry", "entry: boostEntry"]);
+        assert_eq!(runner.active_states(), vec!["on", "high", "boost"]);
+    }
+
+    #[test]
+    fn exiting_composite_state_runs_exit_actions_bottom_up() {
+        let mut runner = StateMachineRunner::new(create_nested_ir());
+        runner.step(Some("power"));
+        runner.step(Some("up"));
+
+        // The innermost state's own transition wins over the ancestor's
+        let result = runner.step(Some("power"));
+        assert_eq!(result.state, "low");
+        assert_eq!(result.outputs, vec!["exit: boostExit", "exit: highExit", "entry: lowEntry"]);
+
+        let result = runner.step(Some("power"));
+        assert_eq!(result.state, "off");
+        assert_eq!(result.outputs, vec!["exit: lowExit", "exit: onExit", "entry: offEntry"]);
+        assert_eq!(runner.active_states(), vec!["off"]);
+    }
+
+    #[test]
+    fn ancestor_transition_completes_machine() {
+        let mut runner = StateMachineRunner::new(create_nested_ir());
+        runner.step(Some("power"));
+        runner.step(Some("up"));
+
+        let result = runner.step(Some("stop"));
+        assert!(result.completed);
+        assert_eq!(runner.active_states(), vec!["done"]);
+
+        runner.reset();
+        assert_eq!(runner.current_state(), "off");
+    }
+
+    #[test]
+    fn compile_nested_states() {
+        let mut graph = ModelGraph::new();
+        let sm = graph.add_element(Element::new_with_kind(ElementKind::StateDefinition).with_name("Heater"));
+        let off = graph.add_element(
+            Element::new_with_kind(ElementKind::StateUsage)
+                .with_name("off")
+                .with_owner(sm.clone())
+                .with_prop("initial", true),
+        );
+        let on = graph.add_element(Element::new_with_kind(ElementKind::StateUsage).with_name("on").with_owner(sm));
+        let low = graph.add_element(Element::new_with_kind(ElementKind::StateUsage).with_name("low").with_owner(on.clone()));
+        let high = graph.add_element(
+            Element::new_with_kind(ElementKind::StateUsage)
+                .with_name("high")
+                .with_owner(on.clone())
+                .with_prop("initial", true),
+        );
+        graph.add_relationship(Relationship::new(RelationshipKind::Transition, off, on).with_prop("event", "power"));
+        graph.add_relationship(Relationship::new(RelationshipKind::Transition, high, low).with_prop("event", "down"));
+
+        let ir = StateMachineCompiler::compile(&graph).unwrap();
+        assert!(!ir.is_parallel());
+        assert_eq!(ir.states.len(), 2);
+        let on_ir = ir.find_state("on").unwrap();
+        assert_eq!(on_ir.substates.len(), 2);
+        assert_eq!(on_ir.initial_substate.as_deref(), Some("high"));
+        // Transitions between nested states are compiled too
+        assert!(ir.transitions.iter().any(|t| t.from == "high" && t.to == "low"));
+
+        let mut runner = StateMachineRunner::new(ir);
+        assert_eq!(runner.step(Some("power")).state, "high");
+        assert_eq!(runner.step(Some("down")).state, "low");
+    }
+
     fn create_parallel_state_machine_graph() -> ModelGraph {
         let mut graph = ModelGraph::new();
 
@@ -984,9 +2624,489 @@ mod tests {
         if let Some(ActionIR::Structured { assignments, .. }) = &transition.action {
             assert_eq!(assignments.len(), 1);
             assert_eq!(assignments[0].variable, "t");
-            assert_eq!(assignments[0].value, 20.0);
+            assert_eq!(assignments[0].value.as_literal(), Some(20.0));
         } else {
             panic!("Expected structured action");
         }
     }
+
+    fn create_subaction_graph() -> ModelGraph {
+        let mut graph = ModelGraph::new();
+
+        let sm = Element::new_with_kind(ElementKind::StateDefinition).with_name("Pump");
+        let sm_id = graph.add_element(sm);
+
+        let idle = Element::new_with_kind(ElementKind::StateUsage)
+            .with_name("Idle")
+            .with_owner(sm_id.clone())
+            .with_prop("initial", true);
+        let idle_id = graph.add_element(idle);
+
+        let running = Element::new_with_kind(ElementKind::StateUsage)
+            .with_name("Running")
+            .with_owner(sm_id.clone());
+        let running_id = graph.add_element(running);
+
+        // entry: inline body on the subaction element
+        let entry = Element::new_with_kind(ElementKind::ActionUsage)
+            .with_owner(running_id.clone())
+            .with_prop("kind", Value::Enum("entry".to_string()))
+            .with_prop("body", "ticks = 0");
+        graph.add_element(entry);
+
+        // do: references a separately declared action usage
+        let tick = Element::new_with_kind(ElementKind::ActionUsage)
+            .with_name("tick")
+            .with_owner(sm_id.clone())
+            .with_prop("body", "ticks += 1");
+        let tick_id = graph.add_element(tick);
+        let do_sub = Element::new_with_kind(ElementKind::ReferenceUsage)
+            .with_owner(running_id.clone())
+            .with_prop("kind", Value::Enum("do".to_string()))
+            .with_prop("action", Value::Ref(tick_id));
+        graph.add_element(do_sub);
+
+        // exit: action usage owned by the subaction element
+        let exit_sub = Element::new_with_kind(ElementKind::ReferenceUsage)
+            .with_owner(running_id.clone())
+            .with_prop("kind", Value::Enum("exit".to_string()));
+        let exit_sub_id = graph.add_element(exit_sub);
+        let stop = Element::new_with_kind(ElementKind::ActionUsage)
+            .with_owner(exit_sub_id)
+            .with_prop("body", "stopMotor()");
+        graph.add_element(stop);
+
+        graph.add_relationship(
+            Relationship::new(RelationshipKind::Transition, idle_id.clone(), running_id.clone())
+                .with_prop("event", "start"),
+        );
+        graph.add_relationship(
+            Relationship::new(RelationshipKind::Transition, running_id, idle_id)
+                .with_prop("event", "stop"),
+        );
+
+        graph
+    }
+
+    #[test]
+    fn compile_state_subaction_children() {
+        let graph = create_subaction_graph();
+        let ir = StateMachineCompiler::compile(&graph).unwrap();
+
+        // Subaction ActionUsages are not states
+        assert_eq!(ir.states.len(), 2);
+
+        let running = ir.find_state("Running").unwrap();
+        match &running.entry_action {
+            Some(ActionIR::Structured { assignments, .. }) => {
+                assert_eq!(assignments[0].variable, "ticks");
+                assert_eq!(assignments[0].operator, AssignmentOp::Set);
+            }
+            other => panic!("Expected structured entry action, got {:?}", other),
+        }
+        match &running.do_action {
+            Some(ActionIR::Structured { assignments, .. }) => {
+                assert_eq!(assignments[0].operator, AssignmentOp::Add);
+                assert_eq!(assignments[0].value.as_literal(), Some(1.0));
+            }
+            other => panic!("Expected structured do action, got {:?}", other),
+        }
+        assert_eq!(
+            running.exit_action.as_ref().and_then(|a| a.as_simple()),
+            Some("stopMotor()")
+        );
+        assert!(ir.find_state("Idle").unwrap().do_action.is_none());
+    }
+
+    #[test]
+    fn runner_do_action_runs_each_tick() {
+        let graph = create_subaction_graph();
+        let mut runner = StateMachineRunner::from_graph(&graph).unwrap();
+
+        runner.step(Some("start"));
+        assert_eq!(runner.current_state(), "Running");
+        // Set by the entry action
+        assert_eq!(runner.get_context("ticks"), Some(ContextValue::Number(0.0)));
+
+        for expected in 1..=3 {
+            let result = runner.step(None);
+            assert_eq!(result.state, "Running");
+            assert_eq!(result.outputs, vec!["do: ticks += 1".to_string()]);
+            assert_eq!(runner.get_context("ticks"), Some(ContextValue::Number(expected as f64)));
+        }
+
+        // Leaving the state does not perform the do action
+        let result = runner.step(Some("stop"));
+        assert_eq!(result.state, "Idle");
+        assert!(!result.outputs.iter().any(|o| o.starts_with("do:")));
+        assert_eq!(runner.get_context("ticks"), Some(ContextValue::Number(3.0)));
+    }
+
+    #[test]
+    fn history_state_resumes_last_substate_on_reentry() {
+        let mut ir = create_nested_ir();
+        ir.transitions.retain(|t| t.from != "boost");
+        let mut with_history = ir.clone();
+        with_history.states[1] = with_history.states[1].clone().with_history(true);
+
+        // Without history the composite state starts over at its initial substate
+        let mut runner = StateMachineRunner::new(ir);
+        runner.step(Some("power"));
+        runner.step(Some("up"));
+        runner.step(Some("power"));
+        assert_eq!(runner.history_state("on"), None);
+        runner.step(Some("power"));
+        assert_eq!(runner.active_states(), vec!["on", "low"]);
+
+        let mut runner = StateMachineRunner::new(with_history);
+        runner.step(Some("power"));
+        runner.step(Some("up"));
+        runner.step(Some("power"));
+        assert_eq!(runner.active_states(), vec!["off"]);
+        assert_eq!(runner.history_state("on"), Some("high"));
+
+        // Shallow: `high` itself starts over at its initial substate
+        let result = runner.step(Some("power"));
+        assert_eq!(result.outputs, vec!["entry: onEntry", "entry: highEntry", "entry: boostEntry"]);
+        assert_eq!(runner.active_states(), vec!["on", "high", "boost"]);
+
+        runner.reset();
+        assert_eq!(runner.history_state("on"), None);
+        runner.step(Some("power"));
+        assert_eq!(runner.active_states(), vec!["on", "low"]);
+    }
+
+    #[test]
+    fn compile_region_history_flag() {
+        let mut graph = create_parallel_state_machine_graph();
+        let relay_id = graph
+            .elements
+            .values()
+            .find(|e| e.name.as_deref() == Some("relay"))
+            .map(|e| e.id.clone())
+            .unwrap();
+        graph
+            .get_element_mut(&relay_id)
+            .unwrap()
+            .set_prop("isHistory", true);
+
+        let ir = StateMachineCompiler::compile(&graph).unwrap();
+        assert!(ir.find_region("relay").unwrap().history);
+        assert!(!ir.find_region("grid").unwrap().history);
+    }
+
+    #[test]
+    fn runner_trace_distinguishes_taken_and_ignored() {
+        let graph = create_traffic_light_graph();
+        let mut runner = StateMachineRunner::from_graph(&graph).unwrap().with_tracing();
+
+        runner.step(Some("honk"));
+        runner.step(Some("timer"));
+
+        assert_eq!(
+            runner.trace(),
+            &[
+                TraceEvent::EventIgnored {
+                    event: "honk".to_string(),
+                    state: "Red".to_string(),
+                },
+                TraceEvent::TransitionTaken {
+                    from: "Red".to_string(),
+                    to: "Green".to_string(),
+                    event: Some("timer".to_string()),
+                    guard: None,
+                    guard_result: None,
+                    actions: Vec::new(),
+                    timestamp_step: 2,
+                },
+            ]
+        );
+
+        assert_eq!(runner.take_trace().len(), 2);
+        assert!(runner.trace().is_empty());
+
+        runner.step(Some("timer"));
+        assert_eq!(runner.take_trace().len(), 1);
+        runner.clear_trace();
+        assert!(runner.trace().is_empty());
+    }
+
+    #[test]
+    fn runner_trace_records_guards_actions_and_steps() {
+        let ir = StateMachineIR::new("Door", "closed")
+            .with_state(StateIR::new("closed").with_exit("unlock"))
+            .with_state(StateIR::new("open").with_entry("light"))
+            .with_transition(
+                TransitionIR::new("closed", "open")
+                    .with_event("push")
+                    .with_guard("force > 2")
+                    .with_action("swing"),
+            )
+            .with_transition(TransitionIR::new("open", "closed").with_event("pull"));
+
+        // Tracing is opt-in
+        let mut runner = StateMachineRunner::new(ir.clone());
+        runner.set_context("force", 5.0);
+        runner.step(Some("push"));
+        assert!(runner.trace().is_empty());
+
+        let mut runner = StateMachineRunner::new(ir).with_tracing();
+        runner.set_context("force", 5.0);
+        runner.step(Some("knock"));
+        runner.step(Some("push"));
+        runner.step(Some("pull"));
+
+        let action = |kind, detail: &str| TraceEvent::ActionExecuted {
+            kind,
+            detail: detail.to_string(),
+        };
+        assert_eq!(
+            runner.take_trace(),
+            vec![
+                TraceEvent::EventIgnored {
+                    event: "knock".to_string(),
+                    state: "closed".to_string(),
+                },
+                TraceEvent::TransitionTaken {
+                    from: "closed".to_string(),
+                    to: "open".to_string(),
+                    event: Some("push".to_string()),
+                    guard: Some("force > 2".to_string()),
+                    guard_result: Some(true),
+                    actions: vec!["unlock".to_string(), "swing".to_string(), "light".to_string()],
+                    timestamp_step: 2,
+                },
+                action(ActionKind::Exit, "unlock"),
+                action(ActionKind::Transition, "swing"),
+                action(ActionKind::Entry, "light"),
+                TraceEvent::TransitionTaken {
+                    from: "open".to_string(),
+                    to: "closed".to_string(),
+                    event: Some("pull".to_string()),
+                    guard: None,
+                    guard_result: None,
+                    actions: Vec::new(),
+                    timestamp_step: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn runner_trace_records_actions_and_is_capped() {
+        let graph = create_subaction_graph();
+        let mut runner = StateMachineRunner::from_graph(&graph)
+            .unwrap()
+            .with_trace_capacity(3);
+
+        runner.step(Some("start"));
+        runner.step(None);
+
+        // TransitionTaken, entry, do -- all within capacity
+        assert!(matches!(runner.trace()[0], TraceEvent::TransitionTaken { .. }));
+        assert_eq!(
+            runner.trace()[1],
+            TraceEvent::ActionExecuted {
+                kind: ActionKind::Entry,
+                detail: "ticks = 0".to_string(),
+            }
+        );
+        assert_eq!(
+            runner.trace()[2],
+            TraceEvent::ActionExecuted {
+                kind: ActionKind::Do,
+                detail: "ticks += 1".to_string(),
+            }
+        );
+
+        runner.step(None);
+        assert_eq!(runner.trace().len(), 3);
+        assert!(matches!(
+            runner.trace()[0],
+            TraceEvent::ActionExecuted { kind: ActionKind::Entry, .. }
+        ));
+    }
+
+    /// A state definition with a TransitionUsage whose trigger, guard, and effect
+    /// are owned child elements, as the parser emits them.
+    fn create_transition_usage_graph() -> (ModelGraph, ElementId, ElementId) {
+        let mut graph = ModelGraph::new();
+
+        let def = Element::new_with_kind(ElementKind::StateDefinition).with_name("Engine");
+        let def_id = graph.add_element(def);
+        for (name, initial) in [("start", true), ("running", false)] {
+            let state = Element::new_with_kind(ElementKind::StateUsage)
+                .with_name(name)
+                .with_owner(def_id.clone())
+                .with_prop("initial", initial);
+            graph.add_element(state);
+        }
+
+        // transition first start accept go if fuel > 0 do rpm = 800 then running;
+        let transition = Element::new_with_kind(ElementKind::TransitionUsage)
+            .with_owner(def_id.clone())
+            .with_prop("unresolved_source", "start")
+            .with_prop("unresolved_target", "running");
+        let transition_id = graph.add_element(transition);
+
+        let accept = Element::new_with_kind(ElementKind::AcceptActionUsage)
+            .with_owner(transition_id.clone());
+        let accept_id = graph.add_element(accept);
+        let payload = Element::new_with_kind(ElementKind::ReferenceUsage)
+            .with_name("go")
+            .with_owner(accept_id);
+        graph.add_element(payload);
+
+        let guard = Element::new_with_kind(ElementKind::Expression)
+            .with_owner(transition_id.clone())
+            .with_prop("body", "fuel > 0");
+        graph.add_element(guard);
+
+        let effect = Element::new_with_kind(ElementKind::ActionUsage)
+            .with_owner(transition_id.clone())
+            .with_prop("body", "rpm = 800");
+        graph.add_element(effect);
+
+        (graph, def_id, transition_id)
+    }
+
+    #[test]
+    fn compile_transition_usage_from_child_elements() {
+        let (graph, def_id, transition_id) = create_transition_usage_graph();
+        let transition = graph.get_element(&transition_id).unwrap();
+
+        let ir = StateMachineCompiler::compile_transition_usage(&graph, transition, &HashSet::new())
+            .unwrap();
+        assert_eq!(ir.from, "start");
+        assert_eq!(ir.to, "running");
+        assert_eq!(ir.event.as_deref(), Some("go"));
+        assert_eq!(ir.guard.as_deref(), Some("fuel > 0"));
+        match &ir.action {
+            Some(ActionIR::Structured { assignments, .. }) => {
+                assert_eq!(assignments[0].variable, "rpm");
+                assert_eq!(assignments[0].value.as_literal(), Some(800.0));
+            }
+            other => panic!("Expected structured effect, got {:?}", other),
+        }
+
+        // The same transition is picked up when compiling the state definition as a region
+        let region = StateMachineCompiler::state_def_to_region(&graph, &def_id, "engine").unwrap();
+        assert_eq!(region.transitions.len(), 1);
+        assert_eq!(region.transitions[0].event.as_deref(), Some("go"));
+    }
+
+    #[test]
+    fn compile_transition_usage_props_take_precedence_over_children() {
+        let (mut graph, _, transition_id) = create_transition_usage_graph();
+        graph
+            .get_element_mut(&transition_id)
+            .unwrap()
+            .set_prop("trigger", "ignite");
+
+        // A child classified through an explicit TransitionFeatureKind
+        let trigger = Element::new_with_kind(ElementKind::ReferenceUsage)
+            .with_owner(transition_id.clone())
+            .with_prop("kind", Value::Enum("trigger".to_string()))
+            .with_prop("payload", "other");
+        graph.add_element(trigger);
+
+        let transition = graph.get_element(&transition_id).unwrap();
+        let ir = StateMachineCompiler::compile_transition_usage(&graph, transition, &HashSet::new())
+            .unwrap();
+        assert_eq!(ir.event.as_deref(), Some("ignite"));
+        assert_eq!(ir.guard.as_deref(), Some("fuel > 0"));
+    }
+
+    #[test]
+    fn transition_feature_kind_classification() {
+        let kind_of = |kind: ElementKind| {
+            StateMachineCompiler::transition_feature_kind(&Element::new_with_kind(kind))
+        };
+        assert_eq!(kind_of(ElementKind::AcceptActionUsage), Some(TransitionFeatureKind::Trigger));
+        assert_eq!(kind_of(ElementKind::ConstraintUsage), Some(TransitionFeatureKind::Guard));
+        assert_eq!(kind_of(ElementKind::ActionUsage), Some(TransitionFeatureKind::Effect));
+        assert_eq!(kind_of(ElementKind::PartUsage), None);
+    }
+
+    fn send(event: &str) -> ActionIR {
+        ActionIR::structured(vec![], vec![event.to_string()])
+    }
+
+    #[test]
+    fn sent_events_drive_further_transitions() {
+        let ir = StateMachineIR::new("Chain", "idle")
+            .with_state(StateIR::new("idle"))
+            .with_state(StateIR::new("armed").with_entry_action(send("go")))
+            .with_state(StateIR::new("running"))
+            .with_state(StateIR::new("ignored"))
+            .with_transition(TransitionIR::new("idle", "armed").with_event("start").with_action(send("unknown")))
+            .with_transition(TransitionIR::new("armed", "running").with_event("go"))
+            .with_transition(TransitionIR::new("idle", "ignored").with_event("unknown"));
+        let mut runner = StateMachineRunner::new(ir).with_tracing();
+
+        let result = runner.step(Some("start"));
+
+        // The transition's 'unknown' is queued before the entry action's 'go'
+        assert_eq!(result.state, "running");
+        assert_eq!(
+            result.outputs,
+            vec!["action: send('unknown')", "entry: send('go')", "event 'go': armed -> running"]
+        );
+        assert!(result.diagnostics.is_empty());
+        assert!(runner
+            .trace()
+            .iter()
+            .any(|e| matches!(e, TraceEvent::EventIgnored { event, .. } if event == "unknown")));
+    }
+
+    #[test]
+    fn self_sending_loop_stops_at_iteration_limit() {
+        let ir = StateMachineIR::new("Loop", "idle")
+            .with_state(StateIR::new("idle"))
+            .with_state(StateIR::new("spinning"))
+            .with_transition(TransitionIR::new("idle", "spinning").with_event("start").with_action(send("spin")))
+            .with_transition(TransitionIR::new("spinning", "spinning").with_event("spin").with_action(send("spin")));
+        let mut runner = StateMachineRunner::new(ir).with_max_internal_iterations(3);
+        assert_eq!(runner.max_internal_iterations(), 3);
+
+        let result = runner.step(Some("start"));
+
+        let fired = result.outputs.iter().filter(|o| o.starts_with("event 'spin'")).count();
+        assert_eq!(fired, 3);
+        assert_eq!(result.outputs.last().map(String::as_str), Some(parallel::ITERATION_LIMIT_OUTPUT));
+        assert_eq!(result.diagnostics.len(), 1);
+        assert!(result.diagnostics[0].message.contains("limit of 3"));
+
+        // The queue was dropped, so the next step starts clean
+        let result = runner.step(None);
+        assert!(result.diagnostics.is_empty());
+        assert!(result.outputs.is_empty());
+    }
+
+    #[test]
+    fn entry_exit_and_transition_actions_update_context() {
+        let ir = StateMachineIR::new("Counter", "idle")
+            .with_state(StateIR::new("idle").with_exit_action(parse_action("exits += 1")))
+            .with_state(StateIR::new("counting").with_entry_action(parse_action("count += 1")))
+            .with_state(StateIR::new("done").final_state())
+            .with_transition(
+                TransitionIR::new("idle", "counting")
+                    .with_event("go")
+                    .with_action(parse_action("limit = 2")),
+            )
+            .with_transition(TransitionIR::new("counting", "counting").with_event("go"))
+            .with_transition(TransitionIR::new("counting", "done").with_guard("count >= limit").with_priority(1));
+        let mut runner = StateMachineRunner::new(ir);
+
+        runner.step(Some("go"));
+        assert_eq!(runner.get_context("exits"), Some(ContextValue::Number(1.0)));
+        assert_eq!(runner.get_context("limit"), Some(ContextValue::Number(2.0)));
+        assert_eq!(runner.get_context("count"), Some(ContextValue::Number(1.0)));
+
+        // Re-entering counting increments again, after which the guard holds
+        assert!(!runner.step(None).completed);
+        runner.step(Some("go"));
+        assert_eq!(runner.get_context("count"), Some(ContextValue::Number(2.0)));
+        assert!(runner.step(None).completed);
+    }
 }
@@ -2,9 +2,27 @@
 
 use std::collections::{HashMap, VecDeque};
 use sysml_core::ModelGraph;
-use sysml_run::{ActionIR, AssignmentOp, ParallelStepResult, StateMachineIR};
+use sysml_run::{
+    ActionIR, ActionKind, ParallelStepResult, StateMachineIR, TraceEvent, TraceLog, TransitionIR,
+    DEFAULT_TRACE_CAPACITY,
+};
 use sysml_span::Diagnostic;
 
+use crate::guard::{evaluate_guard, ContextValue};
+use crate::TIME_CONTEXT_VARIABLE;
+
+/// Default limit on internal events processed per external event.
+pub const DEFAULT_MAX_INTERNAL_ITERATIONS: usize = 100;
+
+/// Output recorded when the internal event limit stops processing.
+pub const ITERATION_LIMIT_OUTPUT: &str = "internal event loop limit reached";
+
+/// Context variable holding the time spent in the current state of a region,
+/// e.g. `relay.dwell`. See [`ParallelStateMachineRunner::advance_time`].
+pub fn dwell_variable(region: &str) -> String {
+    format!("{}.dwell", region)
+}
+
 /// Runner for parallel/composite state machines with multiple concurrent regions.
 ///
 /// Supports:
@@ -12,6 +30,25 @@ use sysml_span::Diagnostic;
 /// - Internal event queue for cross-region communication via `send()`
 /// - Timing context variables (e.g., `t_ms`) updated by structured actions
 /// - Run-to-completion semantics (drains event queue before returning)
+/// - Shallow history for regions, restored by [`ParallelStateMachineRunner::reenter_region`]
+///   and by transitions that target their own region (see below)
+/// - Timed (`after`) transitions: see [`ParallelStateMachineRunner::advance_time`]
+///
+/// # Processing order
+///
+/// Event processing is deterministic. An external event is delivered to all
+/// regions in declaration order. Events sent by the resulting actions are
+/// queued and processed in FIFO order, each again delivered to the regions in
+/// declaration order. At most `max_internal_iterations` internal events are
+/// processed per external event (see [`DEFAULT_MAX_INTERNAL_ITERATIONS`]), so
+/// regions that keep sending events to each other cannot loop forever.
+///
+/// # Re-entering a region
+///
+/// A transition whose target is the name of its own region, such as
+/// `TransitionIR::new("open", "relay")` in region `relay`, exits the current
+/// state and re-enters the region. A region with `history` resumes at the
+/// state it was in; any other region starts over at its initial state.
 pub struct ParallelStateMachineRunner {
     ir: StateMachineIR,
     /// Current state of each region (region name -> state name).
@@ -22,6 +59,52 @@ pub struct ParallelStateMachineRunner {
     context: HashMap<String, f64>,
     /// Whether execution has completed.
     completed: bool,
+    /// Maximum number of internal events processed per external event.
+    max_internal_iterations: usize,
+    /// Last active state of each exited region (region name -> state name).
+    history: HashMap<String, String>,
+    /// Structured execution trace, empty unless tracing is enabled.
+    trace: TraceLog,
+}
+
+/// The execution state of a [`ParallelStateMachineRunner`], captured by
+/// [`ParallelStateMachineRunner::snapshot`].
+///
+/// Holds the state of every active region, the context variables (including
+/// dwell times), the remembered history states and the completion flag. The
+/// IR, configuration and trace are not part of the snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParallelRunnerSnapshot {
+    region_states: HashMap<String, String>,
+    context: HashMap<String, f64>,
+    history: HashMap<String, String>,
+    completed: bool,
+}
+
+impl ParallelRunnerSnapshot {
+    /// Get the state of each active region (region name -> state name).
+    pub fn region_states(&self) -> &HashMap<String, String> {
+        &self.region_states
+    }
+
+    /// Get the context variables.
+    pub fn context(&self) -> &HashMap<String, f64> {
+        &self.context
+    }
+
+    /// Check whether the runner had completed.
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+}
+
+/// A transition selected in a region, with the actions it runs.
+struct PendingTransition {
+    region: String,
+    exit_action: Option<ActionIR>,
+    transition_action: Option<ActionIR>,
+    new_state: String,
+    entry_action: Option<ActionIR>,
 }
 
 impl ParallelStateMachineRunner {
@@ -40,6 +123,9 @@ impl ParallelStateMachineRunner {
             event_queue: VecDeque::new(),
             context: HashMap::new(),
             completed: false,
+            max_internal_iterations: DEFAULT_MAX_INTERNAL_ITERATIONS,
+            history: HashMap::new(),
+            trace: TraceLog::new(0),
         }
     }
 
@@ -58,6 +144,50 @@ impl ParallelStateMachineRunner {
         self
     }
 
+    /// Set the maximum number of internal events processed per external event.
+    pub fn with_max_internal_iterations(mut self, max: usize) -> Self {
+        self.max_internal_iterations = max;
+        self
+    }
+
+    /// Get the maximum number of internal events processed per external event.
+    pub fn max_internal_iterations(&self) -> usize {
+        self.max_internal_iterations
+    }
+
+    /// Record an execution trace of up to [`DEFAULT_TRACE_CAPACITY`] events.
+    pub fn with_tracing(self) -> Self {
+        self.with_trace_capacity(DEFAULT_TRACE_CAPACITY)
+    }
+
+    /// Set the maximum number of trace events kept (0 disables tracing).
+    pub fn with_trace_capacity(mut self, capacity: usize) -> Self {
+        self.trace.set_capacity(capacity);
+        self
+    }
+
+    /// Get the execution trace recorded by `send` and region exit/re-entry, oldest first.
+    ///
+    /// Tracing is off by default; see [`with_tracing`](Self::with_tracing).
+    /// Action details are prefixed with the region name, e.g. `[relay] t += 20`.
+    /// The trace is kept across `reset`; use [`clear_trace`](Self::clear_trace)
+    /// to discard it.
+    pub fn trace(&self) -> &[TraceEvent] {
+        self.trace.events()
+    }
+
+    /// Discard the recorded execution trace.
+    pub fn clear_trace(&mut self) {
+        self.trace.clear();
+    }
+
+    /// Remove and return the recorded execution trace, oldest first.
+    ///
+    /// Useful for collecting the trace of one step at a time.
+    pub fn take_trace(&mut self) -> Vec<TraceEvent> {
+        self.trace.take()
+    }
+
     /// Set a single context variable.
     pub fn set_context(&mut self, name: impl Into<String>, value: f64) {
         self.context.insert(name.into(), value);
@@ -97,9 +227,126 @@ impl ParallelStateMachineRunner {
         }
         self.event_queue.clear();
         self.context.clear();
+        self.history.clear();
         self.completed = false;
     }
 
+    /// Capture the runner's execution state, e.g. to explore several event
+    /// sequences from a common prefix.
+    pub fn snapshot(&self) -> ParallelRunnerSnapshot {
+        ParallelRunnerSnapshot {
+            region_states: self.region_states.clone(),
+            context: self.context.clone(),
+            history: self.history.clone(),
+            completed: self.completed,
+        }
+    }
+
+    /// Return to a state captured by [`snapshot`](Self::snapshot).
+    ///
+    /// The snapshot should come from a runner for the same IR. The trace is
+    /// left as it is.
+    pub fn restore(&mut self, snapshot: ParallelRunnerSnapshot) {
+        self.region_states = snapshot.region_states;
+        self.context = snapshot.context;
+        self.history = snapshot.history;
+        self.completed = snapshot.completed;
+        self.event_queue.clear();
+    }
+
+    /// Check whether a region is currently active (not exited).
+    pub fn is_region_active(&self, region: &str) -> bool {
+        self.region_states.contains_key(region)
+    }
+
+    /// Get the state a region will resume at when re-entered, if remembered.
+    ///
+    /// Only regions with `history` enabled remember their last active state.
+    pub fn history_state(&self, region: &str) -> Option<&str> {
+        self.history.get(region).map(|s| s.as_str())
+    }
+
+    /// Exit a region, running the exit action of its current state.
+    ///
+    /// An exited region ignores events until [`reenter_region`](Self::reenter_region)
+    /// is called. If the region has `history` enabled, its current state is
+    /// remembered. Exiting an unknown or already exited region does nothing.
+    pub fn exit_region(&mut self, region_name: &str) -> ParallelStepResult {
+        let mut result = ParallelStepResult::new();
+
+        if let Some(state_name) = self.region_states.remove(region_name) {
+            let region = self.ir.find_region(region_name);
+
+            if let Some(action) = region
+                .and_then(|r| r.find_state(&state_name))
+                .and_then(|s| s.exit_action.clone())
+            {
+                // Events sent while leaving the region are discarded with it
+                let (outputs, _) = Self::execute_action(&mut self.context, &action);
+                Self::trace_action(&mut self.trace, ActionKind::Exit, region_name, &outputs);
+                for output in outputs {
+                    result
+                        .outputs
+                        .push(format!("[{}] exit: {}", region_name, output));
+                }
+            }
+
+            if region.map(|r| r.history).unwrap_or(false) {
+                self.history.insert(region_name.to_string(), state_name);
+            }
+            self.context.remove(&dwell_variable(region_name));
+        }
+
+        result.region_states = self.region_states.clone();
+        result.context = self.context.clone();
+        result
+    }
+
+    /// Re-enter a previously exited region and execute until stable.
+    ///
+    /// With `history` enabled the region resumes at its remembered state,
+    /// otherwise it starts over at its initial state. The entry action of that
+    /// state runs, and events it sends are processed like internal events.
+    /// Re-entering an unknown or already active region does nothing.
+    pub fn reenter_region(&mut self, region_name: &str) -> ParallelStepResult {
+        if self.is_region_active(region_name) {
+            return self.execute_until_stable();
+        }
+        let region = match self.ir.find_region(region_name) {
+            Some(r) => r,
+            None => return self.execute_until_stable(),
+        };
+
+        let state_name = match self.history.remove(region_name) {
+            Some(remembered) if region.history => remembered,
+            _ => region.initial.clone(),
+        };
+
+        let entry_action = region
+            .find_state(&state_name)
+            .and_then(|s| s.entry_action.clone());
+        self.region_states
+            .insert(region_name.to_string(), state_name);
+        self.context.insert(dwell_variable(region_name), 0.0);
+
+        let mut outputs = Vec::new();
+        if let Some(action) = entry_action {
+            let (action_outputs, events) = Self::execute_action(&mut self.context, &action);
+            Self::trace_action(&mut self.trace, ActionKind::Entry, region_name, &action_outputs);
+            outputs.extend(
+                action_outputs
+                    .into_iter()
+                    .map(|o| format!("[{}] entry: {}", region_name, o)),
+            );
+            self.event_queue.extend(events);
+        }
+
+        let mut result = self.execute_until_stable();
+        outputs.append(&mut result.outputs);
+        result.outputs = outputs;
+        result
+    }
+
     /// Send an external event and execute until stable.
     ///
     /// This is the primary way to interact with the state machine.
@@ -110,12 +357,131 @@ impl ParallelStateMachineRunner {
         self.execute_until_stable()
     }
 
+    /// Advance simulated time by `dt`, firing every `after` transition that
+    /// becomes due, and execute until stable.
+    ///
+    /// Time accumulates in the [`dwell_variable`] of every active region and in
+    /// the [`TIME_CONTEXT_VARIABLE`] context variable, so guards can refer to
+    /// both; a region's dwell time restarts whenever it changes state. Pending
+    /// timed transitions fire in order of remaining time (regions in declaration
+    /// order on ties), each once its guard holds at the moment it becomes due.
+    /// Events sent by a timed transition's actions are processed, like those of
+    /// [`send`](Self::send), before the next timed transition is considered, and
+    /// time left over keeps running in the new states. At most
+    /// `max_internal_iterations` timed transitions fire per call.
+    pub fn advance_time(&mut self, dt: f64) -> ParallelStepResult {
+        let mut outputs = Vec::new();
+        let mut internal_events = Vec::new();
+        let mut iteration_limit_reached = false;
+        let mut remaining = dt.max(0.0);
+        let mut fired = 0;
+
+        while !self.completed {
+            // Pending transitions of every active region, in declaration order
+            let mut pending: Vec<(f64, String, TransitionIR)> = Vec::new();
+            for region in &self.ir.regions {
+                if let Some(state) = self.region_states.get(&region.name) {
+                    let dwell = self.get_context(&dwell_variable(&region.name)).unwrap_or(0.0);
+                    for transition in region.timed_transitions(state) {
+                        let wait = (transition.after.unwrap_or(0.0) - dwell).max(0.0);
+                        pending.push((wait, region.name.clone(), transition.clone()));
+                    }
+                }
+            }
+            // Stable, so earlier regions win ties
+            pending.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut waited = 0.0;
+            let mut due = None;
+            for (wait, region, transition) in pending {
+                if wait > remaining {
+                    break;
+                }
+                self.tick(wait - waited);
+                waited = wait;
+                if self.guard_holds(&transition, &mut outputs) {
+                    due = Some((region, transition));
+                    break;
+                }
+            }
+            remaining -= waited;
+
+            let (region, transition) = match due {
+                Some(due) => due,
+                None => break,
+            };
+            if fired == self.max_internal_iterations {
+                outputs.push(ITERATION_LIMIT_OUTPUT.to_string());
+                iteration_limit_reached = true;
+                break;
+            }
+            fired += 1;
+
+            let pending = self.pending_transition(&region, &transition);
+            let mut events = Vec::new();
+            self.take_transition(pending, &mut outputs, &mut events);
+            internal_events.extend(events.iter().cloned());
+            self.event_queue.extend(events);
+
+            let mut step = self.execute_until_stable();
+            outputs.append(&mut step.outputs);
+            internal_events.append(&mut step.internal_events);
+            iteration_limit_reached |= step.iteration_limit_reached;
+        }
+        self.tick(remaining);
+
+        let mut result = self.execute_until_stable();
+        result.outputs = outputs;
+        result.internal_events = internal_events;
+        result.iteration_limit_reached = iteration_limit_reached;
+        result
+    }
+
+    /// Let `dt` pass in the active regions.
+    fn tick(&mut self, dt: f64) {
+        let active: Vec<String> = self.region_states.keys().cloned().collect();
+        for region in active {
+            *self.context.entry(dwell_variable(&region)).or_insert(0.0) += dt;
+        }
+        *self.context.entry(TIME_CONTEXT_VARIABLE.to_string()).or_insert(0.0) += dt;
+    }
+
+    /// Check a transition's guard against the context, reporting evaluation
+    /// errors as outputs.
+    fn guard_holds(&self, transition: &TransitionIR, outputs: &mut Vec<String>) -> bool {
+        let guard = match &transition.guard {
+            Some(guard) => guard,
+            None => return true,
+        };
+        match evaluate_guard(guard, |name| self.get_context(name).map(ContextValue::Number)) {
+            Ok(enabled) => enabled,
+            Err(e) => {
+                outputs.push(format!("guard error: {}", e));
+                false
+            }
+        }
+    }
+
     /// Execute until the event queue is empty (run-to-completion).
+    ///
+    /// The first queued event is the external one; every event after it is an
+    /// internal event and counts towards `max_internal_iterations`. When the
+    /// limit is exceeded, the remaining queue is dropped and the result is marked
+    /// with `iteration_limit_reached`.
     fn execute_until_stable(&mut self) -> ParallelStepResult {
         let mut result = ParallelStepResult::new();
         let mut all_internal_events = Vec::new();
+        let mut processed = 0usize;
 
         while let Some(event) = self.event_queue.pop_front() {
+            if processed > self.max_internal_iterations {
+                result.outputs.push(ITERATION_LIMIT_OUTPUT.to_string());
+                result.iteration_limit_reached = true;
+                self.event_queue.clear();
+                break;
+            }
+            processed += 1;
+
             let step_result = self.process_event(&event);
             result.outputs.extend(step_result.outputs);
             all_internal_events.extend(step_result.internal_events.clone());
@@ -141,87 +507,106 @@ impl ParallelStateMachineRunner {
         let mut result = ParallelStepResult::new();
         let mut generated_events = Vec::new();
 
-        // Collect all transitions that need to be executed
-        // (region_name, exit_action, transition_action, new_state, entry_action)
-        let mut transitions_to_execute: Vec<(
-            String,
-            Option<ActionIR>,
-            Option<ActionIR>,
-            String,
-            Option<ActionIR>,
-        )> = Vec::new();
-
         // First pass: find all matching transitions (immutable borrow of self.ir)
+        let mut transitions_to_execute = Vec::new();
         for region in &self.ir.regions {
-            if let Some(current_state) = self.region_states.get(&region.name).cloned() {
-                let transitions = region.transitions_from(&current_state);
-                let matching = transitions.iter().find(|t| t.matches(Some(event)));
-
-                if let Some(transition) = matching {
-                    let exit_action = region
-                        .find_state(&current_state)
-                        .and_then(|s| s.exit_action.clone());
-                    let transition_action = transition.action.clone();
-                    let new_state = transition.to.clone();
-                    let entry_action = region
-                        .find_state(&transition.to)
-                        .and_then(|s| s.entry_action.clone());
-
-                    transitions_to_execute.push((
-                        region.name.clone(),
-                        exit_action,
-                        transition_action,
-                        new_state,
-                        entry_action,
-                    ));
+            if let Some(current_state) = self.region_states.get(&region.name) {
+                let transitions = region.matching_transitions(current_state, Some(event));
+                if let Some(transition) = transitions.first() {
+                    transitions_to_execute.push(self.pending_transition(&region.name, transition));
                 }
             }
         }
 
+        if transitions_to_execute.is_empty() {
+            let states: Vec<String> = self
+                .ir
+                .regions
+                .iter()
+                .filter_map(|r| {
+                    self.region_states
+                        .get(&r.name)
+                        .map(|state| format!("{}: {}", r.name, state))
+                })
+                .collect();
+            self.trace.push(TraceEvent::EventIgnored {
+                event: event.to_string(),
+                state: states.join(", "),
+            });
+        }
+
         // Second pass: execute all transitions (mutable borrow of self)
-        for (region_name, exit_action, transition_action, new_state, entry_action) in
-            transitions_to_execute
-        {
-            // Execute exit action
-            if let Some(action) = exit_action {
-                let (outputs, events) = Self::execute_action(&mut self.context, &action);
-                for output in outputs {
-                    result
-                        .outputs
-                        .push(format!("[{}] exit: {}", region_name, output));
-                }
-                generated_events.extend(events);
-            }
+        for transition in transitions_to_execute {
+            self.take_transition(transition, &mut result.outputs, &mut generated_events);
+        }
 
-            // Execute transition action
-            if let Some(action) = transition_action {
-                let (outputs, events) = Self::execute_action(&mut self.context, &action);
-                for output in outputs {
-                    result
-                        .outputs
-                        .push(format!("[{}] action: {}", region_name, output));
+        result.region_states = self.region_states.clone();
+        result.internal_events = generated_events;
+        result
+    }
+
+    /// Look up the actions a region runs when taking a transition.
+    ///
+    /// A transition whose target is the name of its own region (and not one of
+    /// its states) leaves the region and re-enters it: with `history` enabled
+    /// the region resumes at the state it left, otherwise at its initial state.
+    fn pending_transition(&self, region_name: &str, transition: &TransitionIR) -> PendingTransition {
+        let region = self.ir.find_region(region_name);
+        let find_state = |name: &str| region.and_then(|r| r.find_state(name));
+        let new_state = match region {
+            Some(region) if transition.to == region.name && region.find_state(&transition.to).is_none() => {
+                if region.history {
+                    transition.from.clone()
+                } else {
+                    region.initial.clone()
                 }
-                generated_events.extend(events);
             }
+            _ => transition.to.clone(),
+        };
+        PendingTransition {
+            region: region_name.to_string(),
+            exit_action: find_state(&transition.from).and_then(|s| s.exit_action.clone()),
+            transition_action: transition.action.clone(),
+            entry_action: find_state(&new_state).and_then(|s| s.entry_action.clone()),
+            new_state,
+        }
+    }
 
-            // Move to new state
-            self.region_states.insert(region_name.clone(), new_state);
+    /// Take a transition: run the exit, transition, and entry actions and move
+    /// the region to its new state, restarting its dwell time.
+    fn take_transition(&mut self, transition: PendingTransition, outputs: &mut Vec<String>, events: &mut Vec<String>) {
+        let region_name = transition.region;
+        let old_state = self.region_states.get(&region_name).cloned().unwrap_or_default();
+        self.trace.push(TraceEvent::RegionChanged {
+            region: region_name.clone(),
+            from: old_state,
+            to: transition.new_state.clone(),
+        });
 
-            // Execute entry action
-            if let Some(action) = entry_action {
-                let (outputs, events) = Self::execute_action(&mut self.context, &action);
-                for output in outputs {
-                    result
-                        .outputs
-                        .push(format!("[{}] entry: {}", region_name, output));
+        let mut run = |runner: &mut Self, action: Option<ActionIR>, kind: ActionKind, label: &str| {
+            if let Some(action) = action {
+                let (action_outputs, action_events) = Self::execute_action(&mut runner.context, &action);
+                Self::trace_action(&mut runner.trace, kind, &region_name, &action_outputs);
+                for output in action_outputs {
+                    outputs.push(format!("[{}] {}: {}", region_name, label, output));
                 }
-                generated_events.extend(events);
+                events.extend(action_events);
             }
-        }
+        };
 
-        result.region_states = self.region_states.clone();
-        result.internal_events = generated_events;
-        result
+        run(self, transition.exit_action, ActionKind::Exit, "exit");
+        run(self, transition.transition_action, ActionKind::Transition, "action");
+        self.region_states.insert(region_name.clone(), transition.new_state);
+        self.context.insert(dwell_variable(&region_name), 0.0);
+        run(self, transition.entry_action, ActionKind::Entry, "entry");
+    }
+
+    /// Record an executed action in the trace.
+    fn trace_action(trace: &mut TraceLog, kind: ActionKind, region: &str, outputs: &[String]) {
+        trace.push(TraceEvent::ActionExecuted {
+            kind,
+            detail: format!("[{}] {}", region, outputs.join("; ")),
+        });
     }
 
     /// Execute an action and return any outputs and generated events.
@@ -240,22 +625,9 @@ impl ParallelStateMachineRunner {
                 // Execute assignments
                 for assign in assignments {
                     let current = context.get(&assign.variable).copied().unwrap_or(0.0);
-                    let new_value = match assign.operator {
-                        AssignmentOp::Set => assign.value,
-                        AssignmentOp::Add => current + assign.value,
-                        AssignmentOp::Subtract => current - assign.value,
-                    };
+                    let new_value = assign.apply(current, |name| context.get(name).copied());
                     context.insert(assign.variable.clone(), new_value);
-                    outputs.push(format!(
-                        "{} {} {}",
-                        assign.variable,
-                        match assign.operator {
-                            AssignmentOp::Set => "=",
-                            AssignmentOp::Add => "+=",
-                            AssignmentOp::Subtract => "-=",
-                        },
-                        assign.value
-                    ));
+                    outputs.push(format!("{} {} {}", assign.variable, assign.operator.symbol(), assign.value));
                 }
 
                 // Collect send events
@@ -290,6 +662,36 @@ mod tests {
     use super::*;
     use sysml_run::{AssignmentIR, RegionIR, StateIR, TransitionIR};
 
+    /// Two regions that answer each other's events forever.
+    fn create_ping_pong_ir() -> StateMachineIR {
+        let send = |event: &str| ActionIR::structured(vec![], vec![event.to_string()]);
+        StateMachineIR::parallel("PingPong")
+            .with_region(
+                RegionIR::new("ping", "idle")
+                    .with_state(StateIR::new("idle"))
+                    .with_state(StateIR::new("playing"))
+                    .with_transition(
+                        TransitionIR::new("idle", "playing")
+                            .with_event("serve")
+                            .with_action(send("ping")),
+                    )
+                    .with_transition(
+                        TransitionIR::new("playing", "playing")
+                            .with_event("pong")
+                            .with_action(send("ping")),
+                    ),
+            )
+            .with_region(
+                RegionIR::new("pong", "ready")
+                    .with_state(StateIR::new("ready"))
+                    .with_transition(
+                        TransitionIR::new("ready", "ready")
+                            .with_event("ping")
+                            .with_action(send("pong")),
+                    ),
+            )
+    }
+
     fn create_simple_parallel_ir() -> StateMachineIR {
         StateMachineIR::parallel("TestParallel")
             .with_region(
@@ -382,6 +784,31 @@ mod tests {
         assert_eq!(runner.t_ms(), 10.0);
     }
 
+    #[test]
+    fn variable_assignments_read_from_context() {
+        let ir = StateMachineIR::parallel("CopyTest").with_region(
+            RegionIR::new("main", "idle")
+                .with_state(StateIR::new("idle"))
+                .with_state(StateIR::new("saved"))
+                .with_transition(
+                    TransitionIR::new("idle", "saved")
+                        .with_event("save")
+                        .with_action(crate::parse_action("backup = t_ms; total += t_ms; t_ms = 0")),
+                ),
+        );
+
+        let mut runner = ParallelStateMachineRunner::new(ir);
+        runner.set_context("t_ms", 40.0);
+        runner.set_context("total", 2.0);
+
+        let result = runner.send("save");
+
+        assert_eq!(runner.get_context("backup"), Some(40.0));
+        assert_eq!(runner.get_context("total"), Some(42.0));
+        assert_eq!(runner.get_context("t_ms"), Some(0.0));
+        assert!(result.outputs.iter().any(|o| o.contains("backup = t_ms")));
+    }
+
     #[test]
     fn execute_until_stable() {
         // Chain of events: start -> A sends triggerB -> B sends triggerC -> C transitions
@@ -460,4 +887,316 @@ mod tests {
         assert_eq!(runner.get_context("relay_time"), Some(20.0));
         assert_eq!(runner.get_context("unknown"), None);
     }
+
+    #[test]
+    fn ping_pong_stops_at_iteration_limit() {
+        let mut runner = ParallelStateMachineRunner::new(create_ping_pong_ir())
+            .with_max_internal_iterations(10);
+
+        let result = runner.send("serve");
+
+        assert!(result.iteration_limit_reached);
+        assert_eq!(result.outputs.last().map(String::as_str), Some(ITERATION_LIMIT_OUTPUT));
+        // Ten internal events were delivered, alternating ping/pong
+        let delivered: Vec<_> = result
+            .outputs
+            .iter()
+            .filter(|o| o.contains("send("))
+            .collect();
+        assert_eq!(delivered.len(), 11);
+        assert_eq!(runner.region_state("ping"), Some("playing"));
+
+        // The dropped queue does not leak into the next step
+        let result = runner.send("unrelated");
+        assert!(!result.iteration_limit_reached);
+        assert!(result.outputs.is_empty());
+    }
+
+    #[test]
+    fn ping_pong_uses_default_limit() {
+        let mut runner = ParallelStateMachineRunner::new(create_ping_pong_ir());
+        assert_eq!(runner.max_internal_iterations(), DEFAULT_MAX_INTERNAL_ITERATIONS);
+
+        let result = runner.send("serve");
+        assert!(result.iteration_limit_reached);
+        assert_eq!(
+            result.internal_events.len(),
+            DEFAULT_MAX_INTERNAL_ITERATIONS + 1
+        );
+    }
+
+    #[test]
+    fn chained_events_below_limit_not_marked() {
+        let mut runner = ParallelStateMachineRunner::new(create_simple_parallel_ir());
+        let result = runner.send("go");
+        assert!(!result.iteration_limit_reached);
+    }
+
+    #[test]
+    fn events_delivered_in_declaration_then_fifo_order() {
+        let ir = create_ping_pong_ir();
+        let mut runner = ParallelStateMachineRunner::new(ir).with_max_internal_iterations(2);
+
+        let result = runner.send("serve");
+        let outputs: Vec<_> = result.outputs.iter().map(String::as_str).collect();
+        assert_eq!(
+            outputs,
+            vec![
+                "[ping] action: send('ping')",
+                "[pong] action: send('pong')",
+                "[ping] action: send('ping')",
+                ITERATION_LIMIT_OUTPUT,
+            ]
+        );
+    }
+
+    /// Grid and relay regions; the relay is switched manually and loses power on grid failure.
+    fn create_failover_ir(relay_history: bool) -> StateMachineIR {
+        let relay = RegionIR::new("relay", "closed")
+            .with_history(relay_history)
+            .with_state(StateIR::new("closed"))
+            .with_state(
+                StateIR::new("open").with_entry_action(ActionIR::structured(
+                    vec![AssignmentIR::add("openings", 1.0)],
+                    vec![],
+                )),
+            )
+            .with_transition(TransitionIR::new("closed", "open").with_event("open"))
+            .with_transition(TransitionIR::new("open", "closed").with_event("close"));
+
+        StateMachineIR::parallel("Failover")
+            .with_region(
+                RegionIR::new("grid", "energized")
+                    .with_state(StateIR::new("energized"))
+                    .with_state(StateIR::new("deEnergized"))
+                    .with_transition(TransitionIR::new("energized", "deEnergized").with_event("gridFail"))
+                    .with_transition(TransitionIR::new("deEnergized", "energized").with_event("gridRestore")),
+            )
+            .with_region(relay)
+    }
+
+    #[test]
+    fn history_region_restores_previous_state() {
+        let mut runner = ParallelStateMachineRunner::new(create_failover_ir(true));
+
+        runner.send("open");
+        assert_eq!(runner.region_state("relay"), Some("open"));
+
+        // Failover: the grid fails and the relay region is exited
+        runner.send("gridFail");
+        runner.exit_region("relay");
+        assert!(!runner.is_region_active("relay"));
+        assert_eq!(runner.history_state("relay"), Some("open"));
+
+        // Events are ignored while the region is exited
+        runner.send("close");
+        assert_eq!(runner.region_state("relay"), None);
+
+        // Restore: the relay returns to its previous position
+        runner.send("gridRestore");
+        let result = runner.reenter_region("relay");
+        assert_eq!(runner.region_state("grid"), Some("energized"));
+        assert_eq!(result.region_states.get("relay"), Some(&"open".to_string()));
+        assert_eq!(result.outputs, vec!["[relay] entry: openings += 1".to_string()]);
+        assert_eq!(runner.get_context("openings"), Some(2.0));
+        assert_eq!(runner.history_state("relay"), None);
+    }
+
+    #[test]
+    fn region_without_history_reenters_initial_state() {
+        let mut runner = ParallelStateMachineRunner::new(create_failover_ir(false));
+
+        runner.send("open");
+        runner.send("gridFail");
+        runner.exit_region("relay");
+        assert_eq!(runner.history_state("relay"), None);
+
+        runner.send("gridRestore");
+        runner.reenter_region("relay");
+        assert_eq!(runner.region_state("relay"), Some("closed"));
+    }
+
+    #[test]
+    fn transition_to_own_region_restores_history() {
+        for (history, expected) in [(true, "open"), (false, "closed")] {
+            let mut ir = create_failover_ir(history);
+            ir.regions[1] = ir.regions[1]
+                .clone()
+                .with_transition(TransitionIR::new("open", "relay").with_event("trip"));
+            let mut runner = ParallelStateMachineRunner::new(ir);
+
+            runner.send("open");
+            let result = runner.send("trip");
+            assert_eq!(result.region_states.get("relay"), Some(&expected.to_string()));
+            assert_eq!(runner.history_state("relay"), None);
+            // Re-entering the open state runs its entry action again
+            let openings = if history { 2.0 } else { 1.0 };
+            assert_eq!(runner.get_context("openings"), Some(openings));
+        }
+    }
+
+    #[test]
+    fn reset_clears_region_history() {
+        let mut runner = ParallelStateMachineRunner::new(create_failover_ir(true));
+
+        runner.send("open");
+        runner.exit_region("relay");
+        runner.reset();
+
+        assert!(runner.is_region_active("relay"));
+        assert_eq!(runner.history_state("relay"), None);
+        assert_eq!(runner.region_state("relay"), Some("closed"));
+    }
+
+    #[test]
+    fn parallel_trace_distinguishes_region_changes_and_ignored_events() {
+        let ir = StateMachineIR::parallel("Traced").with_region(
+            RegionIR::new("relay", "closed")
+                .with_state(StateIR::new("closed"))
+                .with_state(StateIR::new("open"))
+                .with_transition(
+                    TransitionIR::new("closed", "open")
+                        .with_event("trip")
+                        .with_action(ActionIR::structured(vec![AssignmentIR::add("t", 20.0)], vec![])),
+                ),
+        );
+        let mut runner = ParallelStateMachineRunner::new(ir).with_tracing();
+
+        runner.send("noise");
+        runner.send("trip");
+
+        assert_eq!(
+            runner.trace(),
+            &[
+                TraceEvent::EventIgnored {
+                    event: "noise".to_string(),
+                    state: "relay: closed".to_string(),
+                },
+                TraceEvent::RegionChanged {
+                    region: "relay".to_string(),
+                    from: "closed".to_string(),
+                    to: "open".to_string(),
+                },
+                TraceEvent::ActionExecuted {
+                    kind: ActionKind::Transition,
+                    detail: "[relay] t += 20".to_string(),
+                },
+            ]
+        );
+
+        runner.clear_trace();
+        assert!(runner.trace().is_empty());
+    }
+
+    #[test]
+    fn parallel_trace_is_capped() {
+        let mut runner =
+            ParallelStateMachineRunner::new(create_ping_pong_ir()).with_trace_capacity(5);
+
+        runner.send("serve");
+        assert_eq!(runner.trace().len(), 5);
+    }
+
+    /// The relay opens 20 time units after the grid fails, unless it is
+    /// restored first or the relay is held.
+    fn create_timed_relay_ir() -> StateMachineIR {
+        StateMachineIR::parallel("TimedFailover")
+            .with_region(
+                RegionIR::new("grid", "energized")
+                    .with_state(StateIR::new("energized"))
+                    .with_state(StateIR::new("deEnergized"))
+                    .with_transition(TransitionIR::new("energized", "deEnergized").with_event("gridFail"))
+                    .with_transition(TransitionIR::new("deEnergized", "energized").with_event("gridRestore")),
+            )
+            .with_region(
+                RegionIR::new("relay", "closed")
+                    .with_state(StateIR::new("closed"))
+                    .with_state(StateIR::new("arming"))
+                    .with_state(StateIR::new("open").with_entry_action(ActionIR::structured(
+                        vec![AssignmentIR::add("openings", 1.0)],
+                        vec!["relayOpened".to_string()],
+                    )))
+                    .with_transition(TransitionIR::new("closed", "arming").with_event("gridFail"))
+                    .with_transition(TransitionIR::new("arming", "closed").with_event("gridRestore"))
+                    .with_transition(TransitionIR::new("arming", "open").with_after(20.0).with_guard("hold == 0")),
+            )
+    }
+
+    #[test]
+    fn after_transition_fires_without_event() {
+        let mut runner = ParallelStateMachineRunner::new(create_timed_relay_ir());
+        runner.set_context("hold", 0.0);
+        runner.send("gridFail");
+
+        let result = runner.advance_time(15.0);
+        assert_eq!(result.region_states.get("relay"), Some(&"arming".to_string()));
+        assert_eq!(runner.get_context(&dwell_variable("relay")), Some(15.0));
+
+        let result = runner.advance_time(10.0);
+        assert_eq!(result.region_states.get("relay"), Some(&"open".to_string()));
+        assert_eq!(result.region_states.get("grid"), Some(&"deEnergized".to_string()));
+        assert_eq!(result.internal_events, vec!["relayOpened".to_string()]);
+        assert!(result.outputs.contains(&"[relay] entry: openings += 1".to_string()));
+        // The 5 units left over are spent in the new state
+        assert_eq!(runner.get_context(&dwell_variable("relay")), Some(5.0));
+        assert_eq!(runner.get_context(&dwell_variable("grid")), Some(25.0));
+        assert_eq!(runner.get_context(crate::TIME_CONTEXT_VARIABLE), Some(25.0));
+        assert_eq!(runner.get_context("openings"), Some(1.0));
+    }
+
+    #[test]
+    fn events_interleave_with_advance_time() {
+        let mut runner = ParallelStateMachineRunner::new(create_timed_relay_ir());
+        runner.set_context("hold", 0.0);
+
+        // Time passing in `closed` does not count towards `arming`
+        runner.advance_time(100.0);
+        runner.send("gridFail");
+        runner.advance_time(15.0);
+        let result = runner.send("gridRestore");
+        assert_eq!(result.region_states.get("relay"), Some(&"closed".to_string()));
+
+        // Leaving `arming` restarted its dwell time
+        runner.send("gridFail");
+        assert_eq!(runner.advance_time(15.0).region_states.get("relay"), Some(&"arming".to_string()));
+        assert_eq!(runner.advance_time(5.0).region_states.get("relay"), Some(&"open".to_string()));
+        assert_eq!(runner.get_context("openings"), Some(1.0));
+    }
+
+    #[test]
+    fn after_transition_waits_for_its_guard() {
+        let mut runner = ParallelStateMachineRunner::new(create_timed_relay_ir());
+        runner.set_context("hold", 1.0);
+        runner.send("gridFail");
+
+        assert_eq!(runner.advance_time(30.0).region_states.get("relay"), Some(&"arming".to_string()));
+        runner.set_context("hold", 0.0);
+        assert_eq!(runner.advance_time(0.0).region_states.get("relay"), Some(&"open".to_string()));
+    }
+
+    #[test]
+    fn restore_replays_from_a_common_prefix() {
+        let mut runner = ParallelStateMachineRunner::new(create_failover_ir(true));
+        runner.send("open");
+        runner.exit_region("relay");
+        let prefix = runner.snapshot();
+        assert_eq!(prefix.region_states().get("grid"), Some(&"energized".to_string()));
+
+        runner.send("gridFail");
+        runner.reenter_region("relay");
+        assert_eq!(runner.region_state("grid"), Some("deEnergized"));
+        assert_eq!(runner.get_context("openings"), Some(2.0));
+
+        runner.restore(prefix.clone());
+        assert_eq!(runner.region_state("grid"), Some("energized"));
+        assert_eq!(runner.get_context("openings"), Some(1.0));
+        assert!(!runner.is_region_active("relay"));
+        // History is part of the snapshot, so the relay resumes where it left off
+        assert_eq!(runner.history_state("relay"), Some("open"));
+        runner.reenter_region("relay");
+        assert_eq!(runner.region_state("relay"), Some("open"));
+
+        runner.restore(prefix.clone());
+        assert_eq!(runner.snapshot(), prefix);
+    }
 }
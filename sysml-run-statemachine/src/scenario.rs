@@ -0,0 +1,509 @@
+//! Scripted scenarios for checking state machine behavior without writing
+//! Rust per test.
+//!
+//! A [`Scenario`] is a list of steps: send an event, advance time, or assert
+//! the state of the machine (or of one region) or the value of a context
+//! variable. Scenarios deserialize with serde, e.g. from JSON:
+//!
+//! ```json
+//! {
+//!   "name": "grid failover",
+//!   "steps": [
+//!     { "send": "gridFail" },
+//!     { "expect_state": { "region": "relay", "state": "open" } },
+//!     { "advance": 5 },
+//!     { "expect_context": { "variable": "t", "op": ">=", "value": 25 } }
+//!   ]
+//! }
+//! ```
+//!
+//! or the equivalent TOML, loaded with [`Scenario::from_toml`]:
+//!
+//! ```toml
+//! name = "grid failover"
+//!
+//! [[steps]]
+//! send = "gridFail"
+//!
+//! [[steps]]
+//! expect_state = { region = "relay", state = "open" }
+//! ```
+//!
+//! [`ScenarioRunner::run`] plays the steps against a fresh runner and records
+//! a [`ScenarioReport`].
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sysml_run::{Runner, StateMachineIR};
+
+use crate::{ParallelStateMachineRunner, StateMachineRunner};
+
+/// Tolerance for `==` assertions on context variables.
+const EQUALITY_TOLERANCE: f64 = 1e-9;
+
+/// A named list of steps to play against a state machine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    /// Scenario name, shown in the report.
+    pub name: String,
+    /// Optional free-form description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Steps, played in order.
+    pub steps: Vec<ScenarioStep>,
+}
+
+impl Scenario {
+    /// Create an empty scenario.
+    pub fn new(name: impl Into<String>) -> Self {
+        Scenario {
+            name: name.into(),
+            description: None,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Add a step.
+    pub fn with_step(mut self, step: ScenarioStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Parse a scenario from JSON.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Parse a scenario from TOML.
+    pub fn from_toml(toml: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml)
+    }
+}
+
+/// One step of a [`Scenario`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScenarioStep {
+    /// Send an event and run to completion.
+    Send(String),
+    /// Advance simulated time, firing due `after` transitions.
+    Advance(f64),
+    /// Assert that a state is active, in the given region of a parallel
+    /// machine or anywhere in the active state stack otherwise.
+    ExpectState {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        region: Option<String>,
+        state: String,
+    },
+    /// Assert the value of a context variable.
+    ExpectContext {
+        variable: String,
+        op: Comparison,
+        value: f64,
+    },
+}
+
+impl ScenarioStep {
+    /// Check whether the step is an assertion.
+    pub fn is_assertion(&self) -> bool {
+        matches!(self, ScenarioStep::ExpectState { .. } | ScenarioStep::ExpectContext { .. })
+    }
+}
+
+impl fmt::Display for ScenarioStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioStep::Send(event) => write!(f, "send '{}'", event),
+            ScenarioStep::Advance(dt) => write!(f, "advance {}", dt),
+            ScenarioStep::ExpectState { region: Some(region), state } => {
+                write!(f, "expect '{}' in {}", state, region)
+            }
+            ScenarioStep::ExpectState { region: None, state } => write!(f, "expect state '{}'", state),
+            ScenarioStep::ExpectContext { variable, op, value } => write!(f, "expect {} {} {}", variable, op, value),
+        }
+    }
+}
+
+/// Comparison used by [`ScenarioStep::ExpectContext`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparison {
+    #[serde(rename = "==")]
+    Equal,
+    #[serde(rename = ">=")]
+    AtLeast,
+    #[serde(rename = "<=")]
+    AtMost,
+}
+
+impl Comparison {
+    /// Compare an actual value with the expected one.
+    pub fn holds(self, actual: f64, expected: f64) -> bool {
+        match self {
+            Comparison::Equal => (actual - expected).abs() <= EQUALITY_TOLERANCE,
+            Comparison::AtLeast => actual >= expected,
+            Comparison::AtMost => actual <= expected,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Comparison::Equal => "==",
+            Comparison::AtLeast => ">=",
+            Comparison::AtMost => "<=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// What happened in one step of a scenario.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepReport {
+    /// 1-based step number.
+    pub index: usize,
+    /// The step that was played.
+    pub step: ScenarioStep,
+    /// The machine's state after the step: the current state, or
+    /// `region: state` pairs for a parallel machine.
+    pub state: String,
+    /// Outputs of the actions run by the step.
+    pub outputs: Vec<String>,
+    /// Why an assertion failed; `None` for passed assertions and other steps.
+    pub failure: Option<String>,
+}
+
+impl StepReport {
+    /// Check whether the step did not fail.
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// The result of running a scenario.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioReport {
+    /// Name of the scenario.
+    pub scenario: String,
+    /// One report per step, in order.
+    pub steps: Vec<StepReport>,
+}
+
+impl ScenarioReport {
+    /// Check whether every assertion passed.
+    pub fn is_pass(&self) -> bool {
+        self.steps.iter().all(StepReport::passed)
+    }
+
+    /// The steps whose assertion failed.
+    pub fn failures(&self) -> impl Iterator<Item = &StepReport> {
+        self.steps.iter().filter(|s| !s.passed())
+    }
+}
+
+impl fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let assertions = self.steps.iter().filter(|s| s.step.is_assertion()).count();
+        let failed = self.failures().count();
+        if failed == 0 {
+            writeln!(f, "Scenario '{}': PASS ({} steps, {} assertions)", self.scenario, self.steps.len(), assertions)?;
+        } else {
+            writeln!(f, "Scenario '{}': FAIL ({} of {} assertions failed)", self.scenario, failed, assertions)?;
+        }
+
+        for step in &self.steps {
+            match (&step.failure, step.step.is_assertion()) {
+                (Some(failure), _) => writeln!(f, "  {}. {}: FAILED, {}", step.index, step.step, failure)?,
+                (None, true) => writeln!(f, "  {}. {}: ok", step.index, step.step)?,
+                (None, false) => writeln!(f, "  {}. {} ({})", step.index, step.step, step.state)?,
+            }
+            for output in &step.outputs {
+                writeln!(f, "       {}", output)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Plays scenarios against state machines.
+pub struct ScenarioRunner;
+
+impl ScenarioRunner {
+    /// Run a scenario against a fresh runner for `ir`: a
+    /// [`ParallelStateMachineRunner`] for parallel machines, a
+    /// [`StateMachineRunner`] otherwise.
+    ///
+    /// Every step is played, so one report lists all failing assertions.
+    pub fn run(ir: StateMachineIR, scenario: &Scenario) -> ScenarioReport {
+        let regions: Vec<String> = ir.regions.iter().map(|r| r.name.clone()).collect();
+        let mut driver = if ir.is_parallel() {
+            Driver::Parallel(ParallelStateMachineRunner::new(ir), regions)
+        } else {
+            Driver::Single(StateMachineRunner::new(ir))
+        };
+
+        let steps = scenario
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                let (outputs, failure) = match step {
+                    ScenarioStep::Send(event) => (driver.send(event), None),
+                    ScenarioStep::Advance(dt) => (driver.advance(*dt), None),
+                    ScenarioStep::ExpectState { region, state } => {
+                        (Vec::new(), driver.check_state(region.as_deref(), state))
+                    }
+                    ScenarioStep::ExpectContext { variable, op, value } => {
+                        let failure = match driver.context(variable) {
+                            Some(actual) if op.holds(actual, *value) => None,
+                            Some(actual) => Some(format!("{} is {}", variable, actual)),
+                            None => Some(format!("{} is not set", variable)),
+                        };
+                        (Vec::new(), failure)
+                    }
+                };
+                StepReport {
+                    index: i + 1,
+                    step: step.clone(),
+                    state: driver.state(),
+                    outputs,
+                    failure,
+                }
+            })
+            .collect();
+
+        ScenarioReport {
+            scenario: scenario.name.clone(),
+            steps,
+        }
+    }
+}
+
+/// The runner a scenario drives.
+enum Driver {
+    Single(StateMachineRunner),
+    /// The runner and its region names, in declaration order.
+    Parallel(ParallelStateMachineRunner, Vec<String>),
+}
+
+impl Driver {
+    fn send(&mut self, event: &str) -> Vec<String> {
+        match self {
+            Driver::Single(runner) => runner.step(Some(event)).outputs,
+            Driver::Parallel(runner, _) => runner.send(event).outputs,
+        }
+    }
+
+    fn advance(&mut self, dt: f64) -> Vec<String> {
+        match self {
+            Driver::Single(runner) => runner.advance_time(dt).outputs,
+            Driver::Parallel(runner, _) => runner.advance_time(dt).outputs,
+        }
+    }
+
+    fn context(&self, variable: &str) -> Option<f64> {
+        match self {
+            Driver::Single(runner) => runner.get_context(variable).and_then(|v| v.as_number()),
+            Driver::Parallel(runner, _) => runner.get_context(variable),
+        }
+    }
+
+    fn state(&self) -> String {
+        match self {
+            Driver::Single(runner) => runner.current_state().to_string(),
+            Driver::Parallel(runner, regions) => regions
+                .iter()
+                .map(|region| format!("{}: {}", region, runner.region_state(region).unwrap_or("(exited)")))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    /// Check an `expect_state` assertion, returning the failure message.
+    fn check_state(&self, region: Option<&str>, state: &str) -> Option<String> {
+        match (self, region) {
+            (Driver::Single(runner), None) => {
+                let active = runner.active_states();
+                (!active.contains(&state)).then(|| format!("active states are {}", active.join(" > ")))
+            }
+            (Driver::Single(_), Some(region)) => {
+                Some(format!("machine is not parallel, so it has no region '{}'", region))
+            }
+            (Driver::Parallel(runner, _), Some(region)) => match runner.region_state(region) {
+                Some(actual) if actual == state => None,
+                Some(actual) => Some(format!("{} is in '{}'", region, actual)),
+                None => Some(format!("region '{}' is not active", region)),
+            },
+            (Driver::Parallel(runner, regions), None) => {
+                let found = regions.iter().any(|r| runner.region_state(r) == Some(state));
+                (!found).then(|| format!("no region is in '{}'", state))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysml_run::{ActionIR, AssignmentIR, RegionIR, StateIR, TransitionIR};
+
+    fn traffic_light_ir() -> StateMachineIR {
+        StateMachineIR::new("TrafficLight", "Red")
+            .with_state(StateIR::new("Red"))
+            .with_state(StateIR::new("Green"))
+            .with_state(StateIR::new("Yellow"))
+            .with_transition(TransitionIR::new("Red", "Green").with_event("timer"))
+            .with_transition(TransitionIR::new("Green", "Yellow").with_event("timer"))
+            .with_transition(TransitionIR::new("Yellow", "Red").with_after(3.0))
+    }
+
+    fn hybrid_system_ir() -> StateMachineIR {
+        StateMachineIR::parallel("HybridSystem")
+            .with_region(
+                RegionIR::new("grid", "energized")
+                    .with_state(StateIR::new("energized"))
+                    .with_state(StateIR::new("deEnergized"))
+                    .with_transition(TransitionIR::new("energized", "deEnergized").with_event("gridFail"))
+                    .with_transition(TransitionIR::new("deEnergized", "energized").with_event("gridRestore")),
+            )
+            .with_region(
+                RegionIR::new("relay", "closed")
+                    .with_state(StateIR::new("closed"))
+                    .with_state(StateIR::new("open"))
+                    .with_transition(
+                        TransitionIR::new("closed", "open")
+                            .with_event("gridFail")
+                            .with_action_ir(ActionIR::structured(vec![AssignmentIR::add("t", 20.0)], vec![])),
+                    )
+                    .with_transition(TransitionIR::new("open", "closed").with_event("gridRestore")),
+            )
+    }
+
+    #[test]
+    fn traffic_light_scenario_passes() {
+        let scenario = Scenario::from_json(include_str!("../fixtures/traffic_light_scenario.json")).unwrap();
+        let report = ScenarioRunner::run(traffic_light_ir(), &scenario);
+
+        assert!(report.is_pass(), "{}", report);
+        assert_eq!(report.steps.len(), scenario.steps.len());
+        assert_eq!(report.steps[2].state, "Yellow");
+        let text = report.to_string();
+        assert!(text.starts_with("Scenario 'traffic light cycle': PASS (8 steps, 4 assertions)\n"));
+        assert!(text.contains("  4. advance 2 (Yellow)\n"));
+        assert!(text.contains("  6. expect state 'Red': ok\n"));
+    }
+
+    #[test]
+    fn hybrid_system_failure_is_reported_with_step_number() {
+        let scenario = Scenario::from_json(include_str!("../fixtures/hybrid_system_scenario.json")).unwrap();
+        let report = ScenarioRunner::run(hybrid_system_ir(), &scenario);
+
+        assert!(!report.is_pass());
+        let failures: Vec<_> = report.failures().collect();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].index, 5);
+        assert_eq!(failures[0].failure.as_deref(), Some("t is 25"));
+
+        assert_eq!(report.steps[0].state, "grid: deEnergized, relay: open");
+        assert_eq!(report.steps[0].outputs, vec!["[relay] action: t += 20".to_string()]);
+        let text = report.to_string();
+        assert!(text.starts_with("Scenario 'grid failover': FAIL (1 of 5 assertions failed)\n"));
+        assert!(text.contains("  1. send 'gridFail' (grid: deEnergized, relay: open)\n       [relay] action: t += 20\n"));
+        assert!(text.contains("  5. expect t == 30: FAILED, t is 25\n"));
+    }
+
+    #[test]
+    fn state_assertions_report_what_was_found() {
+        let scenario = Scenario::new("misc")
+            .with_step(ScenarioStep::ExpectState {
+                region: Some("relay".into()),
+                state: "closed".into(),
+            })
+            .with_step(ScenarioStep::ExpectState {
+                region: None,
+                state: "open".into(),
+            })
+            .with_step(ScenarioStep::ExpectContext {
+                variable: "missing".into(),
+                op: Comparison::AtMost,
+                value: 1.0,
+            });
+        let report = ScenarioRunner::run(hybrid_system_ir(), &scenario);
+        let failures: Vec<_> = report.failures().map(|s| (s.index, s.failure.clone().unwrap())).collect();
+        assert_eq!(
+            failures,
+            vec![(2, "no region is in 'open'".to_string()), (3, "missing is not set".to_string())]
+        );
+
+        let report = ScenarioRunner::run(traffic_light_ir(), &scenario);
+        assert_eq!(
+            report.steps[0].failure.as_deref(),
+            Some("machine is not parallel, so it has no region 'relay'")
+        );
+        assert_eq!(report.steps[1].failure.as_deref(), Some("active states are Red"));
+    }
+
+    #[test]
+    fn steps_serialize_in_the_documented_shape() {
+        let json = r#"{"name":"n","steps":[{"send":"go"},{"advance":1.5},{"expect_state":{"state":"A"}},{"expect_context":{"variable":"x","op":"<=","value":2.0}}]}"#;
+        let scenario = Scenario::from_json(json).unwrap();
+        assert_eq!(scenario.steps[1], ScenarioStep::Advance(1.5));
+        assert_eq!(serde_json::to_string(&scenario).unwrap(), json);
+    }
+
+    #[test]
+    fn toml_scenario_matches_json() {
+        let toml = r#"
+name = "grid failover"
+
+[[steps]]
+send = "gridFail"
+
+[[steps]]
+expect_state = { region = "relay", state = "open" }
+
+[[steps]]
+advance = 5
+
+[[steps]]
+expect_context = { variable = "t", op = ">=", value = 25 }
+"#;
+        let json = r#"{"name":"grid failover","steps":[{"send":"gridFail"},{"expect_state":{"region":"relay","state":"open"}},{"advance":5},{"expect_context":{"variable":"t","op":">=","value":25}}]}"#;
+        assert_eq!(Scenario::from_toml(toml).unwrap(), Scenario::from_json(json).unwrap());
+        assert!(Scenario::from_toml("name = \"n\"\n[[steps]]\njump = 1\n").is_err());
+    }
+
+    #[test]
+    fn steps_display_as_in_the_report() {
+        let steps = [
+            (ScenarioStep::Send("gridFail".to_string()), "send 'gridFail'"),
+            (ScenarioStep::Advance(2.5), "advance 2.5"),
+            (
+                ScenarioStep::ExpectState {
+                    region: Some("relay".to_string()),
+                    state: "open".to_string(),
+                },
+                "expect 'open' in relay",
+            ),
+            (
+                ScenarioStep::ExpectState {
+                    region: None,
+                    state: "Red".to_string(),
+                },
+                "expect state 'Red'",
+            ),
+            (
+                ScenarioStep::ExpectContext {
+                    variable: "t".to_string(),
+                    op: Comparison::AtLeast,
+                    value: 25.0,
+                },
+                "expect t >= 25",
+            ),
+        ];
+        for (step, expected) in steps {
+            assert_eq!(step.to_string(), expected);
+        }
+    }
+}
@@ -0,0 +1,893 @@
+//! Export of state machine IR to W3C SCXML, and import back into the IR.
+//!
+//! [`to_scxml`] writes a document that mirrors the IR so that the same machine can be run by an
+//! SCXML interpreter and the traces compared with [`StateMachineRunner`]:
+//! - states become `<state>` (or `<final>`), nested for composite states
+//! - a parallel machine becomes one `<parallel>` with a `<state>` per region
+//! - transitions are written in selection order (see [`order_by_priority`]),
+//!   since SCXML picks the first enabled transition in document order
+//! - assignments become `<assign>` and sent events `<send>` to the internal
+//!   queue, as the runner processes them before a step returns
+//! - simple (unstructured) actions become `<log>`, and do actions, which have
+//!   no SCXML counterpart, a comment
+//! - timed (`after`) transitions become a delayed `<send>` on entry that is
+//!   cancelled on exit, with a transition on the sent event
+//!
+//! Every variable an action assigns is declared in the `<datamodel>` with an
+//! initial value of 0. State names are used as ids as they are, so they need
+//! to be unique across the machine, including across regions.
+//!
+//! [`StateMachineRunner`]: crate::StateMachineRunner
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use sysml_run::{
+    order_by_priority, ActionIR, AssignmentIR, AssignmentOp, BinaryOp, RegionIR, StateIR, StateMachineIR, TransitionIR,
+    ValueRef,
+};
+use sysml_span::Diagnostic;
+
+use crate::parse_action;
+
+/// Export a state machine to an SCXML document.
+///
+/// # Examples
+///
+/// ```
+/// use sysml_run::{StateIR, StateMachineIR, TransitionIR};
+/// use sysml_run_statemachine::to_scxml;
+///
+/// let ir = StateMachineIR::new("Door", "Closed")
+///     .with_state(StateIR::new("Closed"))
+///     .with_state(StateIR::new("Open"))
+///     .with_transition(TransitionIR::new("Closed", "Open").with_event("open"));
+///
+/// let scxml = to_scxml(&ir);
+/// assert!(scxml.contains(r#"<scxml xmlns="http://www.w3.org/2005/07/scxml" version="1.0" name="Door" initial="Closed">"#));
+/// assert!(scxml.contains(r#"<transition event="open" target="Open"/>"#));
+/// ```
+pub fn to_scxml(ir: &StateMachineIR) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let initial = if ir.is_parallel() { &ir.name } else { &ir.initial };
+    writeln!(
+        out,
+        "<scxml xmlns=\"http://www.w3.org/2005/07/scxml\" version=\"1.0\" name=\"{}\" initial=\"{}\">",
+        escape_xml(&ir.name),
+        escape_xml(initial)
+    )
+    .unwrap();
+
+    let variables = assigned_variables(ir);
+    if !variables.is_empty() {
+        out.push_str("  <datamodel>\n");
+        for variable in variables {
+            writeln!(out, "    <data id=\"{}\" expr=\"0\"/>", escape_xml(variable)).unwrap();
+        }
+        out.push_str("  </datamodel>\n");
+    }
+
+    if ir.is_parallel() {
+        writeln!(out, "  <parallel id=\"{}\">", escape_xml(&ir.name)).unwrap();
+        for region in &ir.regions {
+            writeln!(
+                out,
+                "    <state id=\"{}\" initial=\"{}\">",
+                escape_xml(&region.name),
+                escape_xml(&region.initial)
+            )
+            .unwrap();
+            if region.history {
+                writeln!(
+                    out,
+                    "      <history id=\"{}.history\" type=\"shallow\">",
+                    escape_xml(&region.name)
+                )
+                .unwrap();
+                writeln!(out, "        <transition target=\"{}\"/>", escape_xml(&region.initial)).unwrap();
+                out.push_str("      </history>\n");
+            }
+            for state in &region.states {
+                write_state(&mut out, state, &region.transitions, 3);
+            }
+            out.push_str("    </state>\n");
+        }
+        out.push_str("  </parallel>\n");
+    } else {
+        for state in &ir.states {
+            write_state(&mut out, state, &ir.transitions, 1);
+        }
+    }
+
+    out.push_str("</scxml>\n");
+    out
+}
+
+/// Write a state, its actions, its outgoing transitions and its substates.
+fn write_state(out: &mut String, state: &StateIR, transitions: &[TransitionIR], depth: usize) {
+    let indent = "  ".repeat(depth);
+    let id = escape_xml(&state.name);
+    let outgoing = order_by_priority(transitions.iter().filter(|t| t.from == state.name).collect());
+    let timed: Vec<&TransitionIR> = outgoing.iter().copied().filter(|t| t.is_timed()).collect();
+
+    let tag = if state.is_final { "final" } else { "state" };
+    let mut open = format!("{}<{} id=\"{}\"", indent, tag, id);
+    if state.is_composite() {
+        let initial = state.initial().map(|s| s.name.as_str()).unwrap_or_default();
+        write!(open, " initial=\"{}\"", escape_xml(initial)).unwrap();
+    }
+    let empty = state.entry_action.is_none()
+        && state.exit_action.is_none()
+        && state.do_action.is_none()
+        && outgoing.is_empty()
+        && state.substates.is_empty();
+    if empty {
+        writeln!(out, "{}/>", open).unwrap();
+        return;
+    }
+    writeln!(out, "{}>", open).unwrap();
+
+    if state.entry_action.is_some() || !timed.is_empty() {
+        writeln!(out, "{}  <onentry>", indent).unwrap();
+        if let Some(action) = &state.entry_action {
+            write_action(out, action, depth + 2);
+        }
+        for (i, transition) in timed.iter().enumerate() {
+            writeln!(
+                out,
+                "{}    <send id=\"{}\" event=\"{}\" delay=\"{}s\"/>",
+                indent,
+                timer_event(&id, i),
+                timer_event(&id, i),
+                transition.after.unwrap_or_default()
+            )
+            .unwrap();
+        }
+        writeln!(out, "{}  </onentry>", indent).unwrap();
+    }
+    if state.exit_action.is_some() || !timed.is_empty() {
+        writeln!(out, "{}  <onexit>", indent).unwrap();
+        if let Some(action) = &state.exit_action {
+            write_action(out, action, depth + 2);
+        }
+        for i in 0..timed.len() {
+            writeln!(out, "{}    <cancel sendid=\"{}\"/>", indent, timer_event(&id, i)).unwrap();
+        }
+        writeln!(out, "{}  </onexit>", indent).unwrap();
+    }
+    if let Some(action) = &state.do_action {
+        // `--` may not appear inside an XML comment
+        writeln!(out, "{}  <!-- do: {} -->", indent, action.to_string().replace("--", "- -")).unwrap();
+    }
+
+    for transition in &outgoing {
+        let mut open = format!("{}  <transition", indent);
+        let event = match timed.iter().position(|t| std::ptr::eq(*t, *transition)) {
+            Some(i) => Some(timer_event(&id, i)),
+            None => transition.event.as_deref().map(escape_xml),
+        };
+        if let Some(event) = event {
+            write!(open, " event=\"{}\"", event).unwrap();
+        }
+        if let Some(guard) = &transition.guard {
+            write!(open, " cond=\"{}\"", escape_xml(guard)).unwrap();
+        }
+        write!(open, " target=\"{}\"", escape_xml(&transition.to)).unwrap();
+        match &transition.action {
+            Some(action) => {
+                writeln!(out, "{}>", open).unwrap();
+                write_action(out, action, depth + 2);
+                writeln!(out, "{}  </transition>", indent).unwrap();
+            }
+            None => writeln!(out, "{}/>", open).unwrap(),
+        }
+    }
+
+    for substate in &state.substates {
+        write_state(out, substate, transitions, depth + 1);
+    }
+    writeln!(out, "{}</{}>", indent, tag).unwrap();
+}
+
+/// Write the executable content of an action.
+fn write_action(out: &mut String, action: &ActionIR, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match action {
+        ActionIR::Simple(text) => {
+            writeln!(out, "{}<log label=\"{}\"/>", indent, escape_xml(text)).unwrap();
+        }
+        ActionIR::Structured { assignments, sends } => {
+            for assign in assignments {
+                let expr = match assign.operator.binary_op() {
+                    Some(op) => {
+                        ValueRef::binary(op, ValueRef::variable(&assign.variable), assign.value.clone()).to_string()
+                    }
+                    None => assign.value.to_string(),
+                };
+                writeln!(
+                    out,
+                    "{}<assign location=\"{}\" expr=\"{}\"/>",
+                    indent,
+                    escape_xml(&assign.variable),
+                    escape_xml(&expr)
+                )
+                .unwrap();
+            }
+            for send in sends {
+                writeln!(out, "{}<send event=\"{}\" target=\"#_internal\"/>", indent, escape_xml(send)).unwrap();
+            }
+        }
+    }
+}
+
+/// The event (and send id) that fires the `index`th timed transition of a state.
+fn timer_event(state_id: &str, index: usize) -> String {
+    format!("after.{}.{}", state_id, index)
+}
+
+/// Variables assigned by any action in the machine, sorted.
+fn assigned_variables(ir: &StateMachineIR) -> BTreeSet<&str> {
+    fn from_state<'a>(state: &'a StateIR, actions: &mut Vec<&'a ActionIR>) {
+        actions.extend(&state.entry_action);
+        actions.extend(&state.exit_action);
+        actions.extend(&state.do_action);
+        for substate in &state.substates {
+            from_state(substate, actions);
+        }
+    }
+
+    let mut actions = Vec::new();
+    let states = ir.states.iter().chain(ir.regions.iter().flat_map(|r| &r.states));
+    for state in states {
+        from_state(state, &mut actions);
+    }
+    let transitions = ir.transitions.iter().chain(ir.regions.iter().flat_map(|r| &r.transitions));
+    actions.extend(transitions.filter_map(|t| t.action.as_ref()));
+
+    actions
+        .into_iter()
+        .flat_map(|action| match action {
+            ActionIR::Simple(_) => Vec::new(),
+            ActionIR::Structured { assignments, .. } => {
+                assignments.iter().map(|a| a.variable.as_str()).collect()
+            }
+        })
+        .collect()
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Import a state machine from an SCXML document, reporting skipped
+/// constructs as warnings alongside the IR.
+///
+/// `<state>`, `<final>` and `<transition>` map to the IR directly, with
+/// nested states becoming composite states; a document whose only top-level
+/// state is a `<parallel>` becomes a parallel machine with one region per
+/// child state. `<onentry>`, `<onexit>` and transition content map `<assign>`
+/// (`x = 1`, `x = y`, `x = x + 1`, `x = x - 1`) and `<send>`/`<raise>` to a
+/// structured [`ActionIR`], and an action made only of `<log label>`s to a
+/// simple one. The constructs [`to_scxml`] writes for timed transitions and do
+/// actions are read back, so export and import round-trip.
+///
+/// Where SCXML's document order would pick a different transition than the
+/// runner (an unguarded transition before a guarded one), the transitions of
+/// that state get descending priorities to keep the SCXML choice.
+///
+/// Anything else, such as `<script>`, `<invoke>`, data models, nested
+/// parallel states, or expressions that are not simple assignments, is
+/// skipped with a warning. Only malformed XML or a document without an
+/// `<scxml>` root or states fails the import.
+pub fn from_scxml(xml: &str) -> Result<(StateMachineIR, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let root = parse_xml(xml).map_err(|msg| vec![Diagnostic::error(format!("Invalid SCXML: {}", msg))])?;
+    if root.name != "scxml" {
+        return Err(vec![Diagnostic::error(format!(
+            "Invalid SCXML: root element is <{}>, not <scxml>",
+            root.name
+        ))]);
+    }
+
+    let mut importer = Importer::default();
+    let name = root.attr("name").unwrap_or("StateMachine").to_string();
+    let top: Vec<&XmlNode> = root.children.iter().filter(|n| n.is_state()).collect();
+
+    let ir = match top.as_slice() {
+        [parallel] if parallel.name == "parallel" => {
+            let mut ir = StateMachineIR::parallel(name);
+            for region in &parallel.children {
+                if region.name != "state" {
+                    importer.skip(region, parallel);
+                    continue;
+                }
+                let mut transitions = Vec::new();
+                let states: Vec<StateIR> = region
+                    .children
+                    .iter()
+                    .filter(|n| n.is_state())
+                    .filter_map(|n| importer.state(n, &mut transitions))
+                    .collect();
+                let id = region.attr("id").unwrap_or_default();
+                let initial = importer.initial_of(region).or_else(|| states.first().map(|s| s.name.clone()));
+                let mut region_ir = RegionIR::new(id, initial.unwrap_or_default());
+                for child in &region.children {
+                    match child.name.as_str() {
+                        "history" => region_ir = region_ir.with_history(true),
+                        "initial" | "state" | "final" => {}
+                        _ => importer.skip(child, region),
+                    }
+                }
+                region_ir.states = states;
+                region_ir.transitions = transitions;
+                ir = ir.with_region(region_ir);
+            }
+            ir
+        }
+        _ => {
+            let mut transitions = Vec::new();
+            let states: Vec<StateIR> = top.iter().filter_map(|n| importer.state(n, &mut transitions)).collect();
+            let initial = importer.initial_of(&root).or_else(|| states.first().map(|s| s.name.clone()));
+            let mut ir = StateMachineIR::new(name, initial.unwrap_or_default());
+            ir.states = states;
+            ir.transitions = transitions;
+            ir
+        }
+    };
+    for child in &root.children {
+        match child.name.as_str() {
+            "state" | "final" | "parallel" | "initial" => {}
+            // Only the declarations written by `to_scxml` carry nothing the IR lacks
+            "datamodel" if child.children.iter().all(|d| d.name == "data" && d.attr("expr") == Some("0")) => {}
+            _ => importer.skip(child, &root),
+        }
+    }
+
+    if ir.states.is_empty() && ir.regions.is_empty() {
+        importer.warnings.push(Diagnostic::error("SCXML document has no states"));
+        return Err(importer.warnings);
+    }
+    Ok((ir, importer.warnings))
+}
+
+/// An element of a parsed XML document.
+#[derive(Debug, Default)]
+struct XmlNode {
+    /// The local element name.
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<XmlNode>,
+    /// Comments directly inside the element.
+    comments: Vec<String>,
+}
+
+impl XmlNode {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    fn is_state(&self) -> bool {
+        matches!(self.name.as_str(), "state" | "final" | "parallel")
+    }
+
+    fn describe(&self) -> String {
+        match self.attr("id") {
+            Some(id) => format!("<{} id=\"{}\">", self.name, id),
+            None => format!("<{}>", self.name),
+        }
+    }
+}
+
+/// Parse an XML document into a tree, returning its root element.
+fn parse_xml(xml: &str) -> Result<XmlNode, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut stack: Vec<XmlNode> = vec![XmlNode::default()];
+
+    loop {
+        let event = reader.read_event().map_err(|e| e.to_string())?;
+        match event {
+            Event::Start(e) => stack.push(xml_node(&e)?),
+            Event::Empty(e) => {
+                let node = xml_node(&e)?;
+                stack.last_mut().unwrap().children.push(node);
+            }
+            Event::End(_) => {
+                let node = stack.pop().unwrap();
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => return Err("unexpected closing tag".to_string()),
+                }
+            }
+            Event::Comment(text) => {
+                let text = String::from_utf8_lossy(&text).trim().to_string();
+                stack.last_mut().unwrap().comments.push(text);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let mut document = stack.pop().unwrap();
+    if !stack.is_empty() {
+        return Err("unexpected end of document".to_string());
+    }
+    match document.children.len() {
+        1 => Ok(document.children.pop().unwrap()),
+        0 => Err("no root element".to_string()),
+        _ => Err("more than one root element".to_string()),
+    }
+}
+
+fn xml_node(e: &BytesStart<'_>) -> Result<XmlNode, String> {
+    let mut attrs = Vec::new();
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| e.to_string())?;
+        let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
+        let value = attr.unescape_value().map_err(|e| e.to_string())?;
+        attrs.push((key, value.to_string()));
+    }
+    Ok(XmlNode {
+        name: String::from_utf8_lossy(e.local_name().as_ref()).to_string(),
+        attrs,
+        ..XmlNode::default()
+    })
+}
+
+/// Converts SCXML elements to IR, collecting warnings.
+#[derive(Default)]
+struct Importer {
+    warnings: Vec<Diagnostic>,
+}
+
+impl Importer {
+    fn warn(&mut self, message: String) {
+        self.warnings.push(Diagnostic::warning(message));
+    }
+
+    fn skip(&mut self, node: &XmlNode, parent: &XmlNode) {
+        self.warn(format!(
+            "Unsupported SCXML element {} in {} was skipped",
+            node.describe(),
+            parent.describe()
+        ));
+    }
+
+    /// The initial child of a state or document, from an `initial` attribute
+    /// or an `<initial>` element.
+    fn initial_of(&mut self, node: &XmlNode) -> Option<String> {
+        if let Some(initial) = node.attr("initial") {
+            return Some(self.single_target(initial, node));
+        }
+        node.children
+            .iter()
+            .find(|n| n.name == "initial")
+            .and_then(|n| n.children.iter().find(|t| t.name == "transition"))
+            .and_then(|t| t.attr("target"))
+            .map(|target| target.to_string())
+            .map(|target| self.single_target(&target, node))
+    }
+
+    /// The first of a space-separated list of targets.
+    fn single_target(&mut self, targets: &str, node: &XmlNode) -> String {
+        let mut targets = targets.split_whitespace();
+        let first = targets.next().unwrap_or_default().to_string();
+        if targets.next().is_some() {
+            self.warn(format!("Only the first target of {} is used", node.describe()));
+        }
+        first
+    }
+
+    /// Convert a `<state>` or `<final>`, adding its transitions (and those of
+    /// its substates) to `transitions`.
+    fn state(&mut self, node: &XmlNode, transitions: &mut Vec<TransitionIR>) -> Option<StateIR> {
+        if node.name == "parallel" {
+            self.warn(format!("Nested {} is not supported and was skipped", node.describe()));
+            return None;
+        }
+        let id = match node.attr("id") {
+            Some(id) => id.to_string(),
+            None => {
+                self.warn(format!("<{}> without an id was skipped", node.name));
+                return None;
+            }
+        };
+
+        let mut state = StateIR::new(id.clone());
+        state.is_final = node.name == "final";
+        let mut timers: Vec<(String, f64)> = Vec::new();
+        let mut own_transitions = Vec::new();
+
+        for child in &node.children {
+            match child.name.as_str() {
+                "onentry" => state.entry_action = self.action(child, Some(&mut timers)),
+                "onexit" => state.exit_action = self.action(child, None),
+                "transition" => {
+                    if let Some(transition) = self.transition(child, &id) {
+                        own_transitions.push(transition);
+                    }
+                }
+                "state" | "final" | "parallel" => {
+                    if let Some(substate) = self.state(child, transitions) {
+                        state = state.with_substate(substate);
+                    }
+                }
+                "initial" => {}
+                _ => self.skip(child, node),
+            }
+        }
+        if state.is_composite() {
+            state.initial_substate = self.initial_of(node);
+        }
+        if let Some(text) = node.comments.iter().find_map(|c| c.strip_prefix("do:")) {
+            state.do_action = Some(parse_action(text));
+        }
+
+        // Transitions on a timer event sent on entry fire after its delay
+        for transition in &mut own_transitions {
+            let delay = transition
+                .event
+                .as_ref()
+                .and_then(|event| timers.iter().find(|(timer, _)| timer == event));
+            if let Some((_, delay)) = delay {
+                transition.after = Some(*delay);
+                transition.event = None;
+            }
+        }
+        keep_document_order(&mut own_transitions);
+        transitions.extend(own_transitions);
+
+        Some(state)
+    }
+
+    fn transition(&mut self, node: &XmlNode, from: &str) -> Option<TransitionIR> {
+        let target = match node.attr("target") {
+            Some(target) => self.single_target(target, node),
+            None => {
+                self.warn(format!("Targetless <transition> in state '{}' was skipped", from));
+                return None;
+            }
+        };
+        let mut transition = TransitionIR::new(from, target);
+        transition.event = node.attr("event").map(String::from);
+        transition.guard = node.attr("cond").map(String::from);
+        transition.action = self.action(node, None);
+        Some(transition)
+    }
+
+    /// Convert executable content. Delayed sends are timers rather than
+    /// actions; they are collected into `timers` where that is expected.
+    fn action(&mut self, node: &XmlNode, mut timers: Option<&mut Vec<(String, f64)>>) -> Option<ActionIR> {
+        let mut assignments = Vec::new();
+        let mut sends = Vec::new();
+        let mut logs = Vec::new();
+
+        for child in &node.children {
+            match child.name.as_str() {
+                "assign" => match (child.attr("location"), child.attr("expr")) {
+                    (Some(location), Some(expr)) => match parse_assign(location, expr) {
+                        Some(assign) => assignments.push(assign),
+                        None => self.warn(format!("Unsupported <assign> expression '{}' was skipped", expr)),
+                    },
+                    _ => self.skip(child, node),
+                },
+                "send" | "raise" => {
+                    let event = match child.attr("event") {
+                        Some(event) => event.to_string(),
+                        None => {
+                            self.skip(child, node);
+                            continue;
+                        }
+                    };
+                    match (child.attr("delay"), timers.as_deref_mut()) {
+                        (None, _) => sends.push(event),
+                        (Some(delay), Some(timers)) => match parse_delay(delay) {
+                            Some(seconds) => timers.push((event, seconds)),
+                            None => self.warn(format!("Unsupported <send> delay '{}' was skipped", delay)),
+                        },
+                        (Some(_), None) => self.skip(child, node),
+                    }
+                }
+                "log" => logs.extend(child.attr("label").or(child.attr("expr")).map(String::from)),
+                // Cancels only stop the timers read from <onentry>
+                "cancel" if node.name == "onexit" => {}
+                _ => self.skip(child, node),
+            }
+        }
+
+        if assignments.is_empty() && sends.is_empty() {
+            return (!logs.is_empty()).then(|| ActionIR::Simple(logs.join("; ")));
+        }
+        if !logs.is_empty() {
+            self.warn(format!("<log> mixed with assignments or sends in {} was skipped", node.describe()));
+        }
+        Some(ActionIR::structured(assignments, sends))
+    }
+}
+
+/// Parse `<assign location expr>` of the forms written by [`to_scxml`].
+///
+/// `x = x <op> value` becomes the compound assignment `x <op>= value`.
+fn parse_assign(location: &str, expr: &str) -> Option<AssignmentIR> {
+    let mut assign = match parse_action(&format!("{} = {}", location, expr.trim())) {
+        ActionIR::Structured { mut assignments, sends } if assignments.len() == 1 && sends.is_empty() => {
+            assignments.pop()?
+        }
+        _ => return None,
+    };
+    if let ValueRef::Binary { op, left, right } = &assign.value {
+        if left.as_variable() == Some(location) {
+            let operator = match op {
+                BinaryOp::Add => AssignmentOp::Add,
+                BinaryOp::Subtract => AssignmentOp::Subtract,
+                BinaryOp::Multiply => AssignmentOp::Multiply,
+                BinaryOp::Divide => AssignmentOp::Divide,
+            };
+            assign = AssignmentIR::new(location, operator, (**right).clone());
+        }
+    }
+    Some(assign)
+}
+
+/// Parse an SCXML delay (`30s`, `500ms`, or a bare number of seconds).
+fn parse_delay(delay: &str) -> Option<f64> {
+    let delay = delay.trim();
+    let (value, scale) = if let Some(ms) = delay.strip_suffix("ms") {
+        (ms, 0.001)
+    } else {
+        (delay.strip_suffix('s').unwrap_or(delay), 1.0)
+    };
+    value.trim().parse::<f64>().ok().map(|v| v * scale)
+}
+
+/// Give a state's transitions descending priorities when the runner's
+/// ordering (guarded before unguarded) would otherwise differ from document
+/// order.
+fn keep_document_order(transitions: &mut [TransitionIR]) {
+    let reordered = transitions
+        .windows(2)
+        .any(|pair| pair[0].guard.is_none() && pair[1].guard.is_some());
+    if reordered {
+        let count = transitions.len() as i32;
+        for (i, transition) in transitions.iter_mut().enumerate() {
+            transition.priority = count - 1 - i as i32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_machine_with_actions_and_guards() {
+        let ir = StateMachineIR::new("Counter", "Idle")
+            .with_state(
+                StateIR::new("Idle").with_entry_action(ActionIR::structured(
+                    vec![AssignmentIR::set("count", 0.0)],
+                    vec!["ready".to_string()],
+                )),
+            )
+            .with_state(StateIR::new("Busy").with_exit("cleanup()").with_do(ActionIR::structured(
+                vec![AssignmentIR::subtract("budget", 1.0)],
+                vec![],
+            )))
+            .with_state(StateIR::new("Done").final_state())
+            .with_transition(TransitionIR::new("Idle", "Busy").with_event("go"))
+            .with_transition(
+                TransitionIR::new("Idle", "Done")
+                    .with_event("go")
+                    .with_guard("count >= 3 && !busy")
+                    .with_action_ir(ActionIR::structured(vec![AssignmentIR::add("count", 1.0)], vec![])),
+            )
+            .with_transition(TransitionIR::new("Busy", "Idle").with_event("stop"));
+
+        let expected = r##"<?xml version="1.0" encoding="UTF-8"?>
+<scxml xmlns="http://www.w3.org/2005/07/scxml" version="1.0" name="Counter" initial="Idle">
+  <datamodel>
+    <data id="budget" expr="0"/>
+    <data id="count" expr="0"/>
+  </datamodel>
+  <state id="Idle">
+    <onentry>
+      <assign location="count" expr="0"/>
+      <send event="ready" target="#_internal"/>
+    </onentry>
+    <transition event="go" cond="count &gt;= 3 &amp;&amp; !busy" target="Done">
+      <assign location="count" expr="count + 1"/>
+    </transition>
+    <transition event="go" target="Busy"/>
+  </state>
+  <state id="Busy">
+    <onexit>
+      <log label="cleanup()"/>
+    </onexit>
+    <!-- do: budget -= 1 -->
+    <transition event="stop" target="Idle"/>
+  </state>
+  <final id="Done"/>
+</scxml>
+"##;
+        // The guarded transition is written first, as the runner prefers it
+        assert_eq!(to_scxml(&ir), expected);
+    }
+
+    #[test]
+    fn composite_and_timed_states() {
+        let ir = StateMachineIR::new("Oven", "On")
+            .with_state(
+                StateIR::new("On")
+                    .with_substate(StateIR::new("Heating"))
+                    .with_substate(StateIR::new("Holding"))
+                    .with_initial_substate("Heating"),
+            )
+            .with_state(StateIR::new("Off"))
+            .with_transition(TransitionIR::new("Heating", "Holding").with_after(30.0))
+            .with_transition(TransitionIR::new("On", "Off").with_event("power"));
+
+        let scxml = to_scxml(&ir);
+        assert!(scxml.contains(
+            "  <state id=\"On\" initial=\"Heating\">\n    <transition event=\"power\" target=\"Off\"/>\n    <state id=\"Heating\">\n"
+        ));
+        assert!(scxml.contains("        <send id=\"after.Heating.0\" event=\"after.Heating.0\" delay=\"30s\"/>\n"));
+        assert!(scxml.contains("        <cancel sendid=\"after.Heating.0\"/>\n"));
+        assert!(scxml.contains("      <transition event=\"after.Heating.0\" target=\"Holding\"/>\n"));
+        assert!(!scxml.contains("<datamodel>"));
+    }
+
+    #[test]
+    fn parallel_regions() {
+        let ir = StateMachineIR::parallel("Car")
+            .with_region(
+                RegionIR::new("engine", "Stopped")
+                    .with_state(StateIR::new("Stopped"))
+                    .with_state(StateIR::new("Running"))
+                    .with_transition(TransitionIR::new("Stopped", "Running").with_event("start")),
+            )
+            .with_region(RegionIR::new("radio", "Quiet").with_history(true).with_state(StateIR::new("Quiet")));
+
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<scxml xmlns="http://www.w3.org/2005/07/scxml" version="1.0" name="Car" initial="Car">
+  <parallel id="Car">
+    <state id="engine" initial="Stopped">
+      <state id="Stopped">
+        <transition event="start" target="Running"/>
+      </state>
+      <state id="Running"/>
+    </state>
+    <state id="radio" initial="Quiet">
+      <history id="radio.history" type="shallow">
+        <transition target="Quiet"/>
+      </history>
+      <state id="Quiet"/>
+    </state>
+  </parallel>
+</scxml>
+"#;
+        assert_eq!(to_scxml(&ir), expected);
+    }
+
+    #[test]
+    fn import_round_trips_export() {
+        let ir = StateMachineIR::new("Oven", "On")
+            .with_state(
+                StateIR::new("On")
+                    .with_entry_action(ActionIR::structured(vec![AssignmentIR::set("temp", 20.0)], vec![]))
+                    .with_substate(StateIR::new("Heating").with_do("heat()"))
+                    .with_substate(StateIR::new("Holding"))
+                    .with_initial_substate("Holding"),
+            )
+            .with_state(StateIR::new("Off").final_state())
+            .with_transition(TransitionIR::new("Heating", "Holding").with_after(0.5))
+            .with_transition(
+                TransitionIR::new("On", "Off")
+                    .with_event("power")
+                    .with_guard("temp < 100")
+                    .with_action_ir(ActionIR::structured(vec![], vec!["beep".to_string()])),
+            );
+
+        let scxml = to_scxml(&ir);
+        let (imported, warnings) = from_scxml(&scxml).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(imported.name, "Oven");
+        assert_eq!(imported.initial, "On");
+        assert_eq!(imported.find_state("On").unwrap().initial_substate.as_deref(), Some("Holding"));
+        assert!(imported.find_state("Off").unwrap().is_final);
+        assert_eq!(imported.find_state("Heating").unwrap().do_action, Some(ActionIR::simple("heat()")));
+
+        let timed = imported.timed_transitions("Heating");
+        assert_eq!(timed.len(), 1);
+        assert_eq!(timed[0].after, Some(0.5));
+        assert_eq!(timed[0].event, None);
+
+        assert_eq!(to_scxml(&imported), scxml);
+        let flat_and_parallel = [
+            StateMachineIR::new("M", "A")
+                .with_state(StateIR::new("A").with_exit_action(ActionIR::structured(
+                    vec![
+                        AssignmentIR::add("n", 1.0),
+                        AssignmentIR::subtract("m", ValueRef::variable("n")),
+                        AssignmentIR::divide("m", ValueRef::binary(BinaryOp::Add, ValueRef::variable("n"), 2.0)),
+                        AssignmentIR::set("k", ValueRef::binary(BinaryOp::Multiply, ValueRef::variable("k"), 2.0)),
+                    ],
+                    vec![],
+                )))
+                .with_state(StateIR::new("B"))
+                .with_transition(TransitionIR::new("A", "B").with_event("go")),
+            StateMachineIR::parallel("P").with_region(
+                RegionIR::new("r", "X")
+                    .with_history(true)
+                    .with_state(StateIR::new("X"))
+                    .with_state(StateIR::new("Y"))
+                    .with_transition(TransitionIR::new("X", "Y").with_event("e")),
+            ),
+        ];
+        for ir in flat_and_parallel {
+            let scxml = to_scxml(&ir);
+            assert_eq!(to_scxml(&from_scxml(&scxml).unwrap().0), scxml);
+        }
+    }
+
+    #[test]
+    fn import_keeps_document_order_of_transitions() {
+        let scxml = r#"<scxml xmlns="http://www.w3.org/2005/07/scxml" version="1.0" initial="A">
+  <state id="A">
+    <transition event="go" target="B"/>
+    <transition event="go" cond="x &gt; 1" target="C"/>
+  </state>
+  <state id="B"/>
+  <state id="C"/>
+</scxml>"#;
+        let (ir, _) = from_scxml(scxml).unwrap();
+        assert_eq!(ir.name, "StateMachine");
+        let chosen = order_by_priority(ir.matching_transitions("A", Some("go")));
+        assert_eq!(chosen[0].to, "B");
+        assert_eq!(chosen[1].guard.as_deref(), Some("x > 1"));
+    }
+
+    #[test]
+    fn import_reports_unsupported_constructs() {
+        let scxml = r#"<?xml version="1.0"?>
+<scxml xmlns="http://www.w3.org/2005/07/scxml" version="1.0" name="Lamp">
+  <datamodel><data id="level" expr="3"/></datamodel>
+  <script>level = level * 2;</script>
+  <state id="Off">
+    <invoke type="http://www.w3.org/TR/scxml/" src="child.scxml"/>
+    <transition event="on" target="On">
+      <assign location="level" expr="Math.max(level, 2)"/>
+    </transition>
+    <transition event="noop"/>
+  </state>
+  <state id="On">
+    <onentry><log label="lit"/></onentry>
+  </state>
+</scxml>"#;
+        let (ir, warnings) = from_scxml(scxml).unwrap();
+        assert_eq!(ir.initial, "Off");
+        assert_eq!(ir.states.len(), 2);
+        assert_eq!(ir.transitions.len(), 1);
+        assert_eq!(ir.transitions[0].action, None);
+        assert_eq!(ir.find_state("On").unwrap().entry_action, Some(ActionIR::simple("lit")));
+
+        let messages: Vec<&str> = warnings.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages.len(), 5, "{:?}", messages);
+        assert!(messages.iter().any(|m| m.contains("<script>")));
+        assert!(messages.iter().any(|m| m.contains("<datamodel>")));
+        assert!(messages.iter().any(|m| m.contains("<invoke>")));
+        assert!(messages.iter().any(|m| m.contains("'Math.max(level, 2)'")));
+        assert!(messages.iter().any(|m| m.contains("Targetless")));
+    }
+
+    #[test]
+    fn import_rejects_invalid_documents() {
+        assert!(from_scxml("<scxml><state id=\"A\"></scxml>").is_err());
+        assert!(from_scxml("<statechart/>").is_err());
+        let errors = from_scxml("<scxml version=\"1.0\"/>").unwrap_err();
+        assert!(errors[0].message.contains("no states"));
+    }
+}
@@ -0,0 +1,449 @@
+//! Parser and evaluator for the expressions of constraints, guards and
+//! action values.
+//!
+//! Handles expressions like:
+//! - `mass <= 1200`
+//! - `speed > 0 && speed < 300`
+//! - `(load + payload) * 1.5 < maxLoad or !loaded`
+//!
+//! Operands are numeric literals (`2`, `.5`, `1e3`), `true`/`false`, and
+//! variable names (which may be dotted, as in `engine.mass`). `and`, `or`,
+//! and `not` are accepted as aliases for `&&`, `||`, and `!`, and a bracketed
+//! unit after a literal (`1200 [kg]`) is ignored. From loosest to tightest,
+//! the operators are `||`, `&&`, comparisons (which do not chain), `+ -`,
+//! `* /`, and the prefix operators `! - +`.
+//!
+//! Evaluation uses three-valued logic: a variable without a value makes
+//! every comparison that reads it unknown, and unknowns only decide the
+//! result when the rest of the expression cannot, so `x > 0 || true` holds
+//! while `x > 0 && true` stays unknown.
+
+use std::fmt;
+
+/// A value an expression computes with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scalar {
+    /// Numeric value.
+    Number(f64),
+    /// Boolean value.
+    Bool(bool),
+}
+
+/// An error raised while parsing or evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprError {
+    /// The expression could not be parsed.
+    Syntax(String),
+    /// An operator was applied to operands of the wrong type.
+    Type(String),
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExprError::Syntax(msg) => write!(f, "syntax error: {}", msg),
+            ExprError::Type(msg) => write!(f, "type error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+/// A parsed expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// A numeric literal.
+    Number(f64),
+    /// `true` or `false`.
+    Bool(bool),
+    /// A variable name.
+    Variable(String),
+    /// A prefix operator (`!` or `-`) and its operand.
+    Unary(&'static str, Box<Expr>),
+    /// An infix operator and its operands.
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Parse an expression.
+    pub fn parse(input: &str) -> Result<Expr, ExprError> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            pos: 0,
+        };
+        let expr = parser.parse_or()?;
+        match parser.peek() {
+            Some(token) => Err(ExprError::Syntax(format!("unexpected '{}'", token))),
+            None => Ok(expr),
+        }
+    }
+
+    /// The variable names the expression reads, in order of first use.
+    pub fn variables(&self) -> Vec<&str> {
+        fn collect<'a>(expr: &'a Expr, names: &mut Vec<&'a str>) {
+            match expr {
+                Expr::Variable(name) => {
+                    if !names.contains(&name.as_str()) {
+                        names.push(name);
+                    }
+                }
+                Expr::Unary(_, operand) => collect(operand, names),
+                Expr::Binary(_, left, right) => {
+                    collect(left, names);
+                    collect(right, names);
+                }
+                Expr::Number(_) | Expr::Bool(_) => {}
+            }
+        }
+
+        let mut names = Vec::new();
+        collect(self, &mut names);
+        names
+    }
+
+    /// Evaluate the expression, yielding `None` when unknown variables
+    /// leave the result open.
+    pub fn evaluate<F>(&self, lookup: &F) -> Result<Option<Scalar>, ExprError>
+    where
+        F: Fn(&str) -> Option<Scalar>,
+    {
+        match self {
+            Expr::Number(n) => Ok(Some(Scalar::Number(*n))),
+            Expr::Bool(b) => Ok(Some(Scalar::Bool(*b))),
+            Expr::Variable(name) => Ok(lookup(name)),
+            Expr::Unary(op, operand) => {
+                let value = operand.evaluate(lookup)?;
+                if *op == "!" {
+                    Ok(expect_bool(value, op)?.map(|b| Scalar::Bool(!b)))
+                } else {
+                    Ok(expect_number(value, op)?.map(|n| Scalar::Number(-n)))
+                }
+            }
+            Expr::Binary(op, left, right) => {
+                let left = left.evaluate(lookup)?;
+                let right = right.evaluate(lookup)?;
+                match *op {
+                    "||" | "&&" => {
+                        let (l, r) = (expect_bool(left, op)?, expect_bool(right, op)?);
+                        // The value that decides the result on its own
+                        let dominant = *op == "||";
+                        let result = if l == Some(dominant) || r == Some(dominant) {
+                            Some(dominant)
+                        } else if l.is_some() && r.is_some() {
+                            Some(!dominant)
+                        } else {
+                            None
+                        };
+                        Ok(result.map(Scalar::Bool))
+                    }
+                    "==" | "!=" => Ok(match (left, right) {
+                        (Some(l), Some(r)) => Some(Scalar::Bool((l == r) == (*op == "=="))),
+                        _ => None,
+                    }),
+                    _ => {
+                        let (l, r) = match (expect_number(left, op)?, expect_number(right, op)?) {
+                            (Some(l), Some(r)) => (l, r),
+                            _ => return Ok(None),
+                        };
+                        Ok(Some(match *op {
+                            "<" => Scalar::Bool(l < r),
+                            "<=" => Scalar::Bool(l <= r),
+                            ">" => Scalar::Bool(l > r),
+                            ">=" => Scalar::Bool(l >= r),
+                            "+" => Scalar::Number(l + r),
+                            "-" => Scalar::Number(l - r),
+                            "*" => Scalar::Number(l * r),
+                            _ => Scalar::Number(l / r),
+                        }))
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn expect_bool(value: Option<Scalar>, op: &str) -> Result<Option<bool>, ExprError> {
+    match value {
+        Some(Scalar::Bool(b)) => Ok(Some(b)),
+        Some(Scalar::Number(_)) => Err(ExprError::Type(format!("'{}' requires boolean operands", op))),
+        None => Ok(None),
+    }
+}
+
+fn expect_number(value: Option<Scalar>, op: &str) -> Result<Option<f64>, ExprError> {
+    match value {
+        Some(Scalar::Number(n)) => Ok(Some(n)),
+        Some(Scalar::Bool(_)) => Err(ExprError::Type(format!("'{}' requires numeric operands", op))),
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Ident(s) => write!(f, "{}", s),
+            Token::Op(op) => write!(f, "{}", op),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+        }
+    }
+}
+
+/// Split an expression into tokens.
+fn tokenize(input: &str) -> Result<Vec<Token>, ExprError> {
+    const OPERATORS: [&str; 13] = ["<=", ">=", "==", "!=", "&&", "||", "<", ">", "!", "-", "+", "*", "/"];
+
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = input.chars().collect();
+    let mut i = 0;
+
+    'outer: while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+            continue;
+        }
+        if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+            continue;
+        }
+
+        // Units only label a literal, so `[kg]` is skipped
+        if c == '[' && matches!(tokens.last(), Some(Token::Number(_))) {
+            match chars[i..].iter().position(|c| *c == ']') {
+                Some(len) => {
+                    i += len + 1;
+                    continue;
+                }
+                None => return Err(ExprError::Syntax("expected ']'".to_string())),
+            }
+        }
+
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // An exponent, as in `1e3` or `2.5E-4`
+            if matches!(chars.get(i), Some('e' | 'E')) {
+                let digits = if matches!(chars.get(i + 1), Some('+' | '-')) { i + 2 } else { i + 1 };
+                if chars.get(digits).is_some_and(|c| c.is_ascii_digit()) {
+                    i = digits;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| ExprError::Syntax(format!("invalid number '{}'", text)))?;
+            tokens.push(Token::Number(value));
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            match word.as_str() {
+                "and" => tokens.push(Token::Op("&&")),
+                "or" => tokens.push(Token::Op("||")),
+                "not" => tokens.push(Token::Op("!")),
+                _ => tokens.push(Token::Ident(word)),
+            }
+            continue;
+        }
+
+        for op in OPERATORS {
+            let len = op.len();
+            if i + len <= chars.len() && chars[i..i + len].iter().copied().eq(op.chars()) {
+                tokens.push(Token::Op(op));
+                i += len;
+                continue 'outer;
+            }
+        }
+
+        return Err(ExprError::Syntax(format!("unexpected character '{}'", c)));
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive-descent parser building an [`Expr`].
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Consume the next token if it is one of `ops`.
+    fn eat_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ExprError> {
+        let mut left = self.parse_and()?;
+        while let Some(op) = self.eat_op(&["||"]) {
+            left = Expr::Binary(op, Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ExprError> {
+        let mut left = self.parse_comparison()?;
+        while let Some(op) = self.eat_op(&["&&"]) {
+            left = Expr::Binary(op, Box::new(left), Box::new(self.parse_comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, ExprError> {
+        let left = self.parse_additive()?;
+        match self.eat_op(&["<", "<=", ">", ">=", "==", "!="]) {
+            Some(op) => Ok(Expr::Binary(op, Box::new(left), Box::new(self.parse_additive()?))),
+            None => Ok(left),
+        }
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, ExprError> {
+        let mut left = self.parse_multiplicative()?;
+        while let Some(op) = self.eat_op(&["+", "-"]) {
+            left = Expr::Binary(op, Box::new(left), Box::new(self.parse_multiplicative()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, ExprError> {
+        let mut left = self.parse_unary()?;
+        while let Some(op) = self.eat_op(&["*", "/"]) {
+            left = Expr::Binary(op, Box::new(left), Box::new(self.parse_unary()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ExprError> {
+        match self.eat_op(&["!", "-", "+"]) {
+            Some("+") => self.parse_unary(),
+            Some(op) => Ok(Expr::Unary(op, Box::new(self.parse_unary()?))),
+            None => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ExprError> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Expr::Bool(true)),
+                "false" => Ok(Expr::Bool(false)),
+                _ => Ok(Expr::Variable(name)),
+            },
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err(ExprError::Syntax("expected ')'".to_string())),
+                }
+            }
+            Some(token) => Err(ExprError::Syntax(format!("unexpected '{}'", token))),
+            None => Err(ExprError::Syntax("unexpected end of expression".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expr: &str, vars: &[(&str, Scalar)]) -> Result<Option<bool>, ExprError> {
+        let lookup = |name: &str| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| *v);
+        Expr::parse(expr)?.evaluate(&lookup).map(|value| match value {
+            Some(Scalar::Bool(b)) => Some(b),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn arithmetic_precedence_and_units() {
+        let vars = [("a", Scalar::Number(2.0)), ("b", Scalar::Number(3.0))];
+        assert_eq!(eval("a + b * 2 == 8", &vars), Ok(Some(true)));
+        assert_eq!(eval("(a + b) * 2 == 10", &vars), Ok(Some(true)));
+        assert_eq!(eval("a - b - 1 == -2", &vars), Ok(Some(true)));
+        assert_eq!(eval("b / a > 1.4 and not (a > b)", &vars), Ok(Some(true)));
+        assert_eq!(eval("a <= 1200 [kg]", &vars), Ok(Some(true)));
+    }
+
+    #[test]
+    fn unknown_operands_use_three_valued_logic() {
+        let vars = [("x", Scalar::Number(1.0))];
+        assert_eq!(eval("y > 0", &vars), Ok(None));
+        assert_eq!(eval("y > 0 || x == 1", &vars), Ok(Some(true)));
+        assert_eq!(eval("y > 0 && x == 1", &vars), Ok(None));
+        assert_eq!(eval("y > 0 && x == 2", &vars), Ok(Some(false)));
+        assert_eq!(eval("!(y > 0)", &vars), Ok(None));
+    }
+
+    #[test]
+    fn exponent_literals() {
+        assert_eq!(Expr::parse("1e3"), Ok(Expr::Number(1000.0)));
+        assert_eq!(Expr::parse("2.5E-1"), Ok(Expr::Number(0.25)));
+        assert_eq!(Expr::parse(".5e+1"), Ok(Expr::Number(5.0)));
+        assert!(matches!(Expr::parse("1e999"), Err(ExprError::Syntax(_))));
+        assert!(matches!(Expr::parse("2e"), Err(ExprError::Syntax(_))));
+    }
+
+    #[test]
+    fn variables_in_order_of_first_use() {
+        let expr = Expr::parse("speed > 0 && engine.rpm < 6000 && speed < 300").unwrap();
+        assert_eq!(expr.variables(), vec!["speed", "engine.rpm"]);
+    }
+
+    #[test]
+    fn syntax_and_type_errors() {
+        assert!(matches!(Expr::parse("x <"), Err(ExprError::Syntax(_))));
+        assert!(matches!(Expr::parse("(x"), Err(ExprError::Syntax(_))));
+        assert!(matches!(Expr::parse("x < 1 2"), Err(ExprError::Syntax(_))));
+        assert!(matches!(Expr::parse("x < 1 [kg"), Err(ExprError::Syntax(_))));
+        assert!(matches!(eval("true > 1", &[]), Err(ExprError::Type(_))));
+        assert!(matches!(eval("1 && true", &[]), Err(ExprError::Type(_))));
+    }
+}
@@ -5,14 +5,24 @@
 //! This crate defines the core abstractions for executing SysML models:
 //! - Runner trait for stepping through execution
 //! - CompileToIR trait for compiling ModelGraph to executable IR
-//! - IR structs for state machines, constraints, etc.
+//! - IR structs for state machines, activities, constraints, etc.
 //!
-//! Actual implementations are in sub-crates (sysml-run-statemachine, etc.).
+//! Actual implementations are in sub-crates (sysml-run-statemachine, etc.),
+//! which share the expression parser in [`expr`].
+//!
+//! ## Features
+//!
+//! - `serde`: Enable serde serialization of the state machine IR, so compiled
+//!   machines can be cached (see `StateMachineIR::to_json`)
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use sysml_core::ModelGraph;
 use sysml_span::Diagnostic;
 use std::collections::HashMap;
 
+pub mod expr;
+
 /// The result of a single execution step.
 #[derive(Debug, Clone)]
 pub struct StepResult {
@@ -22,6 +32,8 @@ pub struct StepResult {
     pub outputs: Vec<String>,
     /// Whether execution has completed.
     pub completed: bool,
+    /// Warnings raised during the step, such as a cut-short event loop.
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl StepResult {
@@ -31,6 +43,7 @@ impl StepResult {
             state: state.into(),
             outputs: Vec::new(),
             completed: false,
+            diagnostics: Vec::new(),
         }
     }
 
@@ -51,6 +64,12 @@ impl StepResult {
         self.outputs.extend(outputs.into_iter().map(|o| o.into()));
         self
     }
+
+    /// Add a diagnostic.
+    pub fn with_diagnostic(mut self, diagnostic: Diagnostic) -> Self {
+        self.diagnostics.push(diagnostic);
+        self
+    }
 }
 
 /// Extended result for parallel state machine execution.
@@ -66,6 +85,8 @@ pub struct ParallelStepResult {
     pub completed: bool,
     /// Timing and other context variables.
     pub context: HashMap<String, f64>,
+    /// Whether internal event processing was cut short by the iteration limit.
+    pub iteration_limit_reached: bool,
 }
 
 impl ParallelStepResult {
@@ -77,6 +98,7 @@ impl ParallelStepResult {
             internal_events: Vec::new(),
             completed: false,
             context: HashMap::new(),
+            iteration_limit_reached: false,
         }
     }
 
@@ -111,8 +133,141 @@ impl Default for ParallelStepResult {
     }
 }
 
+/// The kind of action recorded in an execution trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ActionKind {
+    /// Entry action of a state.
+    Entry,
+    /// Exit action of a state.
+    Exit,
+    /// Action of a transition.
+    Transition,
+    /// Do action of a state.
+    Do,
+}
+
+/// A structured record of something that happened while running a machine.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TraceEvent {
+    /// A transition was taken.
+    TransitionTaken {
+        /// The source state.
+        from: String,
+        /// The target state.
+        to: String,
+        /// The triggering event, if any.
+        event: Option<String>,
+        /// The guard that held, if any.
+        guard: Option<String>,
+        /// The result of the guard, if the transition has one.
+        guard_result: Option<bool>,
+        /// The exit, transition and entry actions run, in order.
+        actions: Vec<String>,
+        /// The runner step during which the transition was taken, from 1.
+        timestamp_step: u64,
+    },
+    /// An action was executed.
+    ActionExecuted {
+        /// Which kind of action ran.
+        kind: ActionKind,
+        /// The formatted action.
+        detail: String,
+    },
+    /// An event triggered no transition.
+    EventIgnored {
+        /// The ignored event.
+        event: String,
+        /// The state (or region states) the machine was in.
+        state: String,
+    },
+    /// A region of a parallel machine changed state.
+    RegionChanged {
+        /// The region name.
+        region: String,
+        /// The previous state.
+        from: String,
+        /// The new state.
+        to: String,
+    },
+}
+
+/// Default maximum number of events kept in a [`TraceLog`].
+pub const DEFAULT_TRACE_CAPACITY: usize = 1000;
+
+/// A bounded log of [`TraceEvent`]s.
+///
+/// When the log is full, the oldest events are dropped. A capacity of 0
+/// disables tracing.
+#[derive(Debug, Clone)]
+pub struct TraceLog {
+    events: Vec<TraceEvent>,
+    capacity: usize,
+}
+
+impl TraceLog {
+    /// Create an empty trace log with the given capacity.
+    pub fn new(capacity: usize) -> Self {
+        TraceLog {
+            events: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Record an event, dropping the oldest one if the log is full.
+    pub fn push(&mut self, event: TraceEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() >= self.capacity {
+            let excess = self.events.len() + 1 - self.capacity;
+            self.events.drain(..excess);
+        }
+        self.events.push(event);
+    }
+
+    /// Get the recorded events, oldest first.
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    /// Remove all recorded events.
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Remove and return the recorded events, oldest first.
+    pub fn take(&mut self) -> Vec<TraceEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Get the maximum number of events kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the capacity, dropping the oldest events if needed.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        if self.events.len() > capacity {
+            let excess = self.events.len() - capacity;
+            self.events.drain(..excess);
+        }
+    }
+}
+
+impl Default for TraceLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_TRACE_CAPACITY)
+    }
+}
+
 /// Assignment operator for structured actions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum AssignmentOp {
     /// Direct assignment (=)
     Set,
@@ -120,47 +275,257 @@ pub enum AssignmentOp {
     Add,
     /// Subtraction assignment (-=)
     Subtract,
+    /// Multiplication assignment (*=)
+    Multiply,
+    /// Division assignment (/=)
+    Divide,
+}
+
+impl AssignmentOp {
+    /// The operator as written in an action (`=`, `+=`, `-=`, `*=`, `/=`).
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            AssignmentOp::Set => "=",
+            AssignmentOp::Add => "+=",
+            AssignmentOp::Subtract => "-=",
+            AssignmentOp::Multiply => "*=",
+            AssignmentOp::Divide => "/=",
+        }
+    }
+
+    /// The arithmetic operator of a compound assignment, `None` for `=`.
+    pub fn binary_op(&self) -> Option<BinaryOp> {
+        match self {
+            AssignmentOp::Set => None,
+            AssignmentOp::Add => Some(BinaryOp::Add),
+            AssignmentOp::Subtract => Some(BinaryOp::Subtract),
+            AssignmentOp::Multiply => Some(BinaryOp::Multiply),
+            AssignmentOp::Divide => Some(BinaryOp::Divide),
+        }
+    }
+}
+
+/// An arithmetic operator in an assignment's right-hand side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BinaryOp {
+    /// Addition (+)
+    Add,
+    /// Subtraction (-)
+    Subtract,
+    /// Multiplication (*)
+    Multiply,
+    /// Division (/)
+    Divide,
+}
+
+impl BinaryOp {
+    /// The operator as written in an expression.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+        }
+    }
+
+    /// Binding strength: `*` and `/` bind tighter than `+` and `-`.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOp::Add | BinaryOp::Subtract => 1,
+            BinaryOp::Multiply | BinaryOp::Divide => 2,
+        }
+    }
+
+    /// Apply the operator with `f64` semantics (dividing by zero gives an
+    /// infinity or NaN).
+    pub fn apply(&self, left: f64, right: f64) -> f64 {
+        match self {
+            BinaryOp::Add => left + right,
+            BinaryOp::Subtract => left - right,
+            BinaryOp::Multiply => left * right,
+            BinaryOp::Divide => left / right,
+        }
+    }
+}
+
+/// The right-hand side of an assignment: a literal, another variable, or
+/// arithmetic over both.
+///
+/// Serialized untagged, as a JSON number, a variable name string, or an
+/// `{"op", "left", "right"}` object.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum ValueRef {
+    /// A numeric literal.
+    Literal(f64),
+    /// The current value of a context variable.
+    Variable(String),
+    /// An arithmetic expression such as `u + 5` or `x * 2`.
+    Binary {
+        /// The operator.
+        op: BinaryOp,
+        /// The left operand.
+        left: Box<ValueRef>,
+        /// The right operand.
+        right: Box<ValueRef>,
+    },
+}
+
+impl ValueRef {
+    /// Create a variable reference.
+    pub fn variable(name: impl Into<String>) -> Self {
+        ValueRef::Variable(name.into())
+    }
+
+    /// Create an arithmetic expression.
+    pub fn binary(op: BinaryOp, left: impl Into<ValueRef>, right: impl Into<ValueRef>) -> Self {
+        ValueRef::Binary {
+            op,
+            left: Box::new(left.into()),
+            right: Box::new(right.into()),
+        }
+    }
+
+    /// Get the literal value, if this is a literal.
+    pub fn as_literal(&self) -> Option<f64> {
+        match self {
+            ValueRef::Literal(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Get the variable name, if this is a variable reference.
+    pub fn as_variable(&self) -> Option<&str> {
+        match self {
+            ValueRef::Variable(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Resolve to a number, looking up variables with `lookup`.
+    ///
+    /// Unknown variables resolve to 0.
+    pub fn resolve(&self, lookup: impl Fn(&str) -> Option<f64>) -> f64 {
+        self.evaluate(&lookup)
+    }
+
+    fn evaluate(&self, lookup: &dyn Fn(&str) -> Option<f64>) -> f64 {
+        match self {
+            ValueRef::Literal(v) => *v,
+            ValueRef::Variable(name) => lookup(name).unwrap_or(0.0),
+            ValueRef::Binary { op, left, right } => op.apply(left.evaluate(lookup), right.evaluate(lookup)),
+        }
+    }
+
+    /// The precedence of the outermost operator, if this is an expression.
+    fn precedence(&self) -> Option<u8> {
+        match self {
+            ValueRef::Binary { op, .. } => Some(op.precedence()),
+            _ => None,
+        }
+    }
+}
+
+impl From<f64> for ValueRef {
+    fn from(value: f64) -> Self {
+        ValueRef::Literal(value)
+    }
+}
+
+impl std::fmt::Display for ValueRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueRef::Literal(v) => write!(f, "{}", v),
+            ValueRef::Variable(name) => write!(f, "{}", name),
+            ValueRef::Binary { op, left, right } => {
+                // Parenthesize operands that would otherwise regroup when parsed
+                let precedence = op.precedence();
+                if left.precedence().is_some_and(|p| p < precedence) {
+                    write!(f, "({})", left)?;
+                } else {
+                    write!(f, "{}", left)?;
+                }
+                write!(f, " {} ", op.symbol())?;
+                if right.precedence().is_some_and(|p| p <= precedence) {
+                    write!(f, "({})", right)
+                } else {
+                    write!(f, "{}", right)
+                }
+            }
+        }
+    }
 }
 
 /// A variable assignment in a structured action.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AssignmentIR {
     /// The variable name being assigned.
     pub variable: String,
     /// The assignment operator.
     pub operator: AssignmentOp,
     /// The value being assigned.
-    pub value: f64,
+    pub value: ValueRef,
 }
 
 impl AssignmentIR {
     /// Create a new assignment.
-    pub fn new(variable: impl Into<String>, operator: AssignmentOp, value: f64) -> Self {
+    pub fn new(variable: impl Into<String>, operator: AssignmentOp, value: impl Into<ValueRef>) -> Self {
         AssignmentIR {
             variable: variable.into(),
             operator,
-            value,
+            value: value.into(),
         }
     }
 
     /// Create a set assignment (x = value).
-    pub fn set(variable: impl Into<String>, value: f64) -> Self {
+    pub fn set(variable: impl Into<String>, value: impl Into<ValueRef>) -> Self {
         Self::new(variable, AssignmentOp::Set, value)
     }
 
     /// Create an add assignment (x += value).
-    pub fn add(variable: impl Into<String>, value: f64) -> Self {
+    pub fn add(variable: impl Into<String>, value: impl Into<ValueRef>) -> Self {
         Self::new(variable, AssignmentOp::Add, value)
     }
 
     /// Create a subtract assignment (x -= value).
-    pub fn subtract(variable: impl Into<String>, value: f64) -> Self {
+    pub fn subtract(variable: impl Into<String>, value: impl Into<ValueRef>) -> Self {
         Self::new(variable, AssignmentOp::Subtract, value)
     }
+
+    /// Create a multiply assignment (x *= value).
+    pub fn multiply(variable: impl Into<String>, value: impl Into<ValueRef>) -> Self {
+        Self::new(variable, AssignmentOp::Multiply, value)
+    }
+
+    /// Create a divide assignment (x /= value).
+    pub fn divide(variable: impl Into<String>, value: impl Into<ValueRef>) -> Self {
+        Self::new(variable, AssignmentOp::Divide, value)
+    }
+
+    /// Compute the new value of the variable given its current value.
+    ///
+    /// Variable references on the right-hand side are resolved with `lookup`.
+    pub fn apply(&self, current: f64, lookup: impl Fn(&str) -> Option<f64>) -> f64 {
+        let value = self.value.resolve(lookup);
+        match self.operator.binary_op() {
+            Some(op) => op.apply(current, value),
+            None => value,
+        }
+    }
 }
 
 /// Action IR that can be simple text or structured with assignments and sends.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "type", content = "data", rename_all = "snake_case")
+)]
 pub enum ActionIR {
     /// Simple action as a string (backward compatible).
     Simple(String),
@@ -198,6 +563,24 @@ impl ActionIR {
     }
 }
 
+impl std::fmt::Display for ActionIR {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionIR::Simple(s) => write!(f, "{}", s),
+            ActionIR::Structured { assignments, sends } => {
+                let mut parts = Vec::new();
+                for assign in assignments {
+                    parts.push(format!("{} {} {}", assign.variable, assign.operator.symbol(), assign.value));
+                }
+                for send in sends {
+                    parts.push(format!("send('{}')", send));
+                }
+                write!(f, "{}", parts.join("; "))
+            }
+        }
+    }
+}
+
 impl From<String> for ActionIR {
     fn from(s: String) -> Self {
         ActionIR::Simple(s)
@@ -211,7 +594,8 @@ impl From<&str> for ActionIR {
 }
 
 /// Parallel region within a composite state machine.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RegionIR {
     /// The region name.
     pub name: String,
@@ -221,6 +605,12 @@ pub struct RegionIR {
     pub transitions: Vec<TransitionIR>,
     /// The initial state name for this region.
     pub initial: String,
+    /// Whether the region has a (shallow) history pseudostate.
+    ///
+    /// A history region resumes at its last active state when re-entered,
+    /// instead of starting over at `initial`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub history: bool,
 }
 
 impl RegionIR {
@@ -231,9 +621,16 @@ impl RegionIR {
             states: Vec::new(),
             transitions: Vec::new(),
             initial: initial.into(),
+            history: false,
         }
     }
 
+    /// Set whether this region has shallow history.
+    pub fn with_history(mut self, history: bool) -> Self {
+        self.history = history;
+        self
+    }
+
     /// Add a state to this region.
     pub fn with_state(mut self, state: StateIR) -> Self {
         self.states.push(state);
@@ -255,6 +652,49 @@ impl RegionIR {
     pub fn transitions_from(&self, state: &str) -> Vec<&TransitionIR> {
         self.transitions.iter().filter(|t| t.from == state).collect()
     }
+
+    /// Get the transitions from a state that match an event, in selection order.
+    ///
+    /// See [`order_by_priority`] for the ordering rules.
+    pub fn matching_transitions(&self, state: &str, event: Option<&str>) -> Vec<&TransitionIR> {
+        order_by_priority(
+            self.transitions
+                .iter()
+                .filter(|t| t.from == state && t.matches(event))
+                .collect(),
+        )
+    }
+
+    /// Get the timed transitions from a state, in firing order.
+    ///
+    /// See [`StateMachineIR::timed_transitions`] for the ordering rules.
+    pub fn timed_transitions(&self, state: &str) -> Vec<&TransitionIR> {
+        timed_in_firing_order(&self.transitions, state)
+    }
+}
+
+/// The timed transitions from `state`, by ascending `after` delay and then
+/// [`order_by_priority`].
+fn timed_in_firing_order<'a>(transitions: &'a [TransitionIR], state: &str) -> Vec<&'a TransitionIR> {
+    let mut timed = order_by_priority(transitions.iter().filter(|t| t.from == state && t.is_timed()).collect());
+    // Stable, so the priority order is kept for equal delays
+    timed.sort_by(|a, b| a.after.unwrap_or(0.0).total_cmp(&b.after.unwrap_or(0.0)));
+    timed
+}
+
+/// Order candidate transitions for deterministic selection.
+///
+/// Transitions are ordered by descending `priority`; ties are broken by
+/// preferring guarded transitions over unguarded ones, and finally by
+/// declaration order (the order of `candidates`).
+pub fn order_by_priority(mut candidates: Vec<&TransitionIR>) -> Vec<&TransitionIR> {
+    // sort_by is stable, so declaration order is kept for full ties
+    candidates.sort_by(|a, b| {
+        b.priority
+            .cmp(&a.priority)
+            .then_with(|| b.is_guarded().cmp(&a.is_guarded()))
+    });
+    candidates
 }
 
 /// Trait for executable runners.
@@ -298,7 +738,8 @@ pub trait CompileToIR<T> {
 }
 
 /// IR for a state machine.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct StateMachineIR {
     /// The name of this state machine.
     pub name: String,
@@ -309,9 +750,23 @@ pub struct StateMachineIR {
     /// The initial state name (for simple, non-parallel state machines).
     pub initial: String,
     /// Parallel regions (for composite state machines with concurrent regions).
+    #[cfg_attr(feature = "serde", serde(default))]
     pub regions: Vec<RegionIR>,
 }
 
+#[cfg(feature = "serde")]
+impl StateMachineIR {
+    /// Serialize this IR to a JSON string.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Deserialize IR from a JSON string produced by [`StateMachineIR::to_json`].
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
 impl StateMachineIR {
     /// Create a new state machine IR.
     pub fn new(name: impl Into<String>, initial: impl Into<String>) -> Self {
@@ -363,19 +818,63 @@ impl StateMachineIR {
         self
     }
 
-    /// Find a state by name.
+    /// Find a state by name, searching nested states too.
     pub fn find_state(&self, name: &str) -> Option<&StateIR> {
-        self.states.iter().find(|s| s.name == name)
+        self.state_path(name).and_then(|path| path.last().copied())
+    }
+
+    /// Get the chain of states from a top-level state down to `name`.
+    ///
+    /// Returns None if no state, at any depth, has that name.
+    pub fn state_path(&self, name: &str) -> Option<Vec<&StateIR>> {
+        fn search<'a>(states: &'a [StateIR], name: &str, path: &mut Vec<&'a StateIR>) -> bool {
+            for state in states {
+                path.push(state);
+                if state.name == name || search(&state.substates, name, path) {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+
+        let mut path = Vec::new();
+        if search(&self.states, name, &mut path) {
+            Some(path)
+        } else {
+            None
+        }
     }
 
     /// Get all transitions from a given state.
     pub fn transitions_from(&self, state: &str) -> Vec<&TransitionIR> {
         self.transitions.iter().filter(|t| t.from == state).collect()
     }
+
+    /// Get the transitions from a state that match an event, in selection order.
+    ///
+    /// See [`order_by_priority`] for the ordering rules.
+    pub fn matching_transitions(&self, state: &str, event: Option<&str>) -> Vec<&TransitionIR> {
+        order_by_priority(
+            self.transitions
+                .iter()
+                .filter(|t| t.from == state && t.matches(event))
+                .collect(),
+        )
+    }
+
+    /// Get the timed transitions from a state, in firing order.
+    ///
+    /// Transitions are ordered by ascending `after` delay; equal delays fall
+    /// back to [`order_by_priority`].
+    pub fn timed_transitions(&self, state: &str) -> Vec<&TransitionIR> {
+        timed_in_firing_order(&self.transitions, state)
+    }
 }
 
 /// IR for a state within a state machine.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct StateIR {
     /// The state name.
     pub name: String,
@@ -383,8 +882,23 @@ pub struct StateIR {
     pub entry_action: Option<ActionIR>,
     /// Exit action (optional).
     pub exit_action: Option<ActionIR>,
+    /// Do action (optional), performed on every step while the state stays active.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub do_action: Option<ActionIR>,
     /// Whether this is a final state.
     pub is_final: bool,
+    /// Nested states of a composite state.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub substates: Vec<StateIR>,
+    /// The substate entered with this state (defaults to the first substate).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub initial_substate: Option<String>,
+    /// Whether the substates have a (shallow) history pseudostate.
+    ///
+    /// A composite state with history resumes at its last active substate
+    /// when re-entered, instead of starting over at its initial substate.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub history: bool,
 }
 
 impl StateIR {
@@ -394,7 +908,42 @@ impl StateIR {
             name: name.into(),
             entry_action: None,
             exit_action: None,
+            do_action: None,
             is_final: false,
+            substates: Vec::new(),
+            initial_substate: None,
+            history: false,
+        }
+    }
+
+    /// Add a nested state.
+    pub fn with_substate(mut self, state: StateIR) -> Self {
+        self.substates.push(state);
+        self
+    }
+
+    /// Set the substate entered with this state.
+    pub fn with_initial_substate(mut self, name: impl Into<String>) -> Self {
+        self.initial_substate = Some(name.into());
+        self
+    }
+
+    /// Set whether this state's substates have shallow history.
+    pub fn with_history(mut self, history: bool) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Check if this state has nested states.
+    pub fn is_composite(&self) -> bool {
+        !self.substates.is_empty()
+    }
+
+    /// Get the substate entered with this state, if it is composite.
+    pub fn initial(&self) -> Option<&StateIR> {
+        match &self.initial_substate {
+            Some(name) => self.substates.iter().find(|s| &s.name == name),
+            None => self.substates.first(),
         }
     }
 
@@ -422,6 +971,18 @@ impl StateIR {
         self
     }
 
+    /// Set do action (accepts string or ActionIR).
+    pub fn with_do(mut self, action: impl Into<ActionIR>) -> Self {
+        self.do_action = Some(action.into());
+        self
+    }
+
+    /// Set a structured do action.
+    pub fn with_do_action(mut self, action: ActionIR) -> Self {
+        self.do_action = Some(action);
+        self
+    }
+
     /// Mark as final state.
     pub fn final_state(mut self) -> Self {
         self.is_final = true;
@@ -430,7 +991,8 @@ impl StateIR {
 }
 
 /// IR for a transition between states.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TransitionIR {
     /// The source state name.
     pub from: String,
@@ -442,6 +1004,13 @@ pub struct TransitionIR {
     pub guard: Option<String>,
     /// The action to execute (optional).
     pub action: Option<ActionIR>,
+    /// Selection priority when several transitions match (higher wins, default 0).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub priority: i32,
+    /// Time after entering the source state at which the transition fires
+    /// (an `after` trigger). Timed transitions never match events.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub after: Option<f64>,
 }
 
 impl TransitionIR {
@@ -453,6 +1022,8 @@ impl TransitionIR {
             event: None,
             guard: None,
             action: None,
+            priority: 0,
+            after: None,
         }
     }
 
@@ -480,8 +1051,35 @@ impl TransitionIR {
         self
     }
 
+    /// Set the selection priority.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Fire the transition once `duration` has elapsed in the source state.
+    pub fn with_after(mut self, duration: f64) -> Self {
+        self.after = Some(duration);
+        self
+    }
+
+    /// Check if this transition has a guard condition.
+    pub fn is_guarded(&self) -> bool {
+        self.guard.is_some()
+    }
+
+    /// Check if this transition has an `after` trigger.
+    pub fn is_timed(&self) -> bool {
+        self.after.is_some()
+    }
+
     /// Check if this transition matches an event.
+    ///
+    /// Timed transitions never match; they fire as time advances.
     pub fn matches(&self, event: Option<&str>) -> bool {
+        if self.is_timed() {
+            return false;
+        }
         match (&self.event, event) {
             (None, _) => true, // Auto-transition
             (Some(e), Some(ev)) => e == ev,
@@ -490,6 +1088,152 @@ impl TransitionIR {
     }
 }
 
+/// IR for an activity: the actions of an action definition, ordered by
+/// successions.
+///
+/// An action (or control node) may start once all of its predecessors have
+/// finished. Actions that become ready together run as one parallel group;
+/// fork and join nodes only shape the ordering and are never executed.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ActivityIR {
+    /// The name of this activity.
+    pub name: String,
+    /// The actions and control nodes, in declaration order.
+    pub nodes: Vec<ActivityNodeIR>,
+    /// The successions between nodes.
+    pub edges: Vec<SuccessionIR>,
+}
+
+impl ActivityIR {
+    /// Create a new empty activity IR.
+    pub fn new(name: impl Into<String>) -> Self {
+        ActivityIR {
+            name: name.into(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Add a node.
+    pub fn with_node(mut self, node: ActivityNodeIR) -> Self {
+        self.nodes.push(node);
+        self
+    }
+
+    /// Add a succession from one node to another.
+    pub fn with_succession(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.edges.push(SuccessionIR::new(from, to));
+        self
+    }
+
+    /// Find a node by name.
+    pub fn find_node(&self, name: &str) -> Option<&ActivityNodeIR> {
+        self.nodes.iter().find(|n| n.name == name)
+    }
+
+    /// Get the names of the nodes that must finish before `name` starts.
+    pub fn predecessors(&self, name: &str) -> Vec<&str> {
+        self.edges
+            .iter()
+            .filter(|e| e.to == name)
+            .map(|e| e.from.as_str())
+            .collect()
+    }
+
+    /// Get the names of the nodes that follow `name`.
+    pub fn successors(&self, name: &str) -> Vec<&str> {
+        self.edges
+            .iter()
+            .filter(|e| e.from == name)
+            .map(|e| e.to.as_str())
+            .collect()
+    }
+}
+
+/// The kind of an activity node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ActivityNodeKind {
+    /// An action that is executed.
+    Action,
+    /// A fork node, after which its successors run in parallel.
+    Fork,
+    /// A join node, which waits for all of its predecessors.
+    Join,
+}
+
+/// IR for an action or control node within an activity.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ActivityNodeIR {
+    /// The node name.
+    pub name: String,
+    /// Whether this is an action or a control node.
+    pub kind: ActivityNodeKind,
+    /// The action body, if the action has one.
+    pub action: Option<ActionIR>,
+}
+
+impl ActivityNodeIR {
+    /// Create an action node.
+    pub fn action(name: impl Into<String>) -> Self {
+        ActivityNodeIR {
+            name: name.into(),
+            kind: ActivityNodeKind::Action,
+            action: None,
+        }
+    }
+
+    /// Create a fork node.
+    pub fn fork(name: impl Into<String>) -> Self {
+        ActivityNodeIR {
+            kind: ActivityNodeKind::Fork,
+            ..Self::action(name)
+        }
+    }
+
+    /// Create a join node.
+    pub fn join(name: impl Into<String>) -> Self {
+        ActivityNodeIR {
+            kind: ActivityNodeKind::Join,
+            ..Self::action(name)
+        }
+    }
+
+    /// Set the action body.
+    pub fn with_body(mut self, action: impl Into<ActionIR>) -> Self {
+        self.action = Some(action.into());
+        self
+    }
+
+    /// Check if this is a control node rather than an action.
+    pub fn is_control(&self) -> bool {
+        self.kind != ActivityNodeKind::Action
+    }
+}
+
+/// IR for a succession: `to` starts after `from` finishes.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SuccessionIR {
+    /// The earlier node.
+    pub from: String,
+    /// The later node.
+    pub to: String,
+}
+
+impl SuccessionIR {
+    /// Create a new succession.
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        SuccessionIR {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+}
+
 /// IR for a constraint.
 #[derive(Debug, Clone)]
 pub struct ConstraintIR {
@@ -585,6 +1329,60 @@ mod tests {
         assert!(t2.matches(None));
     }
 
+    #[test]
+    fn matching_transitions_ordered_by_priority() {
+        let ir = StateMachineIR::new("Test", "s1")
+            .with_transition(TransitionIR::new("s1", "a").with_event("go"))
+            .with_transition(TransitionIR::new("s1", "b").with_event("go").with_guard("x > 1"))
+            .with_transition(TransitionIR::new("s1", "c").with_event("go").with_priority(5))
+            .with_transition(TransitionIR::new("s1", "d").with_event("go"))
+            .with_transition(TransitionIR::new("s1", "e").with_event("stop").with_priority(10));
+
+        let targets: Vec<_> = ir
+            .matching_transitions("s1", Some("go"))
+            .iter()
+            .map(|t| t.to.as_str())
+            .collect();
+        assert_eq!(targets, vec!["c", "b", "a", "d"]);
+    }
+
+    #[test]
+    fn timed_transitions_ordered_by_delay() {
+        let ir = StateMachineIR::new("Test", "s1")
+            .with_transition(TransitionIR::new("s1", "slow").with_after(10.0))
+            .with_transition(TransitionIR::new("s1", "event").with_event("go"))
+            .with_transition(TransitionIR::new("s1", "fast").with_after(2.0))
+            .with_transition(TransitionIR::new("s1", "urgent").with_after(10.0).with_priority(1));
+
+        let targets: Vec<_> = ir.timed_transitions("s1").iter().map(|t| t.to.as_str()).collect();
+        assert_eq!(targets, vec!["fast", "urgent", "slow"]);
+
+        // Timed transitions are not auto-transitions
+        let auto: Vec<_> = ir.matching_transitions("s1", None).iter().map(|t| t.to.as_str()).collect();
+        assert!(auto.is_empty());
+    }
+
+    #[test]
+    fn nested_state_lookup() {
+        let ir = StateMachineIR::new("M", "off")
+            .with_state(StateIR::new("off"))
+            .with_state(
+                StateIR::new("on")
+                    .with_substate(StateIR::new("low"))
+                    .with_substate(StateIR::new("high").with_substate(StateIR::new("boost")))
+                    .with_initial_substate("high"),
+            );
+
+        let on = ir.find_state("on").unwrap();
+        assert!(on.is_composite());
+        assert_eq!(on.initial().map(|s| s.name.as_str()), Some("high"));
+        assert!(ir.find_state("boost").is_some());
+
+        let path: Vec<_> = ir.state_path("boost").unwrap().iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(path, vec!["on", "high", "boost"]);
+        assert!(ir.state_path("missing").is_none());
+    }
+
     #[test]
     fn state_with_actions() {
         let state = StateIR::new("running")
@@ -595,6 +1393,17 @@ mod tests {
         assert_eq!(state.exit_action.as_ref().and_then(|a| a.as_simple()), Some("onExit()"));
     }
 
+    #[test]
+    fn state_with_do_action() {
+        let state = StateIR::new("running").with_do("poll()");
+        assert_eq!(state.do_action.as_ref().and_then(|a| a.as_simple()), Some("poll()"));
+        assert!(state.entry_action.is_none());
+
+        let state = StateIR::new("counting")
+            .with_do_action(ActionIR::structured(vec![AssignmentIR::add("ticks", 1.0)], vec![]));
+        assert!(!state.do_action.as_ref().unwrap().is_simple());
+    }
+
     #[test]
     fn parallel_step_result() {
         let result = ParallelStepResult::new()
@@ -643,10 +1452,44 @@ mod tests {
         let set = AssignmentIR::set("x", 5.0);
         assert_eq!(set.variable, "x");
         assert_eq!(set.operator, AssignmentOp::Set);
-        assert_eq!(set.value, 5.0);
+        assert_eq!(set.value, ValueRef::Literal(5.0));
 
         let add = AssignmentIR::add("t", 10.0);
         assert_eq!(add.operator, AssignmentOp::Add);
+        assert_eq!(add.apply(1.0, |_| None), 11.0);
+
+        let copy = AssignmentIR::set("y", ValueRef::variable("x"));
+        assert_eq!(copy.value.as_variable(), Some("x"));
+        assert_eq!(copy.apply(0.0, |name| (name == "x").then_some(3.0)), 3.0);
+        assert_eq!(copy.apply(7.0, |_| None), 0.0);
+        assert_eq!(copy.value.to_string(), "x");
+
+        let halve = AssignmentIR::divide("rate", 4.0);
+        assert_eq!(halve.apply(6.0, |_| None), 1.5);
+        assert_eq!(AssignmentIR::multiply("x", 2.0).apply(6.0, |_| None), 12.0);
+    }
+
+    #[test]
+    fn binary_value_ref() {
+        // t = (u + 5) * k - 1
+        let sum = ValueRef::binary(BinaryOp::Add, ValueRef::variable("u"), 5.0);
+        let value = ValueRef::binary(
+            BinaryOp::Subtract,
+            ValueRef::binary(BinaryOp::Multiply, sum, ValueRef::variable("k")),
+            1.0,
+        );
+        let lookup = |name: &str| match name {
+            "u" => Some(1.0),
+            "k" => Some(3.0),
+            _ => None,
+        };
+        assert_eq!(value.resolve(lookup), 17.0);
+        assert_eq!(value.to_string(), "(u + 5) * k - 1");
+        assert_eq!(value.as_literal(), None);
+
+        let grouped = ValueRef::binary(BinaryOp::Subtract, 10.0, ValueRef::binary(BinaryOp::Subtract, 4.0, 1.0));
+        assert_eq!(grouped.resolve(|_| None), 7.0);
+        assert_eq!(grouped.to_string(), "10 - (4 - 1)");
     }
 
     #[test]
@@ -669,4 +1512,119 @@ mod tests {
         assert_eq!(constraint.expr, "speed < 100");
         assert!(constraint.description.is_some());
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn state_machine_ir_json_roundtrip() {
+        let ir = StateMachineIR::new("Pump", "idle")
+            .with_state(StateIR::new("idle"))
+            .with_state(
+                StateIR::new("running")
+                    .with_entry("startMotor()")
+                    .with_do_action(ActionIR::structured(vec![AssignmentIR::add("ticks", 1.0)], vec![]))
+                    .with_exit_action(ActionIR::structured(
+                        vec![AssignmentIR::set("rpm", 0.0), AssignmentIR::subtract("load", 2.5)],
+                        vec!["stopped".to_string()],
+                    )),
+            )
+            .with_state(StateIR::new("done").final_state())
+            .with_transition(
+                TransitionIR::new("idle", "running")
+                    .with_event("start")
+                    .with_guard("pressure > 2")
+                    .with_priority(3),
+            )
+            .with_transition(TransitionIR::new("running", "done").with_event("stop"));
+
+        let json = ir.to_json().unwrap();
+        let restored = StateMachineIR::from_json(&json).unwrap();
+        assert_eq!(restored, ir);
+
+        let parallel = StateMachineIR::parallel("Grid").with_region(
+            RegionIR::new("relay", "closed")
+                .with_state(StateIR::new("closed"))
+                .with_state(StateIR::new("open"))
+                .with_transition(TransitionIR::new("closed", "open").with_event("trip")),
+        );
+        let restored = StateMachineIR::from_json(&parallel.to_json().unwrap()).unwrap();
+        assert_eq!(restored, parallel);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn action_ir_json_representation() {
+        let simple = serde_json::to_value(ActionIR::simple("beep()")).unwrap();
+        assert_eq!(simple, serde_json::json!({"type": "simple", "data": "beep()"}));
+
+        let structured = serde_json::to_value(ActionIR::structured(
+            vec![AssignmentIR::add("t", 10.0), AssignmentIR::set("u", ValueRef::variable("t"))],
+            vec!["tick".to_string()],
+        ))
+        .unwrap();
+        assert_eq!(
+            structured,
+            serde_json::json!({
+                "type": "structured",
+                "data": {
+                    "assignments": [
+                        {"variable": "t", "operator": "add", "value": 10.0},
+                        {"variable": "u", "operator": "set", "value": "t"}
+                    ],
+                    "sends": ["tick"]
+                }
+            })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn state_machine_ir_json_defaults_optional_fields() {
+        // Caches written before priorities, do actions, and regions existed still load
+        let json = r#"{
+            "name": "Old",
+            "initial": "a",
+            "states": [{"name": "a", "entry_action": null, "exit_action": null, "is_final": false}],
+            "transitions": [{"from": "a", "to": "a", "event": "e", "guard": null, "action": null}]
+        }"#;
+        let ir = StateMachineIR::from_json(json).unwrap();
+        assert_eq!(ir.transitions[0].priority, 0);
+        assert!(ir.states[0].do_action.is_none());
+        assert!(ir.regions.is_empty());
+    }
+
+    #[test]
+    fn trace_log_drops_oldest_when_full() {
+        let mut log = TraceLog::new(2);
+        for i in 0..3 {
+            log.push(TraceEvent::EventIgnored {
+                event: format!("e{}", i),
+                state: "s".to_string(),
+            });
+        }
+
+        let events: Vec<_> = log
+            .events()
+            .iter()
+            .map(|e| match e {
+                TraceEvent::EventIgnored { event, .. } => event.as_str(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(events, vec!["e1", "e2"]);
+
+        log.set_capacity(1);
+        assert_eq!(log.events().len(), 1);
+        assert_eq!(log.take().len(), 1);
+        assert!(log.events().is_empty());
+    }
+
+    #[test]
+    fn trace_log_zero_capacity_disables_tracing() {
+        let mut log = TraceLog::new(0);
+        log.push(TraceEvent::ActionExecuted {
+            kind: ActionKind::Entry,
+            detail: "x = 1".to_string(),
+        });
+        assert!(log.events().is_empty());
+    }
 }
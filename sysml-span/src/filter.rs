@@ -0,0 +1,455 @@
+//! Project-level diagnostic filtering.
+//!
+//! A [`DiagnosticFilter`] is an ordered list of rules. Each rule matches
+//! diagnostics by code, by a substring of the message, or both, and either
+//! suppresses them or changes their severity. The first matching rule wins.
+//!
+//! Filters can be built in code or read from a config string, either a
+//! small subset of TOML or (with the `serde` feature) JSON:
+//!
+//! ```toml
+//! # Orphans are expected in library fragments
+//! [[rules]]
+//! code = "E001"
+//! action = "suppress"
+//!
+//! [[rules]]
+//! code = "E200"
+//! action = "downgrade"
+//! severity = "warning"
+//! ```
+//!
+//! The JSON form is `{"rules": [{"code": "E001", "action": "suppress"}]}`.
+
+use crate::{Diagnostic, Severity};
+
+/// Diagnostic codes emitted by the SysML crates of this workspace.
+///
+/// Config rules naming any other code still apply, but produce a warning
+/// since they are most likely a typo.
+pub const KNOWN_CODES: &[&str] = &[
+    "E001", "E002", "E003", "E004", "E005", "E006", "E007", "E008", "E009", "E200", "V001", "V002", "V003", "V004",
+    "V005", "V007", "V008", "V009", "V010", "V011", "W200",
+];
+
+/// What a [`FilterRule`] does with the diagnostics it matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterAction {
+    /// Drop the diagnostic.
+    Suppress,
+    /// Lower the severity to at most the given level.
+    Downgrade(Severity),
+    /// Raise the severity to at least the given level.
+    Upgrade(Severity),
+}
+
+/// A rule matching diagnostics by code and/or message substring.
+///
+/// A rule without a code or message matches every diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterRule {
+    /// The diagnostic code to match exactly.
+    pub code: Option<String>,
+    /// A substring the diagnostic message must contain.
+    pub message: Option<String>,
+    /// The action applied to matching diagnostics.
+    pub action: FilterAction,
+}
+
+impl FilterRule {
+    /// Create a rule matching diagnostics with a code.
+    pub fn code(code: impl Into<String>, action: FilterAction) -> Self {
+        FilterRule {
+            code: Some(code.into()),
+            message: None,
+            action,
+        }
+    }
+
+    /// Create a rule matching diagnostics whose message contains a substring.
+    pub fn message(substring: impl Into<String>, action: FilterAction) -> Self {
+        FilterRule {
+            code: None,
+            message: Some(substring.into()),
+            action,
+        }
+    }
+
+    /// Additionally require the message to contain a substring.
+    pub fn with_message(mut self, substring: impl Into<String>) -> Self {
+        self.message = Some(substring.into());
+        self
+    }
+
+    /// Check if the rule applies to a diagnostic.
+    pub fn matches(&self, diag: &Diagnostic) -> bool {
+        let code_matches = match &self.code {
+            Some(code) => diag.code.as_deref() == Some(code.as_str()),
+            None => true,
+        };
+        let message_matches = match &self.message {
+            Some(substring) => diag.message.contains(substring.as_str()),
+            None => true,
+        };
+        code_matches && message_matches
+    }
+}
+
+/// An ordered set of rules that suppress or re-grade diagnostics.
+///
+/// # Examples
+///
+/// ```
+/// use sysml_span::{Diagnostic, DiagnosticFilter, FilterAction, FilterRule, Severity};
+///
+/// let filter = DiagnosticFilter::new()
+///     .with_rule(FilterRule::code("E001", FilterAction::Suppress))
+///     .with_rule(FilterRule::code("E200", FilterAction::Downgrade(Severity::Warning)));
+///
+/// let mut diags = vec![
+///     Diagnostic::error("orphan element").with_code("E001"),
+///     Diagnostic::error("unresolved reference").with_code("E200"),
+/// ];
+/// filter.apply(&mut diags);
+///
+/// assert_eq!(diags.len(), 1);
+/// assert_eq!(diags[0].severity, Severity::Warning);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagnosticFilter {
+    rules: Vec<FilterRule>,
+}
+
+impl DiagnosticFilter {
+    /// Create a filter without rules, which keeps diagnostics unchanged.
+    pub fn new() -> Self {
+        DiagnosticFilter { rules: Vec::new() }
+    }
+
+    /// Add a rule, matched after the existing ones.
+    pub fn with_rule(mut self, rule: FilterRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Get the rules in matching order.
+    pub fn rules(&self) -> &[FilterRule] {
+        &self.rules
+    }
+
+    /// Check if the filter has no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Apply the first matching rule to each diagnostic, removing the
+    /// suppressed ones.
+    pub fn apply(&self, diags: &mut Vec<Diagnostic>) {
+        if self.rules.is_empty() {
+            return;
+        }
+        diags.retain_mut(|diag| match self.rules.iter().find(|rule| rule.matches(diag)) {
+            Some(rule) => match rule.action {
+                FilterAction::Suppress => false,
+                FilterAction::Downgrade(severity) => {
+                    diag.severity = diag.severity.min(severity);
+                    true
+                }
+                FilterAction::Upgrade(severity) => {
+                    diag.severity = diag.severity.max(severity);
+                    true
+                }
+            },
+            None => true,
+        });
+    }
+
+    /// Read a filter from a TOML or JSON config string.
+    ///
+    /// JSON is recognised by a leading `{` and needs the `serde` feature.
+    /// Malformed configs, unknown actions and unknown severities are errors;
+    /// rules naming a code outside [`KNOWN_CODES`] are kept and reported in
+    /// the returned warnings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_span::DiagnosticFilter;
+    ///
+    /// let config = r#"
+    /// [[rules]]
+    /// code = "E001"
+    /// action = "suppress"
+    ///
+    /// [[rules]]
+    /// code = "E999"
+    /// action = "upgrade"
+    /// severity = "error"
+    /// "#;
+    ///
+    /// let (filter, warnings) = DiagnosticFilter::from_config(config).unwrap();
+    /// assert_eq!(filter.rules().len(), 2);
+    /// assert_eq!(warnings.len(), 1);
+    /// ```
+    pub fn from_config(text: &str) -> Result<(Self, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let to_errors = |message: String| vec![Diagnostic::error(message)];
+        let raw_rules = if text.trim_start().starts_with('{') {
+            parse_json(text).map_err(to_errors)?
+        } else {
+            parse_toml(text).map_err(to_errors)?
+        };
+
+        let mut filter = DiagnosticFilter::new();
+        let mut warnings = Vec::new();
+        for (index, raw) in raw_rules.iter().enumerate() {
+            let rule = raw.to_rule(index + 1).map_err(to_errors)?;
+            if let Some(code) = &rule.code {
+                if !KNOWN_CODES.contains(&code.as_str()) {
+                    warnings.push(
+                        Diagnostic::warning(format!("filter rule {} names unknown diagnostic code '{}'", index + 1, code))
+                            .with_note("the rule is kept, but may never match"),
+                    );
+                }
+            }
+            filter.rules.push(rule);
+        }
+        Ok((filter, warnings))
+    }
+}
+
+/// A rule as written in a config, before its values are checked.
+#[derive(Debug, Default)]
+struct RawRule {
+    code: Option<String>,
+    message: Option<String>,
+    action: Option<String>,
+    severity: Option<String>,
+}
+
+impl RawRule {
+    fn set(&mut self, key: &str, value: String) -> Result<(), String> {
+        let slot = match key {
+            "code" => &mut self.code,
+            "message" => &mut self.message,
+            "action" => &mut self.action,
+            "severity" => &mut self.severity,
+            _ => return Err(format!("unknown key '{}'", key)),
+        };
+        *slot = Some(value);
+        Ok(())
+    }
+
+    fn to_rule(&self, number: usize) -> Result<FilterRule, String> {
+        let invalid = |message: String| format!("invalid filter rule {}: {}", number, message);
+
+        let severity = match self.severity.as_deref() {
+            Some(name) => Some(parse_severity(name).ok_or_else(|| invalid(format!("unknown severity '{}'", name)))?),
+            None => None,
+        };
+        let action = match (self.action.as_deref(), severity) {
+            (Some("suppress"), None) => FilterAction::Suppress,
+            (Some("suppress"), Some(_)) => return Err(invalid("'suppress' takes no severity".to_string())),
+            (Some("downgrade"), Some(severity)) => FilterAction::Downgrade(severity),
+            (Some("upgrade"), Some(severity)) => FilterAction::Upgrade(severity),
+            (Some(action @ ("downgrade" | "upgrade")), None) => {
+                return Err(invalid(format!("'{}' needs a severity", action)))
+            }
+            (Some(action), _) => return Err(invalid(format!("unknown action '{}'", action))),
+            (None, _) => return Err(invalid("missing action".to_string())),
+        };
+
+        Ok(FilterRule {
+            code: self.code.clone(),
+            message: self.message.clone(),
+            action,
+        })
+    }
+}
+
+fn parse_severity(name: &str) -> Option<Severity> {
+    match name {
+        "error" => Some(Severity::Error),
+        "warning" => Some(Severity::Warning),
+        "info" => Some(Severity::Info),
+        _ => None,
+    }
+}
+
+/// Parse the TOML subset used by filter configs: `[[rules]]` tables of
+/// `key = "string"` pairs, with `#` comments.
+fn parse_toml(text: &str) -> Result<Vec<RawRule>, String> {
+    let mut rules: Vec<RawRule> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let error = |message: &str| format!("filter config line {}: {}", index + 1, message);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("[[rules]]") {
+            if !rest.is_empty() && !rest.trim_start().starts_with('#') {
+                return Err(error("unexpected text after '[[rules]]'"));
+            }
+            rules.push(RawRule::default());
+            continue;
+        }
+
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => return Err(error("expected '[[rules]]' or 'key = \"value\"'")),
+        };
+        let value = parse_toml_string(value).ok_or_else(|| error("expected a quoted string value"))?;
+        match rules.last_mut() {
+            Some(rule) => rule.set(key, value).map_err(|message| error(&message))?,
+            None => return Err(error("key outside of a '[[rules]]' table")),
+        }
+    }
+    Ok(rules)
+}
+
+/// Parse a basic TOML string, allowing a trailing comment.
+fn parse_toml_string(text: &str) -> Option<String> {
+    let mut chars = text.strip_prefix('"')?.chars();
+    let mut value = String::new();
+    loop {
+        match chars.next()? {
+            '"' => break,
+            '\\' => match chars.next()? {
+                '"' => value.push('"'),
+                '\\' => value.push('\\'),
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                _ => return None,
+            },
+            c => value.push(c),
+        }
+    }
+    let rest = chars.as_str().trim_start();
+    if rest.is_empty() || rest.starts_with('#') {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "serde")]
+fn parse_json(text: &str) -> Result<Vec<RawRule>, String> {
+    use serde_json::Value;
+
+    let error = |message: String| format!("invalid filter config: {}", message);
+    let value: Value = serde_json::from_str(text).map_err(|e| error(e.to_string()))?;
+    let rules = match value.get("rules") {
+        Some(Value::Array(rules)) => rules,
+        Some(_) => return Err(error("'rules' must be an array".to_string())),
+        None => return Ok(Vec::new()),
+    };
+
+    let mut raw_rules = Vec::new();
+    for (index, rule) in rules.iter().enumerate() {
+        let fields = match rule {
+            Value::Object(fields) => fields,
+            _ => return Err(error(format!("rule {} must be an object", index + 1))),
+        };
+        let mut raw = RawRule::default();
+        for (key, value) in fields {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                _ => return Err(error(format!("rule {}: '{}' must be a string", index + 1, key))),
+            };
+            raw.set(key, value)
+                .map_err(|message| error(format!("rule {}: {}", index + 1, message)))?;
+        }
+        raw_rules.push(raw);
+    }
+    Ok(raw_rules)
+}
+
+#[cfg(not(feature = "serde"))]
+fn parse_json(_text: &str) -> Result<Vec<RawRule>, String> {
+    Err("JSON filter configs need the `serde` feature of sysml-span".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diags() -> Vec<Diagnostic> {
+        vec![
+            Diagnostic::error("orphan element 'a'").with_code("E001"),
+            Diagnostic::error("unresolved reference 'Engine'").with_code("E200"),
+            Diagnostic::warning("unused import").with_code("V003"),
+        ]
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let filter = DiagnosticFilter::new()
+            .with_rule(FilterRule::code("E200", FilterAction::Downgrade(Severity::Info)).with_message("Engine"))
+            .with_rule(FilterRule::code("E200", FilterAction::Suppress))
+            .with_rule(FilterRule::message("unused", FilterAction::Upgrade(Severity::Error)))
+            .with_rule(FilterRule::code("E001", FilterAction::Upgrade(Severity::Warning)));
+
+        let mut diags = diags();
+        filter.apply(&mut diags);
+
+        let severities: Vec<_> = diags.iter().map(|d| d.severity).collect();
+        // Upgrading never lowers: the E001 error stays an error
+        assert_eq!(severities, vec![Severity::Error, Severity::Info, Severity::Error]);
+    }
+
+    #[test]
+    fn toml_config_with_comments() {
+        let config = r#"
+            # project overrides
+            [[rules]]  # orphans
+            code = "E001"
+            action = "suppress"
+
+            [[rules]]
+            message = "say \"hi\""   # escaped quotes
+            action = "downgrade"
+            severity = "info"
+        "#;
+        let (filter, warnings) = DiagnosticFilter::from_config(config).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(
+            filter.rules(),
+            &[
+                FilterRule::code("E001", FilterAction::Suppress),
+                FilterRule::message("say \"hi\"", FilterAction::Downgrade(Severity::Info)),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_configs_are_errors() {
+        let cases = [
+            ("code = \"E001\"", "outside of a '[[rules]]' table"),
+            ("[[rules]]\naction = suppress", "line 2: expected a quoted string value"),
+            ("[[rules]]\nlevel = \"x\"", "unknown key 'level'"),
+            ("[[rules]]\ncode = \"E001\"", "missing action"),
+            ("[[rules]]\naction = \"downgrade\"", "'downgrade' needs a severity"),
+            ("[[rules]]\naction = \"upgrade\"\nseverity = \"fatal\"", "unknown severity 'fatal'"),
+        ];
+        for (config, expected) in cases {
+            let errors = DiagnosticFilter::from_config(config).unwrap_err();
+            assert!(errors[0].message.contains(expected), "{}: {}", config, errors[0].message);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_config_warns_about_unknown_codes() {
+        let config = r#"{"rules": [
+            {"code": "E200", "action": "downgrade", "severity": "warning"},
+            {"code": "X123", "action": "suppress"}
+        ]}"#;
+        let (filter, warnings) = DiagnosticFilter::from_config(config).unwrap();
+        assert_eq!(filter.rules().len(), 2);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("'X123'"));
+
+        let mut diags = diags();
+        filter.apply(&mut diags);
+        assert_eq!(diags[1].severity, Severity::Warning);
+    }
+}
@@ -0,0 +1,186 @@
+//! Stable JSON output for diagnostics.
+//!
+//! Unlike the derived serde representation, the shape written here is part
+//! of the crate's interface: every key is always present (`null` when a value
+//! is unknown), so CI scripts can read it without probing.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use crate::{Diagnostic, LineIndex, SourceProvider, Span};
+
+/// Serializer for diagnostics as stable JSON.
+///
+/// Each diagnostic becomes an object with `severity`, `code`, `message`,
+/// `span`, `notes` and `related` keys; spans carry `file`, `start`, `end`,
+/// `line`, `col`, `endLine` and `endCol`. When a source provider is set,
+/// spans without line and column numbers get them computed from the source
+/// text.
+///
+/// # Examples
+///
+/// ```
+/// use sysml_span::{Diagnostic, DiagnosticJson, Span};
+///
+/// let diags = vec![Diagnostic::error("unexpected token")
+///     .with_code("E001")
+///     .with_span(Span::with_location("file.sysml", 10, 14, 2, 5))];
+///
+/// let json = DiagnosticJson::new().to_value(&diags);
+/// assert_eq!(json[0]["code"], "E001");
+/// assert_eq!(json[0]["span"]["line"], 2);
+/// assert!(json[0]["span"]["endLine"].is_null());
+/// ```
+#[derive(Default)]
+pub struct DiagnosticJson<'a> {
+    source: Option<&'a dyn SourceProvider>,
+}
+
+impl<'a> DiagnosticJson<'a> {
+    /// Create a serializer that writes spans as they are.
+    pub fn new() -> Self {
+        DiagnosticJson { source: None }
+    }
+
+    /// Compute missing line and column numbers from the provided sources.
+    pub fn with_source(mut self, source: &'a dyn SourceProvider) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Convert diagnostics to a JSON array.
+    pub fn to_value(&self, diags: &[Diagnostic]) -> Value {
+        let mut line_cols = LineCols::new(self.source);
+        Value::Array(diags.iter().map(|diag| diagnostic_value(diag, &mut line_cols)).collect())
+    }
+
+    /// Convert diagnostics to a pretty-printed JSON array.
+    pub fn to_json(&self, diags: &[Diagnostic]) -> String {
+        serde_json::to_string_pretty(&self.to_value(diags)).expect("JSON values always serialize")
+    }
+}
+
+fn diagnostic_value(diag: &Diagnostic, line_cols: &mut LineCols<'_>) -> Value {
+    let related: Vec<Value> = diag
+        .related
+        .iter()
+        .map(|related| {
+            json!({
+                "message": related.message,
+                "span": span_value(&line_cols.resolve(&related.span)),
+            })
+        })
+        .collect();
+
+    json!({
+        "severity": diag.severity,
+        "code": diag.code,
+        "message": diag.message,
+        "span": diag.span.as_ref().map(|span| span_value(&line_cols.resolve(span))),
+        "notes": diag.notes,
+        "related": related,
+    })
+}
+
+fn span_value(span: &Span) -> Value {
+    json!({
+        "file": span.file,
+        "start": span.start,
+        "end": span.end,
+        "line": span.line,
+        "col": span.col,
+        "endLine": span.end_line,
+        "endCol": span.end_col,
+    })
+}
+
+/// Fills in missing line and column numbers from source text, indexing each
+/// file at most once.
+pub(crate) struct LineCols<'a> {
+    source: Option<&'a dyn SourceProvider>,
+    indexes: HashMap<String, Option<LineIndex>>,
+}
+
+impl<'a> LineCols<'a> {
+    pub(crate) fn new(source: Option<&'a dyn SourceProvider>) -> Self {
+        LineCols {
+            source,
+            indexes: HashMap::new(),
+        }
+    }
+
+    /// The span with line and column numbers, if it lacks them and the
+    /// source of its file is available.
+    pub(crate) fn resolve(&mut self, span: &Span) -> Span {
+        let mut span = span.clone();
+        let source = match self.source {
+            Some(source) if span.line.is_none() => source,
+            _ => return span,
+        };
+        let index = self
+            .indexes
+            .entry(span.file.clone())
+            .or_insert_with(|| source.source(&span.file).map(LineIndex::new));
+        if let Some(index) = index {
+            span.with_line_cols(index);
+        }
+        span
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Severity;
+
+    struct Files(Vec<(&'static str, &'static str)>);
+
+    impl SourceProvider for Files {
+        fn source(&self, file: &str) -> Option<&str> {
+            self.0.iter().find(|(name, _)| *name == file).map(|(_, text)| *text)
+        }
+    }
+
+    #[test]
+    fn every_key_is_present() {
+        let json = DiagnosticJson::new().to_value(&[Diagnostic::info("no span")]);
+        assert_eq!(
+            json,
+            json!([{
+                "severity": "info",
+                "code": null,
+                "message": "no span",
+                "span": null,
+                "notes": [],
+                "related": [],
+            }])
+        );
+    }
+
+    #[test]
+    fn line_cols_come_from_source_when_missing() {
+        let files = Files(vec![("a.sysml", "part a;\npart b;\n")]);
+        let diags = vec![Diagnostic::warning("unused")
+            .with_code("W001")
+            .with_note("remove it")
+            .with_span(Span::new("a.sysml", 13, 14))
+            .with_related(Span::new("b.sysml", 0, 1), "other file")];
+
+        let without = DiagnosticJson::new().to_value(&diags);
+        assert!(without[0]["span"]["line"].is_null());
+
+        let json = DiagnosticJson::new().with_source(&files).to_json(&diags);
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[0]["span"]["line"], 2);
+        assert_eq!(value[0]["span"]["col"], 6);
+        assert_eq!(value[0]["span"]["endCol"], 7);
+        assert_eq!(value[0]["notes"], json!(["remove it"]));
+        // No source for the related file, so it stays without line numbers
+        assert_eq!(value[0]["related"][0]["message"], "other file");
+        assert!(value[0]["related"][0]["span"]["line"].is_null());
+
+        let severity: Severity = serde_json::from_value(value[0]["severity"].clone()).unwrap();
+        assert_eq!(severity, Severity::Warning);
+    }
+}
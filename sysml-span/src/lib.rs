@@ -7,7 +7,8 @@
 //!
 //! ## Features
 //!
-//! - `serde`: Enable serde serialization support
+//! - `serde`: Enable serde serialization support, stable JSON
+//!   ([`DiagnosticJson`]), SARIF output and JSON filter configs
 //!
 //! ## Examples
 //!
@@ -31,10 +32,28 @@ use std::fmt;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+mod filter;
+pub use filter::{DiagnosticFilter, FilterAction, FilterRule, KNOWN_CODES};
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "serde")]
+pub use json::DiagnosticJson;
 #[cfg(feature = "pretty")]
 mod pretty;
 #[cfg(feature = "pretty")]
-pub use pretty::{DiagnosticRenderer, HashMapSourceProvider, SourceProvider};
+pub use pretty::{DiagnosticRenderer, FileSystemSourceProvider, HashMapSourceProvider};
+#[cfg(feature = "serde")]
+mod sarif;
+#[cfg(feature = "serde")]
+pub use sarif::{diagnostics_to_sarif, to_sarif, to_sarif_with_source, SARIF_SCHEMA, SARIF_VERSION};
+
+/// Source provider for diagnostic rendering and for filling in missing line
+/// and column numbers.
+///
+/// Implementations should return the full source text for the requested file.
+pub trait SourceProvider {
+    fn source(&self, file: &str) -> Option<&str>;
+}
 
 /// A span representing a range in a source file.
 ///
@@ -66,6 +85,12 @@ pub struct Span {
     pub line: Option<u32>,
     /// Start column number (1-indexed, optional).
     pub col: Option<u32>,
+    /// End line number (1-indexed, optional).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub end_line: Option<u32>,
+    /// End column number (1-indexed, exclusive, optional).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub end_col: Option<u32>,
 }
 
 impl Span {
@@ -88,6 +113,8 @@ impl Span {
             end,
             line: None,
             col: None,
+            end_line: None,
+            end_col: None,
         }
     }
 
@@ -114,6 +141,37 @@ impl Span {
             end,
             line: Some(line),
             col: Some(col),
+            end_line: None,
+            end_col: None,
+        }
+    }
+
+    /// Create a span with start and end line and column information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_span::Span;
+    ///
+    /// let span = Span::with_full_location("file.sysml", 10, 48, (2, 1), (4, 2));
+    /// assert_eq!((span.line, span.col), (Some(2), Some(1)));
+    /// assert_eq!((span.end_line, span.end_col), (Some(4), Some(2)));
+    /// ```
+    pub fn with_full_location(
+        file: impl Into<String>,
+        start: usize,
+        end: usize,
+        (line, col): (u32, u32),
+        (end_line, end_col): (u32, u32),
+    ) -> Self {
+        Span {
+            file: file.into(),
+            start,
+            end,
+            line: Some(line),
+            col: Some(col),
+            end_line: Some(end_line),
+            end_col: Some(end_col),
         }
     }
 
@@ -150,6 +208,8 @@ impl Span {
             end: 0,
             line: None,
             col: None,
+            end_line: None,
+            end_col: None,
         }
     }
 
@@ -203,6 +263,9 @@ impl Span {
 
     /// Merge two spans into one covering both.
     ///
+    /// The start line and column come from the span that starts first, and
+    /// the end line and column from the one that ends last.
+    ///
     /// # Examples
     ///
     /// ```
@@ -215,14 +278,45 @@ impl Span {
     /// assert_eq!(merged.end, 30);
     /// ```
     pub fn merge(&self, other: &Span) -> Span {
+        let (first, second) = if other.start < self.start { (other, self) } else { (self, other) };
+        let (last, earlier) = if other.end > self.end { (other, self) } else { (self, other) };
         Span {
             file: self.file.clone(),
-            start: self.start.min(other.start),
-            end: self.end.max(other.end),
-            line: self.line.or(other.line),
-            col: self.col.or(other.col),
+            start: first.start,
+            end: last.end,
+            line: first.line.or(second.line),
+            col: first.col.or(second.col),
+            end_line: last.end_line.or(earlier.end_line),
+            end_col: last.end_col.or(earlier.end_col),
         }
     }
+
+    /// Fill in start and end line/column information from a line index.
+    ///
+    /// Overwrites `line`, `col`, `end_line` and `end_col` using the span's
+    /// byte offsets, so multi-line spans carry a correct end position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_span::{LineIndex, Span};
+    ///
+    /// let source = "part def A {\n    part b;\n}";
+    /// let index = LineIndex::new(source);
+    ///
+    /// let mut span = Span::new("file.sysml", 0, source.len());
+    /// span.with_line_cols(&index);
+    /// assert_eq!((span.line, span.col), (Some(1), Some(1)));
+    /// assert_eq!((span.end_line, span.end_col), (Some(3), Some(2)));
+    /// ```
+    pub fn with_line_cols(&mut self, index: &LineIndex) {
+        let (line, col) = index.line_col(self.start);
+        let (end_line, end_col) = index.line_col(self.end);
+        self.line = Some(line);
+        self.col = Some(col);
+        self.end_line = Some(end_line);
+        self.end_col = Some(end_col);
+    }
 }
 
 impl fmt::Display for Span {
@@ -784,6 +878,82 @@ impl Diagnostics {
     pub fn len(&self) -> usize {
         self.items.len()
     }
+
+    /// Sort diagnostics by file, then line, then column.
+    ///
+    /// Diagnostics without a span sort last, and within a file, spans without
+    /// line information sort after located ones (by byte offset). The sort is
+    /// stable, so diagnostics at the same location keep their insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_span::{Diagnostic, Diagnostics, Span};
+    ///
+    /// let mut diags = Diagnostics::new();
+    /// diags.error("no location");
+    /// diags.push(Diagnostic::error("b").with_span(Span::with_location("b.sysml", 0, 1, 1, 1)));
+    /// diags.push(Diagnostic::error("a2").with_span(Span::with_location("a.sysml", 20, 21, 3, 1)));
+    /// diags.push(Diagnostic::error("a1").with_span(Span::with_location("a.sysml", 5, 6, 1, 6)));
+    ///
+    /// diags.sort_by_location();
+    /// let order: Vec<_> = diags.iter().map(|d| d.message.as_str()).collect();
+    /// assert_eq!(order, ["a1", "a2", "b", "no location"]);
+    /// ```
+    pub fn sort_by_location(&mut self) {
+        use std::cmp::Ordering;
+
+        self.items.sort_by(|a, b| match (&a.span, &b.span) {
+            (Some(a), Some(b)) => a
+                .file
+                .cmp(&b.file)
+                .then_with(|| a.line.is_none().cmp(&b.line.is_none()))
+                .then_with(|| (a.line, a.col, a.start).cmp(&(b.line, b.col, b.start))),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+    }
+
+    /// Get the diagnostics at or above a severity, in their current order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_span::{Diagnostic, Diagnostics, Severity};
+    ///
+    /// let mut diags = Diagnostics::new();
+    /// diags.error("error");
+    /// diags.warning("warning");
+    /// diags.push(Diagnostic::info("info"));
+    ///
+    /// assert_eq!(diags.filter_severity(Severity::Error).len(), 1);
+    /// assert_eq!(diags.filter_severity(Severity::Warning).len(), 2);
+    /// assert_eq!(diags.filter_severity(Severity::Info).len(), 3);
+    /// ```
+    pub fn filter_severity(&self, min: Severity) -> Diagnostics {
+        self.items
+            .iter()
+            .filter(|d| d.severity >= min)
+            .cloned()
+            .collect()
+    }
+
+    /// Suppress or re-grade diagnostics in place with a filter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_span::{DiagnosticFilter, Diagnostics, FilterAction, FilterRule};
+    ///
+    /// let mut diags = Diagnostics::new();
+    /// diags.push(sysml_span::Diagnostic::error("orphan").with_code("E001"));
+    /// diags.apply_filter(&DiagnosticFilter::new().with_rule(FilterRule::code("E001", FilterAction::Suppress)));
+    /// assert!(diags.is_empty());
+    /// ```
+    pub fn apply_filter(&mut self, filter: &DiagnosticFilter) {
+        filter.apply(&mut self.items);
+    }
 }
 
 impl IntoIterator for Diagnostics {
@@ -833,6 +1003,8 @@ impl Extend<Diagnostic> for Diagnostics {
 pub struct LineIndex {
     /// Byte offset of each line start. line_offsets[0] = 0 (first line starts at byte 0).
     line_offsets: Vec<usize>,
+    /// Total length of the indexed source in bytes.
+    len: usize,
 }
 
 impl LineIndex {
@@ -853,7 +1025,10 @@ impl LineIndex {
                 offsets.push(i + 1);
             }
         }
-        Self { line_offsets: offsets }
+        Self {
+            line_offsets: offsets,
+            len: source.len(),
+        }
     }
 
     /// Convert byte offset to (line, column). O(log n) via binary search.
@@ -880,6 +1055,98 @@ impl LineIndex {
         (line as u32, col as u32)
     }
 
+    /// Convert a 1-indexed (line, column) back to a byte offset.
+    ///
+    /// Returns `None` if the line is out of range. Columns past the end of the
+    /// line are clamped to the line's terminating newline (or to the end of the
+    /// source on the last line).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_span::LineIndex;
+    ///
+    /// let index = LineIndex::new("hello\nworld");
+    /// assert_eq!(index.offset_at(1, 1), Some(0));   // 'h'
+    /// assert_eq!(index.offset_at(2, 5), Some(10));  // 'd'
+    /// assert_eq!(index.offset_at(1, 99), Some(5));  // clamped to '\n'
+    /// assert_eq!(index.offset_at(3, 1), None);
+    /// ```
+    pub fn offset_at(&self, line: u32, col: u32) -> Option<usize> {
+        let line_idx = (line as usize).checked_sub(1)?;
+        let line_start = *self.line_offsets.get(line_idx)?;
+        let line_end = self
+            .line_offsets
+            .get(line_idx + 1)
+            .map(|next| next - 1)
+            .unwrap_or(self.len);
+        let col_offset = (col as usize).saturating_sub(1);
+        Some((line_start + col_offset).min(line_end))
+    }
+
+    /// Convert byte offset to (line, column) with the column counted in UTF-16 code units.
+    ///
+    /// This is the column LSP clients expect. `source` must be the text the index
+    /// was built from. Both values are 1-indexed, like [`line_col`](Self::line_col);
+    /// an offset inside a multi-byte character maps to that character's column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_span::LineIndex;
+    ///
+    /// let source = "part 属性;";
+    /// let index = LineIndex::new(source);
+    /// assert_eq!(index.line_col(11), (1, 12));       // ';' by bytes
+    /// assert_eq!(index.line_col_utf16(11, source), (1, 8));
+    /// ```
+    pub fn line_col_utf16(&self, offset: usize, source: &str) -> (u32, u32) {
+        let offset = offset.min(source.len());
+        let line = self.line_offsets.partition_point(|&o| o <= offset);
+        let line_start = self.line_offsets.get(line.saturating_sub(1)).copied().unwrap_or(0);
+        let col: usize = source[line_start..]
+            .char_indices()
+            .take_while(|(i, c)| line_start + i + c.len_utf8() <= offset)
+            .map(|(_, c)| c.len_utf16())
+            .sum();
+        (line as u32, col as u32 + 1)
+    }
+
+    /// Convert a 1-indexed (line, UTF-16 column) back to a byte offset.
+    ///
+    /// The inverse of [`line_col_utf16`](Self::line_col_utf16), with the same
+    /// clamping rules as [`offset_at`](Self::offset_at), except that a column
+    /// past the end of a `\r\n`-terminated line clamps before the `\r`. A column
+    /// that falls inside a surrogate pair maps to the start of that character.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_span::LineIndex;
+    ///
+    /// let source = "part 属性;";
+    /// let index = LineIndex::new(source);
+    /// assert_eq!(index.offset_at_utf16(1, 7, source), Some(8)); // '性'
+    /// assert_eq!(index.offset_at_utf16(1, 99, source), Some(source.len()));
+    /// ```
+    pub fn offset_at_utf16(&self, line: u32, utf16_col: u32, source: &str) -> Option<usize> {
+        let line_start = self.offset_at(line, 1)?;
+        let mut line_end = self.offset_at(line, u32::MAX)?;
+        if source[line_start..line_end].ends_with('\r') {
+            line_end -= 1;
+        }
+        let target = (utf16_col as usize).saturating_sub(1);
+
+        let mut units = 0;
+        for (i, c) in source[line_start..line_end].char_indices() {
+            if units + c.len_utf16() > target {
+                return Some(line_start + i);
+            }
+            units += c.len_utf16();
+        }
+        Some(line_end)
+    }
+
     /// Get the number of lines in the indexed source.
     pub fn line_count(&self) -> usize {
         self.line_offsets.len()
@@ -916,6 +1183,47 @@ mod tests {
         assert_eq!(merged.end, 30);
     }
 
+    #[test]
+    fn span_merge_keeps_end_position_of_later_span() {
+        let index = LineIndex::new("part a;\npart b;\n");
+        let mut a = Span::new("test.sysml", 0, 7);
+        a.with_line_cols(&index);
+        let mut b = Span::new("test.sysml", 8, 15);
+        b.with_line_cols(&index);
+
+        let merged = a.merge(&b);
+        assert_eq!((merged.line, merged.col), (Some(1), Some(1)));
+        assert_eq!((merged.end_line, merged.end_col), (Some(2), Some(8)));
+
+        // Same result with the later span first
+        let merged = b.merge(&a);
+        assert_eq!((merged.start, merged.end), (0, 15));
+        assert_eq!((merged.line, merged.col), (Some(1), Some(1)));
+        assert_eq!((merged.end_line, merged.end_col), (Some(2), Some(8)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn span_without_end_position_deserializes() {
+        let span: Span =
+            serde_json::from_str(r#"{"file": "a.sysml", "start": 3, "end": 9, "line": 1, "col": 4}"#).unwrap();
+        assert_eq!(span, Span::with_location("a.sysml", 3, 9, 1, 4));
+
+        let full = Span::with_full_location("a.sysml", 3, 40, (1, 4), (3, 2));
+        let round_trip: Span = serde_json::from_str(&serde_json::to_string(&full).unwrap()).unwrap();
+        assert_eq!(round_trip, full);
+    }
+
+    #[test]
+    fn span_with_line_cols_multiline() {
+        let source = "package P {\n    part x;\n}\n";
+        let index = LineIndex::new(source);
+        let mut span = Span::with_location("test.sysml", 16, 25, 9, 9);
+        span.with_line_cols(&index);
+        assert_eq!((span.line, span.col), (Some(2), Some(5)));
+        assert_eq!((span.end_line, span.end_col), (Some(3), Some(2)));
+    }
+
     #[test]
     fn diagnostic_error() {
         let diag = Diagnostic::error("something went wrong")
@@ -988,6 +1296,58 @@ mod tests {
 
     // LineIndex tests
 
+    #[test]
+    fn diagnostics_sort_by_location_is_stable() {
+        let at = |msg: &str, file: &str, line: u32, col: u32| {
+            Diagnostic::error(msg).with_span(Span::with_location(file, 0, 1, line, col))
+        };
+        let mut diags: Diagnostics = vec![
+            Diagnostic::warning("unlocated 1"),
+            at("b 1:1", "b.sysml", 1, 1),
+            Diagnostic::error("offset only").with_span(Span::new("a.sysml", 3, 4)),
+            at("a 2:5", "a.sysml", 2, 5),
+            at("a 2:1 first", "a.sysml", 2, 1),
+            at("a 2:1 second", "a.sysml", 2, 1),
+            at("a 10:1", "a.sysml", 10, 1),
+            Diagnostic::error("unlocated 2"),
+        ]
+        .into_iter()
+        .collect();
+
+        diags.sort_by_location();
+        let order: Vec<_> = diags.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            order,
+            [
+                "a 2:1 first",
+                "a 2:1 second",
+                "a 2:5",
+                "a 10:1",
+                "offset only",
+                "b 1:1",
+                "unlocated 1",
+                "unlocated 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostics_filter_severity_keeps_order() {
+        let diags: Diagnostics = vec![
+            Diagnostic::warning("w1"),
+            Diagnostic::info("i1"),
+            Diagnostic::error("e1"),
+            Diagnostic::warning("w2"),
+        ]
+        .into_iter()
+        .collect();
+
+        let warnings = diags.filter_severity(Severity::Warning);
+        let order: Vec<_> = warnings.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(order, ["w1", "e1", "w2"]);
+        assert_eq!(diags.len(), 4);
+    }
+
     #[test]
     fn line_index_single_line() {
         let index = LineIndex::new("hello world");
@@ -1034,4 +1394,65 @@ mod tests {
         // Line 3: "b"
         assert_eq!(index.line_col(3), (3, 1)); // 'b'
     }
+
+    #[test]
+    fn line_index_utf16_columns() {
+        // '属' and '性' are 3 bytes / 1 UTF-16 unit, '𝔸' is 4 bytes / 2 units
+        let source = "a\npart 属性 𝔸b;\n";
+        let index = LineIndex::new(source);
+        let b = source.find('b').unwrap();
+        assert_eq!(index.line_col(b), (2, 17));
+        assert_eq!(index.line_col_utf16(b, source), (2, 11));
+        assert_eq!(index.line_col_utf16(0, source), (1, 1));
+        // Inside '属' maps to the start of it
+        assert_eq!(index.line_col_utf16(8, source), (2, 6));
+
+        assert_eq!(index.offset_at_utf16(2, 11, source), Some(b));
+        assert_eq!(index.offset_at_utf16(2, 10, source), Some(b - 4)); // second half of '𝔸'
+        assert_eq!(index.offset_at_utf16(2, 99, source), Some(source.len() - 1));
+        assert_eq!(index.offset_at_utf16(4, 1, source), None);
+
+        let crlf = "a\r\nb";
+        let index = LineIndex::new(crlf);
+        assert_eq!(index.offset_at_utf16(1, 99, crlf), Some(1));
+    }
+
+    #[test]
+    fn line_index_utf16_round_trips_on_char_boundaries() {
+        let source = "package 包 {\n  part 𝔸: 属性;\n}";
+        let index = LineIndex::new(source);
+        for (offset, _) in source.char_indices() {
+            let (line, col) = index.line_col_utf16(offset, source);
+            assert_eq!(index.offset_at_utf16(line, col, source), Some(offset));
+        }
+    }
+
+    #[test]
+    fn line_index_offset_at_round_trips() {
+        let source = "part a;\n\npart def B {\n}";
+        let index = LineIndex::new(source);
+        for offset in 0..=source.len() {
+            let (line, col) = index.line_col(offset);
+            assert_eq!(index.offset_at(line, col), Some(offset));
+        }
+    }
+
+    #[test]
+    fn line_index_offset_at_last_line_without_newline() {
+        let index = LineIndex::new("a\nbc");
+        assert_eq!(index.offset_at(2, 1), Some(2));
+        assert_eq!(index.offset_at(2, 3), Some(4)); // end of source
+        assert_eq!(index.offset_at(2, 10), Some(4));
+        assert_eq!(index.offset_at(3, 1), None);
+        assert_eq!(index.offset_at(0, 1), None);
+    }
+
+    #[test]
+    fn line_index_offset_at_clamps_past_end_of_line() {
+        let index = LineIndex::new("abc\n\nd\n");
+        assert_eq!(index.offset_at(1, 10), Some(3)); // the newline after "abc"
+        assert_eq!(index.offset_at(2, 5), Some(4)); // empty line
+        assert_eq!(index.offset_at(4, 1), Some(7)); // empty line after trailing newline
+        assert_eq!(index.offset_at(4, 2), Some(7));
+    }
 }
@@ -1,15 +1,9 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use annotate_snippets::{Level, Renderer, Snippet};
 
-use crate::{Diagnostic, Severity, Span};
-
-/// Source provider for diagnostic rendering.
-///
-/// Implementations should return the full source text for the requested file.
-pub trait SourceProvider {
-    fn source(&self, file: &str) -> Option<&str>;
-}
+use crate::{Diagnostic, Severity, SourceProvider, Span};
 
 /// Simple in-memory source provider backed by a HashMap.
 #[derive(Debug, Default, Clone)]
@@ -40,6 +34,76 @@ impl SourceProvider for HashMapSourceProvider {
     }
 }
 
+/// Source provider that reads files from disk.
+///
+/// Files are read with [`load`](Self::load) or, for everything a set of
+/// diagnostics points at, [`load_diagnostics`](Self::load_diagnostics). Each
+/// `Span::file` path is read at most once; later loads, including of files that
+/// could not be read, are served from the cache. Relative paths are resolved
+/// against the root directory when one is set. Pseudo-files written in angle
+/// brackets, such as `<synthetic>`, have no source.
+#[derive(Debug, Default)]
+pub struct FileSystemSourceProvider {
+    root: Option<PathBuf>,
+    sources: HashMap<String, Option<String>>,
+}
+
+impl FileSystemSourceProvider {
+    /// Create a provider that reads span paths as given.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve relative span paths against `root`.
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: Some(root.into()),
+            sources: HashMap::new(),
+        }
+    }
+
+    /// Read `file` unless it has been loaded before, and return its source.
+    pub fn load(&mut self, file: &str) -> Option<&str> {
+        if is_pseudo_file(file) {
+            return None;
+        }
+        if !self.sources.contains_key(file) {
+            let source = self.read(file);
+            self.sources.insert(file.to_string(), source);
+        }
+        self.source(file)
+    }
+
+    /// Load every file the primary and related spans of `diagnostics` refer to.
+    pub fn load_diagnostics(&mut self, diagnostics: &[Diagnostic]) {
+        for diagnostic in diagnostics {
+            let related = diagnostic.related.iter().map(|related| &related.span);
+            for span in diagnostic.span.iter().chain(related) {
+                self.load(&span.file);
+            }
+        }
+    }
+
+    fn read(&self, file: &str) -> Option<String> {
+        let path = Path::new(file);
+        let path = match &self.root {
+            Some(root) if path.is_relative() => root.join(path),
+            _ => path.to_path_buf(),
+        };
+        std::fs::read_to_string(path).ok()
+    }
+}
+
+impl SourceProvider for FileSystemSourceProvider {
+    fn source(&self, file: &str) -> Option<&str> {
+        self.sources.get(file)?.as_deref()
+    }
+}
+
+fn is_pseudo_file(file: &str) -> bool {
+    file.starts_with('<') && file.ends_with('>')
+}
+
 /// Renderer for converting Diagnostics into annotate-snippets output.
 #[derive(Debug, Clone)]
 pub struct DiagnosticRenderer {
@@ -217,3 +281,92 @@ fn normalized_range(start: usize, end: usize, len: usize) -> std::ops::Range<usi
     }
     start..end
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sysml-span-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn renders_multi_line_spans() {
+        let source = "package P {\n    part def Broken {\n        attribute x\n    }\n}\n";
+        let start = source.find("part def").unwrap();
+        let end = source.find("    }").unwrap() + 5;
+        let diag = Diagnostic::error("malformed definition").with_span(Span::new("model.sysml", start, end));
+        let provider = HashMapSourceProvider::new().with_source("model.sysml", source);
+
+        let rendered = DiagnosticRenderer::plain().render(&diag, &provider);
+        assert!(rendered.contains("model.sysml:2:5"), "{}", rendered);
+        for line in ["part def Broken {", "attribute x", "    }"] {
+            assert!(rendered.contains(line), "{}", rendered);
+        }
+        assert!(!rendered.contains("package P"), "{}", rendered);
+    }
+
+    #[test]
+    fn file_system_provider_reads_and_caches() {
+        let dir = temp_dir("fs-cache");
+        let path = dir.join("model.sysml");
+        std::fs::write(&path, "package P;\n").unwrap();
+        let file = path.to_str().unwrap();
+
+        let mut provider = FileSystemSourceProvider::new();
+        assert_eq!(provider.source(file), None);
+        assert_eq!(provider.load(file), Some("package P;\n"));
+
+        // Later loads are served from the cache, not the changed file
+        std::fs::write(&path, "package Q;\n").unwrap();
+        assert_eq!(provider.load(file), Some("package P;\n"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(provider.source(file), Some("package P;\n"));
+    }
+
+    #[test]
+    fn file_system_provider_resolves_against_root() {
+        let dir = temp_dir("fs-root");
+        std::fs::write(dir.join("a.sysml"), "part def A;").unwrap();
+        std::fs::write(dir.join("b.sysml"), "part def B;").unwrap();
+
+        let mut provider = FileSystemSourceProvider::with_root(&dir);
+        assert_eq!(provider.load("a.sysml"), Some("part def A;"));
+        assert_eq!(provider.load("b.sysml"), Some("part def B;"));
+        assert_eq!(provider.load("missing.sysml"), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_system_provider_loads_diagnostic_files() {
+        let dir = temp_dir("fs-diagnostics");
+        std::fs::write(dir.join("a.sysml"), "part def A;\n").unwrap();
+        std::fs::write(dir.join("b.sysml"), "part def A;\n").unwrap();
+        let diag = Diagnostic::error("duplicate definition")
+            .with_span(Span::new("b.sysml", 9, 10))
+            .with_related(Span::new("a.sysml", 9, 10), "first defined here");
+
+        let mut provider = FileSystemSourceProvider::with_root(&dir);
+        provider.load_diagnostics(std::slice::from_ref(&diag));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(provider.source("a.sysml"), Some("part def A;\n"));
+        let rendered = DiagnosticRenderer::plain().render(&diag, &provider);
+        assert!(rendered.contains("b.sysml:1:10"), "{}", rendered);
+    }
+
+    #[test]
+    fn file_system_provider_skips_synthetic_files() {
+        let mut provider = FileSystemSourceProvider::new();
+        assert_eq!(provider.load(&Span::synthetic().file), None);
+
+        let diag = Diagnostic::error("no source").with_span(Span::synthetic());
+        provider.load_diagnostics(std::slice::from_ref(&diag));
+        let rendered = DiagnosticRenderer::plain().render(&diag, &provider);
+        assert_eq!(rendered, diag.to_string());
+    }
+}
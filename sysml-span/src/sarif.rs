@@ -0,0 +1,303 @@
+//! SARIF 2.1.0 output for diagnostics.
+//!
+//! SARIF is the format GitHub code scanning and most CI dashboards ingest.
+//! Each diagnostic becomes a `result`, each distinct file a run `artifact`,
+//! and each distinct diagnostic code a tool `rule`.
+
+use serde_json::{json, Map, Value};
+
+use crate::json::LineCols;
+use crate::{Diagnostic, Severity, SourceProvider, Span};
+
+/// The SARIF schema URI written to the log.
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The SARIF version written to the log.
+pub const SARIF_VERSION: &str = "2.1.0";
+
+/// Convert diagnostics into a SARIF 2.1.0 log with a single run.
+///
+/// Severities map to SARIF levels (`error`, `warning`, `note`), codes to
+/// `ruleId`, and notes are appended to the message text on separate lines.
+/// Spans become physical locations whose region carries `charOffset` and
+/// `charLength` from the byte offsets, plus the line and column fields that
+/// are present; spans without a line get no region. Related locations are
+/// emitted as `relatedLocations`. The tool is named `sysml-rs`.
+///
+/// # Examples
+///
+/// ```
+/// use sysml_span::{diagnostics_to_sarif, Diagnostic, Span};
+///
+/// let diags = vec![Diagnostic::error("unexpected token")
+///     .with_code("E001")
+///     .with_span(Span::with_location("file.sysml", 10, 14, 2, 5))];
+///
+/// let sarif = diagnostics_to_sarif(&diags);
+/// let result = &sarif["runs"][0]["results"][0];
+/// assert_eq!(result["ruleId"], "E001");
+/// assert_eq!(result["level"], "error");
+/// assert_eq!(result["locations"][0]["physicalLocation"]["region"]["charLength"], 4);
+/// ```
+pub fn diagnostics_to_sarif(diags: &[Diagnostic]) -> Value {
+    sarif_log(diags, "sysml-rs", None)
+}
+
+/// Convert diagnostics into a SARIF 2.1.0 log, as pretty-printed JSON, with
+/// the given tool name. See [`diagnostics_to_sarif`] for the mapping.
+pub fn to_sarif(diags: &[Diagnostic], tool_name: &str) -> String {
+    serde_json::to_string_pretty(&sarif_log(diags, tool_name, None)).expect("JSON values always serialize")
+}
+
+/// Like [`to_sarif`], computing the region's line and column numbers from the
+/// source text for spans that lack them.
+pub fn to_sarif_with_source(diags: &[Diagnostic], tool_name: &str, source: &dyn SourceProvider) -> String {
+    serde_json::to_string_pretty(&sarif_log(diags, tool_name, Some(source))).expect("JSON values always serialize")
+}
+
+fn sarif_log(diags: &[Diagnostic], tool_name: &str, source: Option<&dyn SourceProvider>) -> Value {
+    let mut line_cols = LineCols::new(source);
+    let mut artifacts: Vec<&str> = Vec::new();
+    let mut rules: Vec<&str> = Vec::new();
+
+    let results: Vec<Value> = diags
+        .iter()
+        .map(|diag| {
+            let mut result = Map::new();
+            if let Some(code) = &diag.code {
+                result.insert("ruleId".into(), json!(code));
+                result.insert("ruleIndex".into(), json!(index_of(&mut rules, code)));
+            }
+            result.insert("level".into(), json!(level(diag.severity)));
+            result.insert("message".into(), json!({ "text": message_text(diag) }));
+            if let Some(span) = &diag.span {
+                result.insert(
+                    "locations".into(),
+                    json!([{ "physicalLocation": physical_location(span, &mut artifacts, &mut line_cols) }]),
+                );
+            }
+            if !diag.related.is_empty() {
+                let related: Vec<Value> = diag
+                    .related
+                    .iter()
+                    .enumerate()
+                    .map(|(id, related)| {
+                        json!({
+                            "id": id,
+                            "physicalLocation": physical_location(&related.span, &mut artifacts, &mut line_cols),
+                            "message": { "text": related.message },
+                        })
+                    })
+                    .collect();
+                result.insert("relatedLocations".into(), Value::Array(related));
+            }
+            Value::Object(result)
+        })
+        .collect();
+
+    let rules: Vec<Value> = rules.iter().map(|id| json!({ "id": id })).collect();
+    let artifacts: Vec<Value> = artifacts
+        .iter()
+        .map(|uri| json!({ "location": { "uri": uri } }))
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": tool_name,
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "artifacts": artifacts,
+            "results": results,
+        }],
+    })
+}
+
+/// The SARIF level for a severity.
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    }
+}
+
+/// The message followed by each note on its own line.
+fn message_text(diag: &Diagnostic) -> String {
+    let mut text = diag.message.clone();
+    for note in &diag.notes {
+        text.push('\n');
+        text.push_str(note);
+    }
+    text
+}
+
+/// Index of `item` in `list`, appending it if not yet present.
+fn index_of<'a>(list: &mut Vec<&'a str>, item: &'a str) -> usize {
+    match list.iter().position(|existing| *existing == item) {
+        Some(index) => index,
+        None => {
+            list.push(item);
+            list.len() - 1
+        }
+    }
+}
+
+fn physical_location<'a>(span: &'a Span, artifacts: &mut Vec<&'a str>, line_cols: &mut LineCols<'_>) -> Value {
+    let mut location = Map::new();
+    location.insert(
+        "artifactLocation".into(),
+        json!({
+            "uri": span.file,
+            "index": index_of(artifacts, &span.file),
+        }),
+    );
+
+    let span = line_cols.resolve(span);
+    if span.line.is_some() {
+        let mut region = Map::new();
+        region.insert("charOffset".into(), json!(span.start));
+        region.insert("charLength".into(), json!(span.end.saturating_sub(span.start)));
+        for (key, value) in [
+            ("startLine", span.line),
+            ("startColumn", span.col),
+            ("endLine", span.end_line),
+            ("endColumn", span.end_col),
+        ] {
+            if let Some(value) = value {
+                region.insert(key.into(), json!(value));
+            }
+        }
+        location.insert("region".into(), Value::Object(region));
+    }
+
+    Value::Object(location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_log_has_one_run() {
+        let sarif = diagnostics_to_sarif(&[]);
+        assert_eq!(sarif["version"], "2.1.0");
+        assert_eq!(sarif["$schema"], SARIF_SCHEMA);
+        assert_eq!(sarif["runs"].as_array().unwrap().len(), 1);
+        assert_eq!(sarif["runs"][0]["results"], json!([]));
+        assert_eq!(sarif["runs"][0]["artifacts"], json!([]));
+    }
+
+    #[test]
+    fn maps_severity_code_message_and_region() {
+        let mut span = Span::with_location("a.sysml", 10, 14, 2, 5);
+        span.end_line = Some(2);
+        span.end_col = Some(9);
+        let diags = vec![
+            Diagnostic::warning("unused import").with_code("W010").with_span(span).with_note("remove it"),
+            Diagnostic::info("no span"),
+        ];
+
+        let sarif = diagnostics_to_sarif(&diags);
+        let results = &sarif["runs"][0]["results"];
+
+        assert_eq!(results[0]["level"], "warning");
+        assert_eq!(results[0]["ruleId"], "W010");
+        assert_eq!(results[0]["message"]["text"], "unused import\nremove it");
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "a.sysml", "index": 0 },
+                "region": {
+                    "charOffset": 10,
+                    "charLength": 4,
+                    "startLine": 2,
+                    "startColumn": 5,
+                    "endLine": 2,
+                    "endColumn": 9,
+                },
+            })
+        );
+
+        assert_eq!(results[1]["level"], "note");
+        assert!(results[1].get("ruleId").is_none());
+        assert!(results[1].get("locations").is_none());
+    }
+
+    #[test]
+    fn artifacts_and_rules_are_deduplicated() {
+        let diags = vec![
+            Diagnostic::error("first")
+                .with_code("E001")
+                .with_span(Span::new("a.sysml", 0, 1))
+                .with_related(Span::new("b.sysml", 5, 8), "defined here"),
+            Diagnostic::error("second").with_code("E002").with_span(Span::new("b.sysml", 2, 3)),
+            Diagnostic::error("third").with_code("E001").with_span(Span::new("a.sysml", 4, 6)),
+        ];
+
+        let sarif = diagnostics_to_sarif(&diags);
+        let run = &sarif["runs"][0];
+
+        assert_eq!(
+            run["artifacts"],
+            json!([{ "location": { "uri": "a.sysml" } }, { "location": { "uri": "b.sysml" } }])
+        );
+        assert_eq!(run["tool"]["driver"]["rules"], json!([{ "id": "E001" }, { "id": "E002" }]));
+        assert_eq!(run["results"][2]["ruleIndex"], 0);
+        assert_eq!(run["results"][1]["ruleIndex"], 1);
+
+        let related = &run["results"][0]["relatedLocations"][0];
+        assert_eq!(related["message"]["text"], "defined here");
+        assert_eq!(related["physicalLocation"]["artifactLocation"]["index"], 1);
+        assert_eq!(run["results"][1]["locations"][0]["physicalLocation"]["artifactLocation"]["index"], 1);
+    }
+
+    struct OneFile(&'static str, &'static str);
+
+    impl SourceProvider for OneFile {
+        fn source(&self, file: &str) -> Option<&str> {
+            (file == self.0).then_some(self.1)
+        }
+    }
+
+    #[test]
+    fn to_sarif_round_trips_and_fills_regions_from_source() {
+        let diags = vec![Diagnostic::error("duplicate definition 'A'")
+            .with_code("E020")
+            .with_span(Span::new("m.sysml", 21, 22))
+            .with_related(Span::new("m.sysml", 9, 10), "first defined here")
+            .with_related(Span::new("lib.sysml", 0, 4), "imported from here")];
+
+        let plain: Value = serde_json::from_str(&to_sarif(&diags, "sysml-check")).unwrap();
+        assert_eq!(plain["runs"][0]["tool"]["driver"]["name"], "sysml-check");
+        let location = &plain["runs"][0]["results"][0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "m.sysml");
+        assert!(location.get("region").is_none());
+
+        let source = OneFile("m.sysml", "part def A;\npart def A;\n");
+        let sarif: Value = serde_json::from_str(&to_sarif_with_source(&diags, "sysml-check", &source)).unwrap();
+        let result = &sarif["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "E020");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["region"],
+            json!({ "charOffset": 21, "charLength": 1, "startLine": 2, "startColumn": 10, "endLine": 2, "endColumn": 11 })
+        );
+
+        let related = result["relatedLocations"].as_array().unwrap();
+        assert_eq!(related.len(), 2);
+        assert_eq!(related[0]["id"], 0);
+        assert_eq!(related[0]["message"]["text"], "first defined here");
+        assert_eq!(related[0]["physicalLocation"]["region"]["startLine"], 1);
+        assert_eq!(related[1]["physicalLocation"]["artifactLocation"]["index"], 1);
+        assert!(related[1]["physicalLocation"].get("region").is_none());
+
+        // The log is plain JSON, so a round trip leaves it unchanged
+        let text = serde_json::to_string(&sarif).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&text).unwrap(), sarif);
+    }
+}
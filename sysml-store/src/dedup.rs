@@ -0,0 +1,316 @@
+//! Content-addressed storage of snapshots, element by element.
+//!
+//! [`DedupStore`] splits the canonical JSON of a graph into one blob per
+//! element and relationship, keyed by a hash of the blob's bytes, and stores
+//! a commit as a manifest mapping each id to its blob key:
+//!
+//! ```text
+//! {"elements": {"<id>": "<key>", ...}, "relationships": {...}, "version": "1.0"}
+//! ```
+//!
+//! A commit that changes one element of an otherwise unchanged graph adds
+//! one blob and a manifest. Keys are [`bytes_hash`] hashes of the blobs.
+//! That hash is not collision resistant, so a key that is already stored is
+//! checked to hold the same bytes before it is reused.
+
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+use sysml_canon::{bytes_hash, from_json_value, to_json_string, to_json_value};
+use sysml_core::ModelGraph;
+use sysml_id::{CommitId, ProjectId};
+
+use crate::{BlobStore, Branch, Snapshot, SnapshotMeta, Store, StoreError, Tag};
+
+/// The arrays of the canonical graph JSON that are split into blobs.
+const SECTIONS: &[&str] = &["elements", "relationships"];
+
+/// A store that deduplicates elements across commits.
+///
+/// Wraps a backend that supports [`put_snapshot_data`](Store::put_snapshot_data)
+/// and [`BlobStore`], such as [`InMemoryStore`](crate::InMemoryStore) or
+/// [`FileStore`](crate::FileStore). The backend keeps the commit history,
+/// branches and tags, with manifests as snapshot data; snapshots returned by
+/// [`get_snapshot`](Store::get_snapshot) carry the canonical JSON of the
+/// reassembled graph, as if stored directly.
+///
+/// # Examples
+///
+/// ```
+/// use sysml_core::ModelGraph;
+/// use sysml_id::{CommitId, ProjectId};
+/// use sysml_store::{DedupStore, InMemoryStore, SnapshotMeta, Store};
+///
+/// let mut store = DedupStore::new(InMemoryStore::new());
+/// let project = ProjectId::new("vehicles");
+/// let meta = SnapshotMeta::new(CommitId::new("v1"), "Initial");
+/// store.put_snapshot(&project, meta, &ModelGraph::new()).unwrap();
+/// assert!(store.get_snapshot(&project, &CommitId::new("v1")).unwrap().is_some());
+/// ```
+#[derive(Debug, Default)]
+pub struct DedupStore<S> {
+    inner: S,
+}
+
+impl<S> DedupStore<S> {
+    /// Wrap a backend.
+    pub fn new(inner: S) -> Self {
+        DedupStore { inner }
+    }
+
+    /// The wrapped backend.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwrap the backend.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: BlobStore> DedupStore<S> {
+    /// Rebuild a graph from a manifest and its blobs.
+    fn reassemble(&self, manifest: &str) -> Result<ModelGraph, StoreError> {
+        let invalid = |reason: &str| StoreError::DeserializationError(format!("invalid manifest: {}", reason));
+        let manifest: Value = serde_json::from_str(manifest).map_err(|e| invalid(&e.to_string()))?;
+
+        let mut canonical = Map::new();
+        canonical.insert("version".to_string(), manifest["version"].clone());
+        for section in SECTIONS {
+            let entries = manifest[section].as_object().ok_or_else(|| invalid(section))?;
+            let mut items = Vec::with_capacity(entries.len());
+            // Map keys are sorted, which is the canonical order by id
+            for key in entries.values() {
+                let key = key.as_str().ok_or_else(|| invalid(section))?;
+                let blob = self
+                    .inner
+                    .get_blob(key)?
+                    .ok_or_else(|| StoreError::DatabaseError(format!("missing blob {}", key)))?;
+                let item: Value =
+                    serde_json::from_str(&blob).map_err(|e| StoreError::DeserializationError(e.to_string()))?;
+                items.push(item);
+            }
+            canonical.insert(section.to_string(), Value::Array(items));
+        }
+        from_json_value(Value::Object(canonical)).map_err(|e| StoreError::DeserializationError(e.to_string()))
+    }
+}
+
+impl<S: Store + BlobStore> Store for DedupStore<S> {
+    fn put_snapshot(
+        &mut self,
+        project: &ProjectId,
+        meta: SnapshotMeta,
+        graph: &ModelGraph,
+    ) -> Result<(), StoreError> {
+        // Everything is checked before the first blob is written, so that a
+        // rejected commit adds no blobs
+        if self.inner.get_snapshot(project, &meta.commit)?.is_some() {
+            return Err(StoreError::Conflict(format!(
+                "commit {} already exists",
+                meta.commit
+            )));
+        }
+
+        let canonical = to_json_value(graph);
+        let mut manifest = Map::new();
+        let mut blobs: BTreeMap<String, String> = BTreeMap::new();
+        manifest.insert("version".to_string(), canonical["version"].clone());
+        for section in SECTIONS {
+            let mut entries = BTreeMap::new();
+            for item in canonical[section].as_array().into_iter().flatten() {
+                let id = match &item["id"] {
+                    Value::String(id) => id.clone(),
+                    id => id.to_string(),
+                };
+                let blob = serde_json::to_string(item).map_err(|e| StoreError::SerializationError(e.to_string()))?;
+                let key = bytes_hash(blob.as_bytes());
+                entries.insert(id, Value::String(key.clone()));
+                blobs.insert(key, blob);
+            }
+            manifest.insert(section.to_string(), Value::Object(entries.into_iter().collect()));
+        }
+
+        for (key, blob) in &blobs {
+            if self.inner.get_blob(key)?.is_some_and(|stored| &stored != blob) {
+                return Err(StoreError::Conflict(format!(
+                    "hash collision: blob {} is stored with different content",
+                    key
+                )));
+            }
+        }
+        for (key, blob) in &blobs {
+            self.inner.put_blob(key, blob)?;
+        }
+
+        let manifest = serde_json::to_string(&Value::Object(manifest))
+            .map_err(|e| StoreError::SerializationError(e.to_string()))?;
+        self.inner.put_snapshot_data(project, meta, manifest)
+    }
+
+    fn get_snapshot(
+        &self,
+        project: &ProjectId,
+        commit: &CommitId,
+    ) -> Result<Option<Snapshot>, StoreError> {
+        let snapshot = match self.inner.get_snapshot(project, commit)? {
+            Some(snapshot) => snapshot,
+            None => return Ok(None),
+        };
+        let graph = self.reassemble(&snapshot.data)?;
+        Ok(Some(Snapshot {
+            meta: snapshot.meta,
+            data: to_json_string(&graph),
+        }))
+    }
+
+    fn latest(&self, project: &ProjectId) -> Result<Option<CommitId>, StoreError> {
+        self.inner.latest(project)
+    }
+
+    fn list_commits(&self, project: &ProjectId) -> Result<Vec<SnapshotMeta>, StoreError> {
+        self.inner.list_commits(project)
+    }
+
+    fn list_projects(&self) -> Result<Vec<ProjectId>, StoreError> {
+        self.inner.list_projects()
+    }
+
+    fn history(&self, project: &ProjectId, from: &CommitId) -> Result<Vec<SnapshotMeta>, StoreError> {
+        self.inner.history(project, from)
+    }
+
+    fn create_branch(&mut self, project: &ProjectId, name: &str, at: &CommitId) -> Result<(), StoreError> {
+        self.inner.create_branch(project, name, at)
+    }
+
+    fn list_branches(&self, project: &ProjectId) -> Result<Vec<Branch>, StoreError> {
+        self.inner.list_branches(project)
+    }
+
+    fn branch_head(&self, project: &ProjectId, name: &str) -> Result<Option<CommitId>, StoreError> {
+        self.inner.branch_head(project, name)
+    }
+
+    fn update_branch(
+        &mut self,
+        project: &ProjectId,
+        name: &str,
+        to: &CommitId,
+        force: bool,
+    ) -> Result<(), StoreError> {
+        self.inner.update_branch(project, name, to, force)
+    }
+
+    fn tag(&mut self, project: &ProjectId, name: &str, at: &CommitId) -> Result<(), StoreError> {
+        self.inner.tag(project, name, at)
+    }
+
+    fn list_tags(&self, project: &ProjectId) -> Result<Vec<Tag>, StoreError> {
+        self.inner.list_tags(project)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryStore;
+    use sysml_canon::from_json_str;
+    use sysml_core::{Element, ElementKind, Relationship, RelationshipKind};
+
+    /// A package owning `parts` part definitions, with one relationship.
+    fn graph(parts: usize) -> ModelGraph {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Vehicles"));
+        let mut first = None;
+        for i in 0..parts {
+            let part = Element::new_with_kind(ElementKind::PartDefinition)
+                .with_name(format!("Part{}", i))
+                .with_owner(pkg.clone());
+            let id = graph.add_element(part);
+            first.get_or_insert(id);
+        }
+        if let Some(first) = first {
+            graph.add_relationship(Relationship::new(RelationshipKind::Owning, pkg, first));
+        }
+        graph
+    }
+
+    /// The graph after a direct canonical round trip.
+    fn canonical(graph: &ModelGraph) -> String {
+        to_json_string(&from_json_str(&to_json_string(graph)).unwrap())
+    }
+
+    #[test]
+    fn snapshots_reassemble_the_canonical_graph() {
+        let mut store = DedupStore::new(InMemoryStore::new());
+        let project = ProjectId::new("vehicles");
+        let graph = graph(5);
+        store
+            .put_snapshot(&project, SnapshotMeta::new(CommitId::new("v1"), "First"), &graph)
+            .unwrap();
+
+        let snapshot = store.get_snapshot(&project, &CommitId::new("v1")).unwrap().unwrap();
+        assert_eq!(snapshot.data, to_json_string(&graph));
+        assert_eq!(to_json_string(&snapshot.graph().unwrap()), canonical(&graph));
+        assert_eq!(snapshot.meta.message, "First");
+        assert!(store.get_snapshot(&project, &CommitId::new("v2")).unwrap().is_none());
+        assert_eq!(store.latest(&project).unwrap().unwrap().as_str(), "v1");
+    }
+
+    #[test]
+    fn nearly_identical_commits_share_blobs() {
+        let mut store = DedupStore::new(InMemoryStore::new());
+        let project = ProjectId::new("vehicles");
+        let mut graph = graph(50);
+        store
+            .put_snapshot(&project, SnapshotMeta::new(CommitId::new("v1"), "First"), &graph)
+            .unwrap();
+        let blobs = store.inner().blob_count().unwrap();
+        assert_eq!(blobs, graph.element_count() + graph.relationships.len());
+
+        // Renaming one element adds exactly one blob
+        let renamed = graph.elements.values_mut().find(|e| e.name.as_deref() == Some("Part7")).unwrap();
+        renamed.name = Some("Engine".to_string());
+        let meta = SnapshotMeta::new(CommitId::new("v2"), "Rename").with_parent(CommitId::new("v1"));
+        store.put_snapshot(&project, meta, &graph).unwrap();
+        assert_eq!(store.inner().blob_count().unwrap(), blobs + 1);
+
+        let snapshot = store.get_snapshot(&project, &CommitId::new("v2")).unwrap().unwrap();
+        assert_eq!(to_json_string(&snapshot.graph().unwrap()), canonical(&graph));
+        let first = store.get_snapshot(&project, &CommitId::new("v1")).unwrap().unwrap();
+        assert!(first.data.contains("Part7") && !first.data.contains("Engine"));
+    }
+
+    #[test]
+    fn colliding_blob_keys_are_rejected() {
+        let graph = graph(1);
+        let relationship = &to_json_value(&graph)["relationships"][0];
+        let key = bytes_hash(serde_json::to_string(relationship).unwrap().as_bytes());
+
+        // Another blob already stored under the key of the last item serialized
+        let mut inner = InMemoryStore::new();
+        inner.put_blob(&key, "{\"other\":true}").unwrap();
+        let mut store = DedupStore::new(inner);
+        let project = ProjectId::new("vehicles");
+        let result = store.put_snapshot(&project, SnapshotMeta::new(CommitId::new("v1"), "First"), &graph);
+        assert!(matches!(result, Err(StoreError::Conflict(message)) if message.contains(&key)));
+        assert!(store.get_snapshot(&project, &CommitId::new("v1")).unwrap().is_none());
+        // None of the commit's other blobs were written
+        assert_eq!(store.inner().blob_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn duplicate_commits_conflict_without_new_blobs() {
+        let mut store = DedupStore::new(InMemoryStore::new());
+        let project = ProjectId::new("vehicles");
+        let meta = SnapshotMeta::new(CommitId::new("v1"), "First");
+        store.put_snapshot(&project, meta.clone(), &graph(2)).unwrap();
+        let blobs = store.inner().blob_count().unwrap();
+
+        let result = store.put_snapshot(&project, meta, &graph(3));
+        assert!(matches!(result, Err(StoreError::Conflict(_))));
+        assert_eq!(store.inner().blob_count().unwrap(), blobs);
+    }
+}
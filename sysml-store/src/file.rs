@@ -0,0 +1,436 @@
+//! A store that persists snapshots as files under a root directory.
+//!
+//! Each project gets a subdirectory holding a `commits.json` index of
+//! snapshot metadata (oldest first) and one `<commit>.json` file per commit
+//! with the canonical graph serialization:
+//!
+//! ```text
+//! root/
+//!   my-project/
+//!     commits.json
+//!     snapshots/
+//!       v1.json
+//!       v2.json
+//! ```
+//!
+//! Blobs are kept in a `.blobs` directory under the root, one file per key;
+//! the dot keeps it apart from project directories.
+//!
+//! Project and commit IDs and blob keys are percent-encoded where they
+//! contain characters other than ASCII letters, digits, `-` and `_`, so any
+//! ID maps to a single path component.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use sysml_canon::to_json_string;
+use sysml_core::ModelGraph;
+use sysml_id::{CommitId, ProjectId};
+
+use crate::{BlobStore, Snapshot, SnapshotMeta, Store, StoreError};
+
+const INDEX_FILE: &str = "commits.json";
+const SNAPSHOT_DIR: &str = "snapshots";
+const BLOB_DIR: &str = ".blobs";
+
+/// A file-based store that survives restarts.
+///
+/// Writes go to a temporary file that is then renamed into place, so a
+/// crash leaves either the old or the new file, never a partial one. Only
+/// the index is read to list commits; snapshot files are read one at a time
+/// by [`get_snapshot`](Store::get_snapshot).
+///
+/// There are no branches or tags, so [`latest`](Store::latest) is the most
+/// recently stored commit rather than the head of [`DEFAULT_BRANCH`](crate::DEFAULT_BRANCH).
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    root: PathBuf,
+}
+
+impl FileStore {
+    /// Open a store rooted at a directory, creating it if needed.
+    ///
+    /// Snapshots written by an earlier store over the same directory are
+    /// visible immediately.
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self, StoreError> {
+        let root = root.into();
+        fs::create_dir_all(&root).map_err(|e| io_error(&root, e))?;
+        Ok(FileStore { root })
+    }
+
+    /// The root directory of the store.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn project_dir(&self, project: &ProjectId) -> PathBuf {
+        self.root.join(encode(project.as_str()))
+    }
+
+    fn snapshot_path(&self, project: &ProjectId, commit: &CommitId) -> PathBuf {
+        self.project_dir(project)
+            .join(SNAPSHOT_DIR)
+            .join(format!("{}.json", encode(commit.as_str())))
+    }
+
+    fn blob_path(&self, key: &str) -> PathBuf {
+        self.root.join(BLOB_DIR).join(format!("{}.json", encode(key)))
+    }
+
+    /// Read a project's commits, oldest first. A missing project has none.
+    fn read_index(&self, project: &ProjectId) -> Result<Vec<SnapshotMeta>, StoreError> {
+        let path = self.project_dir(project).join(INDEX_FILE);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error(&path, e)),
+        };
+        let corrupt = |reason: String| StoreError::DatabaseError(format!("corrupt index {}: {}", path.display(), reason));
+
+        let value: Value = serde_json::from_str(&text).map_err(|e| corrupt(e.to_string()))?;
+        let entries = value.as_array().ok_or_else(|| corrupt("expected an array".to_string()))?;
+        entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| meta_from_value(entry).ok_or_else(|| corrupt(format!("invalid entry {}", i))))
+            .collect()
+    }
+
+    fn write_index(&self, project: &ProjectId, commits: &[SnapshotMeta]) -> Result<(), StoreError> {
+        let value = Value::Array(commits.iter().map(meta_value).collect());
+        let text = serde_json::to_string_pretty(&value).map_err(|e| StoreError::SerializationError(e.to_string()))?;
+        write_atomic(&self.project_dir(project).join(INDEX_FILE), &text)
+    }
+}
+
+impl Store for FileStore {
+    fn put_snapshot(
+        &mut self,
+        project: &ProjectId,
+        meta: SnapshotMeta,
+        graph: &ModelGraph,
+    ) -> Result<(), StoreError> {
+        self.put_snapshot_data(project, meta, to_json_string(graph))
+    }
+
+    fn put_snapshot_data(
+        &mut self,
+        project: &ProjectId,
+        meta: SnapshotMeta,
+        data: String,
+    ) -> Result<(), StoreError> {
+        let mut commits = self.read_index(project)?;
+        let path = self.snapshot_path(project, &meta.commit);
+        if commits.iter().any(|existing| existing.commit == meta.commit) || path.exists() {
+            return Err(StoreError::Conflict(format!(
+                "commit {} already exists",
+                meta.commit
+            )));
+        }
+
+        // The snapshot goes first: an index entry must never point at a
+        // missing file
+        write_atomic(&path, &data)?;
+        commits.push(meta);
+        self.write_index(project, &commits)
+    }
+
+    fn get_snapshot(
+        &self,
+        project: &ProjectId,
+        commit: &CommitId,
+    ) -> Result<Option<Snapshot>, StoreError> {
+        let meta = match self.read_index(project)?.into_iter().find(|meta| &meta.commit == commit) {
+            Some(meta) => meta,
+            None => return Ok(None),
+        };
+        let path = self.snapshot_path(project, commit);
+        let data = fs::read_to_string(&path).map_err(|e| io_error(&path, e))?;
+        Ok(Some(Snapshot { meta, data }))
+    }
+
+    fn latest(&self, project: &ProjectId) -> Result<Option<CommitId>, StoreError> {
+        Ok(self.read_index(project)?.pop().map(|meta| meta.commit))
+    }
+
+    fn list_commits(&self, project: &ProjectId) -> Result<Vec<SnapshotMeta>, StoreError> {
+        Ok(self.read_index(project)?.into_iter().rev().collect())
+    }
+
+    fn list_projects(&self) -> Result<Vec<ProjectId>, StoreError> {
+        let entries = fs::read_dir(&self.root).map_err(|e| io_error(&self.root, e))?;
+        let mut projects = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| io_error(&self.root, e))?;
+            let is_dir = entry.file_type().map_err(|e| io_error(&entry.path(), e))?.is_dir();
+            let name = entry.file_name();
+            // Directories that are not encoded IDs are not projects
+            if let Some(id) = name.to_str().filter(|_| is_dir).and_then(decode) {
+                projects.push(ProjectId::new(id));
+            }
+        }
+        projects.sort();
+        Ok(projects)
+    }
+}
+
+impl BlobStore for FileStore {
+    fn put_blob(&mut self, key: &str, data: &str) -> Result<bool, StoreError> {
+        let path = self.blob_path(key);
+        if path.exists() {
+            return Ok(false);
+        }
+        write_atomic(&path, data)?;
+        Ok(true)
+    }
+
+    fn get_blob(&self, key: &str) -> Result<Option<String>, StoreError> {
+        let path = self.blob_path(key);
+        match fs::read_to_string(&path) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(&path, e)),
+        }
+    }
+
+    fn blob_count(&self) -> Result<usize, StoreError> {
+        let dir = self.root.join(BLOB_DIR);
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(io_error(&dir, e)),
+        };
+        let mut count = 0;
+        for entry in entries {
+            let entry = entry.map_err(|e| io_error(&dir, e))?;
+            // Temporary files start with a dot
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> StoreError {
+    StoreError::DatabaseError(format!("{}: {}", path.display(), e))
+}
+
+/// Write a file by writing a temporary sibling and renaming it into place.
+fn write_atomic(path: &Path, contents: &str) -> Result<(), StoreError> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir).map_err(|e| io_error(dir, e))?;
+    let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or("file");
+    let tmp = dir.join(format!(".{}.tmp", file_name));
+    fs::write(&tmp, contents).map_err(|e| io_error(&tmp, e))?;
+    fs::rename(&tmp, path).map_err(|e| io_error(path, e))
+}
+
+fn meta_value(meta: &SnapshotMeta) -> Value {
+    json!({
+        "commit": meta.commit.as_str(),
+        "parent": meta.parent.as_ref().map(|parent| parent.as_str()),
+        "message": meta.message,
+        "timestamp": meta.timestamp,
+    })
+}
+
+fn meta_from_value(value: &Value) -> Option<SnapshotMeta> {
+    let commit = CommitId::new(value.get("commit")?.as_str()?);
+    let parent = match value.get("parent") {
+        None | Some(Value::Null) => None,
+        Some(parent) => Some(CommitId::new(parent.as_str()?)),
+    };
+    Some(SnapshotMeta {
+        commit,
+        parent,
+        message: value.get("message")?.as_str()?.to_string(),
+        timestamp: value.get("timestamp")?.as_u64()?,
+    })
+}
+
+/// Encode an ID as a path component, percent-encoding every byte other than
+/// ASCII letters, digits, `-` and `_`.
+fn encode(id: &str) -> String {
+    let mut encoded = String::with_capacity(id.len());
+    for byte in id.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Invert [`encode`], returning None for names it cannot produce.
+fn decode(name: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(name.len());
+    let mut rest = name.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            bytes.push(byte);
+            rest = tail;
+        } else {
+            return None;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use sysml_core::{Element, ElementKind};
+
+    /// A fresh directory under the system temp dir, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+            TempDir(std::env::temp_dir().join(format!("sysml-file-store-{}-{}", std::process::id(), n)))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn graph(name: &str) -> ModelGraph {
+        let mut graph = ModelGraph::new();
+        graph.add_element(Element::new_with_kind(ElementKind::Package).with_name(name));
+        graph
+    }
+
+    #[test]
+    fn commits_survive_a_restart() {
+        let dir = TempDir::new();
+        let project = ProjectId::new("vehicles");
+        {
+            let mut store = FileStore::open(&dir.0).unwrap();
+            let first = SnapshotMeta::new(CommitId::new("v1"), "First").with_timestamp(1);
+            store.put_snapshot(&project, first, &graph("One")).unwrap();
+            let second = SnapshotMeta::new(CommitId::new("v2"), "Second")
+                .with_parent(CommitId::new("v1"))
+                .with_timestamp(2);
+            store.put_snapshot(&project, second, &graph("Two")).unwrap();
+        }
+
+        let store = FileStore::open(&dir.0).unwrap();
+        assert_eq!(store.latest(&project).unwrap().unwrap().as_str(), "v2");
+        let commits = store.list_commits(&project).unwrap();
+        let ids: Vec<&str> = commits.iter().map(|meta| meta.commit.as_str()).collect();
+        assert_eq!(ids, ["v2", "v1"]);
+        assert_eq!(commits[0].parent.as_ref().map(CommitId::as_str), Some("v1"));
+        assert_eq!(commits[0].message, "Second");
+        assert_eq!(commits[0].timestamp, 2);
+
+        let snapshot = store.get_snapshot(&project, &CommitId::new("v1")).unwrap().unwrap();
+        let restored = snapshot.graph().unwrap();
+        assert!(restored.elements.values().any(|element| element.name.as_deref() == Some("One")));
+        assert!(store.get_snapshot(&project, &CommitId::new("v3")).unwrap().is_none());
+        assert_eq!(store.history(&project, &CommitId::new("v2")).unwrap().len(), 2);
+        assert!(matches!(store.list_branches(&project), Err(StoreError::Unsupported(_))));
+        assert!(store.latest(&ProjectId::new("other")).unwrap().is_none());
+    }
+
+    #[test]
+    fn duplicate_commits_conflict() {
+        let dir = TempDir::new();
+        let project = ProjectId::new("vehicles");
+        let meta = SnapshotMeta::new(CommitId::new("v1"), "First");
+        FileStore::open(&dir.0).unwrap().put_snapshot(&project, meta.clone(), &graph("One")).unwrap();
+
+        let mut store = FileStore::open(&dir.0).unwrap();
+        let result = store.put_snapshot(&project, meta, &graph("Changed"));
+        assert!(matches!(result, Err(StoreError::Conflict(_))));
+        // The original snapshot is untouched
+        let snapshot = store.get_snapshot(&project, &CommitId::new("v1")).unwrap().unwrap();
+        assert!(snapshot.data.contains("One"));
+        assert_eq!(store.list_commits(&project).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn projects_are_listed_from_directories() {
+        let dir = TempDir::new();
+        let mut store = FileStore::open(&dir.0).unwrap();
+        for project in ["b", "a/../escape", "a"] {
+            let meta = SnapshotMeta::new(CommitId::new("v1"), "First");
+            store.put_snapshot(&ProjectId::new(project), meta, &graph("One")).unwrap();
+        }
+        fs::create_dir(dir.0.join("not.a.project")).unwrap();
+
+        let projects = store.list_projects().unwrap();
+        let names: Vec<&str> = projects.iter().map(ProjectId::as_str).collect();
+        assert_eq!(names, ["a", "a/../escape", "b"]);
+        assert_eq!(fs::read_dir(&dir.0).unwrap().count(), 4, "every project stays under the root");
+    }
+
+    #[test]
+    fn corrupt_index_is_an_error_with_its_path() {
+        let dir = TempDir::new();
+        let project = ProjectId::new("vehicles");
+        let mut store = FileStore::open(&dir.0).unwrap();
+        store
+            .put_snapshot(&project, SnapshotMeta::new(CommitId::new("v1"), "First"), &graph("One"))
+            .unwrap();
+        let index = dir.0.join("vehicles").join(INDEX_FILE);
+        fs::write(&index, "{ not json").unwrap();
+
+        match store.list_commits(&project) {
+            Err(StoreError::DatabaseError(message)) => {
+                assert!(message.contains(&index.display().to_string()), "{}", message)
+            }
+            other => panic!("expected a database error, got {:?}", other),
+        }
+        let result = store.put_snapshot(&project, SnapshotMeta::new(CommitId::new("v2"), "Second"), &graph("Two"));
+        assert!(matches!(result, Err(StoreError::DatabaseError(_))));
+    }
+
+    #[test]
+    fn blobs_survive_a_restart_and_are_not_projects() {
+        let dir = TempDir::new();
+        let project = ProjectId::new("vehicles");
+        let original = graph("One");
+        {
+            let mut store = crate::DedupStore::new(FileStore::open(&dir.0).unwrap());
+            let meta = SnapshotMeta::new(CommitId::new("v1"), "First");
+            store.put_snapshot(&project, meta, &original).unwrap();
+        }
+
+        let mut store = FileStore::open(&dir.0).unwrap();
+        let blobs = store.blob_count().unwrap();
+        assert_eq!(blobs, original.element_count());
+        assert!(store.put_blob("other/key", "data").unwrap());
+        assert!(!store.put_blob("other/key", "changed").unwrap());
+        assert_eq!(store.get_blob("other/key").unwrap().as_deref(), Some("data"));
+        assert_eq!(store.get_blob("missing").unwrap(), None);
+        assert_eq!(store.blob_count().unwrap(), blobs + 1);
+        assert_eq!(store.list_projects().unwrap(), std::slice::from_ref(&project));
+
+        let store = crate::DedupStore::new(store);
+        let snapshot = store.get_snapshot(&project, &CommitId::new("v1")).unwrap().unwrap();
+        assert_eq!(snapshot.data, to_json_string(&original));
+    }
+
+    #[test]
+    fn ids_round_trip_through_path_components() {
+        for id in ["plain-id_1", "a/b", "..", "%41", "ünïcode", ""] {
+            let encoded = encode(id);
+            assert!(!encoded.contains(['/', '.']), "{:?}", encoded);
+            assert_eq!(decode(&encoded).as_deref(), Some(id));
+        }
+        assert_eq!(decode("bad%4"), None);
+        assert_eq!(decode("has.dot"), None);
+    }
+}
@@ -3,10 +3,29 @@
 //! Storage trait and types for SysML v2 model snapshots.
 //!
 //! This crate defines the interface for storing and retrieving model
-//! snapshots with version control.
+//! snapshots with version control, with an in-memory backend
+//! ([`InMemoryStore`]) and a file-based one that persists across restarts
+//! ([`FileStore`]).
+//!
+//! Commits form a history through their parent links. Backends may also
+//! keep branches, which move as the history grows, and tags, which stay put;
+//! [`Store::latest`] is the head of the [`DEFAULT_BRANCH`], or the most recent
+//! commit in a backend without branches.
+//!
+//! For large models, [`DedupStore`] wraps a backend that is also a
+//! [`BlobStore`] and stores each element once per distinct content instead
+//! of once per commit.
+//!
+//! Commit ids can be derived from content with [`CommitIdExt::from_graph`].
+
+mod dedup;
+mod file;
+
+pub use dedup::DedupStore;
+pub use file::FileStore;
 
-use std::collections::HashMap;
-use sysml_canon::{from_json_str, to_json_string};
+use std::collections::{BTreeMap, HashMap};
+use sysml_canon::{from_json_str, graph_hash, to_json_string};
 use sysml_core::ModelGraph;
 use sysml_id::{CommitId, ProjectId};
 use thiserror::Error;
@@ -22,6 +41,10 @@ pub enum StoreError {
     #[error("commit not found: {0}")]
     CommitNotFound(String),
 
+    /// The requested branch was not found.
+    #[error("branch not found: {0}")]
+    BranchNotFound(String),
+
     /// Serialization failed.
     #[error("serialization error: {0}")]
     SerializationError(String),
@@ -37,6 +60,60 @@ pub enum StoreError {
     /// Conflict (e.g., commit already exists).
     #[error("conflict: {0}")]
     Conflict(String),
+
+    /// The backend does not support the operation.
+    #[error("unsupported: {0}")]
+    Unsupported(String),
+}
+
+/// The branch whose head [`Store::latest`] returns.
+pub const DEFAULT_BRANCH: &str = "main";
+
+/// A named pointer to a commit that moves as the history grows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+    /// The branch name.
+    pub name: String,
+    /// The commit the branch points at.
+    pub head: CommitId,
+}
+
+/// A named pointer to a commit that never moves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    /// The tag name.
+    pub name: String,
+    /// The commit the tag points at.
+    pub commit: CommitId,
+}
+
+/// Content-derived commit ids.
+///
+/// `CommitId` lives in `sysml-id`, which knows nothing of graphs, so the
+/// constructor is provided here as an extension trait.
+pub trait CommitIdExt {
+    /// The commit id of a graph's content, its [`graph_hash`].
+    ///
+    /// Graphs with the same elements and relationships get the same id,
+    /// whatever order they were built in and wherever their source text is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use sysml_core::ModelGraph;
+    /// use sysml_id::CommitId;
+    /// use sysml_store::CommitIdExt;
+    ///
+    /// let id = CommitId::from_graph(&ModelGraph::new());
+    /// assert_eq!(id, CommitId::from_graph(&ModelGraph::new()));
+    /// ```
+    fn from_graph(graph: &ModelGraph) -> Self;
+}
+
+impl CommitIdExt for CommitId {
+    fn from_graph(graph: &ModelGraph) -> Self {
+        CommitId::new(graph_hash(graph))
+    }
 }
 
 /// Metadata about a snapshot.
@@ -124,6 +201,20 @@ pub trait Store {
         graph: &ModelGraph,
     ) -> Result<(), StoreError>;
 
+    /// Store a snapshot whose data is already serialized.
+    ///
+    /// Like [`put_snapshot`](Self::put_snapshot), but the data is stored as
+    /// given, so that wrappers such as [`DedupStore`] can store their own
+    /// encoding. The default implementation fails with `Unsupported`.
+    fn put_snapshot_data(
+        &mut self,
+        _project: &ProjectId,
+        _meta: SnapshotMeta,
+        _data: String,
+    ) -> Result<(), StoreError> {
+        Err(StoreError::Unsupported("raw snapshot data".to_string()))
+    }
+
     /// Retrieve a model snapshot.
     ///
     /// # Arguments
@@ -140,7 +231,11 @@ pub trait Store {
         commit: &CommitId,
     ) -> Result<Option<Snapshot>, StoreError>;
 
-    /// Get the latest commit ID for a project.
+    /// Get the latest commit ID for a project: the head of its
+    /// [`DEFAULT_BRANCH`].
+    ///
+    /// A backend without branches, such as [`FileStore`], returns the most
+    /// recently stored commit instead.
     fn latest(&self, project: &ProjectId) -> Result<Option<CommitId>, StoreError>;
 
     /// List all commits for a project (most recent first).
@@ -148,17 +243,116 @@ pub trait Store {
 
     /// List all projects.
     fn list_projects(&self) -> Result<Vec<ProjectId>, StoreError>;
+
+    /// Walk the parent links from a commit.
+    ///
+    /// # Returns
+    ///
+    /// The commit and its ancestors, newest first, ending at a commit
+    /// without a parent or whose parent is not stored. Fails with
+    /// `CommitNotFound` if `from` is not stored.
+    fn history(&self, project: &ProjectId, from: &CommitId) -> Result<Vec<SnapshotMeta>, StoreError> {
+        let commits: HashMap<CommitId, SnapshotMeta> = self
+            .list_commits(project)?
+            .into_iter()
+            .map(|meta| (meta.commit.clone(), meta))
+            .collect();
+        if !commits.contains_key(from) {
+            return Err(StoreError::CommitNotFound(from.to_string()));
+        }
+
+        let mut history = Vec::new();
+        let mut next = Some(from);
+        // Bounded in case of a parent cycle
+        while let Some(meta) = next.and_then(|commit| commits.get(commit)) {
+            if history.len() == commits.len() {
+                break;
+            }
+            history.push(meta.clone());
+            next = meta.parent.as_ref();
+        }
+        Ok(history)
+    }
+
+    /// Create a branch pointing at a stored commit.
+    ///
+    /// Fails with `Conflict` if the branch exists. The default
+    /// implementation fails with `Unsupported`.
+    fn create_branch(&mut self, _project: &ProjectId, _name: &str, _at: &CommitId) -> Result<(), StoreError> {
+        Err(StoreError::Unsupported("branches".to_string()))
+    }
+
+    /// List the branches of a project, sorted by name.
+    fn list_branches(&self, _project: &ProjectId) -> Result<Vec<Branch>, StoreError> {
+        Err(StoreError::Unsupported("branches".to_string()))
+    }
+
+    /// Get the commit a branch points at, or None if there is no such branch.
+    fn branch_head(&self, _project: &ProjectId, _name: &str) -> Result<Option<CommitId>, StoreError> {
+        Err(StoreError::Unsupported("branches".to_string()))
+    }
+
+    /// Move a branch to another stored commit.
+    ///
+    /// Unless `force` is set, the new commit must be a descendant of the
+    /// current head (a fast-forward), or the update fails with `Conflict`.
+    fn update_branch(
+        &mut self,
+        _project: &ProjectId,
+        _name: &str,
+        _to: &CommitId,
+        _force: bool,
+    ) -> Result<(), StoreError> {
+        Err(StoreError::Unsupported("branches".to_string()))
+    }
+
+    /// Tag a stored commit.
+    ///
+    /// Fails with `Conflict` if the tag exists, since tags never move.
+    fn tag(&mut self, _project: &ProjectId, _name: &str, _at: &CommitId) -> Result<(), StoreError> {
+        Err(StoreError::Unsupported("tags".to_string()))
+    }
+
+    /// List the tags of a project, sorted by name.
+    fn list_tags(&self, _project: &ProjectId) -> Result<Vec<Tag>, StoreError> {
+        Err(StoreError::Unsupported("tags".to_string()))
+    }
+}
+
+/// Storage for content-addressed blobs, shared by all projects.
+pub trait BlobStore {
+    /// Store a blob under a key, unless a blob is stored under it already.
+    ///
+    /// # Returns
+    ///
+    /// Whether the blob was added.
+    fn put_blob(&mut self, key: &str, data: &str) -> Result<bool, StoreError>;
+
+    /// Retrieve a blob, or None if nothing is stored under the key.
+    fn get_blob(&self, key: &str) -> Result<Option<String>, StoreError>;
+
+    /// Count the stored blobs.
+    fn blob_count(&self) -> Result<usize, StoreError>;
 }
 
 /// An in-memory store implementation.
+///
+/// A project's [`DEFAULT_BRANCH`] is created by its first commit and follows
+/// every commit whose parent is its head, as well as commits without a
+/// parent. Other branches only move through
+/// [`update_branch`](Store::update_branch).
 #[derive(Debug, Default)]
 pub struct InMemoryStore {
     /// Snapshots indexed by (project, commit).
     snapshots: HashMap<(String, String), Snapshot>,
-    /// Latest commit for each project.
-    latest: HashMap<String, CommitId>,
+    /// Branch heads for each project.
+    branches: HashMap<String, BTreeMap<String, CommitId>>,
+    /// Tags for each project.
+    tags: HashMap<String, BTreeMap<String, CommitId>>,
     /// All commits for each project (in order).
     commits: HashMap<String, Vec<SnapshotMeta>>,
+    /// Blobs by key.
+    blobs: HashMap<String, String>,
 }
 
 impl InMemoryStore {
@@ -166,8 +360,19 @@ impl InMemoryStore {
     pub fn new() -> Self {
         InMemoryStore {
             snapshots: HashMap::new(),
-            latest: HashMap::new(),
+            branches: HashMap::new(),
+            tags: HashMap::new(),
             commits: HashMap::new(),
+            blobs: HashMap::new(),
+        }
+    }
+
+    fn require_commit(&self, project: &ProjectId, commit: &CommitId) -> Result<(), StoreError> {
+        let key = (project.as_str().to_string(), commit.as_str().to_string());
+        if self.snapshots.contains_key(&key) {
+            Ok(())
+        } else {
+            Err(StoreError::CommitNotFound(commit.to_string()))
         }
     }
 }
@@ -178,6 +383,15 @@ impl Store for InMemoryStore {
         project: &ProjectId,
         meta: SnapshotMeta,
         graph: &ModelGraph,
+    ) -> Result<(), StoreError> {
+        self.put_snapshot_data(project, meta, to_json_string(graph))
+    }
+
+    fn put_snapshot_data(
+        &mut self,
+        project: &ProjectId,
+        meta: SnapshotMeta,
+        data: String,
     ) -> Result<(), StoreError> {
         let project_key = project.as_str().to_string();
         let commit_key = meta.commit.as_str().to_string();
@@ -190,9 +404,20 @@ impl Store for InMemoryStore {
             )));
         }
 
-        let snapshot = Snapshot::new(meta.clone(), graph);
+        let snapshot = Snapshot {
+            meta: meta.clone(),
+            data,
+        };
         self.snapshots.insert(key, snapshot);
-        self.latest.insert(project_key.clone(), meta.commit.clone());
+
+        let branches = self.branches.entry(project_key.clone()).or_default();
+        let advances = match (branches.get(DEFAULT_BRANCH), &meta.parent) {
+            (Some(head), Some(parent)) => head == parent,
+            _ => true,
+        };
+        if advances {
+            branches.insert(DEFAULT_BRANCH.to_string(), meta.commit.clone());
+        }
 
         self.commits
             .entry(project_key)
@@ -212,7 +437,7 @@ impl Store for InMemoryStore {
     }
 
     fn latest(&self, project: &ProjectId) -> Result<Option<CommitId>, StoreError> {
-        Ok(self.latest.get(project.as_str()).cloned())
+        self.branch_head(project, DEFAULT_BRANCH)
     }
 
     fn list_commits(&self, project: &ProjectId) -> Result<Vec<SnapshotMeta>, StoreError> {
@@ -233,6 +458,102 @@ impl Store for InMemoryStore {
             .map(|k| ProjectId::new(k.clone()))
             .collect())
     }
+
+    fn create_branch(&mut self, project: &ProjectId, name: &str, at: &CommitId) -> Result<(), StoreError> {
+        self.require_commit(project, at)?;
+        let branches = self.branches.entry(project.as_str().to_string()).or_default();
+        if branches.contains_key(name) {
+            return Err(StoreError::Conflict(format!("branch {} already exists", name)));
+        }
+        branches.insert(name.to_string(), at.clone());
+        Ok(())
+    }
+
+    fn list_branches(&self, project: &ProjectId) -> Result<Vec<Branch>, StoreError> {
+        Ok(self
+            .branches
+            .get(project.as_str())
+            .into_iter()
+            .flatten()
+            .map(|(name, head)| Branch {
+                name: name.clone(),
+                head: head.clone(),
+            })
+            .collect())
+    }
+
+    fn branch_head(&self, project: &ProjectId, name: &str) -> Result<Option<CommitId>, StoreError> {
+        Ok(self
+            .branches
+            .get(project.as_str())
+            .and_then(|branches| branches.get(name))
+            .cloned())
+    }
+
+    fn update_branch(
+        &mut self,
+        project: &ProjectId,
+        name: &str,
+        to: &CommitId,
+        force: bool,
+    ) -> Result<(), StoreError> {
+        self.require_commit(project, to)?;
+        let head = self
+            .branch_head(project, name)?
+            .ok_or_else(|| StoreError::BranchNotFound(name.to_string()))?;
+        if !force && !self.history(project, to)?.iter().any(|meta| meta.commit == head) {
+            return Err(StoreError::Conflict(format!(
+                "{} is not a descendant of {}, the head of branch {}",
+                to, head, name
+            )));
+        }
+        self.branches
+            .entry(project.as_str().to_string())
+            .or_default()
+            .insert(name.to_string(), to.clone());
+        Ok(())
+    }
+
+    fn tag(&mut self, project: &ProjectId, name: &str, at: &CommitId) -> Result<(), StoreError> {
+        self.require_commit(project, at)?;
+        let tags = self.tags.entry(project.as_str().to_string()).or_default();
+        if tags.contains_key(name) {
+            return Err(StoreError::Conflict(format!("tag {} already exists", name)));
+        }
+        tags.insert(name.to_string(), at.clone());
+        Ok(())
+    }
+
+    fn list_tags(&self, project: &ProjectId) -> Result<Vec<Tag>, StoreError> {
+        Ok(self
+            .tags
+            .get(project.as_str())
+            .into_iter()
+            .flatten()
+            .map(|(name, commit)| Tag {
+                name: name.clone(),
+                commit: commit.clone(),
+            })
+            .collect())
+    }
+}
+
+impl BlobStore for InMemoryStore {
+    fn put_blob(&mut self, key: &str, data: &str) -> Result<bool, StoreError> {
+        if self.blobs.contains_key(key) {
+            return Ok(false);
+        }
+        self.blobs.insert(key.to_string(), data.to_string());
+        Ok(true)
+    }
+
+    fn get_blob(&self, key: &str) -> Result<Option<String>, StoreError> {
+        Ok(self.blobs.get(key).cloned())
+    }
+
+    fn blob_count(&self) -> Result<usize, StoreError> {
+        Ok(self.blobs.len())
+    }
 }
 
 #[cfg(test)]
@@ -343,6 +664,97 @@ mod tests {
         assert!(matches!(result, Err(StoreError::Conflict(_))));
     }
 
+    /// Commit `id` on top of `parent`.
+    fn commit(store: &mut InMemoryStore, project: &ProjectId, id: &str, parent: Option<&str>) {
+        let mut meta = SnapshotMeta::new(CommitId::new(id), id);
+        if let Some(parent) = parent {
+            meta = meta.with_parent(CommitId::new(parent));
+        }
+        store.put_snapshot(project, meta, &create_test_graph()).unwrap();
+    }
+
+    fn head(store: &InMemoryStore, project: &ProjectId, branch: &str) -> Option<String> {
+        let head = store.branch_head(project, branch).unwrap();
+        head.map(|commit| commit.as_str().to_string())
+    }
+
+    #[test]
+    fn in_memory_store_branches_diverge() {
+        let mut store = InMemoryStore::new();
+        let project = ProjectId::new("test-project");
+        commit(&mut store, &project, "v1", None);
+        store.create_branch(&project, "feature", &CommitId::new("v1")).unwrap();
+
+        // Main follows commits on its head; the feature branch stays put
+        // until it is moved
+        commit(&mut store, &project, "v2", Some("v1"));
+        commit(&mut store, &project, "f1", Some("v1"));
+        assert_eq!(store.latest(&project).unwrap().unwrap().as_str(), "v2");
+        assert_eq!(head(&store, &project, "feature").as_deref(), Some("v1"));
+        store.update_branch(&project, "feature", &CommitId::new("f1"), false).unwrap();
+
+        let branches = store.list_branches(&project).unwrap();
+        let branches: Vec<(&str, &str)> = branches.iter().map(|b| (b.name.as_str(), b.head.as_str())).collect();
+        assert_eq!(branches, [("feature", "f1"), ("main", "v2")]);
+        let history = store.history(&project, &CommitId::new("f1")).unwrap();
+        let history: Vec<&str> = history.iter().map(|meta| meta.commit.as_str()).collect();
+        assert_eq!(history, ["f1", "v1"]);
+
+        let duplicate = store.create_branch(&project, "feature", &CommitId::new("v2"));
+        assert!(matches!(duplicate, Err(StoreError::Conflict(_))));
+        let missing = store.create_branch(&project, "other", &CommitId::new("v9"));
+        assert!(matches!(missing, Err(StoreError::CommitNotFound(_))));
+        assert!(matches!(
+            store.history(&project, &CommitId::new("v9")),
+            Err(StoreError::CommitNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn in_memory_store_branch_updates_fast_forward() {
+        let mut store = InMemoryStore::new();
+        let project = ProjectId::new("test-project");
+        commit(&mut store, &project, "v1", None);
+        store.create_branch(&project, "feature", &CommitId::new("v1")).unwrap();
+        commit(&mut store, &project, "v2", Some("v1"));
+        commit(&mut store, &project, "f1", Some("v1"));
+        commit(&mut store, &project, "f2", Some("f1"));
+
+        // v1 -> f2 skips ahead through f1
+        store.update_branch(&project, "feature", &CommitId::new("f2"), false).unwrap();
+        assert_eq!(head(&store, &project, "feature").as_deref(), Some("f2"));
+
+        // main is at v2, which f2 does not descend from
+        let rejected = store.update_branch(&project, DEFAULT_BRANCH, &CommitId::new("f2"), false);
+        assert!(matches!(rejected, Err(StoreError::Conflict(_))));
+        assert_eq!(store.latest(&project).unwrap().unwrap().as_str(), "v2");
+        store.update_branch(&project, DEFAULT_BRANCH, &CommitId::new("f2"), true).unwrap();
+        assert_eq!(store.latest(&project).unwrap().unwrap().as_str(), "f2");
+
+        let unknown = store.update_branch(&project, "missing", &CommitId::new("f2"), true);
+        assert!(matches!(unknown, Err(StoreError::BranchNotFound(_))));
+    }
+
+    #[test]
+    fn in_memory_store_tags_survive_branch_updates() {
+        let mut store = InMemoryStore::new();
+        let project = ProjectId::new("test-project");
+        commit(&mut store, &project, "v1", None);
+        store.tag(&project, "release-1", &CommitId::new("v1")).unwrap();
+        commit(&mut store, &project, "v2", Some("v1"));
+        store.tag(&project, "beta", &CommitId::new("v2")).unwrap();
+        commit(&mut store, &project, "v3", Some("v2"));
+
+        let tags = store.list_tags(&project).unwrap();
+        let tags: Vec<(&str, &str)> = tags.iter().map(|t| (t.name.as_str(), t.commit.as_str())).collect();
+        assert_eq!(tags, [("beta", "v2"), ("release-1", "v1")]);
+        assert_eq!(store.latest(&project).unwrap().unwrap().as_str(), "v3");
+
+        let moved = store.tag(&project, "beta", &CommitId::new("v3"));
+        assert!(matches!(moved, Err(StoreError::Conflict(_))));
+        assert!(store.list_tags(&ProjectId::new("other")).unwrap().is_empty());
+    }
+
     #[test]
     fn snapshot_meta_with_parent() {
         let meta = SnapshotMeta::new(CommitId::new("v2"), "Second")
@@ -352,4 +764,23 @@ mod tests {
         assert_eq!(meta.parent.unwrap().as_str(), "v1");
         assert_eq!(meta.timestamp, 1234567890);
     }
+
+    #[test]
+    fn commit_ids_from_graphs_follow_content() {
+        let a = Element::new_with_kind(ElementKind::Package).with_name("A");
+        let b = Element::new_with_kind(ElementKind::Package).with_name("B");
+        let mut forward = ModelGraph::new();
+        forward.add_element(a.clone());
+        forward.add_element(b.clone());
+        let mut backward = ModelGraph::new();
+        backward.add_element(b.clone());
+        backward.add_element(a);
+
+        let id = CommitId::from_graph(&forward);
+        assert_eq!(id, CommitId::from_graph(&backward));
+        assert_eq!(id.as_str(), graph_hash(&forward));
+
+        backward.add_element(b.with_name("C"));
+        assert_ne!(id, CommitId::from_graph(&backward));
+    }
 }
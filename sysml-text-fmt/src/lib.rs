@@ -0,0 +1,814 @@
+//! # sysml-text-fmt
+//!
+//! Formatter that writes a SysML v2 `ModelGraph` back out as textual notation.
+//!
+//! [`TextFormatter`] implements [`sysml_text::Formatter`]. It walks the
+//! ownership tree from the root elements and emits:
+//!
+//! - Packages and library packages
+//! - Definitions and usages (`part def`, `attribute`, `action`, `state`, `requirement`, ...)
+//! - Feature typings (`part x : T;`), specializations (`:>`), redefinitions (`:>>`)
+//!   and reference subsettings (`::>`), taken from the owned relationship elements
+//!   the parser creates
+//! - Multiplicities, literal and textual feature values
+//! - Imports, doc comments and comments
+//! - Transitions whose source and target are known from their props
+//!   (`transition first Off accept powerOn if ready then On;`)
+//!
+//! Names that are not plain identifiers, or that collide with a keyword, are
+//! emitted as quoted names. Elements without a name become anonymous usages.
+//! Element kinds the formatter has no notation for are written as line comments
+//! so they are not silently dropped.
+//!
+//! ## Examples
+//!
+//! ```
+//! use sysml_core::{Element, ElementKind, ModelGraph, VisibilityKind};
+//! use sysml_text::Formatter;
+//! use sysml_text_fmt::TextFormatter;
+//!
+//! let mut graph = ModelGraph::new();
+//! let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Vehicles"));
+//! graph.add_owned_element(
+//!     Element::new_with_kind(ElementKind::PartDefinition).with_name("Car"),
+//!     pkg,
+//!     VisibilityKind::Public,
+//! );
+//!
+//! let text = TextFormatter::new().with_indent(2).format(&graph);
+//! assert_eq!(text, "package Vehicles {\n  part def Car;\n}\n");
+//! ```
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use sysml_core::resolution::{resolved_props, unresolved_props};
+use sysml_core::{Element, ElementId, ElementKind, ModelGraph, Value, VisibilityKind};
+use sysml_text::Formatter;
+
+/// The order in which owned members are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MemberOrder {
+    /// Source order, by the start of each element's first span. Elements
+    /// without spans follow, ordered by name.
+    #[default]
+    Source,
+    /// Alphabetical by name; unnamed elements come last.
+    Name,
+}
+
+/// Formats a `ModelGraph` as SysML v2 textual notation.
+#[derive(Debug, Clone)]
+pub struct TextFormatter {
+    indent: usize,
+    order: MemberOrder,
+}
+
+impl Default for TextFormatter {
+    fn default() -> Self {
+        TextFormatter {
+            indent: 4,
+            order: MemberOrder::Source,
+        }
+    }
+}
+
+impl TextFormatter {
+    /// Create a formatter with four-space indentation and source ordering.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of spaces per nesting level.
+    pub fn with_indent(mut self, width: usize) -> Self {
+        self.indent = width;
+        self
+    }
+
+    /// Set the order in which owned members are written.
+    pub fn with_order(mut self, order: MemberOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Sort elements according to the configured member order.
+    fn sort(&self, elements: &mut [&Element]) {
+        match self.order {
+            MemberOrder::Source => elements.sort_by(|a, b| {
+                match (a.spans.first(), b.spans.first()) {
+                    (Some(x), Some(y)) => x.start.cmp(&y.start),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+                .then_with(|| by_name(a, b))
+            }),
+            MemberOrder::Name => elements.sort_by(|a, b| by_name(a, b)),
+        }
+    }
+}
+
+impl Formatter for TextFormatter {
+    fn format(&self, graph: &ModelGraph) -> String {
+        let mut roots: Vec<&Element> = graph
+            .elements
+            .values()
+            .filter(|element| element.owner.is_none() && is_member(&element.kind))
+            .collect();
+        self.sort(&mut roots);
+
+        let mut writer = Writer {
+            formatter: self,
+            graph,
+            out: String::new(),
+        };
+        for root in roots {
+            writer.element(root, 0);
+        }
+        writer.out
+    }
+}
+
+/// Order by name (unnamed last), then by id for stability.
+fn by_name(a: &Element, b: &Element) -> Ordering {
+    match (&a.name, &b.name) {
+        (Some(x), Some(y)) => x.cmp(y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+    .then_with(|| a.id.cmp(&b.id))
+}
+
+/// Output buffer for a single `format` call.
+struct Writer<'a> {
+    formatter: &'a TextFormatter,
+    graph: &'a ModelGraph,
+    out: String,
+}
+
+impl Writer<'_> {
+    fn indent(&mut self, depth: usize) {
+        for _ in 0..depth * self.formatter.indent {
+            self.out.push(' ');
+        }
+    }
+
+    /// Write an element and, recursively, its owned members.
+    fn element(&mut self, element: &Element, depth: usize) {
+        self.indent(depth);
+        self.out.push_str(visibility_prefix(self.graph, &element.id));
+
+        match &element.kind {
+            ElementKind::Import => self.import(element),
+            ElementKind::Documentation => {
+                self.out.push_str("doc ");
+                self.out.push_str(&comment_body(element));
+                self.out.push('\n');
+            }
+            ElementKind::Comment => {
+                self.out.push_str("comment ");
+                if let Some(name) = &element.name {
+                    self.out.push_str(&escape_name(name));
+                    self.out.push(' ');
+                }
+                self.out.push_str(&comment_body(element));
+                self.out.push('\n');
+            }
+            ElementKind::TransitionUsage => match (
+                self.transition_end(element, "source", "unresolved_source"),
+                self.transition_end(element, "target", "unresolved_target"),
+            ) {
+                (Some(source), Some(target)) => self.transition(element, &source, &target),
+                _ => self.unsupported(element),
+            },
+            kind => match keyword(kind) {
+                Some(keyword) => self.declaration(element, keyword, depth),
+                None => self.unsupported(element),
+            },
+        }
+    }
+
+    /// Write an element without notation as a line comment.
+    fn unsupported(&mut self, element: &Element) {
+        self.out.push_str("// ");
+        self.out.push_str(element.kind.as_str());
+        if let Some(name) = &element.name {
+            self.out.push(' ');
+            self.out.push_str(&escape_name(name));
+        }
+        self.out.push('\n');
+    }
+
+    /// The source or target state of a transition: the name of the element a
+    /// `key` ref points to, or the name as written in `key` or `unresolved`.
+    fn transition_end(&self, transition: &Element, key: &str, unresolved: &str) -> Option<String> {
+        transition
+            .get_ref(key)
+            .and_then(|id| self.graph.get_element(id))
+            .and_then(|state| state.name.as_deref())
+            .map(|name| escape_name(name).into_owned())
+            .or_else(|| transition.get_str(key).map(String::from))
+            .or_else(|| transition.get_str(unresolved).map(String::from))
+    }
+
+    /// Write `transition [name] first S [accept E] [if G] [do A] then T;` from
+    /// the `trigger` (or `after`), `guard` and `effect` props.
+    ///
+    /// An effect that is not a reference to an action has no notation here,
+    /// so it follows as a line comment.
+    fn transition(&mut self, element: &Element, source: &str, target: &str) {
+        self.out.push_str("transition");
+        if let Some(name) = &element.name {
+            self.out.push(' ');
+            self.out.push_str(&escape_name(name));
+        }
+        self.out.push_str(" first ");
+        self.out.push_str(source);
+
+        let after = element.get_prop("after").and_then(|v| {
+            v.as_float()
+                .map(|f| f.to_string())
+                .or_else(|| v.as_int().map(|i| i.to_string()))
+                .or_else(|| v.as_str().map(String::from))
+        });
+        match (element.get_str("trigger"), after) {
+            (Some(trigger), _) => {
+                self.out.push_str(" accept ");
+                self.out.push_str(trigger);
+            }
+            (None, Some(after)) => {
+                self.out.push_str(" accept after ");
+                self.out.push_str(&after);
+            }
+            (None, None) => {}
+        }
+        if let Some(guard) = element.get_str("guard") {
+            self.out.push_str(" if ");
+            self.out.push_str(guard);
+        }
+        let mut effect_comment = None;
+        match element.get_str("effect") {
+            Some(effect) if is_reference(effect) => {
+                self.out.push_str(" do ");
+                self.out.push_str(effect);
+            }
+            effect => effect_comment = effect,
+        }
+        self.out.push_str(" then ");
+        self.out.push_str(target);
+        self.out.push(';');
+        if let Some(effect) = effect_comment {
+            self.out.push_str(" // do ");
+            self.out.push_str(&effect.replace(['\n', '\r'], " "));
+        }
+        self.out.push('\n');
+    }
+
+    fn import(&mut self, element: &Element) {
+        self.out.push_str("import ");
+        if element.get_bool("importsAll") == Some(true) {
+            self.out.push_str("all ");
+        }
+        self.out.push_str(element.get_str("importedReference").unwrap_or_default());
+        if element.get_bool("isRecursive") == Some(true) {
+            self.out.push_str("::**");
+        } else if element.get_bool("isNamespace") == Some(true) {
+            self.out.push_str("::*");
+        }
+        self.out.push_str(";\n");
+    }
+
+    /// Write a definition, usage or package declaration with its body.
+    fn declaration(&mut self, element: &Element, keyword: &str, depth: usize) {
+        for (flag, prefix) in PREFIX_FLAGS {
+            if element.get_bool(flag) == Some(true) {
+                self.out.push_str(prefix);
+                self.out.push(' ');
+            }
+        }
+        if let Some(direction) = element.get_str("direction") {
+            self.out.push_str(direction);
+            self.out.push(' ');
+        }
+        if element.kind == ElementKind::LibraryPackage && element.get_bool("isStandard") == Some(true) {
+            self.out.push_str("standard ");
+        }
+
+        self.out.push_str(keyword);
+        if let Some(name) = &element.name {
+            self.out.push(' ');
+            self.out.push_str(&escape_name(name));
+        }
+
+        let mut members: Vec<&Element> = Vec::new();
+        let mut typings = Vec::new();
+        let mut subsets = Vec::new();
+        let mut redefines = Vec::new();
+        let mut references = Vec::new();
+        let mut literal = None;
+        for child in self.graph.children_of(&element.id) {
+            match &child.kind {
+                ElementKind::FeatureTyping => {
+                    typings.push(self.reference(child, resolved_props::TYPE, unresolved_props::TYPE))
+                }
+                ElementKind::Redefinition => redefines.push(self.reference(
+                    child,
+                    resolved_props::REDEFINED_FEATURE,
+                    unresolved_props::REDEFINED_FEATURE,
+                )),
+                ElementKind::ReferenceSubsetting => references.push(self.reference(
+                    child,
+                    resolved_props::REFERENCED_FEATURE,
+                    unresolved_props::REFERENCED_FEATURE,
+                )),
+                ElementKind::Subsetting => subsets.push(self.reference(
+                    child,
+                    resolved_props::SUBSETTED_FEATURE,
+                    unresolved_props::SUBSETTED_FEATURE,
+                )),
+                ElementKind::Subclassification => subsets.push(self.reference(
+                    child,
+                    resolved_props::SUPERCLASSIFIER,
+                    unresolved_props::SUPERCLASSIFIER,
+                )),
+                ElementKind::Specialization => {
+                    subsets.push(self.reference(child, resolved_props::GENERAL, unresolved_props::GENERAL))
+                }
+                kind if is_literal(kind) => literal = Some(child),
+                kind if is_member(kind) => members.push(child),
+                _ => {}
+            }
+        }
+
+        let joined = |list: &mut Vec<(Option<usize>, String)>| {
+            list.sort_by_key(|(start, _)| *start);
+            list.iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>().join(", ")
+        };
+        if !typings.is_empty() {
+            self.out.push_str(" : ");
+            self.out.push_str(&joined(&mut typings));
+        }
+        self.multiplicity(element);
+        for (operator, list) in [(":>", &mut subsets), (":>>", &mut redefines), ("::>", &mut references)] {
+            if !list.is_empty() {
+                self.out.push(' ');
+                self.out.push_str(operator);
+                self.out.push(' ');
+                self.out.push_str(&joined(list));
+            }
+        }
+        self.value(element, literal);
+
+        if members.is_empty() {
+            self.out.push_str(";\n");
+            return;
+        }
+        self.formatter.sort(&mut members);
+        self.out.push_str(" {\n");
+        for member in members {
+            self.element(member, depth + 1);
+        }
+        self.indent(depth);
+        self.out.push_str("}\n");
+    }
+
+    /// The text of a reference held by a relationship element, with the start of
+    /// its span for ordering. Prefers the name as written in the source.
+    fn reference(&self, relationship: &Element, resolved: &str, unresolved: &str) -> (Option<usize>, String) {
+        let start = relationship.spans.first().map(|span| span.start);
+        if let Some(text) = relationship.get_str(unresolved) {
+            return (start, text.to_string());
+        }
+        let text = relationship
+            .get_ref(resolved)
+            .and_then(|target| self.graph.qualified_name_of(target))
+            .map(|qname| {
+                qname
+                    .segments()
+                    .iter()
+                    .map(|segment| escape_name(segment))
+                    .collect::<Vec<_>>()
+                    .join("::")
+            })
+            .unwrap_or_default();
+        (start, text)
+    }
+
+    fn multiplicity(&mut self, element: &Element) {
+        let lower = match element.get_int("multiplicity_lower") {
+            Some(lower) => lower,
+            None => return,
+        };
+        match element.get_prop("multiplicity_upper") {
+            Some(Value::Int(upper)) if *upper == lower => self.out.push_str(&format!("[{}]", lower)),
+            Some(Value::Int(upper)) => self.out.push_str(&format!("[{}..{}]", lower, upper)),
+            _ => self.out.push_str(&format!("[{}..*]", lower)),
+        }
+    }
+
+    fn value(&mut self, element: &Element, literal: Option<&Element>) {
+        let text = match (element.get_str(unresolved_props::VALUE), literal) {
+            (Some(expression), _) => expression.to_string(),
+            (None, Some(literal)) => literal_text(literal),
+            (None, None) => return,
+        };
+        if element.get_bool("isInitial") == Some(true) {
+            self.out.push_str(" := ");
+        } else if element.get_bool("isDefault") == Some(true) {
+            self.out.push_str(" default = ");
+        } else {
+            self.out.push_str(" = ");
+        }
+        self.out.push_str(&text);
+    }
+}
+
+/// Boolean props written as declaration prefixes, in notation order.
+const PREFIX_FLAGS: [(&str, &str); 6] = [
+    ("isAbstract", "abstract"),
+    ("isVariation", "variation"),
+    ("isReadOnly", "readonly"),
+    ("isDerived", "derived"),
+    ("isEnd", "end"),
+    ("isConstant", "constant"),
+];
+
+/// The declaration keyword for an element kind, if it has one.
+fn keyword(kind: &ElementKind) -> Option<&'static str> {
+    let keyword = match kind {
+        ElementKind::Package => "package",
+        ElementKind::LibraryPackage => "library package",
+
+        ElementKind::AttributeDefinition => "attribute def",
+        ElementKind::EnumerationDefinition => "enum def",
+        ElementKind::OccurrenceDefinition => "occurrence def",
+        ElementKind::ItemDefinition => "item def",
+        ElementKind::PartDefinition => "part def",
+        ElementKind::PortDefinition => "port def",
+        ElementKind::ConnectionDefinition => "connection def",
+        ElementKind::FlowDefinition => "flow def",
+        ElementKind::InterfaceDefinition => "interface def",
+        ElementKind::AllocationDefinition => "allocation def",
+        ElementKind::ActionDefinition => "action def",
+        ElementKind::CalculationDefinition => "calc def",
+        ElementKind::StateDefinition => "state def",
+        ElementKind::ConstraintDefinition => "constraint def",
+        ElementKind::RequirementDefinition => "requirement def",
+        ElementKind::ConcernDefinition => "concern def",
+        ElementKind::CaseDefinition => "case def",
+        ElementKind::AnalysisCaseDefinition => "analysis def",
+        ElementKind::VerificationCaseDefinition => "verification def",
+        ElementKind::UseCaseDefinition => "use case def",
+        ElementKind::ViewDefinition => "view def",
+        ElementKind::ViewpointDefinition => "viewpoint def",
+        ElementKind::RenderingDefinition => "rendering def",
+        ElementKind::MetadataDefinition => "metadata def",
+
+        ElementKind::AttributeUsage => "attribute",
+        ElementKind::EnumerationUsage => "enum",
+        ElementKind::OccurrenceUsage => "occurrence",
+        ElementKind::EventOccurrenceUsage => "event occurrence",
+        ElementKind::ItemUsage => "item",
+        ElementKind::PartUsage => "part",
+        ElementKind::PortUsage => "port",
+        ElementKind::ConnectionUsage => "connection",
+        ElementKind::FlowUsage => "flow",
+        ElementKind::InterfaceUsage => "interface",
+        ElementKind::AllocationUsage => "allocation",
+        ElementKind::ReferenceUsage => "ref",
+        ElementKind::ActionUsage => "action",
+        ElementKind::PerformActionUsage => "perform action",
+        ElementKind::CalculationUsage => "calc",
+        ElementKind::StateUsage => "state",
+        ElementKind::ExhibitStateUsage => "exhibit state",
+        ElementKind::ConstraintUsage => "constraint",
+        ElementKind::AssertConstraintUsage => "assert constraint",
+        ElementKind::RequirementUsage => "requirement",
+        ElementKind::SatisfyRequirementUsage => "satisfy requirement",
+        ElementKind::ConcernUsage => "concern",
+        ElementKind::CaseUsage => "case",
+        ElementKind::AnalysisCaseUsage => "analysis",
+        ElementKind::VerificationCaseUsage => "verification",
+        ElementKind::UseCaseUsage => "use case",
+        ElementKind::IncludeUseCaseUsage => "include use case",
+        ElementKind::ViewUsage => "view",
+        ElementKind::ViewpointUsage => "viewpoint",
+        ElementKind::RenderingUsage => "rendering",
+        _ => return None,
+    };
+    Some(keyword)
+}
+
+/// Check whether an element is written as a member of its owner.
+///
+/// Memberships, the relationship elements folded into their owner's
+/// declaration, and literal values are not.
+fn is_member(kind: &ElementKind) -> bool {
+    let folded = [
+        ElementKind::Membership,
+        ElementKind::Specialization,
+        ElementKind::Subclassification,
+        ElementKind::FeatureTyping,
+        ElementKind::Subsetting,
+        ElementKind::Redefinition,
+        ElementKind::ReferenceSubsetting,
+    ];
+    !folded
+        .iter()
+        .any(|folded| kind == folded || kind.is_subtype_of(folded.clone()))
+        && !is_literal(kind)
+}
+
+fn is_literal(kind: &ElementKind) -> bool {
+    matches!(
+        kind,
+        ElementKind::LiteralBoolean
+            | ElementKind::LiteralString
+            | ElementKind::LiteralInteger
+            | ElementKind::LiteralRational
+            | ElementKind::LiteralInfinity
+    )
+}
+
+/// Render a literal element as an expression.
+fn literal_text(literal: &Element) -> String {
+    match (&literal.kind, literal.get_prop("value")) {
+        (ElementKind::LiteralInfinity, _) => "*".to_string(),
+        (ElementKind::LiteralString, Some(Value::String(s))) => format!("\"{}\"", s),
+        // Debug keeps a decimal point so the value reparses as a rational
+        (_, Some(Value::Float(f))) => format!("{:?}", f),
+        (_, Some(value)) => value.to_string(),
+        (_, None) => String::new(),
+    }
+}
+
+/// The visibility keyword for an element's membership in its owner, if not public.
+fn visibility_prefix(graph: &ModelGraph, id: &ElementId) -> &'static str {
+    let visibility = graph
+        .owning_membership_of(id)
+        .and_then(|membership| membership.as_membership_view())
+        .map(|view| view.visibility());
+    match visibility {
+        Some(VisibilityKind::Private) => "private ",
+        Some(VisibilityKind::Protected) => "protected ",
+        _ => "",
+    }
+}
+
+/// A comment or documentation body wrapped in `/* */`.
+fn comment_body(element: &Element) -> String {
+    format!("/* {} */", element.get_str("body").unwrap_or_default())
+}
+
+/// Keywords that cannot be used as plain names.
+const KEYWORDS: &[&str] = &[
+    "about", "abstract", "accept", "action", "actor", "after", "alias", "all", "allocate",
+    "allocation", "analysis", "and", "as", "assert", "assign", "assoc", "assume", "at",
+    "attribute", "behavior", "bind", "binding", "bool", "by", "calc", "case", "chains", "class",
+    "classifier", "comment", "composite", "concern", "conjugate", "conjugates", "conjugation",
+    "connect", "connection", "connector", "const", "constant", "constraint", "crosses", "datatype",
+    "decide", "def", "default", "defined", "dependency", "derived", "differences", "disjoining",
+    "disjoint", "do", "doc", "else", "end", "entry", "enum", "event", "exhibit", "exit", "expose",
+    "expr", "false", "feature", "featured", "featuring", "filter", "first", "flow", "for", "fork",
+    "frame", "from", "function", "hastype", "if", "implies", "import", "in", "include",
+    "individual", "inout", "interaction", "interface", "intersects", "inv", "inverse", "inverting",
+    "istype", "item", "join", "language", "library", "locale", "loop", "member", "merge",
+    "message", "meta", "metaclass", "metadata", "multiplicity", "namespace", "new", "nonunique",
+    "not", "null", "objective", "occurrence", "of", "or", "ordered", "out", "package", "parallel",
+    "part", "perform", "port", "portion", "predicate", "private", "protected", "public",
+    "readonly", "redefines", "redefinition", "ref", "references", "render", "rendering", "rep",
+    "require", "requirement", "return", "satisfy", "send", "snapshot", "specialization",
+    "specializes", "stakeholder", "standard", "state", "step", "struct", "subclassifier",
+    "subject", "subset", "subsets", "subtype", "succession", "terminate", "then", "timeslice",
+    "to", "transition", "true", "type", "typed", "typing", "unions", "until", "use", "var",
+    "variant", "variation", "verification", "verify", "via", "view", "viewpoint", "when",
+    "while", "xor",
+];
+
+/// Check whether text is a (possibly qualified) name that needs no quoting.
+fn is_reference(text: &str) -> bool {
+    text.split("::")
+        .all(|segment| !segment.is_empty() && matches!(escape_name(segment), Cow::Borrowed(_)))
+}
+
+/// Quote a name unless it is a plain identifier that is not a keyword.
+///
+/// Quoted names escape `'` and `\` with a backslash.
+pub fn escape_name(name: &str) -> Cow<'_, str> {
+    let mut chars = name.chars();
+    let is_identifier = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_identifier && KEYWORDS.binary_search(&name).is_err() {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysml_core::Span;
+
+    fn owned(graph: &mut ModelGraph, owner: &ElementId, element: Element) -> ElementId {
+        graph.add_owned_element(element, owner.clone(), VisibilityKind::Public)
+    }
+
+    fn typed(graph: &mut ModelGraph, feature: &ElementId, type_name: &str) {
+        owned(
+            graph,
+            feature,
+            Element::new_with_kind(ElementKind::FeatureTyping)
+                .with_prop("typedFeature", Value::Ref(feature.clone()))
+                .with_prop(unresolved_props::TYPE, type_name),
+        );
+    }
+
+    #[test]
+    fn keywords_are_sorted() {
+        assert!(KEYWORDS.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn escape_name_quotes_when_needed() {
+        assert_eq!(escape_name("Engine_2"), "Engine_2");
+        assert_eq!(escape_name("part"), "'part'");
+        assert_eq!(escape_name("Fuel Tank"), "'Fuel Tank'");
+        assert_eq!(escape_name("2nd"), "'2nd'");
+        assert_eq!(escape_name("属性"), "'属性'");
+        assert_eq!(escape_name("it's"), "'it\\'s'");
+        assert_eq!(escape_name(""), "''");
+    }
+
+    #[test]
+    fn formats_usages_with_typing_multiplicity_and_value() {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("P"));
+        let car = owned(&mut graph, &pkg, Element::new_with_kind(ElementKind::PartDefinition).with_name("Car"));
+        let wheels = owned(
+            &mut graph,
+            &car,
+            Element::new_with_kind(ElementKind::PartUsage)
+                .with_name("wheels")
+                .with_prop("multiplicity_lower", 4i64)
+                .with_prop("multiplicity_upper", 4i64),
+        );
+        typed(&mut graph, &wheels, "Wheel");
+        let mass = owned(&mut graph, &car, Element::new_with_kind(ElementKind::AttributeUsage).with_name("mass"));
+        typed(&mut graph, &mass, "Real");
+        owned(
+            &mut graph,
+            &mass,
+            Element::new_with_kind(ElementKind::LiteralRational).with_prop("value", 1500.0),
+        );
+        let anon = owned(&mut graph, &car, Element::new_with_kind(ElementKind::PortUsage));
+        typed(&mut graph, &anon, "FuelPort");
+
+        let text = TextFormatter::new().with_order(MemberOrder::Name).format(&graph);
+        assert_eq!(
+            text,
+            "package P {\n    part def Car {\n        attribute mass : Real = 1500.0;\n        part wheels : Wheel[4];\n        port : FuelPort;\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn formats_specializations_imports_and_docs() {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Fleet Model"));
+        owned(
+            &mut graph,
+            &pkg,
+            Element::new_with_kind(ElementKind::Import)
+                .with_prop("importedReference", "Vehicles")
+                .with_prop("isNamespace", true),
+        );
+        owned(
+            &mut graph,
+            &pkg,
+            Element::new_with_kind(ElementKind::Documentation).with_prop("body", "All vehicles."),
+        );
+        let truck = owned(
+            &mut graph,
+            &pkg,
+            Element::new_with_kind(ElementKind::PartDefinition)
+                .with_name("Truck")
+                .with_prop("isAbstract", true),
+        );
+        owned(
+            &mut graph,
+            &truck,
+            Element::new_with_kind(ElementKind::Specialization)
+                .with_prop("specific", Value::Ref(truck.clone()))
+                .with_prop(unresolved_props::GENERAL, "Vehicles::Vehicle"),
+        );
+        let hidden = graph.add_element(Element::new_with_kind(ElementKind::StateDefinition).with_name("state"));
+        graph.create_owning_membership(pkg.clone(), hidden, VisibilityKind::Private, None);
+
+        let text = TextFormatter::new().with_indent(2).format(&graph);
+        assert!(text.starts_with("package 'Fleet Model' {\n"));
+        assert!(text.contains("  import Vehicles::*;\n"));
+        assert!(text.contains("  doc /* All vehicles. */\n"));
+        assert!(text.contains("  abstract part def Truck :> Vehicles::Vehicle;\n"));
+        assert!(text.contains("  private state def 'state';\n"));
+    }
+
+    #[test]
+    fn source_order_follows_spans() {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("P"));
+        for (name, start) in [("b", 30), ("a", 50), ("c", 10)] {
+            owned(
+                &mut graph,
+                &pkg,
+                Element::new_with_kind(ElementKind::PartUsage)
+                    .with_name(name)
+                    .with_span(Span::new("m.sysml", start, start + 5)),
+            );
+        }
+        owned(&mut graph, &pkg, Element::new_with_kind(ElementKind::PartUsage).with_name("z"));
+
+        let text = TextFormatter::new().format(&graph);
+        assert_eq!(
+            text,
+            "package P {\n    part c;\n    part b;\n    part a;\n    part z;\n}\n"
+        );
+    }
+
+    #[test]
+    fn resolved_references_use_qualified_names() {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("P"));
+        let def = owned(&mut graph, &pkg, Element::new_with_kind(ElementKind::PartDefinition).with_name("Wheel Hub"));
+        let usage = owned(&mut graph, &pkg, Element::new_with_kind(ElementKind::PartUsage).with_name("hub"));
+        owned(
+            &mut graph,
+            &usage,
+            Element::new_with_kind(ElementKind::FeatureTyping)
+                .with_prop("typedFeature", Value::Ref(usage.clone()))
+                .with_prop(resolved_props::TYPE, Value::Ref(def)),
+        );
+
+        let text = TextFormatter::new().with_order(MemberOrder::Name).format(&graph);
+        assert!(text.contains("    part hub : P::'Wheel Hub';\n"));
+    }
+
+    #[test]
+    fn formats_transitions_with_known_ends() {
+        let mut graph = ModelGraph::new();
+        let def = graph.add_element(Element::new_with_kind(ElementKind::StateDefinition).with_name("Lamp"));
+        let mut states = Vec::new();
+        for (name, start) in [("Off", 0), ("On", 10)] {
+            states.push(owned(
+                &mut graph,
+                &def,
+                Element::new_with_kind(ElementKind::StateUsage)
+                    .with_name(name)
+                    .with_span(Span::new("m.sysml", start, start + 5)),
+            ));
+        }
+        let transitions = [
+            Element::new_with_kind(ElementKind::TransitionUsage)
+                .with_name("switchOn")
+                .with_prop("source", Value::Ref(states[0].clone()))
+                .with_prop("target", Value::Ref(states[1].clone()))
+                .with_prop("trigger", "powerOn")
+                .with_prop("guard", "ready")
+                .with_prop("effect", "Actions::lightUp"),
+            Element::new_with_kind(ElementKind::TransitionUsage)
+                .with_prop("unresolved_source", "On")
+                .with_prop("unresolved_target", "Off")
+                .with_prop("after", 30.0)
+                .with_prop("effect", "count += 1"),
+            // Without a target there is nothing to write
+            Element::new_with_kind(ElementKind::TransitionUsage).with_name("dangling").with_prop("source", "On"),
+        ];
+        for (i, transition) in transitions.into_iter().enumerate() {
+            let start = 20 + 10 * i;
+            owned(&mut graph, &def, transition.with_span(Span::new("m.sysml", start, start + 5)));
+        }
+
+        let text = TextFormatter::new().format(&graph);
+        assert_eq!(
+            text,
+            "state def Lamp {
+    state Off;
+    state On;
+    transition switchOn first Off accept powerOn if ready do Actions::lightUp then On;
+    transition first On accept after 30 then Off; // do count += 1
+    // TransitionUsage dangling
+}
+"
+        );
+    }
+
+    #[test]
+    fn unsupported_kinds_become_line_comments() {
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("P"));
+        owned(&mut graph, &pkg, Element::new_with_kind(ElementKind::Dependency).with_name("d"));
+
+        let text = TextFormatter::new().format(&graph);
+        assert_eq!(text, "package P {\n    // Dependency d\n}\n");
+    }
+}
@@ -0,0 +1,58 @@
+//! Round-trip tests: parse a model, format it, parse the output again and check
+//! that the same elements come back with the same owners.
+
+use std::collections::BTreeSet;
+
+use sysml_core::{ElementKind, ModelGraph};
+use sysml_text::{Formatter, Parser, SysmlFile};
+use sysml_text_fmt::{MemberOrder, TextFormatter};
+use sysml_text_pest::PestParser;
+
+const VEHICLE: &str = include_str!("fixtures/vehicle.sysml");
+
+fn parse(path: &str, source: &str) -> ModelGraph {
+    let result = PestParser::new().parse(&[SysmlFile::new(path, source)]);
+    assert!(
+        result.diagnostics.is_empty(),
+        "Parse errors in {}: {:?}\n{}",
+        path,
+        result.diagnostics,
+        source
+    );
+    result.graph
+}
+
+/// Kind, name and owner name of every element that is not a membership.
+fn shape(graph: &ModelGraph) -> BTreeSet<(String, Option<String>, Option<String>)> {
+    graph
+        .elements
+        .values()
+        .filter(|e| !e.kind.is_subtype_of(ElementKind::Membership) && e.kind != ElementKind::Membership)
+        .map(|e| {
+            let owner = e
+                .owner
+                .as_ref()
+                .and_then(|id| graph.get_element(id))
+                .and_then(|owner| owner.name.clone());
+            (e.kind.as_str().to_string(), e.name.clone(), owner)
+        })
+        .collect()
+}
+
+#[test]
+fn roundtrip_preserves_kinds_names_and_ownership() {
+    let original = parse("vehicle.sysml", VEHICLE);
+    let text = TextFormatter::new().format(&original);
+    let reparsed = parse("formatted.sysml", &text);
+
+    assert_eq!(shape(&original), shape(&reparsed), "Formatted text:\n{}", text);
+}
+
+#[test]
+fn formatting_is_stable() {
+    let formatter = TextFormatter::new().with_indent(2).with_order(MemberOrder::Name);
+    let once = formatter.format(&parse("vehicle.sysml", VEHICLE));
+    let twice = formatter.format(&parse("formatted.sysml", &once));
+
+    assert_eq!(once, twice);
+}
@@ -14,7 +14,6 @@
 //! Set `MONTICORE_MODE=jvm` and `MONTICORE_JAR=/path/to/monticore.jar` to use JVM mode.
 
 use sysml_core::ModelGraph;
-use sysml_span::Diagnostic;
 use sysml_text::{ParseResult, Parser, SysmlFile};
 
 /// Transport trait for communicating with the MontiCore parser.
@@ -126,13 +125,7 @@ impl<T: Transport> Parser for MontiCoreParser<T> {
                 // TODO: Parse the JSON result and convert to ModelGraph
                 ParseResult::success(ModelGraph::new())
             }
-            Err(e) => ParseResult {
-                graph: ModelGraph::new(),
-                diagnostics: vec![Diagnostic::error(format!(
-                    "MontiCore transport error: {}",
-                    e
-                ))],
-            },
+            Err(e) => ParseResult::error(format!("MontiCore transport error: {}", e)),
         }
     }
 
@@ -55,6 +55,8 @@ pub struct UsageExtraction<'a> {
 
     // === Body members (stored for later processing) ===
     pub body_pairs: Vec<Pair<'a, Rule>>,
+    /// Source text of the result expression of a calculation or constraint body
+    pub result_expression: Option<String>,
 }
 
 /// All data extracted from a Definition element in one pass.
@@ -72,6 +74,8 @@ pub struct DefinitionExtraction<'a> {
 
     // === Body members (stored for later processing) ===
     pub body_pairs: Vec<Pair<'a, Rule>>,
+    /// Source text of the result expression of a calculation or constraint body
+    pub result_expression: Option<String>,
 }
 
 /// All data extracted from a Package element in one pass.
@@ -462,6 +466,9 @@ impl<'a> UsageExtraction<'a> {
     /// Extract body children pairs for later processing.
     fn extract_body(&mut self, pair: Pair<'a, Rule>) {
         for inner in pair.into_inner() {
+            if inner.as_rule() == Rule::ResultExpressionMember {
+                self.result_expression = result_expression_text(&inner);
+            }
             // Store body items for later processing by the work stack
             self.body_pairs.push(inner);
         }
@@ -582,6 +589,9 @@ impl<'a> DefinitionExtraction<'a> {
     /// Extract body children pairs for later processing.
     fn extract_body(&mut self, pair: Pair<'a, Rule>) {
         for inner in pair.into_inner() {
+            if inner.as_rule() == Rule::ResultExpressionMember {
+                self.result_expression = result_expression_text(&inner);
+            }
             self.body_pairs.push(inner);
         }
     }
@@ -647,6 +657,14 @@ impl<'a> PackageExtraction<'a> {
 // Helper functions
 // =============================================================================
 
+/// Extract the expression text of a ResultExpressionMember, without its visibility.
+fn result_expression_text(pair: &Pair<'_, Rule>) -> Option<String> {
+    pair.clone()
+        .into_inner()
+        .find(|inner| inner.as_rule() == Rule::OwnedExpression)
+        .map(|expression| expression.as_str().trim().to_string())
+}
+
 /// Extract name from RegularName pair.
 fn extract_name_from_regular_name(pair: &Pair<'_, Rule>) -> Option<String> {
     for inner in pair.clone().into_inner() {
@@ -28,7 +28,7 @@
 mod extraction;
 
 use pest::iterators::{Pair, Pairs};
-use sysml_core::{Element, ElementKind, ModelGraph, Value, VisibilityKind};
+use sysml_core::{Element, ElementKind, MembershipBuilder, ModelGraph, Value, VisibilityKind};
 use sysml_id::ElementId;
 use sysml_span::{LineIndex, Span};
 
@@ -355,8 +355,9 @@ impl<'a> Converter<'a> {
             Rule::JoinNode => { self.process_usage(pair, graph, ElementKind::JoinNode, span, work_stack)?; }
             Rule::ForkNode => { self.process_usage(pair, graph, ElementKind::ForkNode, span, work_stack)?; }
 
-            // Imports
+            // Imports and aliases
             Rule::Import => { self.process_import(pair, graph, span)?; }
+            Rule::AliasMember => { self.process_alias(pair, graph, span)?; }
 
             // Annotations - AnnotatingElement passes through, AnnotatingMember extracts visibility
             Rule::AnnotatingElement => {
@@ -453,8 +454,6 @@ impl<'a> Converter<'a> {
             // Action body parameters
             | Rule::ActionBodyParameter | Rule::ActionBodyParameterMember
             | Rule::ActionNodeUsageDeclaration
-            // Alias members
-            | Rule::AliasMember
             // Arguments for invocations/expressions
             | Rule::Argument | Rule::ArgumentList | Rule::ArgumentMember | Rule::ArgumentValue
             | Rule::PositionalArgumentList
@@ -689,6 +688,11 @@ impl<'a> Converter<'a> {
             element.set_prop("isVariation", true);
         }
 
+        // Result expression of a calculation or constraint body, as written
+        if let Some(expression) = extraction.result_expression {
+            element.set_prop("expression", expression);
+        }
+
         if let Some(s) = span.clone() {
             element.spans.push(s);
         }
@@ -785,6 +789,11 @@ impl<'a> Converter<'a> {
             element.set_prop("isConstant", true);
         }
 
+        // Result expression of a calculation or constraint body, as written
+        if let Some(expression) = extraction.result_expression {
+            element.set_prop("expression", expression);
+        }
+
         if let Some(s) = span.clone() {
             element.spans.push(s);
         }
@@ -830,6 +839,16 @@ impl<'a> Converter<'a> {
             element.set_prop("importedReference", reference);
         }
 
+        // Record an explicit visibility, which decides whether the import
+        // re-exports its names
+        for inner in pair.clone().into_inner() {
+            if inner.as_rule() == Rule::ImportPrefix {
+                if let Some(visibility) = inner.into_inner().find(|p| p.as_rule() == Rule::Visibility) {
+                    element.set_prop("visibility", visibility.as_str().trim());
+                }
+            }
+        }
+
         // Check for flags
         let text = pair.as_str();
         if text.contains("all ") {
@@ -853,6 +872,38 @@ impl<'a> Converter<'a> {
         Ok(())
     }
 
+    /// Process an alias (`alias X for A::B;`).
+    ///
+    /// An alias becomes a non-owning Membership of the current namespace,
+    /// named by the alias, with its target left for name resolution.
+    fn process_alias(&mut self, pair: Pair<'_, Rule>, graph: &mut ModelGraph, span: Option<Span>) -> Result<(), ParseError> {
+        let owner_id = match self.owner_stack.last() {
+            Some(owner_id) => owner_id.clone(),
+            None => return Ok(()),
+        };
+
+        let mut builder = MembershipBuilder::new()
+            .owning_namespace(owner_id)
+            .visibility(self.extract_visibility(&pair));
+        if let Some(name) = self.extract_name(&pair) {
+            builder = builder.member_name(name);
+        }
+        let mut element = builder.build();
+
+        if let Some(target) = self.extract_qualified_name(&pair) {
+            element.set_prop("unresolved_memberElement", target);
+        }
+
+        // Add span
+        if let Some(s) = span {
+            element.spans.push(s);
+        }
+
+        graph.add_element(element);
+
+        Ok(())
+    }
+
     /// Process a Comment (no children to process).
     fn process_comment(&mut self, pair: Pair<'_, Rule>, graph: &mut ModelGraph, span: Option<Span>) -> Result<(), ParseError> {
         let mut element = Element::new_with_kind(ElementKind::Comment);
@@ -1050,22 +1101,24 @@ impl<'a> Converter<'a> {
         }
 
         let pest_span = pair.as_span();
-        let (line, col) = if let Some(ref index) = self.line_index {
-            // O(log n) lookup using pre-computed line index
-            index.line_col(pest_span.start())
+        let mut span = Span::new(self.file_path, pest_span.start(), pest_span.end());
+        if let Some(ref index) = self.line_index {
+            // O(log n) lookups using pre-computed line index
+            span.with_line_cols(index);
         } else {
             // O(n) fallback - scans from byte 0 to position
             let (l, c) = pest_span.start_pos().line_col();
-            (l as u32, c as u32)
-        };
+            let (end_l, end_c) = pest_span.end_pos().line_col();
+            span = Span::with_full_location(
+                self.file_path,
+                pest_span.start(),
+                pest_span.end(),
+                (l as u32, c as u32),
+                (end_l as u32, end_c as u32),
+            );
+        }
 
-        Some(Span::with_location(
-            self.file_path,
-            pest_span.start(),
-            pest_span.end(),
-            line,
-            col,
-        ))
+        Some(span)
     }
 
     // =========================================================================
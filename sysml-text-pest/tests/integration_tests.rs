@@ -1781,6 +1781,79 @@ fn parse_and_resolve_import() {
     );
 }
 
+/// The element a usage's FeatureTyping resolved to, by name.
+fn resolved_type_name(graph: &sysml_core::ModelGraph, usage: &str) -> Option<String> {
+    let usage = graph.elements.values().find(|e| e.name.as_deref() == Some(usage))?;
+    let typing = graph
+        .children_of(&usage.id)
+        .find(|e| e.kind == sysml_core::ElementKind::FeatureTyping)?;
+    let target = typing.get_ref("type")?;
+    graph.get_element(target)?.name.clone()
+}
+
+#[test]
+fn parse_and_resolve_alias() {
+    let source = r#"
+        package Lib {
+            package Inner {
+                part def Deep;
+            }
+        }
+        package Use {
+            alias D for Lib::Inner::Deep;
+            part d : D;
+        }
+    "#;
+
+    let parser = PestParser::new();
+    let file = SysmlFile::new("test.sysml", source);
+    let result = parser.parse(&[file]).into_resolved();
+    assert!(result.is_ok(), "Parse and resolve should succeed: {:?}", result.diagnostics);
+
+    // The alias is named by its own name and targets the qualified name
+    let alias = result
+        .graph
+        .elements_by_kind(&sysml_core::ElementKind::Membership)
+        .find(|e| e.get_prop("memberName").and_then(|v| v.as_str()) == Some("D"))
+        .expect("alias membership");
+    assert_eq!(
+        alias.get_prop("unresolved_memberElement").and_then(|v| v.as_str()),
+        Some("Lib::Inner::Deep")
+    );
+
+    assert_eq!(resolved_type_name(&result.graph, "d").as_deref(), Some("Deep"));
+}
+
+#[test]
+fn parse_and_resolve_public_import_reexport() {
+    let source = r#"
+        package A {
+            part def Engine;
+        }
+        package B {
+            public import A::*;
+        }
+        package C {
+            import B::*;
+            part e : Engine;
+        }
+    "#;
+
+    let parser = PestParser::new();
+    let file = SysmlFile::new("test.sysml", source);
+    let result = parser.parse(&[file]).into_resolved();
+    assert!(result.is_ok(), "Parse and resolve should succeed: {:?}", result.diagnostics);
+
+    let visibilities: Vec<Option<&str>> = result
+        .graph
+        .elements_by_kind(&sysml_core::ElementKind::Import)
+        .map(|e| e.get_prop("visibility").and_then(|v| v.as_str()))
+        .collect();
+    assert!(visibilities.contains(&Some("public")), "{:?}", visibilities);
+
+    assert_eq!(resolved_type_name(&result.graph, "e").as_deref(), Some("Engine"));
+}
+
 #[test]
 fn parse_and_resolve_unresolved_error() {
     let source = r#"
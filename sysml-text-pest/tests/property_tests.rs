@@ -397,6 +397,49 @@ fn flag_end_feature() {
     assert_eq!(parts.len(), 2, "Expected 2 PartUsage (source and target)");
 }
 
+// =============================================================================
+// Result Expression Tests (constraint and calculation bodies)
+// =============================================================================
+
+#[test]
+fn constraint_body_expression() {
+    let source = "package P { constraint def MassLimit { mass <= 1200 } assert constraint speedOk { speed > 0 } }";
+    let result = parse_source(source);
+
+    assert!(
+        result.diagnostics.is_empty(),
+        "Parse errors: {:?}",
+        result.diagnostics
+    );
+
+    let def = result
+        .graph
+        .elements_by_kind(&ElementKind::ConstraintDefinition)
+        .next()
+        .expect("Should have ConstraintDefinition");
+    assert_eq!(def.get_str("expression"), Some("mass <= 1200"));
+
+    let assert = result
+        .graph
+        .elements_by_kind(&ElementKind::AssertConstraintUsage)
+        .next()
+        .expect("Should have AssertConstraintUsage");
+    assert_eq!(assert.get_str("expression"), Some("speed > 0"));
+}
+
+#[test]
+fn constraint_without_body_has_no_expression() {
+    let source = "package P { constraint def Empty; }";
+    let result = parse_source(source);
+
+    let def = result
+        .graph
+        .elements_by_kind(&ElementKind::ConstraintDefinition)
+        .next()
+        .expect("Should have ConstraintDefinition");
+    assert!(!def.props.contains_key("expression"));
+}
+
 // =============================================================================
 // False Positive Prevention Tests (Phase 2b.3)
 // =============================================================================
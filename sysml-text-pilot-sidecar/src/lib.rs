@@ -14,7 +14,6 @@
 //! Set `PILOT_MODE=jvm` and `PILOT_JAR=/path/to/pilot.jar` to use JVM mode.
 
 use sysml_core::ModelGraph;
-use sysml_span::Diagnostic;
 use sysml_text::{ParseResult, Parser, SysmlFile};
 
 /// Transport trait for communicating with the Pilot parser.
@@ -126,10 +125,7 @@ impl<T: Transport> Parser for PilotParser<T> {
                 // TODO: Parse the JSON result and convert to ModelGraph
                 ParseResult::success(ModelGraph::new())
             }
-            Err(e) => ParseResult {
-                graph: ModelGraph::new(),
-                diagnostics: vec![Diagnostic::error(format!("Pilot transport error: {}", e))],
-            },
+            Err(e) => ParseResult::error(format!("Pilot transport error: {}", e)),
         }
     }
 
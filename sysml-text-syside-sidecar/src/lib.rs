@@ -12,7 +12,6 @@
 //! Set `SYSIDE_ENABLED=1` and `SYSIDE_URL=http://localhost:8082` to enable.
 
 use sysml_core::ModelGraph;
-use sysml_span::Diagnostic;
 use sysml_text::{ParseResult, Parser, SysmlFile};
 
 /// Transport trait for communicating with the SySide parser.
@@ -99,13 +98,7 @@ impl<T: Transport> Parser for SySideParser<T> {
                 // TODO: Parse the JSON result and convert to ModelGraph
                 ParseResult::success(ModelGraph::new())
             }
-            Err(e) => ParseResult {
-                graph: ModelGraph::new(),
-                diagnostics: vec![Diagnostic::error(format!(
-                    "SySide transport error: {}",
-                    e
-                ))],
-            },
+            Err(e) => ParseResult::error(format!("SySide transport error: {}", e)),
         }
     }
 
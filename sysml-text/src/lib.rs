@@ -20,12 +20,18 @@
 //! // Parse and resolve with library
 //! let result = parser.parse(&files).into_resolved_with_library(library);
 //! ```
+//!
+//! ## Workspaces
+//!
+//! To resolve references across files, keep them in a
+//! [`workspace::Workspace`], which reparses only the files that change.
 
 pub mod library;
+pub mod workspace;
 
 use sysml_core::resolution::{resolve_references, resolve_references_excluding, ResolutionResult};
-use sysml_core::ModelGraph;
-use sysml_span::Diagnostic;
+use sysml_core::{MergePolicy, ModelGraph};
+use sysml_span::{Diagnostic, DiagnosticFilter};
 
 /// A SysML source file to be parsed.
 #[derive(Debug, Clone)]
@@ -53,12 +59,18 @@ pub struct ParseResult {
     pub graph: ModelGraph,
     /// Any diagnostics (errors, warnings) from parsing.
     pub diagnostics: Vec<Diagnostic>,
+    /// Filter re-applied whenever resolution or validation adds diagnostics.
+    filter: Option<DiagnosticFilter>,
 }
 
 impl ParseResult {
     /// Create a new parse result.
     pub fn new(graph: ModelGraph, diagnostics: Vec<Diagnostic>) -> Self {
-        ParseResult { graph, diagnostics }
+        ParseResult {
+            graph,
+            diagnostics,
+            filter: None,
+        }
     }
 
     /// Create a successful parse result with no diagnostics.
@@ -66,6 +78,7 @@ impl ParseResult {
         ParseResult {
             graph,
             diagnostics: Vec::new(),
+            filter: None,
         }
     }
 
@@ -74,6 +87,31 @@ impl ParseResult {
         ParseResult {
             graph: ModelGraph::new(),
             diagnostics: vec![Diagnostic::error(message)],
+            filter: None,
+        }
+    }
+
+    /// Apply a project-level diagnostic filter.
+    ///
+    /// The filter applies to the current diagnostics and to those added later
+    /// by [`into_resolved`](Self::into_resolved) and the validation methods,
+    /// so [`is_ok`](Self::is_ok) and [`has_errors`](Self::has_errors) reflect
+    /// the filtered view.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let (filter, warnings) = DiagnosticFilter::from_config(&config)?;
+    /// let result = parser.parse(&files).with_filter(filter).into_resolved().into_validated();
+    /// ```
+    pub fn with_filter(mut self, filter: DiagnosticFilter) -> Self {
+        filter.apply(&mut self.diagnostics);
+        self.filter = Some(filter);
+        self
+    }
+
+    fn apply_filter(&mut self) {
+        if let Some(filter) = &self.filter {
+            filter.apply(&mut self.diagnostics);
         }
     }
 
@@ -112,6 +150,7 @@ impl ParseResult {
         for diag in res.diagnostics.iter() {
             self.diagnostics.push(diag.clone());
         }
+        self.apply_filter();
 
         self
     }
@@ -162,8 +201,9 @@ impl ParseResult {
             library.elements.keys().cloned().collect();
 
         // Merge library into our graph (as_library=true registers root packages)
-        // Note: merge() now properly merges indexes, so no rebuild_indexes() needed
-        self.graph.merge(library, true);
+        // Note: merge() indexes the merged content, so no rebuild_indexes() needed.
+        // KeepExisting cannot fail and makes re-merging a library already present a no-op.
+        let _ = self.graph.merge(library, true, MergePolicy::KeepExisting);
 
         // Resolve only non-library elements
         let _result = resolve_references_excluding(&mut self.graph, &library_element_ids);
@@ -182,9 +222,9 @@ impl ParseResult {
         let library_element_ids: std::collections::HashSet<_> =
             library.elements.keys().cloned().collect();
 
-        // Merge library into our graph
-        // Note: merge() now properly merges indexes, so no rebuild_indexes() needed
-        self.graph.merge(library, true);
+        // Merge library into our graph, keeping elements already present
+        // Note: merge() indexes the merged content, so no rebuild_indexes() needed
+        let _ = self.graph.merge(library, true, MergePolicy::KeepExisting);
 
         // Resolve only non-library elements
         resolve_references_excluding(&mut self.graph, &library_element_ids)
@@ -197,6 +237,7 @@ impl ParseResult {
     /// - Ownership cycles
     /// - Dangling references in memberships
     /// - Invalid owning_membership references
+    /// - Duplicate names within a namespace
     ///
     /// # Example
     /// ```ignore
@@ -215,6 +256,7 @@ impl ParseResult {
             self.diagnostics
                 .push(error.to_diagnostic_with_graph(&self.graph));
         }
+        self.apply_filter();
     }
 
     /// Run relationship type validation and add any errors to diagnostics.
@@ -238,11 +280,72 @@ impl ParseResult {
             self.diagnostics
                 .push(error.to_diagnostic_with_graph(&self.graph));
         }
+        self.apply_filter();
+    }
+
+    /// Run multiplicity validation and add any errors to diagnostics.
+    ///
+    /// This checks that each feature's multiplicity bounds are consistent
+    /// and within those of the features it redefines or subsets (see
+    /// [`sysml_core::validate_multiplicities`]). Like relationship
+    /// validation, it is most useful after name resolution.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let parser = PestParser::new();
+    /// let mut result = parser.parse(&files).into_resolved();
+    /// result.validate_multiplicities();
+    /// ```
+    pub fn validate_multiplicities(&mut self) {
+        let errors = sysml_core::validate_multiplicities(&self.graph);
+        for error in errors {
+            self.diagnostics
+                .push(error.to_diagnostic_with_graph(&self.graph));
+        }
+        self.apply_filter();
+    }
+
+    /// Report the references that are still unresolved, as warnings.
+    ///
+    /// Unlike the errors of a resolution pass, the report is computed from
+    /// the graph as it stands (see [`ModelGraph::unresolved_references`]) and
+    /// is returned rather than added to the diagnostics. Each warning is at
+    /// the span of the referencing element and, when the graph has names
+    /// close to the reference, has a note suggesting up to five of them.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let parser = PestParser::new();
+    /// let result = parser.parse(&files).into_resolved();
+    /// for warning in result.report_unresolved() {
+    ///     eprintln!("{}", warning);
+    /// }
+    /// ```
+    pub fn report_unresolved(&self) -> Vec<Diagnostic> {
+        self.graph
+            .unresolved_references()
+            .into_iter()
+            .map(|unresolved| {
+                let mut diagnostic = Diagnostic::warning(format!(
+                    "Reference '{}' for property '{}' is unresolved",
+                    unresolved.reference, unresolved.property
+                ))
+                .with_code("W200");
+                if let Some(span) = unresolved.span {
+                    diagnostic = diagnostic.with_span(span);
+                }
+                let candidates = sysml_core::similar_names(&self.graph, &unresolved.reference);
+                if !candidates.is_empty() {
+                    diagnostic = diagnostic.with_note(format!("did you mean: {}", candidates.join(", ")));
+                }
+                diagnostic
+            })
+            .collect()
     }
 
     /// Run all validations and add any errors to diagnostics.
     ///
-    /// This runs both structural validation and relationship type validation.
+    /// This runs structural, relationship type and multiplicity validation.
     /// Returns `self` for method chaining.
     ///
     /// # Example
@@ -253,8 +356,37 @@ impl ParseResult {
     pub fn into_validated(mut self) -> Self {
         self.validate_structure();
         self.validate_relationships();
+        self.validate_multiplicities();
         self
     }
+
+    /// Convert the diagnostics into a SARIF 2.1.0 log (see [`sysml_span::to_sarif`]).
+    ///
+    /// Spans without line and column numbers get no region; use
+    /// [`diagnostics_to_sarif_with_sources`](Self::diagnostics_to_sarif_with_sources)
+    /// to compute them from the parsed files.
+    #[cfg(feature = "sarif")]
+    pub fn diagnostics_to_sarif(&self) -> String {
+        sysml_span::to_sarif(&self.diagnostics, "sysml-rs")
+    }
+
+    /// Convert the diagnostics into a SARIF 2.1.0 log, computing missing line
+    /// and column numbers from the text of `files`.
+    #[cfg(feature = "sarif")]
+    pub fn diagnostics_to_sarif_with_sources(&self, files: &[SysmlFile]) -> String {
+        sysml_span::to_sarif_with_source(&self.diagnostics, "sysml-rs", &FileSources(files))
+    }
+}
+
+/// Looks up source text among parsed files by path.
+#[cfg(feature = "sarif")]
+struct FileSources<'a>(&'a [SysmlFile]);
+
+#[cfg(feature = "sarif")]
+impl sysml_span::SourceProvider for FileSources<'_> {
+    fn source(&self, file: &str) -> Option<&str> {
+        self.0.iter().find(|f| f.path == file).map(|f| f.text.as_str())
+    }
 }
 
 impl Default for ParseResult {
@@ -386,6 +518,77 @@ mod tests {
         assert_eq!(result.error_count(), 1);
     }
 
+    #[test]
+    fn filter_suppresses_orphan_errors() {
+        use sysml_core::{Element, ElementKind};
+        use sysml_span::{FilterAction, FilterRule};
+
+        let mut graph = ModelGraph::new();
+        graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("orphan"));
+
+        let mut unfiltered = ParseResult::success(graph.clone());
+        unfiltered.validate_structure();
+        assert!(unfiltered.has_errors());
+
+        let filter = DiagnosticFilter::new().with_rule(FilterRule::code("E001", FilterAction::Suppress));
+        let mut result = ParseResult::success(graph).with_filter(filter);
+        result.validate_structure();
+        assert!(result.is_ok());
+        assert!(result.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn filter_downgrades_resolution_errors() {
+        use sysml_core::resolution::unresolved_props;
+        use sysml_core::{Element, ElementKind, Value, VisibilityKind};
+        use sysml_span::{FilterAction, FilterRule, Severity};
+
+        let mut graph = ModelGraph::new();
+        let pkg_id = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Pkg"));
+        let mut typing = Element::new_with_kind(ElementKind::FeatureTyping);
+        typing.set_prop(unresolved_props::TYPE, Value::String("Pkg::Missing".to_string()));
+        graph.add_owned_element(typing, pkg_id, VisibilityKind::Public);
+
+        assert!(!ParseResult::success(graph.clone()).into_resolved().is_ok());
+
+        let filter =
+            DiagnosticFilter::new().with_rule(FilterRule::code("E200", FilterAction::Downgrade(Severity::Warning)));
+        let result = ParseResult::success(graph).with_filter(filter).into_resolved();
+        assert!(result.is_ok());
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn unresolved_report_suggests_close_names() {
+        use sysml_core::resolution::unresolved_props;
+        use sysml_core::{Element, ElementKind, VisibilityKind};
+        use sysml_span::{Severity, Span};
+
+        let mut graph = ModelGraph::new();
+        let pkg_id = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Pkg"));
+        let engine = Element::new_with_kind(ElementKind::PartDefinition).with_name("Engine");
+        graph.add_owned_element(engine, pkg_id.clone(), VisibilityKind::Public);
+        let typing = Element::new_with_kind(ElementKind::FeatureTyping)
+            .with_prop(unresolved_props::TYPE, "Engin")
+            .with_span(Span::new("car.sysml", 12, 30));
+        graph.add_owned_element(typing, pkg_id, VisibilityKind::Public);
+
+        let result = ParseResult::success(graph).into_resolved();
+        let diagnostics_before = result.diagnostics.len();
+        let report = result.report_unresolved();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].severity, Severity::Warning);
+        assert_eq!(report[0].code.as_deref(), Some("W200"));
+        assert!(sysml_span::KNOWN_CODES.contains(&"W200"));
+        assert_eq!(report[0].span, Some(Span::new("car.sysml", 12, 30)));
+        assert!(report[0].notes.iter().any(|note| note == "did you mean: Engine"));
+        assert_eq!(result.diagnostics.len(), diagnostics_before);
+
+        let resolved = ParseResult::success(ModelGraph::new()).into_resolved();
+        assert!(resolved.report_unresolved().is_empty());
+    }
+
     #[test]
     fn noop_parser() {
         let parser = NoopParser::new();
@@ -439,6 +642,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn validate_structure_reports_duplicate_names() {
+        use sysml_core::{Element, ElementKind, VisibilityKind};
+
+        let mut graph = ModelGraph::new();
+        let pkg = Element::new_with_kind(ElementKind::Package).with_name("Pkg");
+        let pkg_id = graph.add_element(pkg);
+        for _ in 0..2 {
+            let part = Element::new_with_kind(ElementKind::PartUsage).with_name("engine");
+            graph.add_owned_element(part, pkg_id.clone(), VisibilityKind::Public);
+        }
+
+        let mut result = ParseResult::success(graph);
+        result.validate_structure();
+
+        let duplicates: Vec<_> = result
+            .diagnostics
+            .iter()
+            .filter(|d| d.code == Some("E009".to_string()))
+            .collect();
+        assert_eq!(duplicates.len(), 1);
+        assert!(duplicates[0].message.contains("'engine'"));
+    }
+
     #[test]
     fn validate_structure_no_errors_on_valid_graph() {
         use sysml_core::{Element, ElementKind};
@@ -468,4 +695,46 @@ mod tests {
         let result = ParseResult::success(graph).into_validated();
         assert!(result.is_ok(), "Valid graph should pass validation");
     }
+
+    #[test]
+    fn into_validated_reports_multiplicities_at_their_span() {
+        use sysml_core::{Element, ElementKind, VisibilityKind};
+        use sysml_span::Span;
+
+        let mut graph = ModelGraph::new();
+        let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Pkg"));
+        let part = Element::new_with_kind(ElementKind::PartUsage)
+            .with_name("wheels")
+            .with_prop("multiplicity_lower", 4i64)
+            .with_prop("multiplicity_upper", 2i64)
+            .with_span(Span::new("a.sysml", 10, 30));
+        graph.add_owned_element(part, pkg, VisibilityKind::Public);
+
+        let result = ParseResult::success(graph).into_validated();
+        assert_eq!(result.error_count(), 1);
+        let diagnostic = &result.diagnostics[0];
+        assert_eq!(diagnostic.code.as_deref(), Some("V010"));
+        assert_eq!(diagnostic.span.as_ref().map(|span| span.start), Some(10));
+    }
+
+    #[cfg(feature = "sarif")]
+    #[test]
+    fn diagnostics_to_sarif_uses_file_text() {
+        use sysml_span::Span;
+
+        let files = vec![SysmlFile::new("a.sysml", "package A {\n    part x : Missing;\n}\n")];
+        let mut result = ParseResult::success(ModelGraph::new());
+        result.diagnostics.push(
+            Diagnostic::error("unresolved type 'Missing'")
+                .with_code("E101")
+                .with_span(Span::new("a.sysml", 25, 32)),
+        );
+
+        assert!(result.diagnostics_to_sarif().contains("\"ruleId\": \"E101\""));
+        assert!(!result.diagnostics_to_sarif().contains("startLine"));
+
+        let sarif = result.diagnostics_to_sarif_with_sources(&files);
+        assert!(sarif.contains("\"startLine\": 2"));
+        assert!(sarif.contains("\"startColumn\": 14"));
+    }
 }
@@ -21,7 +21,7 @@
 
 use std::path::{Path, PathBuf};
 
-use sysml_core::{ElementKind, ModelGraph};
+use sysml_core::{ElementKind, MergePolicy, ModelGraph};
 use thiserror::Error;
 use walkdir::WalkDir;
 
@@ -161,7 +161,7 @@ pub fn load_standard_library<P: Parser>(
         let kerml_dir = config.library_path.join("library.kernel");
         if kerml_dir.exists() {
             let (graph, errors) = load_files_from_dir(parser, &kerml_dir, "kerml")?;
-            combined.merge(graph, false);
+            let _ = combined.merge(graph, false, MergePolicy::ReplaceExisting);
             total_errors += errors;
         }
     }
@@ -171,7 +171,7 @@ pub fn load_standard_library<P: Parser>(
         let sysml_dir = config.library_path.join("library.systems");
         if sysml_dir.exists() {
             let (graph, errors) = load_files_from_dir(parser, &sysml_dir, "sysml")?;
-            combined.merge(graph, false);
+            let _ = combined.merge(graph, false, MergePolicy::ReplaceExisting);
             total_errors += errors;
         }
     }
@@ -186,7 +186,7 @@ pub fn load_standard_library<P: Parser>(
                     let path = entry.path();
                     if path.is_dir() {
                         let (graph, errors) = load_files_from_dir(parser, &path, "sysml")?;
-                        combined.merge(graph, false);
+                        let _ = combined.merge(graph, false, MergePolicy::ReplaceExisting);
                         total_errors += errors;
                     }
                 }
@@ -194,8 +194,7 @@ pub fn load_standard_library<P: Parser>(
         }
     }
 
-    // Rebuild indexes after merging
-    combined.rebuild_indexes();
+    // merge() keeps the indexes up to date, so no rebuild_indexes() is needed
 
     // Register all root packages as library packages
     register_library_packages(&mut combined);
@@ -0,0 +1,269 @@
+//! Multi-file workspaces for cross-file resolution.
+//!
+//! A [`Workspace`] keeps the parsed, unresolved content of every file in one
+//! [`ModelGraph`] and remembers which elements came from which file. When a
+//! file changes, only that file is reparsed: its previous elements are
+//! removed and the new ones merged in. [`Workspace::analyze`] then resolves a
+//! copy of the whole workspace (optionally with the standard library), so
+//! references into sibling files resolve.
+//!
+//! ```ignore
+//! let mut workspace = Workspace::new();
+//! for path in sysml_files("models") {
+//!     let text = std::fs::read_to_string(&path)?;
+//!     workspace.update_file(&parser, SysmlFile::new(path.display().to_string(), text));
+//! }
+//! let result = workspace.analyze("models/car.sysml", Some(&library));
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use sysml_core::{ElementId, MergePolicy, ModelGraph};
+use sysml_span::{Diagnostic, Severity};
+use walkdir::WalkDir;
+
+use crate::{ParseResult, Parser, SysmlFile};
+
+/// The parsed files of a workspace, kept in one graph.
+#[derive(Debug, Clone, Default)]
+pub struct Workspace {
+    graph: ModelGraph,
+    files: BTreeMap<String, WorkspaceFile>,
+}
+
+/// What the workspace graph holds for one file.
+#[derive(Debug, Clone, Default)]
+struct WorkspaceFile {
+    /// Elements merged from the file's last successful parse.
+    elements: Vec<ElementId>,
+    /// Relationships merged from the file's last successful parse.
+    relationships: Vec<ElementId>,
+    /// Diagnostics from the file's latest parse.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Workspace {
+    /// Create an empty workspace.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a file and replace its previous content in the workspace.
+    ///
+    /// When the parse has errors, the file's previous content is kept so that
+    /// other files keep resolving against it while it is being edited; the
+    /// errors are still reported by [`analyze`](Self::analyze).
+    ///
+    /// # Returns
+    ///
+    /// The diagnostics from parsing the file.
+    pub fn update_file<P: Parser + ?Sized>(&mut self, parser: &P, file: SysmlFile) -> &[Diagnostic] {
+        let path = file.path.clone();
+        let result = parser.parse(&[file]);
+
+        if result.error_count() == 0 {
+            self.remove_content(&path);
+            let entry = self.files.entry(path.clone()).or_default();
+            entry.elements = result.graph.elements.keys().cloned().collect();
+            entry.relationships = result.graph.relationships.keys().cloned().collect();
+            // Stale content was removed above, so collisions are only with
+            // other files; keep theirs
+            let _ = self.graph.merge(result.graph, false, MergePolicy::KeepExisting);
+        }
+
+        let entry = self.files.entry(path).or_default();
+        entry.diagnostics = result.diagnostics;
+        &entry.diagnostics
+    }
+
+    /// Remove a file and its content from the workspace.
+    ///
+    /// Returns false if the file was not in the workspace.
+    pub fn remove_file(&mut self, path: &str) -> bool {
+        self.remove_content(path);
+        self.files.remove(path).is_some()
+    }
+
+    /// Check whether a file is in the workspace.
+    pub fn contains_file(&self, path: &str) -> bool {
+        self.files.contains_key(path)
+    }
+
+    /// The paths of the files in the workspace, in sorted order.
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(String::as_str)
+    }
+
+    /// The unresolved graph of all files.
+    pub fn graph(&self) -> &ModelGraph {
+        &self.graph
+    }
+
+    /// Resolve the workspace and return the result for one file.
+    ///
+    /// The graph of the result is the whole workspace, resolved and merged
+    /// with the library if one is given. Its diagnostics are the file's
+    /// parse diagnostics followed by the resolution diagnostics located in
+    /// the file. Resolution is skipped when the file has parse errors.
+    pub fn analyze(&self, path: &str, library: Option<&ModelGraph>) -> ParseResult {
+        let diagnostics = self
+            .files
+            .get(path)
+            .map(|file| file.diagnostics.clone())
+            .unwrap_or_default();
+        let mut result = ParseResult::new(self.graph.clone(), diagnostics);
+        if result.diagnostics.iter().any(|diag| diag.severity == Severity::Error) {
+            return result;
+        }
+
+        let resolution = match library {
+            Some(library) => result.resolve_with_library(library.clone()),
+            None => result.resolve(),
+        };
+        result.diagnostics.extend(
+            resolution
+                .diagnostics
+                .into_iter()
+                .filter(|diag| diag.span.as_ref().is_some_and(|span| span.file == path)),
+        );
+        result
+    }
+
+    /// Remove the elements and relationships merged from a file.
+    fn remove_content(&mut self, path: &str) {
+        let file = match self.files.get_mut(path) {
+            Some(file) => file,
+            None => return,
+        };
+        for id in file.relationships.drain(..) {
+            self.graph.remove_relationship(&id);
+        }
+        for id in file.elements.drain(..) {
+            self.graph.remove_element(&id);
+        }
+    }
+}
+
+/// Find the `.sysml` files under a directory, in sorted order.
+///
+/// Entries that cannot be read are skipped.
+pub fn sysml_files(root: impl AsRef<Path>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sysml"))
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysml_core::resolution::unresolved_props;
+    use sysml_core::{Element, ElementKind, Span, VisibilityKind};
+
+    /// Parses one declaration per file: `def Pkg::Name` for a part
+    /// definition, `use Pkg::name : Type` for a typed part usage and `error`
+    /// for a syntax error.
+    struct DeclParser;
+
+    impl Parser for DeclParser {
+        fn parse(&self, inputs: &[SysmlFile]) -> ParseResult {
+            let file = &inputs[0];
+            let span = Span::new(file.path.as_str(), 0, file.text.len());
+            let (decl, typ) = match file.text.split_once(" : ") {
+                Some((decl, typ)) => (decl, Some(typ)),
+                None => (file.text.as_str(), None),
+            };
+            let (kind, qname) = match decl.split_once(' ') {
+                Some(("def", qname)) => (ElementKind::PartDefinition, qname),
+                Some(("use", qname)) => (ElementKind::PartUsage, qname),
+                _ => return ParseResult::error("syntax error"),
+            };
+            let (pkg, name) = qname.split_once("::").unwrap();
+
+            let mut graph = ModelGraph::new();
+            let pkg = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name(pkg));
+            let element = Element::new_with_kind(kind).with_name(name).with_span(span.clone());
+            let element = graph.add_owned_element(element, pkg, VisibilityKind::Public);
+            if let Some(typ) = typ {
+                let typing = Element::new_with_kind(ElementKind::FeatureTyping)
+                    .with_prop("typedFeature", sysml_core::Value::Ref(element.clone()))
+                    .with_prop(unresolved_props::TYPE, typ)
+                    .with_span(span);
+                graph.add_owned_element(typing, element, VisibilityKind::Public);
+            }
+            ParseResult::success(graph)
+        }
+
+        fn name(&self) -> &str {
+            "decl"
+        }
+    }
+
+    fn update(workspace: &mut Workspace, path: &str, text: &str) -> usize {
+        workspace.update_file(&DeclParser, SysmlFile::new(path, text)).len()
+    }
+
+    #[test]
+    fn usage_resolves_against_a_sibling_file() {
+        let mut workspace = Workspace::new();
+        update(&mut workspace, "car.sysml", "use Vehicles::engine : Parts::Engine");
+        let alone = workspace.analyze("car.sysml", None);
+        assert!(alone.has_errors(), "{:?}", alone.diagnostics);
+
+        update(&mut workspace, "parts.sysml", "def Parts::Engine");
+        let result = workspace.analyze("car.sysml", None);
+        assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+        assert_eq!(workspace.files().collect::<Vec<_>>(), ["car.sysml", "parts.sysml"]);
+    }
+
+    #[test]
+    fn changed_files_replace_their_previous_content() {
+        let mut workspace = Workspace::new();
+        update(&mut workspace, "parts.sysml", "def Parts::Engine");
+        update(&mut workspace, "car.sysml", "use Vehicles::engine : Parts::Engine");
+        let elements = workspace.graph().element_count();
+
+        // Renaming the definition leaves no stale Engine behind
+        update(&mut workspace, "parts.sysml", "def Parts::Motor");
+        assert_eq!(workspace.graph().element_count(), elements);
+        assert!(workspace.analyze("car.sysml", None).has_errors());
+        assert!(workspace.analyze("parts.sysml", None).diagnostics.is_empty());
+
+        update(&mut workspace, "car.sysml", "use Vehicles::engine : Parts::Motor");
+        assert!(workspace.analyze("car.sysml", None).diagnostics.is_empty());
+
+        assert!(workspace.remove_file("parts.sysml"));
+        assert!(!workspace.remove_file("parts.sysml"));
+        assert!(workspace.analyze("car.sysml", None).has_errors());
+    }
+
+    #[test]
+    fn broken_file_keeps_its_last_good_content() {
+        let mut workspace = Workspace::new();
+        update(&mut workspace, "parts.sysml", "def Parts::Engine");
+        update(&mut workspace, "car.sysml", "use Vehicles::engine : Parts::Engine");
+
+        assert_eq!(update(&mut workspace, "parts.sysml", "error"), 1);
+        assert!(workspace.analyze("parts.sysml", None).has_errors());
+        assert!(workspace.analyze("car.sysml", None).diagnostics.is_empty());
+    }
+
+    #[test]
+    fn finds_sysml_files_recursively() {
+        let root = std::env::temp_dir().join(format!("sysml-workspace-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+        std::fs::write(root.join("b.sysml"), "").unwrap();
+        std::fs::write(root.join("nested/a.sysml"), "").unwrap();
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+
+        let files = sysml_files(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(files, [root.join("b.sysml"), root.join("nested/a.sysml")]);
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 
-use sysml_core::{ElementId, ElementKind, ModelGraph, RelationshipKind};
+use sysml_core::{Element, ElementId, ElementKind, ModelGraph, RelationshipKind};
 
 use crate::classify::{
     is_interconnection_kind, is_membership_kind, is_part_kind, is_port_kind,
@@ -14,7 +14,19 @@ pub fn to_dot(graph: &ModelGraph) -> String {
     to_dot_general_view(graph)
 }
 
+/// Maximum nesting depth of ownership clusters in the general view.
+///
+/// Deeper elements are drawn as plain nodes in the innermost cluster.
+pub const MAX_CLUSTER_DEPTH: usize = 32;
+
 /// Export a ModelGraph to a general DOT (Graphviz) view.
+///
+/// Namespace-like elements (packages, part definitions and usages, state
+/// definitions) that own other elements are drawn as `subgraph cluster_<id>`
+/// blocks containing their owned elements, recursively. The element itself
+/// is also drawn as a node inside its cluster, so edges can still address
+/// every element by id. Ownership cycles and nesting deeper than
+/// [`MAX_CLUSTER_DEPTH`] are reported as `// warning:` comments.
 pub fn to_dot_general_view(graph: &ModelGraph) -> String {
     let mut output = String::new();
     output.push_str("digraph sysml {\n");
@@ -23,21 +35,26 @@ pub fn to_dot_general_view(graph: &ModelGraph) -> String {
     output.push_str("  edge [fontname=\"Helvetica\", fontsize=10];\n");
     output.push('\n');
 
-    // Export elements as nodes
+    // Owned elements grouped under their owner; the rest are top level
+    let mut children: BTreeMap<&ElementId, Vec<&ElementId>> = BTreeMap::new();
+    let mut roots = Vec::new();
     for (id, element) in &graph.elements {
-        let name = element.name.as_deref().unwrap_or("unnamed");
-        let kind = element.kind.as_str();
-        let shape = element_shape(&element.kind);
-        let color = element_color(&element.kind);
+        match element.owner.as_ref().filter(|owner| graph.elements.contains_key(*owner)) {
+            Some(owner) => children.entry(owner).or_default().push(id),
+            None => roots.push(id),
+        }
+    }
 
-        output.push_str(&format!(
-            "  \"{}\" [label=\"{{{} | {}}}\", shape={}, fillcolor=\"{}\", style=filled];\n",
-            id,
-            kind,
-            escape_dot(name),
-            shape,
-            color
-        ));
+    // Export elements as nodes, nested in their owners' clusters
+    let mut written = BTreeSet::new();
+    for id in roots {
+        write_general_node(graph, id, &children, &mut written, 1, &mut output);
+    }
+    for id in graph.elements.keys() {
+        if !written.contains(id) {
+            output.push_str(&format!("  // warning: \"{}\" is on an ownership cycle\n", id));
+            write_general_node(graph, id, &children, &mut written, 1, &mut output);
+        }
     }
 
     output.push('\n');
@@ -58,6 +75,92 @@ pub fn to_dot_general_view(graph: &ModelGraph) -> String {
     output
 }
 
+/// Write an element of the general view, as a cluster if it owns elements.
+fn write_general_node<'a>(
+    graph: &ModelGraph,
+    id: &'a ElementId,
+    children: &BTreeMap<&ElementId, Vec<&'a ElementId>>,
+    written: &mut BTreeSet<&'a ElementId>,
+    depth: usize,
+    output: &mut String,
+) {
+    if !written.insert(id) {
+        return;
+    }
+    let element = &graph.elements[id];
+    let indent = "  ".repeat(depth);
+    let owned = children.get(id).filter(|_| is_cluster_kind(&element.kind));
+
+    let owned = match owned {
+        Some(owned) if depth > MAX_CLUSTER_DEPTH => {
+            output.push_str(&format!(
+                "{}// warning: cluster depth limit {} reached at \"{}\"\n",
+                indent, MAX_CLUSTER_DEPTH, id
+            ));
+            // Draw the rest of the subtree flat at this level
+            output.push_str(&general_node_line(&indent, id, element));
+            for child in owned {
+                write_general_node(graph, child, children, written, depth, output);
+            }
+            return;
+        }
+        Some(owned) => owned,
+        None => {
+            output.push_str(&general_node_line(&indent, id, element));
+            if let Some(owned) = children.get(id) {
+                // Owned by an element that is not drawn as a cluster
+                for child in owned {
+                    write_general_node(graph, child, children, written, depth, output);
+                }
+            }
+            return;
+        }
+    };
+
+    let name = element.name.as_deref().unwrap_or("unnamed");
+    output.push_str(&format!("{}subgraph \"cluster_{}\" {{\n", indent, id));
+    output.push_str(&format!("{}  label=\"{}\";\n", indent, escape_dot_string(name)));
+    output.push_str(&format!(
+        "{}  style=filled;\n{}  fillcolor=\"{}\";\n",
+        indent,
+        indent,
+        element_color(&element.kind)
+    ));
+    output.push_str(&general_node_line(&format!("{}  ", indent), id, element));
+    for child in owned {
+        if written.contains(child) {
+            output.push_str(&format!("{}  // warning: \"{}\" is on an ownership cycle\n", indent, child));
+            continue;
+        }
+        write_general_node(graph, child, children, written, depth + 1, output);
+    }
+    output.push_str(&format!("{}}}\n", indent));
+}
+
+fn general_node_line(indent: &str, id: &ElementId, element: &Element) -> String {
+    let name = element.name.as_deref().unwrap_or("unnamed");
+    format!(
+        "{}\"{}\" [label=\"{{{} | {}}}\", shape={}, fillcolor=\"{}\", style=filled];\n",
+        indent,
+        id,
+        element.kind.as_str(),
+        escape_dot(name),
+        element_shape(&element.kind),
+        element_color(&element.kind)
+    )
+}
+
+/// Elements drawn as clusters around their owned elements.
+fn is_cluster_kind(kind: &ElementKind) -> bool {
+    matches!(
+        kind,
+        ElementKind::Package
+            | ElementKind::PartDefinition
+            | ElementKind::PartUsage
+            | ElementKind::StateDefinition
+    )
+}
+
 /// Export a ModelGraph to a browser-style DOT view (ownership tree).
 ///
 /// This view only shows ownership edges (namespace -> owned members).
@@ -301,6 +404,11 @@ pub fn to_dot_interconnection_view(graph: &ModelGraph) -> String {
     output
 }
 
+/// Escape a plain (non-record) quoted DOT string.
+fn escape_dot_string(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 fn escape_dot(s: &str) -> String {
     s.replace('\\', "\\\\")
         .replace('"', "\\\"")
@@ -343,7 +451,7 @@ fn element_color(kind: &ElementKind) -> &'static str {
     }
 }
 
-fn relationship_style(kind: &RelationshipKind) -> &'static str {
+pub(crate) fn relationship_style(kind: &RelationshipKind) -> &'static str {
     match kind {
         RelationshipKind::Owning => "solid",
         RelationshipKind::TypeOf => "solid",
@@ -0,0 +1,251 @@
+use std::collections::{BTreeSet, HashSet};
+
+use sysml_core::{Element, ElementId, ElementKind, ModelGraph, Relationship, RelationshipKind};
+
+use crate::cytoscape::to_cytoscape_json;
+use crate::dot::to_dot;
+use crate::plantuml::to_plantuml;
+
+/// Selects the part of a ModelGraph that a filtered export shows.
+///
+/// An element is shown when it passes every configured option; the default
+/// filter shows everything. Relationships are shown only when both endpoints
+/// are shown and their kind passes the relationship kind options.
+///
+/// # Example
+///
+/// ```
+/// use sysml_core::{ElementKind, ModelGraph};
+/// use sysml_vis::{to_dot_filtered, VisFilter};
+///
+/// let graph = ModelGraph::new();
+/// let filter = VisFilter::new()
+///     .hide_libraries()
+///     .exclude_kind(ElementKind::Documentation)
+///     .max_depth(2);
+/// let dot = to_dot_filtered(&graph, &filter);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VisFilter {
+    root: Option<ElementId>,
+    include_kinds: Option<HashSet<ElementKind>>,
+    exclude_kinds: HashSet<ElementKind>,
+    include_relationship_kinds: Option<HashSet<RelationshipKind>>,
+    exclude_relationship_kinds: HashSet<RelationshipKind>,
+    hide_libraries: bool,
+    max_depth: Option<usize>,
+    show_related: bool,
+}
+
+impl VisFilter {
+    /// Create a filter that shows everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show only `root` and the elements it owns, directly or indirectly.
+    pub fn root(mut self, root: ElementId) -> Self {
+        self.root = Some(root);
+        self
+    }
+
+    /// Show only elements of this kind (may be called repeatedly).
+    pub fn include_kind(mut self, kind: ElementKind) -> Self {
+        self.include_kinds.get_or_insert_with(HashSet::new).insert(kind);
+        self
+    }
+
+    /// Show only elements of these kinds.
+    pub fn include_kinds(mut self, kinds: impl IntoIterator<Item = ElementKind>) -> Self {
+        self.include_kinds.get_or_insert_with(HashSet::new).extend(kinds);
+        self
+    }
+
+    /// Hide elements of this kind.
+    pub fn exclude_kind(mut self, kind: ElementKind) -> Self {
+        self.exclude_kinds.insert(kind);
+        self
+    }
+
+    /// Hide elements of these kinds.
+    pub fn exclude_kinds(mut self, kinds: impl IntoIterator<Item = ElementKind>) -> Self {
+        self.exclude_kinds.extend(kinds);
+        self
+    }
+
+    /// Show only relationships of this kind (may be called repeatedly).
+    pub fn include_relationship_kind(mut self, kind: RelationshipKind) -> Self {
+        self.include_relationship_kinds
+            .get_or_insert_with(HashSet::new)
+            .insert(kind);
+        self
+    }
+
+    /// Show only relationships of these kinds.
+    pub fn include_relationship_kinds(mut self, kinds: impl IntoIterator<Item = RelationshipKind>) -> Self {
+        self.include_relationship_kinds
+            .get_or_insert_with(HashSet::new)
+            .extend(kinds);
+        self
+    }
+
+    /// Hide relationships of this kind.
+    pub fn exclude_relationship_kind(mut self, kind: RelationshipKind) -> Self {
+        self.exclude_relationship_kinds.insert(kind);
+        self
+    }
+
+    /// Hide library packages and everything they own.
+    pub fn hide_libraries(mut self) -> Self {
+        self.hide_libraries = true;
+        self
+    }
+
+    /// Hide elements nested more than `depth` levels below the root (or below
+    /// the top-level elements when no root is set, which are at depth 0).
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Also show the far ends of relationships from shown elements, whatever
+    /// the other options say, so that their edges are kept.
+    ///
+    /// With [`include_kind`](Self::include_kind) this shows, for example,
+    /// requirements together with the parts that satisfy them.
+    pub fn show_related(mut self) -> Self {
+        self.show_related = true;
+        self
+    }
+
+    /// Get the ids of the elements this filter shows.
+    pub fn visible_elements(&self, graph: &ModelGraph) -> BTreeSet<ElementId> {
+        let mut visible: BTreeSet<ElementId> = graph
+            .elements
+            .values()
+            .filter(|element| self.shows_element(graph, element))
+            .map(|element| element.id.clone())
+            .collect();
+        if self.show_related {
+            let related: Vec<ElementId> = graph
+                .relationships
+                .values()
+                .filter(|rel| self.shows_relationship_kind(&rel.kind))
+                .filter_map(|rel| match (visible.contains(&rel.source), visible.contains(&rel.target)) {
+                    (true, false) => Some(rel.target.clone()),
+                    (false, true) => Some(rel.source.clone()),
+                    _ => None,
+                })
+                .filter(|id| graph.elements.contains_key(id))
+                .collect();
+            visible.extend(related);
+        }
+        visible
+    }
+
+    /// Check whether a relationship kind passes this filter.
+    pub fn shows_relationship_kind(&self, kind: &RelationshipKind) -> bool {
+        self.include_relationship_kinds
+            .as_ref()
+            .is_none_or(|kinds| kinds.contains(kind))
+            && !self.exclude_relationship_kinds.contains(kind)
+    }
+
+    /// Build the filtered graph that the `*_filtered` exporters render.
+    ///
+    /// Owners that are filtered out are cleared, so shown elements become
+    /// top level rather than disappearing from tree-based views.
+    pub fn apply(&self, graph: &ModelGraph) -> ModelGraph {
+        let visible = self.visible_elements(graph);
+        let mut filtered = ModelGraph::new();
+
+        for id in &visible {
+            let mut element = graph.elements[id].clone();
+            if element.owner.as_ref().is_some_and(|owner| !visible.contains(owner)) {
+                element.owner = None;
+                element.owning_membership = None;
+            }
+            filtered.add_element(element);
+        }
+
+        let relationships: Vec<Relationship> = graph
+            .relationships
+            .values()
+            .filter(|rel| {
+                visible.contains(&rel.source)
+                    && visible.contains(&rel.target)
+                    && self.shows_relationship_kind(&rel.kind)
+            })
+            .cloned()
+            .collect();
+        for rel in relationships {
+            filtered.add_relationship(rel);
+        }
+
+        filtered
+    }
+
+    fn shows_element(&self, graph: &ModelGraph, element: &Element) -> bool {
+        if let Some(kinds) = &self.include_kinds {
+            if !kinds.contains(&element.kind) {
+                return false;
+            }
+        }
+        if self.exclude_kinds.contains(&element.kind) {
+            return false;
+        }
+
+        // Walk up the ownership chain once for root, depth and library checks
+        let mut depth = 0;
+        let mut reached_root = self.root.as_ref() == Some(&element.id);
+        let mut seen = HashSet::new();
+        let mut current = element;
+        loop {
+            if self.hide_libraries && graph.is_library_package(&current.id) {
+                return false;
+            }
+            if !seen.insert(&current.id) {
+                break;
+            }
+            let parent = match parent_of(current).and_then(|id| graph.get_element(id)) {
+                Some(parent) => parent,
+                None => break,
+            };
+            if !reached_root {
+                depth += 1;
+                reached_root = self.root.as_ref() == Some(&parent.id);
+            }
+            current = parent;
+        }
+
+        if self.root.is_some() && !reached_root {
+            return false;
+        }
+        self.max_depth.is_none_or(|max| depth <= max)
+    }
+}
+
+/// The owning element, or for a membership its owning namespace.
+fn parent_of(element: &Element) -> Option<&ElementId> {
+    element.owner.as_ref().or_else(|| {
+        element
+            .props
+            .get("membershipOwningNamespace")
+            .and_then(|value| value.as_ref())
+    })
+}
+
+/// Export the part of a ModelGraph selected by a filter to DOT.
+pub fn to_dot_filtered(graph: &ModelGraph, filter: &VisFilter) -> String {
+    to_dot(&filter.apply(graph))
+}
+
+/// Export the part of a ModelGraph selected by a filter to PlantUML.
+pub fn to_plantuml_filtered(graph: &ModelGraph, filter: &VisFilter) -> String {
+    to_plantuml(&filter.apply(graph))
+}
+
+/// Export the part of a ModelGraph selected by a filter to Cytoscape JSON.
+pub fn to_cytoscape_json_filtered(graph: &ModelGraph, filter: &VisFilter) -> String {
+    to_cytoscape_json(&filter.apply(graph))
+}
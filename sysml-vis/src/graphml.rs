@@ -0,0 +1,141 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use sysml_core::{Element, ElementId, ModelGraph};
+
+use crate::classify::is_membership_kind;
+
+/// Export a ModelGraph to GraphML (for yEd, Gephi and similar tools).
+///
+/// Elements become nodes with `label`, `name`, `kind` and `qualifiedName`
+/// data; relationships become edges with a `kind`. Ownership is expressed by
+/// nesting: each owned element is placed in a `<graph>` inside its owner's
+/// node. Membership elements are omitted, and elements without a name are
+/// labelled with their ElementId.
+///
+/// # Arguments
+///
+/// * `graph` - The model graph to export
+///
+/// # Returns
+///
+/// A GraphML document as a string.
+pub fn to_graphml(graph: &ModelGraph) -> String {
+    let nodes: BTreeSet<&ElementId> = graph
+        .elements
+        .values()
+        .filter(|element| !is_membership_kind(&element.kind))
+        .map(|element| &element.id)
+        .collect();
+
+    // Owned elements grouped under their owner; the rest are top level
+    let mut children: BTreeMap<&ElementId, Vec<&Element>> = BTreeMap::new();
+    let mut roots = Vec::new();
+    for id in &nodes {
+        let element = &graph.elements[*id];
+        match element.owner.as_ref().filter(|owner| nodes.contains(owner)) {
+            Some(owner) => children.entry(owner).or_default().push(element),
+            None => roots.push(element),
+        }
+    }
+
+    let mut output = String::new();
+    output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    output.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n");
+    output.push_str("  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n");
+    output.push_str("  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n");
+    output.push_str("  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n");
+    output.push_str("  <key id=\"qualifiedName\" for=\"node\" attr.name=\"qualifiedName\" attr.type=\"string\"/>\n");
+    output.push_str("  <key id=\"relationshipKind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n");
+    output.push_str("  <graph id=\"sysml\" edgedefault=\"directed\">\n");
+
+    let mut written = BTreeSet::new();
+    for root in roots {
+        write_node(graph, root, &children, &mut written, 2, &mut output);
+    }
+    // Elements on an ownership cycle have no root to hang from
+    for id in &nodes {
+        if !written.contains(*id) {
+            write_node(graph, &graph.elements[*id], &children, &mut written, 2, &mut output);
+        }
+    }
+
+    for (id, rel) in &graph.relationships {
+        if !nodes.contains(&rel.source) || !nodes.contains(&rel.target) {
+            continue;
+        }
+        output.push_str(&format!(
+            "    <edge id=\"{}\" source=\"{}\" target=\"{}\">\n",
+            escape_xml(&id.to_string()),
+            escape_xml(&rel.source.to_string()),
+            escape_xml(&rel.target.to_string())
+        ));
+        output.push_str(&format!(
+            "      <data key=\"relationshipKind\">{}</data>\n",
+            escape_xml(rel.kind.as_str())
+        ));
+        output.push_str("    </edge>\n");
+    }
+
+    output.push_str("  </graph>\n");
+    output.push_str("</graphml>\n");
+    output
+}
+
+fn write_node<'a>(
+    graph: &ModelGraph,
+    element: &'a Element,
+    children: &BTreeMap<&ElementId, Vec<&'a Element>>,
+    written: &mut BTreeSet<&'a ElementId>,
+    depth: usize,
+    output: &mut String,
+) {
+    if !written.insert(&element.id) {
+        return;
+    }
+
+    let indent = "  ".repeat(depth);
+    let id = escape_xml(&element.id.to_string());
+    output.push_str(&format!("{}<node id=\"{}\">\n", indent, id));
+
+    let label = match &element.name {
+        Some(name) => escape_xml(name),
+        None => id.clone(),
+    };
+    output.push_str(&format!("{}  <data key=\"label\">{}</data>\n", indent, label));
+    if let Some(name) = &element.name {
+        output.push_str(&format!("{}  <data key=\"name\">{}</data>\n", indent, escape_xml(name)));
+    }
+    output.push_str(&format!(
+        "{}  <data key=\"kind\">{}</data>\n",
+        indent,
+        escape_xml(element.kind.as_str())
+    ));
+    if let Some(qname) = graph.qualified_name_of(&element.id) {
+        output.push_str(&format!(
+            "{}  <data key=\"qualifiedName\">{}</data>\n",
+            indent,
+            escape_xml(&qname.to_string())
+        ));
+    }
+
+    if let Some(owned) = children.get(&element.id) {
+        output.push_str(&format!(
+            "{}  <graph id=\"{}:\" edgedefault=\"directed\">\n",
+            indent, id
+        ));
+        for child in owned {
+            write_node(graph, child, children, written, depth + 2, output);
+        }
+        output.push_str(&format!("{}  </graph>\n", indent));
+    }
+
+    output.push_str(&format!("{}</node>\n", indent));
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -6,6 +6,11 @@
 //! - DOT (Graphviz)
 //! - PlantUML
 //! - Cytoscape JSON
+//! - GraphML (yEd, Gephi)
+//! - Mermaid (class diagrams, flowcharts and state diagrams)
+//!
+//! The DOT, PlantUML and Cytoscape exporters also have `*_filtered` variants
+//! that render only the part of the graph selected by a [`VisFilter`].
 //!
 //! ## Example
 //!
@@ -22,7 +27,10 @@
 mod classify;
 mod cytoscape;
 mod dot;
+mod filter;
+mod graphml;
 mod graphviz;
+mod mermaid;
 mod plantuml;
 
 pub use cytoscape::to_cytoscape_json;
@@ -30,16 +38,22 @@ pub use dot::{
     to_dot, to_dot_browser_view, to_dot_general_view, to_dot_interconnection_view,
     to_dot_requirements_view,
 };
+pub use filter::{to_cytoscape_json_filtered, to_dot_filtered, to_plantuml_filtered, VisFilter};
+pub use graphml::to_graphml;
 pub use graphviz::{
     render_dot, render_dot_to_pdf, render_dot_to_png, render_dot_to_svg, GraphvizEngine,
     GraphvizFormat, GraphvizOptions, VisError,
 };
+pub use mermaid::{to_mermaid, to_mermaid_flowchart, to_mermaid_statechart};
 pub use plantuml::{to_plantuml, to_plantuml_state_view};
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sysml_core::{Element, ElementKind, ModelGraph, Relationship, RelationshipKind};
+    use sysml_core::{
+        Element, ElementId, ElementKind, MergePolicy, ModelGraph, Relationship, RelationshipKind, VisibilityKind,
+    };
+    use sysml_run::{ActionIR, AssignmentIR, RegionIR, StateIR, StateMachineIR, TransitionIR};
 
     fn create_test_graph() -> ModelGraph {
         let mut graph = ModelGraph::new();
@@ -96,6 +110,55 @@ mod tests {
         assert!(dot.contains("Satisfy"));
     }
 
+    /// The body of the cluster block opened for `id`, up to its closing brace.
+    fn cluster_body<'a>(dot: &'a str, id: &ElementId) -> &'a str {
+        let open = format!("subgraph \"cluster_{}\" {{\n", id);
+        let start = dot.find(&open).expect("cluster block") + open.len();
+        let indent = dot[..start - open.len()].rsplit('\n').next().unwrap();
+        let close = format!("\n{}}}\n", indent);
+        let end = dot[start..].find(&close).expect("cluster end") + start;
+        &dot[start..end]
+    }
+
+    #[test]
+    fn dot_nests_owned_elements_in_clusters() {
+        let graph = create_test_graph();
+        let dot = to_dot(&graph);
+        let pkg = graph.elements.values().find(|e| e.name.as_deref() == Some("TestPackage")).unwrap();
+        let engine = graph.elements.values().find(|e| e.name.as_deref() == Some("Engine")).unwrap();
+
+        assert!(dot.contains(&format!("subgraph \"cluster_{}\" {{", pkg.id)));
+        let body = cluster_body(&dot, &pkg.id);
+        assert!(body.contains("label=\"TestPackage\";"));
+        assert!(body.contains("fillcolor=\"#E8F4EA\";"));
+        assert!(body.contains(&format!("\"{}\" [label=\"{{PartUsage | Engine}}\"", engine.id)));
+        assert!(body.contains("SafetyReq"));
+
+        // Edges crossing clusters still address nodes by id
+        assert!(dot.contains(&format!("\"{}\" -> ", engine.id)));
+    }
+
+    #[test]
+    fn dot_nested_part_clusters_and_ownership_cycles() {
+        let mut graph = create_test_graph();
+        let engine = graph.elements.values().find(|e| e.name.as_deref() == Some("Engine")).unwrap().id.clone();
+        graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("Piston").with_owner(engine.clone()));
+
+        let dot = to_dot(&graph);
+        assert!(cluster_body(&dot, &engine).contains("Piston"));
+
+        // A cycle is reported instead of recursing forever
+        let mut cyclic = ModelGraph::new();
+        let a = cyclic.add_element(Element::new_with_kind(ElementKind::Package).with_name("A"));
+        let b = cyclic.add_element(Element::new_with_kind(ElementKind::Package).with_name("B").with_owner(a.clone()));
+        cyclic.elements.get_mut(&a).unwrap().owner = Some(b);
+
+        let dot = to_dot(&cyclic);
+        assert!(dot.contains("// warning:"));
+        assert_eq!(dot.matches("[label=\"{Package | A}\"").count(), 1);
+        assert_eq!(dot.matches("[label=\"{Package | B}\"").count(), 1);
+    }
+
     #[test]
     fn plantuml_output_structure() {
         let graph = create_test_graph();
@@ -145,6 +208,390 @@ mod tests {
         assert!(json.contains("SafetyReq"));
     }
 
+    /// Check that `xml` is well-formed: tags nest and close in order, and
+    /// attribute values are quoted. Returns the element names in document order.
+    fn check_well_formed(xml: &str) -> Vec<String> {
+        let body = xml.strip_prefix("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n").expect("XML declaration");
+        let mut stack: Vec<String> = Vec::new();
+        let mut names = Vec::new();
+        let mut rest = body;
+        while let Some(start) = rest.find('<') {
+            let text = &rest[..start];
+            assert!(!text.contains('>'), "stray '>' in text: {:?}", text);
+            let end = rest[start..].find('>').expect("unterminated tag") + start;
+            let tag = &rest[start + 1..end];
+            assert!(!tag.contains('<'), "'<' inside tag: {:?}", tag);
+            assert_eq!(tag.matches('"').count() % 2, 0, "unbalanced quotes: {:?}", tag);
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(stack.pop().as_deref(), Some(name), "mismatched closing tag");
+            } else {
+                let name = tag.trim_end_matches('/').split_whitespace().next().unwrap().to_string();
+                names.push(name.clone());
+                if !tag.ends_with('/') {
+                    stack.push(name);
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        assert!(stack.is_empty(), "unclosed tags: {:?}", stack);
+        assert!(rest.trim().is_empty());
+        names
+    }
+
+    #[test]
+    fn graphml_well_formed() {
+        let graph = create_test_graph();
+        let graphml = to_graphml(&graph);
+
+        let names = check_well_formed(&graphml);
+        assert_eq!(names[0], "graphml");
+        assert_eq!(names.iter().filter(|n| *n == "node").count(), 3);
+        assert_eq!(names.iter().filter(|n| *n == "edge").count(), 1);
+        assert_eq!(names.iter().filter(|n| *n == "key").count(), 5);
+    }
+
+    #[test]
+    fn graphml_nests_owned_elements() {
+        let graph = create_test_graph();
+        let graphml = to_graphml(&graph);
+
+        let package = graphml.find("<data key=\"label\">TestPackage</data>").unwrap();
+        let nested = graphml[package..].find("edgedefault=\"directed\">").unwrap() + package;
+        let engine = graphml.find("<data key=\"label\">Engine</data>").unwrap();
+        assert!(nested < engine);
+        assert!(graphml.contains("<data key=\"qualifiedName\">TestPackage::Engine</data>"));
+        assert!(graphml.contains("<data key=\"relationshipKind\">Satisfy</data>"));
+    }
+
+    #[test]
+    fn graphml_escapes_and_labels_unnamed() {
+        let mut graph = ModelGraph::new();
+        graph.add_element(Element::new_with_kind(ElementKind::PartDefinition).with_name("A & <B> \"C\""));
+        let unnamed = graph.add_element(Element::new_with_kind(ElementKind::PartUsage));
+
+        let graphml = to_graphml(&graph);
+        check_well_formed(&graphml);
+        assert!(graphml.contains("<data key=\"name\">A &amp; &lt;B&gt; &quot;C&quot;</data>"));
+        assert!(graphml.contains(&format!("<data key=\"label\">{}</data>", unnamed)));
+    }
+
+    #[test]
+    fn mermaid_class_diagram() {
+        let mut graph = create_test_graph();
+        let vehicle = graph.add_element(Element::new_with_kind(ElementKind::PartDefinition).with_name("Vehicle"));
+        let car = graph.add_element(Element::new_with_kind(ElementKind::PartDefinition).with_name("Sports Car"));
+        graph.add_relationship(Relationship::new(RelationshipKind::Specialize, car, vehicle));
+
+        let mermaid = to_mermaid(&graph);
+        let lines: Vec<&str> = mermaid.lines().collect();
+
+        assert_eq!(lines[0], "classDiagram");
+        assert!(lines.contains(&"    class Engine[\"Engine\"]"));
+        assert!(lines.contains(&"    <<part>> Engine"));
+        assert!(lines.contains(&"    class SafetyReq[\"SafetyReq\"]"));
+        assert!(lines.contains(&"    <<requirement>> SafetyReq"));
+        assert!(lines.contains(&"    class Sports_Car[\"Sports Car\"]"));
+        assert!(lines.contains(&"    Vehicle <|-- Sports_Car"));
+        assert!(lines.contains(&"    Engine ..> SafetyReq : satisfy"));
+        assert!(!mermaid.contains("TestPackage"));
+    }
+
+    #[test]
+    fn mermaid_ids_are_unique() {
+        let mut graph = ModelGraph::new();
+        graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("a b"));
+        graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("a-b"));
+        graph.add_element(Element::new_with_kind(ElementKind::AttributeUsage).with_name("2nd \"x\""));
+
+        let mermaid = to_mermaid(&graph);
+        assert!(mermaid.contains("[\"a b\"]"));
+        assert!(mermaid.contains("    class a_b_2["));
+        assert!(mermaid.contains("    class _2nd__x_[\"2nd #quot;x#quot;\"]"));
+        assert_eq!(mermaid, to_mermaid(&graph));
+    }
+
+    #[test]
+    fn mermaid_flowchart_nests_packages() {
+        let mut graph = create_test_graph();
+        let pkg = graph.elements_by_kind(&ElementKind::Package).next().unwrap().id.clone();
+        let inner = Element::new_with_kind(ElementKind::Package).with_name("Inner").with_owner(pkg);
+        let inner = graph.add_element(inner);
+        let wheel = Element::new_with_kind(ElementKind::PartUsage)
+            .with_name("<Wheel> \"front\"")
+            .with_owner(inner.clone());
+        let wheel = graph.add_element(wheel);
+        let engine = graph.elements.values().find(|e| e.name.as_deref() == Some("Engine")).unwrap().id.clone();
+        graph.add_relationship(Relationship::new(RelationshipKind::Derive, wheel.clone(), engine.clone()));
+        graph.add_relationship(Relationship::new(RelationshipKind::Flow, engine, wheel));
+
+        let mermaid = to_mermaid_flowchart(&graph);
+        let lines: Vec<&str> = mermaid.lines().collect();
+        assert_eq!(lines[0], "flowchart TD");
+        assert_eq!(lines[1], "    subgraph TestPackage[\"TestPackage\"]");
+        assert!(lines.contains(&"        Engine[\"Engine<br/>(PartUsage)\"]"));
+        assert!(lines.contains(&"        SafetyReq[\"SafetyReq<br/>(RequirementUsage)\"]"));
+        assert!(lines.contains(&"        subgraph Inner[\"Inner\"]"));
+        assert!(lines.contains(&"            _Wheel___front_[\"#lt;Wheel#gt; #quot;front#quot;<br/>(PartUsage)\"]"));
+        assert_eq!(lines.iter().filter(|line| line.trim() == "end").count(), 2);
+
+        // Edge styles follow the DOT exporter
+        assert!(lines.contains(&"    Engine -.->|Satisfy| SafetyReq"));
+        assert!(lines.contains(&"    _Wheel___front_ -.->|Derive| Engine"));
+        assert!(lines.contains(&"    Engine ==>|Flow| _Wheel___front_"));
+        assert_eq!(mermaid, to_mermaid_flowchart(&graph));
+    }
+
+    #[test]
+    fn mermaid_traffic_light_statechart() {
+        let ir = StateMachineIR::new("TrafficLight", "Red")
+            .with_state(StateIR::new("Red"))
+            .with_state(StateIR::new("Green"))
+            .with_state(StateIR::new("Yellow"))
+            .with_state(StateIR::new("Out of Service").final_state())
+            .with_transition(TransitionIR::new("Red", "Green").with_event("timer").with_guard("carsWaiting > 0"))
+            .with_transition(TransitionIR::new("Green", "Yellow").with_event("timer"))
+            .with_transition(TransitionIR::new("Yellow", "Red").with_event("timer"))
+            .with_transition(TransitionIR::new("Red", "Out of Service"));
+
+        let chart = to_mermaid_statechart(&ir);
+        let lines: Vec<&str> = chart.lines().collect();
+
+        assert_eq!(lines[0], "stateDiagram-v2");
+        assert!(lines.contains(&"    state \"Out of Service\" as Out_of_Service"));
+        assert!(lines.contains(&"    [*] --> Red"));
+        assert!(lines.contains(&"    Red --> Green : timer [carsWaiting > 0]"));
+        assert!(lines.contains(&"    Green --> Yellow : timer"));
+        assert!(lines.contains(&"    Yellow --> Red : timer"));
+        assert!(lines.contains(&"    Red --> Out_of_Service"));
+        assert!(lines.contains(&"    Out_of_Service --> [*]"));
+    }
+
+    #[test]
+    fn mermaid_hybrid_system_statechart() {
+        let ir = StateMachineIR::parallel("HybridSystem")
+            .with_region(
+                RegionIR::new("grid", "energized")
+                    .with_state(StateIR::new("energized"))
+                    .with_state(StateIR::new("deEnergized"))
+                    .with_transition(TransitionIR::new("energized", "deEnergized").with_event("gridFail"))
+                    .with_transition(TransitionIR::new("deEnergized", "energized").with_event("gridRestore")),
+            )
+            .with_region(
+                RegionIR::new("relay", "closed")
+                    .with_state(StateIR::new("closed"))
+                    .with_state(StateIR::new("open"))
+                    .with_transition(TransitionIR::new("closed", "open").with_event("gridFail"))
+                    .with_transition(TransitionIR::new("open", "closed").with_event("gridRestore")),
+            );
+
+        let chart = to_mermaid_statechart(&ir);
+        let expected = "stateDiagram-v2
+    [*] --> HybridSystem
+    state HybridSystem {
+        state grid {
+            [*] --> energized
+            energized --> deEnergized : gridFail
+            deEnergized --> energized : gridRestore
+        }
+        --
+        state relay {
+            [*] --> closed
+            closed --> open : gridFail
+            open --> closed : gridRestore
+        }
+    }
+";
+        assert_eq!(chart, expected);
+    }
+
+    #[test]
+    fn mermaid_statechart_labels_actions_and_timers() {
+        let ir = StateMachineIR::new("Counter", "Idle")
+            .with_state(StateIR::new("Idle").with_entry_action(ActionIR::structured(
+                vec![AssignmentIR::set("count", 0.0)],
+                vec!["ready".to_string()],
+            )))
+            .with_state(StateIR::new("Busy"))
+            .with_state(StateIR::new("Done").final_state())
+            .with_transition(
+                TransitionIR::new("Idle", "Busy")
+                    .with_event("go")
+                    .with_guard("count < 3")
+                    .with_action_ir(ActionIR::structured(vec![AssignmentIR::add("count", 1.0)], vec![])),
+            )
+            .with_transition(TransitionIR::new("Busy", "Idle").with_after(2.5))
+            .with_transition(TransitionIR::new("Busy", "Done").with_guard("count >= 3"));
+
+        let expected = "stateDiagram-v2
+    [*] --> Idle
+    Idle : entry / count = 0, send('ready')
+    Idle --> Busy : go [count < 3] / count += 1
+    Busy --> Idle : after(2.5)
+    Busy --> Done : [count >= 3]
+    Done --> [*]
+";
+        assert_eq!(to_mermaid_statechart(&ir), expected);
+    }
+
+    #[test]
+    fn mermaid_statechart_composite_states() {
+        let ir = StateMachineIR::new("Door", "Door Closed")
+            .with_state(
+                StateIR::new("Door Closed")
+                    .with_substate(StateIR::new("Unlocked"))
+                    .with_substate(StateIR::new("Locked"))
+                    .with_initial_substate("Unlocked"),
+            )
+            .with_state(StateIR::new("Open").with_do("beep()"))
+            .with_transition(TransitionIR::new("Unlocked", "Locked").with_event("lock"))
+            .with_transition(TransitionIR::new("Door Closed", "Open").with_event("open"));
+
+        let expected = "stateDiagram-v2
+    state \"Door Closed\" as Door_Closed
+    [*] --> Door_Closed
+    state Door_Closed {
+        [*] --> Unlocked
+        Unlocked --> Locked : lock
+    }
+    Open : do / beep()
+    Door_Closed --> Open : open
+";
+        assert_eq!(to_mermaid_statechart(&ir), expected);
+    }
+
+    #[test]
+    fn mermaid_statechart_clashing_and_quoted_names() {
+        let ir = StateMachineIR::new("Door", "Door Open")
+            .with_state(StateIR::new("Door Open"))
+            .with_state(StateIR::new("Door_Open"))
+            .with_state(StateIR::new("Say \"hi\""))
+            .with_transition(TransitionIR::new("Door Open", "Door_Open"))
+            .with_transition(TransitionIR::new("Door_Open", "Say \"hi\""));
+
+        let chart = to_mermaid_statechart(&ir);
+        let lines: Vec<&str> = chart.lines().collect();
+
+        assert!(lines.contains(&"    state \"Door Open\" as Door_Open"));
+        assert!(lines.contains(&"    state \"Door_Open\" as Door_Open_2"));
+        assert!(lines.contains(&"    state \"Say #quot;hi#quot;\" as Say__hi_"));
+        assert!(lines.contains(&"    Door_Open --> Door_Open_2"));
+        assert!(lines.contains(&"    Door_Open_2 --> Say__hi_"));
+    }
+
+    fn graph_with_library() -> (ModelGraph, ElementId, ElementId) {
+        let mut library = ModelGraph::new();
+        let scalar_values = library.add_element(Element::new_with_kind(ElementKind::Package).with_name("ScalarValues"));
+        library.add_owned_element(
+            Element::new_with_kind(ElementKind::AttributeDefinition).with_name("Real"),
+            scalar_values,
+            VisibilityKind::Public,
+        );
+
+        let mut graph = ModelGraph::new();
+        let model = graph.add_element(Element::new_with_kind(ElementKind::Package).with_name("Model"));
+        let vehicle = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartDefinition).with_name("Vehicle"),
+            model.clone(),
+            VisibilityKind::Public,
+        );
+        let engine = graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartUsage).with_name("engine"),
+            vehicle.clone(),
+            VisibilityKind::Public,
+        );
+        graph.add_owned_element(
+            Element::new_with_kind(ElementKind::PartUsage).with_name("piston"),
+            engine,
+            VisibilityKind::Public,
+        );
+        graph.merge(library, true, MergePolicy::ReplaceExisting).unwrap();
+
+        let real = graph.elements.values().find(|e| e.name.as_deref() == Some("Real")).unwrap().id.clone();
+        graph.add_relationship(Relationship::new(RelationshipKind::TypeOf, vehicle.clone(), real));
+        (graph, model, vehicle)
+    }
+
+    #[test]
+    fn filtered_exports_hide_libraries() {
+        let (graph, _, _) = graph_with_library();
+        let filter = VisFilter::new().hide_libraries();
+
+        for output in [
+            to_dot_filtered(&graph, &filter),
+            to_plantuml_filtered(&graph, &filter),
+            to_cytoscape_json_filtered(&graph, &filter),
+        ] {
+            assert!(output.contains("Vehicle"));
+            assert!(!output.contains("ScalarValues"));
+            assert!(!output.contains("Real"));
+        }
+
+        // The typing edge lost its library endpoint
+        let filtered = filter.apply(&graph);
+        assert!(filtered.relationships.values().all(|rel| rel.kind != RelationshipKind::TypeOf));
+        assert!(filtered
+            .elements
+            .values()
+            .all(|e| e.owner.as_ref().is_none_or(|owner| filtered.elements.contains_key(owner))));
+
+        // Without the filter the library is rendered
+        assert!(to_dot(&graph).contains("ScalarValues"));
+    }
+
+    #[test]
+    fn filter_root_depth_and_kinds() {
+        let (graph, model, vehicle) = graph_with_library();
+        let names = |filter: &VisFilter| -> Vec<String> {
+            let visible = filter.visible_elements(&graph);
+            let mut names: Vec<String> =
+                visible.iter().filter_map(|id| graph.elements[id].name.clone()).collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(&VisFilter::new().root(vehicle.clone())), ["Vehicle", "engine", "piston"]);
+        assert_eq!(names(&VisFilter::new().root(vehicle).max_depth(1)), ["Vehicle", "engine"]);
+        assert_eq!(
+            names(&VisFilter::new().hide_libraries().max_depth(1)),
+            ["Model", "Vehicle"]
+        );
+        assert_eq!(
+            names(&VisFilter::new().root(model).include_kind(ElementKind::PartUsage)),
+            ["engine", "piston"]
+        );
+        assert_eq!(
+            names(&VisFilter::new().hide_libraries().exclude_kinds([ElementKind::Package, ElementKind::PartUsage])),
+            ["Vehicle"]
+        );
+
+        let no_typing = VisFilter::new().exclude_relationship_kind(RelationshipKind::TypeOf);
+        assert!(no_typing.apply(&graph).relationships.is_empty());
+        let only_typing = VisFilter::new().include_relationship_kind(RelationshipKind::TypeOf);
+        assert_eq!(only_typing.apply(&graph).relationships.len(), 1);
+    }
+
+    #[test]
+    fn filter_shows_related_elements() {
+        let mut graph = create_test_graph();
+        let engine = graph.elements.values().find(|e| e.name.as_deref() == Some("Engine")).unwrap().id.clone();
+        let gearbox = graph.add_element(Element::new_with_kind(ElementKind::PartUsage).with_name("Gearbox"));
+        graph.add_relationship(Relationship::new(RelationshipKind::TypeOf, engine, gearbox));
+
+        let requirements = VisFilter::new().include_kind(ElementKind::RequirementUsage);
+        assert!(requirements.apply(&graph).relationships.is_empty());
+
+        // Only the satisfying part and its edge come back
+        let filter = requirements
+            .show_related()
+            .include_relationship_kinds([RelationshipKind::Satisfy, RelationshipKind::Verify]);
+        let filtered = filter.apply(&graph);
+        let mut names: Vec<&str> = filtered.elements.values().filter_map(|e| e.name.as_deref()).collect();
+        names.sort();
+        assert_eq!(names, ["Engine", "SafetyReq"]);
+        assert_eq!(filtered.relationships.len(), 1);
+        assert!(to_dot_filtered(&graph, &filter).contains("color=\"green\""));
+    }
+
     #[test]
     fn empty_graph() {
         let graph = ModelGraph::new();
@@ -157,5 +604,8 @@ mod tests {
 
         let json = to_cytoscape_json(&graph);
         assert!(json.contains("\"nodes\": []"));
+
+        let graphml = to_graphml(&graph);
+        assert!(graphml.contains("<graph id=\"sysml\" edgedefault=\"directed\">\n  </graph>"));
     }
 }
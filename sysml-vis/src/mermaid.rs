@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use sysml_core::{Element, ElementId, ElementKind, ModelGraph, RelationshipKind};
+use sysml_run::{StateIR, StateMachineIR, TransitionIR};
+
+use crate::classify::{is_part_kind, is_requirement_kind};
+use crate::dot::relationship_style;
+
+/// Export a ModelGraph to a Mermaid class diagram.
+///
+/// Part, requirement and attribute definitions and usages become classes
+/// annotated with their kind. Specialize relationships are drawn as
+/// inheritance, Satisfy and Verify as labelled dependencies.
+///
+/// Class ids are derived from element names with every character other than
+/// ASCII letters, digits and `_` replaced by `_`; the original name is kept as
+/// the class label. Clashing ids get a numeric suffix in ElementId order.
+pub fn to_mermaid(graph: &ModelGraph) -> String {
+    let mut ids = MermaidIds::default();
+    let mut classes: BTreeMap<&ElementId, String> = BTreeMap::new();
+
+    let mut output = String::new();
+    output.push_str("classDiagram\n");
+
+    for (id, element) in &graph.elements {
+        if !is_class_kind(&element.kind) {
+            continue;
+        }
+        let label = element.name.as_deref().unwrap_or_else(|| element.kind.as_str());
+        let class_id = ids.get(id.clone(), label);
+        output.push_str(&format!("    class {}[\"{}\"]\n", class_id, escape_label(label)));
+        output.push_str(&format!("    <<{}>> {}\n", class_annotation(&element.kind), class_id));
+        classes.insert(id, class_id);
+    }
+
+    for rel in graph.relationships.values() {
+        let (source, target) = match (classes.get(&rel.source), classes.get(&rel.target)) {
+            (Some(source), Some(target)) => (source, target),
+            _ => continue,
+        };
+        match rel.kind {
+            RelationshipKind::Specialize => {
+                output.push_str(&format!("    {} <|-- {}\n", target, source));
+            }
+            RelationshipKind::Satisfy => {
+                output.push_str(&format!("    {} ..> {} : satisfy\n", source, target));
+            }
+            RelationshipKind::Verify => {
+                output.push_str(&format!("    {} ..> {} : verify\n", source, target));
+            }
+            _ => {}
+        }
+    }
+
+    output
+}
+
+/// Export a ModelGraph to a Mermaid `flowchart TD`.
+///
+/// Every element other than relationship elements becomes a node labelled
+/// with its name and kind, and packages become `subgraph`s holding the
+/// elements they own, nested like the packages themselves. Relationships
+/// are edges labelled with their kind, solid, dashed (`-.->`, also used for
+/// dotted) or thick (`==>`) like their DOT style.
+///
+/// Node ids are sanitized like class ids in [`to_mermaid`]; labels have
+/// quotes and angle brackets escaped as Mermaid entity codes.
+pub fn to_mermaid_flowchart(graph: &ModelGraph) -> String {
+    // Nodes grouped under their nearest owning package, None for the top level
+    let mut contents: BTreeMap<Option<&ElementId>, Vec<&Element>> = BTreeMap::new();
+    for element in graph.elements.values() {
+        if !element.kind.is_relationship() {
+            contents.entry(owning_package(graph, element)).or_default().push(element);
+        }
+    }
+
+    let mut ids = MermaidIds::default();
+    let mut nodes: BTreeMap<&ElementId, String> = BTreeMap::new();
+    let mut output = String::new();
+    output.push_str("flowchart TD\n");
+    write_flowchart_nodes(None, &contents, &mut ids, &mut nodes, 1, &mut output);
+
+    for rel in graph.relationships.values() {
+        let (source, target) = match (nodes.get(&rel.source), nodes.get(&rel.target)) {
+            (Some(source), Some(target)) => (source, target),
+            _ => continue,
+        };
+        let arrow = match relationship_style(&rel.kind) {
+            "dashed" | "dotted" => "-.->",
+            "bold" => "==>",
+            _ => "-->",
+        };
+        output.push_str(&format!("    {} {}|{}| {}\n", source, arrow, rel.kind.as_str(), target));
+    }
+
+    output
+}
+
+/// Write the nodes and package subgraphs in `parent`, recursively.
+fn write_flowchart_nodes<'a>(
+    parent: Option<&ElementId>,
+    contents: &BTreeMap<Option<&'a ElementId>, Vec<&'a Element>>,
+    ids: &mut MermaidIds<ElementId>,
+    nodes: &mut BTreeMap<&'a ElementId, String>,
+    depth: usize,
+    output: &mut String,
+) {
+    let indent = "    ".repeat(depth);
+    for element in contents.get(&parent).into_iter().flatten() {
+        let name = element.name.as_deref().unwrap_or_else(|| element.kind.as_str());
+        let id = ids.get(element.id.clone(), name);
+        nodes.insert(&element.id, id.clone());
+        if is_package(&element.kind) {
+            output.push_str(&format!("{}subgraph {}[\"{}\"]\n", indent, id, escape_html_label(name)));
+            write_flowchart_nodes(Some(&element.id), contents, ids, nodes, depth + 1, output);
+            output.push_str(&format!("{}end\n", indent));
+        } else {
+            let label = match &element.name {
+                Some(name) => format!("{}<br/>({})", escape_html_label(name), element.kind.as_str()),
+                None => format!("({})", element.kind.as_str()),
+            };
+            output.push_str(&format!("{}{}[\"{}\"]\n", indent, id, label));
+        }
+    }
+}
+
+/// The nearest package among an element's owners.
+fn owning_package<'a>(graph: &'a ModelGraph, element: &'a Element) -> Option<&'a ElementId> {
+    let mut owner = element.owner.as_ref();
+    // Bounded in case of an ownership cycle
+    for _ in 0..graph.element_count() {
+        let owner_id = owner?;
+        let owner_element = graph.get_element(owner_id)?;
+        if is_package(&owner_element.kind) {
+            return Some(owner_id);
+        }
+        owner = owner_element.owner.as_ref();
+    }
+    None
+}
+
+fn is_package(kind: &ElementKind) -> bool {
+    *kind == ElementKind::Package || kind.is_subtype_of(ElementKind::Package)
+}
+
+/// Export a state machine IR to a Mermaid `stateDiagram-v2`.
+///
+/// The initial state of each scope is marked with `[*] -->` and final states
+/// with `--> [*]`; transitions are labelled `event [guard] / action`, with
+/// `after(N)` as the event of a timed transition. Entry, exit and do actions
+/// become state descriptions, and composite states `state S { ... }` blocks
+/// holding the transitions that leave their substates. A parallel IR is drawn
+/// as a composite state named after the machine, with one nested `state`
+/// block per region separated by `--`.
+///
+/// State ids are sanitized like class ids in [`to_mermaid`]; states whose id
+/// differs from their name are declared with `state "name" as id`.
+pub fn to_mermaid_statechart(ir: &StateMachineIR) -> String {
+    let mut ids = MermaidIds::default();
+    let mut output = String::new();
+    output.push_str("stateDiagram-v2\n");
+
+    if ir.is_parallel() {
+        let machine = ids.get((String::new(), ir.name.clone()), &ir.name);
+        output.push_str(&format!("    [*] --> {}\n", machine));
+        output.push_str(&state_declaration(&machine, &ir.name, 1));
+        output.push_str(&format!("    state {} {{\n", machine));
+        for (index, region) in ir.regions.iter().enumerate() {
+            if index > 0 {
+                output.push_str("        --\n");
+            }
+            let region_id = ids.get((ir.name.clone(), region.name.clone()), &region.name);
+            output.push_str(&state_declaration(&region_id, &region.name, 2));
+            output.push_str(&format!("        state {} {{\n", region_id));
+            let transitions: Vec<&TransitionIR> = region.transitions.iter().collect();
+            write_states(&mut ids, &region.name, &region.initial, &region.states, &transitions, 3, &mut output);
+            output.push_str("        }\n");
+        }
+        output.push_str("    }\n");
+    } else {
+        let transitions: Vec<&TransitionIR> = ir.transitions.iter().collect();
+        write_states(&mut ids, "", &ir.initial, &ir.states, &transitions, 1, &mut output);
+    }
+
+    output
+}
+
+/// Write the declarations, initial marker, actions, transitions and final
+/// markers of one scope (the whole machine, a region or a composite state).
+///
+/// Substates share the scope of their region, since transitions name states
+/// without their owner. Each transition is drawn in the innermost block that
+/// holds its source state.
+fn write_states(
+    ids: &mut MermaidIds<(String, String)>,
+    scope: &str,
+    initial: &str,
+    states: &[StateIR],
+    transitions: &[&TransitionIR],
+    depth: usize,
+    output: &mut String,
+) {
+    let indent = "    ".repeat(depth);
+
+    for state in states {
+        let id = state_id(ids, scope, &state.name);
+        output.push_str(&state_declaration(&id, &state.name, depth));
+    }
+    if !initial.is_empty() {
+        output.push_str(&format!("{}[*] --> {}\n", indent, state_id(ids, scope, initial)));
+    }
+
+    let mut own: Vec<&TransitionIR> = transitions.to_vec();
+    for state in states {
+        let id = state_id(ids, scope, &state.name);
+        let actions = [
+            ("entry", &state.entry_action),
+            ("exit", &state.exit_action),
+            ("do", &state.do_action),
+        ];
+        for (kind, action) in actions {
+            if let Some(action) = action {
+                output.push_str(&format!("{}{} : {} / {}\n", indent, id, kind, escape_text(&action.to_string())));
+            }
+        }
+        if state.is_composite() {
+            let (inner, rest): (Vec<&TransitionIR>, Vec<&TransitionIR>) = own
+                .into_iter()
+                .partition(|t| state.substates.iter().any(|s| contains_state(s, &t.from)));
+            own = rest;
+            let initial = state.initial().map(|s| s.name.as_str()).unwrap_or_default();
+            output.push_str(&format!("{}state {} {{\n", indent, id));
+            write_states(ids, scope, initial, &state.substates, &inner, depth + 1, output);
+            output.push_str(&format!("{}}}\n", indent));
+        }
+    }
+
+    for transition in own {
+        let from = state_id(ids, scope, &transition.from);
+        let to = state_id(ids, scope, &transition.to);
+        match transition_label(transition) {
+            Some(label) => output.push_str(&format!("{}{} --> {} : {}\n", indent, from, to, label)),
+            None => output.push_str(&format!("{}{} --> {}\n", indent, from, to)),
+        }
+    }
+    for state in states.iter().filter(|s| s.is_final) {
+        output.push_str(&format!("{}{} --> [*]\n", indent, state_id(ids, scope, &state.name)));
+    }
+}
+
+fn state_id(ids: &mut MermaidIds<(String, String)>, scope: &str, name: &str) -> String {
+    ids.get((scope.to_string(), name.to_string()), name)
+}
+
+/// Whether `name` is this state or one of its (nested) substates.
+fn contains_state(state: &StateIR, name: &str) -> bool {
+    state.name == name || state.substates.iter().any(|s| contains_state(s, name))
+}
+
+fn state_declaration(id: &str, name: &str, depth: usize) -> String {
+    if id == name {
+        return String::new();
+    }
+    format!("{}state \"{}\" as {}\n", "    ".repeat(depth), escape_label(name), id)
+}
+
+/// `event [guard] / action`, any part alone, or nothing.
+fn transition_label(transition: &TransitionIR) -> Option<String> {
+    let mut parts = Vec::new();
+    match (transition.after, &transition.event) {
+        (Some(after), _) => parts.push(format!("after({})", after)),
+        (None, Some(event)) => parts.push(event.clone()),
+        (None, None) => {}
+    }
+    if let Some(guard) = &transition.guard {
+        parts.push(format!("[{}]", guard));
+    }
+    if let Some(action) = &transition.action {
+        parts.push(format!("/ {}", action));
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    Some(escape_text(&parts.join(" ")))
+}
+
+fn is_class_kind(kind: &ElementKind) -> bool {
+    is_part_kind(kind)
+        || is_requirement_kind(kind)
+        || *kind == ElementKind::AttributeDefinition
+        || kind.is_subtype_of(ElementKind::AttributeDefinition)
+        || *kind == ElementKind::AttributeUsage
+        || kind.is_subtype_of(ElementKind::AttributeUsage)
+}
+
+fn class_annotation(kind: &ElementKind) -> &str {
+    match kind {
+        ElementKind::PartDefinition => "part def",
+        ElementKind::PartUsage => "part",
+        ElementKind::RequirementDefinition => "requirement def",
+        ElementKind::RequirementUsage => "requirement",
+        ElementKind::AttributeDefinition => "attribute def",
+        ElementKind::AttributeUsage => "attribute",
+        other => other.as_str(),
+    }
+}
+
+/// Sanitized, unique Mermaid ids keyed by the thing they identify.
+struct MermaidIds<K> {
+    ids: BTreeMap<K, String>,
+    used: BTreeSet<String>,
+}
+
+impl<K> Default for MermaidIds<K> {
+    fn default() -> Self {
+        MermaidIds {
+            ids: BTreeMap::new(),
+            used: BTreeSet::new(),
+        }
+    }
+}
+
+impl<K: Ord> MermaidIds<K> {
+    /// The id for `key`, allocating one from `name` on first use.
+    fn get(&mut self, key: K, name: &str) -> String {
+        if let Some(id) = self.ids.get(&key) {
+            return id.clone();
+        }
+        let base = sanitize_id(name);
+        let mut id = base.clone();
+        let mut suffix = 2;
+        while self.used.contains(&id) {
+            id = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        self.used.insert(id.clone());
+        self.ids.insert(key, id.clone());
+        id
+    }
+}
+
+fn sanitize_id(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    if !id.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        id.insert(0, '_');
+    }
+    id
+}
+
+/// Escape a transition label or state description, which ends at a newline
+/// or `;`.
+fn escape_text(s: &str) -> String {
+    s.replace(';', ",").replace(['\n', '\r'], " ")
+}
+
+fn escape_label(s: &str) -> String {
+    s.replace('"', "#quot;").replace(['\n', '\r'], " ")
+}
+
+/// Escape a label that may also contain markup, such as `<br/>`.
+fn escape_html_label(s: &str) -> String {
+    escape_label(s).replace('<', "#lt;").replace('>', "#gt;")
+}